@@ -0,0 +1,13 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/spatial_maker.proto");
+        let file_descriptors = protox::compile(["proto/spatial_maker.proto"], ["proto"])
+            .expect("failed to parse proto/spatial_maker.proto");
+        tonic_build::configure()
+            .build_client(true)
+            .build_server(true)
+            .compile_fds(file_descriptors)
+            .expect("failed to generate gRPC bindings");
+    }
+}
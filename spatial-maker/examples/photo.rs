@@ -4,10 +4,32 @@
 //!   cargo run --example photo -- --input input.jpg --output spatial.jpg --encoder s
 //!   cargo run --example photo -- --input input.jpg --output spatial.jpg --format top-bottom
 //!   cargo run --example photo -- --input input.jpg --output spatial.png --format png
+//!   cargo run --example photo --features exr -- --input plate.exr --output stereo.png --image-format png --sixteen-bit --png-compression best
+//!   cargo run --example photo -- --input input.jpg --output spatial.webp --image-format webp --quality 85
+//!   cargo run --example photo --features avif -- --input input.jpg --output spatial.avif --image-format avif --quality 70 --speed 6
+//!   cargo run --example photo --features jxl -- --input photo.jxl --output spatial.jxl --image-format jxl
+//!   cargo run --example photo -- --input input.jpg --output half-sbs.jpg --half
+//!   cargo run --example photo --features exr -- --input plate.exr --output stereo.exr --image-format exr
+//!   cargo run --example photo -- --input pano180.jpg --output vr180.jpg --vr180
+//!   cargo run --example photo -- --input pano360.jpg --output pano_tb.jpg --pano360 --format top-bottom
+//!   cargo run --example photo -- --input input.jpg --output wiggle.gif --format wiggle
+//!   cargo run --example photo -- --input input.jpg --output quilt.png --format quilt --image-format png
+//!   cargo run --example photo -- --input input.jpg --output spatial.jpg --manifest run.json
+//!   cargo run --example photo -- --input input.jpg --output depth.png --depth-preview --depth-blend 0.5
+//!   cargo run --example photo -- --input input.jpg --output photo.ply --point-cloud --fov 70
+//!   cargo run --example photo -- --input input.jpg --output photo.usdz --mesh --relief 0.3
+//!   cargo run --example photo -- --input input.jpg --output spatial.jpg --depth-upsampling guided
+//!   cargo run --example photo -- --input input.jpg --output spatial.jpg --depth-clip 2:98 --depth-gamma 0.8
+//!   cargo run --example photo -- --input left.jpg --right right.jpg --output spatial.jpg
 
 use clap::Parser;
 use spatial_maker::{
-    process_photo, ImageEncoding, MVHEVCConfig, OutputFormat, OutputOptions, SpatialConfig,
+    estimate_depth, load_image, process_photo, process_photo_pair, process_photo_quilt,
+    process_photo_wiggle, process_photos, save_depth_visualization, save_mesh, save_ply,
+    AlignConfig, Attribution, Colormap, DepthShaping, DepthUpsampling, EncoderSize,
+    FilenameTemplate, ImageEncoding, MVHEVCBackend, MVHEVCConfig, MeshConfig, OutputFormat,
+    OutputOptions, PlayerNaming, PngCompression, PngFilter, PointCloudConfig, PreviewSize,
+    Projection, QuiltConfig, ResizeFilter, SpatialConfig, TargetSize, TilingConfig, WiggleConfig,
 };
 use std::path::PathBuf;
 use tracing_subscriber;
@@ -24,38 +46,174 @@ struct Args {
     #[arg(short, long, value_name = "FILE")]
     output: PathBuf,
 
+    /// Second photo of the scene, taken a few cm to the right of --input;
+    /// the two are aligned into a stereo pair without a depth model
+    #[arg(long, value_name = "FILE")]
+    right: Option<PathBuf>,
+
+    /// With --right, treat the photos as a synchronized stereo rig capture:
+    /// only rotation and vertical offset are corrected, keeping all parallax
+    #[arg(long, requires = "right")]
+    rig: bool,
+
     /// Depth model encoder size: s (small), b (base), or l (large)
     #[arg(short, long, default_value = "s")]
-    encoder: String,
+    encoder: EncoderSize,
 
     /// Maximum disparity for stereo generation (pixels)
     #[arg(long, default_value = "30")]
     max_disparity: u32,
 
-    /// Target input size for depth model (shorter side)
+    /// Target input size for depth model (shorter side), or "auto"
     #[arg(long, default_value = "518")]
-    target_size: u32,
+    target_size: TargetSize,
+
+    /// Filter for scaling the photo to the model input: nearest, triangle,
+    /// catmull-rom, gaussian, or lanczos3
+    #[arg(long, default_value = "lanczos3")]
+    resize_filter: String,
+
+    /// Depth upscaling for stereo: nearest, bilinear, bicubic, or guided
+    /// (follows the photo's edges, fewest halos)
+    #[arg(long, default_value = "nearest")]
+    depth_upsampling: String,
+
+    /// Depth gamma: below 1 makes the foreground pop more, above 1 less
+    #[arg(long, default_value = "1.0")]
+    depth_gamma: f32,
 
-    /// Output stereo format: side-by-side, top-bottom, or separate
+    /// Depth contrast around mid-depth: above 1 spreads near and far apart
+    #[arg(long, default_value = "1.0")]
+    depth_contrast: f32,
+
+    /// Stretch depth between two percentiles, clamping outliers (e.g. 2:98)
+    #[arg(long, value_name = "LOW:HIGH")]
+    depth_clip: Option<String>,
+
+    /// Estimate depth in overlapping tiles of this many pixels (large images)
+    #[arg(long, value_name = "PX")]
+    tile_size: Option<u32>,
+
+    /// Output format: side-by-side, top-bottom, separate, cross-eye, mirrored, quilt, or wiggle
     #[arg(short, long, default_value = "side-by-side")]
     format: String,
 
-    /// Output image encoding: jpeg or png
+    /// Squeeze each eye to half size (half-SBS / half-TB) for 3D TVs and players
+    #[arg(long)]
+    half: bool,
+
+    /// Treat the input as a 180° equirectangular photo and write VR180 stereo
+    #[arg(long)]
+    vr180: bool,
+
+    /// Treat the input as a 360° equirectangular panorama and write
+    /// over/under stereo (needs --format top-bottom)
+    #[arg(long, conflicts_with = "vr180")]
+    pano360: bool,
+
+    /// Creator credited in the output's EXIF/XMP metadata
+    #[arg(long, value_name = "NAME")]
+    creator: Option<String>,
+
+    /// Copyright notice embedded in the output's EXIF/XMP metadata
+    #[arg(long, value_name = "TEXT")]
+    copyright: Option<String>,
+
+    /// Quilt views per row (only used with --format quilt)
+    #[arg(long, default_value = "5")]
+    quilt_columns: u32,
+
+    /// Quilt rows of views (only used with --format quilt)
+    #[arg(long, default_value = "9")]
+    quilt_rows: u32,
+
+    /// Disparity between the outermost quilt views, in pixels
+    #[arg(long, default_value = "60")]
+    quilt_disparity: f32,
+
+    /// Write a colormapped depth PNG to the output path instead of stereo
+    #[arg(long)]
+    depth_preview: bool,
+
+    /// Depth preview colormap: turbo or viridis
+    #[arg(long, default_value = "turbo")]
+    colormap: String,
+
+    /// Blend the depth preview over the photo at this opacity (0-1)
+    #[arg(long, value_name = "OPACITY")]
+    depth_blend: Option<f32>,
+
+    /// Write a colored PLY point cloud to the output path instead of stereo
+    #[arg(long, conflicts_with = "depth_preview")]
+    point_cloud: bool,
+
+    /// Horizontal field of view of the camera, in degrees (point clouds only)
+    #[arg(long, default_value = "60")]
+    fov: f32,
+
+    /// Write a depth-displaced, textured mesh to the output path instead of
+    /// stereo (.glb or .usdz)
+    #[arg(long, conflicts_with_all = ["depth_preview", "point_cloud"])]
+    mesh: bool,
+
+    /// How far the nearest depth stands out of the mesh, in meters (meshes only)
+    #[arg(long, default_value = "0.25")]
+    relief: f32,
+
+    /// Viewpoints in the wiggle sweep (only used with --format wiggle)
+    #[arg(long, default_value = "6")]
+    wiggle_views: u32,
+
+    /// Disparity between the outermost wiggle viewpoints, in pixels
+    #[arg(long, default_value = "24")]
+    wiggle_disparity: f32,
+
+    /// Output image encoding: jpeg, png, webp, avif (needs --features avif or heic), jxl (lossless, needs --features jxl), heic
+    /// (needs --features heic), or exr
+    /// (float, needs --features exr)
     #[arg(long, default_value = "jpeg")]
     image_format: String,
 
-    /// JPEG quality (1-100, only used with jpeg format)
+    /// JPEG, WebP, AVIF, or HEIC quality (1-100, only used with those formats)
     #[arg(long, default_value = "95")]
     quality: u8,
 
+    /// PNG compression: fast, balanced, or best (smallest, slowest)
+    #[arg(long, default_value = "fast")]
+    png_compression: String,
+
+    /// PNG scanline filter: none, sub, up, avg, paeth, or adaptive
+    #[arg(long, default_value = "adaptive")]
+    png_filter: String,
+
+    /// Write 16 bits per channel (only used with png format)
+    #[arg(long)]
+    sixteen_bit: bool,
+
+    /// Lossless WebP (only used with webp format)
+    #[arg(long)]
+    lossless: bool,
+
+    /// AVIF encoder speed, 1 (slowest, smallest) to 10 (fastest)
+    #[arg(long, default_value = "4")]
+    speed: u8,
+
     /// Encode to MV-HEVC format using spatial CLI
     #[arg(long)]
     mvhevc: bool,
 
-    /// Path to spatial CLI tool (only used with --mvhevc)
+    /// Path to spatial CLI tool (only used with --mvhevc; macOS writes natively unless set)
     #[arg(long)]
     spatial_path: Option<PathBuf>,
 
+    /// MV-HEVC encoder: auto, native (macOS), spatial, or ffmpeg
+    #[arg(long, default_value = "auto")]
+    mvhevc_backend: String,
+
+    /// Path to ffmpeg for the ffmpeg MV-HEVC backend
+    #[arg(long)]
+    ffmpeg_path: Option<PathBuf>,
+
     /// MV-HEVC quality (0-100, only used with --mvhevc)
     #[arg(long, default_value = "95")]
     mvhevc_quality: u8,
@@ -64,15 +222,139 @@ struct Args {
     #[arg(long)]
     keep_intermediate: bool,
 
+    /// Name separate eyes and the MV-HEVC file: {stem}, {layout}, {eye}, {ext}
+    #[arg(long, value_name = "TEMPLATE", default_value = FilenameTemplate::DEFAULT)]
+    name_template: String,
+
+    /// Also write downscaled previews: SUFFIX=HEIGHT per eye (repeatable),
+    /// e.g. --preview 1080p=1080 --preview thumb=256
+    #[arg(long, value_name = "SUFFIX=HEIGHT")]
+    preview: Vec<String>,
+
+    /// Tag the file name for VR players (e.g. _SBS_FULL, _180x180_3dh)
+    #[arg(long)]
+    player_tag: bool,
+
+    /// Write a JSON sidecar describing the stereo mode for VR players
+    #[arg(long)]
+    player_sidecar: bool,
+
+    /// Write a run manifest (inputs, outputs, model hash, timings) to this path
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+
     /// Disable CoreML (use CPU on macOS)
     #[arg(long)]
     no_coreml: bool,
 
+    /// Threads for decoding, inference, and rendering (default: all cores but one)
+    #[arg(long, value_name = "N")]
+    threads: Option<usize>,
+
     /// Enable verbose logging
     #[arg(short, long)]
     verbose: bool,
 }
 
+/// Tiling settings from `--tile-size`, overlapping tiles by an eighth
+fn depth_tiling(args: &Args) -> Option<TilingConfig> {
+    args.tile_size.map(|tile_size| TilingConfig {
+        tile_size,
+        overlap: tile_size / 8,
+    })
+}
+
+/// Shaping from `--depth-gamma`, `--depth-contrast`, and `--depth-clip`
+fn depth_shaping(args: &Args) -> DepthShaping {
+    let clip_percentiles = args.depth_clip.as_ref().map(|spec| {
+        match spec
+            .split_once(':')
+            .and_then(|(low, high)| Some((low.parse().ok()?, high.parse().ok()?)))
+        {
+            Some(percentiles) => percentiles,
+            None => {
+                eprintln!("❌ Invalid --depth-clip '{}': expected LOW:HIGH", spec);
+                std::process::exit(1);
+            }
+        }
+    });
+    DepthShaping {
+        clip_percentiles,
+        contrast: args.depth_contrast,
+        gamma: args.depth_gamma,
+    }
+}
+
+/// Compression from `--png-compression`
+fn png_compression(args: &Args) -> PngCompression {
+    match args.png_compression.to_lowercase().as_str() {
+        "fast" => PngCompression::Fast,
+        "balanced" | "default" => PngCompression::Balanced,
+        "best" => PngCompression::Best,
+        other => {
+            eprintln!(
+                "❌ Invalid PNG compression '{}'. Use: fast, balanced, or best",
+                other
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Filter from `--png-filter`
+fn png_filter(args: &Args) -> PngFilter {
+    match args.png_filter.to_lowercase().as_str() {
+        "none" => PngFilter::NoFilter,
+        "sub" => PngFilter::Sub,
+        "up" => PngFilter::Up,
+        "avg" => PngFilter::Avg,
+        "paeth" => PngFilter::Paeth,
+        "adaptive" => PngFilter::Adaptive,
+        other => {
+            eprintln!(
+                "❌ Invalid PNG filter '{}'. Use: none, sub, up, avg, paeth, or adaptive",
+                other
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Filter from `--resize-filter`
+fn resize_filter(args: &Args) -> ResizeFilter {
+    match args.resize_filter.to_lowercase().as_str() {
+        "nearest" => ResizeFilter::Nearest,
+        "triangle" | "bilinear" => ResizeFilter::Triangle,
+        "catmull-rom" | "bicubic" => ResizeFilter::CatmullRom,
+        "gaussian" => ResizeFilter::Gaussian,
+        "lanczos3" | "lanczos" => ResizeFilter::Lanczos3,
+        other => {
+            eprintln!(
+                "❌ Invalid resize filter '{}'. Use: nearest, triangle, catmull-rom, gaussian, or lanczos3",
+                other
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Method from `--depth-upsampling`
+fn depth_upsampling(args: &Args) -> DepthUpsampling {
+    match args.depth_upsampling.to_lowercase().as_str() {
+        "nearest" => DepthUpsampling::Nearest,
+        "bilinear" => DepthUpsampling::Bilinear,
+        "bicubic" => DepthUpsampling::Bicubic,
+        "guided" => DepthUpsampling::Guided,
+        other => {
+            eprintln!(
+                "❌ Invalid depth upsampling '{}'. Use: nearest, bilinear, bicubic, or guided",
+                other
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
@@ -81,20 +363,60 @@ async fn main() -> anyhow::Result<()> {
     let log_level = if args.verbose { "debug" } else { "info" };
     tracing_subscriber::fmt().with_env_filter(log_level).init();
 
+    if let Some(threads) = args.threads {
+        spatial_maker::cpu::set_threads(threads)?;
+    }
+
     // Validate input file exists
     if !args.input.exists() {
         eprintln!("❌ Input file not found: {:?}", args.input);
         std::process::exit(1);
     }
 
-    // Parse output stereo format
+    // Wiggle animations skip the stereo pair; GIF or MP4 follows the extension
+    if args.format.eq_ignore_ascii_case("wiggle") {
+        let wiggle = WiggleConfig {
+            views: args.wiggle_views.max(2),
+            disparity_range: args.wiggle_disparity,
+            ffmpeg_path: args.ffmpeg_path.clone(),
+            ..Default::default()
+        };
+        let spatial_config = SpatialConfig {
+            encoder_size: args.encoder,
+            target_depth_size: args.target_size,
+            use_coreml: !args.no_coreml,
+            resize_filter: resize_filter(&args),
+            depth_tiling: depth_tiling(&args),
+            ..Default::default()
+        };
+        println!("🎬 Rendering wiggle animation...");
+        println!("  Input:       {:?}", args.input);
+        println!(
+            "  Views:       {} ({} px range)",
+            wiggle.views, wiggle.disparity_range
+        );
+        if let Err(e) =
+            process_photo_wiggle(&args.input, &args.output, spatial_config, &wiggle).await
+        {
+            eprintln!("❌ Error processing photo: {}", e);
+            std::process::exit(1);
+        }
+        println!("✅ Wiggle complete!");
+        println!("   Saved to: {:?}", args.output);
+        return Ok(());
+    }
+
+    // Parse output stereo format (None = Looking Glass quilt)
     let layout = match args.format.to_lowercase().as_str() {
-        "side-by-side" | "sbs" => OutputFormat::SideBySide,
-        "top-bottom" | "tb" => OutputFormat::TopAndBottom,
-        "separate" => OutputFormat::Separate,
+        "side-by-side" | "sbs" => Some(OutputFormat::SideBySide),
+        "top-bottom" | "tb" => Some(OutputFormat::TopAndBottom),
+        "separate" => Some(OutputFormat::Separate),
+        "cross-eye" | "cross" => Some(OutputFormat::CrossEye),
+        "mirrored" | "mirrored-side-by-side" => Some(OutputFormat::MirroredSideBySide),
+        "quilt" => None,
         other => {
             eprintln!(
-                "❌ Invalid format '{}'. Use: side-by-side, top-bottom, or separate",
+                "❌ Invalid format '{}'. Use: side-by-side, top-bottom, separate, cross-eye, mirrored, quilt, or wiggle",
                 other
             );
             std::process::exit(1);
@@ -103,48 +425,261 @@ async fn main() -> anyhow::Result<()> {
 
     // Parse image encoding
     let image_encoding = match args.image_format.to_lowercase().as_str() {
-        "png" => ImageEncoding::Png,
+        "png" => ImageEncoding::Png {
+            compression: png_compression(&args),
+            filter: png_filter(&args),
+            sixteen_bit: args.sixteen_bit,
+        },
+        "exr" => ImageEncoding::Exr,
+        "webp" => ImageEncoding::WebP {
+            quality: args.quality.clamp(1, 100),
+            lossless: args.lossless,
+        },
+        "avif" => ImageEncoding::Avif {
+            quality: args.quality.clamp(1, 100),
+            speed: args.speed.clamp(1, 10),
+        },
+        "jxl" => ImageEncoding::Jxl,
+        "heic" | "heif" => ImageEncoding::Heic {
+            quality: args.quality.clamp(1, 100),
+        },
         "jpeg" | "jpg" => {
             let quality = args.quality.max(1).min(100);
             ImageEncoding::Jpeg { quality }
         }
         other => {
-            eprintln!("❌ Invalid image format '{}'. Use: jpeg or png", other);
+            eprintln!(
+                "❌ Invalid image format '{}'. Use: jpeg, png, webp, avif, jxl, heic, or exr",
+                other
+            );
             std::process::exit(1);
         }
     };
 
     // Create spatial config
     let spatial_config = SpatialConfig {
-        encoder_size: args.encoder.clone(),
+        encoder_size: args.encoder,
         max_disparity: args.max_disparity,
         target_depth_size: args.target_size,
         use_coreml: !args.no_coreml,
+        resize_filter: resize_filter(&args),
+        depth_upsampling: depth_upsampling(&args),
+        depth_shaping: depth_shaping(&args),
+        depth_tiling: depth_tiling(&args),
+        ..Default::default()
+    };
+
+    // Depth previews stop before any stereo output
+    if args.depth_preview {
+        let colormap = match args.colormap.to_lowercase().as_str() {
+            "turbo" => Colormap::Turbo,
+            "viridis" => Colormap::Viridis,
+            other => {
+                eprintln!("❌ Invalid colormap '{}'. Use: turbo or viridis", other);
+                std::process::exit(1);
+            }
+        };
+        println!("🌈 Rendering depth preview...");
+        println!("  Input:       {:?}", args.input);
+        println!("  Colormap:    {}", colormap.name());
+        let image = load_image(&args.input).await?;
+        let depth = estimate_depth(&image, &spatial_config.depth_config()).await?;
+        let overlay = args.depth_blend.map(|opacity| (&image, opacity));
+        save_depth_visualization(&depth, &args.output, colormap, overlay)?;
+        println!("✅ Depth preview saved to: {:?}", args.output);
+        return Ok(());
+    }
+
+    if args.point_cloud {
+        println!("☁️  Exporting point cloud...");
+        println!("  Input:       {:?}", args.input);
+        let image = load_image(&args.input).await?;
+        let depth = estimate_depth(&image, &spatial_config.depth_config()).await?;
+        let config = PointCloudConfig {
+            field_of_view: args.fov,
+            ..Default::default()
+        };
+        save_ply(&image, &depth, &args.output, &config, None)?;
+        println!("✅ Point cloud saved to: {:?}", args.output);
+        return Ok(());
+    }
+
+    if args.mesh {
+        println!("🧊 Exporting displaced mesh...");
+        println!("  Input:       {:?}", args.input);
+        let image = load_image(&args.input).await?;
+        let depth = estimate_depth(&image, &spatial_config.depth_config()).await?;
+        let config = MeshConfig {
+            relief: args.relief,
+            ..Default::default()
+        };
+        save_mesh(&image, &depth, &args.output, &config, None)?;
+        println!("✅ Mesh saved to: {:?}", args.output);
+        return Ok(());
+    }
+
+    // Quilts skip the stereo pair entirely
+    let Some(layout) = layout else {
+        let quilt = QuiltConfig {
+            columns: args.quilt_columns.max(1),
+            rows: args.quilt_rows.max(1),
+            disparity_range: args.quilt_disparity,
+            ..Default::default()
+        };
+        println!("🎬 Rendering Looking Glass quilt...");
+        println!("  Input:       {:?}", args.input);
+        println!(
+            "  Views:       {}x{} ({} px range)",
+            quilt.columns, quilt.rows, quilt.disparity_range
+        );
+        match process_photo_quilt(
+            &args.input,
+            &args.output,
+            spatial_config,
+            &quilt,
+            image_encoding,
+        )
+        .await
+        {
+            Ok(written) => {
+                println!("✅ Quilt complete!");
+                println!("   Saved to: {:?}", written);
+            }
+            Err(e) => {
+                eprintln!("❌ Error processing photo: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    };
+
+    // Parse MV-HEVC backend
+    let mvhevc_backend = match args.mvhevc_backend.to_lowercase().as_str() {
+        "auto" => MVHEVCBackend::Auto,
+        "native" => MVHEVCBackend::Native,
+        "spatial" => MVHEVCBackend::SpatialCli,
+        "ffmpeg" => MVHEVCBackend::Ffmpeg,
+        other => {
+            eprintln!(
+                "❌ Invalid MV-HEVC backend '{}'. Use: auto, native, spatial, or ffmpeg",
+                other
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let filename_template = match FilenameTemplate::new(&args.name_template) {
+        Ok(template) => template,
+        Err(e) => {
+            eprintln!("❌ Invalid --name-template: {}", e);
+            std::process::exit(1);
+        }
     };
 
     // Create output options
     let mvhevc = if args.mvhevc {
         Some(MVHEVCConfig {
+            backend: mvhevc_backend,
             spatial_cli_path: args.spatial_path,
+            ffmpeg_path: args.ffmpeg_path,
             enabled: true,
             quality: args.mvhevc_quality,
             keep_intermediate: args.keep_intermediate,
+            ..Default::default()
         })
     } else {
         None
     };
 
+    let mut previews = Vec::new();
+    for spec in &args.preview {
+        match spec
+            .split_once('=')
+            .and_then(|(suffix, height)| Some((suffix, height.parse::<u32>().ok()?)))
+        {
+            Some((suffix, height)) => previews.push(PreviewSize::new(suffix, height)),
+            None => {
+                eprintln!("❌ Invalid --preview '{}': expected SUFFIX=HEIGHT", spec);
+                std::process::exit(1);
+            }
+        }
+    }
+
     let output_options = OutputOptions {
         layout,
         image_format: image_encoding,
         mvhevc,
+        half_resolution: args.half,
+        projection: if args.vr180 {
+            Projection::Vr180
+        } else if args.pano360 {
+            Projection::Panorama360
+        } else {
+            Projection::Rectilinear
+        },
+        attribution: (args.creator.is_some() || args.copyright.is_some()).then(|| Attribution {
+            creator: args.creator.clone(),
+            copyright: args.copyright.clone(),
+            ..Default::default()
+        }),
+        filename_template,
+        previews,
+        player: PlayerNaming {
+            tag_filename: args.player_tag,
+            json_sidecar: args.player_sidecar,
+            ..Default::default()
+        },
+        ..Default::default()
     };
 
+    // Two-photo stereo replaces depth estimation entirely
+    if let Some(right) = &args.right {
+        println!("🎬 Aligning photo pair...");
+        println!("  Left:        {:?}", args.input);
+        println!("  Right:       {:?}", right);
+        println!("  Output:      {:?}", args.output);
+        match process_photo_pair(
+            &args.input,
+            right,
+            &args.output,
+            &if args.rig {
+                AlignConfig::stereo_rig()
+            } else {
+                AlignConfig::default()
+            },
+            output_options,
+        )
+        .await
+        {
+            Ok(report) => {
+                println!("✅ Photo pair complete!");
+                println!(
+                    "   Aligned on {} of {} features (RMS error {:.2} px)",
+                    report.inliers, report.matches, report.rms_error
+                );
+                println!("   Saved to: {:?}", args.output);
+            }
+            Err(e) => {
+                eprintln!("❌ Error processing photo pair: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     // Print summary
     println!("🎬 Processing photo...");
     println!("  Input:       {:?}", args.input);
     println!("  Output:      {:?}", args.output);
-    println!("  Format:      {}", layout.name());
+    if args.half {
+        println!("  Format:      {} (half resolution)", layout.name());
+    } else if args.vr180 {
+        println!("  Format:      {} (VR180)", layout.name());
+    } else if args.pano360 {
+        println!("  Format:      {} (360° panorama)", layout.name());
+    } else {
+        println!("  Format:      {}", layout.name());
+    }
     println!(
         "  Encoder:     {} (CoreML: {})",
         spatial_config.encoder_size, spatial_config.use_coreml
@@ -154,7 +689,26 @@ async fn main() -> anyhow::Result<()> {
         println!("  MV-HEVC:     enabled (quality: {})", args.mvhevc_quality);
     }
 
-    // Run processing
+    // Run processing, recording a manifest when requested
+    if let Some(manifest_path) = &args.manifest {
+        let jobs = vec![(args.input.clone(), args.output.clone())];
+        let manifest = process_photos(
+            &jobs,
+            spatial_config,
+            output_options,
+            Some(manifest_path.as_path()),
+        )
+        .await?;
+        if let Some(error) = manifest.entries.iter().find_map(|e| e.error.as_ref()) {
+            eprintln!("❌ Error processing photo: {}", error);
+            std::process::exit(1);
+        }
+        println!("✅ Photo processing complete!");
+        println!("   Saved to: {:?}", args.output);
+        println!("   Manifest: {:?}", manifest_path);
+        return Ok(());
+    }
+
     match process_photo(&args.input, &args.output, spatial_config, output_options).await {
         Ok(_) => {
             println!("✅ Photo processing complete!");
@@ -0,0 +1,955 @@
+//! Stereo pairs from two photos
+//!
+//! When two handheld shots taken a few centimeters apart are available, real
+//! parallax beats anything synthesized from a depth map: there are no
+//! disocclusion holes to fill and no model to download. The two shots are
+//! never perfectly parallel, though, so they are aligned first:
+//! 1. Corners are detected in both photos (Harris) at a reduced working size
+//! 2. Corners are matched by normalized patch correlation, keeping only
+//!    mutual, unambiguous matches near each other
+//! 3. A homography mapping left to right is fitted with RANSAC, which removes
+//!    rotation, vertical offset, and keystone between the shots
+//! 4. The right photo is warped onto the left, shifted horizontally to set
+//!    the convergence, and both are cropped to the area they share
+//!
+//! The homography locks onto the scene's dominant plane, so parallax that
+//! remains after alignment is horizontal and comes from real depth.
+//!
+//! Photos from a stereo rig or a phone's spatial capture are already taken
+//! side by side with parallel lenses; warping them onto a plane would undo
+//! some of their real parallax. [`AlignModel::Rigid`] (see
+//! [`AlignConfig::stereo_rig`]) corrects only what such rigs get wrong, a
+//! small roll between the cameras and a vertical offset, fitted to the
+//! vertical positions of the matches alone.
+//!
+//! ```no_run
+//! use spatial_maker::{align_stereo_pair, load_image, AlignConfig};
+//! use std::path::Path;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let left = load_image(Path::new("left.jpg")).await?;
+//! let right = load_image(Path::new("right.jpg")).await?;
+//! let pair = align_stereo_pair(&left, &right, &AlignConfig::default(), None)?;
+//! println!("{} matched features", pair.report.inliers);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancel::{self, CancellationToken};
+use crate::error::{SpatialError, SpatialResult};
+use image::{DynamicImage, RgbImage};
+use ndarray::Array2;
+
+/// Radius of the square patch compared between corners (11x11 pixels)
+const PATCH_RADIUS: usize = 5;
+
+/// Matches must lie within this fraction of the working width horizontally
+/// and of the working height vertically; handheld pairs never move further
+const MAX_OFFSET_X: f32 = 0.3;
+const MAX_OFFSET_Y: f32 = 0.1;
+
+/// Where zero parallax (the screen plane) ends up after alignment
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Convergence {
+    /// The nearest matched content sits at the screen; everything else
+    /// appears behind it, so nothing pops out through the frame edges
+    #[default]
+    Nearest,
+    /// The scene's dominant plane (usually the background) sits at the screen
+    Plane,
+}
+
+/// Transform fitted between the two photos
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlignModel {
+    /// Full homography: rotation, offset, scale, and keystone, for
+    /// handheld shots taken one after the other
+    #[default]
+    Homography,
+    /// Rotation and vertical offset only, for simultaneous captures from a
+    /// stereo rig or phone; horizontal parallax is left as captured
+    Rigid,
+}
+
+/// Settings for aligning two photos into a stereo pair
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlignConfig {
+    /// Photos are scaled down to this width for feature matching
+    pub working_width: u32,
+
+    /// Strongest corners kept per photo
+    pub max_features: usize,
+
+    /// RANSAC iterations for the homography fit
+    pub ransac_iterations: u32,
+
+    /// Distance in working pixels within which a match agrees with the fit
+    pub inlier_threshold: f32,
+
+    /// Fewer agreeing matches than this fails the alignment
+    pub min_inliers: usize,
+
+    /// Screen-plane placement
+    pub convergence: Convergence,
+
+    /// Transform fitted between the photos
+    pub model: AlignModel,
+}
+
+impl Default for AlignConfig {
+    fn default() -> Self {
+        Self {
+            working_width: 1024,
+            max_features: 1500,
+            ransac_iterations: 1000,
+            inlier_threshold: 2.0,
+            min_inliers: 20,
+            convergence: Convergence::default(),
+            model: AlignModel::default(),
+        }
+    }
+}
+
+impl AlignConfig {
+    /// Settings for left and right photos captured at the same moment,
+    /// e.g. by a stereo rig or a phone's spatial camera: rotation and
+    /// vertical offset are corrected, nothing else
+    pub fn stereo_rig() -> Self {
+        Self {
+            model: AlignModel::Rigid,
+            ..Default::default()
+        }
+    }
+}
+
+/// How well the two photos lined up
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlignmentReport {
+    /// Mutual feature matches found between the photos
+    pub matches: usize,
+    /// Matches that agree with the fitted transform
+    pub inliers: usize,
+    /// RMS distance of the inliers from the fit, in output pixels; only the
+    /// vertical distance for [`AlignModel::Rigid`], which leaves horizontal
+    /// parallax alone
+    pub rms_error: f32,
+    /// Smallest and largest horizontal parallax of matched content after
+    /// alignment, in output pixels (positive = in front of the screen)
+    pub disparity_range: (f32, f32),
+    /// Region of the left photo kept in the output: x, y, width, height
+    pub crop: (u32, u32, u32, u32),
+}
+
+/// Two photos aligned into a stereo pair of equal size
+#[derive(Clone, Debug)]
+pub struct AlignedPair {
+    pub left: DynamicImage,
+    pub right: DynamicImage,
+    pub report: AlignmentReport,
+}
+
+/// Row-major 3x3 matrix
+type Mat3 = [f64; 9];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+/// Align a left and right photo of the same scene into a stereo pair
+///
+/// The photos may differ in size; the output has the size of the region
+/// both photos cover, in left-photo pixels. Fails with
+/// [`SpatialError::Alignment`] when too few features match (featureless
+/// scenes, unrelated photos) or the photos barely overlap.
+pub fn align_stereo_pair(
+    left: &DynamicImage,
+    right: &DynamicImage,
+    config: &AlignConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<AlignedPair> {
+    tracing::info!(
+        "🔗 Aligning {}x{} and {}x{} photos",
+        left.width(),
+        left.height(),
+        right.width(),
+        right.height()
+    );
+
+    let (left_gray, left_scale) = working_gray(left, config.working_width);
+    let (right_gray, right_scale) = working_gray(right, config.working_width);
+
+    cancel::check(cancel)?;
+    let left_features = describe(&left_gray, config.max_features);
+    let right_features = describe(&right_gray, config.max_features);
+
+    cancel::check(cancel)?;
+    let (rows, cols) = left_gray.dim();
+    let matches = match_features(
+        &left_features,
+        &right_features,
+        cols as f32 * MAX_OFFSET_X,
+        rows as f32 * MAX_OFFSET_Y,
+    );
+    tracing::debug!(
+        "{} and {} corners, {} mutual matches",
+        left_features.len(),
+        right_features.len(),
+        matches.len()
+    );
+    if matches.len() < config.min_inliers.max(4) {
+        return Err(SpatialError::Alignment(format!(
+            "only {} matching features (need {})",
+            matches.len(),
+            config.min_inliers.max(4)
+        )));
+    }
+
+    cancel::check(cancel)?;
+    let threshold = config.inlier_threshold.max(0.1) as f64;
+    let fitted = match config.model {
+        AlignModel::Homography => ransac_homography(&matches, config.ransac_iterations, threshold),
+        AlignModel::Rigid => ransac_rigid(
+            &matches,
+            config.ransac_iterations,
+            threshold,
+            Point {
+                x: cols as f64 / 2.0,
+                y: rows as f64 / 2.0,
+            },
+        ),
+    };
+    let (homography, inliers) = fitted
+        .filter(|(_, inliers)| inliers.len() >= config.min_inliers.max(4))
+        .ok_or_else(|| {
+            SpatialError::Alignment(format!(
+                "fewer than {} of {} matches agree on a transform",
+                config.min_inliers.max(4),
+                matches.len()
+            ))
+        })?;
+    if !is_plausible(&homography, cols as f64, rows as f64) {
+        return Err(SpatialError::Alignment(
+            "the transform between them is implausible".to_string(),
+        ));
+    }
+
+    // Parallax of every match consistent with a horizontal baseline, the
+    // plane's inliers and the off-plane content alike
+    let mut disparities: Vec<f64> = matches
+        .iter()
+        .filter_map(|(l, r)| {
+            let p = project(&homography, *l);
+            ((r.y - p.y).abs() <= threshold * 2.0).then_some(p.x - r.x)
+        })
+        .collect();
+    disparities.sort_by(|a, b| a.total_cmp(b));
+    let rms_error = (inliers
+        .iter()
+        .map(|&i| {
+            let (l, r) = matches[i];
+            match config.model {
+                AlignModel::Homography => squared_distance(project(&homography, l), r),
+                AlignModel::Rigid => (project(&homography, l).y - r.y).powi(2),
+            }
+        })
+        .sum::<f64>()
+        / inliers.len() as f64)
+        .sqrt();
+
+    // Working-size homography to one between full-size photos
+    let homography = mul3(
+        &mul3(&scale3(1.0 / right_scale), &homography),
+        &scale3(left_scale),
+    );
+    let shift = match config.convergence {
+        Convergence::Plane => 0.0,
+        Convergence::Nearest => -percentile(&disparities, 0.98) / left_scale,
+    };
+    let disparity_range = (
+        (disparities.first().copied().unwrap_or(0.0) / left_scale + shift) as f32,
+        (disparities.last().copied().unwrap_or(0.0) / left_scale + shift) as f32,
+    );
+
+    cancel::check(cancel)?;
+    let crop = shared_region(&homography, shift, left, right).ok_or_else(|| {
+        SpatialError::Alignment("they overlap too little after alignment".to_string())
+    })?;
+    let (x0, y0, width, height) = crop;
+    let right_rgb = right.to_rgb8();
+    let mut warped = RgbImage::new(width, height);
+    for y in 0..height {
+        if y % 64 == 0 {
+            cancel::check(cancel)?;
+        }
+        for x in 0..width {
+            let source = project(
+                &homography,
+                Point {
+                    x: (x0 + x) as f64 + shift,
+                    y: (y0 + y) as f64,
+                },
+            );
+            warped.put_pixel(x, y, sample_bilinear(&right_rgb, source.x, source.y));
+        }
+    }
+
+    let report = AlignmentReport {
+        matches: matches.len(),
+        inliers: inliers.len(),
+        rms_error: (rms_error / left_scale) as f32,
+        disparity_range,
+        crop,
+    };
+    tracing::info!(
+        "Aligned with {} of {} matches (RMS error {:.2} px), parallax {:.1} to {:.1} px",
+        report.inliers,
+        report.matches,
+        report.rms_error,
+        report.disparity_range.0,
+        report.disparity_range.1
+    );
+
+    Ok(AlignedPair {
+        left: DynamicImage::ImageRgb8(left.crop_imm(x0, y0, width, height).to_rgb8()),
+        right: DynamicImage::ImageRgb8(warped),
+        report,
+    })
+}
+
+/// Grayscale (0-1) at most `max_width` wide, and the working/full scale
+fn working_gray(image: &DynamicImage, max_width: u32) -> (Array2<f32>, f64) {
+    let scale = (max_width.max(64) as f64 / image.width() as f64).min(1.0);
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    let gray = image
+        .resize_exact(width, height, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let array = Array2::from_shape_fn((height as usize, width as usize), |(y, x)| {
+        gray.get_pixel(x as u32, y as u32)[0] as f32 / 255.0
+    });
+    (array, width as f64 / image.width() as f64)
+}
+
+/// A corner and its normalized patch
+struct Feature {
+    point: Point,
+    descriptor: Vec<f32>,
+}
+
+/// Detect Harris corners and describe each with its normalized patch
+fn describe(gray: &Array2<f32>, max_features: usize) -> Vec<Feature> {
+    let (rows, cols) = gray.dim();
+    let margin = PATCH_RADIUS + 2;
+    if rows <= margin * 2 || cols <= margin * 2 {
+        return Vec::new();
+    }
+
+    // Structure tensor summed over a 5x5 window
+    let mut ixx = Array2::<f32>::zeros((rows, cols));
+    let mut iyy = Array2::<f32>::zeros((rows, cols));
+    let mut ixy = Array2::<f32>::zeros((rows, cols));
+    for y in 1..rows - 1 {
+        for x in 1..cols - 1 {
+            let gx = (gray[[y, x + 1]] - gray[[y, x - 1]]) * 0.5;
+            let gy = (gray[[y + 1, x]] - gray[[y - 1, x]]) * 0.5;
+            ixx[[y, x]] = gx * gx;
+            iyy[[y, x]] = gy * gy;
+            ixy[[y, x]] = gx * gy;
+        }
+    }
+    let window = |m: &Array2<f32>, y: usize, x: usize| {
+        m.slice(ndarray::s![y - 2..=y + 2, x - 2..=x + 2]).sum()
+    };
+    let mut response = Array2::<f32>::zeros((rows, cols));
+    let mut strongest = 0.0f32;
+    for y in margin..rows - margin {
+        for x in margin..cols - margin {
+            let (a, b, c) = (window(&ixx, y, x), window(&iyy, y, x), window(&ixy, y, x));
+            let r = a * b - c * c - 0.04 * (a + b) * (a + b);
+            response[[y, x]] = r;
+            strongest = strongest.max(r);
+        }
+    }
+    if strongest <= 0.0 {
+        return Vec::new();
+    }
+
+    // Local maxima in a 7x7 neighbourhood above 1% of the strongest
+    let mut corners = Vec::new();
+    for y in margin..rows - margin {
+        for x in margin..cols - margin {
+            let r = response[[y, x]];
+            if r < strongest * 0.01 {
+                continue;
+            }
+            let neighbourhood = response.slice(ndarray::s![
+                y.saturating_sub(3)..(y + 4).min(rows),
+                x.saturating_sub(3)..(x + 4).min(cols)
+            ]);
+            if neighbourhood.iter().all(|&n| n <= r) {
+                corners.push((r, y, x));
+            }
+        }
+    }
+    corners.sort_by(|a, b| b.0.total_cmp(&a.0));
+    corners.truncate(max_features);
+
+    corners
+        .into_iter()
+        .filter_map(|(_, y, x)| {
+            let patch = gray.slice(ndarray::s![
+                y - PATCH_RADIUS..=y + PATCH_RADIUS,
+                x - PATCH_RADIUS..=x + PATCH_RADIUS
+            ]);
+            let mean = patch.mean().unwrap_or(0.0);
+            let descriptor: Vec<f32> = patch.iter().map(|&v| v - mean).collect();
+            let norm = descriptor.iter().map(|v| v * v).sum::<f32>().sqrt();
+            (norm > 1e-3).then(|| Feature {
+                point: Point {
+                    x: x as f64,
+                    y: y as f64,
+                },
+                descriptor: descriptor.iter().map(|v| v / norm).collect(),
+            })
+        })
+        .collect()
+}
+
+/// Mutual best matches by patch correlation that clearly beat the runner-up
+fn match_features(
+    left: &[Feature],
+    right: &[Feature],
+    max_dx: f32,
+    max_dy: f32,
+) -> Vec<(Point, Point)> {
+    let correlation = |l: &Feature, r: &Feature| -> f32 {
+        if (l.point.x - r.point.x).abs() > max_dx as f64
+            || (l.point.y - r.point.y).abs() > max_dy as f64
+        {
+            return f32::NEG_INFINITY;
+        }
+        l.descriptor
+            .iter()
+            .zip(&r.descriptor)
+            .map(|(a, b)| a * b)
+            .sum()
+    };
+    let scores: Vec<Vec<f32>> = left
+        .iter()
+        .map(|l| right.iter().map(|r| correlation(l, r)).collect())
+        .collect();
+
+    let best_right_for_left = |i: usize| -> Option<usize> {
+        let (mut best, mut second) = ((f32::NEG_INFINITY, 0), f32::NEG_INFINITY);
+        for (j, &score) in scores[i].iter().enumerate() {
+            if score > best.0 {
+                second = best.0;
+                best = (score, j);
+            } else if score > second {
+                second = score;
+            }
+        }
+        // Distances between unit patches: sqrt(2 - 2 * correlation)
+        let distance = |c: f32| (2.0 - 2.0 * c).max(0.0).sqrt();
+        let distinct = !second.is_finite() || distance(best.0) < 0.8 * distance(second);
+        (best.0 >= 0.8 && distinct).then_some(best.1)
+    };
+    let best_left_for_right = |j: usize| -> Option<usize> {
+        (0..left.len())
+            .max_by(|&a, &b| scores[a][j].total_cmp(&scores[b][j]))
+            .filter(|&i| scores[i][j].is_finite())
+    };
+
+    (0..left.len())
+        .filter_map(|i| {
+            let j = best_right_for_left(i)?;
+            (best_left_for_right(j) == Some(i)).then(|| (left[i].point, right[j].point))
+        })
+        .collect()
+}
+
+/// Fit a homography to the matches with RANSAC, refined on its inliers
+///
+/// Returns the homography and the indices of the matches within
+/// `threshold` pixels of it.
+fn ransac_homography(
+    matches: &[(Point, Point)],
+    iterations: u32,
+    threshold: f64,
+) -> Option<(Mat3, Vec<usize>)> {
+    if matches.len() < 4 {
+        return None;
+    }
+    let threshold_sq = threshold * threshold;
+    let inliers_of = |h: &Mat3| -> Vec<usize> {
+        (0..matches.len())
+            .filter(|&i| squared_distance(project(h, matches[i].0), matches[i].1) <= threshold_sq)
+            .collect()
+    };
+
+    // Fixed seed so the same photos always align the same way
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut next = |n: usize| -> usize {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+
+    let mut best: Vec<usize> = Vec::new();
+    for _ in 0..iterations.max(1) {
+        let mut sample = [0usize; 4];
+        for k in 0..4 {
+            sample[k] = loop {
+                let candidate = next(matches.len());
+                if !sample[..k].contains(&candidate) {
+                    break candidate;
+                }
+            };
+        }
+        let points: Vec<(Point, Point)> = sample.iter().map(|&i| matches[i]).collect();
+        let Some(h) = fit_homography(&points) else {
+            continue;
+        };
+        let inliers = inliers_of(&h);
+        if inliers.len() > best.len() {
+            best = inliers;
+        }
+    }
+
+    // Refit on all inliers, then once more on the refined inlier set
+    let mut homography = None;
+    for _ in 0..2 {
+        let points: Vec<(Point, Point)> = best.iter().map(|&i| matches[i]).collect();
+        let h = fit_homography(&points)?;
+        best = inliers_of(&h);
+        homography = Some(h);
+    }
+    homography.filter(|_| best.len() >= 4).map(|h| (h, best))
+}
+
+/// Fit a roll about `center` plus a vertical offset with RANSAC, judging
+/// matches by their vertical distance only
+///
+/// Horizontal positions differ by each point's parallax, so they carry no
+/// information about the rig. Returns the transform as a homography and the
+/// indices of the matches within `threshold` pixels of it vertically.
+fn ransac_rigid(
+    matches: &[(Point, Point)],
+    iterations: u32,
+    threshold: f64,
+    center: Point,
+) -> Option<(Mat3, Vec<usize>)> {
+    if matches.len() < 2 {
+        return None;
+    }
+    let inliers_of = |h: &Mat3| -> Vec<usize> {
+        (0..matches.len())
+            .filter(|&i| (project(h, matches[i].0).y - matches[i].1.y).abs() <= threshold)
+            .collect()
+    };
+
+    // Same fixed seed as the homography fit
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut next = |n: usize| -> usize {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+
+    let mut best: Vec<usize> = Vec::new();
+    for _ in 0..iterations.max(1) {
+        let (i, j) = (next(matches.len()), next(matches.len()));
+        let Some(h) = fit_rigid(&[matches[i], matches[j]], center) else {
+            continue;
+        };
+        let inliers = inliers_of(&h);
+        if inliers.len() > best.len() {
+            best = inliers;
+        }
+    }
+
+    let mut rigid = None;
+    for _ in 0..2 {
+        let points: Vec<(Point, Point)> = best.iter().map(|&i| matches[i]).collect();
+        let h = fit_rigid(&points, center)?;
+        best = inliers_of(&h);
+        rigid = Some(h);
+    }
+    rigid.filter(|_| best.len() >= 2).map(|h| (h, best))
+}
+
+/// Least-squares roll about `center` and vertical offset mapping each `.0`
+/// onto the row of its `.1`, as a homography
+///
+/// For small angles the vertical shift of a point is `angle * (x - cx) +
+/// offset`, a straight-line fit over the points' horizontal positions.
+fn fit_rigid(points: &[(Point, Point)], center: Point) -> Option<Mat3> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(l, _)| l.x - center.x).sum::<f64>() / n;
+    let mean_dy = points.iter().map(|(l, r)| r.y - l.y).sum::<f64>() / n;
+    let (mut cov, mut var) = (0.0f64, 0.0f64);
+    for (l, r) in points {
+        let dx = l.x - center.x - mean_x;
+        cov += dx * (r.y - l.y - mean_dy);
+        var += dx * dx;
+    }
+    // Points stacked in one column say nothing about the roll
+    if var < 1.0 {
+        return None;
+    }
+    let angle = (cov / var).atan();
+    let offset = mean_dy - angle.tan() * mean_x;
+
+    let (sin, cos) = angle.sin_cos();
+    let (cx, cy) = (center.x, center.y);
+    Some([
+        cos,
+        -sin,
+        cx - cos * cx + sin * cy,
+        sin,
+        cos,
+        cy - sin * cx - cos * cy + offset,
+        0.0,
+        0.0,
+        1.0,
+    ])
+}
+
+/// Least-squares homography (h33 = 1) mapping each `.0` onto its `.1`
+///
+/// Points are normalized to zero mean and unit spread before solving, which
+/// keeps the normal equations well conditioned.
+fn fit_homography(points: &[(Point, Point)]) -> Option<Mat3> {
+    if points.len() < 4 {
+        return None;
+    }
+    let from_norm = normalizer(points.iter().map(|p| p.0));
+    let to_norm = normalizer(points.iter().map(|p| p.1));
+
+    let mut ata = [[0.0f64; 8]; 8];
+    let mut atb = [0.0f64; 8];
+    for (from, to) in points {
+        let p = project(&from_norm, *from);
+        let q = project(&to_norm, *to);
+        let rows = [
+            ([p.x, p.y, 1.0, 0.0, 0.0, 0.0, -q.x * p.x, -q.x * p.y], q.x),
+            ([0.0, 0.0, 0.0, p.x, p.y, 1.0, -q.y * p.x, -q.y * p.y], q.y),
+        ];
+        for (row, rhs) in rows {
+            for i in 0..8 {
+                for j in 0..8 {
+                    ata[i][j] += row[i] * row[j];
+                }
+                atb[i] += row[i] * rhs;
+            }
+        }
+    }
+
+    let h = solve8(ata, atb)?;
+    let normalized = [h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7], 1.0];
+    let h = mul3(&mul3(&invert3(&to_norm)?, &normalized), &from_norm);
+    (h[8].abs() > 1e-12).then(|| h.map(|v| v / h[8]))
+}
+
+/// Similarity transform taking points to zero mean and mean distance √2
+fn normalizer(points: impl Iterator<Item = Point> + Clone) -> Mat3 {
+    let n = points.clone().count().max(1) as f64;
+    let cx = points.clone().map(|p| p.x).sum::<f64>() / n;
+    let cy = points.clone().map(|p| p.y).sum::<f64>() / n;
+    let spread = points
+        .map(|p| ((p.x - cx).powi(2) + (p.y - cy).powi(2)).sqrt())
+        .sum::<f64>()
+        / n;
+    let s = if spread > 1e-12 {
+        std::f64::consts::SQRT_2 / spread
+    } else {
+        1.0
+    };
+    [s, 0.0, -s * cx, 0.0, s, -s * cy, 0.0, 0.0, 1.0]
+}
+
+/// Solve an 8x8 linear system by Gaussian elimination with partial pivoting
+fn solve8(mut a: [[f64; 8]; 8], mut b: [f64; 8]) -> Option<[f64; 8]> {
+    for col in 0..8 {
+        let pivot = (col..8).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let pivot_row = a[col];
+        for row in col + 1..8 {
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot) in a[row].iter_mut().zip(&pivot_row).skip(col) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0f64; 8];
+    for row in (0..8).rev() {
+        let sum: f64 = (row + 1..8).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Reject transforms no handheld pair produces: mirroring, large scale
+/// changes, or a strongly tilted projection
+fn is_plausible(h: &Mat3, width: f64, height: f64) -> bool {
+    let det = h[0] * h[4] - h[1] * h[3];
+    let perspective = h[6].abs() * width + h[7].abs() * height;
+    det > 0.25 && det < 4.0 && perspective < 0.5
+}
+
+/// The axis-aligned region of the left photo that the shifted, warped right
+/// photo covers: (x, y, width, height)
+///
+/// `None` when that is less than half of the left photo.
+fn shared_region(
+    homography: &Mat3,
+    shift: f64,
+    left: &DynamicImage,
+    right: &DynamicImage,
+) -> Option<(u32, u32, u32, u32)> {
+    let inverse = invert3(homography)?;
+    let (rw, rh) = (right.width() as f64, right.height() as f64);
+    let corner = |x: f64, y: f64| {
+        let p = project(&inverse, Point { x, y });
+        Point {
+            x: p.x - shift,
+            y: p.y,
+        }
+    };
+    let (tl, tr) = (corner(0.0, 0.0), corner(rw - 1.0, 0.0));
+    let (bl, br) = (corner(0.0, rh - 1.0), corner(rw - 1.0, rh - 1.0));
+
+    let (lw, lh) = (left.width() as f64, left.height() as f64);
+    let x0 = tl.x.max(bl.x).max(0.0).ceil();
+    let y0 = tl.y.max(tr.y).max(0.0).ceil();
+    let x1 = tr.x.min(br.x).min(lw - 1.0).floor();
+    let y1 = bl.y.min(br.y).min(lh - 1.0).floor();
+    if !(x0.is_finite() && y0.is_finite() && x1.is_finite() && y1.is_finite()) {
+        return None;
+    }
+
+    let (width, height) = (x1 - x0 + 1.0, y1 - y0 + 1.0);
+    if width <= 0.0 || height <= 0.0 || width * height < lw * lh * 0.5 {
+        return None;
+    }
+    Some((x0 as u32, y0 as u32, width as u32, height as u32))
+}
+
+fn sample_bilinear(image: &RgbImage, x: f64, y: f64) -> image::Rgb<u8> {
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    let x = x.clamp(0.0, (w - 1) as f64);
+    let y = y.clamp(0.0, (h - 1) as f64);
+    let (x0, y0) = (x.floor() as i64, y.floor() as i64);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+    let px = |x: i64, y: i64| image.get_pixel(x as u32, y as u32);
+    let (a, b, c, d) = (px(x0, y0), px(x1, y0), px(x0, y1), px(x1, y1));
+    image::Rgb(std::array::from_fn(|i| {
+        let top = a[i] as f64 * (1.0 - tx) + b[i] as f64 * tx;
+        let bottom = c[i] as f64 * (1.0 - tx) + d[i] as f64 * tx;
+        (top * (1.0 - ty) + bottom * ty).round() as u8
+    }))
+}
+
+/// Value at fraction `q` (0-1) of sorted `values`; 0 when empty
+fn percentile(values: &[f64], q: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let index = ((values.len() - 1) as f64 * q).round() as usize;
+    values[index.min(values.len() - 1)]
+}
+
+fn project(h: &Mat3, p: Point) -> Point {
+    let w = h[6] * p.x + h[7] * p.y + h[8];
+    Point {
+        x: (h[0] * p.x + h[1] * p.y + h[2]) / w,
+        y: (h[3] * p.x + h[4] * p.y + h[5]) / w,
+    }
+}
+
+fn squared_distance(a: Point, b: Point) -> f64 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+}
+
+fn scale3(s: f64) -> Mat3 {
+    [s, 0.0, 0.0, 0.0, s, 0.0, 0.0, 0.0, 1.0]
+}
+
+fn mul3(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|i| {
+        let (row, col) = (i / 3, i % 3);
+        (0..3).map(|k| a[row * 3 + k] * b[k * 3 + col]).sum()
+    })
+}
+
+fn invert3(m: &Mat3) -> Option<Mat3> {
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+        m[r0 * 3 + c0] * m[r1 * 3 + c1] - m[r0 * 3 + c1] * m[r1 * 3 + c0]
+    };
+    let det =
+        m[0] * cofactor(1, 2, 1, 2) - m[1] * cofactor(1, 2, 0, 2) + m[2] * cofactor(1, 2, 0, 1);
+    if det.abs() < 1e-15 {
+        return None;
+    }
+    let adjugate = [
+        cofactor(1, 2, 1, 2),
+        -cofactor(0, 2, 1, 2),
+        cofactor(0, 1, 1, 2),
+        -cofactor(1, 2, 0, 2),
+        cofactor(0, 2, 0, 2),
+        -cofactor(0, 1, 0, 2),
+        cofactor(1, 2, 0, 1),
+        -cofactor(0, 2, 0, 1),
+        cofactor(0, 1, 0, 1),
+    ];
+    Some(adjugate.map(|v| v / det))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scene of random overlapping rectangles, rendered through `to_scene`
+    /// (output pixel -> scene coordinates)
+    fn render(width: u32, height: u32, to_scene: impl Fn(f64, f64) -> (f64, f64)) -> RgbImage {
+        let mut state = 12345u32;
+        let mut next = |n: u32| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 8) % n
+        };
+        let rects: Vec<(f64, f64, f64, f64, [u8; 3])> = (0..120)
+            .map(|_| {
+                let (x, y) = (next(340) as f64 - 20.0, next(260) as f64 - 20.0);
+                let (w, h) = (8.0 + next(40) as f64, 8.0 + next(40) as f64);
+                let color = [next(256) as u8, next(256) as u8, next(256) as u8];
+                (x, y, w, h, color)
+            })
+            .collect();
+        RgbImage::from_fn(width, height, |x, y| {
+            let (sx, sy) = to_scene(x as f64, y as f64);
+            let mut color = [128u8, 128, 128];
+            for &(rx, ry, rw, rh, c) in &rects {
+                if sx >= rx && sx < rx + rw && sy >= ry && sy < ry + rh {
+                    color = c;
+                }
+            }
+            image::Rgb(color)
+        })
+    }
+
+    #[test]
+    fn test_fit_homography_exact() {
+        let h: Mat3 = [1.02, 0.03, 5.0, -0.02, 0.98, -3.0, 1e-5, -2e-5, 1.0];
+        let points: Vec<(Point, Point)> = [(0.0, 0.0), (100.0, 0.0), (0.0, 80.0), (90.0, 70.0)]
+            .iter()
+            .map(|&(x, y)| {
+                let p = Point { x, y };
+                (p, project(&h, p))
+            })
+            .collect();
+        let fitted = fit_homography(&points).unwrap();
+        for (a, b) in fitted.iter().zip(&h) {
+            assert!((a - b).abs() < 1e-6, "{:?}", fitted);
+        }
+
+        let identity = mul3(&h, &invert3(&h).unwrap());
+        assert!((identity[0] - 1.0).abs() < 1e-9 && identity[1].abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_align_rotated_shifted_pair() {
+        // The right shot is rotated by 1° and moved 12 px left, 4 px down
+        let left = render(300, 220, |x, y| (x, y));
+        let (sin, cos) = 1.0f64.to_radians().sin_cos();
+        let right = render(300, 220, |x, y| {
+            let (x, y) = (x + 12.0 - 150.0, y - 4.0 - 110.0);
+            (cos * x - sin * y + 150.0, sin * x + cos * y + 110.0)
+        });
+
+        let config = AlignConfig {
+            convergence: Convergence::Plane,
+            ..Default::default()
+        };
+        let pair = align_stereo_pair(
+            &DynamicImage::ImageRgb8(left),
+            &DynamicImage::ImageRgb8(right),
+            &config,
+            None,
+        )
+        .unwrap();
+
+        assert!(pair.report.inliers >= config.min_inliers);
+        assert!(pair.report.rms_error < 1.0, "{:?}", pair.report);
+        assert_eq!(
+            (pair.left.width(), pair.left.height()),
+            (pair.right.width(), pair.right.height())
+        );
+        let (width, height) = (pair.report.crop.2, pair.report.crop.3);
+        assert!(width > 250 && height > 180, "{:?}", pair.report.crop);
+
+        // A flat scene aligns to the same picture in both eyes
+        let (l, r) = (pair.left.to_rgb8(), pair.right.to_rgb8());
+        let differing = l
+            .pixels()
+            .zip(r.pixels())
+            .filter(|(a, b)| (0..3).any(|c| (a[c] as i32 - b[c] as i32).abs() > 40))
+            .count();
+        assert!(differing * 20 < (width * height) as usize, "{}", differing);
+    }
+
+    #[test]
+    fn test_rigid_keeps_parallax() {
+        // A rig whose right camera is rolled by 1.5° and sits 6 px low; the
+        // nearer half of the scene (left side) has 10 px more parallax
+        let left = render(300, 220, |x, y| (x, y));
+        let (sin, cos) = 1.5f64.to_radians().sin_cos();
+        let right = render(300, 220, |x, y| {
+            let (x, y) = (x - 150.0, y + 6.0 - 110.0);
+            let (x, y) = (cos * x - sin * y + 150.0, sin * x + cos * y + 110.0);
+            (if x < 150.0 { x + 10.0 } else { x }, y)
+        });
+
+        let config = AlignConfig {
+            convergence: Convergence::Plane,
+            ..AlignConfig::stereo_rig()
+        };
+        let pair = align_stereo_pair(
+            &DynamicImage::ImageRgb8(left),
+            &DynamicImage::ImageRgb8(right),
+            &config,
+            None,
+        )
+        .unwrap();
+        assert!(pair.report.rms_error < 1.0, "{:?}", pair.report);
+
+        // The near half keeps its extra parallax
+        let (low, high) = pair.report.disparity_range;
+        assert!(high - low > 8.0, "{:?}", pair.report);
+    }
+
+    #[test]
+    fn test_featureless_photos_fail() {
+        let flat = DynamicImage::ImageRgb8(RgbImage::from_pixel(120, 90, image::Rgb([90; 3])));
+        let result = align_stereo_pair(&flat, &flat, &AlignConfig::default(), None);
+        assert!(matches!(result, Err(SpatialError::Alignment(_))));
+    }
+
+    #[test]
+    fn test_align_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let image = DynamicImage::new_rgb8(64, 64);
+        let result = align_stereo_pair(&image, &image, &AlignConfig::default(), Some(&token));
+        assert!(matches!(result, Err(SpatialError::Cancelled)));
+    }
+}
@@ -0,0 +1,419 @@
+//! Audio passthrough for video output
+//!
+//! Stereo conversion only touches the picture, so the source's audio streams
+//! are carried into the output unchanged by default. [`probe_audio_streams`]
+//! lists them with ffprobe and [`audio_args`] turns them into ffmpeg
+//! `-map`/`-c:a` arguments for the muxing step:
+//! - [`AudioMode::Copy`] stream-copies every track the output container can
+//!   hold and transcodes only the ones it cannot
+//! - [`AudioMode::Transcode`] re-encodes every track
+//! - [`AudioMode::Drop`] writes silent video
+//!
+//! Multichannel and ambisonic (spatial audio) tracks keep their channel count
+//! and layout either way. Stream copy also keeps per-stream side data such as
+//! ffmpeg's ambisonic tags; transcoding falls back to a codec that holds the
+//! full channel count.
+//!
+//! ```no_run
+//! use spatial_maker::audio::{audio_args, probe_audio_streams, AudioConfig};
+//! use std::path::Path;
+//!
+//! # fn example() -> spatial_maker::SpatialResult<()> {
+//! let streams = probe_audio_streams(Path::new("ffprobe"), Path::new("input.mov"))?;
+//! // Input 0 is the stereo video, input 1 the original file
+//! let args = audio_args(&streams, 1, &AudioConfig::default(), "mov");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// How source audio is written to the output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioMode {
+    /// Stream-copy tracks, transcoding only those the container cannot hold
+    #[default]
+    Copy,
+    /// Re-encode every track with the configured codec
+    Transcode,
+    /// Leave audio out of the output
+    Drop,
+}
+
+/// Audio settings for video output
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Passthrough, transcode, or drop
+    pub mode: AudioMode,
+    /// ffmpeg encoder used when a track is transcoded
+    pub codec: String,
+    /// Bitrate per channel in kbps for lossy codecs
+    pub bitrate_per_channel_kbps: u32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            mode: AudioMode::Copy,
+            codec: "aac".to_string(),
+            bitrate_per_channel_kbps: 96,
+        }
+    }
+}
+
+/// An audio stream of the source file, as reported by ffprobe
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AudioStream {
+    /// Position among the file's audio streams (ffmpeg's `a:N`)
+    pub index: usize,
+    /// ffmpeg codec name (e.g. "aac", "pcm_s24le", "opus")
+    pub codec: String,
+    pub channels: u32,
+    /// ffmpeg channel layout (e.g. "5.1", "ambisonic 1"), when known
+    pub channel_layout: Option<String>,
+}
+
+impl AudioStream {
+    /// Whether this looks like an ambisonic (spatial audio) track
+    ///
+    /// ffmpeg names ambisonic layouts "ambisonic N". Tracks with no known
+    /// layout and a full-sphere channel count (4, 9, 16) are treated as
+    /// ambisonic too, since that is how most 360° cameras write them.
+    pub fn is_ambisonic(&self) -> bool {
+        match self.channel_layout.as_deref() {
+            Some(layout) if layout.starts_with("ambisonic") => true,
+            Some(layout) if !layout.is_empty() && !layout.starts_with("unknown") => false,
+            _ => matches!(self.channels, 4 | 9 | 16),
+        }
+    }
+}
+
+/// List the audio streams of `input` with ffprobe
+pub fn probe_audio_streams(ffprobe: &Path, input: &Path) -> SpatialResult<Vec<AudioStream>> {
+    let output = Command::new(ffprobe)
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "a",
+            "-show_entries",
+            "stream=codec_name,channels,channel_layout",
+            "-of",
+            "json",
+        ])
+        .arg(input)
+        .output()
+        .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffprobe), e))?;
+
+    if !output.status.success() {
+        return Err(SpatialError::io(
+            format!("ffprobe failed on {:?}", input),
+            std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        ));
+    }
+
+    parse_ffprobe_audio(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `ffprobe -select_streams a -of json` output
+pub fn parse_ffprobe_audio(json: &str) -> SpatialResult<Vec<AudioStream>> {
+    #[derive(Deserialize)]
+    struct Probe {
+        #[serde(default)]
+        streams: Vec<ProbeStream>,
+    }
+
+    #[derive(Deserialize)]
+    struct ProbeStream {
+        #[serde(default)]
+        codec_name: Option<String>,
+        #[serde(default)]
+        channels: u32,
+        #[serde(default)]
+        channel_layout: Option<String>,
+    }
+
+    let probe: Probe = serde_json::from_str(json)
+        .map_err(|e| SpatialError::io("Invalid ffprobe output", e.into()))?;
+
+    Ok(probe
+        .streams
+        .into_iter()
+        .enumerate()
+        .map(|(index, stream)| AudioStream {
+            index,
+            codec: stream.codec_name.unwrap_or_default(),
+            channels: stream.channels,
+            channel_layout: stream.channel_layout,
+        })
+        .collect())
+}
+
+/// Whether a container (by file extension) can hold `codec` as-is
+pub fn container_accepts(extension: &str, codec: &str) -> bool {
+    match extension.to_ascii_lowercase().as_str() {
+        "webm" => matches!(codec, "opus" | "vorbis"),
+        "mkv" => !codec.is_empty(),
+        "mov" => {
+            codec.starts_with("pcm_")
+                || matches!(
+                    codec,
+                    "aac" | "alac" | "ac3" | "eac3" | "mp3" | "opus" | "flac"
+                )
+        }
+        "mp4" | "m4v" => matches!(
+            codec,
+            "aac" | "alac" | "ac3" | "eac3" | "mp3" | "opus" | "flac"
+        ),
+        _ => false,
+    }
+}
+
+/// ffmpeg arguments that map and encode the audio of input `input_index`
+///
+/// Output streams keep the source order and per-stream metadata (language,
+/// titles). Returns `-an` when there is nothing to write.
+pub fn audio_args(
+    streams: &[AudioStream],
+    input_index: usize,
+    config: &AudioConfig,
+    extension: &str,
+) -> Vec<String> {
+    if config.mode == AudioMode::Drop || streams.is_empty() {
+        return vec!["-an".to_string()];
+    }
+
+    let mut args = Vec::new();
+    for (out, stream) in streams.iter().enumerate() {
+        args.push("-map".to_string());
+        args.push(format!("{}:a:{}", input_index, stream.index));
+
+        let copy = config.mode == AudioMode::Copy && container_accepts(extension, &stream.codec);
+        if copy {
+            args.push(format!("-c:a:{}", out));
+            args.push("copy".to_string());
+        } else {
+            let codec = transcode_codec(stream, config, extension);
+            if codec != config.codec {
+                tracing::warn!(
+                    "Audio track {} ({} channels) cannot be encoded as {} in .{}; using {}",
+                    stream.index,
+                    stream.channels,
+                    config.codec,
+                    extension,
+                    codec
+                );
+            }
+            args.push(format!("-c:a:{}", out));
+            args.push(codec.to_string());
+            if !is_lossless(codec) {
+                let bitrate = config.bitrate_per_channel_kbps * stream.channels.max(1);
+                args.push(format!("-b:a:{}", out));
+                args.push(format!("{}k", bitrate));
+            }
+            // Encoders may otherwise pick a default layout for the channel count
+            if let Some(layout) = &stream.channel_layout {
+                if !layout.is_empty() && !layout.starts_with("unknown") {
+                    args.push(format!("-channel_layout:a:{}", out));
+                    args.push(layout.clone());
+                }
+            }
+        }
+
+        args.push(format!("-map_metadata:s:a:{}", out));
+        args.push(format!("{}:s:a:{}", input_index, stream.index));
+    }
+    args
+}
+
+/// ffmpeg arguments that cut every audio stream of `input` to `spans`
+/// (start and end seconds) and join the pieces, in order
+///
+/// Each span is read through its own seeking input, so nothing is buffered
+/// between spans. Tracks are written as 24-bit PCM, which holds any channel
+/// count, for a Matroska output that [`audio_args`] then maps like a source
+/// file.
+pub fn cut_args(input: &Path, streams: &[AudioStream], spans: &[(f64, f64)]) -> Vec<String> {
+    let mut args = Vec::new();
+    for (start, end) in spans {
+        args.extend(["-ss".to_string(), format!("{:.6}", start)]);
+        args.extend(["-to".to_string(), format!("{:.6}", end)]);
+        args.extend(["-i".to_string(), input.to_string_lossy().into_owned()]);
+    }
+
+    let graph: Vec<String> = streams
+        .iter()
+        .enumerate()
+        .map(|(out, stream)| {
+            let pieces: String = (0..spans.len())
+                .map(|i| format!("[{}:a:{}]", i, stream.index))
+                .collect();
+            format!("{}concat=n={}:v=0:a=1[a{}]", pieces, spans.len(), out)
+        })
+        .collect();
+    args.extend(["-filter_complex".to_string(), graph.join(";")]);
+
+    for (out, stream) in streams.iter().enumerate() {
+        args.extend(["-map".to_string(), format!("[a{}]", out)]);
+        args.extend([
+            format!("-map_metadata:s:a:{}", out),
+            format!("0:s:a:{}", stream.index),
+        ]);
+    }
+    args.extend(["-c:a", "pcm_s24le"].map(String::from));
+    args
+}
+
+/// Encoder for a track that cannot be copied
+///
+/// Native AAC tops out at 8 channels and does not know ambisonic layouts, so
+/// larger or spatial tracks fall back to a lossless codec the container holds.
+fn transcode_codec<'a>(stream: &AudioStream, config: &'a AudioConfig, extension: &str) -> &'a str {
+    let too_wide_for_aac = config.codec == "aac" && (stream.channels > 8 || stream.is_ambisonic());
+    if !too_wide_for_aac && container_accepts(extension, codec_name(&config.codec)) {
+        return &config.codec;
+    }
+    match extension.to_ascii_lowercase().as_str() {
+        "mov" => "pcm_s24le",
+        "webm" => "libopus",
+        _ => "flac",
+    }
+}
+
+/// ffprobe codec name for an ffmpeg encoder name
+fn codec_name(encoder: &str) -> &str {
+    match encoder {
+        "libopus" => "opus",
+        "libvorbis" => "vorbis",
+        "libmp3lame" => "mp3",
+        "aac_at" | "libfdk_aac" => "aac",
+        other => other,
+    }
+}
+
+fn is_lossless(codec: &str) -> bool {
+    codec.starts_with("pcm_") || matches!(codec, "flac" | "alac")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(codec: &str, channels: u32, layout: Option<&str>) -> AudioStream {
+        AudioStream {
+            index: 0,
+            codec: codec.to_string(),
+            channels,
+            channel_layout: layout.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_ffprobe_audio() {
+        let json = r#"{"streams": [
+            {"codec_name": "aac", "channels": 2, "channel_layout": "stereo"},
+            {"codec_name": "pcm_s24le", "channels": 4}
+        ]}"#;
+        let streams = parse_ffprobe_audio(json).unwrap();
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[1].index, 1);
+        assert_eq!(streams[1].codec, "pcm_s24le");
+        assert!(streams[1].is_ambisonic());
+        assert!(!streams[0].is_ambisonic());
+
+        assert!(parse_ffprobe_audio("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_quad_layout_is_not_ambisonic() {
+        assert!(!stream("aac", 4, Some("quad")).is_ambisonic());
+        assert!(stream("opus", 4, Some("ambisonic 1")).is_ambisonic());
+    }
+
+    #[test]
+    fn test_copy_when_container_accepts() {
+        let streams = vec![stream("aac", 2, Some("stereo"))];
+        let args = audio_args(&streams, 1, &AudioConfig::default(), "mov");
+        assert_eq!(
+            args,
+            [
+                "-map",
+                "1:a:0",
+                "-c:a:0",
+                "copy",
+                "-map_metadata:s:a:0",
+                "1:s:a:0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pcm_transcoded_for_mp4() {
+        let streams = vec![stream("pcm_s16le", 2, Some("stereo"))];
+        let args = audio_args(&streams, 1, &AudioConfig::default(), "mp4");
+        assert!(args.windows(2).any(|w| w == ["-c:a:0", "aac"]));
+        assert!(args.windows(2).any(|w| w == ["-b:a:0", "192k"]));
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["-channel_layout:a:0", "stereo"]));
+    }
+
+    #[test]
+    fn test_ambisonic_transcode_stays_lossless() {
+        let streams = vec![stream("pcm_s24le", 4, Some("ambisonic 1"))];
+        let config = AudioConfig {
+            mode: AudioMode::Transcode,
+            ..Default::default()
+        };
+        let args = audio_args(&streams, 1, &config, "mov");
+        assert!(args.windows(2).any(|w| w == ["-c:a:0", "pcm_s24le"]));
+        assert!(!args.iter().any(|a| a.starts_with("-b:a")));
+
+        let args = audio_args(&streams, 1, &config, "mp4");
+        assert!(args.windows(2).any(|w| w == ["-c:a:0", "flac"]));
+    }
+
+    #[test]
+    fn test_cut_args_join_spans_per_stream() {
+        let streams = vec![
+            stream("aac", 2, Some("stereo")),
+            AudioStream {
+                index: 1,
+                ..stream("pcm_s24le", 4, Some("ambisonic 1"))
+            },
+        ];
+        let args = cut_args(Path::new("in.mov"), &streams, &[(1.0, 2.5), (10.0, 12.0)]);
+        assert_eq!(
+            args[..12],
+            ["-ss", "1.000000", "-to", "2.500000", "-i", "in.mov"]
+                .into_iter()
+                .chain(["-ss", "10.000000", "-to", "12.000000", "-i", "in.mov"])
+                .collect::<Vec<_>>()[..]
+        );
+        let graph = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert_eq!(
+            graph,
+            "[0:a:0][1:a:0]concat=n=2:v=0:a=1[a0];[0:a:1][1:a:1]concat=n=2:v=0:a=1[a1]"
+        );
+        assert!(args.windows(2).any(|w| w == ["-map", "[a1]"]));
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["-map_metadata:s:a:1", "0:s:a:1"]));
+    }
+
+    #[test]
+    fn test_drop_or_silent_source() {
+        let config = AudioConfig {
+            mode: AudioMode::Drop,
+            ..Default::default()
+        };
+        let streams = vec![stream("aac", 2, None)];
+        assert_eq!(audio_args(&streams, 1, &config, "mov"), ["-an"]);
+        assert_eq!(audio_args(&[], 1, &AudioConfig::default(), "mov"), ["-an"]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Model benchmarks
+//!
+//! Times each encoder size on a sample image so users can weigh speed
+//! against quality on their own hardware. For every size the report has:
+//! - How long the model took to load
+//! - Mean preprocessing, inference, and stereo generation time per image
+//!
+//! ```no_run
+//! use spatial_maker::{bench, EncoderSize};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let image = image::open("sample.jpg")?;
+//! let report = bench::run(&image, &EncoderSize::ALL).await?;
+//! println!("{}", report);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Missing models are downloaded first; download time is not counted. Each
+//! size gets one untimed warm-up pass, as the first run of a session is
+//! slower than the rest. Depth is always a single pass (tiling is skipped).
+
+use crate::depth::DepthEstimator;
+use crate::error::{SpatialError, SpatialResult};
+use crate::model::{self, EncoderSize};
+use crate::output::Projection;
+use crate::{cpu, SpatialConfig};
+use image::DynamicImage;
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Benchmark settings
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+    /// Timed passes per encoder size, after the warm-up pass
+    pub runs: u32,
+    /// Depth and stereo settings; `encoder_size` is replaced by each size
+    /// benchmarked
+    pub config: SpatialConfig,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            runs: 3,
+            config: SpatialConfig::default(),
+        }
+    }
+}
+
+/// Timings for one encoder size, in milliseconds
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BenchResult {
+    pub encoder_size: EncoderSize,
+    /// Model name (e.g. "depth-anything-v2-small")
+    pub model: String,
+    /// Model output size (width, height)
+    pub inference_size: (u32, u32),
+    /// Building the session from the model file
+    pub load_ms: f64,
+    /// Mean time to resize and normalize the image
+    pub preprocess_ms: f64,
+    /// Mean model run time
+    pub inference_ms: f64,
+    /// Mean time to render the stereo pair
+    pub stereo_ms: f64,
+}
+
+impl BenchResult {
+    /// Mean time for one image, load excluded
+    pub fn per_image_ms(&self) -> f64 {
+        self.preprocess_ms + self.inference_ms + self.stereo_ms
+    }
+}
+
+/// Benchmark results for each encoder size, in the order requested
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchReport {
+    /// Benchmark image size (width, height)
+    pub image_size: (u32, u32),
+    /// Timed passes per size
+    pub runs: u32,
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    /// The size with the lowest per-image time
+    pub fn fastest(&self) -> Option<&BenchResult> {
+        self.results
+            .iter()
+            .min_by(|a, b| a.per_image_ms().total_cmp(&b.per_image_ms()))
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}x{} image, mean of {} runs (ms)",
+            self.image_size.0, self.image_size.1, self.runs
+        )?;
+        writeln!(
+            f,
+            "{:<8} {:>9} {:>10} {:>10} {:>9} {:>10}",
+            "size", "load", "preprocess", "inference", "stereo", "per image"
+        )?;
+        for result in &self.results {
+            writeln!(
+                f,
+                "{:<8} {:>9.1} {:>10.1} {:>10.1} {:>9.1} {:>10.1}",
+                result.encoder_size.as_str(),
+                result.load_ms,
+                result.preprocess_ms,
+                result.inference_ms,
+                result.stereo_ms,
+                result.per_image_ms()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmark `sizes` on `image` with the default settings
+pub async fn run(image: &DynamicImage, sizes: &[EncoderSize]) -> SpatialResult<BenchReport> {
+    run_with_config(image, sizes, &BenchConfig::default()).await
+}
+
+/// Benchmark `sizes` on `image`
+pub async fn run_with_config(
+    image: &DynamicImage,
+    sizes: &[EncoderSize],
+    bench: &BenchConfig,
+) -> SpatialResult<BenchReport> {
+    if bench.runs == 0 {
+        return Err(SpatialError::ConfigError(
+            "Benchmarks need at least one run".to_string(),
+        ));
+    }
+    bench.config.validate()?;
+
+    let image = Arc::new(image.clone());
+    let mut results = Vec::with_capacity(sizes.len());
+    for &encoder_size in sizes {
+        tracing::info!("Benchmarking encoder: {}", encoder_size);
+        let config = SpatialConfig {
+            encoder_size,
+            ..bench.config.clone()
+        };
+        model::ensure_model_exists::<fn(u64, u64)>(encoder_size, None).await?;
+
+        let started = Instant::now();
+        let mut estimator = DepthEstimator::load(config.depth_config()).await?;
+        let load = started.elapsed();
+
+        let (image, runs) = (Arc::clone(&image), bench.runs);
+        let result = cpu::run(move || {
+            let mut totals = StageTotals::default();
+            for pass in 0..=runs {
+                let (depth, timings) = estimator.estimate_timed(&image)?;
+                let started = Instant::now();
+                crate::render_stereo(&image, &depth, &config, Projection::default())?;
+                let stereo = started.elapsed();
+
+                // Pass 0 warms the session up
+                if pass > 0 {
+                    totals.preprocess += timings.preprocess;
+                    totals.inference += timings.inference;
+                    totals.stereo += stereo;
+                }
+                totals.inference_size = depth.inference_size;
+            }
+            Ok(totals.into_result(encoder_size, load, runs))
+        })
+        .await?;
+        results.push(result);
+    }
+
+    Ok(BenchReport {
+        image_size: (image.width(), image.height()),
+        runs: bench.runs,
+        results,
+    })
+}
+
+/// Stage times summed over the timed passes
+#[derive(Default)]
+struct StageTotals {
+    preprocess: Duration,
+    inference: Duration,
+    stereo: Duration,
+    inference_size: (u32, u32),
+}
+
+impl StageTotals {
+    fn into_result(self, encoder_size: EncoderSize, load: Duration, runs: u32) -> BenchResult {
+        let mean_ms = |total: Duration| total.as_secs_f64() * 1000.0 / runs as f64;
+        BenchResult {
+            encoder_size,
+            model: model::ModelMetadata::for_encoder(encoder_size).name,
+            inference_size: self.inference_size,
+            load_ms: load.as_secs_f64() * 1000.0,
+            preprocess_ms: mean_ms(self.preprocess),
+            inference_ms: mean_ms(self.inference),
+            stereo_ms: mean_ms(self.stereo),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_totals_average_over_runs() {
+        let totals = StageTotals {
+            preprocess: Duration::from_millis(30),
+            inference: Duration::from_millis(600),
+            stereo: Duration::from_millis(90),
+            inference_size: (518, 392),
+        };
+        let result = totals.into_result(EncoderSize::Base, Duration::from_millis(250), 3);
+        assert_eq!(result.model, "depth-anything-v2-base");
+        assert_eq!(result.load_ms, 250.0);
+        assert_eq!(result.preprocess_ms, 10.0);
+        assert_eq!(result.inference_ms, 200.0);
+        assert_eq!(result.stereo_ms, 30.0);
+        assert_eq!(result.per_image_ms(), 240.0);
+    }
+
+    #[test]
+    fn test_report_fastest_and_table() {
+        let result = |encoder_size, inference_ms| BenchResult {
+            encoder_size,
+            model: String::new(),
+            inference_size: (518, 518),
+            load_ms: 100.0,
+            preprocess_ms: 5.0,
+            inference_ms,
+            stereo_ms: 20.0,
+        };
+        let report = BenchReport {
+            image_size: (1024, 768),
+            runs: 3,
+            results: vec![
+                result(EncoderSize::Base, 400.0),
+                result(EncoderSize::Small, 120.0),
+            ],
+        };
+        assert_eq!(
+            report.fastest().map(|r| r.encoder_size),
+            Some(EncoderSize::Small)
+        );
+
+        let table = report.to_string();
+        assert!(table.starts_with("1024x768 image, mean of 3 runs"));
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.lines().last().unwrap().starts_with("s "));
+    }
+}
@@ -0,0 +1,788 @@
+//! spatial-maker command-line tool
+//!
+//! Usage:
+//!   spatial-maker photo input.jpg --output spatial.jpg --encoder b
+//!   spatial-maker photo left.jpg --right right.jpg --output spatial.heic --mvhevc
+//!   spatial-maker video input.mp4 --output stereo.mp4 --format top-bottom --half
+//!   spatial-maker video input.mp4 --output clip.mp4 --duration 30
+//!   spatial-maker video clips/ --output clips-3d/
+//!   spatial-maker input.mp4 --output stereo.mp4 --json-progress
+//!   spatial-maker model download b
+//!   spatial-maker model list
+//!   spatial-maker model check s --locked
+//!
+//! Without a subcommand the input is converted as a video when its extension
+//! names a video container, and as a photo otherwise. A directory input
+//! converts every video in it into the output directory; re-running the
+//! same command resumes an interrupted batch.
+//!
+//! With `--json-progress`, stdout carries one JSON object per line and logs
+//! go to stderr:
+//!   {"event":"stage","stage":"depth"}
+//!   {"event":"file","input":"/path/to/clip.mp4"}
+//!   {"event":"progress","pct":42.0}
+//!   {"event":"done","output":"/path/to/output"}
+//!   {"event":"error","message":"..."}
+//!
+//! Exit codes: 0 on success, 1 when processing fails, 2 for invalid
+//! arguments or settings, 3 when a model is missing or fails to download,
+//! and 130 when cancelled with Ctrl-C.
+
+use clap::{Args, Parser, Subcommand};
+use serde_json::json;
+use spatial_maker::cutlist::{Cut, CutList};
+use spatial_maker::depth_cache::default_cache_dir;
+use spatial_maker::manifest::default_manifest_path;
+use spatial_maker::model::{ensure_model_exists, find_model, list_downloaded_models, model_exists};
+use spatial_maker::model_lock::{lock_installed_models, lock_path, LockStatus, ModelLock};
+use spatial_maker::naming::player_path;
+use spatial_maker::session::{GraphOptimization, SessionTuning};
+use spatial_maker::video::{is_video_path, process_video_dir};
+use spatial_maker::{
+    process_photo_pair, process_photo_with_progress, process_stereo_capture, process_video,
+    AlignConfig, CancellationToken, DepthShaping, DepthUpsampling, EncoderSize, HoleFill,
+    HoleFillStrategy, ImageEncoding, OutputFormat, OutputOptions, PlayerNaming, SpatialConfig,
+    SpatialError, SpatialResult, SubjectFocus, TargetSize, VideoConfig,
+};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_INVALID: u8 = 2;
+const EXIT_MODEL: u8 = 3;
+const EXIT_CANCELLED: u8 = 130;
+
+#[derive(Parser, Debug)]
+#[command(name = "spatial-maker", version)]
+#[command(about = "Convert photos and videos to stereo 3D with monocular depth", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Photo or video to convert (picked by the input's extension)
+    #[command(flatten)]
+    convert: Option<VideoArgs>,
+
+    /// Print progress as JSON lines on stdout
+    #[arg(long, global = true)]
+    json_progress: bool,
+
+    /// Enable verbose logging
+    #[arg(short, long, global = true)]
+    verbose: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert a photo to a stereo image
+    Photo(PhotoArgs),
+    /// Convert a video to a stereo video
+    Video(VideoArgs),
+    /// Download and inspect depth models
+    #[command(subcommand)]
+    Model(ModelCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum ModelCommand {
+    /// Download models that are not present yet
+    Download {
+        /// Encoder sizes: s (small), b (base), or l (large)
+        #[arg(default_value = "s")]
+        encoders: Vec<EncoderSize>,
+    },
+    /// List downloaded models
+    List,
+    /// Exit with code 3 unless every given model is downloaded
+    Check {
+        /// Encoder sizes: s (small), b (base), or l (large)
+        #[arg(default_value = "s")]
+        encoders: Vec<EncoderSize>,
+
+        /// Also hash each model and compare it with models.lock
+        #[arg(long)]
+        locked: bool,
+    },
+    /// Record downloaded models that are not in models.lock yet
+    Lock,
+}
+
+/// Settings shared by photo and video conversion
+#[derive(Args, Debug)]
+struct ConvertArgs {
+    /// Input file, or a directory of videos to convert as a batch
+    #[arg(value_name = "INPUT")]
+    input: PathBuf,
+
+    /// Output file, or the output directory for a batch
+    #[arg(short, long, value_name = "FILE")]
+    output: PathBuf,
+
+    /// Depth model encoder size: s (small), b (base), or l (large)
+    #[arg(short, long, default_value = "s")]
+    encoder: EncoderSize,
+
+    /// Maximum disparity for stereo generation (pixels)
+    #[arg(long, default_value = "30")]
+    max_disparity: u32,
+
+    /// Target input size for depth model (shorter side), or "auto"
+    #[arg(long, default_value = "518")]
+    target_size: TargetSize,
+
+    /// Depth gamma: below 1 makes the foreground pop more, above 1 less
+    #[arg(long, default_value = "1.0")]
+    depth_gamma: f32,
+
+    /// Depth contrast around mid-depth: above 1 spreads near and far apart
+    #[arg(long, default_value = "1.0")]
+    depth_contrast: f32,
+
+    /// Stretch depth between two percentiles, clamping outliers (e.g. 2:98)
+    #[arg(long, value_name = "LOW:HIGH")]
+    depth_clip: Option<String>,
+
+    /// Put the detected subject at the screen plane, with the background
+    /// receding behind it (strength 0-1)
+    #[arg(long, value_name = "STRENGTH", num_args = 0..=1, default_missing_value = "1.0")]
+    subject_focus: Option<f32>,
+
+    /// Cache depth maps so re-running with other settings skips inference
+    /// (default directory: ~/.spatial-maker/depth-cache)
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "")]
+    depth_cache: Option<PathBuf>,
+
+    /// How the depth map is scaled up to the image: guided (edges follow
+    /// the image), bicubic, bilinear, or nearest (blocky)
+    #[arg(long, default_value = "guided")]
+    depth_upsampling: DepthUpsampling,
+
+    /// How to fill disocclusions: background-stretch, nearest-valid,
+    /// horizontal-stretch, blur, or none
+    #[arg(long, default_value = "background-stretch")]
+    hole_fill: HoleFillStrategy,
+
+    /// How far (pixels) hole filling looks for warped pixels
+    #[arg(long, default_value = "20")]
+    hole_radius: u32,
+
+    /// Output format: side-by-side, top-bottom, cross-eye, or mirrored
+    /// (photos also take separate)
+    #[arg(short, long, default_value = "side-by-side")]
+    format: String,
+
+    /// Squeeze each eye to half size (half-SBS / half-TB)
+    #[arg(long)]
+    half: bool,
+
+    /// Tag the output file name for VR players (e.g. `_SBS_FULL`)
+    #[arg(long)]
+    player_tag: bool,
+
+    /// Disable CoreML (use CPU on macOS)
+    #[arg(long)]
+    no_coreml: bool,
+
+    /// Recompile the CoreML model on every run instead of caching it
+    #[arg(long)]
+    no_coreml_cache: bool,
+
+    /// On running out of memory, retry with a smaller target size, then a
+    /// smaller model, then the CPU instead of failing the input
+    #[arg(long)]
+    degrade_on_failure: bool,
+
+    /// GPU to run inference on (builds with the cuda or directml feature)
+    #[arg(long, value_name = "N", default_value = "0")]
+    device: u32,
+
+    /// Threads each inference operator is split across (default: the CPU
+    /// thread budget)
+    #[arg(long, value_name = "N")]
+    intra_threads: Option<usize>,
+
+    /// Threads running independent inference operators in parallel
+    #[arg(long, value_name = "N")]
+    inter_threads: Option<usize>,
+
+    /// ONNX Runtime graph optimization: disable, basic, extended, layout,
+    /// or all
+    #[arg(long, default_value = "all")]
+    graph_optimization: GraphOptimization,
+
+    /// Return freed inference memory to the system instead of keeping it
+    /// in ONNX Runtime's arena
+    #[arg(long)]
+    no_cpu_arena: bool,
+
+    /// Accepted for older callers; inputs are never downscaled before conversion
+    #[arg(long, hide = true)]
+    skip_downscale: bool,
+}
+
+#[derive(Args, Debug)]
+struct PhotoArgs {
+    #[command(flatten)]
+    convert: ConvertArgs,
+
+    /// JPEG, WebP, or AVIF quality (1-100); the encoding follows the output extension
+    #[arg(long, default_value = "95")]
+    quality: u8,
+
+    /// Right-eye photo of a stereo capture; the input is the left eye, and
+    /// the pair is rectified and written without depth estimation
+    #[arg(long, value_name = "FILE")]
+    right: Option<PathBuf>,
+
+    /// With --right, align handheld shots with a full homography instead of
+    /// only correcting a rig's rotation and vertical offset
+    #[arg(long, requires = "right")]
+    handheld: bool,
+}
+
+#[derive(Args, Debug)]
+struct VideoArgs {
+    #[command(flatten)]
+    convert: ConvertArgs,
+
+    /// Convert only the first SECONDS of the video
+    #[arg(long, value_name = "SECONDS")]
+    duration: Option<f64>,
+
+    /// Convert only the spans in this cut list (plain text, EDL, or OpenTimelineIO)
+    #[arg(long, value_name = "FILE", conflicts_with = "duration")]
+    cuts: Option<PathBuf>,
+
+    /// ffmpeg encoder for the stereo video
+    #[arg(long, default_value = "libx264")]
+    codec: String,
+
+    /// Constant rate factor (lower = better)
+    #[arg(long, default_value = "18")]
+    crf: u32,
+}
+
+/// Writes progress as JSON lines or as human-readable text
+#[derive(Clone, Copy)]
+struct Reporter {
+    json: bool,
+}
+
+impl Reporter {
+    fn stage(&self, stage: &str) {
+        if self.json {
+            println!("{}", json!({ "event": "stage", "stage": stage }));
+        } else {
+            println!("▶️  {}", stage);
+        }
+    }
+
+    fn progress(&self, pct: f32) {
+        if self.json {
+            println!("{}", json!({ "event": "progress", "pct": pct }));
+        }
+    }
+
+    fn file(&self, input: &Path) {
+        if self.json {
+            println!("{}", json!({ "event": "file", "input": input }));
+        } else {
+            println!("🎬 {:?}", input);
+        }
+    }
+
+    fn done(&self, output: &Path) {
+        if self.json {
+            println!("{}", json!({ "event": "done", "output": output }));
+        } else {
+            println!("✅ Saved to: {:?}", output);
+        }
+    }
+
+    fn error(&self, error: &SpatialError) {
+        if self.json {
+            println!(
+                "{}",
+                json!({ "event": "error", "message": error.to_string() })
+            );
+        }
+        eprintln!("❌ {}", error);
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let level = if cli.verbose { "debug" } else { "info" };
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(level)),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let reporter = Reporter {
+        json: cli.json_progress,
+    };
+
+    // Ctrl-C stops the pipeline at its next checkpoint
+    let cancel = CancellationToken::new();
+    {
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                cancel.cancel();
+            }
+        });
+    }
+
+    let result = match (cli.command, cli.convert) {
+        (Some(Command::Photo(args)), _) => run_photo(args, reporter, cancel).await,
+        (Some(Command::Video(args)), _) => run_video(args, reporter, cancel).await,
+        (Some(Command::Model(command)), _) => run_model(command, reporter).await,
+        (None, Some(args)) if is_video(&args.convert.input) => {
+            run_video(args, reporter, cancel).await
+        }
+        (None, Some(args)) => {
+            if args.duration.is_some() || args.cuts.is_some() {
+                Err(SpatialError::ConfigError(
+                    "--duration and --cuts only apply to videos".to_string(),
+                ))
+            } else {
+                let photo = PhotoArgs {
+                    convert: args.convert,
+                    quality: 95,
+                    right: None,
+                    handheld: false,
+                };
+                run_photo(photo, reporter, cancel).await
+            }
+        }
+        (None, None) => {
+            eprintln!("❌ Give an input file or a subcommand; see --help");
+            return ExitCode::from(EXIT_INVALID);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            reporter.error(&e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn exit_code(error: &SpatialError) -> u8 {
+    match error {
+        SpatialError::ConfigError(_) => EXIT_INVALID,
+        SpatialError::ModelNotFound(_)
+        | SpatialError::ModelMismatch(_)
+        | SpatialError::Download { .. } => EXIT_MODEL,
+        SpatialError::Cancelled => EXIT_CANCELLED,
+        _ => EXIT_FAILED,
+    }
+}
+
+fn is_video(path: &Path) -> bool {
+    path.is_dir() || is_video_path(path)
+}
+
+fn parse_layout(format: &str) -> SpatialResult<OutputFormat> {
+    match format.to_lowercase().as_str() {
+        "side-by-side" | "sbs" => Ok(OutputFormat::SideBySide),
+        "top-bottom" | "tb" => Ok(OutputFormat::TopAndBottom),
+        "separate" => Ok(OutputFormat::Separate),
+        "cross-eye" | "cross" => Ok(OutputFormat::CrossEye),
+        "mirrored" | "mirrored-side-by-side" => Ok(OutputFormat::MirroredSideBySide),
+        other => Err(SpatialError::ConfigError(format!(
+            "Invalid format '{}'. Use: side-by-side, top-bottom, separate, cross-eye, or mirrored",
+            other
+        ))),
+    }
+}
+
+impl ConvertArgs {
+    fn spatial_config(&self, cancel: CancellationToken) -> SpatialResult<SpatialConfig> {
+        if !self.input.exists() {
+            return Err(SpatialError::io(
+                format!("Input file not found: {:?}", self.input),
+                std::io::ErrorKind::NotFound.into(),
+            ));
+        }
+        let clip_percentiles = match &self.depth_clip {
+            Some(spec) => Some(
+                spec.split_once(':')
+                    .and_then(|(low, high)| Some((low.parse().ok()?, high.parse().ok()?)))
+                    .ok_or_else(|| {
+                        SpatialError::ConfigError(format!(
+                            "Invalid --depth-clip '{}': expected LOW:HIGH",
+                            spec
+                        ))
+                    })?,
+            ),
+            None => None,
+        };
+        let mut builder = SpatialConfig::builder()
+            .encoder_size(self.encoder)
+            .max_disparity(self.max_disparity)
+            .target_depth_size(self.target_size)
+            .use_coreml(!self.no_coreml)
+            .coreml_cache(!self.no_coreml_cache)
+            .degrade_on_failure(self.degrade_on_failure)
+            .device_id(self.device)
+            .depth_shaping(DepthShaping {
+                clip_percentiles,
+                contrast: self.depth_contrast,
+                gamma: self.depth_gamma,
+            })
+            .depth_upsampling(self.depth_upsampling)
+            .hole_fill(HoleFill {
+                strategy: self.hole_fill,
+                search_radius: self.hole_radius,
+            })
+            .session_tuning(SessionTuning {
+                intra_threads: self.intra_threads,
+                inter_threads: self.inter_threads,
+                optimization: self.graph_optimization,
+                cpu_arena: !self.no_cpu_arena,
+                ..Default::default()
+            })
+            .cancel(cancel);
+        if let Some(strength) = self.subject_focus {
+            builder = builder.subject_focus(SubjectFocus {
+                strength,
+                ..Default::default()
+            });
+        }
+        if let Some(dir) = &self.depth_cache {
+            builder = builder.depth_cache(if dir.as_os_str().is_empty() {
+                default_cache_dir()?
+            } else {
+                dir.clone()
+            });
+        }
+        builder.build()
+    }
+
+    fn output_options(&self, image_format: ImageEncoding) -> SpatialResult<OutputOptions> {
+        Ok(OutputOptions {
+            layout: parse_layout(&self.format)?,
+            image_format,
+            half_resolution: self.half,
+            player: PlayerNaming {
+                tag_filename: self.player_tag,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+}
+
+/// Download the model up front so its progress is reported separately
+async fn prepare_model(encoder_size: EncoderSize, reporter: Reporter) -> SpatialResult<PathBuf> {
+    if model_exists(encoder_size) {
+        return find_model(encoder_size);
+    }
+    reporter.stage("download");
+    let mut last_pct = -1.0;
+    ensure_model_exists(
+        encoder_size,
+        Some(|bytes: u64, total: u64| {
+            if total == 0 {
+                return;
+            }
+            let pct = (bytes as f32 / total as f32 * 100.0).floor();
+            if pct > last_pct {
+                last_pct = pct;
+                reporter.progress(pct);
+            }
+        }),
+    )
+    .await
+    .map_err(|e| match e {
+        SpatialError::Cancelled
+        | SpatialError::ModelMismatch(_)
+        | SpatialError::Download { .. } => e,
+        other => SpatialError::download("Download failed", other),
+    })
+}
+
+async fn run_photo(
+    args: PhotoArgs,
+    reporter: Reporter,
+    cancel: CancellationToken,
+) -> SpatialResult<()> {
+    let convert = &args.convert;
+    let config = convert.spatial_config(cancel)?;
+    let image_format = ImageEncoding::from_path_with_defaults(
+        &convert.output,
+        ImageEncoding::Jpeg {
+            quality: args.quality.clamp(1, 100),
+        },
+    )?;
+    let options = convert.output_options(image_format)?;
+    let output = player_path(&convert.output, &options);
+
+    // A true stereo capture needs no depth model
+    if let Some(right) = &args.right {
+        let mut options = options;
+        options.cancel = config.cancel.clone();
+        reporter.stage("align");
+        let report = if args.handheld {
+            process_photo_pair(
+                &convert.input,
+                right,
+                &convert.output,
+                &AlignConfig::default(),
+                options,
+            )
+            .await?
+        } else {
+            process_stereo_capture(&convert.input, right, &convert.output, options).await?
+        };
+        tracing::info!(
+            "Aligned on {} of {} features (RMS error {:.2} px)",
+            report.inliers,
+            report.matches,
+            report.rms_error
+        );
+        reporter.done(&output);
+        return Ok(());
+    }
+
+    prepare_model(config.encoder_size, reporter).await?;
+    process_photo_with_progress(
+        &convert.input,
+        &convert.output,
+        config,
+        options,
+        Some(|stage: spatial_maker::ProcessingStage, pct: f32| {
+            if pct < 100.0 {
+                reporter.stage(stage.name());
+            }
+            reporter.progress(pct);
+        }),
+    )
+    .await?;
+
+    reporter.done(&output);
+    Ok(())
+}
+
+async fn run_video(
+    args: VideoArgs,
+    reporter: Reporter,
+    cancel: CancellationToken,
+) -> SpatialResult<()> {
+    let convert = &args.convert;
+    let config = convert.spatial_config(cancel)?;
+    let options = convert.output_options(OutputOptions::default().image_format)?;
+    let cuts = match (&args.cuts, args.duration) {
+        (Some(path), _) => Some(CutList::load(path)?),
+        (None, Some(duration)) if duration > 0.0 => Some(CutList {
+            cuts: vec![Cut::from_secs(0.0, duration)],
+        }),
+        (None, Some(duration)) => {
+            return Err(SpatialError::ConfigError(format!(
+                "--duration must be positive, got {}",
+                duration
+            )))
+        }
+        (None, None) => None,
+    };
+    let video = VideoConfig {
+        codec: args.codec.clone(),
+        crf: args.crf,
+        cuts,
+        ..Default::default()
+    };
+    if convert.input.is_dir() {
+        return run_video_dir(convert, config, options, &video, reporter).await;
+    }
+
+    prepare_model(config.encoder_size, reporter).await?;
+    reporter.stage("depth_stereo");
+    let mut last_pct = -1.0;
+    let report = process_video(
+        &convert.input,
+        &convert.output,
+        config,
+        options,
+        &video,
+        Some(|frames: u64, total: Option<u64>| {
+            let Some(total) = total.filter(|&t| t > 0) else {
+                return;
+            };
+            let pct = (frames as f32 / total as f32 * 100.0).floor().min(100.0);
+            if pct > last_pct {
+                last_pct = pct;
+                reporter.progress(pct);
+            }
+        }),
+    )
+    .await?;
+
+    if reporter.json {
+        reporter.done(&report.output);
+    } else {
+        println!(
+            "✅ Wrote {} frames ({} resumed, {} depth passes)",
+            report.frames, report.resumed_frames, report.depth_keyframes
+        );
+        println!("   Saved to: {:?}", report.output);
+    }
+    Ok(())
+}
+
+async fn run_video_dir(
+    convert: &ConvertArgs,
+    config: SpatialConfig,
+    options: OutputOptions,
+    video: &VideoConfig,
+    reporter: Reporter,
+) -> SpatialResult<()> {
+    prepare_model(config.encoder_size, reporter).await?;
+    reporter.stage("depth_stereo");
+    let manifest_path = default_manifest_path(&convert.output);
+    let mut current = PathBuf::new();
+    let mut last_pct = -1.0;
+    let manifest = process_video_dir(
+        &convert.input,
+        &convert.output,
+        config,
+        options,
+        video,
+        Some(&manifest_path),
+        Some(|input: &Path, frames: u64, total: Option<u64>| {
+            if input != current {
+                current = input.to_path_buf();
+                last_pct = -1.0;
+                reporter.file(input);
+            }
+            let Some(total) = total.filter(|&t| t > 0) else {
+                return;
+            };
+            let pct = (frames as f32 / total as f32 * 100.0).floor().min(100.0);
+            if pct > last_pct {
+                last_pct = pct;
+                reporter.progress(pct);
+            }
+        }),
+    )
+    .await?;
+
+    let stats = &manifest.stats;
+    if !reporter.json {
+        println!(
+            "✅ Converted {} of {} videos ({} from an earlier run)",
+            stats.succeeded, stats.total, stats.resumed
+        );
+    }
+    if stats.cancelled > 0 {
+        return Err(SpatialError::Cancelled);
+    }
+    if stats.failed > 0 {
+        return Err(SpatialError::BatchFailed {
+            failed: stats.failed,
+            total: stats.total,
+            manifest: manifest_path,
+        });
+    }
+    reporter.done(&manifest_path);
+    Ok(())
+}
+
+async fn run_model(command: ModelCommand, reporter: Reporter) -> SpatialResult<()> {
+    match command {
+        ModelCommand::Download { encoders } => {
+            for encoder_size in encoders {
+                let path = prepare_model(encoder_size, reporter).await?;
+                reporter.done(&path);
+            }
+            Ok(())
+        }
+        ModelCommand::List => {
+            let models = list_downloaded_models()?;
+            if reporter.json {
+                println!("{}", json!({ "event": "models", "models": models }));
+            } else if models.is_empty() {
+                println!("No models downloaded");
+            } else {
+                for model in models {
+                    println!(
+                        "{:<6} {:<26} {:>8.1} MB  {:?}",
+                        model.encoder_size.as_str(),
+                        model.name,
+                        model.size_bytes as f64 / 1_000_000.0,
+                        model.path
+                    );
+                }
+            }
+            Ok(())
+        }
+        ModelCommand::Check { encoders, locked } => {
+            let missing: Vec<&str> = encoders
+                .iter()
+                .filter(|&&encoder_size| !model_exists(encoder_size))
+                .map(|encoder_size| encoder_size.as_str())
+                .collect();
+            if !missing.is_empty() {
+                return Err(SpatialError::ModelNotFound(format!(
+                    "Missing models: {} (run `spatial-maker model download {}`)",
+                    missing.join(", "),
+                    missing.join(" ")
+                )));
+            }
+            if locked {
+                let lock = ModelLock::load(&lock_path()?)?;
+                for encoder_size in &encoders {
+                    let path = find_model(*encoder_size)?;
+                    match lock.check(*encoder_size, &path)? {
+                        LockStatus::Verified => {}
+                        LockStatus::Changed {
+                            expected_sha256,
+                            actual_sha256,
+                            ..
+                        } => {
+                            return Err(SpatialError::ModelMismatch(format!(
+                                "Model {} does not match models.lock (sha256 {}, expected {})",
+                                encoder_size, actual_sha256, expected_sha256
+                            )))
+                        }
+                        LockStatus::Unlocked | LockStatus::NotInstalled => {
+                            return Err(SpatialError::ModelMismatch(format!(
+                                "Model {} is not in models.lock (run `spatial-maker model lock`)",
+                                encoder_size
+                            )))
+                        }
+                    }
+                }
+            }
+            if !reporter.json {
+                println!("✅ All models present");
+            }
+            Ok(())
+        }
+        ModelCommand::Lock => {
+            let lock = lock_installed_models()?;
+            if reporter.json {
+                println!("{}", json!({ "event": "lock", "lock": lock }));
+            } else {
+                for model in &lock.models {
+                    println!(
+                        "{:<6} {:<26} {}",
+                        model.encoder_size.as_str(),
+                        model.name,
+                        model.sha256
+                    );
+                }
+                println!("🔒 Wrote {:?}", lock_path()?);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Depth-of-field (bokeh) rendering
+//!
+//! Uses the depth map to blur each pixel by its distance from a focus plane,
+//! like a phone's portrait mode. The blur is a disc (the shape of an out-of-
+//! focus highlight through a round aperture) whose radius grows linearly with
+//! depth distance outside an in-focus band.
+//!
+//! The image is blurred once per radius level (1, 2, 4, ... px up to the
+//! aperture) and each pixel blends the two levels around its own radius, so
+//! cost grows with the aperture rather than with the number of distinct
+//! radii. Sharp foreground edges may bleed slightly into a blurred
+//! background.
+//!
+//! ```no_run
+//! use spatial_maker::{estimate_depth, render_bokeh, BokehConfig, DepthConfig};
+//!
+//! # async fn example(image: image::DynamicImage) -> anyhow::Result<()> {
+//! let depth = estimate_depth(&image, &DepthConfig::default()).await?;
+//! let portrait = render_bokeh(&image, &depth, &BokehConfig::default(), None)?;
+//! portrait.save("portrait.jpg")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancel::{self, CancellationToken};
+use crate::error::{SpatialError, SpatialResult};
+use crate::stereo::get_depth_at;
+use image::{DynamicImage, RgbImage};
+use ndarray::Array2;
+
+/// Depth-of-field settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct BokehConfig {
+    /// Depth to keep sharp (0-1, higher = closer); `None` focuses on the
+    /// subject at the centre of the frame
+    pub focus_depth: Option<f32>,
+
+    /// Depth distance either side of the focus plane that stays fully sharp
+    pub focus_range: f32,
+
+    /// Blur radius in pixels at the greatest depth distance
+    pub aperture: f32,
+}
+
+impl Default for BokehConfig {
+    fn default() -> Self {
+        Self {
+            focus_depth: None,
+            focus_range: 0.05,
+            aperture: 12.0,
+        }
+    }
+}
+
+impl BokehConfig {
+    /// Blur radius in pixels for a pixel at `depth` with focus at `focus`
+    pub fn blur_radius(&self, depth: f32, focus: f32) -> f32 {
+        let range = self.focus_range.clamp(0.0, 0.99);
+        let distance = ((depth - focus).abs() - range).max(0.0) / (1.0 - range);
+        self.aperture.max(0.0) * distance.min(1.0)
+    }
+}
+
+/// Render a portrait-mode style image with depth-dependent blur
+///
+/// `depth` is a normalized depth map (0-1, higher = closer) at any
+/// resolution; it is sampled by relative position.
+pub fn render_bokeh(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    config: &BokehConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<DynamicImage> {
+    if depth.is_empty() {
+        return Err(SpatialError::ConfigError("Depth map is empty".to_string()));
+    }
+
+    let source = image.to_rgb8();
+    let (width, height) = (source.width() as usize, source.height() as usize);
+    let focus = config
+        .focus_depth
+        .unwrap_or_else(|| center_depth(depth))
+        .clamp(0.0, 1.0);
+
+    tracing::info!(
+        "📷 Rendering bokeh (focus {:.2}, aperture {} px)",
+        focus,
+        config.aperture
+    );
+
+    // Radius levels: 0, 1, 2, 4, ... up to the aperture
+    let mut radii = vec![0.0f32];
+    let mut radius = 1.0f32;
+    while radius < config.aperture {
+        radii.push(radius);
+        radius *= 2.0;
+    }
+    if config.aperture > 0.0 {
+        radii.push(config.aperture);
+    }
+
+    let mut levels = Vec::with_capacity(radii.len());
+    for &radius in &radii {
+        cancel::check(cancel)?;
+        levels.push(if radius == 0.0 {
+            source.clone()
+        } else {
+            disc_blur(&source, radius)
+        });
+    }
+
+    cancel::check(cancel)?;
+    let output = RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let d = get_depth_at(depth, x as usize, y as usize, width, height);
+        let r = config.blur_radius(d, focus);
+
+        let upper = radii
+            .iter()
+            .position(|&level| level >= r)
+            .unwrap_or(radii.len() - 1);
+        if upper == 0 {
+            return *levels[0].get_pixel(x, y);
+        }
+        let lower = upper - 1;
+        let t = ((r - radii[lower]) / (radii[upper] - radii[lower])).clamp(0.0, 1.0);
+        let a = levels[lower].get_pixel(x, y);
+        let b = levels[upper].get_pixel(x, y);
+        image::Rgb(std::array::from_fn(|c| {
+            (a[c] as f32 * (1.0 - t) + b[c] as f32 * t).round() as u8
+        }))
+    });
+
+    Ok(DynamicImage::ImageRgb8(output))
+}
+
+/// Median depth of the central ninth of the frame
+fn center_depth(depth: &Array2<f32>) -> f32 {
+    let (h, w) = depth.dim();
+    let mut samples: Vec<f32> = depth
+        .slice(ndarray::s![
+            h / 3..(2 * h / 3).max(h / 3 + 1),
+            w / 3..(2 * w / 3).max(w / 3 + 1)
+        ])
+        .iter()
+        .copied()
+        .filter(|d| d.is_finite())
+        .collect();
+    if samples.is_empty() {
+        return 0.5;
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    samples[samples.len() / 2]
+}
+
+/// Average each pixel over a disc of `radius`, using per-row prefix sums so
+/// every disc row costs one subtraction
+fn disc_blur(image: &RgbImage, radius: f32) -> RgbImage {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let r = radius.ceil() as isize;
+
+    // prefix[y][(x + 1) * 3 + c] = sum of channel c over row y, columns 0..=x
+    let prefix: Vec<Vec<u32>> = image
+        .rows()
+        .map(|row| {
+            let mut sums = vec![0u32; (width + 1) * 3];
+            for (x, pixel) in row.enumerate() {
+                for c in 0..3 {
+                    sums[(x + 1) * 3 + c] = sums[x * 3 + c] + pixel[c] as u32;
+                }
+            }
+            sums
+        })
+        .collect();
+
+    // Half-width of the disc at each row offset
+    let spans: Vec<isize> = (-r..=r)
+        .map(|dy| {
+            let dy = dy as f32;
+            (radius * radius - dy * dy).max(0.0).sqrt().floor() as isize
+        })
+        .collect();
+
+    RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let (x, y) = (x as isize, y as isize);
+        let mut sum = [0u32; 3];
+        let mut count = 0u32;
+        for (i, dy) in (-r..=r).enumerate() {
+            let row = y + dy;
+            if row < 0 || row >= height as isize {
+                continue;
+            }
+            let left = (x - spans[i]).max(0) as usize;
+            let right = ((x + spans[i]) as usize).min(width - 1);
+            let sums = &prefix[row as usize];
+            for c in 0..3 {
+                sum[c] += sums[(right + 1) * 3 + c] - sums[left * 3 + c];
+            }
+            count += (right + 1 - left) as u32;
+        }
+        image::Rgb(sum.map(|s| ((s + count / 2) / count.max(1)) as u8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            if (x + y) % 2 == 0 {
+                image::Rgb([255, 255, 255])
+            } else {
+                image::Rgb([0, 0, 0])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_blur_radius() {
+        let config = BokehConfig {
+            focus_depth: Some(0.5),
+            focus_range: 0.1,
+            aperture: 10.0,
+        };
+        assert_eq!(config.blur_radius(0.55, 0.5), 0.0);
+        assert!((config.blur_radius(1.0, 0.5) - 10.0 * 0.4 / 0.9).abs() < 1e-5);
+        assert!(config.blur_radius(0.0, 1.0) <= 10.0);
+    }
+
+    #[test]
+    fn test_focus_plane_stays_sharp() {
+        let image = checkerboard(40, 20);
+        // Left half near (in focus), right half far
+        let depth = Array2::from_shape_fn((20, 40), |(_, x)| if x < 20 { 0.9 } else { 0.1 });
+        let config = BokehConfig {
+            focus_depth: Some(0.9),
+            ..Default::default()
+        };
+
+        let output = render_bokeh(&image, &depth, &config, None)
+            .unwrap()
+            .to_rgb8();
+        let source = image.to_rgb8();
+        assert_eq!(output.get_pixel(5, 10), source.get_pixel(5, 10));
+
+        // The far checkerboard is averaged towards grey
+        let far = output.get_pixel(32, 10)[0];
+        assert!((100..=155).contains(&far), "{}", far);
+    }
+
+    #[test]
+    fn test_auto_focus_uses_center() {
+        let depth = Array2::from_shape_fn((9, 9), |(y, x)| {
+            if (3..6).contains(&y) && (3..6).contains(&x) {
+                0.8
+            } else {
+                0.2
+            }
+        });
+        assert_eq!(center_depth(&depth), 0.8);
+    }
+
+    #[test]
+    fn test_disc_blur_preserves_flat_color() {
+        let flat = RgbImage::from_pixel(16, 16, image::Rgb([10, 200, 90]));
+        let blurred = disc_blur(&flat, 4.0);
+        assert!(blurred.pixels().all(|p| p.0 == [10, 200, 90]));
+    }
+
+    #[test]
+    fn test_bokeh_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let depth = Array2::from_elem((4, 4), 0.5);
+        let result = render_bokeh(
+            &checkerboard(4, 4),
+            &depth,
+            &BokehConfig::default(),
+            Some(&token),
+        );
+        assert!(matches!(result, Err(SpatialError::Cancelled)));
+    }
+}
@@ -0,0 +1,141 @@
+//! Cooperative cancellation for long-running pipeline stages
+//!
+//! A [`CancellationToken`] is a cheap, cloneable flag shared between the caller
+//! and the pipeline. Stages poll it at natural checkpoints (between inference
+//! steps, per image row, while waiting on external encoders) and return
+//! [`SpatialError::Cancelled`] promptly once it is set.
+//!
+//! The same token pauses the pipeline: while paused, the next checkpoint
+//! blocks its thread until the token is resumed or cancelled. Work that only
+//! polls [`CancellationToken::is_cancelled`], such as waiting on an external
+//! encoder, keeps running.
+
+use crate::error::{SpatialError, SpatialResult};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+/// How often a paused checkpoint looks at the token again
+#[cfg(not(target_arch = "wasm32"))]
+const PAUSE_POLL: Duration = Duration::from_millis(50);
+
+/// Shared cancellation and pause flags
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new, non-cancelled token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation; all clones of this token observe it
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Hold the pipeline at its next checkpoint until [`resume`](Self::resume)
+    /// or [`cancel`](Self::cancel)
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Let a paused pipeline continue
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the pipeline has been paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Return `Err(SpatialError::Cancelled)` if cancellation has been requested
+    ///
+    /// While paused, blocks the calling thread first, until resumed or
+    /// cancelled (not on wasm32, which cannot block).
+    pub fn check(&self) -> SpatialResult<()> {
+        #[cfg(not(target_arch = "wasm32"))]
+        while self.is_paused() && !self.is_cancelled() {
+            std::thread::sleep(PAUSE_POLL);
+        }
+        if self.is_cancelled() {
+            Err(SpatialError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Check an optional token, treating `None` as never cancelled
+pub(crate) fn check(token: Option<&CancellationToken>) -> SpatialResult<()> {
+    token.map_or(Ok(()), CancellationToken::check)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_shared_between_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(token.check().is_ok());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert!(matches!(token.check(), Err(SpatialError::Cancelled)));
+    }
+
+    #[test]
+    fn test_pause_holds_check_until_resumed() {
+        let token = CancellationToken::new();
+        token.pause();
+        assert!(token.is_paused());
+
+        let worker = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                let start = std::time::Instant::now();
+                token.check().map(|_| start.elapsed())
+            })
+        };
+        std::thread::sleep(Duration::from_millis(200));
+        token.resume();
+        let waited = worker.join().unwrap().unwrap();
+        assert!(waited >= Duration::from_millis(150));
+        assert!(!token.is_paused());
+    }
+
+    #[test]
+    fn test_cancel_releases_paused_check() {
+        let token = CancellationToken::new();
+        token.pause();
+        let worker = {
+            let token = token.clone();
+            std::thread::spawn(move || token.check())
+        };
+        token.cancel();
+        assert!(matches!(
+            worker.join().unwrap(),
+            Err(SpatialError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn test_check_optional_token() {
+        assert!(check(None).is_ok());
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(check(Some(&token)).is_err());
+    }
+}
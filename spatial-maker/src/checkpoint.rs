@@ -0,0 +1,389 @@
+//! Checkpoints for resumable video jobs
+//!
+//! Long spatial video conversions write their output in segments. A
+//! [`VideoCheckpoint`] stored next to the output records which segments are
+//! complete and how many frames they cover, so a re-run after a crash can
+//! skip finished work:
+//! - Complete segments are kept and their frames skipped
+//! - The segment that was being written is discarded and redone
+//! - A checkpoint for a different input file or different settings is ignored
+//!
+//! ```no_run
+//! use spatial_maker::checkpoint::{settings_fingerprint, VideoCheckpoint};
+//! use spatial_maker::{OutputOptions, SpatialConfig};
+//! use std::path::Path;
+//!
+//! # fn example() -> anyhow::Result<()> {
+//! let input = Path::new("input.mp4");
+//! let output = Path::new("output_sbs.mp4");
+//! let fingerprint = settings_fingerprint(&SpatialConfig::default(), &OutputOptions::default());
+//!
+//! let checkpoint_path = VideoCheckpoint::path_for(output);
+//! let mut checkpoint = match VideoCheckpoint::load_matching(&checkpoint_path, input, &fingerprint)? {
+//!     Some(mut existing) => {
+//!         for stale in existing.discard_partial() {
+//!             let _ = std::fs::remove_file(stale);
+//!         }
+//!         existing
+//!     }
+//!     None => VideoCheckpoint::new(input, &fingerprint)?,
+//! };
+//! let start_frame = checkpoint.frames_completed;
+//! // ... seek to `start_frame`, then for each segment:
+//! checkpoint.begin_segment(Path::new("output_sbs.part0003.mp4"));
+//! checkpoint.record_frames(240);
+//! checkpoint.complete_segment();
+//! checkpoint.save(&checkpoint_path)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::output::{OutputOptions, Projection};
+use crate::SpatialConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Checkpoint schema version, bumped on incompatible changes
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// Progress of a video job, persisted between runs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VideoCheckpoint {
+    /// Checkpoint schema version
+    pub version: u32,
+    /// Input video path
+    pub input: PathBuf,
+    /// Input size in bytes when the job started
+    pub input_bytes: u64,
+    /// Input modification time (seconds since the Unix epoch) when the job started
+    pub input_modified: u64,
+    /// Fingerprint of the settings the job runs with (see [`settings_fingerprint`])
+    pub settings: String,
+    /// Frames fully written, counted from the start of the video
+    pub frames_completed: u64,
+    /// Output segments in frame order
+    pub segments: Vec<CheckpointSegment>,
+}
+
+/// One output file covering a contiguous run of frames
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointSegment {
+    /// Segment file path
+    pub path: PathBuf,
+    /// First frame written to this segment
+    pub start_frame: u64,
+    /// Frames written so far
+    pub frame_count: u64,
+    /// Whether the segment file was finalized
+    pub complete: bool,
+}
+
+impl VideoCheckpoint {
+    /// Start a checkpoint for a fresh run over `input`
+    pub fn new(input: &Path, settings: &str) -> SpatialResult<Self> {
+        let (input_bytes, input_modified) = input_identity(input)?;
+        Ok(Self {
+            version: CHECKPOINT_VERSION,
+            input: input.to_path_buf(),
+            input_bytes,
+            input_modified,
+            settings: settings.to_string(),
+            frames_completed: 0,
+            segments: Vec::new(),
+        })
+    }
+
+    /// Checkpoint location for a job writing `output` (`<output>.checkpoint.json`)
+    pub fn path_for(output: &Path) -> PathBuf {
+        let mut name = output.file_name().unwrap_or_default().to_os_string();
+        name.push(".checkpoint.json");
+        output.with_file_name(name)
+    }
+
+    /// Load the checkpoint at `path` if it belongs to this input and settings
+    ///
+    /// Returns `None` when there is no checkpoint, or when it was written for
+    /// a different or modified input, different settings, or an older schema.
+    pub fn load_matching(path: &Path, input: &Path, settings: &str) -> SpatialResult<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| SpatialError::io(format!("Failed to read {:?}", path), e))?;
+        let checkpoint: Self = match serde_json::from_str(&json) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable checkpoint {:?}: {}", path, e);
+                return Ok(None);
+            }
+        };
+
+        let (input_bytes, input_modified) = input_identity(input)?;
+        if checkpoint.version != CHECKPOINT_VERSION
+            || checkpoint.input != input
+            || checkpoint.input_bytes != input_bytes
+            || checkpoint.input_modified != input_modified
+            || checkpoint.settings != settings
+        {
+            tracing::info!(
+                "Checkpoint {:?} is for a different job, starting over",
+                path
+            );
+            return Ok(None);
+        }
+
+        tracing::info!(
+            "⏩ Resuming from checkpoint: {} frames in {} segments",
+            checkpoint.frames_completed,
+            checkpoint.segments.len()
+        );
+        Ok(Some(checkpoint))
+    }
+
+    /// Write the checkpoint, replacing any previous one atomically
+    pub fn save(&self, path: &Path) -> SpatialResult<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| SpatialError::io("Failed to serialize checkpoint", e.into()))?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, json)
+            .map_err(|e| SpatialError::io(format!("Failed to write {:?}", temp_path), e))?;
+        std::fs::rename(&temp_path, path)
+            .map_err(|e| SpatialError::io(format!("Failed to write {:?}", path), e))?;
+        Ok(())
+    }
+
+    /// Delete the checkpoint at `path` once the job has finished
+    pub fn remove(path: &Path) -> SpatialResult<()> {
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Drop segments that were not finalized and rewind to the end of the
+    /// last complete one
+    ///
+    /// Returns the dropped segment paths so the caller can delete the files.
+    pub fn discard_partial(&mut self) -> Vec<PathBuf> {
+        let (complete, partial): (Vec<_>, Vec<_>) = self
+            .segments
+            .drain(..)
+            .partition(|segment| segment.complete);
+        self.segments = complete;
+        self.frames_completed = self
+            .segments
+            .iter()
+            .map(|segment| segment.start_frame + segment.frame_count)
+            .max()
+            .unwrap_or(0);
+        partial.into_iter().map(|segment| segment.path).collect()
+    }
+
+    /// Start writing a new segment at the current frame
+    pub fn begin_segment(&mut self, path: &Path) {
+        self.segments.push(CheckpointSegment {
+            path: path.to_path_buf(),
+            start_frame: self.frames_completed,
+            frame_count: 0,
+            complete: false,
+        });
+    }
+
+    /// Count `frames` as written to the current segment
+    pub fn record_frames(&mut self, frames: u64) {
+        self.frames_completed += frames;
+        if let Some(segment) = self.segments.last_mut().filter(|s| !s.complete) {
+            segment.frame_count += frames;
+        }
+    }
+
+    /// Mark the current segment as finalized
+    pub fn complete_segment(&mut self) {
+        if let Some(segment) = self.segments.last_mut() {
+            segment.complete = true;
+        }
+    }
+
+    /// Paths of the finalized segments, in frame order
+    pub fn completed_segments(&self) -> Vec<&Path> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.complete)
+            .map(|segment| segment.path.as_path())
+            .collect()
+    }
+}
+
+/// Fingerprint of the settings that affect output frames
+///
+/// A checkpoint is only resumed when the fingerprint matches, so changing
+/// the model, disparity, or layout starts the job over. The worker count
+/// session tuning, CoreML cache, and GPU device do not change the frames, so
+/// a job can resume with more or fewer workers or threads, or on another GPU.
+pub fn settings_fingerprint(config: &SpatialConfig, options: &OutputOptions) -> String {
+    let config = SpatialConfig {
+        parallel: Default::default(),
+        session_tuning: Default::default(),
+        coreml_cache: true,
+        device_id: 0,
+        ..config.clone()
+    };
+    let config_json = serde_json::to_string(&config).unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(config_json.as_bytes());
+    hasher.update(options.layout.name().as_bytes());
+    hasher.update(options.image_format.extension().as_bytes());
+    hasher.update([options.half_resolution as u8]);
+    hasher.update([match options.projection {
+        Projection::Rectilinear => 0u8,
+        Projection::Vr180 => 1,
+        Projection::Panorama360 => 2,
+    }]);
+    // Only hashed when set, so checkpoints from before it existed still match
+    if options.player.frame_packing_sei {
+        hasher.update(b"frame-packing-sei");
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Size and modification time identifying a version of the input file
+fn input_identity(input: &Path) -> SpatialResult<(u64, u64)> {
+    let metadata = std::fs::metadata(input)
+        .map_err(|e| SpatialError::io(format!("Failed to read {:?}", input), e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok((metadata.len(), modified))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::OutputFormat;
+
+    #[test]
+    fn test_checkpoint_path() {
+        assert_eq!(
+            VideoCheckpoint::path_for(Path::new("/out/video_sbs.mp4")),
+            PathBuf::from("/out/video_sbs.mp4.checkpoint.json")
+        );
+    }
+
+    #[test]
+    fn test_resume_discards_partial_segment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input = temp_dir.path().join("input.mp4");
+        std::fs::write(&input, b"video").unwrap();
+        let checkpoint_path = VideoCheckpoint::path_for(&temp_dir.path().join("out.mp4"));
+
+        let mut checkpoint = VideoCheckpoint::new(&input, "settings").unwrap();
+        checkpoint.begin_segment(Path::new("part0.mp4"));
+        checkpoint.record_frames(100);
+        checkpoint.complete_segment();
+        checkpoint.begin_segment(Path::new("part1.mp4"));
+        checkpoint.record_frames(40);
+        checkpoint.save(&checkpoint_path).unwrap();
+
+        let mut resumed = VideoCheckpoint::load_matching(&checkpoint_path, &input, "settings")
+            .unwrap()
+            .unwrap();
+        assert_eq!(resumed.frames_completed, 140);
+        assert_eq!(resumed.discard_partial(), vec![PathBuf::from("part1.mp4")]);
+        assert_eq!(resumed.frames_completed, 100);
+        assert_eq!(resumed.completed_segments(), vec![Path::new("part0.mp4")]);
+
+        resumed.begin_segment(Path::new("part1.mp4"));
+        assert_eq!(resumed.segments[1].start_frame, 100);
+
+        VideoCheckpoint::remove(&checkpoint_path).unwrap();
+        assert!(!checkpoint_path.exists());
+        VideoCheckpoint::remove(&checkpoint_path).unwrap();
+    }
+
+    #[test]
+    fn test_mismatched_checkpoint_is_ignored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input = temp_dir.path().join("input.mp4");
+        std::fs::write(&input, b"video").unwrap();
+        let checkpoint_path = temp_dir.path().join("out.mp4.checkpoint.json");
+
+        VideoCheckpoint::new(&input, "settings")
+            .unwrap()
+            .save(&checkpoint_path)
+            .unwrap();
+        assert!(
+            VideoCheckpoint::load_matching(&checkpoint_path, &input, "other")
+                .unwrap()
+                .is_none()
+        );
+
+        // Input replaced with different content
+        std::fs::write(&input, b"another video").unwrap();
+        assert!(
+            VideoCheckpoint::load_matching(&checkpoint_path, &input, "settings")
+                .unwrap()
+                .is_none()
+        );
+
+        std::fs::write(&checkpoint_path, b"{ not json").unwrap();
+        assert!(
+            VideoCheckpoint::load_matching(&checkpoint_path, &input, "settings")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_settings_fingerprint() {
+        let config = SpatialConfig::default();
+        let sbs = OutputOptions::default();
+        let tb = OutputOptions {
+            layout: OutputFormat::TopAndBottom,
+            ..Default::default()
+        };
+        assert_eq!(
+            settings_fingerprint(&config, &sbs),
+            settings_fingerprint(&config, &sbs)
+        );
+        assert_ne!(
+            settings_fingerprint(&config, &sbs),
+            settings_fingerprint(&config, &tb)
+        );
+
+        let parallel = SpatialConfig {
+            parallel: crate::ParallelConfig {
+                workers: 8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            settings_fingerprint(&config, &sbs),
+            settings_fingerprint(&parallel, &sbs)
+        );
+
+        let tuned = SpatialConfig {
+            session_tuning: crate::SessionTuning {
+                intra_threads: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            settings_fingerprint(&config, &sbs),
+            settings_fingerprint(&tuned, &sbs)
+        );
+    }
+}
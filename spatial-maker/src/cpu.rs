@@ -0,0 +1,176 @@
+//! CPU-heavy stages off the async executor
+//!
+//! Decoding, model loading, inference, stereo warping, and encoding take
+//! from hundreds of milliseconds to minutes. Run directly inside an `async`
+//! function they hold an executor thread for that long, stalling every other
+//! task scheduled on it (in a host app, its IPC and UI events). The async
+//! entry points hand each job to a thread of its own instead and await the
+//! result.
+//!
+//! The parallel stages of video and batch processing (the workers of
+//! [`crate::pool`]) run on a pool sized by the crate's CPU thread budget, so
+//! jobs running side by side share it rather than each starting its own
+//! threads; ONNX Runtime sessions split the same budget for inference. It
+//! defaults to one less than the available cores, leaving one for the host,
+//! and can be set once before processing starts:
+//!
+//! ```
+//! spatial_maker::cpu::set_threads(4).unwrap();
+//! assert_eq!(spatial_maker::cpu::threads(), 4);
+//! ```
+//!
+//! Dropping a future does not stop a job already started; use a
+//! [`crate::CancellationToken`] for that.
+
+use crate::error::{SpatialError, SpatialResult};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use tokio::sync::{mpsc, oneshot};
+
+static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// Requested thread count; 0 until set
+static REQUESTED_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Set the CPU thread budget
+///
+/// Must be called before the first photo, video, or model is processed; the
+/// pool is started then and keeps its size. 0 restores the default.
+pub fn set_threads(threads: usize) -> SpatialResult<()> {
+    if POOL.get().is_some() {
+        return Err(SpatialError::ConfigError(
+            "The CPU thread budget is set before processing starts".to_string(),
+        ));
+    }
+    REQUESTED_THREADS.store(threads, Ordering::SeqCst);
+    Ok(())
+}
+
+/// The CPU thread budget: the pool size, or what it will be once started
+pub fn threads() -> usize {
+    if let Some(pool) = POOL.get() {
+        return pool.current_num_threads();
+    }
+    match REQUESTED_THREADS.load(Ordering::SeqCst) {
+        0 => default_threads(),
+        n => n,
+    }
+}
+
+fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get().saturating_sub(1).max(1))
+}
+
+fn pool() -> &'static rayon::ThreadPool {
+    POOL.get_or_init(|| {
+        let threads = threads();
+        tracing::debug!("Starting CPU pool with {} threads", threads);
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("spatial-maker-cpu-{}", index))
+            .build()
+            .expect("failed to start the CPU thread pool")
+    })
+}
+
+/// Run `op` with a scope whose spawned work runs on the pool
+///
+/// `op` itself runs on the calling thread, which waits for the spawned work
+/// before returning. Work beyond the pool size waits for a free thread.
+pub(crate) fn scope<'scope, R>(op: impl FnOnce(&rayon::Scope<'scope>) -> R) -> R {
+    pool().in_place_scope(op)
+}
+
+/// Start `work` on a thread of its own now, returning a future for its
+/// result
+///
+/// The thread is outside the pool: a job holds it from start to finish,
+/// while its parallel stages take pool threads only as long as they run. A
+/// panic in `work` comes back as an error.
+pub(crate) fn spawn<T, F>(work: F) -> impl Future<Output = SpatialResult<T>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> SpatialResult<T> + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    let started = std::thread::Builder::new()
+        .name("spatial-maker-job".to_string())
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(work))
+                .unwrap_or_else(|_| Err(SpatialError::Worker("A CPU task panicked".to_string())));
+            let _ = tx.send(result);
+        });
+    async move {
+        started.map_err(|e| SpatialError::io("Failed to start a CPU task", e))?;
+        rx.await
+            .map_err(|_| SpatialError::Worker("A CPU task was dropped".to_string()))?
+    }
+}
+
+/// Run `work` off the executor and wait for it without blocking
+pub(crate) async fn run<T, F>(work: F) -> SpatialResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> SpatialResult<T> + Send + 'static,
+{
+    spawn(work).await
+}
+
+/// [`run`] for work that reports progress
+///
+/// `work` reports through the function it is given; each report is passed
+/// to `on_progress` on the awaiting task, so the callback need not be `Send`.
+pub(crate) async fn run_with_progress<T, P, F, R>(work: F, mut on_progress: R) -> SpatialResult<T>
+where
+    T: Send + 'static,
+    P: Send + 'static,
+    F: FnOnce(&mut dyn FnMut(P)) -> SpatialResult<T> + Send + 'static,
+    R: FnMut(P),
+{
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let result = spawn(move || {
+        work(&mut |progress| {
+            let _ = progress_tx.send(progress);
+        })
+    });
+    // The sender goes away with `work`, ending this loop
+    while let Some(progress) = progress_rx.recv().await {
+        on_progress(progress);
+    }
+    result.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_run_leaves_executor_free() {
+        let executor = std::thread::current().id();
+        let worker = run(|| Ok(std::thread::current().id())).await.unwrap();
+        assert_ne!(worker, executor);
+
+        let result: SpatialResult<()> = run(|| panic!("boom")).await;
+        assert!(matches!(result, Err(SpatialError::Worker(_))));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_run_with_progress_forwards_reports() {
+        let mut seen = Vec::new();
+        let total = run_with_progress(
+            |report| {
+                for n in 1..=3 {
+                    report(n);
+                }
+                Ok(6)
+            },
+            |n: u32| seen.push(n),
+        )
+        .await
+        .unwrap();
+        assert_eq!(total, 6);
+        assert_eq!(seen, vec![1, 2, 3]);
+    }
+}
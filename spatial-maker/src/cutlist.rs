@@ -0,0 +1,382 @@
+//! Cut lists for converting only parts of a video
+//!
+//! A [`CutList`] names spans of the source video. With [`VideoConfig::cuts`]
+//! set, [`process_video`] converts just those spans and joins them, in list
+//! order, into one output, so highlights can be taken from long footage
+//! without trimming it first. [`CutList::parse`] reads:
+//! - Plain text, one `start end` pair per line, in seconds or
+//!   `[HH:]MM:SS[.fff]` (`#` starts a comment)
+//! - CMX 3600 EDLs, using each event's source in and out points
+//! - OpenTimelineIO (`.otio`) JSON, using the source ranges of the clips on
+//!   the first video track
+//!
+//! ```
+//! use spatial_maker::cutlist::CutList;
+//!
+//! let cuts = CutList::parse("0:10 0:25\n1:02:00 1:02:30.5 # goal").unwrap();
+//! assert_eq!(cuts.frame_ranges(30.0, None).unwrap(), [300..750, 111600..112515]);
+//! ```
+//!
+//! [`VideoConfig::cuts`]: crate::video::VideoConfig::cuts
+//! [`process_video`]: crate::video::process_video
+
+use crate::error::{SpatialError, SpatialResult};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+/// A point in the source video
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestamp {
+    /// Microseconds from the start
+    Micros(u64),
+    /// SMPTE timecode: whole seconds plus frames counted at the nominal
+    /// (rounded) frame rate, as EDLs write it
+    Timecode { seconds: u64, frames: u64 },
+}
+
+impl Timestamp {
+    /// `seconds` from the start, to the microsecond
+    pub fn from_secs(seconds: f64) -> Self {
+        Timestamp::Micros((seconds.max(0.0) * 1_000_000.0).round() as u64)
+    }
+
+    /// Index of the frame at this point in a video running at `fps`
+    pub fn frame(self, fps: f64) -> u64 {
+        match self {
+            Timestamp::Micros(micros) => (micros as f64 * fps / 1_000_000.0).round() as u64,
+            Timestamp::Timecode { seconds, frames } => {
+                seconds * (fps.round() as u64).max(1) + frames
+            }
+        }
+    }
+}
+
+/// A span of the source video, end exclusive
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cut {
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+impl Cut {
+    /// The span from `start` to `end` seconds
+    pub fn from_secs(start: f64, end: f64) -> Self {
+        Self {
+            start: Timestamp::from_secs(start),
+            end: Timestamp::from_secs(end),
+        }
+    }
+}
+
+/// Spans of a video to convert, in output order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CutList {
+    pub cuts: Vec<Cut>,
+}
+
+impl CutList {
+    /// Read a cut list file (plain text, EDL, or OpenTimelineIO)
+    pub fn load(path: &Path) -> SpatialResult<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| SpatialError::io(format!("Failed to read {:?}", path), e))?;
+        Self::parse(&text)
+    }
+
+    /// Parse a cut list, detecting its format from the content
+    pub fn parse(text: &str) -> SpatialResult<Self> {
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        let cuts = if text.starts_with('{') {
+            parse_otio(text)?
+        } else if is_edl(text) {
+            parse_edl(text)
+        } else {
+            parse_plain(text)?
+        };
+        if cuts.is_empty() {
+            return Err(SpatialError::ConfigError(
+                "Cut list has no cuts".to_string(),
+            ));
+        }
+        Ok(Self { cuts })
+    }
+
+    /// Frame ranges of the cuts at `fps`, in list order
+    ///
+    /// Cuts may overlap or repeat. With the video's frame count as `total`,
+    /// cuts running past the end are shortened and cuts starting after it
+    /// are rejected.
+    pub fn frame_ranges(&self, fps: f64, total: Option<u64>) -> SpatialResult<Vec<Range<u64>>> {
+        self.cuts
+            .iter()
+            .enumerate()
+            .map(|(i, cut)| {
+                let (start, mut end) = (cut.start.frame(fps), cut.end.frame(fps));
+                if end <= start {
+                    return Err(SpatialError::ConfigError(format!(
+                        "Cut {} ends before it starts",
+                        i + 1
+                    )));
+                }
+                if let Some(total) = total {
+                    if start >= total {
+                        return Err(SpatialError::ConfigError(format!(
+                            "Cut {} starts after the end of the video ({} frames)",
+                            i + 1,
+                            total
+                        )));
+                    }
+                    end = end.min(total);
+                }
+                Ok(start..end)
+            })
+            .collect()
+    }
+}
+
+/// `start end` pairs, one per line
+fn parse_plain(text: &str) -> SpatialResult<Vec<Cut>> {
+    let mut cuts = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '-')
+            .filter(|field| !field.is_empty())
+            .collect();
+        let invalid = || {
+            SpatialError::ConfigError(format!(
+                "Cut list line {}: expected a start and end time, got {:?}",
+                n + 1,
+                line
+            ))
+        };
+        let [start, end] = fields[..] else {
+            return Err(invalid());
+        };
+        cuts.push(Cut {
+            start: parse_time(start).ok_or_else(invalid)?,
+            end: parse_time(end).ok_or_else(invalid)?,
+        });
+    }
+    Ok(cuts)
+}
+
+/// Seconds, `MM:SS[.fff]`, or `HH:MM:SS[.fff]`
+fn parse_time(text: &str) -> Option<Timestamp> {
+    let mut parts = text.rsplit(':');
+    let mut seconds: f64 = parts.next()?.parse().ok()?;
+    for (part, scale) in parts.zip([60.0, 3600.0]) {
+        seconds += part.parse::<u64>().ok()? as f64 * scale;
+    }
+    if text.matches(':').count() > 2 || !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Some(Timestamp::from_secs(seconds))
+}
+
+/// `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame timecode
+fn parse_timecode(text: &str) -> Option<Timestamp> {
+    let parts: Vec<u64> = text
+        .split([':', ';'])
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [hours, minutes, seconds, frames] = parts[..] else {
+        return None;
+    };
+    Some(Timestamp::Timecode {
+        seconds: hours * 3600 + minutes * 60 + seconds,
+        frames,
+    })
+}
+
+fn is_edl(text: &str) -> bool {
+    text.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("TITLE:") || line.starts_with("FCM:") || edl_event(line).is_some()
+    })
+}
+
+/// Event number, track, and source in/out of an EDL event line
+fn edl_event(line: &str) -> Option<(&str, &str, Cut)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (&number, &track) = (fields.first()?, fields.get(2)?);
+    if !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let timecodes: Vec<Timestamp> = fields.iter().filter_map(|f| parse_timecode(f)).collect();
+    // Source in, source out, record in, record out
+    let [start, end, _, _] = timecodes[..] else {
+        return None;
+    };
+    Some((number, track, Cut { start, end }))
+}
+
+/// Source ranges of the picture events of a CMX 3600 EDL
+fn parse_edl(text: &str) -> Vec<Cut> {
+    let mut seen = HashSet::new();
+    text.lines()
+        .filter_map(|line| edl_event(line.trim()))
+        // Audio-only events ("A", "A2", "AA") carry no picture
+        .filter(|(_, track, _)| !track.starts_with('A') || track.contains('V'))
+        // Split edits list the same event once per track
+        .filter(|(number, _, _)| seen.insert(*number))
+        .map(|(_, _, cut)| cut)
+        .collect()
+}
+
+/// Source ranges of the clips on the first video track of an OpenTimelineIO
+/// timeline
+fn parse_otio(text: &str) -> SpatialResult<Vec<Cut>> {
+    let invalid =
+        |what: &str| SpatialError::ConfigError(format!("Invalid OTIO cut list: {}", what));
+    let root: Value = serde_json::from_str(text).map_err(|e| invalid(&e.to_string()))?;
+    let track = first_video_track(&root).ok_or_else(|| invalid("no video track"))?;
+    let children = track["children"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    children
+        .iter()
+        .filter(|child| schema(child).starts_with("Clip"))
+        .map(|clip| {
+            let range = [
+                &clip["source_range"],
+                &clip["media_reference"]["available_range"],
+            ]
+            .into_iter()
+            .find_map(time_range)
+            .ok_or_else(|| invalid("clip without a source range"))?;
+            Ok(Cut::from_secs(range.0, range.0 + range.1))
+        })
+        .collect()
+}
+
+fn schema(value: &Value) -> &str {
+    value["OTIO_SCHEMA"].as_str().unwrap_or_default()
+}
+
+fn first_video_track(value: &Value) -> Option<&Value> {
+    if schema(value).starts_with("Track") && value["kind"].as_str() == Some("Video") {
+        return Some(value);
+    }
+    ["tracks", "children"]
+        .into_iter()
+        .find_map(|key| match &value[key] {
+            Value::Array(items) => items.iter().find_map(first_video_track),
+            child @ Value::Object(_) => first_video_track(child),
+            _ => None,
+        })
+}
+
+/// Start and duration in seconds of an OTIO `TimeRange`
+fn time_range(range: &Value) -> Option<(f64, f64)> {
+    let seconds = |time: &Value| {
+        let (value, rate) = (time["value"].as_f64()?, time["rate"].as_f64()?);
+        (rate > 0.0).then(|| value / rate)
+    };
+    Some((seconds(&range["start_time"])?, seconds(&range["duration"])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_list() {
+        let cuts = CutList::parse(
+            "# highlights\n\
+             12.5 20\n\
+             \n\
+             1:00, 1:30.25\n\
+             01:02:03-01:02:04.5 # last\n",
+        )
+        .unwrap();
+        assert_eq!(
+            cuts.cuts,
+            [
+                Cut::from_secs(12.5, 20.0),
+                Cut::from_secs(60.0, 90.25),
+                Cut::from_secs(3723.0, 3724.5),
+            ]
+        );
+
+        assert!(CutList::parse("10").is_err());
+        assert!(CutList::parse("10 20 30").is_err());
+        assert!(CutList::parse("1:2:3:4 5").is_err());
+        assert!(CutList::parse("# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_edl() {
+        let edl = "TITLE: Highlights\n\
+                   FCM: NON-DROP FRAME\n\
+                   \n\
+                   001  AX       V     C        00:00:10:00 00:00:12:15 01:00:00:00 01:00:02:15\n\
+                   * FROM CLIP NAME: match.mov\n\
+                   002  AX       A     C        00:05:00:00 00:05:10:00 01:00:02:15 01:00:12:15\n\
+                   003  AX       AA/V  C        00:20:00:10 00:20:01:00 01:00:12:15 01:00:13:05\n\
+                   003  AX       A2    C        00:20:00:10 00:20:01:00 01:00:12:15 01:00:13:05\n";
+        let cuts = CutList::parse(edl).unwrap();
+        assert_eq!(cuts.cuts.len(), 2);
+        assert_eq!(
+            cuts.frame_ranges(25.0, None).unwrap(),
+            [250..315, 30010..30025]
+        );
+        // Timecode frames count at the nominal rate
+        assert_eq!(cuts.frame_ranges(29.97, None).unwrap()[0], 300..375);
+    }
+
+    #[test]
+    fn test_otio() {
+        let otio = r#"{
+            "OTIO_SCHEMA": "Timeline.1",
+            "tracks": {
+                "OTIO_SCHEMA": "Stack.1",
+                "children": [
+                    {"OTIO_SCHEMA": "Track.1", "kind": "Audio", "children": []},
+                    {"OTIO_SCHEMA": "Track.1", "kind": "Video", "children": [
+                        {"OTIO_SCHEMA": "Clip.2", "source_range": {
+                            "start_time": {"value": 48, "rate": 24},
+                            "duration": {"value": 24, "rate": 24}}},
+                        {"OTIO_SCHEMA": "Gap.1", "source_range": {
+                            "start_time": {"value": 0, "rate": 24},
+                            "duration": {"value": 12, "rate": 24}}},
+                        {"OTIO_SCHEMA": "Clip.2", "source_range": null,
+                         "media_reference": {"available_range": {
+                            "start_time": {"value": 0, "rate": 1},
+                            "duration": {"value": 1.5, "rate": 1}}}}
+                    ]}
+                ]
+            }
+        }"#;
+        let cuts = CutList::parse(otio).unwrap();
+        assert_eq!(
+            cuts.cuts,
+            [Cut::from_secs(2.0, 3.0), Cut::from_secs(0.0, 1.5)]
+        );
+
+        assert!(CutList::parse(r#"{"OTIO_SCHEMA": "Timeline.1"}"#).is_err());
+    }
+
+    #[test]
+    fn test_frame_ranges_checked_against_video() {
+        let cuts = CutList {
+            cuts: vec![Cut::from_secs(1.0, 2.0), Cut::from_secs(9.0, 12.0)],
+        };
+        assert_eq!(
+            cuts.frame_ranges(10.0, Some(100)).unwrap(),
+            [10..20, 90..100]
+        );
+        assert!(cuts.frame_ranges(10.0, Some(80)).is_err());
+
+        let backwards = CutList {
+            cuts: vec![Cut::from_secs(5.0, 4.0)],
+        };
+        assert!(backwards.frame_ranges(30.0, None).is_err());
+    }
+}
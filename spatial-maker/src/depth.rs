@@ -1,67 +1,576 @@
 //! Depth estimation using ONNX models
 //!
 //! This module handles:
-//! - Loading ONNX models from disk via the `ort` crate
-//! - Preprocessing images (resize, normalize, convert to NCHW tensor)
+//! - Loading ONNX models from disk via the `ort` crate (or, in the browser,
+//!   from memory or a URL via ONNX Runtime Web; see the `web` module)
+//! - Preprocessing images (resize with [`DepthConfig::resize_filter`],
+//!   normalize, convert to NCHW tensor)
 //! - Running inference
 //! - Extracting and validating depth map output
+//! - Tiling very large images (see [`crate::tiling`])
+//! - Retrying on a lighter setup when a pass runs out of memory or the
+//!   execution provider fails (see [`DepthConfig::degrade_on_failure`])
+//! - Reusing depth already estimated for the same image and settings (see
+//!   [`DepthConfig::cache`])
+//! - Warming a model up ahead of the first image (see [`warmup`])
 
+use crate::cancel::{self, CancellationToken};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::depth_cache::{self, CacheKeySettings, DepthCache};
+use crate::depth_map::{DepthMap, DepthShaping};
 use crate::error::{SpatialError, SpatialResult};
-use crate::model;
+use crate::model::{self, EncoderSize};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::model_lock;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::progress::ProgressEvent;
+use crate::progress::ProgressSink;
+use crate::resample::ResizeFilter;
+use crate::session::SessionTuning;
+use crate::tiling::TilingConfig;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::tiling::{self, TileBlender};
 use image::DynamicImage;
+use ort::environment::Environment;
 use ort::session::Session;
+use std::path::PathBuf;
+use std::sync::Mutex;
 
 /// Configuration for depth estimation
 #[derive(Clone, Debug)]
 pub struct DepthConfig {
-    /// Model size: "s" (small), "b" (base), or "l" (large)
-    pub encoder_size: String,
+    /// Model size
+    pub encoder_size: EncoderSize,
 
     /// Target input size for the shorter side (in pixels)
     /// The model will resize to this size, maintaining aspect ratio
-    pub target_size: u32,
+    pub target_size: TargetSize,
 
     /// Whether to use CoreML execution provider (macOS only)
+    ///
+    /// On by default, as CoreML runs the model on the Neural Engine and GPU.
+    /// ONNX Runtime uses the CPU provider when CoreML cannot be registered.
     pub use_coreml: bool,
+
+    /// Keep the model CoreML compiles on first load in a cache directory
+    /// (see [`model::get_coreml_cache_dir`]), so later runs skip the
+    /// compilation. macOS only.
+    pub coreml_cache: bool,
+
+    /// GPU to run inference on, for builds with the `cuda` or `directml`
+    /// feature on machines with more than one (0 is the first)
+    pub device_id: u32,
+
+    /// Filter for scaling the image (or each tile) to the model's input
+    pub resize_filter: ResizeFilter,
+
+    /// Optional token checked between estimation steps
+    pub cancel: Option<CancellationToken>,
+
+    /// Receives [`ProgressEvent::ModelDownload`] while the model downloads
+    pub progress: Option<ProgressSink>,
+
+    /// Split images larger than one tile into overlapping tiles; `None`
+    /// always uses a single pass
+    pub tiling: Option<TilingConfig>,
+
+    /// When loading or a pass fails for lack of memory or in the execution
+    /// provider, retry one step down the degradation ladder at a time
+    /// instead of failing:
+    /// 1. Smaller target size, through [`AUTO_TARGET_SIZES`] down to 518
+    /// 2. Smaller model (large, then base, then small), if already downloaded
+    /// 3. CPU execution provider instead of CoreML (macOS)
+    ///
+    /// Each step logs a warning. The estimator keeps the degraded settings
+    /// for later images, and depth maps record the model and size they were
+    /// made with. Off by default: each estimator degrades on its own, so
+    /// the workers of a video or batch can end up on different settings.
+    /// Native builds only.
+    pub degrade_on_failure: bool,
+
+    /// Clipping, contrast, and gamma applied to each normalized depth map
+    pub shaping: DepthShaping,
+
+    /// Directory to cache depth maps in, keyed by image content and depth
+    /// settings (see [`crate::depth_cache`]); `None` always runs the model.
+    /// Native builds only.
+    pub cache: Option<PathBuf>,
+
+    /// Thread pools, graph optimization, and memory arena of the ONNX
+    /// Runtime session (see [`crate::session`]). Native builds only.
+    pub session: SessionTuning,
 }
 
 impl Default for DepthConfig {
     fn default() -> Self {
         Self {
-            encoder_size: "s".to_string(),
-            target_size: 518,
+            encoder_size: EncoderSize::default(),
+            target_size: TargetSize::default(),
             use_coreml: true,
+            coreml_cache: true,
+            device_id: 0,
+            resize_filter: ResizeFilter::default(),
+            cancel: None,
+            progress: None,
+            tiling: None,
+            degrade_on_failure: false,
+            shaping: DepthShaping::default(),
+            cache: None,
+            session: SessionTuning::default(),
+        }
+    }
+}
+
+/// Inference resolution for the depth model (shorter side, in pixels)
+///
+/// Serializes as a number, or as `"auto"` for [`TargetSize::Auto`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetSize {
+    /// Always resize the shorter side to this many pixels
+    Fixed(u32),
+    /// Pick from [`AUTO_TARGET_SIZES`] by input size and available memory
+    /// (see [`auto_target_size`])
+    Auto,
+}
+
+impl Default for TargetSize {
+    fn default() -> Self {
+        TargetSize::Fixed(518)
+    }
+}
+
+impl From<u32> for TargetSize {
+    fn from(size: u32) -> Self {
+        TargetSize::Fixed(size)
+    }
+}
+
+impl std::fmt::Display for TargetSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetSize::Fixed(size) => write!(f, "{}", size),
+            TargetSize::Auto => f.write_str("auto"),
         }
     }
 }
 
+impl std::str::FromStr for TargetSize {
+    type Err = SpatialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(TargetSize::Auto);
+        }
+        s.parse::<u32>()
+            .ok()
+            .filter(|&size| size > 0)
+            .map(TargetSize::Fixed)
+            .ok_or_else(|| {
+                SpatialError::ConfigError(format!(
+                    "Invalid target size '{}': use a pixel count or \"auto\"",
+                    s
+                ))
+            })
+    }
+}
+
+impl serde::Serialize for TargetSize {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            TargetSize::Fixed(size) => serializer.serialize_u32(*size),
+            TargetSize::Auto => serializer.serialize_str("auto"),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TargetSize {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Size(u32),
+            Name(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Size(size) => Ok(TargetSize::Fixed(size)),
+            Raw::Name(name) => name.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl TargetSize {
+    /// The size to run the model at for a `width` x `height` input
+    pub fn resolve(self, width: u32, height: u32, encoder_size: EncoderSize) -> u32 {
+        match self {
+            TargetSize::Fixed(size) => size,
+            TargetSize::Auto => {
+                let size = auto_target_size(width, height, encoder_size, available_memory());
+                tracing::info!("Auto target size for {}x{} input: {}", width, height, size);
+                size
+            }
+        }
+    }
+}
+
+/// Inference sizes [`TargetSize::Auto`] picks from, smallest first
+///
+/// All are multiples of the model's 14 px patch size.
+pub const AUTO_TARGET_SIZES: [u32; 3] = [518, 770, 1036];
+
+/// Pick an inference size for a `width` x `height` input
+///
+/// Larger inputs get larger sizes (518 below 1000 px on the shorter side,
+/// 770 below 2000 px, 1036 above) so fine structure survives the resize. The
+/// choice then steps down while the estimated peak memory of the model's
+/// attention layers exceeds half of `available_memory` (when known).
+pub fn auto_target_size(
+    width: u32,
+    height: u32,
+    encoder_size: EncoderSize,
+    available_memory: Option<u64>,
+) -> u32 {
+    let short_side = width.min(height);
+    let mut index = match short_side {
+        0..=999 => 0,
+        1000..=1999 => 1,
+        _ => 2,
+    };
+
+    if let Some(available) = available_memory {
+        while index > 0
+            && estimated_inference_bytes(width, height, AUTO_TARGET_SIZES[index], encoder_size)
+                > available / 2
+        {
+            index -= 1;
+        }
+    }
+    AUTO_TARGET_SIZES[index]
+}
+
+/// Rough peak memory of one inference pass: the attention matrices of one
+/// layer (heads x tokens², plus the softmax copy) and the model weights
+fn estimated_inference_bytes(
+    width: u32,
+    height: u32,
+    target_size: u32,
+    encoder_size: EncoderSize,
+) -> u64 {
+    let short_side = width.min(height).max(1) as u64;
+    let long_side = width.max(height) as u64 * target_size as u64 / short_side;
+    let tokens = (target_size as u64 / 14) * (long_side / 14);
+    let (heads, weights) = match encoder_size {
+        EncoderSize::Large => (16, 1_300_000_000),
+        EncoderSize::Base => (12, 400_000_000),
+        EncoderSize::Small => (6, 100_000_000),
+    };
+    heads * tokens * tokens * 4 * 2 + weights
+}
+
+/// Memory currently available to the process, when the platform reports it
+///
+/// Linux reads `MemAvailable` from /proc/meminfo; macOS uses half of the
+/// physical memory, as unified memory is shared with the GPU.
+fn available_memory() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+        let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kib * 1024)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("sysctl")
+            .args(["-n", "hw.memsize"])
+            .output()
+            .ok()?;
+        let total: u64 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .ok()?;
+        Some(total / 2)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        None
+    }
+}
+
 /// ImageNet normalization constants
 /// These are the standard mean and std values used for preprocessing
 const IMAGENET_MEAN: &[f32] = &[0.485, 0.456, 0.406];
 const IMAGENET_STD: &[f32] = &[0.229, 0.224, 0.225];
 
-/// Load and cache an ONNX model session
+/// The process-wide ONNX Runtime environment, created on first use
+///
+/// ONNX Runtime allows one environment per process and sessions hold a
+/// reference to it, so it is kept alive here for the life of the process.
+fn environment() -> SpatialResult<Environment> {
+    static ENVIRONMENT: Mutex<Option<Environment>> = Mutex::new(None);
+
+    let mut current = ENVIRONMENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(environment) = current.as_ref() {
+        return Ok(environment.clone());
+    }
+    let environment = ort::init()
+        .with_name("spatial-maker")
+        .build()
+        .map_err(|e| SpatialError::ort("Failed to create environment", e))?;
+    *current = Some(environment.clone());
+    Ok(environment)
+}
+
+/// Load an ONNX model session
 ///
 /// This loads the model from the checkpoint directory using the `ort` crate.
 /// The model must be present (use `model::ensure_model_exists` to download).
-async fn load_model_session(encoder_size: &str) -> SpatialResult<Session> {
-    let model_path = model::find_model(encoder_size)?;
+/// Building the session parses and optimizes the whole graph, so it runs on
+/// the CPU pool.
+#[cfg(not(target_arch = "wasm32"))]
+async fn load_model_session(config: &DepthConfig) -> SpatialResult<Session> {
+    let config = config.clone();
+    crate::cpu::run(move || build_session(&config)).await
+}
+
+/// [`load_model_session`] on the calling thread
+#[cfg(not(target_arch = "wasm32"))]
+fn build_session(config: &DepthConfig) -> SpatialResult<Session> {
+    let model_path = model::find_model(config.encoder_size)?;
 
     tracing::info!("Loading ONNX model from: {:?}", model_path);
 
-    let session = Session::builder()
-        .map_err(|e| SpatialError::OrtError(format!("Failed to create session builder: {:?}", e)))?
+    let mut builder = config.session.apply(
+        Session::builder(&environment()?)
+            .map_err(|e| SpatialError::ort("Failed to create session builder", e))?,
+    )?;
+    let mut providers = gpu_execution_providers(config.device_id)?;
+    if config.use_coreml && COREML_AVAILABLE {
+        let mut coreml = ort::ep::CoreML::default();
+        if config.coreml_cache {
+            match coreml_cache_dir(config.encoder_size) {
+                Ok(dir) => {
+                    tracing::debug!("CoreML model cache: {:?}", dir);
+                    coreml = coreml.with_model_cache_dir(dir.display());
+                }
+                Err(e) => tracing::warn!("Compiling CoreML model without a cache: {}", e),
+            }
+        }
+        providers.push(coreml.build());
+    }
+    if !providers.is_empty() {
+        // Falls back to the CPU provider if none can be registered
+        builder = builder
+            .with_execution_providers(providers)
+            .map_err(|e| SpatialError::ort("Failed to register execution providers", e))?;
+    }
+    let session = builder
         .commit_from_file(&model_path)
-        .map_err(|e| SpatialError::ModelError(format!("Failed to load model: {:?}", e)))?;
+        .map_err(|e| SpatialError::ort("Failed to load model", e))?;
 
     tracing::info!("Model loaded successfully");
     Ok(session)
 }
 
+/// GPU execution providers compiled in (the `cuda` and `directml`
+/// features), pinned to GPU `device_id`
+#[cfg(not(target_arch = "wasm32"))]
+fn gpu_execution_providers(
+    device_id: u32,
+) -> SpatialResult<Vec<ort::ep::ExecutionProviderDispatch>> {
+    let device_id = i32::try_from(device_id).map_err(|_| {
+        SpatialError::ConfigError(format!("GPU device id {} is out of range", device_id))
+    })?;
+    let providers = vec![
+        #[cfg(feature = "cuda")]
+        ort::ep::CUDA::default().with_device_id(device_id).build(),
+        #[cfg(feature = "directml")]
+        ort::ep::DirectML::default()
+            .with_device_id(device_id)
+            .build(),
+    ];
+    if providers.is_empty() && device_id != 0 {
+        tracing::warn!(
+            "Ignoring GPU device {}: built without the cuda or directml feature",
+            device_id
+        );
+    }
+    Ok(providers)
+}
+
+/// Sessions [`warmup`] loaded, each taken by the next matching
+/// [`DepthEstimator::load`]
+#[cfg(not(target_arch = "wasm32"))]
+static WARM_SESSIONS: Mutex<Vec<(WarmKey, Session)>> = Mutex::new(Vec::new());
+
+/// What a warmed-up session was built with: model, CoreML, GPU device,
+/// and tuning
+#[cfg(not(target_arch = "wasm32"))]
+type WarmKey = (EncoderSize, bool, u32, SessionTuning);
+
+#[cfg(not(target_arch = "wasm32"))]
+fn warm_key(config: &DepthConfig) -> WarmKey {
+    (
+        config.encoder_size,
+        config.use_coreml,
+        config.device_id,
+        config.session,
+    )
+}
+
+/// Take the warmed-up session built with `config`'s settings, if any
+#[cfg(not(target_arch = "wasm32"))]
+fn take_warm_session(config: &DepthConfig) -> Option<Session> {
+    let key = warm_key(config);
+    let mut warm = WARM_SESSIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let index = warm.iter().position(|(warmed, _)| *warmed == key)?;
+    Some(warm.swap_remove(index).1)
+}
+
+/// Load `encoder_size` and run one dummy inference ahead of the first image
+///
+/// The first inference pays for building the session and, on macOS, for
+/// compiling the model for CoreML, which can take several seconds. Apps can
+/// call this behind a splash screen instead: the warmed-up session is kept
+/// and handed to the next [`DepthEstimator::load`] (or pipeline run) with
+/// the same encoder size, execution provider, GPU device, and session
+/// tuning. Downloads the model if needed. Uses the default [`DepthConfig`];
+/// see [`warmup_with`] to match other settings.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn warmup(encoder_size: EncoderSize) -> SpatialResult<()> {
+    warmup_with(DepthConfig {
+        encoder_size,
+        ..Default::default()
+    })
+    .await
+}
+
+/// [`warmup`] with the settings the app will estimate with
+///
+/// The dummy image is square at the configured target size (the smallest
+/// of [`AUTO_TARGET_SIZES`] for [`TargetSize::Auto`]). If loading or the
+/// dummy pass degrades (see [`DepthConfig::degrade_on_failure`]), the
+/// session is kept for the degraded settings.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn warmup_with(config: DepthConfig) -> SpatialResult<()> {
+    let start = std::time::Instant::now();
+    let size = match config.target_size {
+        TargetSize::Fixed(size) => size,
+        TargetSize::Auto => AUTO_TARGET_SIZES[0],
+    };
+    let mut estimator = DepthEstimator::load(DepthConfig {
+        target_size: TargetSize::Fixed(size),
+        cache: None,
+        ..config
+    })
+    .await?;
+    let estimator = crate::cpu::run(move || {
+        estimator.estimate(&DynamicImage::new_rgb8(size, size))?;
+        Ok::<_, SpatialError>(estimator)
+    })
+    .await?;
+
+    let DepthEstimator {
+        session, config, ..
+    } = estimator;
+    tracing::info!(
+        "Warmed up {} model in {:.2?}",
+        config.encoder_size,
+        start.elapsed()
+    );
+    let key = warm_key(&config);
+    let mut warm = WARM_SESSIONS.lock().unwrap_or_else(|e| e.into_inner());
+    warm.retain(|(warmed, _)| *warmed != key);
+    warm.push((key, session));
+    Ok(())
+}
+
+/// Directory CoreML keeps `encoder_size`'s compiled model in, created if
+/// missing (see [`model::get_coreml_cache_dir`])
+#[cfg(not(target_arch = "wasm32"))]
+fn coreml_cache_dir(encoder_size: EncoderSize) -> SpatialResult<PathBuf> {
+    let dir =
+        model::get_coreml_cache_dir()?.join(model::ModelMetadata::for_encoder(encoder_size).name);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Whether CoreML is registered when `use_coreml` is set
+#[cfg(not(target_arch = "wasm32"))]
+const COREML_AVAILABLE: bool = cfg!(target_os = "macos");
+
+/// One step down the degradation ladder (see
+/// [`DepthConfig::degrade_on_failure`])
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Degradation {
+    TargetSize(u32),
+    Encoder(EncoderSize),
+    CpuProvider,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Display for Degradation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Degradation::TargetSize(size) => write!(f, "target size {}", size),
+            Degradation::Encoder(encoder_size) => write!(f, "the {} model", encoder_size),
+            Degradation::CpuProvider => f.write_str("the CPU execution provider"),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Degradation {
+    fn apply(self, config: &mut DepthConfig) {
+        match self {
+            Degradation::TargetSize(size) => config.target_size = TargetSize::Fixed(size),
+            Degradation::Encoder(encoder_size) => config.encoder_size = encoder_size,
+            Degradation::CpuProvider => config.use_coreml = false,
+        }
+    }
+}
+
+/// The steps below `config`, lightest last; target size steps only apply to
+/// a pass run at `target_size`
+#[cfg(not(target_arch = "wasm32"))]
+fn degradation_ladder(config: &DepthConfig, target_size: Option<u32>) -> Vec<Degradation> {
+    let mut ladder: Vec<Degradation> = AUTO_TARGET_SIZES
+        .iter()
+        .rev()
+        .filter(|&&size| target_size.is_some_and(|current| size < current))
+        .map(|&size| Degradation::TargetSize(size))
+        .collect();
+    let smaller_encoders: &[EncoderSize] = match config.encoder_size {
+        EncoderSize::Large => &[EncoderSize::Base, EncoderSize::Small],
+        EncoderSize::Base => &[EncoderSize::Small],
+        EncoderSize::Small => &[],
+    };
+    ladder.extend(smaller_encoders.iter().map(|&e| Degradation::Encoder(e)));
+    if config.use_coreml && COREML_AVAILABLE {
+        ladder.push(Degradation::CpuProvider);
+    }
+    ladder
+}
+
+/// Whether `error` looks like running out of memory or the execution
+/// provider failing, which a lighter setup may avoid
+#[cfg(not(target_arch = "wasm32"))]
+fn is_resource_failure(error: &SpatialError) -> bool {
+    const MARKERS: [&str; 5] = ["memory", "alloc", "coreml", "execution provider", "oom"];
+    match error {
+        SpatialError::Ort { .. } => {
+            let message = error.to_string().to_lowercase();
+            MARKERS.iter().any(|marker| message.contains(marker))
+        }
+        _ => false,
+    }
+}
+
 /// Preprocess an image for depth estimation
 ///
 /// This converts a DynamicImage to an NCHW float32 tensor (batch=1):
-/// 1. Resize to target size (shorter side), maintaining aspect ratio
+/// 1. Resize to target size (shorter side) with `filter`, maintaining aspect
+///    ratio
 /// 2. Convert to RGB
 /// 3. Normalize using ImageNet mean/std
 /// 4. Convert to NCHW format for ONNX model
@@ -69,28 +578,35 @@ async fn load_model_session(encoder_size: &str) -> SpatialResult<Session> {
 /// # Returns
 ///
 /// A tuple of (input_tensor: Vec<f32>, actual_height: u32, actual_width: u32)
-fn preprocess_image(image: &DynamicImage, target_size: u32) -> (Vec<f32>, u32, u32) {
-    // Compute resize dimensions maintaining aspect ratio
+fn preprocess_image(
+    image: &DynamicImage,
+    target_size: u32,
+    filter: ResizeFilter,
+) -> (Vec<f32>, u32, u32) {
+    // Compute resize dimensions maintaining aspect ratio; rounding (not
+    // truncating) keeps the depth map's aspect closest to the image's, so
+    // scaling it back up does not stretch it
     let (orig_width, orig_height) = (image.width(), image.height());
     let (new_width, new_height) = if orig_width < orig_height {
-        let h = (orig_height as f32 * target_size as f32 / orig_width as f32) as u32;
+        let h = (orig_height as f32 * target_size as f32 / orig_width as f32).round() as u32;
         (target_size, h)
     } else {
-        let w = (orig_width as f32 * target_size as f32 / orig_height as f32) as u32;
+        let w = (orig_width as f32 * target_size as f32 / orig_height as f32).round() as u32;
         (w, target_size)
     };
 
     tracing::debug!(
-        "Preprocessing: Original {}x{}, resizing to {}x{} (target_size={})",
+        "Preprocessing: Original {}x{}, resizing to {}x{} (target_size={}, filter={})",
         orig_width,
         orig_height,
         new_width,
         new_height,
-        target_size
+        target_size,
+        filter.name()
     );
 
     // Resize image
-    let resized = image.resize_exact(new_width, new_height, image::imageops::FilterType::Lanczos3);
+    let resized = image.resize_exact(new_width, new_height, filter.filter_type());
 
     // Convert to RGB
     let rgb_image = resized.to_rgb8();
@@ -133,10 +649,22 @@ fn preprocess_image(image: &DynamicImage, target_size: u32) -> (Vec<f32>, u32, u
     (nchw, new_height, new_width)
 }
 
+/// Wrap a preprocessed tensor as the model input, shape (1, 3, height, width)
+fn input_value(
+    input_tensor: Vec<f32>,
+    height: u32,
+    width: u32,
+) -> SpatialResult<ort::value::Tensor<f32>> {
+    let shape = vec![1, 3, height as i64, width as i64];
+    ort::value::Tensor::from_array((shape, input_tensor))
+        .map_err(|e| SpatialError::TensorError(format!("Failed to create input tensor: {:?}", e)))
+}
+
 /// Run inference on a preprocessed tensor
 ///
 /// Returns a tuple of (depth_data, actual_height, actual_width)
 /// The actual dimensions are extracted from the model output shape
+#[cfg(not(target_arch = "wasm32"))]
 fn run_inference(
     session: &mut Session,
     input_tensor: Vec<f32>,
@@ -144,14 +672,7 @@ fn run_inference(
     width: u32,
 ) -> SpatialResult<(Vec<f32>, u32, u32)> {
     tracing::debug!("Running inference on {}x{} image", width, height);
-
-    // Create input shape: (batch=1, channels=3, height, width)
-    let shape = vec![1, 3, height as i64, width as i64];
-
-    // Convert to ort Value
-    let input_value = ort::value::Value::from_array((shape, input_tensor)).map_err(|e| {
-        SpatialError::TensorError(format!("Failed to create input tensor: {:?}", e))
-    })?;
+    let input_value = input_value(input_tensor, height, width)?;
 
     // Run inference
     // The model expects input name "pixel_values" (discovered in Chunk 0 spike)
@@ -159,8 +680,41 @@ fn run_inference(
 
     let outputs = session
         .run(inputs)
-        .map_err(|e| SpatialError::OrtError(format!("Inference failed: {:?}", e)))?;
+        .map_err(|e| SpatialError::ort("Inference failed", e))?;
+
+    read_depth_output(&outputs)
+}
+
+/// [`run_inference`] for ONNX Runtime Web, which only runs sessions
+/// asynchronously and keeps outputs on the JavaScript side until synced
+#[cfg(target_arch = "wasm32")]
+async fn run_inference(
+    session: &mut Session,
+    input_tensor: Vec<f32>,
+    height: u32,
+    width: u32,
+) -> SpatialResult<(Vec<f32>, u32, u32)> {
+    tracing::debug!("Running inference on {}x{} image", width, height);
+    let input_value = input_value(input_tensor, height, width)?;
+    let inputs = vec![("pixel_values", &input_value)];
 
+    let run_options = ort::session::RunOptions::new()
+        .map_err(|e| SpatialError::ort("Failed to create run options", e))?;
+    let mut outputs = session
+        .run_async(inputs, &run_options)
+        .await
+        .map_err(|e| SpatialError::ort("Inference failed", e))?;
+    ort_web::sync_outputs(&mut outputs)
+        .await
+        .map_err(|e| SpatialError::ort("Failed to read outputs", e))?;
+
+    read_depth_output(&outputs)
+}
+
+/// Extract the depth output and its dimensions from the model outputs
+fn read_depth_output(
+    outputs: &ort::session::SessionOutputs<'_>,
+) -> SpatialResult<(Vec<f32>, u32, u32)> {
     tracing::debug!("Inference completed, extracting outputs");
 
     // Extract the depth output
@@ -225,15 +779,34 @@ fn normalize_depth(depth_raw: &[f32]) -> Vec<f32> {
     }
 }
 
+/// Smallest and largest finite value, or (0, 0) when there are none
+pub(crate) fn value_range<'a>(values: impl IntoIterator<Item = &'a f32>) -> (f32, f32) {
+    let (min, max) = values
+        .into_iter()
+        .filter(|v| v.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    if min > max {
+        (0.0, 0.0)
+    } else {
+        (min, max)
+    }
+}
+
 /// Estimate depth from an image
 ///
 /// This is the main entry point for depth estimation:
 /// 1. Ensure the model is downloaded
 /// 2. Load the ONNX model
 /// 3. Preprocess the image
-/// 4. Run inference
+/// 4. Run inference (per tile when `config.tiling` applies)
 /// 5. Normalize and return the depth map
 ///
+/// The model is loaded on every call; use a [`DepthEstimator`] to keep it
+/// loaded across many images. Loading and inference run on the CPU pool
+/// (see [`crate::cpu`]), not the calling task's executor thread.
+///
 /// # Arguments
 ///
 /// * `image` - Input image (any format supported by `image` crate)
@@ -241,38 +814,467 @@ fn normalize_depth(depth_raw: &[f32]) -> Vec<f32> {
 ///
 /// # Returns
 ///
-/// A 2D array representing the normalized depth map (0-1 range)
-/// with shape (height, width)
-pub async fn estimate_depth(
+/// The normalized depth map (0-1 range, higher = closer) with shape
+/// (height, width), along with its raw range, inference size, and model
+pub async fn estimate_depth(image: &DynamicImage, config: &DepthConfig) -> SpatialResult<DepthMap> {
+    let mut estimator = DepthEstimator::load(config.clone()).await?;
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let image = image.clone();
+        crate::cpu::run(move || estimator.estimate(&image)).await
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        estimator.estimate(image).await
+    }
+}
+
+/// A loaded depth model, reused across images
+///
+/// Inference needs exclusive access to the session, so one estimator serves
+/// one thread at a time; see [`crate::pool`] for sharing or replicating
+/// estimators across workers.
+///
+/// In the browser (`wasm32` with the `web` feature) loading and estimating
+/// are both `async`, as ONNX Runtime Web only runs sessions asynchronously,
+/// and depth is always estimated in a single pass (`tiling` is ignored).
+pub struct DepthEstimator {
+    session: Session,
+    config: DepthConfig,
+    /// SHA-256 of the model, once hashed for a depth cache key, with the
+    /// encoder size it was hashed for
+    #[cfg(not(target_arch = "wasm32"))]
+    model_sha256: Option<(EncoderSize, String)>,
+}
+
+impl DepthEstimator {
+    /// Download the model if needed and load it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn load(config: DepthConfig) -> SpatialResult<Self> {
+        cancel::check(config.cancel.as_ref())?;
+
+        // Ensure model is available (download if needed)
+        let progress = config.progress.clone();
+        let _model_path = model::ensure_model_exists_with(
+            config.encoder_size,
+            progress.map(|sink| {
+                move |downloaded: u64, total: u64| {
+                    sink.emit(ProgressEvent::ModelDownload {
+                        pct: downloaded as f32 / total.max(1) as f32 * 100.0,
+                    })
+                }
+            }),
+            config.cancel.as_ref(),
+        )
+        .await?;
+
+        cancel::check(config.cancel.as_ref())?;
+        if let Some(session) = take_warm_session(&config) {
+            tracing::info!("Using warmed-up {} model", config.encoder_size);
+            return Ok(Self {
+                session,
+                config,
+                model_sha256: None,
+            });
+        }
+        let mut config = config;
+        let session = match load_model_session(&config).await {
+            Err(error) if config.degrade_on_failure && is_resource_failure(&error) => {
+                let mut degraded = None;
+                for step in degradation_ladder(&config, None) {
+                    tracing::warn!(
+                        "Loading the depth model failed ({}), trying {}",
+                        error,
+                        step
+                    );
+                    let mut next = config.clone();
+                    step.apply(&mut next);
+                    match load_model_session(&next).await {
+                        Ok(session) => {
+                            config = next;
+                            degraded = Some(session);
+                            break;
+                        }
+                        Err(e) => tracing::warn!("Could not use {}: {}", step, e),
+                    }
+                }
+                degraded.ok_or(error)?
+            }
+            result => result?,
+        };
+        Ok(Self {
+            session,
+            config,
+            model_sha256: None,
+        })
+    }
+
+    /// Fetch the model from HuggingFace and load it
+    ///
+    /// There is no checkpoint directory in the browser; the model is fetched
+    /// on every load and cached only as far as the browser's HTTP cache goes.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn load(config: DepthConfig) -> SpatialResult<Self> {
+        let url = model::ModelMetadata::for_encoder(config.encoder_size).url;
+        Self::from_url(config, &url).await
+    }
+
+    /// Fetch an ONNX model from `url` and load it
+    #[cfg(target_arch = "wasm32")]
+    pub async fn from_url(config: DepthConfig, url: &str) -> SpatialResult<Self> {
+        cancel::check(config.cancel.as_ref())?;
+        crate::web::init(crate::web::FEATURE_NONE).await?;
+
+        tracing::info!("Loading ONNX model from: {}", url);
+        let session = Session::builder(&environment()?)
+            .map_err(|e| SpatialError::ort("Failed to create session builder", e))?
+            .commit_from_url(url)
+            .await
+            .map_err(|e| SpatialError::ort("Failed to load model", e))?;
+        Ok(Self { session, config })
+    }
+
+    /// Load an ONNX model the caller already has in memory
+    ///
+    /// `config.encoder_size` is only used to name the model in the depth
+    /// maps; the model itself can be any Depth Anything V2 export.
+    pub async fn from_model_bytes(config: DepthConfig, model: &[u8]) -> SpatialResult<Self> {
+        cancel::check(config.cancel.as_ref())?;
+        #[cfg(target_arch = "wasm32")]
+        crate::web::init(crate::web::FEATURE_NONE).await?;
+
+        tracing::info!("Loading ONNX model from memory ({} bytes)", model.len());
+        let mut builder = Session::builder(&environment()?)
+            .map_err(|e| SpatialError::ort("Failed to create session builder", e))?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let session = builder.commit_from_memory(model);
+        #[cfg(target_arch = "wasm32")]
+        let session = builder.commit_from_memory(model).await;
+        let session = session.map_err(|e| SpatialError::ort("Failed to load model", e))?;
+        // The bytes need not match the model file for `encoder_size`
+        #[cfg(not(target_arch = "wasm32"))]
+        let model_sha256 = config
+            .cache
+            .is_some()
+            .then(|| (config.encoder_size, depth_cache::sha256_hex(model)));
+        Ok(Self {
+            session,
+            config,
+            #[cfg(not(target_arch = "wasm32"))]
+            model_sha256,
+        })
+    }
+
+    /// Settings this estimator was loaded with
+    pub fn config(&self) -> &DepthConfig {
+        &self.config
+    }
+
+    /// Estimate the depth of one image with the loaded model
+    ///
+    /// Failures the degradation ladder may avoid are retried lower down it
+    /// (see [`DepthConfig::degrade_on_failure`]).
+    ///
+    /// With [`DepthConfig::cache`] set, a cached map for the same image and
+    /// settings is returned without running the model, and new maps are
+    /// cached.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn estimate(&mut self, image: &DynamicImage) -> SpatialResult<DepthMap> {
+        let cache = self.config.cache.clone().map(DepthCache::new);
+        if let Some(cache) = &cache {
+            if let Some(key) = self.cache_key(image) {
+                if let Some(depth_map) = cache.get(&key) {
+                    tracing::info!("Using cached depth {}", key);
+                    return Ok(self.finish(depth_map));
+                }
+            }
+        }
+
+        let depth_map = loop {
+            match self.estimate_once(image) {
+                Err(error) if self.config.degrade_on_failure && is_resource_failure(&error) => {
+                    let target_size = self.target_size(image);
+                    self.degrade(target_size, error)?;
+                }
+                result => break result?,
+            }
+        };
+
+        // Keyed by the settings it was made with, which degrading may have
+        // changed
+        if let Some(cache) = &cache {
+            if let Some(key) = self.cache_key(image) {
+                if let Err(e) = cache.put(&key, &depth_map) {
+                    tracing::warn!("Could not cache depth in {:?}: {}", cache.dir(), e);
+                }
+            }
+        }
+        Ok(self.finish(depth_map))
+    }
+
+    /// Cache key for the depth of `image` under the current settings, or
+    /// `None` (with a warning) if the model cannot be hashed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cache_key(&mut self, image: &DynamicImage) -> Option<String> {
+        let model_sha256 = match self.model_sha256() {
+            Ok(sha256) => sha256,
+            Err(e) => {
+                tracing::warn!(
+                    "Not using the depth cache, as the model could not be hashed: {}",
+                    e
+                );
+                return None;
+            }
+        };
+        let config = &self.config;
+        let key = depth_cache::cache_key(
+            image,
+            &CacheKeySettings {
+                model_sha256: &model_sha256,
+                target_size: self.target_size(image),
+                resize_filter: config.resize_filter,
+                tiling: config
+                    .tiling
+                    .as_ref()
+                    .filter(|tiling| tiling.applies_to(image.width(), image.height())),
+            },
+        );
+        Some(key)
+    }
+
+    /// SHA-256 of the current model, hashed once per encoder size
+    #[cfg(not(target_arch = "wasm32"))]
+    fn model_sha256(&mut self) -> SpatialResult<String> {
+        let encoder_size = self.config.encoder_size;
+        match &self.model_sha256 {
+            Some((hashed, sha256)) if *hashed == encoder_size => Ok(sha256.clone()),
+            _ => {
+                let sha256 = model_lock::installed_sha256(encoder_size)?;
+                self.model_sha256 = Some((encoder_size, sha256.clone()));
+                Ok(sha256)
+            }
+        }
+    }
+
+    /// One attempt at [`estimate`](Self::estimate), before shaping
+    #[cfg(not(target_arch = "wasm32"))]
+    fn estimate_once(&mut self, image: &DynamicImage) -> SpatialResult<DepthMap> {
+        let config = &self.config;
+        tracing::info!("Estimating depth with encoder: {}", config.encoder_size);
+        let cancel = config.cancel.as_ref();
+        cancel::check(cancel)?;
+
+        let depth_map = match &config.tiling {
+            Some(tiling) if tiling.applies_to(image.width(), image.height()) => {
+                estimate_depth_tiled(&mut self.session, image, config, tiling)?
+            }
+            _ => {
+                let target_size = self.target_size(image);
+                infer_depth(
+                    &mut self.session,
+                    image,
+                    target_size,
+                    config.resize_filter,
+                    cancel,
+                )?
+            }
+        };
+        Ok(depth_map)
+    }
+
+    /// Estimate the depth of one image with the loaded model, in one pass
+    #[cfg(target_arch = "wasm32")]
+    pub async fn estimate(&mut self, image: &DynamicImage) -> SpatialResult<DepthMap> {
+        let config = &self.config;
+        tracing::info!("Estimating depth with encoder: {}", config.encoder_size);
+        let cancel = config.cancel.as_ref();
+        cancel::check(cancel)?;
+
+        if let Some(tiling) = &config.tiling {
+            if tiling.applies_to(image.width(), image.height()) {
+                tracing::warn!("Tiled depth is not available in the browser, using one pass");
+            }
+        }
+        let target_size = self.target_size(image);
+        let depth_map = infer_depth(
+            &mut self.session,
+            image,
+            target_size,
+            config.resize_filter,
+            cancel,
+        )
+        .await?;
+        Ok(self.finish(depth_map))
+    }
+
+    /// [`estimate`](Self::estimate) in a single pass (tiling is skipped),
+    /// timing each step
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn estimate_timed(
+        &mut self,
+        image: &DynamicImage,
+    ) -> SpatialResult<(DepthMap, PassTimings)> {
+        let target_size = self.target_size(image);
+        let (filter, cancel) = (self.config.resize_filter, self.config.cancel.as_ref());
+        let (depth_map, timings) =
+            infer_depth_timed(&mut self.session, image, target_size, filter, cancel)?;
+        Ok((self.finish(depth_map), timings))
+    }
+
+    /// Take the first step down the degradation ladder that can be applied
+    /// after a pass at `target_size` failed with `error`
+    ///
+    /// Smaller models are only used if already downloaded, as this runs
+    /// off the async executor.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn degrade(&mut self, target_size: u32, error: SpatialError) -> SpatialResult<()> {
+        for step in degradation_ladder(&self.config, Some(target_size)) {
+            tracing::warn!(
+                "Depth estimation failed ({}), retrying with {}",
+                error,
+                step
+            );
+            let mut next = self.config.clone();
+            step.apply(&mut next);
+            if let Degradation::TargetSize(_) = step {
+                self.config = next;
+                return Ok(());
+            }
+            match build_session(&next) {
+                Ok(session) => {
+                    self.session = session;
+                    self.config = next;
+                    return Ok(());
+                }
+                Err(e) => tracing::warn!("Could not use {}: {}", step, e),
+            }
+        }
+        Err(error)
+    }
+
+    /// Model input size for a whole-image pass over `image`
+    fn target_size(&self, image: &DynamicImage) -> u32 {
+        let config = &self.config;
+        config
+            .target_size
+            .resolve(image.width(), image.height(), config.encoder_size)
+    }
+
+    fn finish(&self, depth_map: DepthMap) -> DepthMap {
+        let mut depth_map = depth_map.shaped(&self.config.shaping);
+        depth_map.model = Some(model::ModelMetadata::for_encoder(self.config.encoder_size).name);
+
+        tracing::info!(
+            "Depth estimation complete: {}x{}",
+            depth_map.nrows(),
+            depth_map.ncols()
+        );
+        depth_map
+    }
+}
+
+/// Run one model pass over `image` and return the normalized depth map
+#[cfg(not(target_arch = "wasm32"))]
+fn infer_depth(
+    session: &mut Session,
     image: &DynamicImage,
-    config: &DepthConfig,
-) -> SpatialResult<ndarray::Array2<f32>> {
-    tracing::info!("Estimating depth with encoder: {}", config.encoder_size);
+    target_size: u32,
+    filter: ResizeFilter,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<DepthMap> {
+    infer_depth_timed(session, image, target_size, filter, cancel).map(|(depth, _)| depth)
+}
 
-    // Ensure model is available (download if needed)
-    let _model_path =
-        model::ensure_model_exists::<fn(u64, u64)>(&config.encoder_size, None).await?;
+/// Time spent in each step of one model pass
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PassTimings {
+    /// Resizing and normalizing the image into the input tensor
+    pub preprocess: std::time::Duration,
+    /// The model run, including normalizing its output
+    pub inference: std::time::Duration,
+}
 
-    // Load model
-    let mut session = load_model_session(&config.encoder_size).await?;
+/// [`infer_depth`], timing preprocessing and inference
+#[cfg(not(target_arch = "wasm32"))]
+fn infer_depth_timed(
+    session: &mut Session,
+    image: &DynamicImage,
+    target_size: u32,
+    filter: ResizeFilter,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<(DepthMap, PassTimings)> {
+    let started = std::time::Instant::now();
 
     // Preprocess image
     tracing::debug!("Preprocessing image");
-    let (input_tensor, prep_height, prep_width) = preprocess_image(image, config.target_size);
+    let (input_tensor, prep_height, prep_width) = preprocess_image(image, target_size, filter);
+    let preprocessed = std::time::Instant::now();
+
+    cancel::check(cancel)?;
 
     // Run inference
     let (depth_raw, actual_height, actual_width) =
-        run_inference(&mut session, input_tensor, prep_height, prep_width)?;
+        run_inference(session, input_tensor, prep_height, prep_width)?;
+
+    cancel::check(cancel)?;
+    let depth = depth_map_from_output(
+        depth_raw,
+        (actual_height, actual_width),
+        (prep_height, prep_width),
+    )?;
+    let timings = PassTimings {
+        preprocess: preprocessed - started,
+        inference: preprocessed.elapsed(),
+    };
+    Ok((depth, timings))
+}
+
+/// [`infer_depth`] with asynchronous inference, for ONNX Runtime Web
+#[cfg(target_arch = "wasm32")]
+async fn infer_depth(
+    session: &mut Session,
+    image: &DynamicImage,
+    target_size: u32,
+    filter: ResizeFilter,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<DepthMap> {
+    tracing::debug!("Preprocessing image");
+    let (input_tensor, prep_height, prep_width) = preprocess_image(image, target_size, filter);
+
+    cancel::check(cancel)?;
+
+    let (depth_raw, actual_height, actual_width) =
+        run_inference(session, input_tensor, prep_height, prep_width).await?;
+
+    cancel::check(cancel)?;
+    depth_map_from_output(
+        depth_raw,
+        (actual_height, actual_width),
+        (prep_height, prep_width),
+    )
+}
 
+/// Normalize raw model output into a depth map
+///
+/// `output_size` is the (height, width) the model returned, which can differ
+/// from the preprocessed `input_size`; the output size is the one used.
+fn depth_map_from_output(
+    depth_raw: Vec<f32>,
+    output_size: (u32, u32),
+    input_size: (u32, u32),
+) -> SpatialResult<DepthMap> {
+    let (actual_height, actual_width) = output_size;
     tracing::debug!(
         "Model output actual dimensions: {}x{} (expected {}x{})",
         actual_height,
         actual_width,
-        prep_height,
-        prep_width
+        input_size.0,
+        input_size.1
     );
 
     // Normalize depth
+    let raw_range = value_range(&depth_raw);
     let depth_normalized = normalize_depth(&depth_raw);
 
     // Convert to ndarray (height, width)
@@ -290,7 +1292,7 @@ pub async fn estimate_depth(
         actual_elements
     );
 
-    let depth_2d = ndarray::Array1::from_vec(depth_normalized)
+    let data = ndarray::Array1::from_vec(depth_normalized)
         .into_shape((h, w))
         .map_err(|e| {
             SpatialError::TensorError(format!(
@@ -299,13 +1301,74 @@ pub async fn estimate_depth(
             ))
         })?;
 
+    Ok(DepthMap {
+        data,
+        raw_range,
+        inference_size: (actual_width, actual_height),
+        model: None,
+    })
+}
+
+/// Estimate depth tile by tile, aligned to and blended over a global pass
+#[cfg(not(target_arch = "wasm32"))]
+fn estimate_depth_tiled(
+    session: &mut Session,
+    image: &DynamicImage,
+    config: &DepthConfig,
+    tiling: &TilingConfig,
+) -> SpatialResult<DepthMap> {
+    let cancel = config.cancel.as_ref();
+    let (width, height) = (image.width(), image.height());
+    let tiles = tiling::plan_tiles(width, height, tiling);
     tracing::info!(
-        "Depth estimation complete: {}x{}",
-        actual_height,
-        actual_width
+        "Tiled depth: {}x{} image in {} tiles of {} px",
+        width,
+        height,
+        tiles.len(),
+        tiling.tile_size
     );
 
-    Ok(depth_2d)
+    // Auto sizing goes by the tile, which every pass (global included) is
+    // resized from
+    let target_size =
+        config
+            .target_size
+            .resolve(tiling.tile_size, tiling.tile_size, config.encoder_size);
+    let global = infer_depth(session, image, target_size, config.resize_filter, cancel)?;
+    let scale = target_size as f32 / tiling.tile_size.max(1) as f32;
+    let mut blender = TileBlender::new(&global, width, height, scale, tiling.overlap);
+
+    for (index, tile) in tiles.iter().enumerate() {
+        cancel::check(cancel)?;
+        tracing::debug!(
+            "Tile {}/{} at ({}, {})",
+            index + 1,
+            tiles.len(),
+            tile.x,
+            tile.y
+        );
+        let crop = image.crop_imm(tile.x, tile.y, tile.width, tile.height);
+        let depth = infer_depth(session, &crop, target_size, config.resize_filter, cancel)?;
+        blender.add(tile, &depth);
+    }
+
+    // Tiles are aligned to the global pass, so the blend is in its units
+    let blended = blender.finish();
+    let (low, high) = value_range(&blended);
+    Ok(DepthMap {
+        data: normalize_depth_map(blended),
+        raw_range: (global.raw_value(low), global.raw_value(high)),
+        inference_size: global.inference_size,
+        model: None,
+    })
+}
+
+/// [`normalize_depth`] for a 2D map
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn normalize_depth_map(depth: ndarray::Array2<f32>) -> ndarray::Array2<f32> {
+    let dim = depth.dim();
+    let normalized = normalize_depth(&depth.into_raw_vec());
+    ndarray::Array2::from_shape_vec(dim, normalized).expect("normalization keeps the length")
 }
 
 #[cfg(test)]
@@ -350,8 +1413,119 @@ mod tests {
     #[test]
     fn test_depth_config_defaults() {
         let config = DepthConfig::default();
-        assert_eq!(config.encoder_size, "s");
-        assert_eq!(config.target_size, 518);
+        assert_eq!(config.encoder_size, EncoderSize::Small);
+        assert_eq!(config.target_size, TargetSize::Fixed(518));
         assert!(config.use_coreml);
+        assert!(config.tiling.is_none());
+        assert!(!config.degrade_on_failure);
+    }
+
+    #[test]
+    fn test_degradation_ladder_order() {
+        let config = DepthConfig {
+            encoder_size: EncoderSize::Large,
+            ..Default::default()
+        };
+        let mut expected = vec![
+            Degradation::TargetSize(770),
+            Degradation::TargetSize(518),
+            Degradation::Encoder(EncoderSize::Base),
+            Degradation::Encoder(EncoderSize::Small),
+        ];
+        if COREML_AVAILABLE {
+            expected.push(Degradation::CpuProvider);
+        }
+        assert_eq!(degradation_ladder(&config, Some(1036)), expected);
+
+        // At the smallest size with the smallest model on CPU, nothing is left
+        let config = DepthConfig {
+            use_coreml: false,
+            ..Default::default()
+        };
+        assert!(degradation_ladder(&config, Some(518)).is_empty());
+        assert!(degradation_ladder(&config, None).is_empty());
+
+        let mut config = DepthConfig::default();
+        Degradation::TargetSize(770).apply(&mut config);
+        Degradation::Encoder(EncoderSize::Base).apply(&mut config);
+        Degradation::CpuProvider.apply(&mut config);
+        assert_eq!(config.target_size, TargetSize::Fixed(770));
+        assert_eq!(config.encoder_size, EncoderSize::Base);
+        assert!(!config.use_coreml);
+    }
+
+    #[test]
+    fn test_is_resource_failure() {
+        let oom = SpatialError::ort(
+            "Inference failed",
+            ort::Error::new("Failed to allocate memory for requested buffer"),
+        );
+        assert!(is_resource_failure(&oom));
+        let provider = SpatialError::ort("Inference failed", ort::Error::new("CoreML error"));
+        assert!(is_resource_failure(&provider));
+
+        let shape = SpatialError::TensorError("Unexpected output shape".to_string());
+        assert!(!is_resource_failure(&shape));
+        assert!(!is_resource_failure(&SpatialError::Cancelled));
+        let missing = SpatialError::ModelNotFound("small".to_string());
+        assert!(!is_resource_failure(&missing));
+    }
+
+    #[test]
+    fn test_auto_target_size() {
+        assert_eq!(auto_target_size(640, 480, EncoderSize::Small, None), 518);
+        assert_eq!(auto_target_size(1920, 1080, EncoderSize::Small, None), 770);
+        assert_eq!(auto_target_size(8000, 6000, EncoderSize::Small, None), 1036);
+        // Not enough memory for the large size steps down
+        assert_eq!(
+            auto_target_size(8000, 6000, EncoderSize::Large, Some(8_000_000_000)),
+            770
+        );
+        assert_eq!(
+            auto_target_size(8000, 6000, EncoderSize::Large, Some(1)),
+            518
+        );
+    }
+
+    #[test]
+    fn test_target_size_parse_and_serde() {
+        assert_eq!("auto".parse::<TargetSize>().unwrap(), TargetSize::Auto);
+        assert_eq!("770".parse::<TargetSize>().unwrap(), TargetSize::Fixed(770));
+        assert!("0".parse::<TargetSize>().is_err());
+        assert!("big".parse::<TargetSize>().is_err());
+
+        assert_eq!(
+            serde_json::to_string(&TargetSize::Fixed(518)).unwrap(),
+            "518"
+        );
+        assert_eq!(
+            serde_json::to_string(&TargetSize::Auto).unwrap(),
+            "\"auto\""
+        );
+        let parsed: TargetSize = serde_json::from_str("\"auto\"").unwrap();
+        assert_eq!(parsed, TargetSize::Auto);
+        assert!(serde_json::from_str::<TargetSize>("\"huge\"").is_err());
+    }
+
+    #[test]
+    fn test_normalize_depth_map_keeps_shape() {
+        let depth = ndarray::Array2::from_shape_fn((2, 3), |(y, x)| (y * 3 + x) as f32 * 2.0);
+        let normalized = normalize_depth_map(depth);
+        assert_eq!(normalized.dim(), (2, 3));
+        assert_eq!(normalized[[0, 0]], 0.0);
+        assert_eq!(normalized[[1, 2]], 1.0);
+    }
+
+    #[test]
+    fn test_depth_map_from_output_uses_model_size() {
+        // The model may return a different size than it was given
+        let raw: Vec<f32> = (0..6).map(|v| v as f32 + 1.0).collect();
+        let depth = depth_map_from_output(raw.clone(), (2, 3), (4, 6)).unwrap();
+        assert_eq!(depth.dim(), (2, 3));
+        assert_eq!(depth.inference_size, (3, 2));
+        assert_eq!(depth.raw_range, (1.0, 6.0));
+        assert_eq!(depth[[1, 2]], 1.0);
+
+        assert!(depth_map_from_output(raw, (4, 4), (4, 4)).is_err());
     }
 }
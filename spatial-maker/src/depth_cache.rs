@@ -0,0 +1,258 @@
+//! On-disk cache of estimated depth
+//!
+//! Depth inference is by far the slowest step of a photo, yet it depends only
+//! on the image and the depth settings. With [`DepthConfig::cache`] set, each
+//! depth map is stored under a SHA-256 of the decoded pixels, the model's
+//! checksum, the inference size, the resize filter, and the tiling layout, so
+//! re-running with different disparity, hole filling, layout, or encoding
+//! settings skips inference, while a replaced model file misses. Maps are
+//! cached before [`DepthShaping`] is applied, so shaping can change too.
+//!
+//! Entries are plain files and never expire; delete the directory to clear
+//! the cache. Video frames are never cached, as each is seen once.
+//!
+//! [`DepthConfig::cache`]: crate::DepthConfig::cache
+//! [`DepthShaping`]: crate::DepthShaping
+
+use crate::depth_map::DepthMap;
+use crate::error::{SpatialError, SpatialResult};
+use crate::resample::ResizeFilter;
+use crate::tiling::TilingConfig;
+use image::DynamicImage;
+use ndarray::Array2;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Identifies the entry layout; bumped when it changes so old entries miss
+const MAGIC: &[u8; 8] = b"SMDEPTH1";
+
+/// Header: magic, map width and height, inference width and height, raw range
+const HEADER_LEN: usize = 8 + 4 * 4 + 2 * 4;
+
+/// Default cache directory
+///
+/// Respects the `SPATIAL_MAKER_DEPTH_CACHE` environment variable if set,
+/// otherwise `~/.spatial-maker/depth-cache/`.
+pub fn default_cache_dir() -> SpatialResult<PathBuf> {
+    if let Ok(custom_dir) = std::env::var("SPATIAL_MAKER_DEPTH_CACHE") {
+        Ok(PathBuf::from(custom_dir))
+    } else {
+        let home = dirs::home_dir().ok_or_else(|| {
+            SpatialError::ConfigError("Could not determine home directory".to_string())
+        })?;
+        Ok(home.join(".spatial-maker").join("depth-cache"))
+    }
+}
+
+/// Everything besides the pixels that changes the depth a model produces
+#[derive(Clone, Copy, Debug)]
+pub struct CacheKeySettings<'a> {
+    /// Hex SHA-256 of the model file
+    pub model_sha256: &'a str,
+    /// Resolved model input size (shorter side)
+    pub target_size: u32,
+    pub resize_filter: ResizeFilter,
+    /// Tiling, when it applies to the image
+    pub tiling: Option<&'a TilingConfig>,
+}
+
+/// Hex SHA-256 naming the cache entry for `image` under `settings`
+pub fn cache_key(image: &DynamicImage, settings: &CacheKeySettings) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(MAGIC);
+    hasher.update(settings.model_sha256.as_bytes());
+    hasher.update(settings.target_size.to_le_bytes());
+    hasher.update(settings.resize_filter.name().as_bytes());
+    match settings.tiling {
+        Some(tiling) => {
+            hasher.update([1]);
+            hasher.update(tiling.tile_size.to_le_bytes());
+            hasher.update(tiling.overlap.to_le_bytes());
+        }
+        None => hasher.update([0]),
+    }
+    hasher.update(image.width().to_le_bytes());
+    hasher.update(image.height().to_le_bytes());
+    hasher.update(format!("{:?}", image.color()).as_bytes());
+    hasher.update(image.as_bytes());
+    hex(&hasher.finalize())
+}
+
+/// Hex SHA-256 of `bytes`
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A directory of cached depth maps
+#[derive(Clone, Debug)]
+pub struct DepthCache {
+    dir: PathBuf,
+}
+
+impl DepthCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.depth", key))
+    }
+
+    /// The cached map for `key`, if there is a readable one
+    ///
+    /// Unreadable or corrupt entries are logged and treated as misses.
+    pub fn get(&self, key: &str) -> Option<DepthMap> {
+        let path = self.entry_path(key);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                tracing::warn!("Could not read cached depth {:?}: {}", path, e);
+                return None;
+            }
+        };
+        let depth = decode(&bytes);
+        if depth.is_none() {
+            tracing::warn!("Ignoring corrupt cached depth {:?}", path);
+        }
+        depth
+    }
+
+    /// Store `depth` under `key`, replacing the file atomically
+    pub fn put(&self, key: &str, depth: &DepthMap) -> SpatialResult<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(key);
+        let temp = path.with_extension("depth.tmp");
+        std::fs::write(&temp, encode(depth))?;
+        std::fs::rename(&temp, &path)?;
+        Ok(())
+    }
+}
+
+fn encode(depth: &DepthMap) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + depth.len() * 4);
+    bytes.extend_from_slice(MAGIC);
+    for value in [
+        depth.width(),
+        depth.height(),
+        depth.inference_size.0,
+        depth.inference_size.1,
+    ] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&depth.raw_range.0.to_le_bytes());
+    bytes.extend_from_slice(&depth.raw_range.1.to_le_bytes());
+    for value in depth.iter() {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Option<DepthMap> {
+    let (header, data) = bytes.split_at_checked(HEADER_LEN)?;
+    if &header[..8] != MAGIC {
+        return None;
+    }
+    let word = |i: usize| -> [u8; 4] { header[8 + i * 4..12 + i * 4].try_into().unwrap() };
+    let (width, height) = (u32::from_le_bytes(word(0)), u32::from_le_bytes(word(1)));
+    let inference_size = (u32::from_le_bytes(word(2)), u32::from_le_bytes(word(3)));
+    let raw_range = (f32::from_le_bytes(word(4)), f32::from_le_bytes(word(5)));
+
+    if data.len() != width as usize * height as usize * 4 {
+        return None;
+    }
+    let values = data
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    let data = Array2::from_shape_vec((height as usize, width as usize), values).ok()?;
+    Some(DepthMap {
+        data,
+        raw_range,
+        inference_size,
+        model: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> CacheKeySettings<'static> {
+        CacheKeySettings {
+            model_sha256: "4c1a",
+            target_size: 518,
+            resize_filter: ResizeFilter::default(),
+            tiling: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = DepthCache::new(temp_dir.path().join("cache"));
+        let depth = DepthMap {
+            data: Array2::from_shape_fn((3, 5), |(y, x)| (y * 5 + x) as f32 / 15.0),
+            raw_range: (0.5, 7.25),
+            inference_size: (518, 311),
+            model: None,
+        };
+
+        assert_eq!(cache.get("abc"), None);
+        cache.put("abc", &depth).unwrap();
+        assert_eq!(cache.get("abc"), Some(depth));
+    }
+
+    #[test]
+    fn test_corrupt_entry_is_a_miss() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = DepthCache::new(temp_dir.path());
+        let depth = DepthMap::from_normalized(Array2::zeros((4, 4)));
+        cache.put("abc", &depth).unwrap();
+
+        let path = cache.entry_path("abc");
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(cache.get("abc"), None);
+    }
+
+    #[test]
+    fn test_key_covers_pixels_and_settings() {
+        let image = DynamicImage::new_rgb8(8, 6);
+        let key = cache_key(&image, &settings());
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, cache_key(&image.clone(), &settings()));
+
+        let mut changed = image.to_rgb8();
+        changed.put_pixel(7, 5, image::Rgb([0, 0, 1]));
+        assert_ne!(
+            key,
+            cache_key(&DynamicImage::ImageRgb8(changed), &settings())
+        );
+
+        let larger = CacheKeySettings {
+            target_size: 770,
+            ..settings()
+        };
+        assert_ne!(key, cache_key(&image, &larger));
+        let other_model = CacheKeySettings {
+            model_sha256: "9e07",
+            ..settings()
+        };
+        assert_ne!(key, cache_key(&image, &other_model));
+        let tiling = TilingConfig::default();
+        let tiled = CacheKeySettings {
+            tiling: Some(&tiling),
+            ..settings()
+        };
+        assert_ne!(key, cache_key(&image, &tiled));
+    }
+}
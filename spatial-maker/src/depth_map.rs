@@ -0,0 +1,393 @@
+//! Depth maps with their provenance
+//!
+//! [`estimate_depth`](crate::estimate_depth) returns a [`DepthMap`]: the
+//! normalized depth values together with what they were normalized from and
+//! which model at which resolution produced them. Values are always 0-1 with
+//! higher = closer, so downstream code never has to guess the convention.
+//!
+//! A `DepthMap` dereferences to its `Array2<f32>`, so it can be passed
+//! wherever a plain depth array is expected (stereo, bokeh, quilts, wiggles).
+//!
+//! [`DepthMap::shaped`] re-spreads an existing map with a [`DepthShaping`]
+//! (percentile clipping, contrast, gamma), so how strongly the foreground
+//! pops can be tuned without estimating depth again.
+//!
+//! ```no_run
+//! use spatial_maker::{estimate_depth, DepthConfig};
+//!
+//! # async fn example(image: image::DynamicImage) -> anyhow::Result<()> {
+//! let depth = estimate_depth(&image, &DepthConfig::default()).await?;
+//! println!("{:?} at {:?}", depth.model, depth.inference_size);
+//! let full = depth.resize_to(image.width(), image.height());
+//! full.to_image16().save("depth.png")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::tiling::resize_depth;
+use image::{GrayImage, ImageBuffer, Luma};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+
+/// A normalized depth map and its metadata
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthMap {
+    /// Depth values (0-1, higher = closer), shape (height, width)
+    pub data: Array2<f32>,
+
+    /// Raw model output (relative inverse depth) that normalized 0 and 1
+    /// correspond to
+    pub raw_range: (f32, f32),
+
+    /// Width and height of the model output; tiled maps are larger than this
+    pub inference_size: (u32, u32),
+
+    /// Model that produced the map (e.g. "depth-anything-v2-small"), or
+    /// `None` for maps built from a plain array
+    pub model: Option<String>,
+}
+
+impl DepthMap {
+    /// Wrap an already normalized array with no provenance
+    pub fn from_normalized(data: Array2<f32>) -> Self {
+        let (height, width) = data.dim();
+        Self {
+            data,
+            raw_range: (0.0, 1.0),
+            inference_size: (width as u32, height as u32),
+            model: None,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.data.ncols() as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.data.nrows() as u32
+    }
+
+    /// The raw model value a normalized value came from
+    pub fn raw_value(&self, normalized: f32) -> f32 {
+        let (low, high) = self.raw_range;
+        low + normalized * (high - low)
+    }
+
+    /// Resize to `width` x `height` with bilinear interpolation
+    pub fn resize_to(&self, width: u32, height: u32) -> DepthMap {
+        DepthMap {
+            data: resize_depth(&self.data, height as usize, width as usize),
+            raw_range: self.raw_range,
+            inference_size: self.inference_size,
+            model: self.model.clone(),
+        }
+    }
+
+    /// Flip near and far (higher = farther), for tools that expect distance
+    ///
+    /// The raw range is swapped along with the values, so
+    /// [`raw_value`](Self::raw_value) still returns the model's output.
+    pub fn invert(&self) -> DepthMap {
+        DepthMap {
+            data: self.data.mapv(|v| 1.0 - v),
+            raw_range: (self.raw_range.1, self.raw_range.0),
+            inference_size: self.inference_size,
+            model: self.model.clone(),
+        }
+    }
+
+    /// Re-spread the values with `shaping`
+    ///
+    /// Clipping and contrast are linear, so the raw range follows them;
+    /// after a gamma other than 1, [`raw_value`](Self::raw_value) is exact
+    /// only at 0 and 1.
+    pub fn shaped(&self, shaping: &DepthShaping) -> DepthMap {
+        if shaping.is_identity() {
+            return self.clone();
+        }
+
+        // Normalized values that clipping maps to 0 and 1
+        let (mut low, mut high) = (0.0, 1.0);
+        if let Some((low_pct, high_pct)) = shaping.clip_percentiles {
+            let (p_low, p_high) = percentiles(&self.data, low_pct, high_pct);
+            if p_high - p_low > 1e-6 {
+                (low, high) = (p_low, p_high);
+            }
+        }
+        // Contrast scales around mid-depth, moving where 0 and 1 land
+        let span = (high - low) / shaping.contrast;
+        let mid = (low + high) / 2.0;
+        let (low, high) = (mid - span / 2.0, mid + span / 2.0);
+
+        let gamma = shaping.gamma;
+        let data = self.data.mapv(|v| {
+            let t = ((v - low) / (high - low)).clamp(0.0, 1.0);
+            if gamma == 1.0 {
+                t
+            } else {
+                t.powf(gamma)
+            }
+        });
+        DepthMap {
+            data,
+            raw_range: (self.raw_value(low), self.raw_value(high)),
+            inference_size: self.inference_size,
+            model: self.model.clone(),
+        }
+    }
+
+    /// 8-bit grayscale image, brighter = closer
+    pub fn to_image(&self) -> GrayImage {
+        GrayImage::from_fn(self.width(), self.height(), |x, y| {
+            Luma([(sample(&self.data, x, y) * 255.0).round() as u8])
+        })
+    }
+
+    /// 16-bit grayscale image, brighter = closer
+    pub fn to_image16(&self) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        ImageBuffer::from_fn(self.width(), self.height(), |x, y| {
+            Luma([(sample(&self.data, x, y) * 65535.0).round() as u16])
+        })
+    }
+
+    /// The plain array, dropping the metadata
+    pub fn into_array(self) -> Array2<f32> {
+        self.data
+    }
+}
+
+/// Adjustments to how normalized depth is spread over 0-1
+///
+/// Applied in order: percentile clipping, contrast, then gamma. The
+/// defaults leave depth unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DepthShaping {
+    /// Stretch the depth between these percentiles (0-100) over the full
+    /// range, clamping the rest, so a few very near or far pixels do not
+    /// flatten everything else
+    pub clip_percentiles: Option<(f32, f32)>,
+
+    /// Contrast around mid-depth: above 1 pushes near and far apart
+    /// (clamping the extremes), below 1 flattens the scene
+    pub contrast: f32,
+
+    /// Exponent applied last: below 1 brings mid-depths toward the viewer
+    /// so the foreground pops more, above 1 pushes them back
+    pub gamma: f32,
+}
+
+impl Default for DepthShaping {
+    fn default() -> Self {
+        Self {
+            clip_percentiles: None,
+            contrast: 1.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl DepthShaping {
+    /// Whether these settings leave depth unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check the settings are in range
+    pub fn validate(&self) -> SpatialResult<()> {
+        if !(self.gamma.is_finite() && self.gamma > 0.0) {
+            return Err(SpatialError::ConfigError(format!(
+                "Depth gamma must be positive, got {}",
+                self.gamma
+            )));
+        }
+        if !(self.contrast.is_finite() && self.contrast > 0.0) {
+            return Err(SpatialError::ConfigError(format!(
+                "Depth contrast must be positive, got {}",
+                self.contrast
+            )));
+        }
+        if let Some((low, high)) = self.clip_percentiles {
+            if !(0.0..=100.0).contains(&low) || !(0.0..=100.0).contains(&high) || low >= high {
+                return Err(SpatialError::ConfigError(format!(
+                    "Depth clip percentiles must satisfy 0 <= low < high <= 100, got ({}, {})",
+                    low, high
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Values at the `low` and `high` percentiles (0-100) of the finite values
+fn percentiles(data: &Array2<f32>, low: f32, high: f32) -> (f32, f32) {
+    let mut values: Vec<f32> = data.iter().copied().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return (0.0, 1.0);
+    }
+    values.sort_unstable_by(f32::total_cmp);
+    let at = |pct: f32| {
+        let index = (pct / 100.0 * (values.len() - 1) as f32).round() as usize;
+        values[index.min(values.len() - 1)]
+    };
+    (at(low), at(high))
+}
+
+/// Clamped value at (`x`, `y`); non-finite values read as 0
+fn sample(data: &Array2<f32>, x: u32, y: u32) -> f32 {
+    let value = data[[y as usize, x as usize]];
+    if value.is_finite() {
+        value.clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+impl Deref for DepthMap {
+    type Target = Array2<f32>;
+
+    fn deref(&self) -> &Array2<f32> {
+        &self.data
+    }
+}
+
+impl From<Array2<f32>> for DepthMap {
+    fn from(data: Array2<f32>) -> Self {
+        DepthMap::from_normalized(data)
+    }
+}
+
+impl From<DepthMap> for Array2<f32> {
+    fn from(depth: DepthMap) -> Self {
+        depth.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient() -> DepthMap {
+        DepthMap {
+            data: Array2::from_shape_fn((2, 3), |(_, x)| x as f32 / 2.0),
+            raw_range: (1.5, 7.5),
+            inference_size: (3, 2),
+            model: Some("depth-anything-v2-small".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_raw_value_survives_invert() {
+        let depth = gradient();
+        assert_eq!(depth.raw_value(0.5), 4.5);
+
+        let inverted = depth.invert();
+        assert_eq!(inverted.data[[0, 0]], 1.0);
+        assert_eq!(inverted.raw_value(inverted.data[[0, 0]]), 1.5);
+        assert_eq!(inverted.raw_value(inverted.data[[0, 2]]), 7.5);
+        assert_eq!(inverted.model, depth.model);
+    }
+
+    #[test]
+    fn test_resize_keeps_metadata() {
+        let resized = gradient().resize_to(6, 4);
+        assert_eq!((resized.width(), resized.height()), (6, 4));
+        assert_eq!(resized.inference_size, (3, 2));
+        assert_eq!(resized.raw_range, (1.5, 7.5));
+    }
+
+    #[test]
+    fn test_to_image() {
+        let mut depth = gradient();
+        depth.data[[1, 0]] = f32::NAN;
+        let image = depth.to_image();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(1, 0)[0], 128);
+        assert_eq!(image.get_pixel(0, 1)[0], 0);
+        assert_eq!(depth.to_image16().get_pixel(2, 0)[0], 65535);
+    }
+
+    #[test]
+    fn test_shaping_identity() {
+        let depth = gradient();
+        assert!(DepthShaping::default().is_identity());
+        assert_eq!(depth.shaped(&DepthShaping::default()), depth);
+    }
+
+    #[test]
+    fn test_shaping_gamma_and_contrast() {
+        let depth = gradient();
+        let lifted = depth.shaped(&DepthShaping {
+            gamma: 0.5,
+            ..Default::default()
+        });
+        assert!((lifted.data[[0, 1]] - 0.5f32.sqrt()).abs() < 1e-6);
+        assert_eq!(lifted.data[[0, 2]], 1.0);
+        assert_eq!(lifted.raw_range, depth.raw_range);
+
+        let punchy = depth.shaped(&DepthShaping {
+            contrast: 2.0,
+            ..Default::default()
+        });
+        assert_eq!(punchy.data[[0, 1]], 0.5);
+        assert_eq!(punchy.data[[0, 2]], 1.0);
+        assert_eq!(punchy.raw_range, (3.0, 6.0));
+
+        let flat = depth.shaped(&DepthShaping {
+            contrast: 0.5,
+            ..Default::default()
+        });
+        assert_eq!(flat.data[[0, 0]], 0.25);
+        assert_eq!(flat.data[[0, 2]], 0.75);
+    }
+
+    #[test]
+    fn test_shaping_clip_percentiles() {
+        let mut depth = DepthMap::from(Array2::from_shape_fn((1, 101), |(_, x)| x as f32 / 100.0));
+        depth.data[[0, 100]] = f32::NAN;
+        let clipped = depth.shaped(&DepthShaping {
+            clip_percentiles: Some((10.0, 90.0)),
+            ..Default::default()
+        });
+        assert_eq!(clipped.data[[0, 0]], 0.0);
+        assert_eq!(clipped.data[[0, 95]], 1.0);
+        assert!((clipped.data[[0, 50]] - 0.5).abs() < 0.01);
+        assert!(clipped.data[[0, 100]].is_nan());
+    }
+
+    #[test]
+    fn test_shaping_validation() {
+        assert!(DepthShaping::default().validate().is_ok());
+        for bad in [
+            DepthShaping {
+                gamma: 0.0,
+                ..Default::default()
+            },
+            DepthShaping {
+                contrast: f32::NAN,
+                ..Default::default()
+            },
+            DepthShaping {
+                clip_percentiles: Some((90.0, 10.0)),
+                ..Default::default()
+            },
+            DepthShaping {
+                clip_percentiles: Some((0.0, 101.0)),
+                ..Default::default()
+            },
+        ] {
+            assert!(bad.validate().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_deref_to_array() {
+        let depth = DepthMap::from(Array2::from_elem((4, 5), 0.25));
+        let total: f32 = depth.iter().sum();
+        assert_eq!(total, 5.0);
+        assert_eq!(depth.dim(), (4, 5));
+        assert_eq!(depth.inference_size, (5, 4));
+    }
+}
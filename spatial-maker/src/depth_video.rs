@@ -0,0 +1,326 @@
+//! Depth-map video export
+//!
+//! Writes the per-frame depth maps of a video job as a grayscale video with
+//! the same size, frame rate, and frame count as the stereo output, for
+//! compositing tools and for checking temporal stability (flicker shows up
+//! as brightness pumping between frames).
+//!
+//! Frames are piped into ffmpeg as raw grayscale:
+//! - [`DepthVideoEncoding::Gray8`] is H.264 (8-bit, plays anywhere)
+//! - [`DepthVideoEncoding::Gray16`] is lossless FFV1 at 16 bits in Matroska,
+//!   for compositing
+//!
+//! Brighter is closer, matching the normalized depth maps (0-1).
+//!
+//! ```no_run
+//! use spatial_maker::depth_video::{depth_video_path, DepthVideoEncoding, DepthVideoWriter};
+//! use std::path::Path;
+//!
+//! # fn example(depth_maps: Vec<ndarray::Array2<f32>>) -> spatial_maker::SpatialResult<()> {
+//! let encoding = DepthVideoEncoding::Gray16;
+//! let path = depth_video_path(Path::new("output_sbs.mp4"), encoding);
+//! let mut writer = DepthVideoWriter::create(Path::new("ffmpeg"), &path, 1920, 1080, 29.97, encoding)?;
+//! for depth in &depth_maps {
+//!     writer.write_frame(depth)?;
+//! }
+//! writer.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::tiling::resize_depth;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+/// Sample format and codec of a depth video
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepthVideoEncoding {
+    /// 8-bit grayscale, H.264 in MP4
+    #[default]
+    Gray8,
+    /// 16-bit grayscale, lossless FFV1 in Matroska
+    Gray16,
+}
+
+impl DepthVideoEncoding {
+    /// File extension of the container this encoding is written to
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DepthVideoEncoding::Gray8 => "mp4",
+            DepthVideoEncoding::Gray16 => "mkv",
+        }
+    }
+
+    fn bytes_per_sample(&self) -> usize {
+        match self {
+            DepthVideoEncoding::Gray8 => 1,
+            DepthVideoEncoding::Gray16 => 2,
+        }
+    }
+}
+
+/// Depth video path next to a stereo output: `<stem>_depth.<ext>`
+pub fn depth_video_path(output: &Path, encoding: DepthVideoEncoding) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_string());
+    output.with_file_name(format!("{}_depth.{}", stem, encoding.extension()))
+}
+
+/// Quantize a depth map to raw grayscale samples at `width` x `height`
+///
+/// 16-bit samples are little-endian, matching ffmpeg's `gray16le`.
+pub(crate) fn depth_frame_bytes(
+    depth: &Array2<f32>,
+    width: u32,
+    height: u32,
+    encoding: DepthVideoEncoding,
+) -> Vec<u8> {
+    let resized = resize_depth(depth, height as usize, width as usize);
+    let mut bytes = Vec::with_capacity(resized.len() * encoding.bytes_per_sample());
+    for &value in resized.iter() {
+        let value = if value.is_finite() {
+            value.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        match encoding {
+            DepthVideoEncoding::Gray8 => bytes.push((value * 255.0).round() as u8),
+            DepthVideoEncoding::Gray16 => {
+                bytes.extend_from_slice(&((value * 65535.0).round() as u16).to_le_bytes())
+            }
+        }
+    }
+    bytes
+}
+
+/// Streams depth maps into a grayscale video with ffmpeg
+pub struct DepthVideoWriter {
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    encoding: DepthVideoEncoding,
+    frames_written: u64,
+}
+
+impl DepthVideoWriter {
+    /// Start ffmpeg writing a `width` x `height` depth video at `fps`
+    ///
+    /// Use the stereo output's per-eye size and frame rate so the two stay
+    /// frame-for-frame in sync.
+    pub fn create(
+        ffmpeg: &Path,
+        path: &Path,
+        width: u32,
+        height: u32,
+        fps: f64,
+        encoding: DepthVideoEncoding,
+    ) -> SpatialResult<Self> {
+        if width == 0 || height == 0 || !fps.is_finite() || fps <= 0.0 {
+            return Err(SpatialError::ConfigError(format!(
+                "Invalid depth video size {}x{} at {} fps",
+                width, height, fps
+            )));
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+        }
+
+        let mut cmd = Command::new(ffmpeg);
+        cmd.args(["-hide_banner", "-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt"])
+            .arg(match encoding {
+                DepthVideoEncoding::Gray8 => "gray",
+                DepthVideoEncoding::Gray16 => "gray16le",
+            })
+            .arg("-s")
+            .arg(format!("{}x{}", width, height))
+            .arg("-framerate")
+            .arg(format!("{:.3}", fps))
+            .args(["-i", "-"]);
+        match encoding {
+            // yuv420p needs even dimensions; pad rather than scale so depth
+            // pixels stay aligned with the stereo frame
+            DepthVideoEncoding::Gray8 => cmd.args([
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                "-c:v",
+                "libx264",
+                "-crf",
+                "12",
+                "-pix_fmt",
+                "yuv420p",
+                "-movflags",
+                "+faststart",
+            ]),
+            DepthVideoEncoding::Gray16 => cmd.args(["-c:v", "ffv1", "-level", "3"]),
+        };
+        cmd.arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        tracing::debug!("Running: {:?}", cmd);
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffmpeg), e))?;
+        let stdin = child.stdin.take();
+
+        tracing::info!(
+            "🎞️ Writing {}x{} depth video ({:?}) to {:?}",
+            width,
+            height,
+            encoding,
+            path
+        );
+        Ok(Self {
+            child: Some(child),
+            stdin,
+            path: path.to_path_buf(),
+            width,
+            height,
+            encoding,
+            frames_written: 0,
+        })
+    }
+
+    /// Append the depth map of the next video frame
+    ///
+    /// Maps of any resolution are resized to the video size.
+    pub fn write_frame(&mut self, depth: &Array2<f32>) -> SpatialResult<()> {
+        if depth.is_empty() {
+            return Err(SpatialError::ConfigError("Depth map is empty".to_string()));
+        }
+        let bytes = depth_frame_bytes(depth, self.width, self.height, self.encoding);
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err(SpatialError::ConfigError(
+                "Depth video already finished".to_string(),
+            ));
+        };
+        if stdin.write_all(&bytes).is_err() {
+            // ffmpeg exited early; its stderr explains why
+            return Err(self.fail());
+        }
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    /// Frames written so far
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Close the stream and wait for ffmpeg, returning the video path
+    pub fn finish(mut self) -> SpatialResult<PathBuf> {
+        drop(self.stdin.take());
+        let Some(mut child) = self.child.take() else {
+            return Ok(self.path.clone());
+        };
+        let status = child
+            .wait()
+            .map_err(|e| SpatialError::io("Failed to wait for ffmpeg", e))?;
+        if !status.success() {
+            return Err(ffmpeg_error(&mut child));
+        }
+        tracing::info!(
+            "✅ Depth video saved to {:?} ({} frames)",
+            self.path,
+            self.frames_written
+        );
+        Ok(self.path.clone())
+    }
+
+    /// Stop ffmpeg after a failed write and collect its error
+    fn fail(&mut self) -> SpatialError {
+        drop(self.stdin.take());
+        match self.child.take() {
+            Some(mut child) => {
+                let _ = child.wait();
+                ffmpeg_error(&mut child)
+            }
+            None => SpatialError::ConfigError("Depth video already finished".to_string()),
+        }
+    }
+}
+
+impl Drop for DepthVideoWriter {
+    /// An unfinished writer (error or cancellation) kills ffmpeg and removes
+    /// the partial file
+    fn drop(&mut self) {
+        drop(self.stdin.take());
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn ffmpeg_error(child: &mut Child) -> SpatialError {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    SpatialError::io(
+        "Depth video encoding with ffmpeg failed",
+        std::io::Error::other(stderr.trim().to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth_video_path() {
+        assert_eq!(
+            depth_video_path(Path::new("/out/clip_sbs.mp4"), DepthVideoEncoding::Gray16),
+            PathBuf::from("/out/clip_sbs_depth.mkv")
+        );
+        assert_eq!(
+            depth_video_path(Path::new("clip.mov"), DepthVideoEncoding::Gray8),
+            PathBuf::from("clip_depth.mp4")
+        );
+    }
+
+    #[test]
+    fn test_depth_frame_bytes() {
+        let depth = Array2::from_shape_vec((1, 3), vec![0.0, 1.0, f32::NAN]).unwrap();
+        assert_eq!(
+            depth_frame_bytes(&depth, 3, 1, DepthVideoEncoding::Gray8),
+            vec![0, 255, 0]
+        );
+        assert_eq!(
+            depth_frame_bytes(&depth, 3, 1, DepthVideoEncoding::Gray16),
+            vec![0, 0, 255, 255, 0, 0]
+        );
+
+        // Maps are resized to the video size
+        let small = Array2::from_elem((2, 2), 0.5);
+        let bytes = depth_frame_bytes(&small, 8, 6, DepthVideoEncoding::Gray8);
+        assert_eq!(bytes.len(), 48);
+        assert!(bytes.iter().all(|&b| b == 128));
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_size() {
+        let result = DepthVideoWriter::create(
+            Path::new("ffmpeg"),
+            Path::new("depth.mp4"),
+            0,
+            1080,
+            30.0,
+            DepthVideoEncoding::Gray8,
+        );
+        assert!(matches!(result, Err(SpatialError::ConfigError(_))));
+    }
+}
@@ -1,84 +1,217 @@
 //! Error types for spatial-maker operations
-
-use std::fmt;
+//!
+//! Errors from I/O, external tools, image codecs, ONNX Runtime, and model
+//! downloads keep the underlying error as their
+//! [`source`](std::error::Error::source), next to a message saying what was
+//! being attempted, so callers can downcast to it:
+//!
+//! ```
+//! use spatial_maker::SpatialError;
+//! use std::error::Error;
+//!
+//! let error = SpatialError::from(std::io::Error::from(std::io::ErrorKind::TimedOut));
+//! let io = error.source().unwrap().downcast_ref::<std::io::Error>().unwrap();
+//! assert_eq!(io.kind(), std::io::ErrorKind::TimedOut);
+//! assert!(error.is_retryable());
+//! ```
 
 /// Result type for spatial-maker operations
 pub type SpatialResult<T> = Result<T, SpatialError>;
 
+/// An error kept as the source of a [`SpatialError`]
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Comprehensive error type for spatial-maker operations
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum SpatialError {
-    /// Model-related errors (loading, inference, metadata)
-    ModelError(String),
+    /// A model file is not on disk yet
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
 
-    /// Image processing errors (loading, resizing, format)
-    ImageError(String),
+    /// A model file does not match the checksum recorded for it
+    #[error("Model mismatch: {0}")]
+    ModelMismatch(String),
 
     /// Tensor operation errors (shape mismatch, data conversion)
+    #[error("Tensor error: {0}")]
     TensorError(String),
 
-    /// File I/O errors
-    IoError(String),
-
     /// Configuration or validation errors
+    #[error("Configuration error: {0}")]
     ConfigError(String),
 
-    /// ONNX Runtime errors
-    OrtError(String),
+    /// The operation was cancelled through a `CancellationToken`
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    /// Two photos could not be aligned into a stereo pair
+    #[error("Could not align photos: {0}")]
+    Alignment(String),
+
+    /// Some items of a batch failed; the manifest records why
+    #[error("{failed} of {total} videos failed; see {manifest:?}")]
+    BatchFailed {
+        failed: usize,
+        total: usize,
+        manifest: std::path::PathBuf,
+    },
+
+    /// A worker thread panicked or went away before answering
+    #[error("Worker error: {0}")]
+    Worker(String),
 
-    /// Generic catch-all error
-    Other(String),
+    /// A file, pipe, or external tool operation failed; `context` says which
+    #[error("I/O error: {}", with_context(context, source))]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Decoding or encoding an image failed; `context` says which
+    #[error("Image error: {}", with_context(context, source))]
+    Image {
+        context: String,
+        #[source]
+        source: image::ImageError,
+    },
+
+    /// An ONNX Runtime call failed; `context` says which
+    #[error("ONNX Runtime error: {context}: {source}")]
+    Ort {
+        context: String,
+        #[source]
+        source: ort::Error,
+    },
+
+    /// Fetching a model failed; `context` says which step
+    #[error("Download error: {context}: {source}")]
+    Download {
+        context: String,
+        #[source]
+        source: BoxedError,
+    },
+
+    /// An error from code using `anyhow`
+    #[error(transparent)]
+    Anyhow(#[from] anyhow::Error),
 }
 
-impl fmt::Display for SpatialError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            SpatialError::ModelError(msg) => write!(f, "Model error: {}", msg),
-            SpatialError::ImageError(msg) => write!(f, "Image error: {}", msg),
-            SpatialError::TensorError(msg) => write!(f, "Tensor error: {}", msg),
-            SpatialError::IoError(msg) => write!(f, "I/O error: {}", msg),
-            SpatialError::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
-            SpatialError::OrtError(msg) => write!(f, "ONNX Runtime error: {}", msg),
-            SpatialError::Other(msg) => write!(f, "Error: {}", msg),
-        }
+fn with_context(context: &str, source: &dyn std::fmt::Display) -> String {
+    if context.is_empty() {
+        source.to_string()
+    } else {
+        format!("{context}: {source}")
     }
 }
 
-impl std::error::Error for SpatialError {}
-
-/// Convert from ort errors (handled implicitly in depth.rs)
-/// Note: ort::OrtError doesn't expose a public error type in 2.0.0-rc.11
-/// We handle this by converting to String in the depth module
-
-/// Convert from std::io::Error to SpatialError
 impl From<std::io::Error> for SpatialError {
-    fn from(e: std::io::Error) -> Self {
-        SpatialError::IoError(e.to_string())
+    fn from(source: std::io::Error) -> Self {
+        SpatialError::io("", source)
     }
 }
 
-/// Convert from image::ImageError to SpatialError
 impl From<image::ImageError> for SpatialError {
-    fn from(e: image::ImageError) -> Self {
-        SpatialError::ImageError(e.to_string())
+    fn from(source: image::ImageError) -> Self {
+        SpatialError::image("", source)
     }
 }
 
-/// Convert from anyhow::Error to SpatialError
-impl From<anyhow::Error> for SpatialError {
-    fn from(e: anyhow::Error) -> Self {
-        SpatialError::Other(e.to_string())
+/// Decoding `format` failed, for codecs outside the `image` crate
+pub(crate) fn decoding_error(format: &str, source: impl Into<BoxedError>) -> image::ImageError {
+    image::ImageError::Decoding(image::error::DecodingError::new(
+        image::error::ImageFormatHint::Name(format.to_string()),
+        source,
+    ))
+}
+
+/// `path` does not have the extension of an image format spatial-maker reads
+pub(crate) fn unsupported_format(path: &std::path::Path) -> image::ImageError {
+    use image::error::{ImageFormatHint, UnsupportedError, UnsupportedErrorKind};
+    let hint = match path.extension() {
+        Some(extension) => ImageFormatHint::PathExtension(extension.into()),
+        None => ImageFormatHint::Unknown,
+    };
+    image::ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+        hint.clone(),
+        UnsupportedErrorKind::Format(hint),
+    ))
+}
+
+/// Encoding `format` failed, for codecs outside the `image` crate
+pub(crate) fn encoding_error(format: &str, source: impl Into<BoxedError>) -> image::ImageError {
+    image::ImageError::Encoding(image::error::EncodingError::new(
+        image::error::ImageFormatHint::Name(format.to_string()),
+        source,
+    ))
+}
+
+impl SpatialError {
+    /// An I/O error, with what was being attempted
+    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        SpatialError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// An image error, with what was being attempted
+    pub fn image(context: impl Into<String>, source: image::ImageError) -> Self {
+        SpatialError::Image {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// An ONNX Runtime error, with what was being attempted
+    pub fn ort(context: impl Into<String>, source: impl Into<ort::Error>) -> Self {
+        SpatialError::Ort {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    /// A model download error, with what was being attempted
+    pub fn download(context: impl Into<String>, source: impl Into<BoxedError>) -> Self {
+        SpatialError::Download {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    /// Whether trying the same operation again may succeed
+    ///
+    /// True for interrupted or timed-out I/O and for failed downloads;
+    /// configuration, model, and image errors fail the same way every time.
+    pub fn is_retryable(&self) -> bool {
+        use std::io::ErrorKind;
+        match self {
+            SpatialError::Io { source, .. } => matches!(
+                source.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::TimedOut
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            ),
+            SpatialError::Download { .. } => true,
+            _ => false,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::error::Error;
 
     #[test]
     fn test_error_display() {
-        let err = SpatialError::ModelError("test model error".to_string());
-        assert_eq!(err.to_string(), "Model error: test model error");
+        let err = SpatialError::ModelNotFound("small".to_string());
+        assert_eq!(err.to_string(), "Model not found: small");
     }
 
     #[test]
@@ -88,4 +221,42 @@ mod tests {
         }
         assert_eq!(returns_result().unwrap(), 42);
     }
+
+    #[test]
+    fn test_sources_are_kept() {
+        let err = SpatialError::from(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "missing.png",
+        ));
+        assert_eq!(err.to_string(), "I/O error: missing.png");
+        assert!(err.source().unwrap().is::<std::io::Error>());
+        assert!(!err.is_retryable());
+
+        let err = SpatialError::io(
+            "Failed to read clip.mov",
+            std::io::Error::from(std::io::ErrorKind::TimedOut),
+        );
+        assert!(err
+            .to_string()
+            .starts_with("I/O error: Failed to read clip.mov: "));
+        assert!(err.is_retryable());
+
+        let err = SpatialError::ort("Inference failed", ort::Error::new("out of memory"));
+        assert_eq!(
+            err.to_string(),
+            "ONNX Runtime error: Inference failed: out of memory"
+        );
+        assert!(err.source().unwrap().is::<ort::Error>());
+
+        let err = SpatialError::download(
+            "Failed to download model",
+            std::io::Error::from(std::io::ErrorKind::ConnectionReset),
+        );
+        assert!(err.is_retryable());
+        assert!(err.source().unwrap().is::<std::io::Error>());
+
+        assert!(SpatialError::ConfigError("bad".to_string())
+            .source()
+            .is_none());
+    }
 }
@@ -0,0 +1,886 @@
+//! 3D geometry export
+//!
+//! Turns a photo and its depth map into geometry other tools can open:
+//!
+//! - **Point clouds** ([`save_ply`]): each pixel is back-projected through a
+//!   pinhole camera at the origin looking down -Z (+X right, +Y up), so the
+//!   cloud sits in front of the camera the way the photo was taken. Opens
+//!   in Blender, MeshLab, or CloudCompare.
+//! - **Displaced meshes** ([`save_mesh`]): a flat quad the shape of the
+//!   photo, pushed towards the viewer by depth and textured with the photo,
+//!   written as binary glTF (`.glb`) or USDZ for AR Quick Look on Apple
+//!   devices.
+//!
+//! Depth maps are relative, not metric: point clouds treat normalized depth
+//! as inverse distance and spread it between `near` and `far`, and meshes
+//! scale it to a `relief` depth. Large photos are sampled on a coarser grid
+//! to keep files a manageable size.
+//!
+//! ```no_run
+//! use spatial_maker::export::{save_mesh, save_ply, MeshConfig, PointCloudConfig};
+//! use spatial_maker::{estimate_depth, DepthConfig};
+//!
+//! # async fn example(image: image::DynamicImage) -> anyhow::Result<()> {
+//! let depth = estimate_depth(&image, &DepthConfig::default()).await?;
+//! save_ply(&image, &depth, "photo.ply", &PointCloudConfig::default(), None)?;
+//! save_mesh(&image, &depth, "photo.usdz", &MeshConfig::default(), None)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancel::{self, CancellationToken};
+use crate::error::{SpatialError, SpatialResult};
+use crate::tiling::resize_depth;
+use image::DynamicImage;
+use ndarray::Array2;
+use serde_json::json;
+use std::io::Write;
+use std::path::Path;
+
+/// How PLY vertex data is stored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlyEncoding {
+    /// Little-endian binary (compact, fast to load)
+    #[default]
+    Binary,
+    /// One line of text per point
+    Ascii,
+}
+
+/// Settings for point cloud export
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloudConfig {
+    /// Horizontal field of view of the camera that took the photo, in degrees
+    pub field_of_view: f32,
+
+    /// Distance of the closest depth (normalized 1.0), in scene units
+    pub near: f32,
+
+    /// Distance of the farthest depth (normalized 0.0), in scene units
+    pub far: f32,
+
+    /// The sampling grid is scaled down to at most this many columns
+    pub max_width: u32,
+
+    /// PLY storage
+    pub encoding: PlyEncoding,
+}
+
+impl Default for PointCloudConfig {
+    fn default() -> Self {
+        Self {
+            field_of_view: 60.0,
+            near: 1.0,
+            far: 10.0,
+            max_width: 1024,
+            encoding: PlyEncoding::default(),
+        }
+    }
+}
+
+impl PointCloudConfig {
+    /// Check the settings are in range
+    pub fn validate(&self) -> SpatialResult<()> {
+        if !(self.field_of_view > 0.0 && self.field_of_view < 180.0) {
+            return Err(SpatialError::ConfigError(format!(
+                "Point cloud field of view must be between 0 and 180 degrees, got {}",
+                self.field_of_view
+            )));
+        }
+        if !(self.near > 0.0 && self.far > self.near) {
+            return Err(SpatialError::ConfigError(format!(
+                "Point cloud depth range needs 0 < near < far, got {} to {}",
+                self.near, self.far
+            )));
+        }
+        if self.max_width == 0 {
+            return Err(SpatialError::ConfigError(
+                "Point cloud max_width must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Distance from the camera for a normalized depth (0-1, higher = closer)
+    ///
+    /// Interpolates inverse distance, matching the model's relative inverse
+    /// depth, so nearby surfaces get most of the depth resolution.
+    pub fn distance(&self, depth: f32) -> f32 {
+        let inverse = 1.0 / self.far + depth.clamp(0.0, 1.0) * (1.0 / self.near - 1.0 / self.far);
+        1.0 / inverse
+    }
+}
+
+/// A colored point cloud sampled on a regular grid
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PointCloud {
+    /// Point positions (x, y, z), row by row from the top-left of the photo
+    pub positions: Vec<[f32; 3]>,
+
+    /// Point colors (RGB), one per position
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl PointCloud {
+    /// Number of points
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Whether the cloud has no points
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Write the cloud as a PLY file to `writer`
+    pub fn write_ply<W: Write>(&self, mut writer: W, encoding: PlyEncoding) -> std::io::Result<()> {
+        let format = match encoding {
+            PlyEncoding::Binary => "binary_little_endian",
+            PlyEncoding::Ascii => "ascii",
+        };
+        write!(
+            writer,
+            "ply\n\
+             format {} 1.0\n\
+             comment spatial-maker point cloud\n\
+             element vertex {}\n\
+             property float x\n\
+             property float y\n\
+             property float z\n\
+             property uchar red\n\
+             property uchar green\n\
+             property uchar blue\n\
+             end_header\n",
+            format,
+            self.len()
+        )?;
+
+        for (position, color) in self.positions.iter().zip(&self.colors) {
+            match encoding {
+                PlyEncoding::Binary => {
+                    for value in position {
+                        writer.write_all(&value.to_le_bytes())?;
+                    }
+                    writer.write_all(color)?;
+                }
+                PlyEncoding::Ascii => writeln!(
+                    writer,
+                    "{} {} {} {} {} {}",
+                    position[0], position[1], position[2], color[0], color[1], color[2]
+                )?,
+            }
+        }
+        writer.flush()
+    }
+}
+
+/// Back-project a photo into a colored point cloud
+///
+/// `depth` is a normalized depth map (0-1, higher = closer) at any
+/// resolution; it is resized to the sampling grid, and colors are averaged
+/// down to it.
+pub fn point_cloud(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    config: &PointCloudConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<PointCloud> {
+    config.validate()?;
+    if depth.is_empty() {
+        return Err(SpatialError::ConfigError("Depth map is empty".to_string()));
+    }
+
+    let scale = (config.max_width as f32 / image.width() as f32).min(1.0);
+    let width = ((image.width() as f32 * scale).round() as u32).max(1);
+    let height = ((image.height() as f32 * scale).round() as u32).max(1);
+    let colors = image
+        .resize_exact(width, height, image::imageops::FilterType::Triangle)
+        .to_rgb8();
+    let depth = resize_depth(depth, height as usize, width as usize);
+
+    // Focal length in grid pixels, from the horizontal field of view
+    let focal = width as f32 / 2.0 / (config.field_of_view.to_radians() / 2.0).tan();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+
+    let mut cloud = PointCloud {
+        positions: Vec::with_capacity((width * height) as usize),
+        colors: Vec::with_capacity((width * height) as usize),
+    };
+    for y in 0..height {
+        cancel::check(cancel)?;
+        for x in 0..width {
+            let z = config.distance(depth[[y as usize, x as usize]]);
+            cloud.positions.push([
+                (x as f32 + 0.5 - cx) / focal * z,
+                (cy - y as f32 - 0.5) / focal * z,
+                -z,
+            ]);
+            cloud.colors.push(colors.get_pixel(x, y).0);
+        }
+    }
+    Ok(cloud)
+}
+
+/// Back-project a photo and save the point cloud as a PLY file
+pub fn save_ply(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    output_path: impl AsRef<Path>,
+    config: &PointCloudConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    let output_path = output_path.as_ref();
+    let cloud = point_cloud(image, depth, config, cancel)?;
+
+    tracing::info!(
+        "☁️  Saving {} point cloud to {:?}",
+        cloud.len(),
+        output_path
+    );
+
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(output_path)?;
+    cloud.write_ply(std::io::BufWriter::new(file), config.encoding)?;
+    Ok(())
+}
+
+/// File format for a displaced mesh
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshFormat {
+    /// Binary glTF 2.0 with the texture embedded
+    Glb,
+    /// USDZ package (USDA layer plus texture) for AR Quick Look
+    Usdz,
+}
+
+impl MeshFormat {
+    /// Pick the format from the output file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> SpatialResult<Self> {
+        let path = path.as_ref();
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("glb") => Ok(MeshFormat::Glb),
+            Some("usdz") => Ok(MeshFormat::Usdz),
+            _ => Err(SpatialError::ConfigError(format!(
+                "Unknown mesh format for {:?}: use a .glb or .usdz extension",
+                path
+            ))),
+        }
+    }
+}
+
+/// Settings for displaced mesh export
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshConfig {
+    /// Vertex columns across the photo (rows follow the aspect ratio)
+    pub max_columns: u32,
+
+    /// Width of the quad in meters (AR Quick Look places it at this size)
+    pub width: f32,
+
+    /// How far the nearest depth stands out in front of the farthest, in
+    /// meters
+    pub relief: f32,
+
+    /// Longest side of the embedded texture, in pixels
+    pub texture_size: u32,
+
+    /// JPEG quality of the embedded texture (1-100)
+    pub texture_quality: u8,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        Self {
+            max_columns: 256,
+            width: 1.0,
+            relief: 0.25,
+            texture_size: 2048,
+            texture_quality: 90,
+        }
+    }
+}
+
+impl MeshConfig {
+    /// Check the settings are in range
+    pub fn validate(&self) -> SpatialResult<()> {
+        if self.max_columns < 2 {
+            return Err(SpatialError::ConfigError(
+                "Mesh max_columns must be at least 2".to_string(),
+            ));
+        }
+        if !(self.width > 0.0 && self.relief >= 0.0) {
+            return Err(SpatialError::ConfigError(format!(
+                "Mesh width must be positive and relief non-negative, got {} and {}",
+                self.width, self.relief
+            )));
+        }
+        if self.texture_size == 0 {
+            return Err(SpatialError::ConfigError(
+                "Mesh texture_size must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A triangulated grid displaced by depth
+///
+/// The quad is centred on the origin in the XY plane, +Y up, with the photo
+/// facing +Z; closer depths move towards +Z. UVs have their origin at the
+/// top-left of the photo (the glTF convention).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    /// Vertex positions, row by row from the top-left of the photo
+    pub positions: Vec<[f32; 3]>,
+
+    /// Unit vertex normals
+    pub normals: Vec<[f32; 3]>,
+
+    /// Texture coordinates, one per vertex
+    pub uvs: Vec<[f32; 2]>,
+
+    /// Counter-clockwise triangles, three vertex indices each
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Smallest and largest position along each axis
+    fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for position in &self.positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        (min, max)
+    }
+}
+
+/// Build a depth-displaced grid mesh for a photo of `width` x `height`
+///
+/// `depth` is a normalized depth map (0-1, higher = closer) at any
+/// resolution; it is resized to the vertex grid.
+pub fn displaced_mesh(
+    depth: &Array2<f32>,
+    width: u32,
+    height: u32,
+    config: &MeshConfig,
+) -> SpatialResult<Mesh> {
+    config.validate()?;
+    if depth.is_empty() || width == 0 || height == 0 {
+        return Err(SpatialError::ConfigError("Depth map is empty".to_string()));
+    }
+
+    let columns = config.max_columns.min(width).max(2) as usize;
+    let rows = ((columns as f32 * height as f32 / width as f32).round() as usize).max(2);
+    let grid = resize_depth(depth, rows, columns);
+    let quad_height = config.width * height as f32 / width as f32;
+
+    let mut mesh = Mesh::default();
+    for row in 0..rows {
+        let v = row as f32 / (rows - 1) as f32;
+        for column in 0..columns {
+            let u = column as f32 / (columns - 1) as f32;
+            mesh.positions.push([
+                (u - 0.5) * config.width,
+                (0.5 - v) * quad_height,
+                grid[[row, column]].clamp(0.0, 1.0) * config.relief,
+            ]);
+            mesh.uvs.push([u, v]);
+        }
+    }
+
+    // Normals from central differences of the displaced grid
+    let (step_x, step_y) = (
+        config.width / (columns - 1) as f32,
+        quad_height / (rows - 1) as f32,
+    );
+    let z = |row: usize, column: usize| mesh.positions[row * columns + column][2];
+    for row in 0..rows {
+        for column in 0..columns {
+            let (left, right) = (column.saturating_sub(1), (column + 1).min(columns - 1));
+            let (up, down) = (row.saturating_sub(1), (row + 1).min(rows - 1));
+            let dz_dx = (z(row, right) - z(row, left)) / ((right - left) as f32 * step_x);
+            let dz_dy = (z(up, column) - z(down, column)) / ((down - up) as f32 * step_y);
+            let length = (dz_dx * dz_dx + dz_dy * dz_dy + 1.0).sqrt();
+            mesh.normals
+                .push([-dz_dx / length, -dz_dy / length, 1.0 / length]);
+        }
+    }
+
+    for row in 0..rows - 1 {
+        for column in 0..columns - 1 {
+            let top_left = (row * columns + column) as u32;
+            let top_right = top_left + 1;
+            let bottom_left = top_left + columns as u32;
+            let bottom_right = bottom_left + 1;
+            mesh.indices.extend_from_slice(&[
+                top_left,
+                bottom_left,
+                bottom_right,
+                top_left,
+                bottom_right,
+                top_right,
+            ]);
+        }
+    }
+    Ok(mesh)
+}
+
+/// Build a displaced mesh and save it, textured with the photo, as glTF
+/// binary or USDZ (by file extension)
+pub fn save_mesh(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    output_path: impl AsRef<Path>,
+    config: &MeshConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    let output_path = output_path.as_ref();
+    let format = MeshFormat::from_path(output_path)?;
+    let mesh = displaced_mesh(depth, image.width(), image.height(), config)?;
+
+    cancel::check(cancel)?;
+    let texture = encode_texture(image, config)?;
+
+    tracing::info!(
+        "🧊 Saving {}-triangle mesh to {:?}",
+        mesh.indices.len() / 3,
+        output_path
+    );
+
+    cancel::check(cancel)?;
+    let bytes = match format {
+        MeshFormat::Glb => glb(&mesh, &texture),
+        MeshFormat::Usdz => usdz(&[
+            ("photo.usda", usda(&mesh).as_bytes()),
+            ("photo.jpg", &texture),
+        ]),
+    };
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(output_path, bytes)?;
+    Ok(())
+}
+
+/// Scale the photo to the texture size and encode it as JPEG
+fn encode_texture(image: &DynamicImage, config: &MeshConfig) -> SpatialResult<Vec<u8>> {
+    let longest = image.width().max(image.height());
+    let texture = if longest > config.texture_size {
+        let scale = config.texture_size as f32 / longest as f32;
+        image.resize_exact(
+            ((image.width() as f32 * scale).round() as u32).max(1),
+            ((image.height() as f32 * scale).round() as u32).max(1),
+            image::imageops::FilterType::Lanczos3,
+        )
+    } else {
+        image.clone()
+    };
+
+    let mut bytes = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(
+        &mut bytes,
+        config.texture_quality.clamp(1, 100),
+    )
+    .encode_image(&texture.to_rgb8())?;
+    Ok(bytes)
+}
+
+/// Pack a mesh and its JPEG texture into a binary glTF 2.0 file
+fn glb(mesh: &Mesh, texture: &[u8]) -> Vec<u8> {
+    fn pad(buffer: &mut Vec<u8>, byte: u8) {
+        while !buffer.len().is_multiple_of(4) {
+            buffer.push(byte);
+        }
+    }
+
+    // Buffer views: positions, normals, UVs, indices, texture
+    let mut buffer = Vec::new();
+    let mut views = Vec::new();
+    let mut view = |buffer: &mut Vec<u8>, data: &[u8], target: Option<u32>| {
+        let offset = buffer.len();
+        buffer.extend_from_slice(data);
+        pad(buffer, 0);
+        let mut view = json!({ "buffer": 0, "byteOffset": offset, "byteLength": data.len() });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        views.push(view);
+    };
+    fn floats<const N: usize>(items: &[[f32; N]]) -> Vec<u8> {
+        items
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+    const ARRAY_BUFFER: u32 = 34962;
+    const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+    view(&mut buffer, &floats(&mesh.positions), Some(ARRAY_BUFFER));
+    view(&mut buffer, &floats(&mesh.normals), Some(ARRAY_BUFFER));
+    view(&mut buffer, &floats(&mesh.uvs), Some(ARRAY_BUFFER));
+    view(
+        &mut buffer,
+        &mesh
+            .indices
+            .iter()
+            .flat_map(|i| i.to_le_bytes())
+            .collect::<Vec<u8>>(),
+        Some(ELEMENT_ARRAY_BUFFER),
+    );
+    view(&mut buffer, texture, None);
+
+    const FLOAT: u32 = 5126;
+    const UNSIGNED_INT: u32 = 5125;
+    const LINEAR: u32 = 9729;
+    const CLAMP_TO_EDGE: u32 = 33071;
+    let (min, max) = mesh.bounds();
+    let vertices = mesh.positions.len();
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "spatial-maker" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "name": "Photo", "mesh": 0 }],
+        "meshes": [{
+            "primitives": [{
+                "attributes": { "POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2 },
+                "indices": 3,
+                "material": 0,
+            }],
+        }],
+        "materials": [{
+            "pbrMetallicRoughness": {
+                "baseColorTexture": { "index": 0 },
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            },
+        }],
+        "textures": [{ "source": 0, "sampler": 0 }],
+        "samplers": [{
+            "magFilter": LINEAR,
+            "minFilter": LINEAR,
+            "wrapS": CLAMP_TO_EDGE,
+            "wrapT": CLAMP_TO_EDGE,
+        }],
+        "images": [{ "bufferView": 4, "mimeType": "image/jpeg" }],
+        "accessors": [
+            { "bufferView": 0, "componentType": FLOAT, "count": vertices, "type": "VEC3", "min": min, "max": max },
+            { "bufferView": 1, "componentType": FLOAT, "count": vertices, "type": "VEC3" },
+            { "bufferView": 2, "componentType": FLOAT, "count": vertices, "type": "VEC2" },
+            { "bufferView": 3, "componentType": UNSIGNED_INT, "count": mesh.indices.len(), "type": "SCALAR" },
+        ],
+        "bufferViews": views,
+        "buffers": [{ "byteLength": buffer.len() }],
+    });
+    let mut document = document.to_string().into_bytes();
+    pad(&mut document, b' ');
+
+    let length = 12 + 8 + document.len() + 8 + buffer.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(length as u32).to_le_bytes());
+    for (chunk, kind) in [(&document, b"JSON"), (&buffer, b"BIN\0")] {
+        glb.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        glb.extend_from_slice(kind);
+        glb.extend_from_slice(chunk);
+    }
+    glb
+}
+
+/// Write a mesh as a USDA layer textured with `photo.jpg`
+///
+/// USD texture coordinates start at the bottom-left, so V is flipped.
+fn usda(mesh: &Mesh) -> String {
+    fn list<T>(items: &[T], format: impl Fn(&T) -> String) -> String {
+        items.iter().map(format).collect::<Vec<_>>().join(", ")
+    }
+
+    format!(
+        r#"#usda 1.0
+(
+    defaultPrim = "Photo"
+    metersPerUnit = 1
+    upAxis = "Y"
+)
+
+def Xform "Photo"
+{{
+    def Mesh "Mesh" (
+        prepend apiSchemas = ["MaterialBindingAPI"]
+    )
+    {{
+        int[] faceVertexCounts = [{counts}]
+        int[] faceVertexIndices = [{indices}]
+        rel material:binding = </Photo/Material>
+        normal3f[] normals = [{normals}] (
+            interpolation = "vertex"
+        )
+        point3f[] points = [{points}]
+        texCoord2f[] primvars:st = [{uvs}] (
+            interpolation = "vertex"
+        )
+        uniform token subdivisionScheme = "none"
+    }}
+
+    def Material "Material"
+    {{
+        token outputs:surface.connect = </Photo/Material/Surface.outputs:surface>
+
+        def Shader "Surface"
+        {{
+            uniform token info:id = "UsdPreviewSurface"
+            color3f inputs:diffuseColor.connect = </Photo/Material/Texture.outputs:rgb>
+            float inputs:metallic = 0
+            float inputs:roughness = 1
+            token outputs:surface
+        }}
+
+        def Shader "TexCoords"
+        {{
+            uniform token info:id = "UsdPrimvarReader_float2"
+            token inputs:varname = "st"
+            float2 outputs:result
+        }}
+
+        def Shader "Texture"
+        {{
+            uniform token info:id = "UsdUVTexture"
+            asset inputs:file = @photo.jpg@
+            float2 inputs:st.connect = </Photo/Material/TexCoords.outputs:result>
+            token inputs:wrapS = "clamp"
+            token inputs:wrapT = "clamp"
+            float3 outputs:rgb
+        }}
+    }}
+}}
+"#,
+        counts = vec!["3"; mesh.indices.len() / 3].join(", "),
+        indices = list(&mesh.indices, |i| i.to_string()),
+        normals = list(&mesh.normals, |n| format!("({}, {}, {})", n[0], n[1], n[2])),
+        points = list(&mesh.positions, |p| format!(
+            "({}, {}, {})",
+            p[0], p[1], p[2]
+        )),
+        uvs = list(&mesh.uvs, |uv| format!("({}, {})", uv[0], 1.0 - uv[1])),
+    )
+}
+
+/// Package files as USDZ: an uncompressed zip whose file data starts on
+/// 64-byte boundaries, with the root layer first
+fn usdz(files: &[(&str, &[u8])]) -> Vec<u8> {
+    // Zip epoch (1980-01-01), which readers accept for undated entries
+    const DOS_DATE: u16 = 0x0021;
+    const PADDING_ID: u16 = 0x1986;
+
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let crc = crc32fast::hash(data);
+        let offset = archive.len() as u32;
+
+        // Pad the extra field so the data lands on a 64-byte boundary
+        let header_end = archive.len() + 30 + name.len();
+        let padding = (64 - (header_end + 4) % 64) % 64;
+        let extra_len = 4 + padding;
+
+        let mut common = Vec::new();
+        common.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        common.extend_from_slice(&0u16.to_le_bytes()); // flags
+        common.extend_from_slice(&0u16.to_le_bytes()); // stored
+        common.extend_from_slice(&0u16.to_le_bytes()); // time
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+
+        archive.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(&(extra_len as u16).to_le_bytes());
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&PADDING_ID.to_le_bytes());
+        archive.extend_from_slice(&(padding as u16).to_le_bytes());
+        archive.resize(archive.len() + padding, 0);
+        archive.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        directory.extend_from_slice(&common);
+        directory.extend_from_slice(&0u16.to_le_bytes()); // extra length
+        directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        directory.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // this disk
+    archive.extend_from_slice(&0u16.to_le_bytes()); // directory disk
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn gradient() -> (DynamicImage, Array2<f32>) {
+        let image = RgbImage::from_fn(8, 4, |x, _| Rgb([x as u8 * 30, 0, 0]));
+        let depth = Array2::from_shape_fn((4, 8), |(_, x)| x as f32 / 7.0);
+        (DynamicImage::ImageRgb8(image), depth)
+    }
+
+    #[test]
+    fn test_distance_spans_near_to_far() {
+        let config = PointCloudConfig::default();
+        assert!((config.distance(1.0) - config.near).abs() < 1e-5);
+        assert!((config.distance(0.0) - config.far).abs() < 1e-4);
+        assert!(config.distance(0.5) < (config.near + config.far) / 2.0);
+    }
+
+    #[test]
+    fn test_point_cloud_geometry() {
+        let (image, depth) = gradient();
+        let cloud = point_cloud(&image, &depth, &PointCloudConfig::default(), None).unwrap();
+        assert_eq!(cloud.len(), 32);
+
+        // Everything is in front of the camera, left of centre is -X, and
+        // the right (closer) columns come nearer
+        assert!(cloud.positions.iter().all(|p| p[2] < 0.0));
+        assert!(cloud.positions[0][0] < 0.0 && cloud.positions[0][1] > 0.0);
+        assert!(cloud.positions[7][2] > cloud.positions[0][2]);
+        assert_eq!(cloud.colors[7], [210, 0, 0]);
+    }
+
+    #[test]
+    fn test_max_width_subsamples() {
+        let (image, depth) = gradient();
+        let config = PointCloudConfig {
+            max_width: 4,
+            ..Default::default()
+        };
+        assert_eq!(point_cloud(&image, &depth, &config, None).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn test_write_ply() {
+        let (image, depth) = gradient();
+        let cloud = point_cloud(&image, &depth, &PointCloudConfig::default(), None).unwrap();
+
+        let mut binary = Vec::new();
+        cloud.write_ply(&mut binary, PlyEncoding::Binary).unwrap();
+        let header_end = binary
+            .windows(11)
+            .position(|w| w == b"end_header\n")
+            .unwrap()
+            + 11;
+        let header = std::str::from_utf8(&binary[..header_end]).unwrap();
+        assert!(header.contains("format binary_little_endian 1.0"));
+        assert!(header.contains("element vertex 32"));
+        assert_eq!(binary.len() - header_end, 32 * 15);
+
+        let mut ascii = Vec::new();
+        cloud.write_ply(&mut ascii, PlyEncoding::Ascii).unwrap();
+        let text = String::from_utf8(ascii).unwrap();
+        assert_eq!(text.lines().skip_while(|l| *l != "end_header").count(), 33);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(PointCloudConfig::default().validate().is_ok());
+        let config = PointCloudConfig {
+            near: 5.0,
+            far: 2.0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        assert!(MeshConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_displaced_mesh() {
+        let (_, depth) = gradient();
+        let mesh = displaced_mesh(&depth, 8, 4, &MeshConfig::default()).unwrap();
+        assert_eq!(mesh.positions.len(), 32);
+        assert_eq!(mesh.indices.len(), 7 * 3 * 6);
+
+        // A 2:1 quad one meter wide, with the closer right edge raised
+        let (min, max) = mesh.bounds();
+        assert!((max[0] - min[0] - 1.0).abs() < 1e-5);
+        assert!((max[1] - min[1] - 0.5).abs() < 1e-5);
+        assert!(mesh.positions[7][2] > mesh.positions[0][2]);
+
+        // Triangles face the viewer, and normals lean away from the slope
+        let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[mesh.indices[i] as usize]);
+        let cross_z = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        assert!(cross_z > 0.0);
+        assert!(mesh.normals.iter().all(|n| n[0] < 0.0 && n[2] > 0.0));
+    }
+
+    #[test]
+    fn test_mesh_format_from_path() {
+        assert_eq!(MeshFormat::from_path("a.GLB").unwrap(), MeshFormat::Glb);
+        assert_eq!(MeshFormat::from_path("a.usdz").unwrap(), MeshFormat::Usdz);
+        assert!(MeshFormat::from_path("a.obj").is_err());
+    }
+
+    #[test]
+    fn test_glb_layout() {
+        let (_, depth) = gradient();
+        let mesh = displaced_mesh(&depth, 8, 4, &MeshConfig::default()).unwrap();
+        let glb = glb(&mesh, &[0xff, 0xd8, 0xff]);
+
+        let word = |at: usize| u32::from_le_bytes(glb[at..at + 4].try_into().unwrap()) as usize;
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(word(8), glb.len());
+        let json_len = word(12);
+        assert_eq!(json_len % 4, 0);
+        assert_eq!(&glb[16..20], b"JSON");
+        let document: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        assert_eq!(document["accessors"][0]["count"], 32);
+        assert_eq!(&glb[24 + json_len..28 + json_len], b"BIN\0");
+        assert_eq!(word(20 + json_len), document["buffers"][0]["byteLength"]);
+    }
+
+    #[test]
+    fn test_usdz_aligns_file_data() {
+        let archive = usdz(&[("photo.usda", b"#usda 1.0\n"), ("photo.jpg", &[1, 2, 3])]);
+        let short = |at: usize| u16::from_le_bytes([archive[at], archive[at + 1]]) as usize;
+
+        let mut at = 0;
+        for name in ["photo.usda", "photo.jpg"] {
+            assert_eq!(&archive[at..at + 4], &[0x50, 0x4b, 0x03, 0x04]);
+            let size = short(at + 22);
+            let (name_len, extra_len) = (short(at + 26), short(at + 28));
+            assert_eq!(&archive[at + 30..at + 30 + name_len], name.as_bytes());
+            let data = at + 30 + name_len + extra_len;
+            assert_eq!(data % 64, 0);
+            at = data + size;
+        }
+        assert_eq!(&archive[at..at + 4], &[0x50, 0x4b, 0x01, 0x02]);
+        assert_eq!(
+            &archive[archive.len() - 22..archive.len() - 18],
+            &[0x50, 0x4b, 0x05, 0x06]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! gRPC service for remote spatial photo processing (requires the `grpc` feature)
+//!
+//! The wire schema lives in `proto/spatial_maker.proto`. [`SpatialMakerService`]
+//! implements it on top of [`process_photo_with_progress`], streaming progress
+//! events back to the client followed by a single result message.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use spatial_maker::grpc::{SpatialMakerServer, SpatialMakerService};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! tonic::transport::Server::builder()
+//!     .add_service(SpatialMakerServer::new(SpatialMakerService::default()))
+//!     .serve("0.0.0.0:50051".parse()?)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::metadata::Attribution;
+use crate::output::{
+    ImageEncoding, OutputFormat, OutputOptions, PngCompression, PngFilter, Projection,
+};
+use crate::progress::ProcessingStage;
+use crate::{process_photo_with_progress, SpatialConfig};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status};
+
+/// Generated protobuf types and service stubs
+pub mod proto {
+    tonic::include_proto!("spatial_maker.v1");
+}
+
+pub use proto::spatial_maker_client::SpatialMakerClient;
+pub use proto::spatial_maker_server::SpatialMakerServer;
+
+use proto::process_response::Event;
+use proto::{ProcessRequest, ProcessResponse, ProcessResult, ProgressEvent};
+
+/// gRPC service implementation backed by the local photo pipeline
+#[derive(Clone, Debug, Default)]
+pub struct SpatialMakerService {
+    /// Defaults applied when a request leaves a field unset
+    pub defaults: SpatialConfig,
+}
+
+impl SpatialMakerService {
+    /// Create a service using the given defaults for unset request fields
+    pub fn new(defaults: SpatialConfig) -> Self {
+        Self { defaults }
+    }
+
+    fn config_for(&self, req: &ProcessRequest) -> SpatialResult<SpatialConfig> {
+        let mut config = self.defaults.clone();
+        if !req.encoder_size.is_empty() {
+            config.encoder_size = req.encoder_size.parse()?;
+        }
+        if req.max_disparity > 0 {
+            config.max_disparity = req.max_disparity;
+        }
+        if req.target_depth_size > 0 {
+            config.target_depth_size = req.target_depth_size.into();
+        }
+        if req.disable_coreml {
+            config.use_coreml = false;
+        }
+        Ok(config)
+    }
+}
+
+fn output_options_for(req: &ProcessRequest) -> OutputOptions {
+    let layout = match req.layout() {
+        proto::Layout::SideBySide => OutputFormat::SideBySide,
+        proto::Layout::TopAndBottom => OutputFormat::TopAndBottom,
+        proto::Layout::Separate => OutputFormat::Separate,
+        proto::Layout::CrossEye => OutputFormat::CrossEye,
+        proto::Layout::MirroredSideBySide => OutputFormat::MirroredSideBySide,
+    };
+    let quality = if req.jpeg_quality == 0 {
+        95
+    } else {
+        req.jpeg_quality.clamp(1, 100) as u8
+    };
+    let image_format = match req.image_format() {
+        proto::ImageFormat::Png => ImageEncoding::Png {
+            compression: match req.png_compression() {
+                proto::PngCompression::Fast => PngCompression::Fast,
+                proto::PngCompression::Balanced => PngCompression::Balanced,
+                proto::PngCompression::Best => PngCompression::Best,
+            },
+            filter: PngFilter::Adaptive,
+            sixteen_bit: req.png_16bit,
+        },
+        proto::ImageFormat::Jpeg => ImageEncoding::Jpeg { quality },
+        proto::ImageFormat::Webp => ImageEncoding::WebP {
+            quality,
+            lossless: false,
+        },
+        proto::ImageFormat::WebpLossless => ImageEncoding::WebP {
+            quality,
+            lossless: true,
+        },
+        proto::ImageFormat::Avif => ImageEncoding::Avif {
+            quality,
+            speed: match req.avif_speed {
+                0 => 4,
+                speed => speed.clamp(1, 10) as u8,
+            },
+        },
+        proto::ImageFormat::Jxl => ImageEncoding::Jxl,
+        proto::ImageFormat::Heic => ImageEncoding::Heic { quality },
+    };
+
+    OutputOptions {
+        layout,
+        image_format,
+        half_resolution: req.half_resolution,
+        projection: if req.vr180 {
+            Projection::Vr180
+        } else if req.panorama {
+            Projection::Panorama360
+        } else {
+            Projection::Rectilinear
+        },
+        attribution: (!req.creator.is_empty() || !req.copyright.is_empty()).then(|| Attribution {
+            creator: (!req.creator.is_empty()).then(|| req.creator.clone()),
+            copyright: (!req.copyright.is_empty()).then(|| req.copyright.clone()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+impl From<SpatialError> for Status {
+    fn from(e: SpatialError) -> Self {
+        match e {
+            SpatialError::ConfigError(_) => Status::invalid_argument(e.to_string()),
+            SpatialError::Cancelled => Status::cancelled(e.to_string()),
+            SpatialError::Io { .. } | SpatialError::Image { .. } => {
+                Status::failed_precondition(e.to_string())
+            }
+            _ => Status::internal(e.to_string()),
+        }
+    }
+}
+
+type ProcessStream = Pin<Box<dyn Stream<Item = Result<ProcessResponse, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl proto::spatial_maker_server::SpatialMaker for SpatialMakerService {
+    type ProcessPhotoStream = ProcessStream;
+
+    async fn process_photo(
+        &self,
+        request: Request<ProcessRequest>,
+    ) -> Result<Response<Self::ProcessPhotoStream>, Status> {
+        let req = request.into_inner();
+        if req.input_path.is_empty() || req.output_path.is_empty() {
+            return Err(Status::invalid_argument(
+                "input_path and output_path are required",
+            ));
+        }
+
+        let config = self.config_for(&req)?;
+        let output_options = output_options_for(&req);
+        let input_path = PathBuf::from(&req.input_path);
+        let output_path = PathBuf::from(&req.output_path);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let progress_tx = tx.clone();
+            let result = process_photo_with_progress(
+                &input_path,
+                &output_path,
+                config,
+                output_options,
+                Some(move |stage: ProcessingStage, percent: f32| {
+                    let _ = progress_tx.send(Ok(ProcessResponse {
+                        event: Some(Event::Progress(ProgressEvent {
+                            stage: stage.name().to_string(),
+                            percent,
+                        })),
+                    }));
+                }),
+            )
+            .await;
+
+            let message = match result {
+                Ok(()) => Ok(ProcessResponse {
+                    event: Some(Event::Result(ProcessResult {
+                        output_path: output_path.to_string_lossy().into_owned(),
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                    })),
+                }),
+                Err(e) => Err(Status::from(e)),
+            };
+            let _ = tx.send(message);
+        });
+
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::EncoderSize;
+
+    #[test]
+    fn test_request_defaults() {
+        let service = SpatialMakerService::default();
+        let req = ProcessRequest::default();
+        let config = service.config_for(&req).unwrap();
+        assert_eq!(config.encoder_size, EncoderSize::Small);
+        assert_eq!(config.max_disparity, 30);
+        assert!(config.use_coreml);
+
+        let options = output_options_for(&req);
+        assert_eq!(options.layout, OutputFormat::SideBySide);
+        assert_eq!(options.image_format, ImageEncoding::Jpeg { quality: 95 });
+    }
+
+    #[test]
+    fn test_request_overrides() {
+        let service = SpatialMakerService::default();
+        let req = ProcessRequest {
+            encoder_size: "l".to_string(),
+            max_disparity: 40,
+            disable_coreml: true,
+            layout: proto::Layout::TopAndBottom as i32,
+            image_format: proto::ImageFormat::Png as i32,
+            ..Default::default()
+        };
+        let config = service.config_for(&req).unwrap();
+        assert_eq!(config.encoder_size, EncoderSize::Large);
+        assert_eq!(config.max_disparity, 40);
+        assert!(!config.use_coreml);
+
+        let options = output_options_for(&req);
+        assert_eq!(options.layout, OutputFormat::TopAndBottom);
+        assert_eq!(options.image_format, ImageEncoding::PNG);
+
+        let req = ProcessRequest {
+            image_format: proto::ImageFormat::Webp as i32,
+            jpeg_quality: 80,
+            ..Default::default()
+        };
+        assert_eq!(
+            output_options_for(&req).image_format,
+            ImageEncoding::WebP {
+                quality: 80,
+                lossless: false
+            }
+        );
+
+        let req = ProcessRequest {
+            image_format: proto::ImageFormat::Avif as i32,
+            avif_speed: 30,
+            ..Default::default()
+        };
+        assert_eq!(
+            output_options_for(&req).image_format,
+            ImageEncoding::Avif {
+                quality: 95,
+                speed: 10
+            }
+        );
+
+        let req = ProcessRequest {
+            image_format: proto::ImageFormat::Png as i32,
+            png_compression: proto::PngCompression::Best as i32,
+            png_16bit: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            output_options_for(&req).image_format,
+            ImageEncoding::Png {
+                compression: PngCompression::Best,
+                filter: PngFilter::Adaptive,
+                sixteen_bit: true
+            }
+        );
+
+        let req = ProcessRequest {
+            image_format: proto::ImageFormat::Jxl as i32,
+            ..Default::default()
+        };
+        assert_eq!(output_options_for(&req).image_format, ImageEncoding::Jxl);
+    }
+
+    #[test]
+    fn test_request_rejects_unknown_encoder() {
+        let service = SpatialMakerService::default();
+        let req = ProcessRequest {
+            encoder_size: "xl".to_string(),
+            ..Default::default()
+        };
+        let status = Status::from(service.config_for(&req).unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_cancelled_maps_to_status() {
+        let status = Status::from(SpatialError::Cancelled);
+        assert_eq!(status.code(), tonic::Code::Cancelled);
+    }
+}
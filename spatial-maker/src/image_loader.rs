@@ -3,11 +3,24 @@
 //! This module provides unified image loading across common formats.
 //! For advanced formats (AVIF, JXL, HEIC), it supports both native Rust decoders
 //! (via feature flags) and automatic conversion via ffmpeg as a fallback.
+//! Camera RAW files (CR2, NEF, ARW, DNG, ...) are developed in Rust with the
+//! `raw` feature; ffmpeg cannot stand in for it. OpenEXR files load with the
+//! `exr` feature and keep their float samples.
+//!
+//! Grayscale images are promoted to RGB (and grayscale with alpha to RGBA)
+//! at their own bit depth, so 16-bit scans stay 16-bit; paletted PNGs are
+//! expanded to 8-bit RGB or RGBA by the decoder. Both are logged.
 
-use crate::error::{SpatialError, SpatialResult};
+use crate::error::{decoding_error, unsupported_format, SpatialError, SpatialResult};
 use image::DynamicImage;
 use std::path::Path;
 use std::process::Command;
+use std::sync::OnceLock;
+
+/// Camera RAW extensions, decoded with the `raw` feature
+pub const RAW_EXTENSIONS: [&str; 12] = [
+    "cr2", "nef", "nrw", "arw", "srf", "sr2", "dng", "orf", "rw2", "raf", "pef", "srw",
+];
 
 /// Load an image from disk, supporting multiple formats
 ///
@@ -19,22 +32,27 @@ use std::process::Command;
 /// - Falls back to ffmpeg conversion if native decoder unavailable or fails
 /// - Returns helpful error if neither option works
 ///
+/// Camera RAW files ([`RAW_EXTENSIONS`]) need the `raw` feature, and OpenEXR
+/// files the `exr` feature. EXR images come back as 32-bit float RGB(A), which
+/// stereo rendering preserves.
+///
 /// # Arguments
 ///
 /// * `path` - Path to image file
 ///
 /// # Returns
 ///
-/// A `DynamicImage` in RGB format ready for processing
+/// A `DynamicImage` in RGB or RGBA format ready for processing
 ///
 /// # Errors
 ///
-/// Returns `SpatialError::ImageError` if:
-/// - File cannot be read
+/// Returns [`SpatialError::Image`] if:
 /// - Format is not supported
 /// - Image data is corrupted
 /// - Decoder fails
-/// - Conversion fails (for advanced formats)
+///
+/// and [`SpatialError::Io`] if the file cannot be read or the ffmpeg
+/// conversion (for advanced formats) fails.
 ///
 /// # Examples
 ///
@@ -49,14 +67,31 @@ use std::process::Command;
 /// # }
 /// ```
 pub async fn load_image(path: impl AsRef<Path>) -> SpatialResult<DynamicImage> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // Decoding a large photo takes long enough to stall the executor
+        let path = path.as_ref().to_path_buf();
+        crate::cpu::run(move || load_image_blocking(path)).await
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        load_image_blocking(path)
+    }
+}
+
+/// [`load_image`] for threads outside an async runtime
+///
+/// The same work on the calling thread; the worker pool ([`crate::pool`])
+/// loads photos with it.
+pub fn load_image_blocking(path: impl AsRef<Path>) -> SpatialResult<DynamicImage> {
     let path = path.as_ref();
 
     // Validate file exists
     if !path.exists() {
-        return Err(SpatialError::ImageError(format!(
-            "Image file not found: {:?}",
-            path
-        )));
+        return Err(SpatialError::io(
+            format!("Image file not found: {:?}", path),
+            std::io::ErrorKind::NotFound.into(),
+        ));
     }
 
     // Get file extension and normalize to lowercase
@@ -64,38 +99,128 @@ pub async fn load_image(path: impl AsRef<Path>) -> SpatialResult<DynamicImage> {
         .extension()
         .and_then(|ext| ext.to_str())
         .map(|s| s.to_lowercase())
-        .ok_or_else(|| SpatialError::ImageError(format!("File has no extension: {:?}", path)))?;
+        .ok_or_else(|| {
+            SpatialError::image(
+                format!("File has no extension: {:?}", path),
+                unsupported_format(path),
+            )
+        })?;
 
     tracing::debug!("Loading image from {:?} (format: {})", path, extension);
 
-    match extension.as_str() {
+    let img = match extension.as_str() {
         // AVIF: Try native decoder first (if feature enabled), then ffmpeg
-        "avif" => load_avif(path).await,
+        "avif" => load_avif(path),
 
         // JXL: Try native decoder first (if feature enabled), then ffmpeg
-        "jxl" => load_jxl(path).await,
+        "jxl" => load_jxl(path),
 
         // HEIC: Try native decoder first (if feature enabled), then ffmpeg
-        "heic" | "heif" => load_heic(path).await,
+        "heic" | "heif" => load_heic(path),
+
+        // Camera RAW: developed natively (if feature enabled)
+        ext if RAW_EXTENSIONS.contains(&ext) => load_raw(path),
+
+        // OpenEXR: float samples, kept as they are
+        "exr" => load_exr(path),
 
         // Standard formats supported by image crate
         "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "tif" | "webp" => {
             load_standard(path)
         }
 
-        _ => Err(SpatialError::ImageError(format!(
-            "Unsupported image format: .{}. Supported: JPEG, PNG, GIF, BMP, TIFF, WebP, AVIF, JXL, HEIC",
-            extension
-        ))),
+        _ => Err(SpatialError::image(
+            format!(
+                "Unsupported image format: .{}. Supported: JPEG, PNG, GIF, BMP, TIFF, WebP, AVIF, JXL, HEIC, camera RAW, EXR",
+                extension
+            ),
+            unsupported_format(path),
+        )),
+    }?;
+
+    if img.width() == 0 || img.height() == 0 {
+        return Err(SpatialError::image(
+            format!("Image {:?} is empty", path),
+            decoding_error(
+                &extension,
+                format!("{}x{} pixels", img.width(), img.height()),
+            ),
+        ));
+    }
+    Ok(promote_to_rgb(img))
+}
+
+/// Promote grayscale images to RGB, keeping their bit depth and alpha
+///
+/// Color images are returned unchanged.
+pub fn promote_to_rgb(img: DynamicImage) -> DynamicImage {
+    let promoted = match &img {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageRgb8(img.to_rgb8()),
+        DynamicImage::ImageLumaA8(_) => DynamicImage::ImageRgba8(img.to_rgba8()),
+        DynamicImage::ImageLuma16(_) => DynamicImage::ImageRgb16(img.to_rgb16()),
+        DynamicImage::ImageLumaA16(_) => DynamicImage::ImageRgba16(img.to_rgba16()),
+        _ => return img,
+    };
+    tracing::info!(
+        "Promoting grayscale image from {:?} to {:?}",
+        img.color(),
+        promoted.color()
+    );
+    promoted
+}
+
+/// PNG color type for paletted images, from the IHDR chunk
+const PNG_COLOR_PALETTE: u8 = 3;
+/// PNG color type for grayscale images without alpha
+const PNG_COLOR_GRAYSCALE: u8 = 0;
+
+/// Bit depth and color type from a PNG's IHDR chunk, or `None` if the file
+/// is not a PNG
+fn read_png_layout(path: &Path) -> Option<(u8, u8)> {
+    use std::io::Read;
+
+    // Signature (8), IHDR length and type (8), width and height (8), then
+    // bit depth and color type
+    let mut header = [0u8; 26];
+    std::fs::File::open(path)
+        .ok()?
+        .read_exact(&mut header)
+        .ok()?;
+    if &header[..8] != b"\x89PNG\r\n\x1a\n" || &header[12..16] != b"IHDR" {
+        return None;
     }
+    Some((header[24], header[25]))
 }
 
 /// Load standard image formats (JPEG, PNG, GIF, BMP, TIFF, WebP, etc.)
 fn load_standard(path: impl AsRef<Path>) -> SpatialResult<DynamicImage> {
     let path = path.as_ref();
 
-    let img = image::open(path)
-        .map_err(|e| SpatialError::ImageError(format!("Failed to load image {:?}: {}", path, e)))?;
+    let mut img = image::open(path)
+        .map_err(|e| SpatialError::image(format!("Failed to load image {:?}", path), e))?;
+
+    // Rotate phone photos upright before depth estimation
+    if let Some(orientation) = crate::metadata::read_exif(path)
+        .as_deref()
+        .and_then(crate::metadata::exif_orientation)
+    {
+        if orientation != 1 {
+            tracing::debug!("Applying EXIF orientation {}", orientation);
+            img = crate::metadata::apply_orientation(img, orientation);
+        }
+    }
+
+    match read_png_layout(path) {
+        Some((bit_depth, PNG_COLOR_PALETTE)) => tracing::info!(
+            "Expanded {}-bit paletted PNG to {:?}",
+            bit_depth,
+            img.color()
+        ),
+        Some((bit_depth, PNG_COLOR_GRAYSCALE)) if bit_depth < 8 => {
+            tracing::info!("Expanded {}-bit grayscale PNG to 8 bits", bit_depth)
+        }
+        _ => {}
+    }
 
     tracing::info!(
         "Loaded standard format image: {}x{} ({:?})",
@@ -108,7 +233,7 @@ fn load_standard(path: impl AsRef<Path>) -> SpatialResult<DynamicImage> {
 }
 
 /// Load AVIF image (native or ffmpeg fallback)
-async fn load_avif(path: &Path) -> SpatialResult<DynamicImage> {
+fn load_avif(path: &Path) -> SpatialResult<DynamicImage> {
     // Try native decoder if feature is enabled
     #[cfg(feature = "avif")]
     {
@@ -132,11 +257,11 @@ async fn load_avif(path: &Path) -> SpatialResult<DynamicImage> {
     #[cfg(not(feature = "avif"))]
     tracing::debug!("Native AVIF decoder not enabled, using ffmpeg");
 
-    load_with_conversion(path, "avif").await
+    load_with_conversion(path, "avif")
 }
 
 /// Load JXL image (native or ffmpeg fallback)
-async fn load_jxl(path: &Path) -> SpatialResult<DynamicImage> {
+fn load_jxl(path: &Path) -> SpatialResult<DynamicImage> {
     // Try native decoder if feature is enabled
     #[cfg(feature = "jxl")]
     {
@@ -160,11 +285,11 @@ async fn load_jxl(path: &Path) -> SpatialResult<DynamicImage> {
     #[cfg(not(feature = "jxl"))]
     tracing::debug!("Native JXL decoder not enabled, using ffmpeg");
 
-    load_with_conversion(path, "jxl").await
+    load_with_conversion(path, "jxl")
 }
 
 /// Load HEIC image (native or ffmpeg fallback)
-async fn load_heic(path: &Path) -> SpatialResult<DynamicImage> {
+fn load_heic(path: &Path) -> SpatialResult<DynamicImage> {
     // Try native decoder if feature is enabled
     #[cfg(feature = "heic")]
     {
@@ -188,15 +313,94 @@ async fn load_heic(path: &Path) -> SpatialResult<DynamicImage> {
     #[cfg(not(feature = "heic"))]
     tracing::debug!("Native HEIC decoder not enabled, using ffmpeg");
 
-    load_with_conversion(path, "heic").await
+    load_with_conversion(path, "heic")
+}
+
+/// Load a camera RAW file (requires 'raw' feature)
+fn load_raw(path: &Path) -> SpatialResult<DynamicImage> {
+    #[cfg(feature = "raw")]
+    {
+        let img = load_raw_native(path)?;
+        tracing::info!(
+            "Loaded RAW image using native decoder: {}x{}",
+            img.width(),
+            img.height()
+        );
+        Ok(img)
+    }
+
+    #[cfg(not(feature = "raw"))]
+    Err(SpatialError::ConfigError(format!(
+        "Camera RAW files need the native decoder: cargo build --features raw\n\
+         Or export {:?} to JPEG or TIFF from your photo editor first",
+        path
+    )))
+}
+
+/// Load an OpenEXR image (requires 'exr' feature)
+///
+/// The image crate reads the first RGB(A) layer as 32-bit float, linear as
+/// stored; values above 1.0 survive until the output is encoded.
+fn load_exr(path: &Path) -> SpatialResult<DynamicImage> {
+    #[cfg(feature = "exr")]
+    {
+        load_standard(path)
+    }
+
+    #[cfg(not(feature = "exr"))]
+    Err(SpatialError::ConfigError(format!(
+        "OpenEXR files need the EXR decoder: cargo build --features exr\n\
+         Or export {:?} to TIFF first",
+        path
+    )))
+}
+
+/// Develop a camera RAW file (requires 'raw' feature)
+///
+/// rawloader reads the sensor data; imagepipe's default pipeline then
+/// demosaics it, applies the camera's white balance and color matrix, tone
+/// maps it with a base curve, rotates it upright, and converts it to 8-bit
+/// sRGB. That is close to an out-of-camera JPEG, without the maker's own
+/// look (sharpening, noise reduction, lens corrections).
+#[cfg(feature = "raw")]
+fn load_raw_native(path: &Path) -> SpatialResult<DynamicImage> {
+    use imagepipe::{ImageSource, Pipeline};
+
+    let raw = rawloader::decode_file(path).map_err(|e| {
+        SpatialError::image("RAW decode failed", decoding_error("RAW", e.to_string()))
+    })?;
+    tracing::debug!("Developing {} {} RAW", raw.clean_make, raw.clean_model);
+
+    let mut pipeline = Pipeline::new_from_source(ImageSource::Raw(raw)).map_err(|e| {
+        SpatialError::image("RAW pipeline failed", decoding_error("RAW", e.to_string()))
+    })?;
+    let developed = pipeline.output_8bit(None).map_err(|e| {
+        SpatialError::image(
+            "RAW development failed",
+            decoding_error("RAW", e.to_string()),
+        )
+    })?;
+
+    let img_buffer = image::RgbImage::from_raw(
+        developed.width as u32,
+        developed.height as u32,
+        developed.data,
+    )
+    .ok_or_else(|| {
+        SpatialError::image(
+            "Failed to create image buffer from RAW data",
+            decoding_error("RAW", "pixel data does not match the image size"),
+        )
+    })?;
+
+    Ok(DynamicImage::ImageRgb8(img_buffer))
 }
 
 /// Load AVIF using native decoder (requires 'avif' feature)
 #[cfg(feature = "avif")]
 fn load_avif_native(path: &Path) -> SpatialResult<DynamicImage> {
     // AVIF support is built into the image crate when the feature is enabled
-    let img = image::open(path)
-        .map_err(|e| SpatialError::ImageError(format!("Native AVIF decode failed: {}", e)))?;
+    let img = image::open(path).map_err(|e| SpatialError::image("Native AVIF decode failed", e))?;
 
     Ok(img)
 }
@@ -206,27 +410,33 @@ fn load_avif_native(path: &Path) -> SpatialResult<DynamicImage> {
 fn load_jxl_native(path: &Path) -> SpatialResult<DynamicImage> {
     use jxl_oxide::JxlImage;
 
-    let data = std::fs::read(path)
-        .map_err(|e| SpatialError::IoError(format!("Failed to read JXL file: {}", e)))?;
+    let data = std::fs::read(path).map_err(|e| SpatialError::io("Failed to read JXL file", e))?;
 
-    let jxl_image = JxlImage::builder()
-        .read(&data[..])
-        .map_err(|e| SpatialError::ImageError(format!("JXL decode failed: {:?}", e)))?;
+    let jxl_image = JxlImage::builder().read(&data[..]).map_err(|e| {
+        SpatialError::image(
+            "JXL decode failed",
+            decoding_error("JPEG XL", format!("{:?}", e)),
+        )
+    })?;
 
     let width = jxl_image.width();
     let height = jxl_image.height();
 
     // Render the first frame
-    let render = jxl_image
-        .render_frame(0)
-        .map_err(|e| SpatialError::ImageError(format!("JXL render failed: {:?}", e)))?;
+    let render = jxl_image.render_frame(0).map_err(|e| {
+        SpatialError::image(
+            "JXL render failed",
+            decoding_error("JPEG XL", format!("{:?}", e)),
+        )
+    })?;
 
     // Get planar image data (Vec<FrameBuffer>)
     let planar = render.image_planar();
 
     if planar.is_empty() {
-        return Err(SpatialError::ImageError(
-            "JXL image has no color channels".to_string(),
+        return Err(SpatialError::image(
+            "JXL render failed",
+            decoding_error("JPEG XL", "image has no color channels"),
         ));
     }
 
@@ -259,7 +469,10 @@ fn load_jxl_native(path: &Path) -> SpatialResult<DynamicImage> {
 
     // Create RGB image buffer
     let img_buffer = image::RgbImage::from_raw(width, height, rgb_data).ok_or_else(|| {
-        SpatialError::ImageError("Failed to create image buffer from JXL data".to_string())
+        SpatialError::image(
+            "Failed to create image buffer from JXL data",
+            decoding_error("JPEG XL", "pixel data does not match the image size"),
+        )
     })?;
 
     Ok(DynamicImage::ImageRgb8(img_buffer))
@@ -272,14 +485,22 @@ fn load_heic_native(path: &Path) -> SpatialResult<DynamicImage> {
 
     let lib_heif = LibHeif::new();
 
-    let ctx = HeifContext::read_from_file(
-        path.to_str()
-            .ok_or_else(|| SpatialError::IoError("Invalid path encoding".to_string()))?,
-    )
-    .map_err(|e| SpatialError::ImageError(format!("Failed to load HEIC file: {:?}", e)))?;
+    let ctx =
+        HeifContext::read_from_file(path.to_str().ok_or_else(|| {
+            SpatialError::ConfigError(format!("Invalid path encoding: {:?}", path))
+        })?)
+        .map_err(|e| {
+            SpatialError::image(
+                "Failed to load HEIC file",
+                decoding_error("HEIC", format!("{:?}", e)),
+            )
+        })?;
 
     let handle = ctx.primary_image_handle().map_err(|e| {
-        SpatialError::ImageError(format!("Failed to get HEIC image handle: {:?}", e))
+        SpatialError::image(
+            "Failed to get HEIC image handle",
+            decoding_error("HEIC", format!("{:?}", e)),
+        )
     })?;
 
     let width = handle.width();
@@ -288,12 +509,20 @@ fn load_heic_native(path: &Path) -> SpatialResult<DynamicImage> {
     // Decode to RGB
     let image = lib_heif
         .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
-        .map_err(|e| SpatialError::ImageError(format!("HEIC decode failed: {:?}", e)))?;
+        .map_err(|e| {
+            SpatialError::image(
+                "HEIC decode failed",
+                decoding_error("HEIC", format!("{:?}", e)),
+            )
+        })?;
 
     // Get the interleaved plane data
     let planes = image.planes();
     let interleaved = planes.interleaved.ok_or_else(|| {
-        SpatialError::ImageError("No interleaved plane in HEIC image".to_string())
+        SpatialError::image(
+            "HEIC decode failed",
+            decoding_error("HEIC", "no interleaved plane in the image"),
+        )
     })?;
 
     // Convert to RGB image buffer
@@ -306,14 +535,17 @@ fn load_heic_native(path: &Path) -> SpatialResult<DynamicImage> {
     }
 
     let img_buffer = image::RgbImage::from_raw(width, height, rgb_data).ok_or_else(|| {
-        SpatialError::ImageError("Failed to create image buffer from HEIC data".to_string())
+        SpatialError::image(
+            "Failed to create image buffer from HEIC data",
+            decoding_error("HEIC", "pixel data does not match the image size"),
+        )
     })?;
 
     Ok(DynamicImage::ImageRgb8(img_buffer))
 }
 
 /// Load an image by converting it first using ffmpeg
-async fn load_with_conversion(path: impl AsRef<Path>, format: &str) -> SpatialResult<DynamicImage> {
+fn load_with_conversion(path: impl AsRef<Path>, format: &str) -> SpatialResult<DynamicImage> {
     let path = path.as_ref();
 
     tracing::info!(
@@ -323,7 +555,7 @@ async fn load_with_conversion(path: impl AsRef<Path>, format: &str) -> SpatialRe
 
     // Check if ffmpeg is available
     if !is_ffmpeg_available() {
-        return Err(SpatialError::ImageError(format!(
+        return Err(SpatialError::ConfigError(format!(
             "{} format requires either:\n\
              1. Native decoder (enable feature flag: --features {}), OR\n\
              2. ffmpeg for automatic conversion\n\
@@ -365,7 +597,7 @@ async fn load_with_conversion(path: impl AsRef<Path>, format: &str) -> SpatialRe
     // Load the converted image
     let img = image::open(&temp_path).map_err(|e| {
         let _ = std::fs::remove_file(&temp_path);
-        SpatialError::ImageError(format!("Failed to load converted image: {}", e))
+        SpatialError::image("Failed to load converted image", e)
     })?;
 
     tracing::info!(
@@ -394,11 +626,11 @@ fn is_ffmpeg_available() -> bool {
 fn convert_image_with_ffmpeg(input: &Path, output: &Path, format: &str) -> SpatialResult<()> {
     let output_str = output
         .to_str()
-        .ok_or_else(|| SpatialError::IoError("Invalid output path".to_string()))?;
+        .ok_or_else(|| SpatialError::ConfigError(format!("Invalid output path: {:?}", output)))?;
 
     let input_str = input
         .to_str()
-        .ok_or_else(|| SpatialError::IoError("Invalid input path".to_string()))?;
+        .ok_or_else(|| SpatialError::ConfigError(format!("Invalid input path: {:?}", input)))?;
 
     tracing::debug!("Converting {:?} ({}) to {:?}", input, format, output);
 
@@ -409,15 +641,17 @@ fn convert_image_with_ffmpeg(input: &Path, output: &Path, format: &str) -> Spati
         .args(&["-y"]) // Overwrite output file
         .arg(output_str)
         .output()
-        .map_err(|e| SpatialError::IoError(format!("Failed to run ffmpeg: {}", e)))?;
+        .map_err(|e| SpatialError::io("Failed to run ffmpeg", e))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SpatialError::ImageError(format!(
-            "ffmpeg conversion failed for {} format:\n{}",
-            format.to_uppercase(),
-            stderr
-        )));
+        return Err(SpatialError::io(
+            format!(
+                "ffmpeg conversion failed for {} format",
+                format.to_uppercase()
+            ),
+            std::io::Error::other(stderr.trim().to_string()),
+        ));
     }
 
     tracing::debug!("Successfully converted image using ffmpeg");
@@ -437,6 +671,9 @@ pub fn format_name(extension: &str) -> &str {
         "avif" => "AVIF",
         "jxl" => "JPEG XL",
         "heic" | "heif" => "HEIC/HEIF",
+        "dng" => "DNG",
+        ext if RAW_EXTENSIONS.contains(&ext) => "Camera RAW",
+        "exr" => "OpenEXR",
         _ => "Unknown",
     }
 }
@@ -448,30 +685,26 @@ pub fn supported_formats() -> &'static str {
 
 /// List formats that support native decoding (if features enabled)
 pub fn native_decoder_formats() -> &'static str {
-    // Return a static string based on enabled features
-    #[cfg(all(feature = "avif", feature = "jxl", feature = "heic"))]
-    return "AVIF, JXL, HEIC";
-
-    #[cfg(all(feature = "avif", feature = "jxl", not(feature = "heic")))]
-    return "AVIF, JXL";
-
-    #[cfg(all(feature = "avif", not(feature = "jxl"), feature = "heic"))]
-    return "AVIF, HEIC";
-
-    #[cfg(all(not(feature = "avif"), feature = "jxl", feature = "heic"))]
-    return "JXL, HEIC";
-
-    #[cfg(all(feature = "avif", not(feature = "jxl"), not(feature = "heic")))]
-    return "AVIF";
-
-    #[cfg(all(not(feature = "avif"), feature = "jxl", not(feature = "heic")))]
-    return "JXL";
-
-    #[cfg(all(not(feature = "avif"), not(feature = "jxl"), feature = "heic"))]
-    return "HEIC";
-
-    #[cfg(not(any(feature = "avif", feature = "jxl", feature = "heic")))]
-    return "None (use --features to enable)";
+    static FORMATS: OnceLock<String> = OnceLock::new();
+    FORMATS.get_or_init(|| {
+        let enabled = [
+            ("AVIF", cfg!(feature = "avif")),
+            ("JXL", cfg!(feature = "jxl")),
+            ("HEIC", cfg!(feature = "heic")),
+            ("RAW", cfg!(feature = "raw")),
+            ("EXR", cfg!(feature = "exr")),
+        ];
+        let formats: Vec<&str> = enabled
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect();
+        if formats.is_empty() {
+            "None (use --features to enable)".to_string()
+        } else {
+            formats.join(", ")
+        }
+    })
 }
 
 /// List formats that require conversion (ffmpeg fallback)
@@ -533,6 +766,151 @@ mod tests {
         assert_eq!(format_name("heic"), "HEIC/HEIF");
         assert_eq!(format_name("avif"), "AVIF");
         assert_eq!(format_name("jxl"), "JPEG XL");
+        assert_eq!(format_name("NEF"), "Camera RAW");
+        assert_eq!(format_name("dng"), "DNG");
+        assert_eq!(format_name("EXR"), "OpenEXR");
+    }
+
+    #[test]
+    fn test_raw_without_feature_explains() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.CR2");
+        std::fs::write(&path, b"not a real raw file").unwrap();
+
+        let err = load_image_blocking(&path).unwrap_err().to_string();
+        if cfg!(feature = "raw") {
+            assert!(err.contains("RAW decode failed"), "{}", err);
+        } else {
+            assert!(err.contains("--features raw"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_exr_loads_float() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plate.exr");
+        let bright = image::Rgb32FImage::from_pixel(4, 2, image::Rgb([2.5, 0.5, 0.25]));
+        let written = DynamicImage::ImageRgb32F(bright).save(&path);
+
+        if cfg!(feature = "exr") {
+            written.unwrap();
+            let img = load_image_blocking(&path).unwrap();
+            assert!(
+                matches!(
+                    img,
+                    DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+                ),
+                "{:?}",
+                img.color()
+            );
+            assert_eq!(img.to_rgb32f().get_pixel(3, 1)[0], 2.5);
+        } else {
+            assert!(written.is_err());
+            std::fs::write(&path, b"not a real exr file").unwrap();
+            let err = load_image_blocking(&path).unwrap_err().to_string();
+            assert!(err.contains("--features exr"), "{}", err);
+        }
+    }
+
+    /// An encoded 8-bit paletted PNG with the given palette and rows of indices
+    fn palette_png(palette: &[[u8; 3]], transparency: &[u8], rows: &[&[u8]]) -> Vec<u8> {
+        use std::io::Write;
+
+        fn chunk(out: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+            let mut crc = crc32fast::Hasher::new();
+            crc.update(kind);
+            crc.update(data);
+            out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            out.extend_from_slice(kind);
+            out.extend_from_slice(data);
+            out.extend_from_slice(&crc.finalize().to_be_bytes());
+        }
+
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&(rows[0].len() as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(rows.len() as u32).to_be_bytes());
+        ihdr.extend_from_slice(&[8, PNG_COLOR_PALETTE, 0, 0, 0]);
+
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        for row in rows {
+            encoder.write_all(&[0]).unwrap();
+            encoder.write_all(row).unwrap();
+        }
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &ihdr);
+        chunk(&mut png, b"PLTE", palette.concat().as_slice());
+        if !transparency.is_empty() {
+            chunk(&mut png, b"tRNS", transparency);
+        }
+        chunk(&mut png, b"IDAT", &encoder.finish().unwrap());
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    #[test]
+    fn test_grayscale_promoted_at_bit_depth() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("l8.png");
+        image::GrayImage::from_pixel(3, 2, image::Luma([90]))
+            .save(&path)
+            .unwrap();
+        let img = load_image_blocking(&path).unwrap();
+        assert_eq!(img.color(), image::ColorType::Rgb8);
+        assert_eq!(img.to_rgb8().get_pixel(2, 1).0, [90, 90, 90]);
+
+        let path = dir.path().join("l16.png");
+        image::ImageBuffer::<image::Luma<u16>, _>::from_pixel(3, 2, image::Luma([40_000]))
+            .save(&path)
+            .unwrap();
+        let img = load_image_blocking(&path).unwrap();
+        assert_eq!(img.color(), image::ColorType::Rgb16);
+        assert_eq!(img.to_rgb16().get_pixel(0, 0).0, [40_000; 3]);
+
+        let path = dir.path().join("la8.png");
+        image::GrayAlphaImage::from_pixel(3, 2, image::LumaA([200, 10]))
+            .save(&path)
+            .unwrap();
+        let img = load_image_blocking(&path).unwrap();
+        assert_eq!(img.color(), image::ColorType::Rgba8);
+        assert_eq!(img.to_rgba8().get_pixel(1, 1).0, [200, 200, 200, 10]);
+    }
+
+    #[test]
+    fn test_palette_png_expanded() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = dir.path().join("palette.png");
+        std::fs::write(
+            &path,
+            palette_png(&[[255, 0, 0], [0, 0, 255]], &[], &[&[0, 1]]),
+        )
+        .unwrap();
+        assert_eq!(read_png_layout(&path), Some((8, PNG_COLOR_PALETTE)));
+        let img = load_image_blocking(&path).unwrap();
+        assert_eq!(img.color(), image::ColorType::Rgb8);
+        assert_eq!(img.to_rgb8().get_pixel(1, 0).0, [0, 0, 255]);
+
+        // A tRNS chunk makes palette entries transparent
+        let path = dir.path().join("palette_alpha.png");
+        std::fs::write(
+            &path,
+            palette_png(&[[255, 0, 0], [0, 0, 255]], &[0], &[&[0, 1]]),
+        )
+        .unwrap();
+        let img = load_image_blocking(&path).unwrap();
+        assert_eq!(img.color(), image::ColorType::Rgba8);
+        assert_eq!(img.to_rgba8().get_pixel(0, 0)[3], 0);
+        assert_eq!(img.to_rgba8().get_pixel(1, 0)[3], 255);
+    }
+
+    #[test]
+    fn test_color_images_unchanged() {
+        let rgb = DynamicImage::new_rgb16(2, 2);
+        assert_eq!(promote_to_rgb(rgb.clone()), rgb);
+        assert_eq!(read_png_layout(Path::new("missing.png")), None);
     }
 
     #[test]
@@ -5,15 +5,42 @@
 //! ## Features
 //!
 //! - **Depth Estimation**: Load and run ONNX Depth Anything V2 models via the `ort` crate
+//! - **Depth Shaping**: Percentile clipping, contrast, and gamma to tune how far the foreground pops
+//! - **Depth Cache**: Optional on-disk cache keyed by image content and depth settings, so re-renders skip inference
+//! - **Tiled Inference**: Overlapping, blended depth tiles keep local detail in very large images
 //! - **Stereo Generation**: Convert depth maps to stereo pairs using depth-image-based rendering (DIBR)
+//! - **Subject Focus**: Center-weighted saliency puts the main subject at the screen plane and lets the background recede
+//! - **Two-Photo Stereo**: Align two handheld shots, or rectify a stereo rig capture, into a stereo pair without a depth model
+//! - **Wiggle Animations**: Looping GIF/MP4 wigglegrams swept across synthesized viewpoints
+//! - **Depth of Field**: Portrait-mode bokeh from the depth map
+//! - **Point Cloud Export**: Colored PLY point clouds of the photo's geometry for Blender or MeshLab
+//! - **Mesh Export**: Depth-displaced, photo-textured meshes as glTF (`.glb`) or USDZ for AR Quick Look
 //! - **Model Management**: Automatic model discovery and download from HuggingFace
+//! - **Model Pinning**: `models.lock` records each model's URL, ETag, SHA-256, and size, and refuses changed upstream files
 //! - **Photo Pipeline**: End-to-end spatial photo generation (single image)
+//! - **In-Memory API**: Stereo pairs and depth from decoded images, for callers that handle I/O themselves
+//! - **360° Panoramas**: Depth from overlapping perspective views of an equirectangular photo, written as over/under stereo for VR viewers
 //! - **Multi-Format Input**: Support for JPEG, PNG, AVIF, JPEG XL (JXL), and HEIC formats
-//! - **Video Pipeline**: Frame-by-frame video processing with progress callbacks
+//! - **Video Pipeline**: Frame-by-frame video processing streamed through ffmpeg pipes, with progress callbacks
+//! - **Depth Keyframing**: Run depth every Nth video frame and interpolate in between
+//! - **Depth Video Export**: Synchronized 8/16-bit grayscale depth video alongside stereo output
+//! - **Player-Friendly Naming**: `_SBS_FULL` / `_TB` / `_180x180_3dh` file tags and JSON sidecars so VR players pick the stereo mode
+//! - **Audio Passthrough**: Copy source audio (including ambisonic tracks) into video output
+//! - **Parallel Processing**: Worker pool overlapping decode, inference, and encoding for videos and photo batches
+//! - **Executor-Friendly**: Decoding, inference, and warping run on a dedicated CPU pool with a configurable thread budget
+//! - **Resumable Video Jobs**: Per-segment checkpoints so interrupted runs pick up where they stopped, and directory batches that skip finished videos
+//! - **Progress Reporting**: Optional per-stage progress callback for photo processing, and a structured event stream (model download, warping, frames, ...) for photos and videos
+//! - **Run Manifests**: JSON record of inputs, outputs, model hash, and timings for batch runs
+//! - **Benchmarks**: Per-stage timings for each encoder size, to pick a model for the hardware
 //! - **CoreML Support**: Leverages Apple Neural Engine on macOS with CoreML execution provider
+//! - **GPU Selection**: CUDA and DirectML execution providers (`cuda` / `directml` features) pinned to a chosen GPU
+//! - **Session Tuning**: ONNX Runtime thread pools, graph optimization, and memory arena settings for server deployments
+//! - **Model Warm-Up**: Load a model and run a dummy inference ahead of the first image, e.g. behind a splash screen
+//! - **Browser Support**: Depth and stereo on ONNX Runtime Web for `wasm32` (`web` feature, see `web`)
 //!
 //! ## Example
 //!
+
 /// ```no_run
 /// use spatial_maker::{process_photo, SpatialConfig, OutputOptions};
 /// use std::path::Path;
@@ -30,36 +57,212 @@
 /// # Ok(())
 /// # }
 /// ```
+pub mod align;
+pub mod audio;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bench;
+pub mod bokeh;
+pub mod cancel;
+pub mod checkpoint;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cpu;
+pub mod cutlist;
 pub mod depth;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod depth_cache;
+pub mod depth_map;
+pub mod depth_video;
 pub mod error;
+pub mod export;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod image_loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod manifest;
+pub mod metadata;
 pub mod model;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod model_lock;
+pub mod naming;
 pub mod output;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod panorama;
+pub mod pool;
+pub mod progress;
+pub mod resample;
+pub mod session;
+#[cfg(target_os = "macos")]
+mod spatial_heic;
 pub mod stereo;
+#[cfg(feature = "remote-io")]
+pub mod storage;
+pub mod subject;
+pub mod temporal;
+pub mod tiling;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod video;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+pub mod web;
+pub mod wiggle;
+
+#[cfg(all(target_arch = "wasm32", not(feature = "web")))]
+compile_error!("spatial-maker needs the `web` feature to build for wasm32");
 
-pub use depth::{estimate_depth, DepthConfig};
+pub use align::{
+    align_stereo_pair, AlignConfig, AlignModel, AlignedPair, AlignmentReport, Convergence,
+};
+pub use bokeh::{render_bokeh, BokehConfig};
+pub use cancel::CancellationToken;
+pub use cutlist::CutList;
+pub use depth::{auto_target_size, estimate_depth, DepthConfig, DepthEstimator, TargetSize};
+#[cfg(not(target_arch = "wasm32"))]
+pub use depth::{warmup, warmup_with};
+pub use depth_map::{DepthMap, DepthShaping};
+pub use depth_video::{DepthVideoEncoding, DepthVideoWriter};
 pub use error::{SpatialError, SpatialResult};
-pub use image_loader::load_image;
-pub use model::{find_model, get_checkpoint_dir, model_exists};
-pub use output::{save_stereo_image, ImageEncoding, MVHEVCConfig, OutputFormat, OutputOptions};
-pub use stereo::generate_stereo_pair;
+pub use export::{
+    displaced_mesh, point_cloud, save_mesh, save_ply, Mesh, MeshConfig, MeshFormat, PlyEncoding,
+    PointCloud, PointCloudConfig,
+};
+pub use image_loader::{load_image, load_image_blocking};
+#[cfg(not(target_arch = "wasm32"))]
+pub use manifest::RunManifest;
+pub use metadata::Attribution;
+pub use model::EncoderSize;
+#[cfg(not(target_arch = "wasm32"))]
+pub use model::{
+    delete_model, find_model, get_checkpoint_dir, list_downloaded_models, model_exists,
+    DownloadedModel,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use model_lock::{LockStatus, ModelLock};
+pub use naming::PlayerNaming;
+#[cfg(feature = "exr")]
+pub use output::save_depth_exr;
+pub use output::{
+    save_depth_visualization, save_quilt, save_stereo_image, Colormap, FilenameTemplate,
+    ImageEncoding, MVHEVCBackend, MVHEVCConfig, OutputFormat, OutputOptions, PngCompression,
+    PngFilter, PreviewSize, Projection, QuiltConfig,
+};
+pub use pool::{ParallelConfig, SessionSharing};
+pub use progress::{ProcessingStage, ProgressEvent, ProgressSink};
+pub use resample::{upsample_depth, DepthUpsampling, ResizeFilter};
+pub use session::{GraphOptimization, SessionTuning};
+pub use stereo::{
+    generate_stereo_pair, generate_stereo_pair_360, generate_stereo_pair_cancellable,
+    generate_stereo_pair_vr180, generate_stereo_pair_with_fill, synthesize_view, HoleFill,
+    HoleFillStrategy,
+};
+pub use subject::SubjectFocus;
+pub use temporal::{DepthInterpolation, DepthKeyframer};
+pub use tiling::TilingConfig;
+#[cfg(not(target_arch = "wasm32"))]
+pub use video::{
+    probe_video, process_video, process_video_dir, VideoConfig, VideoInfo, VideoReport,
+};
+pub use wiggle::{render_wiggle_frames, save_wiggle, WiggleConfig, WiggleFormat};
 
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
 /// Configuration for spatial photo/video processing
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct SpatialConfig {
-    /// Depth estimation model size: "s" (small), "b" (base), or "l" (large)
-    pub encoder_size: String,
+    /// Depth estimation model size ("s", "b", or "l" when serialized)
+    pub encoder_size: EncoderSize,
 
     /// Maximum disparity for stereo generation (pixels)
     pub max_disparity: u32,
 
-    /// Target input size for depth model (shorter side, in pixels)
-    pub target_depth_size: u32,
+    /// Target input size for depth model (shorter side, in pixels), or
+    /// `"auto"` to pick it from the input size and available memory
+    pub target_depth_size: TargetSize,
 
     /// Whether to use CoreML execution provider on macOS (if available)
+    ///
+    /// On by default, as CoreML runs the model on the Neural Engine and GPU;
+    /// ignored on other platforms.
     pub use_coreml: bool,
+
+    /// Cache the model CoreML compiles so later runs skip the compilation
+    /// (see [`DepthConfig::coreml_cache`])
+    #[serde(default = "default_coreml_cache", skip_serializing_if = "is_true")]
+    pub coreml_cache: bool,
+
+    /// GPU to run inference on with the `cuda` or `directml` feature
+    /// (see [`DepthConfig::device_id`])
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub device_id: u32,
+
+    /// Filter for scaling the image to the depth model's input
+    #[serde(default)]
+    pub resize_filter: ResizeFilter,
+
+    /// How depth is scaled up to the image for stereo (see [`resample`])
+    #[serde(default)]
+    pub depth_upsampling: DepthUpsampling,
+
+    /// Video: run depth estimation on every Nth frame (1 = every frame)
+    #[serde(default = "default_depth_interval")]
+    pub depth_interval: u32,
+
+    /// Video: how depth is filled in for frames between keyframes
+    #[serde(default)]
+    pub depth_interpolation: DepthInterpolation,
+
+    /// Video: also write the per-frame depth maps as a grayscale video next
+    /// to the output (see [`depth_video`])
+    #[serde(default)]
+    pub depth_video: Option<DepthVideoEncoding>,
+
+    /// Tile very large images for depth estimation (see [`TilingConfig`])
+    #[serde(default)]
+    pub depth_tiling: Option<TilingConfig>,
+
+    /// Retry depth estimation with a smaller size, model, or CPU provider
+    /// when it runs out of memory (see [`DepthConfig::degrade_on_failure`]);
+    /// off by default
+    #[serde(default)]
+    pub degrade_on_failure: bool,
+
+    /// Clipping, contrast, and gamma applied to depth after normalization
+    /// (see [`DepthShaping`])
+    #[serde(default, skip_serializing_if = "DepthShaping::is_identity")]
+    pub depth_shaping: DepthShaping,
+
+    /// Directory to cache depth maps in, so re-running with other stereo
+    /// or output settings skips inference (see [`depth_cache`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_cache: Option<std::path::PathBuf>,
+
+    /// Converge on the detected subject so it sits at the screen plane
+    /// (see [`subject`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject_focus: Option<SubjectFocus>,
+
+    /// How disocclusions in the synthesized view are filled (see
+    /// [`HoleFill`])
+    #[serde(default, skip_serializing_if = "HoleFill::is_default")]
+    pub hole_fill: HoleFill,
+
+    /// Worker threads for videos and photo batches (see [`pool`])
+    #[serde(default)]
+    pub parallel: ParallelConfig,
+
+    /// ONNX Runtime thread pools, graph optimization, and memory arena
+    /// (see [`session`])
+    #[serde(default, skip_serializing_if = "SessionTuning::is_default")]
+    pub session_tuning: SessionTuning,
+
+    /// Optional token to abort processing; shared with the depth, stereo,
+    /// and output stages
+    #[serde(skip)]
+    pub cancel: Option<CancellationToken>,
+
+    /// Receives [`ProgressEvent`]s from the photo and video pipelines (see
+    /// [`progress`])
+    #[serde(skip)]
+    pub progress: Option<ProgressSink>,
 }
 
 /// Legacy type alias for backward compatibility
@@ -68,11 +271,237 @@ pub type StereoOutputFormat = OutputFormat;
 impl Default for SpatialConfig {
     fn default() -> Self {
         Self {
-            encoder_size: "s".to_string(),
+            encoder_size: EncoderSize::Small,
             max_disparity: 30,
-            target_depth_size: 518,
+            target_depth_size: TargetSize::default(),
             use_coreml: true,
+            coreml_cache: true,
+            device_id: 0,
+            resize_filter: ResizeFilter::default(),
+            depth_upsampling: DepthUpsampling::default(),
+            depth_interval: 1,
+            depth_interpolation: DepthInterpolation::default(),
+            depth_video: None,
+            depth_tiling: None,
+            degrade_on_failure: false,
+            depth_shaping: DepthShaping::default(),
+            depth_cache: None,
+            subject_focus: None,
+            hole_fill: HoleFill::default(),
+            parallel: ParallelConfig::default(),
+            session_tuning: SessionTuning::default(),
+            cancel: None,
+            progress: None,
+        }
+    }
+}
+
+fn default_depth_interval() -> u32 {
+    1
+}
+
+fn default_coreml_cache() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl SpatialConfig {
+    /// Start building a configuration from the defaults
+    ///
+    /// ```
+    /// use spatial_maker::{EncoderSize, SpatialConfig, TargetSize};
+    ///
+    /// let config = SpatialConfig::builder()
+    ///     .encoder_size(EncoderSize::Base)
+    ///     .max_disparity(40)
+    ///     .target_depth_size(TargetSize::Auto)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.encoder_size, EncoderSize::Base);
+    /// ```
+    pub fn builder() -> SpatialConfigBuilder {
+        SpatialConfigBuilder::default()
+    }
+
+    /// Depth estimation settings derived from this configuration
+    pub fn depth_config(&self) -> DepthConfig {
+        DepthConfig {
+            encoder_size: self.encoder_size,
+            target_size: self.target_depth_size,
+            use_coreml: self.use_coreml,
+            coreml_cache: self.coreml_cache,
+            device_id: self.device_id,
+            resize_filter: self.resize_filter,
+            cancel: self.cancel.clone(),
+            progress: self.progress.clone(),
+            tiling: self.depth_tiling.clone(),
+            degrade_on_failure: self.degrade_on_failure,
+            shaping: self.depth_shaping,
+            cache: self.depth_cache.clone(),
+            session: self.session_tuning,
+        }
+    }
+
+    /// Check settings the types alone cannot rule out
+    pub fn validate(&self) -> SpatialResult<()> {
+        if self.depth_interval == 0 {
+            return Err(SpatialError::ConfigError(
+                "depth_interval must be at least 1".to_string(),
+            ));
         }
+        if self.target_depth_size == TargetSize::Fixed(0) {
+            return Err(SpatialError::ConfigError(
+                "target_depth_size must be positive".to_string(),
+            ));
+        }
+        if self
+            .depth_tiling
+            .as_ref()
+            .is_some_and(|tiling| tiling.tile_size == 0)
+        {
+            return Err(SpatialError::ConfigError(
+                "depth_tiling.tile_size must be positive".to_string(),
+            ));
+        }
+        self.depth_shaping.validate()?;
+        self.session_tuning.validate()?;
+        if i32::try_from(self.device_id).is_err() {
+            return Err(SpatialError::ConfigError(format!(
+                "device_id {} is out of range",
+                self.device_id
+            )));
+        }
+        if let Some(focus) = &self.subject_focus {
+            focus.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`SpatialConfig`]; unset fields keep their defaults
+#[derive(Clone, Debug, Default)]
+pub struct SpatialConfigBuilder {
+    config: SpatialConfig,
+}
+
+impl SpatialConfigBuilder {
+    pub fn encoder_size(mut self, encoder_size: EncoderSize) -> Self {
+        self.config.encoder_size = encoder_size;
+        self
+    }
+
+    pub fn max_disparity(mut self, max_disparity: u32) -> Self {
+        self.config.max_disparity = max_disparity;
+        self
+    }
+
+    pub fn target_depth_size(mut self, target_depth_size: impl Into<TargetSize>) -> Self {
+        self.config.target_depth_size = target_depth_size.into();
+        self
+    }
+
+    pub fn use_coreml(mut self, use_coreml: bool) -> Self {
+        self.config.use_coreml = use_coreml;
+        self
+    }
+
+    pub fn coreml_cache(mut self, coreml_cache: bool) -> Self {
+        self.config.coreml_cache = coreml_cache;
+        self
+    }
+
+    pub fn device_id(mut self, device_id: u32) -> Self {
+        self.config.device_id = device_id;
+        self
+    }
+
+    pub fn resize_filter(mut self, resize_filter: ResizeFilter) -> Self {
+        self.config.resize_filter = resize_filter;
+        self
+    }
+
+    pub fn depth_upsampling(mut self, depth_upsampling: DepthUpsampling) -> Self {
+        self.config.depth_upsampling = depth_upsampling;
+        self
+    }
+
+    pub fn depth_interval(mut self, depth_interval: u32) -> Self {
+        self.config.depth_interval = depth_interval;
+        self
+    }
+
+    pub fn depth_interpolation(mut self, depth_interpolation: DepthInterpolation) -> Self {
+        self.config.depth_interpolation = depth_interpolation;
+        self
+    }
+
+    pub fn depth_video(mut self, encoding: DepthVideoEncoding) -> Self {
+        self.config.depth_video = Some(encoding);
+        self
+    }
+
+    pub fn depth_tiling(mut self, tiling: TilingConfig) -> Self {
+        self.config.depth_tiling = Some(tiling);
+        self
+    }
+
+    pub fn degrade_on_failure(mut self, degrade_on_failure: bool) -> Self {
+        self.config.degrade_on_failure = degrade_on_failure;
+        self
+    }
+
+    pub fn depth_shaping(mut self, depth_shaping: DepthShaping) -> Self {
+        self.config.depth_shaping = depth_shaping;
+        self
+    }
+
+    pub fn depth_cache(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.config.depth_cache = Some(dir.into());
+        self
+    }
+
+    pub fn subject_focus(mut self, subject_focus: SubjectFocus) -> Self {
+        self.config.subject_focus = Some(subject_focus);
+        self
+    }
+
+    pub fn hole_fill(mut self, hole_fill: HoleFill) -> Self {
+        self.config.hole_fill = hole_fill;
+        self
+    }
+
+    pub fn session_tuning(mut self, session_tuning: SessionTuning) -> Self {
+        self.config.session_tuning = session_tuning;
+        self
+    }
+
+    pub fn parallel(mut self, parallel: ParallelConfig) -> Self {
+        self.config.parallel = parallel;
+        self
+    }
+
+    pub fn cancel(mut self, cancel: CancellationToken) -> Self {
+        self.config.cancel = Some(cancel);
+        self
+    }
+
+    /// Push [`ProgressEvent`]s into a callback or channel sender
+    pub fn progress(mut self, progress: impl Into<ProgressSink>) -> Self {
+        self.config.progress = Some(progress.into());
+        self
+    }
+
+    /// Finish the configuration, rejecting invalid settings
+    pub fn build(self) -> SpatialResult<SpatialConfig> {
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
@@ -102,14 +531,13 @@ impl Default for SpatialConfig {
 ///
 /// ```no_run
 /// # async fn example() -> anyhow::Result<()> {
-/// use spatial_maker::{process_photo, SpatialConfig, OutputOptions};
+/// use spatial_maker::{process_photo, EncoderSize, SpatialConfig, OutputOptions};
 /// use std::path::Path;
 ///
-/// let config = SpatialConfig {
-///     encoder_size: "s".to_string(),
-///     max_disparity: 30,
-///     ..Default::default()
-/// };
+/// let config = SpatialConfig::builder()
+///     .encoder_size(EncoderSize::Small)
+///     .max_disparity(30)
+///     .build()?;
 /// let output_options = OutputOptions::default();
 /// process_photo(
 ///     Path::new("photo.heic"),
@@ -120,45 +548,730 @@ impl Default for SpatialConfig {
 /// # Ok(())
 /// # }
 /// ```
+#[cfg(not(target_arch = "wasm32"))]
 pub async fn process_photo(
     input_path: &Path,
     output_path: &Path,
     config: SpatialConfig,
     output_options: OutputOptions,
 ) -> SpatialResult<()> {
+    process_photo_with_progress::<fn(ProcessingStage, f32)>(
+        input_path,
+        output_path,
+        config,
+        output_options,
+        None,
+    )
+    .await
+}
+
+/// Process a single photo, reporting progress through a callback
+///
+/// Identical to [`process_photo`], but invokes `progress_fn` with the current
+/// [`ProcessingStage`] and the overall completion percentage (0-100) as each
+/// stage starts, and once more with 100% when processing finishes.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use spatial_maker::{process_photo_with_progress, OutputOptions, SpatialConfig};
+/// use std::path::Path;
+///
+/// process_photo_with_progress(
+///     Path::new("photo.jpg"),
+///     Path::new("spatial_photo.jpg"),
+///     SpatialConfig::default(),
+///     OutputOptions::default(),
+///     Some(|stage: spatial_maker::ProcessingStage, pct: f32| {
+///         println!("{}: {:.0}%", stage.name(), pct);
+///     }),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn process_photo_with_progress<F>(
+    input_path: &Path,
+    output_path: &Path,
+    config: SpatialConfig,
+    mut output_options: OutputOptions,
+    mut progress_fn: Option<F>,
+) -> SpatialResult<()>
+where
+    F: FnMut(ProcessingStage, f32),
+{
+    let mut report = |stage: ProcessingStage, pct: f32| {
+        if let Some(ref mut f) = progress_fn {
+            f(stage, pct);
+        }
+    };
+
     tracing::info!("📸 Processing photo: {:?}", input_path);
+    output::validate_options(&output_options)?;
+    carry_source_metadata(input_path, &mut output_options);
 
     // Load input image with multi-format support
+    report(
+        ProcessingStage::Loading,
+        ProcessingStage::Loading.start_percent(),
+    );
+    progress::emit(config.progress.as_ref(), ProgressEvent::Preprocess);
     tracing::debug!("Loading image from {:?}", input_path);
     let input_image = load_image(input_path).await?;
 
     // Estimate depth
+    report(
+        ProcessingStage::Depth,
+        ProcessingStage::Depth.start_percent(),
+    );
     tracing::debug!("Estimating depth with encoder: {}", config.encoder_size);
-    let depth_map = estimate_depth(
-        &input_image,
-        &DepthConfig {
-            encoder_size: config.encoder_size.clone(),
-            target_size: config.target_depth_size,
-            use_coreml: config.use_coreml,
+    let mut estimator = DepthEstimator::load(config.depth_config()).await?;
+
+    // Inference, warping, and encoding run on the CPU pool; their stages are
+    // reported back here
+    let output_path = output_path.to_path_buf();
+    cpu::run_with_progress(
+        move |report| {
+            progress::emit(config.progress.as_ref(), ProgressEvent::Inference);
+            let depth_map = estimate_projected(&input_image, output_options.projection, |image| {
+                estimator.estimate(image)
+            })?;
+            render_photo(
+                &input_image,
+                &depth_map,
+                &output_path,
+                &config,
+                output_options,
+                &mut |stage, pct| report((stage, pct)),
+            )
         },
+        |(stage, pct)| report(stage, pct),
     )
     .await?;
+    tracing::info!("✅ Photo processing complete!");
+    Ok(())
+}
+
+/// Carry the input's EXIF and ICC profile through to the output unless the
+/// caller supplied its own. load_image already applies the Orientation tag
+/// to the pixels.
+#[cfg(not(target_arch = "wasm32"))]
+fn carry_source_metadata(input_path: &Path, output_options: &mut OutputOptions) {
+    if output_options.exif.is_none() {
+        output_options.exif = metadata::read_exif(input_path).map(|mut raw| {
+            metadata::reset_orientation(&mut raw);
+            raw
+        });
+    }
+    if output_options.icc_profile.is_none() {
+        output_options.icc_profile = metadata::read_icc_profile(input_path);
+    }
+}
 
+/// Generate the stereo pair from a loaded photo and its depth, then save it
+#[cfg(not(target_arch = "wasm32"))]
+fn render_photo(
+    input_image: &image::DynamicImage,
+    depth_map: &DepthMap,
+    output_path: &Path,
+    config: &SpatialConfig,
+    mut output_options: OutputOptions,
+    report: &mut dyn FnMut(ProcessingStage, f32),
+) -> SpatialResult<()> {
     // Generate stereo pair
+    report(
+        ProcessingStage::Stereo,
+        ProcessingStage::Stereo.start_percent(),
+    );
     tracing::debug!(
         "Generating stereo pair with max_disparity: {}",
         config.max_disparity
     );
-    let (left, right) = generate_stereo_pair(&input_image, &depth_map, config.max_disparity)?;
+    let (left, right) = render_stereo(input_image, depth_map, config, output_options.projection)?;
 
-    // Save stereo output
+    // Save stereo output; MV-HEVC is applied separately so it can be reported
+    // as its own stage
+    report(
+        ProcessingStage::Saving,
+        ProcessingStage::Saving.start_percent(),
+    );
     tracing::info!("Saving stereo image to {:?}", output_path);
+    progress::emit(config.progress.as_ref(), ProgressEvent::Encode);
+    if output_options.cancel.is_none() {
+        output_options.cancel = config.cancel.clone();
+    }
+    let mvhevc = output_options.mvhevc.take();
+    let output_options_cancel = output_options.cancel.clone();
+    let attribution = output_options.attribution.clone();
+    let filename_template = output_options.filename_template.clone();
+    let (output_layout, output_encoding) = (output_options.layout, output_options.image_format);
     save_stereo_image(&left, &right, output_path, output_options)?;
 
-    tracing::info!("✅ Photo processing complete!");
+    let mut last_stage = ProcessingStage::Saving;
+    if let Some(mvhevc_config) = mvhevc.filter(|c| c.enabled) {
+        report(
+            ProcessingStage::Mvhevc,
+            ProcessingStage::Mvhevc.start_percent(),
+        );
+        output::apply_mvhevc(
+            &left,
+            &right,
+            output_path,
+            output_layout,
+            output_encoding,
+            &mvhevc_config,
+            &filename_template,
+            attribution.as_ref(),
+            output_options_cancel.as_ref(),
+        )?;
+        last_stage = ProcessingStage::Mvhevc;
+    }
+
+    report(last_stage, 100.0);
+    progress::emit(config.progress.as_ref(), ProgressEvent::Done);
     Ok(())
 }
 
+/// Warp the stereo pair for `projection`, honoring the config's cancel token,
+/// depth upsampling, subject focus, and hole filling
+fn render_stereo(
+    image: &image::DynamicImage,
+    depth_map: &DepthMap,
+    config: &SpatialConfig,
+    projection: Projection,
+) -> SpatialResult<(image::DynamicImage, image::DynamicImage)> {
+    let depth_map = stereo_depth(image, depth_map, config);
+    warp_stereo(image, &depth_map, config, projection)
+}
+
+/// The depth a stereo pair is warped with: `depth_map` upsampled to `image`
+/// and, with subject focus set, converged on the subject
+fn stereo_depth(
+    image: &image::DynamicImage,
+    depth_map: &DepthMap,
+    config: &SpatialConfig,
+) -> DepthMap {
+    let upsampled = upsample_depth(depth_map, image, config.depth_upsampling);
+    let data = match config.subject_focus {
+        Some(focus) => focus.apply(&upsampled),
+        None => upsampled.into_owned(),
+    };
+    DepthMap {
+        data,
+        raw_range: depth_map.raw_range,
+        inference_size: depth_map.inference_size,
+        model: depth_map.model.clone(),
+    }
+}
+
+/// [`render_stereo`] with depth already from [`stereo_depth`]
+fn warp_stereo(
+    image: &image::DynamicImage,
+    depth_map: &DepthMap,
+    config: &SpatialConfig,
+    projection: Projection,
+) -> SpatialResult<(image::DynamicImage, image::DynamicImage)> {
+    let (cancel, progress) = (config.cancel.as_ref(), config.progress.as_ref());
+    let warp = match projection {
+        Projection::Rectilinear => stereo::warp_stereo_pair,
+        Projection::Vr180 => stereo::warp_stereo_pair_vr180,
+        Projection::Panorama360 => stereo::warp_stereo_pair_360,
+    };
+    warp(
+        image,
+        depth_map,
+        config.max_disparity,
+        &config.hole_fill,
+        cancel,
+        progress,
+    )
+}
+
+/// Estimate depth as `projection` needs it: 360° panoramas view by view
+/// (see [`panorama`]), everything else in one call to `estimate`
+#[cfg(not(target_arch = "wasm32"))]
+fn estimate_projected(
+    image: &image::DynamicImage,
+    projection: Projection,
+    mut estimate: impl FnMut(&image::DynamicImage) -> SpatialResult<DepthMap>,
+) -> SpatialResult<DepthMap> {
+    match projection {
+        Projection::Panorama360 => panorama::estimate_depth(image, estimate),
+        _ => estimate(image),
+    }
+}
+
+/// A stereo pair generated in memory, with the depth it was rendered from
+#[derive(Clone, Debug)]
+pub struct StereoResult {
+    /// Left-eye view (the input image)
+    pub left: image::DynamicImage,
+    /// Right-eye view, synthesized from the depth
+    pub right: image::DynamicImage,
+    /// Depth the right view was rendered from: upsampled to the input's
+    /// resolution, with subject focus applied when set
+    pub depth: DepthMap,
+}
+
+/// Generate a stereo pair from an already decoded image, without touching disk
+///
+/// The in-memory counterpart of [`process_photo`] for servers and GUIs that
+/// decode and save images themselves. Of `options`, only `projection` affects
+/// the result; pass the same options to [`save_stereo_image`] to write it out
+/// as `process_photo` would. The depth model is loaded for each call, so a
+/// caller processing many images should keep a [`DepthEstimator`] and use
+/// [`process_image_with`] instead.
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::{process_image, OutputOptions, SpatialConfig};
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let image = image::open("input.jpg")?;
+/// let result = process_image(&image, &SpatialConfig::default(), &OutputOptions::default()).await?;
+/// println!("{}x{} per eye", result.right.width(), result.right.height());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn process_image(
+    image: &image::DynamicImage,
+    config: &SpatialConfig,
+    options: &OutputOptions,
+) -> SpatialResult<StereoResult> {
+    let mut estimator = DepthEstimator::load(config.depth_config()).await?;
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let (image, config, options) = (image.clone(), config.clone(), options.clone());
+        cpu::run(move || process_image_with(&mut estimator, &image, &config, &options)).await
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        process_image_with(&mut estimator, image, config, options).await
+    }
+}
+
+/// [`process_image`] with an already loaded depth model
+///
+/// The estimator's own settings are used for depth; `config` supplies the
+/// disparity and cancel token.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn process_image_with(
+    estimator: &mut DepthEstimator,
+    image: &image::DynamicImage,
+    config: &SpatialConfig,
+    options: &OutputOptions,
+) -> SpatialResult<StereoResult> {
+    let depth = estimate_projected(image, options.projection, |image| estimator.estimate(image))?;
+    stereo_result(image, depth, config, options)
+}
+
+/// [`process_image`] with an already loaded depth model
+///
+/// The estimator's own settings are used for depth; `config` supplies the
+/// disparity and cancel token.
+#[cfg(target_arch = "wasm32")]
+pub async fn process_image_with(
+    estimator: &mut DepthEstimator,
+    image: &image::DynamicImage,
+    config: &SpatialConfig,
+    options: &OutputOptions,
+) -> SpatialResult<StereoResult> {
+    if options.projection == Projection::Panorama360 {
+        return Err(SpatialError::ConfigError(
+            "360° panoramas are not available in the browser".to_string(),
+        ));
+    }
+    let depth = estimator.estimate(image).await?;
+    stereo_result(image, depth, config, options)
+}
+
+fn stereo_result(
+    image: &image::DynamicImage,
+    depth: DepthMap,
+    config: &SpatialConfig,
+    options: &OutputOptions,
+) -> SpatialResult<StereoResult> {
+    let depth = stereo_depth(image, &depth, config);
+    let (left, right) = warp_stereo(image, &depth, config, options.projection)?;
+    Ok(StereoResult { left, right, depth })
+}
+
+/// [`process_photo`] on a pool worker, with that worker's depth session
+#[cfg(not(target_arch = "wasm32"))]
+fn process_photo_blocking(
+    input_path: &Path,
+    output_path: &Path,
+    config: &SpatialConfig,
+    mut output_options: OutputOptions,
+    depth: &mut pool::DepthWorker,
+) -> SpatialResult<()> {
+    tracing::info!("📸 Processing photo: {:?}", input_path);
+    output::validate_options(&output_options)?;
+    carry_source_metadata(input_path, &mut output_options);
+
+    let input_image = load_image_blocking(input_path)?;
+    let depth_map = estimate_projected(&input_image, output_options.projection, |image| {
+        depth.estimate(image)
+    })?;
+    render_photo(
+        &input_image,
+        &depth_map,
+        output_path,
+        config,
+        output_options,
+        &mut |_, _| {},
+    )
+}
+
+/// Process a single photo into a Looking Glass quilt: load → estimate depth → render views
+///
+/// `config.max_disparity` is not used; the spread of views comes from
+/// `quilt.disparity_range`. Returns the path written (see [`save_quilt`]).
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::{process_photo_quilt, ImageEncoding, QuiltConfig, SpatialConfig};
+/// use std::path::Path;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let written = process_photo_quilt(
+///     Path::new("input.jpg"),
+///     Path::new("output.png"),
+///     SpatialConfig::default(),
+///     &QuiltConfig::default(),
+///     ImageEncoding::PNG,
+/// )
+/// .await?;
+/// println!("Quilt saved to {:?}", written);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn process_photo_quilt(
+    input_path: &Path,
+    output_path: &Path,
+    config: SpatialConfig,
+    quilt: &QuiltConfig,
+    encoding: ImageEncoding,
+) -> SpatialResult<std::path::PathBuf> {
+    tracing::info!("📸 Processing photo into quilt: {:?}", input_path);
+    let input_image = load_image(input_path).await?;
+    let mut estimator = DepthEstimator::load(config.depth_config()).await?;
+
+    let (output_path, quilt) = (output_path.to_path_buf(), quilt.clone());
+    cpu::run(move || {
+        let depth_map = estimator.estimate(&input_image)?;
+        save_quilt(
+            &input_image,
+            &depth_map,
+            &output_path,
+            &quilt,
+            encoding,
+            config.cancel.as_ref(),
+        )
+    })
+    .await
+}
+
+/// Process a single photo into a wiggle animation: load → estimate depth → render views
+///
+/// `config.max_disparity` is not used; the sweep comes from
+/// `wiggle.disparity_range`. The output extension picks GIF or MP4.
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::{process_photo_wiggle, SpatialConfig, WiggleConfig};
+/// use std::path::Path;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// process_photo_wiggle(
+///     Path::new("input.jpg"),
+///     Path::new("wiggle.gif"),
+///     SpatialConfig::default(),
+///     &WiggleConfig::default(),
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn process_photo_wiggle(
+    input_path: &Path,
+    output_path: &Path,
+    config: SpatialConfig,
+    wiggle: &WiggleConfig,
+) -> SpatialResult<()> {
+    tracing::info!("📸 Processing photo into wiggle: {:?}", input_path);
+    let input_image = load_image(input_path).await?;
+    let mut estimator = DepthEstimator::load(config.depth_config()).await?;
+
+    let (output_path, wiggle) = (output_path.to_path_buf(), wiggle.clone());
+    cpu::run(move || {
+        let depth_map = estimator.estimate(&input_image)?;
+        save_wiggle(
+            &input_image,
+            &depth_map,
+            &output_path,
+            &wiggle,
+            config.cancel.as_ref(),
+        )
+    })
+    .await
+}
+
+/// Build a spatial photo from two photos of the same scene: load → align → save
+///
+/// `left_path` and `right_path` are handheld shots taken a few centimeters
+/// apart, left eye first. No depth model is involved; see [`align`] for how
+/// the shots are rectified. EXIF and ICC data are carried over from the left
+/// photo, as in [`process_photo`]. Returns how well the photos lined up.
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::{process_photo_pair, AlignConfig, OutputOptions};
+/// use std::path::Path;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let report = process_photo_pair(
+///     Path::new("left.jpg"),
+///     Path::new("right.jpg"),
+///     Path::new("spatial.jpg"),
+///     &AlignConfig::default(),
+///     OutputOptions::default(),
+/// )
+/// .await?;
+/// println!("{} of {} features agreed", report.inliers, report.matches);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn process_photo_pair(
+    left_path: &Path,
+    right_path: &Path,
+    output_path: &Path,
+    align: &AlignConfig,
+    mut output_options: OutputOptions,
+) -> SpatialResult<AlignmentReport> {
+    tracing::info!(
+        "📸 Processing photo pair: {:?} + {:?}",
+        left_path,
+        right_path
+    );
+    output::validate_options(&output_options)?;
+    carry_source_metadata(left_path, &mut output_options);
+
+    let left = load_image(left_path).await?;
+    let right = load_image(right_path).await?;
+
+    let (output_path, align) = (output_path.to_path_buf(), align.clone());
+    let report = cpu::run(move || {
+        let cancel = output_options.cancel.clone();
+        let pair = align_stereo_pair(&left, &right, &align, cancel.as_ref())?;
+
+        let mvhevc = output_options.mvhevc.take();
+        let attribution = output_options.attribution.clone();
+        let filename_template = output_options.filename_template.clone();
+        let (output_layout, output_encoding) = (output_options.layout, output_options.image_format);
+        save_stereo_image(&pair.left, &pair.right, &output_path, output_options)?;
+
+        if let Some(mvhevc_config) = mvhevc.filter(|c| c.enabled) {
+            output::apply_mvhevc(
+                &pair.left,
+                &pair.right,
+                &output_path,
+                output_layout,
+                output_encoding,
+                &mvhevc_config,
+                &filename_template,
+                attribution.as_ref(),
+                cancel.as_ref(),
+            )?;
+        }
+        Ok(pair.report)
+    })
+    .await?;
+
+    tracing::info!("✅ Photo pair processing complete!");
+    Ok(report)
+}
+
+/// Build a spatial photo from a true stereo capture: load → rectify → save
+///
+/// For left and right photos taken at the same moment, e.g. by a stereo
+/// rig or a phone's spatial camera. [`process_photo_pair`] with
+/// [`AlignConfig::stereo_rig`]: only the roll and vertical offset between
+/// the cameras are corrected, so the captured parallax goes to the output
+/// (and MV-HEVC, when enabled) untouched, with no depth model involved.
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::{process_stereo_capture, OutputOptions};
+/// use std::path::Path;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let report = process_stereo_capture(
+///     Path::new("rig_left.jpg"),
+///     Path::new("rig_right.jpg"),
+///     Path::new("spatial.jpg"),
+///     OutputOptions::default(),
+/// )
+/// .await?;
+/// println!("Vertical error after rectifying: {:.2} px", report.rms_error);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn process_stereo_capture(
+    left_path: &Path,
+    right_path: &Path,
+    output_path: &Path,
+    output_options: OutputOptions,
+) -> SpatialResult<AlignmentReport> {
+    process_photo_pair(
+        left_path,
+        right_path,
+        output_path,
+        &AlignConfig::stereo_rig(),
+        output_options,
+    )
+    .await
+}
+
+/// Process several photos with shared settings and write a run manifest
+///
+/// Each `(input, output)` pair is processed as in [`process_photo`], on
+/// `config.parallel.workers` threads (see [`pool`]) with the depth model
+/// loaded once up front. A failed input is recorded and the batch continues;
+/// a cancellation stops the batch. Inputs are recorded in order whatever
+/// order they finish in. The manifest is written to `manifest_path`, or next
+/// to the first output as `spatial-maker-manifest.json` when `None`.
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::{process_photos, OutputOptions, ParallelConfig, SpatialConfig};
+/// use std::path::PathBuf;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let jobs = vec![
+///     (PathBuf::from("a.jpg"), PathBuf::from("out/a_sbs.jpg")),
+///     (PathBuf::from("b.jpg"), PathBuf::from("out/b_sbs.jpg")),
+/// ];
+/// let config = SpatialConfig::builder()
+///     .parallel(ParallelConfig {
+///         workers: 2,
+///         ..Default::default()
+///     })
+///     .build()?;
+/// let manifest = process_photos(&jobs, config, OutputOptions::default(), None).await?;
+/// println!("{} of {} succeeded", manifest.stats.succeeded, manifest.stats.total);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn process_photos(
+    jobs: &[(std::path::PathBuf, std::path::PathBuf)],
+    config: SpatialConfig,
+    output_options: OutputOptions,
+    manifest_path: Option<&Path>,
+) -> SpatialResult<RunManifest> {
+    // Recording the model hashes the whole file
+    let manifest = {
+        let (config, output_options) = (config.clone(), output_options.clone());
+        cpu::run(move || Ok(RunManifest::new(&config, &output_options))).await?
+    };
+
+    let workers = if jobs.is_empty() {
+        Vec::new()
+    } else {
+        let parallel = &config.parallel;
+        let count = parallel.worker_count().min(jobs.len());
+        pool::depth_workers(&config.depth_config(), parallel, count).await?
+    };
+
+    let manifest_path = match manifest_path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let output_dir = jobs
+                .first()
+                .and_then(|(_, output)| output.parent())
+                .unwrap_or_else(|| Path::new("."));
+            manifest::default_manifest_path(output_dir)
+        }
+    };
+    let jobs = jobs.to_vec();
+    cpu::run(move || {
+        process_photos_blocking(
+            &jobs,
+            workers,
+            &config,
+            &output_options,
+            manifest,
+            &manifest_path,
+        )
+    })
+    .await
+}
+
+/// [`process_photos`] once the depth sessions are loaded: runs the pool and
+/// writes the manifest
+#[cfg(not(target_arch = "wasm32"))]
+fn process_photos_blocking(
+    jobs: &[(std::path::PathBuf, std::path::PathBuf)],
+    workers: Vec<pool::DepthWorker>,
+    config: &SpatialConfig,
+    output_options: &OutputOptions,
+    mut manifest: RunManifest,
+    manifest_path: &Path,
+) -> SpatialResult<RunManifest> {
+    if !workers.is_empty() {
+        let result = pool::run_ordered(
+            workers,
+            config.parallel.queue_depth(),
+            jobs.iter().map(Ok),
+            |depth, (input_path, output_path)| {
+                let started = std::time::Instant::now();
+                let result = process_photo_blocking(
+                    input_path,
+                    output_path,
+                    config,
+                    output_options.clone(),
+                    depth,
+                );
+                Ok((input_path, output_path, result, started.elapsed()))
+            },
+            |(input_path, output_path, result, elapsed)| {
+                if let Err(ref e) = result {
+                    tracing::warn!("Failed to process {:?}: {}", input_path, e);
+                }
+                let outputs = output::produced_paths(output_path, output_options)?;
+                let cancelled = matches!(result, Err(SpatialError::Cancelled));
+                manifest.record(input_path, output_path, outputs, &result, elapsed);
+
+                if cancelled {
+                    return Err(SpatialError::Cancelled);
+                }
+                Ok(())
+            },
+        );
+        match result {
+            Ok(()) | Err(SpatialError::Cancelled) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    manifest.finish();
+    manifest.write(manifest_path)?;
+
+    Ok(manifest)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,9 +1279,78 @@ mod tests {
     #[test]
     fn test_config_defaults() {
         let config = SpatialConfig::default();
-        assert_eq!(config.encoder_size, "s");
+        assert_eq!(config.encoder_size, EncoderSize::Small);
         assert_eq!(config.max_disparity, 30);
-        assert_eq!(config.target_depth_size, 518);
+        assert_eq!(config.target_depth_size, TargetSize::Fixed(518));
         assert!(config.use_coreml);
+        assert_eq!(config.depth_interval, 1);
+        assert_eq!(config.depth_interpolation, DepthInterpolation::Linear);
+        assert!(!config.degrade_on_failure);
+    }
+
+    #[test]
+    fn test_config_builder() {
+        let config = SpatialConfig::builder()
+            .encoder_size(EncoderSize::Large)
+            .target_depth_size(770)
+            .depth_interval(3)
+            .resize_filter(ResizeFilter::CatmullRom)
+            .depth_upsampling(DepthUpsampling::Guided)
+            .build()
+            .unwrap();
+        assert_eq!(config.encoder_size, EncoderSize::Large);
+        assert_eq!(config.target_depth_size, TargetSize::Fixed(770));
+        assert_eq!(config.depth_interval, 3);
+        assert_eq!(config.max_disparity, 30);
+        assert_eq!(config.depth_upsampling, DepthUpsampling::Guided);
+        assert_eq!(
+            config.depth_config().resize_filter,
+            ResizeFilter::CatmullRom
+        );
+
+        assert!(SpatialConfig::builder().depth_interval(0).build().is_err());
+        assert!(SpatialConfig::builder()
+            .target_depth_size(0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_config_rejects_unknown_encoder() {
+        let json = r#"{"encoder_size": "xl", "max_disparity": 30,
+            "target_depth_size": 518, "use_coreml": false}"#;
+        assert!(serde_json::from_str::<SpatialConfig>(json).is_err());
+
+        let json = json.replace("xl", "large");
+        let config: SpatialConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(config.encoder_size, EncoderSize::Large);
+        assert!(!config.degrade_on_failure);
+        assert_eq!(config.resize_filter, ResizeFilter::Lanczos3);
+        assert_eq!(config.depth_upsampling, DepthUpsampling::Nearest);
+    }
+
+    #[test]
+    fn test_stereo_result_keeps_depth_and_size() {
+        let image = image::DynamicImage::new_rgb8(64, 48);
+        let depth = DepthMap::from_normalized(ndarray::Array2::from_elem((48, 64), 0.5));
+        let config = SpatialConfig::default();
+
+        let result = stereo_result(&image, depth, &config, &OutputOptions::default()).unwrap();
+        assert_eq!((result.left.width(), result.left.height()), (64, 48));
+        assert_eq!((result.right.width(), result.right.height()), (64, 48));
+        assert_eq!(result.depth.dim(), (48, 64));
+
+        let vr180 = OutputOptions {
+            projection: Projection::Vr180,
+            ..Default::default()
+        };
+        let result = stereo_result(&image, result.depth, &config, &vr180).unwrap();
+        assert_eq!(result.right.width(), 64);
+
+        // Model-resolution depth comes back at the input's resolution
+        let small = DepthMap::from_normalized(ndarray::Array2::from_elem((12, 16), 0.5));
+        let result = stereo_result(&image, small, &config, &OutputOptions::default()).unwrap();
+        assert_eq!(result.depth.dim(), (48, 64));
+        assert_eq!(result.depth.inference_size, (16, 12));
     }
 }
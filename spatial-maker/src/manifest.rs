@@ -0,0 +1,435 @@
+//! Run manifests for batch processing
+//!
+//! A [`RunManifest`] records what a batch run produced so downstream
+//! automation can verify and index the results:
+//! - The processing configuration and output options
+//! - The depth model used (name, path, size, SHA-256)
+//! - Per-input outputs, status, errors, and timings
+//! - Aggregate statistics for the run
+//!
+//! [`process_photos`](crate::process_photos) builds and writes one automatically.
+//! [`process_video_dir`](crate::video::process_video_dir) rewrites its
+//! manifest after every video and reads it back on the next run, carrying
+//! over the videos that already finished with the same settings.
+
+use crate::checkpoint::settings_fingerprint;
+use crate::error::{SpatialError, SpatialResult};
+use crate::model::EncoderSize;
+use crate::output::{OutputOptions, Projection};
+use crate::SpatialConfig;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Manifest schema version, bumped on incompatible changes
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// File name used when no manifest path is given
+pub const DEFAULT_MANIFEST_NAME: &str = "spatial-maker-manifest.json";
+
+/// Record of a batch run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// Manifest schema version
+    pub manifest_version: u32,
+    /// spatial-maker crate version that produced the run
+    pub tool_version: String,
+    /// Run start time (seconds since the Unix epoch)
+    pub started_at: u64,
+    /// Run end time (seconds since the Unix epoch), set by [`RunManifest::finish`]
+    pub finished_at: Option<u64>,
+    /// Processing configuration
+    pub config: SpatialConfig,
+    /// Fingerprint of the settings that affect output (see
+    /// [`settings_fingerprint`]), used to decide whether a later run can
+    /// reuse these results
+    #[serde(default)]
+    pub settings: Option<String>,
+    /// Output layout and encoding
+    pub output: ManifestOutput,
+    /// Depth model used, if it could be located
+    pub model: Option<ModelInfo>,
+    /// One entry per processed input, in processing order
+    pub entries: Vec<ManifestEntry>,
+    /// Aggregate statistics
+    pub stats: RunStats,
+}
+
+/// Output settings recorded in the manifest
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestOutput {
+    /// Stereo layout name (e.g. "side-by-side")
+    pub layout: String,
+    /// Image file extension ("jpg" or "png")
+    pub image_format: String,
+    /// Whether MV-HEVC encoding was enabled
+    pub mvhevc: bool,
+    /// Whether each eye was squeezed to half size (half-SBS / half-TB)
+    #[serde(default)]
+    pub half_resolution: bool,
+    /// Whether the output was written as VR180 equirectangular stereo
+    #[serde(default)]
+    pub vr180: bool,
+    /// Whether the output was written as a 360° over/under panorama
+    #[serde(default)]
+    pub panorama: bool,
+}
+
+/// Identity of the depth model used for a run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// Encoder size
+    pub encoder_size: EncoderSize,
+    /// Model name (e.g. "depth-anything-v2-small")
+    pub name: String,
+    /// Path to the ONNX file
+    pub path: PathBuf,
+    /// File size in bytes
+    pub size_bytes: u64,
+    /// Hex-encoded SHA-256 of the ONNX file
+    pub sha256: String,
+}
+
+/// Outcome of processing a single input
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// Result of processing a single input
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Input file path
+    pub input: PathBuf,
+    /// Requested output path
+    pub output: PathBuf,
+    /// Files actually present on disk after processing
+    pub outputs: Vec<PathBuf>,
+    /// Outcome
+    pub status: EntryStatus,
+    /// Error message for failed entries
+    pub error: Option<String>,
+    /// Wall-clock processing time in milliseconds
+    pub duration_ms: u64,
+    /// Input file size in bytes
+    pub input_bytes: u64,
+    /// Total size of the produced files in bytes
+    pub output_bytes: u64,
+}
+
+/// Aggregate statistics for a run
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    /// Succeeded entries carried over from an earlier run instead of being
+    /// processed again
+    #[serde(default)]
+    pub resumed: usize,
+    /// Sum of per-entry processing times in milliseconds
+    pub total_duration_ms: u64,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+}
+
+impl ModelInfo {
+    /// Describe the downloaded model for an encoder size, hashing its file
+    pub fn for_encoder(encoder_size: EncoderSize) -> SpatialResult<Self> {
+        let metadata = crate::model::ModelMetadata::for_encoder(encoder_size);
+        let path = crate::model::find_model(encoder_size)?;
+        let size_bytes = std::fs::metadata(&path)?.len();
+
+        Ok(Self {
+            encoder_size,
+            name: metadata.name,
+            sha256: sha256_file(&path)?,
+            path,
+            size_bytes,
+        })
+    }
+}
+
+impl RunManifest {
+    /// Start a manifest for a run with the given settings
+    ///
+    /// The model is looked up and hashed immediately; if it is not downloaded
+    /// yet, `model` is left empty.
+    pub fn new(config: &SpatialConfig, options: &OutputOptions) -> Self {
+        let model = match ModelInfo::for_encoder(config.encoder_size) {
+            Ok(info) => Some(info),
+            Err(e) => {
+                tracing::debug!("Model not recorded in manifest: {}", e);
+                None
+            }
+        };
+
+        Self {
+            manifest_version: MANIFEST_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: unix_now(),
+            finished_at: None,
+            config: config.clone(),
+            settings: Some(settings_fingerprint(config, options)),
+            output: ManifestOutput {
+                layout: options.layout.name().to_string(),
+                image_format: options.image_format.extension().to_string(),
+                mvhevc: options.mvhevc.as_ref().is_some_and(|c| c.enabled),
+                half_resolution: options.half_resolution,
+                vr180: options.projection == Projection::Vr180,
+                panorama: options.projection == Projection::Panorama360,
+            },
+            model,
+            entries: Vec::new(),
+            stats: RunStats::default(),
+        }
+    }
+
+    /// Record the outcome of one input and update the statistics
+    ///
+    /// `outputs` lists candidate output files; only those present on disk are kept.
+    pub fn record(
+        &mut self,
+        input: &Path,
+        output: &Path,
+        outputs: Vec<PathBuf>,
+        result: &SpatialResult<()>,
+        duration: Duration,
+    ) {
+        let status = match result {
+            Ok(()) => EntryStatus::Succeeded,
+            Err(SpatialError::Cancelled) => EntryStatus::Cancelled,
+            Err(_) => EntryStatus::Failed,
+        };
+        let outputs: Vec<PathBuf> = outputs.into_iter().filter(|p| p.is_file()).collect();
+        let output_bytes = outputs.iter().map(|p| file_size(p)).sum();
+
+        self.push(ManifestEntry {
+            input: input.to_path_buf(),
+            output: output.to_path_buf(),
+            outputs,
+            status,
+            error: result.as_ref().err().map(|e| e.to_string()),
+            duration_ms: duration.as_millis() as u64,
+            input_bytes: file_size(input),
+            output_bytes,
+        });
+    }
+
+    /// Find an earlier success for `input` writing `output` that can be
+    /// reused as is
+    ///
+    /// Only matches when the input is still the same size and every output
+    /// the entry lists is still on disk. Check [`RunManifest::settings`]
+    /// first; this does not compare settings.
+    pub fn completed(&self, input: &Path, output: &Path) -> Option<&ManifestEntry> {
+        self.entries.iter().rev().find(|entry| {
+            entry.status == EntryStatus::Succeeded
+                && entry.input == input
+                && entry.output == output
+                && entry.input_bytes == file_size(input)
+                && !entry.outputs.is_empty()
+                && entry.outputs.iter().all(|path| path.is_file())
+        })
+    }
+
+    /// Carry over an entry from an earlier run's manifest
+    pub fn resume(&mut self, entry: ManifestEntry) {
+        self.stats.resumed += 1;
+        self.push(entry);
+    }
+
+    fn push(&mut self, entry: ManifestEntry) {
+        self.stats.total += 1;
+        match entry.status {
+            EntryStatus::Succeeded => self.stats.succeeded += 1,
+            EntryStatus::Failed => self.stats.failed += 1,
+            EntryStatus::Cancelled => self.stats.cancelled += 1,
+        }
+        self.stats.total_duration_ms += entry.duration_ms;
+        self.stats.input_bytes += entry.input_bytes;
+        self.stats.output_bytes += entry.output_bytes;
+        self.entries.push(entry);
+    }
+
+    /// Mark the run as finished
+    pub fn finish(&mut self) {
+        self.finished_at = Some(unix_now());
+    }
+
+    /// Write the manifest as pretty-printed JSON, creating parent directories
+    ///
+    /// The file is replaced atomically, so a crash mid-write leaves the
+    /// previous manifest intact.
+    pub fn write(&self, path: impl AsRef<Path>) -> SpatialResult<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| SpatialError::io("Failed to serialize manifest", e.into()))?;
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, json)
+            .map_err(|e| SpatialError::io(format!("Failed to write {:?}", temp_path), e))?;
+        std::fs::rename(&temp_path, path)
+            .map_err(|e| SpatialError::io(format!("Failed to write {:?}", path), e))?;
+        tracing::info!("📝 Run manifest written to {:?}", path);
+        Ok(())
+    }
+
+    /// Read a manifest previously written with [`RunManifest::write`]
+    pub fn read(path: impl AsRef<Path>) -> SpatialResult<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| SpatialError::io(format!("Failed to read {:?}", path), e))?;
+        serde_json::from_str(&json)
+            .map_err(|e| SpatialError::io(format!("Invalid manifest {:?}", path), e.into()))
+    }
+}
+
+/// Default manifest location for a run writing into `output_dir`
+pub fn default_manifest_path(output_dir: &Path) -> PathBuf {
+    output_dir.join(DEFAULT_MANIFEST_NAME)
+}
+
+pub(crate) fn sha256_file(path: &Path) -> SpatialResult<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_record_and_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input = temp_dir.path().join("in.jpg");
+        let output = temp_dir.path().join("out.jpg");
+        std::fs::write(&input, b"input").unwrap();
+        std::fs::write(&output, b"output!").unwrap();
+
+        let config = SpatialConfig::default();
+        let mut manifest = RunManifest::new(&config, &OutputOptions::default());
+        assert_eq!(
+            manifest.model.is_some(),
+            crate::model::model_exists(config.encoder_size)
+        );
+
+        manifest.record(
+            &input,
+            &output,
+            vec![output.clone(), temp_dir.path().join("missing.jpg")],
+            &Ok(()),
+            Duration::from_millis(12),
+        );
+        manifest.record(
+            &input,
+            &output,
+            vec![],
+            &Err(SpatialError::ConfigError("bad".to_string())),
+            Duration::from_millis(3),
+        );
+        manifest.record(
+            &input,
+            &output,
+            vec![],
+            &Err(SpatialError::Cancelled),
+            Duration::ZERO,
+        );
+        manifest.finish();
+
+        assert_eq!(manifest.entries[0].outputs, vec![output.clone()]);
+        assert_eq!(manifest.entries[0].output_bytes, 7);
+        assert_eq!(manifest.entries[1].status, EntryStatus::Failed);
+        assert!(manifest.entries[1].error.is_some());
+        assert_eq!(manifest.stats.succeeded, 1);
+        assert_eq!(manifest.stats.failed, 1);
+        assert_eq!(manifest.stats.cancelled, 1);
+        assert_eq!(manifest.stats.total_duration_ms, 15);
+
+        let path = default_manifest_path(temp_dir.path());
+        manifest.write(&path).unwrap();
+        let read = RunManifest::read(&path).unwrap();
+        assert_eq!(read.manifest_version, MANIFEST_VERSION);
+        assert_eq!(read.stats.total, 3);
+        assert_eq!(read.output.layout, "side-by-side");
+        assert!(read.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_completed_entries_resume() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let input = temp_dir.path().join("in.mp4");
+        let output = temp_dir.path().join("out.mp4");
+        std::fs::write(&input, b"input").unwrap();
+        std::fs::write(&output, b"output").unwrap();
+
+        let (config, options) = (SpatialConfig::default(), OutputOptions::default());
+        let mut previous = RunManifest::new(&config, &options);
+        previous.record(
+            &input,
+            &output,
+            vec![output.clone()],
+            &Ok(()),
+            Duration::ZERO,
+        );
+        assert_eq!(
+            previous.settings,
+            Some(settings_fingerprint(&config, &options))
+        );
+
+        let entry = previous.completed(&input, &output).unwrap().clone();
+        let mut manifest = RunManifest::new(&config, &options);
+        manifest.resume(entry);
+        assert_eq!((manifest.stats.succeeded, manifest.stats.resumed), (1, 1));
+
+        // A changed input or a missing output is converted again
+        std::fs::write(&input, b"edited input").unwrap();
+        assert!(previous.completed(&input, &output).is_none());
+        std::fs::write(&input, b"input").unwrap();
+        std::fs::remove_file(&output).unwrap();
+        assert!(previous.completed(&input, &output).is_none());
+    }
+}
@@ -0,0 +1,830 @@
+//! EXIF and ICC metadata handling
+//!
+//! This module handles:
+//! - Reading the raw EXIF block from input images
+//! - Applying the EXIF Orientation tag so rotated phone photos are processed upright
+//! - Embedding EXIF into encoded JPEG output
+//! - Reading ICC color profiles and embedding them in JPEG and PNG output
+//! - Embedding XMP packets (e.g. VR180 panorama metadata) in JPEG and PNG output
+//! - Writing creator/copyright/software credits ([`Attribution`]) to EXIF,
+//!   XMP, and ffmpeg container metadata
+
+use image::{DynamicImage, ImageDecoder, ImageFormat};
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+/// EXIF Orientation tag ID
+#[cfg(not(target_arch = "wasm32"))]
+const TAG_ORIENTATION: u16 = 0x0112;
+
+/// TIFF SHORT field type
+#[cfg(not(target_arch = "wasm32"))]
+const TYPE_SHORT: u16 = 3;
+
+/// TIFF ASCII field type
+const TYPE_ASCII: u16 = 2;
+
+/// EXIF Software, Artist, and Copyright tag IDs
+const TAG_SOFTWARE: u16 = 0x0131;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_COPYRIGHT: u16 = 0x8298;
+
+/// Maximum payload of a JPEG APP1 segment (65535 minus length bytes and "Exif\0\0")
+const MAX_APP1_EXIF_LEN: usize = 65535 - 2 - 6;
+
+/// JPEG APP2 identifier for ICC profile chunks
+const ICC_MARKER: &[u8] = b"ICC_PROFILE\0";
+
+/// Maximum ICC payload per APP2 segment (minus length bytes, identifier, and sequence bytes)
+const MAX_APP2_ICC_LEN: usize = 65535 - 2 - 12 - 2;
+
+/// JPEG APP1 identifier for XMP packets
+const XMP_MARKER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Maximum XMP payload per APP1 segment (minus length bytes and identifier)
+const MAX_APP1_XMP_LEN: usize = 65535 - 2 - 29;
+
+/// Creator, copyright, and software credits for generated media
+///
+/// Written to EXIF (JPEG), XMP (JPEG and PNG), and container metadata for
+/// media encoded with ffmpeg, so attribution survives on derivative spatial
+/// photos and videos.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribution {
+    /// Author of the original work (EXIF Artist, XMP dc:creator)
+    pub creator: Option<String>,
+
+    /// Copyright notice (EXIF Copyright, XMP dc:rights)
+    pub copyright: Option<String>,
+
+    /// Creating software (EXIF Software, XMP xmp:CreatorTool); defaults to
+    /// this library and its version
+    pub software: Option<String>,
+}
+
+impl Default for Attribution {
+    fn default() -> Self {
+        Self {
+            creator: None,
+            copyright: None,
+            software: Some(format!("spatial-maker {}", env!("CARGO_PKG_VERSION"))),
+        }
+    }
+}
+
+impl Attribution {
+    /// ffmpeg `-metadata` arguments carrying the credits into a container
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        [
+            ("artist", &self.creator),
+            ("copyright", &self.copyright),
+            ("encoder", &self.software),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.as_ref().map(|v| (key, v)))
+        .flat_map(|(key, value)| ["-metadata".to_string(), format!("{}={}", key, value)])
+        .collect()
+    }
+}
+
+/// Read the raw EXIF block (TIFF structure, without the "Exif\0\0" header) from a file
+///
+/// Returns `None` if the file has no EXIF or its container is not supported.
+pub fn read_exif(path: impl AsRef<Path>) -> Option<Vec<u8>> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path).ok()?;
+    let mut reader = std::io::BufReader::new(file);
+
+    match exif::Reader::new().read_from_container(&mut reader) {
+        Ok(exif) => Some(exif.buf().to_vec()),
+        Err(e) => {
+            tracing::debug!("No EXIF read from {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Get the Orientation value (1-8) from a raw EXIF block
+pub fn exif_orientation(raw: &[u8]) -> Option<u32> {
+    let exif = exif::Reader::new().read_raw(raw.to_vec()).ok()?;
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+}
+
+/// Rotate/flip an image according to an EXIF Orientation value
+///
+/// Values outside 2-8 (including the default 1) leave the image unchanged.
+pub fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Set the Orientation tag in a raw EXIF block to 1 (upright)
+///
+/// Used after pixels have been rotated so viewers don't rotate them again.
+/// Returns `true` if an Orientation entry was found and rewritten.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn reset_orientation(raw: &mut [u8]) -> bool {
+    let big_endian = match raw.get(0..2) {
+        Some(b"MM") => true,
+        Some(b"II") => false,
+        _ => return false,
+    };
+
+    let read_u16 = |buf: &[u8], at: usize| -> Option<u16> {
+        let bytes: [u8; 2] = buf.get(at..at + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let read_u32 = |buf: &[u8], at: usize| -> Option<u32> {
+        let bytes: [u8; 4] = buf.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+
+    let Some(ifd_offset) = read_u32(raw, 4).map(|o| o as usize) else {
+        return false;
+    };
+    let Some(entry_count) = read_u16(raw, ifd_offset) else {
+        return false;
+    };
+
+    for i in 0..entry_count as usize {
+        let entry = ifd_offset + 2 + i * 12;
+        if read_u16(raw, entry) == Some(TAG_ORIENTATION)
+            && read_u16(raw, entry + 2) == Some(TYPE_SHORT)
+        {
+            let value = if big_endian {
+                1u16.to_be_bytes()
+            } else {
+                1u16.to_le_bytes()
+            };
+            if let Some(slot) = raw.get_mut(entry + 8..entry + 10) {
+                slot.copy_from_slice(&value);
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// An EXIF block with an empty primary IFD, to add tags to
+pub(crate) fn empty_exif() -> Vec<u8> {
+    let mut raw = b"II*\0".to_vec();
+    raw.extend_from_slice(&8u32.to_le_bytes());
+    raw.extend_from_slice(&0u16.to_le_bytes());
+    raw.extend_from_slice(&0u32.to_le_bytes());
+    raw
+}
+
+/// Set the Artist, Copyright, and Software tags of a raw EXIF block
+///
+/// The primary IFD is copied to the end of the block with the credits added
+/// (replacing any existing values), so offsets used by the other entries
+/// stay valid. Returns `None` if the block is not valid TIFF.
+pub(crate) fn set_exif_attribution(raw: &[u8], attribution: &Attribution) -> Option<Vec<u8>> {
+    let big_endian = match raw.get(0..2) {
+        Some(b"MM") => true,
+        Some(b"II") => false,
+        _ => return None,
+    };
+    let read_u16 = |at: usize| -> Option<u16> {
+        let bytes: [u8; 2] = raw.get(at..at + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let read_u32 = |at: usize| -> Option<u32> {
+        let bytes: [u8; 4] = raw.get(at..at + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    let u16_bytes = |v: u16| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let u32_bytes = |v: u32| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+
+    let ifd_offset = read_u32(4)? as usize;
+    let entry_count = read_u16(ifd_offset)? as usize;
+    let next_ifd = read_u32(ifd_offset + 2 + entry_count * 12)?;
+
+    let credits: Vec<(u16, Vec<u8>)> = [
+        (TAG_SOFTWARE, &attribution.software),
+        (TAG_ARTIST, &attribution.creator),
+        (TAG_COPYRIGHT, &attribution.copyright),
+    ]
+    .into_iter()
+    .filter_map(|(tag, value)| {
+        let mut bytes = value.as_ref()?.as_bytes().to_vec();
+        bytes.push(0);
+        Some((tag, bytes))
+    })
+    .collect();
+
+    // Existing entries, minus the tags being replaced
+    let mut entries: Vec<(u16, [u8; 12])> = (0..entry_count)
+        .filter_map(|i| {
+            let at = ifd_offset + 2 + i * 12;
+            let tag = read_u16(at)?;
+            let entry: [u8; 12] = raw.get(at..at + 12)?.try_into().ok()?;
+            Some((tag, entry))
+        })
+        .filter(|(tag, _)| !credits.iter().any(|(t, _)| t == tag))
+        .collect();
+
+    let mut out = raw.to_vec();
+    if out.len() % 2 == 1 {
+        out.push(0);
+    }
+    let new_ifd_offset = out.len();
+    let total = entries.len() + credits.len();
+    let mut data_offset = new_ifd_offset + 2 + total * 12 + 4;
+    let mut values = Vec::new();
+
+    for (tag, bytes) in &credits {
+        let mut entry = [0u8; 12];
+        entry[0..2].copy_from_slice(&u16_bytes(*tag));
+        entry[2..4].copy_from_slice(&u16_bytes(TYPE_ASCII));
+        entry[4..8].copy_from_slice(&u32_bytes(bytes.len() as u32));
+        if bytes.len() <= 4 {
+            entry[8..8 + bytes.len()].copy_from_slice(bytes);
+        } else {
+            entry[8..12].copy_from_slice(&u32_bytes(data_offset as u32));
+            values.extend_from_slice(bytes);
+            if bytes.len() % 2 == 1 {
+                values.push(0);
+            }
+            data_offset = new_ifd_offset + 2 + total * 12 + 4 + values.len();
+        }
+        entries.push((*tag, entry));
+    }
+    // TIFF requires ascending tag order
+    entries.sort_by_key(|(tag, _)| *tag);
+
+    out.extend_from_slice(&u16_bytes(total as u16));
+    for (_, entry) in &entries {
+        out.extend_from_slice(entry);
+    }
+    out.extend_from_slice(&u32_bytes(next_ifd));
+    out.extend_from_slice(&values);
+    out[4..8].copy_from_slice(&u32_bytes(new_ifd_offset as u32));
+    Some(out)
+}
+
+/// Insert an EXIF APP1 segment into an encoded JPEG
+///
+/// The segment is placed after SOI and any JFIF APP0 segment. If the data is
+/// not a JPEG or the EXIF block is too large for a single APP1 segment, the
+/// input is returned unchanged.
+pub(crate) fn embed_exif_in_jpeg(jpeg: &[u8], exif: &[u8]) -> Vec<u8> {
+    if jpeg.len() < 4 || jpeg[0..2] != [0xFF, 0xD8] {
+        return jpeg.to_vec();
+    }
+    if exif.len() > MAX_APP1_EXIF_LEN {
+        tracing::warn!(
+            "EXIF block too large to embed ({} bytes), skipping",
+            exif.len()
+        );
+        return jpeg.to_vec();
+    }
+
+    // Skip over a leading JFIF APP0 segment if the encoder wrote one
+    let mut insert_at = 2;
+    if jpeg[2..4] == [0xFF, 0xE0] && jpeg.len() >= 6 {
+        let app0_len = u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+        insert_at = (4 + app0_len).min(jpeg.len());
+    }
+
+    let segment_len = (2 + 6 + exif.len()) as u16;
+    let mut out = Vec::with_capacity(jpeg.len() + exif.len() + 10);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&segment_len.to_be_bytes());
+    out.extend_from_slice(b"Exif\0\0");
+    out.extend_from_slice(exif);
+    out.extend_from_slice(&jpeg[insert_at..]);
+    out
+}
+
+/// Read the embedded ICC color profile from a JPEG, PNG, WebP, or TIFF file
+///
+/// Returns `None` if the file has no profile or its format is not supported.
+pub fn read_icc_profile(path: impl AsRef<Path>) -> Option<Vec<u8>> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path).ok()?;
+    let reader = BufReader::new(std::fs::File::open(path).ok()?);
+
+    let profile = match format {
+        ImageFormat::Jpeg => image::codecs::jpeg::JpegDecoder::new(reader)
+            .ok()?
+            .icc_profile(),
+        ImageFormat::Png => image::codecs::png::PngDecoder::new(reader)
+            .ok()?
+            .icc_profile(),
+        ImageFormat::WebP => image::codecs::webp::WebPDecoder::new(reader)
+            .ok()?
+            .icc_profile(),
+        ImageFormat::Tiff => image::codecs::tiff::TiffDecoder::new(reader)
+            .ok()?
+            .icc_profile(),
+        _ => None,
+    };
+
+    profile.filter(|p| !p.is_empty())
+}
+
+/// Insert ICC profile APP2 segments into an encoded JPEG
+///
+/// Profiles larger than one segment are split across numbered chunks as the
+/// ICC spec requires. Non-JPEG input is returned unchanged.
+pub(crate) fn embed_icc_in_jpeg(jpeg: &[u8], icc: &[u8]) -> Vec<u8> {
+    if jpeg.len() < 4 || jpeg[0..2] != [0xFF, 0xD8] || icc.is_empty() {
+        return jpeg.to_vec();
+    }
+
+    let chunks: Vec<&[u8]> = icc.chunks(MAX_APP2_ICC_LEN).collect();
+    if chunks.len() > u8::MAX as usize {
+        tracing::warn!(
+            "ICC profile too large to embed ({} bytes), skipping",
+            icc.len()
+        );
+        return jpeg.to_vec();
+    }
+
+    // Keep SOI and any APP0/APP1 (JFIF, EXIF) segments ahead of the profile
+    let mut insert_at = 2;
+    while jpeg.len() >= insert_at + 4
+        && jpeg[insert_at] == 0xFF
+        && matches!(jpeg[insert_at + 1], 0xE0 | 0xE1)
+    {
+        let len = u16::from_be_bytes([jpeg[insert_at + 2], jpeg[insert_at + 3]]) as usize;
+        insert_at = (insert_at + 2 + len).min(jpeg.len());
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + icc.len() + chunks.len() * 18);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let segment_len = (2 + ICC_MARKER.len() + 2 + chunk.len()) as u16;
+        out.extend_from_slice(&[0xFF, 0xE2]);
+        out.extend_from_slice(&segment_len.to_be_bytes());
+        out.extend_from_slice(ICC_MARKER);
+        out.push(i as u8 + 1);
+        out.push(chunks.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&jpeg[insert_at..]);
+    out
+}
+
+/// Insert an iCCP chunk after the IHDR chunk of an encoded PNG
+///
+/// Non-PNG input is returned unchanged.
+pub(crate) fn embed_icc_in_png(png: &[u8], icc: &[u8]) -> Vec<u8> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    // Signature (8) + IHDR length/type/data/CRC (4 + 4 + 13 + 4)
+    const IHDR_END: usize = 8 + 25;
+
+    if png.len() < IHDR_END || &png[..8] != SIGNATURE || &png[12..16] != b"IHDR" || icc.is_empty() {
+        return png.to_vec();
+    }
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    let compressed = match encoder.write_all(icc).and_then(|_| encoder.finish()) {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("Failed to compress ICC profile: {}", e);
+            return png.to_vec();
+        }
+    };
+
+    // Profile name, null separator, compression method 0 (zlib)
+    let mut data = b"ICC Profile\0\0".to_vec();
+    data.extend_from_slice(&compressed);
+
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(b"iCCP");
+    crc.update(&data);
+
+    let mut out = Vec::with_capacity(png.len() + data.len() + 12);
+    out.extend_from_slice(&png[..IHDR_END]);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(b"iCCP");
+    out.extend_from_slice(&data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+    out.extend_from_slice(&png[IHDR_END..]);
+    out
+}
+
+/// Insert an XMP APP1 segment into an encoded JPEG
+///
+/// The segment follows SOI and any APP0/APP1 (JFIF, EXIF) segments. Non-JPEG
+/// input, or a packet too large for one segment, is returned unchanged.
+pub(crate) fn embed_xmp_in_jpeg(jpeg: &[u8], xmp: &str) -> Vec<u8> {
+    if jpeg.len() < 4 || jpeg[0..2] != [0xFF, 0xD8] || xmp.is_empty() {
+        return jpeg.to_vec();
+    }
+    if xmp.len() > MAX_APP1_XMP_LEN {
+        tracing::warn!(
+            "XMP packet too large to embed ({} bytes), skipping",
+            xmp.len()
+        );
+        return jpeg.to_vec();
+    }
+
+    let mut insert_at = 2;
+    while jpeg.len() >= insert_at + 4
+        && jpeg[insert_at] == 0xFF
+        && matches!(jpeg[insert_at + 1], 0xE0 | 0xE1)
+    {
+        let len = u16::from_be_bytes([jpeg[insert_at + 2], jpeg[insert_at + 3]]) as usize;
+        insert_at = (insert_at + 2 + len).min(jpeg.len());
+    }
+
+    let segment_len = (2 + XMP_MARKER.len() + xmp.len()) as u16;
+    let mut out = Vec::with_capacity(jpeg.len() + xmp.len() + 33);
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&segment_len.to_be_bytes());
+    out.extend_from_slice(XMP_MARKER);
+    out.extend_from_slice(xmp.as_bytes());
+    out.extend_from_slice(&jpeg[insert_at..]);
+    out
+}
+
+/// Insert an XMP iTXt chunk after the IHDR chunk of an encoded PNG
+///
+/// Non-PNG input is returned unchanged.
+pub(crate) fn embed_xmp_in_png(png: &[u8], xmp: &str) -> Vec<u8> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    const IHDR_END: usize = 8 + 25;
+
+    if png.len() < IHDR_END || &png[..8] != SIGNATURE || &png[12..16] != b"IHDR" || xmp.is_empty() {
+        return png.to_vec();
+    }
+
+    // Keyword, null, uncompressed flag and method, empty language tag and
+    // translated keyword, then the packet
+    let mut data = b"XML:com.adobe.xmp\0\0\0\0\0".to_vec();
+    data.extend_from_slice(xmp.as_bytes());
+
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(b"iTXt");
+    crc.update(&data);
+
+    let mut out = Vec::with_capacity(png.len() + data.len() + 12);
+    out.extend_from_slice(&png[..IHDR_END]);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(b"iTXt");
+    out.extend_from_slice(&data);
+    out.extend_from_slice(&crc.finalize().to_be_bytes());
+    out.extend_from_slice(&png[IHDR_END..]);
+    out
+}
+
+/// Wrap XMP `rdf:Description` elements in a complete packet
+pub(crate) fn xmp_packet(descriptions: &[String]) -> String {
+    format!(
+        concat!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">",
+            "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "{}",
+            "</rdf:RDF></x:xmpmeta>"
+        ),
+        descriptions.concat()
+    )
+}
+
+/// Google Photo Sphere (GPano) XMP description marking one eye of a VR180
+/// image
+///
+/// The eye is described as a 180°-wide crop of a full equirectangular
+/// panorama at the same angular resolution, centred vertically.
+pub(crate) fn vr180_gpano_description(eye_width: u32, eye_height: u32) -> String {
+    let full_width = eye_width * 2;
+    let full_height = eye_width.max(eye_height);
+    let left = eye_width / 2;
+    let top = (full_height - eye_height) / 2;
+
+    format!(
+        concat!(
+            "<rdf:Description rdf:about=\"\" xmlns:GPano=\"http://ns.google.com/photos/1.0/panorama/\"",
+            " GPano:ProjectionType=\"equirectangular\"",
+            " GPano:UsePanoramaViewer=\"True\"",
+            " GPano:CroppedAreaImageWidthPixels=\"{}\"",
+            " GPano:CroppedAreaImageHeightPixels=\"{}\"",
+            " GPano:FullPanoWidthPixels=\"{}\"",
+            " GPano:FullPanoHeightPixels=\"{}\"",
+            " GPano:CroppedAreaLeftPixels=\"{}\"",
+            " GPano:CroppedAreaTopPixels=\"{}\"",
+            "/>"
+        ),
+        eye_width, eye_height, full_width, full_height, left, top
+    )
+}
+
+/// GPano XMP description marking one eye of a 360° panorama, which covers
+/// the whole sphere
+pub(crate) fn panorama_gpano_description(eye_width: u32, eye_height: u32) -> String {
+    format!(
+        concat!(
+            "<rdf:Description rdf:about=\"\" xmlns:GPano=\"http://ns.google.com/photos/1.0/panorama/\"",
+            " GPano:ProjectionType=\"equirectangular\"",
+            " GPano:UsePanoramaViewer=\"True\"",
+            " GPano:CroppedAreaImageWidthPixels=\"{0}\"",
+            " GPano:CroppedAreaImageHeightPixels=\"{1}\"",
+            " GPano:FullPanoWidthPixels=\"{0}\"",
+            " GPano:FullPanoHeightPixels=\"{1}\"",
+            " GPano:CroppedAreaLeftPixels=\"0\"",
+            " GPano:CroppedAreaTopPixels=\"0\"",
+            "/>"
+        ),
+        eye_width, eye_height
+    )
+}
+
+/// Dublin Core and XMP basic description carrying [`Attribution`] credits
+pub(crate) fn attribution_xmp_description(attribution: &Attribution) -> String {
+    let mut xmp = String::from(concat!(
+        "<rdf:Description rdf:about=\"\"",
+        " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+        " xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">"
+    ));
+    if let Some(creator) = &attribution.creator {
+        xmp.push_str(&format!(
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+            xml_escape(creator)
+        ));
+    }
+    if let Some(copyright) = &attribution.copyright {
+        xmp.push_str(&format!(
+            "<dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:rights>",
+            xml_escape(copyright)
+        ));
+    }
+    if let Some(software) = &attribution.software {
+        xmp.push_str(&format!(
+            "<xmp:CreatorTool>{}</xmp:CreatorTool>",
+            xml_escape(software)
+        ));
+    }
+    xmp.push_str("</rdf:Description>");
+    xmp
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal little-endian TIFF block with a single Orientation entry
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(b"II");
+        raw.extend_from_slice(&42u16.to_le_bytes());
+        raw.extend_from_slice(&8u32.to_le_bytes());
+        raw.extend_from_slice(&1u16.to_le_bytes());
+        raw.extend_from_slice(&TAG_ORIENTATION.to_le_bytes());
+        raw.extend_from_slice(&TYPE_SHORT.to_le_bytes());
+        raw.extend_from_slice(&1u32.to_le_bytes());
+        raw.extend_from_slice(&orientation.to_le_bytes());
+        raw.extend_from_slice(&[0, 0]);
+        raw.extend_from_slice(&0u32.to_le_bytes());
+        raw
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        assert_eq!(exif_orientation(&exif_with_orientation(6)), Some(6));
+        assert_eq!(exif_orientation(b"not exif"), None);
+    }
+
+    #[test]
+    fn test_reset_orientation() {
+        let mut raw = exif_with_orientation(8);
+        assert!(reset_orientation(&mut raw));
+        assert_eq!(exif_orientation(&raw), Some(1));
+        assert!(!reset_orientation(&mut [0u8; 4]));
+    }
+
+    #[test]
+    fn test_apply_orientation_dimensions() {
+        let img = DynamicImage::new_rgb8(40, 20);
+        assert_eq!(apply_orientation(img.clone(), 1).width(), 40);
+        assert_eq!(apply_orientation(img.clone(), 3).width(), 40);
+        assert_eq!(apply_orientation(img.clone(), 6).width(), 20);
+        assert_eq!(apply_orientation(img, 8).height(), 40);
+    }
+
+    #[test]
+    fn test_embed_exif_in_jpeg_roundtrip() {
+        let img = DynamicImage::new_rgb8(8, 8);
+        let mut jpeg = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut jpeg),
+            image::ImageOutputFormat::Jpeg(90),
+        )
+        .unwrap();
+
+        let with_exif = embed_exif_in_jpeg(&jpeg, &exif_with_orientation(3));
+        assert!(image::load_from_memory(&with_exif).is_ok());
+
+        let exif = exif::Reader::new()
+            .read_from_container(&mut std::io::Cursor::new(&with_exif))
+            .unwrap();
+        let orientation = exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0));
+        assert_eq!(orientation, Some(3));
+    }
+
+    #[test]
+    fn test_embed_exif_ignores_non_jpeg() {
+        let data = b"PNG data".to_vec();
+        assert_eq!(embed_exif_in_jpeg(&data, &exif_with_orientation(1)), data);
+    }
+
+    #[test]
+    fn test_embed_icc_in_jpeg_roundtrip() {
+        let img = DynamicImage::new_rgb8(8, 8);
+        let mut jpeg = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut jpeg),
+            image::ImageOutputFormat::Jpeg(90),
+        )
+        .unwrap();
+
+        // Large enough to need two APP2 segments
+        let icc: Vec<u8> = (0..MAX_APP2_ICC_LEN + 100).map(|i| i as u8).collect();
+        let with_icc = embed_icc_in_jpeg(&jpeg, &icc);
+
+        let mut decoder =
+            image::codecs::jpeg::JpegDecoder::new(std::io::Cursor::new(&with_icc)).unwrap();
+        assert_eq!(decoder.icc_profile(), Some(icc));
+    }
+
+    #[test]
+    fn test_embed_icc_in_png_roundtrip() {
+        let img = DynamicImage::new_rgb8(8, 8);
+        let mut png = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut png),
+            image::ImageOutputFormat::Png,
+        )
+        .unwrap();
+
+        let icc = b"fake icc profile data".to_vec();
+        let with_icc = embed_icc_in_png(&png, &icc);
+        assert!(image::load_from_memory(&with_icc).is_ok());
+
+        let mut decoder =
+            image::codecs::png::PngDecoder::new(std::io::Cursor::new(&with_icc)).unwrap();
+        assert_eq!(decoder.icc_profile(), Some(icc));
+    }
+
+    #[test]
+    fn test_embed_icc_ignores_other_formats() {
+        let data = b"not an image".to_vec();
+        assert_eq!(embed_icc_in_jpeg(&data, b"icc"), data);
+        assert_eq!(embed_icc_in_png(&data, b"icc"), data);
+    }
+
+    #[test]
+    fn test_embed_xmp_in_jpeg() {
+        let img = DynamicImage::new_rgb8(8, 8);
+        let mut jpeg = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut jpeg),
+            image::ImageOutputFormat::Jpeg(90),
+        )
+        .unwrap();
+
+        let xmp = xmp_packet(&[vr180_gpano_description(100, 100)]);
+        let with_xmp = embed_xmp_in_jpeg(&jpeg, &xmp);
+        assert!(image::load_from_memory(&with_xmp).is_ok());
+
+        let marker_at = with_xmp
+            .windows(XMP_MARKER.len())
+            .position(|w| w == XMP_MARKER)
+            .unwrap();
+        assert_eq!(with_xmp[marker_at - 4..marker_at - 2], [0xFF, 0xE1]);
+    }
+
+    #[test]
+    fn test_embed_xmp_in_png() {
+        let img = DynamicImage::new_rgb8(8, 8);
+        let mut png = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut png),
+            image::ImageOutputFormat::Png,
+        )
+        .unwrap();
+
+        let with_xmp = embed_xmp_in_png(&png, "<x:xmpmeta/>");
+        assert!(image::load_from_memory(&with_xmp).is_ok());
+        assert!(with_xmp
+            .windows(b"XML:com.adobe.xmp".len())
+            .any(|w| w == b"XML:com.adobe.xmp"));
+    }
+
+    #[test]
+    fn test_vr180_gpano_xmp() {
+        let xmp = vr180_gpano_description(2000, 1600);
+        assert!(xmp.contains("GPano:FullPanoWidthPixels=\"4000\""));
+        assert!(xmp.contains("GPano:FullPanoHeightPixels=\"2000\""));
+        assert!(xmp.contains("GPano:CroppedAreaLeftPixels=\"1000\""));
+        assert!(xmp.contains("GPano:CroppedAreaTopPixels=\"200\""));
+
+        let xmp = panorama_gpano_description(4000, 2000);
+        assert!(xmp.contains("GPano:FullPanoWidthPixels=\"4000\""));
+        assert!(xmp.contains("GPano:CroppedAreaImageHeightPixels=\"2000\""));
+        assert!(xmp.contains("GPano:CroppedAreaLeftPixels=\"0\""));
+    }
+
+    #[test]
+    fn test_set_exif_attribution() {
+        let attribution = Attribution {
+            creator: Some("Ada Example".to_string()),
+            copyright: Some("© 2026 Example Agency".to_string()),
+            ..Default::default()
+        };
+        let raw = set_exif_attribution(&exif_with_orientation(6), &attribution).unwrap();
+
+        let exif = exif::Reader::new().read_raw(raw.clone()).unwrap();
+        let artist = exif
+            .get_field(exif::Tag::Artist, exif::In::PRIMARY)
+            .unwrap();
+        assert_eq!(artist.display_value().to_string(), "\"Ada Example\"");
+        assert!(exif
+            .get_field(exif::Tag::Software, exif::In::PRIMARY)
+            .is_some());
+        // Existing entries survive
+        assert_eq!(exif_orientation(&raw), Some(6));
+
+        // Setting again replaces rather than duplicates
+        let again = set_exif_attribution(&raw, &attribution).unwrap();
+        let exif = exif::Reader::new().read_raw(again).unwrap();
+        assert_eq!(
+            exif.fields()
+                .filter(|f| f.tag == exif::Tag::Copyright)
+                .count(),
+            1
+        );
+
+        assert!(set_exif_attribution(b"nope", &attribution).is_none());
+        assert!(set_exif_attribution(&empty_exif(), &attribution).is_some());
+    }
+
+    #[test]
+    fn test_attribution_xmp_and_ffmpeg_args() {
+        let attribution = Attribution {
+            creator: Some("A & B".to_string()),
+            copyright: None,
+            software: Some("tool 1.0".to_string()),
+        };
+        let xmp = xmp_packet(&[attribution_xmp_description(&attribution)]);
+        assert!(xmp.contains("<rdf:li>A &amp; B</rdf:li>"));
+        assert!(xmp.contains("<xmp:CreatorTool>tool 1.0</xmp:CreatorTool>"));
+        assert!(!xmp.contains("dc:rights"));
+
+        assert_eq!(
+            attribution.ffmpeg_args(),
+            ["-metadata", "artist=A & B", "-metadata", "encoder=tool 1.0"]
+        );
+    }
+}
@@ -4,15 +4,86 @@
 //! - Locating the checkpoint directory (respects SPATIAL_MAKER_CHECKPOINTS env var)
 //! - Discovering existing ONNX models
 //! - Downloading models from HuggingFace if not present
+//! - Listing and deleting downloaded models
+//! - Pinning downloads in `models.lock` (see [`crate::model_lock`])
+//! - Locating the CoreML compiled model cache (respects
+//!   SPATIAL_MAKER_COREML_CACHE env var)
 
-use crate::error::{SpatialError, SpatialResult};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cancel::CancellationToken;
+use crate::error::SpatialError;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::SpatialResult;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::model_lock::{self, LockedModel, ModelLock};
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use sha2::{Digest, Sha256};
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::io::AsyncWriteExt;
 
+/// Depth Anything V2 encoder (model) size
+///
+/// Serializes as "s", "b", or "l". Deserializing and parsing also accept the
+/// full names and the "m"/"medium" spelling used by the Python tool for the
+/// base model; anything else is an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EncoderSize {
+    /// ViT-S, ~25M parameters: fastest
+    #[default]
+    #[serde(rename = "s", alias = "small")]
+    Small,
+    /// ViT-B, ~98M parameters
+    #[serde(rename = "b", alias = "base", alias = "m", alias = "medium")]
+    Base,
+    /// ViT-L, ~335M parameters: best quality
+    #[serde(rename = "l", alias = "large")]
+    Large,
+}
+
+impl EncoderSize {
+    /// All sizes, in ascending size order
+    pub const ALL: [EncoderSize; 3] = [EncoderSize::Small, EncoderSize::Base, EncoderSize::Large];
+
+    /// Short name: "s", "b", or "l"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EncoderSize::Small => "s",
+            EncoderSize::Base => "b",
+            EncoderSize::Large => "l",
+        }
+    }
+}
+
+impl std::fmt::Display for EncoderSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for EncoderSize {
+    type Err = SpatialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "s" | "small" => Ok(EncoderSize::Small),
+            "b" | "base" | "m" | "medium" => Ok(EncoderSize::Base),
+            "l" | "large" => Ok(EncoderSize::Large),
+            _ => Err(SpatialError::ConfigError(format!(
+                "Unknown encoder size: '{}'. Use 's', 'b', or 'l'",
+                s
+            ))),
+        }
+    }
+}
+
 /// Get the checkpoint directory path
 ///
 /// Respects the `SPATIAL_MAKER_CHECKPOINTS` environment variable if set,
 /// otherwise defaults to `~/.spatial-maker/checkpoints/`
+#[cfg(not(target_arch = "wasm32"))]
 pub fn get_checkpoint_dir() -> SpatialResult<PathBuf> {
     if let Ok(custom_dir) = std::env::var("SPATIAL_MAKER_CHECKPOINTS") {
         Ok(PathBuf::from(custom_dir))
@@ -24,6 +95,23 @@ pub fn get_checkpoint_dir() -> SpatialResult<PathBuf> {
     }
 }
 
+/// Get the directory CoreML's compiled models are cached in
+///
+/// Respects the `SPATIAL_MAKER_COREML_CACHE` environment variable if set,
+/// otherwise `coreml-cache/` next to the checkpoint directory (by default
+/// `~/.spatial-maker/coreml-cache/`). Each model gets its own subdirectory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_coreml_cache_dir() -> SpatialResult<PathBuf> {
+    if let Ok(custom_dir) = std::env::var("SPATIAL_MAKER_COREML_CACHE") {
+        return Ok(PathBuf::from(custom_dir));
+    }
+    let checkpoints = get_checkpoint_dir()?;
+    Ok(match checkpoints.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join("coreml-cache"),
+        _ => checkpoints.join("coreml-cache"),
+    })
+}
+
 /// Model metadata: name, size, and download URL
 #[derive(Clone, Debug)]
 pub struct ModelMetadata {
@@ -34,29 +122,27 @@ pub struct ModelMetadata {
 }
 
 impl ModelMetadata {
-    fn for_encoder(encoder_size: &str) -> SpatialResult<Self> {
+    /// Metadata of the checkpoint used for `encoder_size`.
+    pub fn for_encoder(encoder_size: EncoderSize) -> Self {
         match encoder_size {
-            "s" | "small" => Ok(ModelMetadata {
+            EncoderSize::Small => ModelMetadata {
                 name: "depth-anything-v2-small".to_string(),
                 filename: "depth_anything_v2_small.onnx".to_string(),
                 url: "https://huggingface.co/onnx-community/depth-anything-v2-small/resolve/main/onnx/model.onnx".to_string(),
                 size_mb: 99,
-            }),
-            "b" | "base" => Ok(ModelMetadata {
+            },
+            EncoderSize::Base => ModelMetadata {
                 name: "depth-anything-v2-base".to_string(),
                 filename: "depth_anything_v2_base.onnx".to_string(),
                 url: "https://huggingface.co/onnx-community/depth-anything-v2-base/resolve/main/onnx/model.onnx".to_string(),
                 size_mb: 380,
-            }),
-            "l" | "large" => Ok(ModelMetadata {
+            },
+            EncoderSize::Large => ModelMetadata {
                 name: "depth-anything-v2-large".to_string(),
                 filename: "depth_anything_v2_large.onnx".to_string(),
                 url: "https://huggingface.co/onnx-community/depth-anything-v2-large/resolve/main/onnx/model.onnx".to_string(),
                 size_mb: 1300,
-            }),
-            other => Err(SpatialError::ConfigError(
-                format!("Unknown encoder size: '{}'. Use 's', 'b', or 'l'", other)
-            )),
+            },
         }
     }
 }
@@ -65,50 +151,141 @@ impl ModelMetadata {
 ///
 /// Returns the path if it exists, otherwise returns an error.
 /// Does not download; use `ensure_model_exists` for automatic downloads.
-pub fn find_model(encoder_size: &str) -> SpatialResult<PathBuf> {
+#[cfg(not(target_arch = "wasm32"))]
+pub fn find_model(encoder_size: EncoderSize) -> SpatialResult<PathBuf> {
     let checkpoint_dir = get_checkpoint_dir()?;
-    let metadata = ModelMetadata::for_encoder(encoder_size)?;
+    let metadata = ModelMetadata::for_encoder(encoder_size);
     let model_path = checkpoint_dir.join(&metadata.filename);
 
     if model_path.exists() {
         tracing::info!("Found model: {:?}", model_path);
         Ok(model_path)
     } else {
-        Err(SpatialError::ModelError(format!(
-            "Model not found: {:?}. Run download_model first.",
+        Err(SpatialError::ModelNotFound(format!(
+            "{:?}. Run download_model first.",
             model_path
         )))
     }
 }
 
 /// Check if a model exists for the given encoder size
-pub fn model_exists(encoder_size: &str) -> bool {
+#[cfg(not(target_arch = "wasm32"))]
+pub fn model_exists(encoder_size: EncoderSize) -> bool {
     find_model(encoder_size).is_ok()
 }
 
+/// A model file present in the checkpoint directory
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DownloadedModel {
+    /// Encoder size this model belongs to
+    pub encoder_size: EncoderSize,
+    /// Model name (e.g. "depth-anything-v2-small")
+    pub name: String,
+    /// Full path to the ONNX file
+    pub path: PathBuf,
+    /// File size on disk in bytes
+    pub size_bytes: u64,
+}
+
+/// List all known models that are currently downloaded
+///
+/// Only files matching a known encoder are reported; partial downloads
+/// and unrelated files in the checkpoint directory are ignored.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_downloaded_models() -> SpatialResult<Vec<DownloadedModel>> {
+    list_models_in(&get_checkpoint_dir()?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn list_models_in(checkpoint_dir: &Path) -> SpatialResult<Vec<DownloadedModel>> {
+    let mut models = Vec::new();
+
+    for encoder_size in EncoderSize::ALL {
+        let metadata = ModelMetadata::for_encoder(encoder_size);
+        let path = checkpoint_dir.join(&metadata.filename);
+
+        if let Ok(file_meta) = std::fs::metadata(&path) {
+            if file_meta.is_file() {
+                models.push(DownloadedModel {
+                    encoder_size,
+                    name: metadata.name,
+                    path,
+                    size_bytes: file_meta.len(),
+                });
+            }
+        }
+    }
+
+    Ok(models)
+}
+
+/// Delete the downloaded model for a given encoder size
+///
+/// Returns `Ok(true)` if a file was removed and `Ok(false)` if the model
+/// was not downloaded in the first place.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn delete_model(encoder_size: EncoderSize) -> SpatialResult<bool> {
+    delete_model_in(&get_checkpoint_dir()?, encoder_size)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn delete_model_in(checkpoint_dir: &Path, encoder_size: EncoderSize) -> SpatialResult<bool> {
+    let metadata = ModelMetadata::for_encoder(encoder_size);
+    let model_path = checkpoint_dir.join(&metadata.filename);
+
+    if !model_path.exists() {
+        return Ok(false);
+    }
+
+    std::fs::remove_file(&model_path)
+        .map_err(|e| SpatialError::io(format!("Failed to delete model {:?}", model_path), e))?;
+
+    tracing::info!("Deleted model: {:?}", model_path);
+    Ok(true)
+}
+
 /// Download a model from HuggingFace if it doesn't exist
 ///
+/// The first download of each model is recorded in `models.lock`; later
+/// downloads use the locked URL and fail if the file no longer matches.
+///
 /// # Arguments
 ///
-/// * `encoder_size` - Model size: "s", "b", or "l"
+/// * `encoder_size` - Model size
 /// * `progress_fn` - Optional callback for progress updates: `(current_bytes, total_bytes)`
+#[cfg(not(target_arch = "wasm32"))]
 pub async fn ensure_model_exists<F>(
-    encoder_size: &str,
+    encoder_size: EncoderSize,
     progress_fn: Option<F>,
 ) -> SpatialResult<PathBuf>
+where
+    F: FnMut(u64, u64),
+{
+    ensure_model_exists_with(encoder_size, progress_fn, None).await
+}
+
+/// [`ensure_model_exists`] that stops the download once `cancel` is set
+///
+/// A cancelled download returns [`SpatialError::Cancelled`] and removes the
+/// partial file. Pausing the token does not hold the download.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn ensure_model_exists_with<F>(
+    encoder_size: EncoderSize,
+    progress_fn: Option<F>,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<PathBuf>
 where
     F: FnMut(u64, u64),
 {
     let checkpoint_dir = get_checkpoint_dir()?;
-    let metadata = ModelMetadata::for_encoder(encoder_size)?;
+    let metadata = ModelMetadata::for_encoder(encoder_size);
     let model_path = checkpoint_dir.join(&metadata.filename);
 
     // Create checkpoint directory if it doesn't exist
     tokio::fs::create_dir_all(&checkpoint_dir)
         .await
-        .map_err(|e| {
-            SpatialError::IoError(format!("Failed to create checkpoint directory: {}", e))
-        })?;
+        .map_err(|e| SpatialError::io("Failed to create checkpoint directory", e))?;
 
     // Return early if model already exists
     if model_path.exists() {
@@ -116,25 +293,89 @@ where
         return Ok(model_path);
     }
 
+    // A locked model is fetched from its pinned URL and must hash the same
+    let lock_path = model_lock::lock_path()?;
+    let mut lock = ModelLock::load(&lock_path)?;
+    let url = lock
+        .get(encoder_size)
+        .map_or(metadata.url.clone(), |locked| locked.url.clone());
+
     tracing::info!("Downloading model: {}", metadata.name);
-    download_model(&metadata, &model_path, progress_fn).await?;
+    let partial_path = partial_download_path(&model_path);
+    let downloaded = download_model(
+        encoder_size,
+        &metadata,
+        &url,
+        &partial_path,
+        progress_fn,
+        cancel,
+    )
+    .await
+    .and_then(|downloaded| {
+        lock.verify_download(&downloaded)?;
+        Ok(downloaded)
+    });
+    let downloaded = match downloaded {
+        Ok(downloaded) => downloaded,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial_path).await;
+            return Err(e);
+        }
+    };
+    tokio::fs::rename(&partial_path, &model_path)
+        .await
+        .map_err(|e| SpatialError::io("Failed to move model into place", e))?;
+
+    if lock.get(encoder_size).is_none() {
+        lock.insert(downloaded);
+        lock.save(&lock_path)?;
+        tracing::info!("Recorded {} in {:?}", metadata.name, lock_path);
+    }
     Ok(model_path)
 }
 
-/// Download a model from the given URL with progress tracking
+/// Where a download of `model_path` is written before it is moved into
+/// place; named per process and start time so concurrent downloads of the
+/// same model do not write into one file
+#[cfg(not(target_arch = "wasm32"))]
+fn partial_download_path(model_path: &Path) -> PathBuf {
+    let mut name = model_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}_{}.part",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    model_path.with_file_name(name)
+}
+
+/// Download a model from the given URL with progress tracking, hashing it
+/// for the lock as it arrives
+#[cfg(not(target_arch = "wasm32"))]
 async fn download_model<F>(
+    encoder_size: EncoderSize,
     metadata: &ModelMetadata,
+    url: &str,
     destination: &Path,
     mut progress_fn: Option<F>,
-) -> SpatialResult<()>
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<LockedModel>
 where
     F: FnMut(u64, u64),
 {
-    tracing::info!("Downloading from: {}", metadata.url);
+    tracing::info!("Downloading from: {}", url);
 
-    let response = reqwest::get(&metadata.url)
+    let response = reqwest::get(url)
         .await
-        .map_err(|e| SpatialError::Other(format!("Failed to download model: {}", e)))?;
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| SpatialError::download("Failed to download model", e))?;
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
 
     let total_bytes = response
         .content_length()
@@ -142,21 +383,25 @@ where
 
     let mut file = tokio::fs::File::create(destination)
         .await
-        .map_err(|e| SpatialError::IoError(format!("Failed to create file: {}", e)))?;
+        .map_err(|e| SpatialError::io("Failed to create file", e))?;
 
     let mut downloaded = 0u64;
+    let mut hasher = Sha256::new();
     let mut stream = response.bytes_stream();
 
     use futures_util::StreamExt;
 
     while let Some(chunk) = stream.next().await {
-        let chunk =
-            chunk.map_err(|e| SpatialError::Other(format!("Download interrupted: {}", e)))?;
+        let chunk = chunk.map_err(|e| SpatialError::download("Download interrupted", e))?;
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            return Err(SpatialError::Cancelled);
+        }
 
         file.write_all(&chunk)
             .await
-            .map_err(|e| SpatialError::IoError(format!("Failed to write to file: {}", e)))?;
+            .map_err(|e| SpatialError::io("Failed to write to file", e))?;
 
+        hasher.update(&chunk);
         downloaded += chunk.len() as u64;
 
         if let Some(ref mut f) = progress_fn {
@@ -171,8 +416,23 @@ where
         );
     }
 
+    file.flush()
+        .await
+        .map_err(|e| SpatialError::io("Failed to write to file", e))?;
+
     tracing::info!("Model downloaded successfully: {:?}", destination);
-    Ok(())
+    Ok(LockedModel {
+        encoder_size,
+        name: metadata.name.clone(),
+        url: url.to_string(),
+        etag,
+        sha256: hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+        size_bytes: downloaded,
+    })
 }
 
 #[cfg(test)]
@@ -181,27 +441,77 @@ mod tests {
 
     #[test]
     fn test_model_metadata_small() {
-        let meta = ModelMetadata::for_encoder("s").unwrap();
+        let meta = ModelMetadata::for_encoder(EncoderSize::Small);
         assert_eq!(meta.name, "depth-anything-v2-small");
         assert!(meta.url.contains("depth-anything-v2-small"));
     }
 
     #[test]
     fn test_model_metadata_base() {
-        let meta = ModelMetadata::for_encoder("b").unwrap();
+        let meta = ModelMetadata::for_encoder(EncoderSize::Base);
         assert_eq!(meta.name, "depth-anything-v2-base");
     }
 
     #[test]
     fn test_model_metadata_large() {
-        let meta = ModelMetadata::for_encoder("l").unwrap();
+        let meta = ModelMetadata::for_encoder(EncoderSize::Large);
         assert_eq!(meta.name, "depth-anything-v2-large");
     }
 
     #[test]
     fn test_invalid_encoder() {
-        let result = ModelMetadata::for_encoder("x");
-        assert!(result.is_err());
+        assert!("x".parse::<EncoderSize>().is_err());
+        assert!(serde_json::from_str::<EncoderSize>("\"x\"").is_err());
+    }
+
+    #[test]
+    fn test_encoder_size_names() {
+        assert_eq!("L".parse::<EncoderSize>().unwrap(), EncoderSize::Large);
+        assert_eq!("m".parse::<EncoderSize>().unwrap(), EncoderSize::Base);
+        assert_eq!(
+            serde_json::from_str::<EncoderSize>("\"base\"").unwrap(),
+            EncoderSize::Base
+        );
+        assert_eq!(serde_json::to_string(&EncoderSize::Base).unwrap(), "\"b\"");
+        assert_eq!(EncoderSize::Small.to_string(), "s");
+    }
+
+    #[test]
+    fn test_list_models_in() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(list_models_in(temp_dir.path()).unwrap().is_empty());
+
+        std::fs::write(temp_dir.path().join("depth_anything_v2_base.onnx"), b"onnx").unwrap();
+        std::fs::write(temp_dir.path().join("unrelated.onnx"), b"other").unwrap();
+
+        let models = list_models_in(temp_dir.path()).unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].encoder_size, EncoderSize::Base);
+        assert_eq!(models[0].name, "depth-anything-v2-base");
+        assert_eq!(models[0].size_bytes, 4);
+    }
+
+    #[test]
+    fn test_partial_download_path() {
+        let model_path = Path::new("/models/depth_anything_v2_small.onnx");
+        let partial = partial_download_path(model_path);
+        assert_eq!(partial.parent(), model_path.parent());
+
+        let name = partial.file_name().unwrap().to_str().unwrap();
+        let prefix = format!("depth_anything_v2_small.onnx.{}_", std::process::id());
+        assert!(name.starts_with(&prefix), "{}", name);
+        assert!(name.ends_with(".part"), "{}", name);
+    }
+
+    #[test]
+    fn test_delete_model_in() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("depth_anything_v2_small.onnx");
+        std::fs::write(&model_path, b"onnx").unwrap();
+
+        assert!(delete_model_in(temp_dir.path(), EncoderSize::Small).unwrap());
+        assert!(!model_path.exists());
+        assert!(!delete_model_in(temp_dir.path(), EncoderSize::Small).unwrap());
     }
 
     #[test]
@@ -211,4 +521,12 @@ mod tests {
         assert_eq!(dir, PathBuf::from("/tmp/test"));
         std::env::remove_var("SPATIAL_MAKER_CHECKPOINTS");
     }
+
+    #[test]
+    fn test_coreml_cache_dir_with_env() {
+        std::env::set_var("SPATIAL_MAKER_COREML_CACHE", "/tmp/coreml");
+        let dir = get_coreml_cache_dir().unwrap();
+        assert_eq!(dir, PathBuf::from("/tmp/coreml"));
+        std::env::remove_var("SPATIAL_MAKER_COREML_CACHE");
+    }
 }
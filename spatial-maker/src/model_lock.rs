@@ -0,0 +1,342 @@
+//! Pinned model downloads
+//!
+//! A `models.lock` file records the exact URL, ETag, SHA-256, and size of
+//! each downloaded depth model. Once a model is locked, it is always
+//! downloaded from the locked URL and a file that does not hash to the
+//! locked SHA-256 is refused instead of silently replacing the model.
+//! [`verify_installed_models`] checks the files already on disk.
+//!
+//! The lock lives next to the models by default. Teams that need identical
+//! outputs across machines can commit one and point
+//! `SPATIAL_MAKER_MODELS_LOCK` at it.
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::model::{get_checkpoint_dir, EncoderSize, ModelMetadata};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Lock file schema version, bumped on incompatible changes
+pub const LOCK_VERSION: u32 = 1;
+
+/// File name of the lock in the checkpoint directory
+pub const DEFAULT_LOCK_NAME: &str = "models.lock";
+
+/// One pinned model file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedModel {
+    /// Encoder size
+    pub encoder_size: EncoderSize,
+    /// Model name (e.g. "depth-anything-v2-small")
+    pub name: String,
+    /// URL the model is downloaded from
+    pub url: String,
+    /// ETag the server sent with the file, when it sent one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    /// Hex-encoded SHA-256 of the ONNX file
+    pub sha256: String,
+    /// File size in bytes
+    pub size_bytes: u64,
+}
+
+/// Contents of a `models.lock` file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelLock {
+    pub version: u32,
+    pub models: Vec<LockedModel>,
+}
+
+impl Default for ModelLock {
+    fn default() -> Self {
+        Self {
+            version: LOCK_VERSION,
+            models: Vec::new(),
+        }
+    }
+}
+
+/// How an installed model compares to the lock
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum LockStatus {
+    /// The file matches the lock
+    Verified,
+    /// The model is not downloaded
+    NotInstalled,
+    /// The model is downloaded but not in the lock
+    Unlocked,
+    /// The file differs from the locked one
+    Changed {
+        expected_sha256: String,
+        actual_sha256: String,
+        expected_bytes: u64,
+        actual_bytes: u64,
+    },
+}
+
+/// Path of the lock file
+///
+/// Respects the `SPATIAL_MAKER_MODELS_LOCK` environment variable if set,
+/// otherwise `models.lock` in the checkpoint directory.
+pub fn lock_path() -> SpatialResult<PathBuf> {
+    match std::env::var("SPATIAL_MAKER_MODELS_LOCK") {
+        Ok(path) => Ok(PathBuf::from(path)),
+        Err(_) => Ok(get_checkpoint_dir()?.join(DEFAULT_LOCK_NAME)),
+    }
+}
+
+impl ModelLock {
+    /// Read a lock file; a missing file is an empty lock
+    pub fn load(path: &Path) -> SpatialResult<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(SpatialError::io(
+                    format!("Failed to read model lock {:?}", path),
+                    e,
+                ))
+            }
+        };
+        let lock: Self = serde_json::from_str(&text).map_err(|e| {
+            SpatialError::ConfigError(format!("Invalid model lock {:?}: {}", path, e))
+        })?;
+        if lock.version > LOCK_VERSION {
+            return Err(SpatialError::ConfigError(format!(
+                "Model lock {:?} has version {}; this build reads up to {}",
+                path, lock.version, LOCK_VERSION
+            )));
+        }
+        Ok(lock)
+    }
+
+    /// Write the lock as pretty-printed JSON, replacing the file atomically
+    pub fn save(&self, path: &Path) -> SpatialResult<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| SpatialError::io("Failed to serialize model lock", e.into()))?;
+        let temp = path.with_extension("lock.tmp");
+        std::fs::write(&temp, json + "\n")?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    /// The pinned file for an encoder size
+    pub fn get(&self, encoder_size: EncoderSize) -> Option<&LockedModel> {
+        self.models.iter().find(|m| m.encoder_size == encoder_size)
+    }
+
+    /// Pin a model, replacing any earlier entry for its encoder size
+    pub fn insert(&mut self, model: LockedModel) {
+        self.models.retain(|m| m.encoder_size != model.encoder_size);
+        self.models.push(model);
+        self.models
+            .sort_by_key(|m| EncoderSize::ALL.iter().position(|&e| e == m.encoder_size));
+    }
+
+    /// Compare the file at `model_path` with the lock, hashing it
+    pub fn check(&self, encoder_size: EncoderSize, model_path: &Path) -> SpatialResult<LockStatus> {
+        let Ok(file_meta) = std::fs::metadata(model_path) else {
+            return Ok(LockStatus::NotInstalled);
+        };
+        let Some(locked) = self.get(encoder_size) else {
+            return Ok(LockStatus::Unlocked);
+        };
+        let actual_sha256 = crate::manifest::sha256_file(model_path)?;
+        if actual_sha256 == locked.sha256 && file_meta.len() == locked.size_bytes {
+            Ok(LockStatus::Verified)
+        } else {
+            Ok(LockStatus::Changed {
+                expected_sha256: locked.sha256.clone(),
+                actual_sha256,
+                expected_bytes: locked.size_bytes,
+                actual_bytes: file_meta.len(),
+            })
+        }
+    }
+
+    /// Refuse a fresh download that differs from the locked file
+    pub(crate) fn verify_download(&self, downloaded: &LockedModel) -> SpatialResult<()> {
+        let Some(locked) = self.get(downloaded.encoder_size) else {
+            return Ok(());
+        };
+        if locked.sha256 != downloaded.sha256 || locked.size_bytes != downloaded.size_bytes {
+            return Err(SpatialError::ModelMismatch(format!(
+                "Downloaded {} does not match models.lock (sha256 {}, {} bytes; expected {}, {} bytes). \
+                 The upstream file changed; delete its lock entry to accept the new file.",
+                downloaded.name,
+                downloaded.sha256,
+                downloaded.size_bytes,
+                locked.sha256,
+                locked.size_bytes
+            )));
+        }
+        if let (Some(expected), Some(actual)) = (&locked.etag, &downloaded.etag) {
+            if expected != actual {
+                tracing::warn!(
+                    "ETag of {} changed ({} -> {}) but its contents match the lock",
+                    downloaded.name,
+                    expected,
+                    actual
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 of the downloaded model for `encoder_size`
+///
+/// Taken from the lock when its entry has the file's size, so the model is
+/// only hashed when it is not locked.
+pub(crate) fn installed_sha256(encoder_size: EncoderSize) -> SpatialResult<String> {
+    let model_path = crate::model::find_model(encoder_size)?;
+    let size_bytes = std::fs::metadata(&model_path)
+        .map_err(|e| SpatialError::io(format!("Failed to read {:?}", model_path), e))?
+        .len();
+    let lock = lock_path().and_then(|path| ModelLock::load(&path)).ok();
+    match lock.as_ref().and_then(|lock| lock.get(encoder_size)) {
+        Some(locked) if locked.size_bytes == size_bytes => Ok(locked.sha256.clone()),
+        _ => crate::manifest::sha256_file(&model_path),
+    }
+}
+
+/// Check every downloaded or locked model against the lock
+pub fn verify_installed_models() -> SpatialResult<Vec<(EncoderSize, LockStatus)>> {
+    let lock = ModelLock::load(&lock_path()?)?;
+    let checkpoint_dir = get_checkpoint_dir()?;
+    let mut statuses = Vec::new();
+    for encoder_size in EncoderSize::ALL {
+        let model_path = checkpoint_dir.join(ModelMetadata::for_encoder(encoder_size).filename);
+        let status = lock.check(encoder_size, &model_path)?;
+        if status != LockStatus::NotInstalled || lock.get(encoder_size).is_some() {
+            statuses.push((encoder_size, status));
+        }
+    }
+    Ok(statuses)
+}
+
+/// Add downloaded models that are not locked yet to the lock
+///
+/// Models downloaded before the lock existed are hashed as they are on
+/// disk and recorded with the default URL. Returns the updated lock.
+pub fn lock_installed_models() -> SpatialResult<ModelLock> {
+    let path = lock_path()?;
+    let mut lock = ModelLock::load(&path)?;
+    for model in crate::model::list_downloaded_models()? {
+        if lock.get(model.encoder_size).is_some() {
+            continue;
+        }
+        let metadata = ModelMetadata::for_encoder(model.encoder_size);
+        lock.insert(LockedModel {
+            encoder_size: model.encoder_size,
+            name: metadata.name,
+            url: metadata.url,
+            etag: None,
+            sha256: crate::manifest::sha256_file(&model.path)?,
+            size_bytes: model.size_bytes,
+        });
+    }
+    lock.save(&path)?;
+    Ok(lock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(encoder_size: EncoderSize, contents: &[u8]) -> LockedModel {
+        let metadata = ModelMetadata::for_encoder(encoder_size);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("model.onnx");
+        std::fs::write(&path, contents).unwrap();
+        LockedModel {
+            encoder_size,
+            name: metadata.name,
+            url: metadata.url,
+            etag: Some("\"abc\"".to_string()),
+            sha256: crate::manifest::sha256_file(&path).unwrap(),
+            size_bytes: contents.len() as u64,
+        }
+    }
+
+    #[test]
+    fn test_missing_lock_is_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lock = ModelLock::load(&temp_dir.path().join(DEFAULT_LOCK_NAME)).unwrap();
+        assert_eq!(lock, ModelLock::default());
+    }
+
+    #[test]
+    fn test_lock_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("nested").join(DEFAULT_LOCK_NAME);
+        let mut lock = ModelLock::default();
+        lock.insert(locked(EncoderSize::Large, b"large"));
+        lock.insert(locked(EncoderSize::Small, b"small"));
+        lock.insert(locked(EncoderSize::Small, b"small v2"));
+        lock.save(&path).unwrap();
+
+        let loaded = ModelLock::load(&path).unwrap();
+        assert_eq!(loaded, lock);
+        let sizes: Vec<_> = loaded.models.iter().map(|m| m.encoder_size).collect();
+        assert_eq!(sizes, [EncoderSize::Small, EncoderSize::Large]);
+        assert_eq!(loaded.get(EncoderSize::Small).unwrap().size_bytes, 8);
+
+        std::fs::write(&path, r#"{"version": 99, "models": []}"#).unwrap();
+        assert!(ModelLock::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_check_statuses() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let model_path = temp_dir.path().join("depth_anything_v2_small.onnx");
+        let mut lock = ModelLock::default();
+
+        assert_eq!(
+            lock.check(EncoderSize::Small, &model_path).unwrap(),
+            LockStatus::NotInstalled
+        );
+        std::fs::write(&model_path, b"onnx").unwrap();
+        assert_eq!(
+            lock.check(EncoderSize::Small, &model_path).unwrap(),
+            LockStatus::Unlocked
+        );
+
+        lock.insert(locked(EncoderSize::Small, b"onnx"));
+        assert_eq!(
+            lock.check(EncoderSize::Small, &model_path).unwrap(),
+            LockStatus::Verified
+        );
+
+        std::fs::write(&model_path, b"retrained").unwrap();
+        match lock.check(EncoderSize::Small, &model_path).unwrap() {
+            LockStatus::Changed {
+                expected_bytes,
+                actual_bytes,
+                ..
+            } => assert_eq!((expected_bytes, actual_bytes), (4, 9)),
+            other => panic!("expected Changed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_download() {
+        let mut lock = ModelLock::default();
+        let original = locked(EncoderSize::Base, b"weights");
+        assert!(lock.verify_download(&original).is_ok());
+
+        lock.insert(original.clone());
+        let mut same = original.clone();
+        same.etag = Some("\"other\"".to_string());
+        assert!(lock.verify_download(&same).is_ok());
+
+        let changed = locked(EncoderSize::Base, b"new weights");
+        assert!(matches!(
+            lock.verify_download(&changed),
+            Err(SpatialError::ModelMismatch(_))
+        ));
+    }
+}
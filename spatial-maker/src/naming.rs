@@ -0,0 +1,404 @@
+//! Player-friendly file names and sidecars
+//!
+//! VR and 3D players (DeoVR, Skybox, Pigasus, ...) pick the stereo mode of a
+//! file from a tag in its name, so tagged output plays in the right mode
+//! without the viewer choosing a layout by hand:
+//!
+//! | Output                     | Tag             |
+//! |----------------------------|-----------------|
+//! | Side-by-side               | `_SBS_FULL`     |
+//! | Half side-by-side          | `_SBS`          |
+//! | Top-and-bottom             | `_TB_FULL`      |
+//! | Half top-and-bottom        | `_TB`           |
+//! | Cross-eye (right first)    | `_RL`           |
+//! | VR180 side-by-side         | `_180x180_3dh`  |
+//! | VR180 top-and-bottom       | `_180x180_3dv`  |
+//!
+//! A JSON sidecar (`{stem}.json`, DeoVR's `screenType` / `stereoMode`
+//! vocabulary) describes the same for players and media servers that read
+//! one, and H.264 video can carry a frame-packing arrangement SEI message so
+//! TVs and hardware players switch modes from the stream itself.
+//!
+//! ```
+//! use spatial_maker::{OutputFormat, OutputOptions, PlayerNaming};
+//! use std::path::{Path, PathBuf};
+//!
+//! let options = OutputOptions {
+//!     layout: OutputFormat::SideBySide,
+//!     player: PlayerNaming {
+//!         tag_filename: true,
+//!         ..Default::default()
+//!     },
+//!     ..Default::default()
+//! };
+//! assert_eq!(
+//!     spatial_maker::naming::player_path(Path::new("out/clip.mp4"), &options),
+//!     PathBuf::from("out/clip_SBS_FULL.mp4")
+//! );
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::output::{OutputFormat, OutputOptions, Projection};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// How output is named and described for VR and 3D players
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerNaming {
+    /// Append the layout's player tag (e.g. `_SBS_FULL`) to the file stem
+    pub tag_filename: bool,
+
+    /// Write a `{stem}.json` sidecar describing the stereo mode
+    pub json_sidecar: bool,
+
+    /// Video: signal the frame packing in the stream with an H.264
+    /// frame-packing arrangement SEI message (`libx264` only)
+    pub frame_packing_sei: bool,
+}
+
+impl PlayerNaming {
+    /// Whether any player convention is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.tag_filename || self.json_sidecar || self.frame_packing_sei
+    }
+}
+
+/// Stereo mode description written to the JSON sidecar
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSidecar {
+    /// Always true; players use it to enable stereo rendering
+    pub is3d: bool,
+
+    /// `flat` for rectilinear output, `dome` for VR180, `sphere` for 360°
+    /// panoramas
+    pub screen_type: &'static str,
+
+    /// `sbs` or `tb`
+    pub stereo_mode: &'static str,
+
+    /// Each eye is squeezed to half size along the stacking axis
+    pub half_resolution: bool,
+
+    /// The right eye comes first (cross-eye output)
+    pub swap_eyes: bool,
+
+    /// Field of view of the projection in degrees, for VR180 and 360°
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fov: Option<u32>,
+}
+
+/// Player tag for the layout, or `None` for layouts players do not read
+/// (separate files, mirrored side-by-side)
+pub fn player_tag(
+    layout: OutputFormat,
+    half_resolution: bool,
+    projection: Projection,
+) -> Option<&'static str> {
+    let tag = match (projection, layout, half_resolution) {
+        (Projection::Vr180, OutputFormat::SideBySide, _) => "_180x180_3dh",
+        (Projection::Vr180, OutputFormat::TopAndBottom, _) => "_180x180_3dv",
+        (Projection::Vr180, _, _) => return None,
+        (Projection::Panorama360, OutputFormat::TopAndBottom, _) => "_360x180_3dv",
+        (Projection::Panorama360, _, _) => return None,
+        (_, OutputFormat::SideBySide, false) => "_SBS_FULL",
+        (_, OutputFormat::SideBySide, true) => "_SBS",
+        (_, OutputFormat::TopAndBottom, false) => "_TB_FULL",
+        (_, OutputFormat::TopAndBottom, true) => "_TB",
+        (_, OutputFormat::CrossEye, _) => "_RL",
+        (_, OutputFormat::Separate | OutputFormat::MirroredSideBySide, _) => return None,
+    };
+    Some(tag)
+}
+
+/// Path the stereo output is written to: `output_path` with the player tag
+/// appended to the stem when `tag_filename` is set
+///
+/// A stem that already ends with the tag is left alone, so re-running on
+/// tagged names does not stack tags.
+pub fn player_path(output_path: &Path, options: &OutputOptions) -> PathBuf {
+    let tag = match player_tag(options.layout, options.half_resolution, options.projection) {
+        Some(tag) if options.player.tag_filename => tag,
+        _ => return output_path.to_path_buf(),
+    };
+    let Some(stem) = output_path.file_stem().and_then(|s| s.to_str()) else {
+        return output_path.to_path_buf();
+    };
+    if stem
+        .to_ascii_lowercase()
+        .ends_with(&tag.to_ascii_lowercase())
+    {
+        return output_path.to_path_buf();
+    }
+    let name = match output_path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}{}.{}", stem, tag, ext),
+        None => format!("{}{}", stem, tag),
+    };
+    output_path.with_file_name(name)
+}
+
+/// Sidecar path for a (tagged) output path: `{stem}.json`
+pub fn sidecar_path(output_path: &Path) -> PathBuf {
+    output_path.with_extension("json")
+}
+
+/// Sidecar contents for the options, or `None` for layouts players do not read
+pub fn sidecar_for(options: &OutputOptions) -> Option<PlayerSidecar> {
+    let stereo_mode = match options.layout {
+        OutputFormat::SideBySide | OutputFormat::CrossEye => "sbs",
+        OutputFormat::TopAndBottom => "tb",
+        OutputFormat::Separate | OutputFormat::MirroredSideBySide => return None,
+    };
+    let (screen_type, fov) = match options.projection {
+        Projection::Rectilinear => ("flat", None),
+        Projection::Vr180 => ("dome", Some(180)),
+        Projection::Panorama360 => ("sphere", Some(360)),
+    };
+    Some(PlayerSidecar {
+        is3d: true,
+        screen_type,
+        stereo_mode,
+        half_resolution: options.half_resolution,
+        swap_eyes: options.layout == OutputFormat::CrossEye,
+        fov,
+    })
+}
+
+/// Write the JSON sidecar next to `output_path` if enabled
+pub(crate) fn write_sidecar(output_path: &Path, options: &OutputOptions) -> SpatialResult<()> {
+    if !options.player.json_sidecar {
+        return Ok(());
+    }
+    let Some(sidecar) = sidecar_for(options) else {
+        return Ok(());
+    };
+    let path = sidecar_path(output_path);
+    let json = serde_json::to_string_pretty(&sidecar)
+        .map_err(|e| SpatialError::io("Failed to serialize sidecar", e.into()))?;
+    std::fs::write(&path, json)
+        .map_err(|e| SpatialError::io(format!("Failed to write {:?}", path), e))
+}
+
+/// x264 `frame-packing` value for the layout (3 = side-by-side,
+/// 4 = top-and-bottom), when the SEI message is enabled
+///
+/// x264 always marks the left view first, so cross-eye output has none.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn frame_packing(options: &OutputOptions) -> Option<u8> {
+    if !options.player.frame_packing_sei {
+        return None;
+    }
+    match options.layout {
+        OutputFormat::SideBySide => Some(3),
+        OutputFormat::TopAndBottom => Some(4),
+        _ => None,
+    }
+}
+
+/// Check that players can describe the layout and that nothing else renames
+/// the output
+pub(crate) fn validate(options: &OutputOptions) -> SpatialResult<()> {
+    if !options.player.is_enabled() {
+        return Ok(());
+    }
+    if player_tag(options.layout, options.half_resolution, options.projection).is_none() {
+        return Err(SpatialError::ConfigError(format!(
+            "Players have no naming convention for {} output; use side-by-side, top-and-bottom, or cross-eye",
+            options.layout.name()
+        )));
+    }
+    if options.player.frame_packing_sei && options.layout == OutputFormat::CrossEye {
+        return Err(SpatialError::ConfigError(
+            "Frame-packing SEI always puts the left eye first, so it cannot describe cross-eye output"
+                .to_string(),
+        ));
+    }
+    if options.mvhevc.as_ref().is_some_and(|c| c.enabled) {
+        return Err(SpatialError::ConfigError(
+            "Player tags and sidecars describe frame-packed stereo; spatial (MV-HEVC) output does not use them"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::MVHEVCConfig;
+
+    fn tagged(
+        layout: OutputFormat,
+        half_resolution: bool,
+        projection: Projection,
+    ) -> OutputOptions {
+        OutputOptions {
+            layout,
+            half_resolution,
+            projection,
+            player: PlayerNaming {
+                tag_filename: true,
+                json_sidecar: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_player_tags() {
+        let cases = [
+            (
+                OutputFormat::SideBySide,
+                false,
+                Projection::Rectilinear,
+                "v_SBS_FULL.mp4",
+            ),
+            (
+                OutputFormat::SideBySide,
+                true,
+                Projection::Rectilinear,
+                "v_SBS.mp4",
+            ),
+            (
+                OutputFormat::TopAndBottom,
+                false,
+                Projection::Rectilinear,
+                "v_TB_FULL.mp4",
+            ),
+            (
+                OutputFormat::TopAndBottom,
+                true,
+                Projection::Rectilinear,
+                "v_TB.mp4",
+            ),
+            (
+                OutputFormat::CrossEye,
+                false,
+                Projection::Rectilinear,
+                "v_RL.mp4",
+            ),
+            (
+                OutputFormat::SideBySide,
+                false,
+                Projection::Vr180,
+                "v_180x180_3dh.mp4",
+            ),
+            (
+                OutputFormat::TopAndBottom,
+                false,
+                Projection::Vr180,
+                "v_180x180_3dv.mp4",
+            ),
+            (
+                OutputFormat::TopAndBottom,
+                false,
+                Projection::Panorama360,
+                "v_360x180_3dv.mp4",
+            ),
+        ];
+        for (layout, half, projection, expected) in cases {
+            let options = tagged(layout, half, projection);
+            assert_eq!(
+                player_path(Path::new("out/v.mp4"), &options),
+                Path::new("out").join(expected)
+            );
+            assert!(validate(&options).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_player_path_is_idempotent_and_optional() {
+        let options = tagged(OutputFormat::SideBySide, true, Projection::Rectilinear);
+        assert_eq!(
+            player_path(Path::new("clip_sbs.mp4"), &options),
+            PathBuf::from("clip_sbs.mp4")
+        );
+
+        let untagged = OutputOptions {
+            player: PlayerNaming {
+                json_sidecar: true,
+                ..Default::default()
+            },
+            ..options
+        };
+        assert_eq!(
+            player_path(Path::new("clip.mp4"), &untagged),
+            PathBuf::from("clip.mp4")
+        );
+    }
+
+    #[test]
+    fn test_sidecar_contents() {
+        let sidecar = sidecar_for(&tagged(
+            OutputFormat::TopAndBottom,
+            false,
+            Projection::Vr180,
+        ))
+        .unwrap();
+        let json: serde_json::Value = serde_json::to_value(&sidecar).unwrap();
+        assert_eq!(json["is3d"], true);
+        assert_eq!(json["screenType"], "dome");
+        assert_eq!(json["stereoMode"], "tb");
+        assert_eq!(json["fov"], 180);
+
+        let sidecar = sidecar_for(&tagged(
+            OutputFormat::CrossEye,
+            false,
+            Projection::Rectilinear,
+        ))
+        .unwrap();
+        assert_eq!(sidecar.stereo_mode, "sbs");
+        assert!(sidecar.swap_eyes);
+        assert_eq!(sidecar.fov, None);
+
+        let sidecar = sidecar_for(&tagged(
+            OutputFormat::TopAndBottom,
+            false,
+            Projection::Panorama360,
+        ))
+        .unwrap();
+        assert_eq!(sidecar.screen_type, "sphere");
+        assert_eq!(sidecar.fov, Some(360));
+    }
+
+    #[test]
+    fn test_validate_rejects_unsupported_outputs() {
+        for layout in [OutputFormat::Separate, OutputFormat::MirroredSideBySide] {
+            let options = tagged(layout, false, Projection::Rectilinear);
+            assert!(matches!(
+                validate(&options),
+                Err(SpatialError::ConfigError(_))
+            ));
+        }
+
+        let mut options = tagged(OutputFormat::CrossEye, false, Projection::Rectilinear);
+        options.player.frame_packing_sei = true;
+        assert!(matches!(
+            validate(&options),
+            Err(SpatialError::ConfigError(_))
+        ));
+        options.layout = OutputFormat::TopAndBottom;
+        assert!(validate(&options).is_ok());
+        assert_eq!(frame_packing(&options), Some(4));
+
+        let options = OutputOptions {
+            mvhevc: Some(MVHEVCConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            ..tagged(OutputFormat::SideBySide, false, Projection::Rectilinear)
+        };
+        assert!(matches!(
+            validate(&options),
+            Err(SpatialError::ConfigError(_))
+        ));
+
+        // Nothing enabled, nothing to check
+        let options = OutputOptions {
+            layout: OutputFormat::Separate,
+            ..Default::default()
+        };
+        assert!(validate(&options).is_ok());
+    }
+}
@@ -1,10 +1,13 @@
 //! Output module for saving stereo images in various formats and generating MV-HEVC
 //!
 //! Supports:
-//! - Side-by-side (SBS) stereo images (JPEG, PNG)
-//! - Top-and-bottom stereo images (JPEG, PNG)
+//! - Side-by-side (SBS) stereo images (JPEG, PNG, WebP, AVIF, JPEG XL, float EXR)
+//! - Top-and-bottom stereo images (JPEG, PNG, WebP, AVIF, JPEG XL, float EXR)
 //! - Separate left/right image files
-//! - Optional MV-HEVC encoding via the `spatial` CLI tool
+//! - Multi-view quilts for Looking Glass displays
+//! - Colormapped depth previews for checking depth before stereo output
+//! - Float depth maps as single-channel EXR (`exr` feature)
+//! - Optional MV-HEVC encoding, natively on macOS or via the `spatial` CLI tool
 //!
 //! # Examples
 //!
@@ -28,10 +31,18 @@
 //! # }
 //! ```
 
-use crate::error::{SpatialError, SpatialResult};
-use image::DynamicImage;
+use crate::cancel::{self, CancellationToken};
+use crate::error::{encoding_error, SpatialError, SpatialResult};
+use crate::metadata::Attribution;
+use crate::naming::{self, PlayerNaming};
+use image::{ColorType, DynamicImage};
+use ndarray::Array2;
+use std::borrow::Cow;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 /// Output format for stereo images
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -40,8 +51,13 @@ pub enum OutputFormat {
     SideBySide,
     /// Top-and-bottom stereo (left above right)
     TopAndBottom,
-    /// Individual left and right files with _L and _R suffixes
+    /// Individual left and right files, named by
+    /// [`OutputOptions::filename_template`] (`_L` and `_R` suffixes by default)
     Separate,
+    /// Cross-eye free-viewing stereo (right | left)
+    CrossEye,
+    /// Side-by-side for mirror stereoscopes (left | horizontally flipped right)
+    MirroredSideBySide,
 }
 
 impl OutputFormat {
@@ -51,50 +67,236 @@ impl OutputFormat {
             OutputFormat::SideBySide => "side-by-side",
             OutputFormat::TopAndBottom => "top-and-bottom",
             OutputFormat::Separate => "separate",
+            OutputFormat::CrossEye => "cross-eye",
+            OutputFormat::MirroredSideBySide => "mirrored-side-by-side",
         }
     }
 }
 
+/// Projection of the source photo and the stereo output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Projection {
+    /// Ordinary perspective photo
+    #[default]
+    Rectilinear,
+    /// 180° equirectangular photo, written as VR180 stereo with GPano
+    /// spherical metadata on each eye
+    Vr180,
+    /// Full 360° equirectangular panorama, with depth estimated view by
+    /// view (see [`crate::panorama`]) and written as over/under
+    /// omnidirectional stereo with GPano metadata on each eye
+    Panorama360,
+}
+
 /// Image encoding format
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ImageEncoding {
     /// JPEG format (lossy)
     Jpeg { quality: u8 },
-    /// PNG format (lossless)
-    Png,
+    /// PNG format (lossless), at a zlib `compression` level and scanline
+    /// `filter`; `sixteen_bit` writes 16 bits per channel, which keeps the
+    /// precision of float (e.g. EXR) renders
+    Png {
+        compression: PngCompression,
+        filter: PngFilter,
+        sixteen_bit: bool,
+    },
+    /// WebP, lossy at `quality` (1-100) or lossless
+    ///
+    /// Lossy encoding uses libwebp and is not available on `wasm32`.
+    WebP { quality: u8, lossless: bool },
+    /// AVIF at `quality` (1-100) and encoder `speed` (1 slowest and
+    /// smallest, 10 fastest)
+    ///
+    /// Encoded with ravif (`avif` feature), or with libheif's AV1 encoder
+    /// when only the `heic` feature is enabled.
+    Avif { quality: u8, speed: u8 },
+    /// JPEG XL, lossless (requires the `jxl` feature)
+    Jxl,
+    /// HEIC (HEVC in a HEIF container) at `quality` (1-100), encoded with
+    /// libheif (requires the `heic` feature)
+    ///
+    /// A plain image per file, not an Apple spatial photo; MV-HEVC output
+    /// writes those.
+    Heic { quality: u8 },
+    /// OpenEXR, 32-bit float RGB (requires the `exr` feature)
+    ///
+    /// Float inputs such as EXR plates keep their values end to end; 8-bit
+    /// inputs are written as their 0-1 float equivalents.
+    Exr,
 }
 
 impl ImageEncoding {
+    /// 8-bit PNG with fast compression and adaptive filtering
+    pub const PNG: ImageEncoding = ImageEncoding::Png {
+        compression: PngCompression::Fast,
+        filter: PngFilter::Adaptive,
+        sixteen_bit: false,
+    };
+
     /// Get file extension for this encoding
     pub fn extension(&self) -> &'static str {
         match self {
             ImageEncoding::Jpeg { .. } => "jpg",
-            ImageEncoding::Png => "png",
+            ImageEncoding::Png { .. } => "png",
+            ImageEncoding::WebP { .. } => "webp",
+            ImageEncoding::Avif { .. } => "avif",
+            ImageEncoding::Jxl => "jxl",
+            ImageEncoding::Heic { .. } => "heic",
+            ImageEncoding::Exr => "exr",
         }
     }
 
-    /// Detect encoding from file extension
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+    /// Detect encoding from file extension, with each format's default
+    /// settings (JPEG and WebP at quality 95)
+    ///
+    /// Fails for extensions that name no supported encoding.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> SpatialResult<Self> {
+        Self::from_path_with_defaults(path, ImageEncoding::Jpeg { quality: 95 })
+    }
+
+    /// Detect encoding from file extension, taking the settings of
+    /// `defaults` when the extension is its format
+    ///
+    /// ```
+    /// use spatial_maker::ImageEncoding;
+    ///
+    /// let defaults = ImageEncoding::Jpeg { quality: 85 };
+    /// let jpeg = ImageEncoding::from_path_with_defaults("out.jpeg", defaults).unwrap();
+    /// assert_eq!(jpeg, ImageEncoding::Jpeg { quality: 85 });
+    /// let png = ImageEncoding::from_path_with_defaults("out.png", defaults).unwrap();
+    /// assert_eq!(png, ImageEncoding::PNG);
+    /// assert!(ImageEncoding::from_path_with_defaults("out.txt", defaults).is_err());
+    /// ```
+    pub fn from_path_with_defaults<P: AsRef<Path>>(
+        path: P,
+        defaults: ImageEncoding,
+    ) -> SpatialResult<Self> {
+        let path = path.as_ref();
         let ext = path
-            .as_ref()
             .extension()
             .and_then(|e| e.to_str())
             .unwrap_or("")
             .to_lowercase();
 
-        match ext.as_str() {
-            "png" => ImageEncoding::Png,
-            _ => ImageEncoding::Jpeg { quality: 95 },
+        let encoding = match ext.as_str() {
+            "jpg" | "jpeg" => ImageEncoding::Jpeg { quality: 95 },
+            "png" => ImageEncoding::PNG,
+            "webp" => ImageEncoding::WebP {
+                quality: 95,
+                lossless: false,
+            },
+            // cavif's defaults
+            "avif" => ImageEncoding::Avif {
+                quality: 80,
+                speed: 4,
+            },
+            "jxl" => ImageEncoding::Jxl,
+            "heic" | "heif" => ImageEncoding::Heic { quality: 90 },
+            "exr" => ImageEncoding::Exr,
+            _ => {
+                return Err(SpatialError::ConfigError(format!(
+                    "Unrecognized image extension for {:?}; use .jpg, .png, .webp, .avif, .jxl, .heic, or .exr",
+                    path
+                )))
+            }
+        };
+        if encoding.extension() == defaults.extension() {
+            Ok(defaults)
+        } else {
+            Ok(encoding)
+        }
+    }
+}
+
+/// zlib effort for PNG output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngCompression {
+    /// Quickest to write, largest files
+    #[default]
+    Fast,
+    /// zlib's default level
+    Balanced,
+    /// Smallest files, several times slower to write
+    Best,
+}
+
+impl PngCompression {
+    fn compression_type(self) -> image::codecs::png::CompressionType {
+        use image::codecs::png::CompressionType;
+        match self {
+            PngCompression::Fast => CompressionType::Fast,
+            PngCompression::Balanced => CompressionType::Default,
+            PngCompression::Best => CompressionType::Best,
+        }
+    }
+}
+
+/// PNG scanline filter, applied before compression
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngFilter {
+    NoFilter,
+    Sub,
+    Up,
+    Avg,
+    Paeth,
+    /// Pick the best filter for each scanline
+    #[default]
+    Adaptive,
+}
+
+impl PngFilter {
+    fn filter_type(self) -> image::codecs::png::FilterType {
+        use image::codecs::png::FilterType;
+        match self {
+            PngFilter::NoFilter => FilterType::NoFilter,
+            PngFilter::Sub => FilterType::Sub,
+            PngFilter::Up => FilterType::Up,
+            PngFilter::Avg => FilterType::Avg,
+            PngFilter::Paeth => FilterType::Paeth,
+            PngFilter::Adaptive => FilterType::Adaptive,
+        }
+    }
+}
+
+/// Encoder used to produce MV-HEVC spatial photos
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MVHEVCBackend {
+    /// Detect: native writer on macOS, then the `spatial` CLI, then ffmpeg.
+    /// An explicit `spatial_cli_path` or `ffmpeg_path` selects that tool.
+    #[default]
+    Auto,
+    /// Native ImageIO writer (macOS only)
+    Native,
+    /// External `spatial` CLI tool
+    SpatialCli,
+    /// ffmpeg with a multiview-enabled libx265 (x265 4.0+); writes a
+    /// single-frame MV-HEVC `.mov` since ffmpeg cannot mux HEIC
+    Ffmpeg,
+}
+
+impl MVHEVCBackend {
+    /// Extension of the spatial file this backend writes
+    pub fn extension(&self) -> &'static str {
+        match self {
+            MVHEVCBackend::Ffmpeg => "mov",
+            _ => "heic",
         }
     }
 }
 
-/// Configuration for MV-HEVC encoding via `spatial` CLI
+/// Configuration for MV-HEVC spatial photo encoding
 #[derive(Clone, Debug)]
 pub struct MVHEVCConfig {
+    /// Which encoder to use
+    pub backend: MVHEVCBackend,
+
     /// Path to the `spatial` CLI tool (defaults to "spatial" in PATH)
     pub spatial_cli_path: Option<PathBuf>,
 
+    /// Path to ffmpeg for the ffmpeg backend (defaults to "ffmpeg" in PATH)
+    pub ffmpeg_path: Option<PathBuf>,
+
     /// Enable MV-HEVC encoding
     pub enabled: bool,
 
@@ -103,17 +305,264 @@ pub struct MVHEVCConfig {
 
     /// Whether to keep the intermediate stereo image after HEVC encoding
     pub keep_intermediate: bool,
+
+    /// Stereo camera baseline recorded in the native writer's metadata (mm)
+    pub baseline_mm: f32,
+
+    /// Horizontal field of view recorded in the native writer's metadata (degrees)
+    pub horizontal_fov: f32,
+}
+
+impl MVHEVCConfig {
+    /// Resolve `Auto` to a concrete backend
+    ///
+    /// PATH detection runs once per process. When nothing is found the
+    /// `spatial` CLI is returned so the error explains how to install it.
+    pub fn resolve_backend(&self) -> MVHEVCBackend {
+        if self.backend != MVHEVCBackend::Auto {
+            return self.backend;
+        }
+        if self.spatial_cli_path.is_some() {
+            return MVHEVCBackend::SpatialCli;
+        }
+        if self.ffmpeg_path.is_some() {
+            return MVHEVCBackend::Ffmpeg;
+        }
+        if cfg!(target_os = "macos") {
+            return MVHEVCBackend::Native;
+        }
+
+        static DETECTED: OnceLock<MVHEVCBackend> = OnceLock::new();
+        *DETECTED.get_or_init(|| {
+            let backend = if tool_runs(Path::new("spatial"), &["--version"]) {
+                MVHEVCBackend::SpatialCli
+            } else if ffmpeg_has_libx265(Path::new("ffmpeg")) {
+                MVHEVCBackend::Ffmpeg
+            } else {
+                MVHEVCBackend::SpatialCli
+            };
+            tracing::debug!("Detected MV-HEVC backend: {:?}", backend);
+            backend
+        })
+    }
 }
 
 impl Default for MVHEVCConfig {
     fn default() -> Self {
         Self {
+            backend: MVHEVCBackend::Auto,
             spatial_cli_path: None,
+            ffmpeg_path: None,
             enabled: false,
             quality: 95,
             keep_intermediate: false,
+            baseline_mm: 65.0,
+            horizontal_fov: 80.0,
+        }
+    }
+}
+
+/// Naming pattern for the files derived from an output path: the eyes of
+/// `Separate` output and the MV-HEVC spatial file
+///
+/// Placeholders are `{stem}` (the output file name without extension),
+/// `{layout}` (the [`OutputFormat::name`], or `spatial` for the MV-HEVC
+/// file), `{eye}` (`L` or `R` for separate views, empty otherwise), and
+/// `{ext}` (the extension of the file being written). A placeholder that
+/// expands to nothing drops one separator (`_`, `-`, `.` or space) next to
+/// it, so `{stem}_{layout}_{eye}.{ext}` names the spatial file
+/// `photo_spatial.heic`. Files always land next to the output path.
+///
+/// ```
+/// use spatial_maker::FilenameTemplate;
+///
+/// let template = FilenameTemplate::new("{stem}-{eye}.{ext}")?;
+/// assert_eq!(template.render("photo", "separate", "L", "png")?, "photo-L.png");
+/// assert_eq!(template.render("photo", "spatial", "", "heic")?, "photo.heic");
+/// # Ok::<(), spatial_maker::SpatialError>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilenameTemplate(String);
+
+impl FilenameTemplate {
+    /// `{stem}_{eye}.{ext}`: `photo_L.jpg` and `photo_R.jpg` for separate
+    /// output, `photo.heic` for MV-HEVC
+    pub const DEFAULT: &'static str = "{stem}_{eye}.{ext}";
+
+    const PLACEHOLDERS: [&'static str; 4] = ["stem", "layout", "eye", "ext"];
+
+    /// Parse a template, rejecting unknown placeholders, unbalanced braces,
+    /// and directory separators
+    pub fn new(template: impl Into<String>) -> SpatialResult<Self> {
+        let template = FilenameTemplate(template.into());
+        if template.0.contains(['/', '\\']) {
+            return Err(SpatialError::ConfigError(format!(
+                "Filename template '{}' must not contain directory separators",
+                template.0
+            )));
+        }
+        template.render("stem", "layout", "eye", "ext")?;
+        Ok(template)
+    }
+
+    /// The template as written
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the template names the two eyes differently
+    pub fn has_eye(&self) -> bool {
+        self.0.contains("{eye}")
+    }
+
+    /// Expand the placeholders into a file name
+    pub fn render(&self, stem: &str, layout: &str, eye: &str, ext: &str) -> SpatialResult<String> {
+        let is_separator = |c: char| matches!(c, '_' | '-' | '.' | ' ');
+        let mut name = String::with_capacity(self.0.len() + stem.len());
+        let mut skip_separator = false;
+        let mut rest = self.0.as_str();
+
+        while let Some(open) = rest.find(['{', '}']) {
+            let mut literal = &rest[..open];
+            if skip_separator {
+                literal = literal.strip_prefix(is_separator).unwrap_or(literal);
+                skip_separator = false;
+            }
+            name.push_str(literal);
+
+            let close = rest[open..]
+                .find('}')
+                .filter(|_| rest.as_bytes()[open] == b'{')
+                .map(|close| open + close)
+                .ok_or_else(|| {
+                    SpatialError::ConfigError(format!(
+                        "Unbalanced braces in filename template '{}'",
+                        self.0
+                    ))
+                })?;
+            let value = match &rest[open + 1..close] {
+                "stem" => stem,
+                "layout" => layout,
+                "eye" => eye,
+                "ext" => ext,
+                other => {
+                    return Err(SpatialError::ConfigError(format!(
+                        "Unknown placeholder '{{{}}}' in filename template '{}'; use {}",
+                        other,
+                        self.0,
+                        Self::PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                    )))
+                }
+            };
+            if value.is_empty() {
+                if name.ends_with(is_separator) {
+                    name.pop();
+                } else if name.is_empty() {
+                    skip_separator = true;
+                }
+            }
+            name.push_str(value);
+            rest = &rest[close + 1..];
+        }
+        if skip_separator {
+            rest = rest.strip_prefix(is_separator).unwrap_or(rest);
+        }
+        name.push_str(rest);
+
+        if name.is_empty() {
+            return Err(SpatialError::ConfigError(format!(
+                "Filename template '{}' produced an empty file name",
+                self.0
+            )));
+        }
+        Ok(name)
+    }
+
+    /// Path of a file derived from `output_path`, in the same directory
+    fn path_for(
+        &self,
+        output_path: &Path,
+        layout: &str,
+        eye: &str,
+        ext: &str,
+    ) -> SpatialResult<PathBuf> {
+        let stem = output_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| {
+                SpatialError::ConfigError(format!("Invalid output path: {:?}", output_path))
+            })?;
+        let parent = output_path.parent().unwrap_or_else(|| Path::new("."));
+        Ok(parent.join(self.render(stem, layout, eye, ext)?))
+    }
+}
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        FilenameTemplate(Self::DEFAULT.to_string())
+    }
+}
+
+impl std::fmt::Display for FilenameTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A downscaled copy of the stereo image written alongside the full-size
+/// output, e.g. for galleries and web previews
+///
+/// Previews keep the output's layout and encoding and are named
+/// `{stem}_{suffix}.{ext}` next to the output path. They are scaled from the
+/// views already in memory, never upscaled, and are not MV-HEVC encoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreviewSize {
+    /// File name suffix, e.g. `1080p` for `photo_1080p.jpg`
+    pub suffix: String,
+
+    /// Maximum height of each eye in pixels
+    pub max_height: u32,
+}
+
+impl PreviewSize {
+    /// Create a preview size with the given suffix and eye height
+    pub fn new(suffix: impl Into<String>, max_height: u32) -> Self {
+        Self {
+            suffix: suffix.into(),
+            max_height,
         }
     }
+
+    /// 1080-pixel-high eyes, suffixed `_1080p`
+    pub fn hd() -> Self {
+        Self::new("1080p", 1080)
+    }
+
+    /// 256-pixel-high eyes, suffixed `_thumb`
+    pub fn thumbnail() -> Self {
+        Self::new("thumb", 256)
+    }
+
+    /// Path of this preview for `output_path`, before any separate-eye naming
+    fn path_for(&self, output_path: &Path) -> SpatialResult<PathBuf> {
+        let stem = output_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| {
+                SpatialError::ConfigError(format!("Invalid output path: {:?}", output_path))
+            })?;
+        let name = match output_path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => format!("{}_{}.{}", stem, self.suffix, ext),
+            None => format!("{}_{}", stem, self.suffix),
+        };
+        Ok(output_path.with_file_name(name))
+    }
+
+    /// Factor that brings an eye of `height` pixels down to `max_height`,
+    /// at most 1
+    fn scale(&self, height: u32) -> f64 {
+        (self.max_height as f64 / height.max(1) as f64).min(1.0)
+    }
 }
 
 /// Options for saving stereo images
@@ -127,6 +576,55 @@ pub struct OutputOptions {
 
     /// Optional MV-HEVC encoding configuration
     pub mvhevc: Option<MVHEVCConfig>,
+
+    /// Optional token checked before each write and while MV-HEVC encodes
+    pub cancel: Option<CancellationToken>,
+
+    /// Raw EXIF block (TIFF structure) to embed in JPEG output
+    ///
+    /// `process_photo` fills this from the input image when unset.
+    pub exif: Option<Vec<u8>>,
+
+    /// ICC color profile to embed in JPEG and PNG output
+    ///
+    /// `process_photo` fills this from the input image when unset, so
+    /// wide-gamut (e.g. Display P3) photos keep their colors.
+    pub icc_profile: Option<Vec<u8>>,
+
+    /// Squeeze each eye to half size along the stacking axis (half-SBS /
+    /// half-TB), so the stereo frame keeps the original image size
+    ///
+    /// Most 3D TVs and players expect this. Ignored for `Separate` output and
+    /// not allowed together with MV-HEVC, which stores full-size views.
+    pub half_resolution: bool,
+
+    /// Projection of the input photo
+    ///
+    /// `Vr180` generates the stereo pair in projection space and tags the
+    /// output with VR180 spherical metadata. It requires a side-by-side or
+    /// top-and-bottom layout at full resolution. `Panorama360` does the same
+    /// for full panoramas and requires a full-resolution top-and-bottom
+    /// layout.
+    pub projection: Projection,
+
+    /// Creator/copyright/software credits to embed
+    ///
+    /// Written as EXIF (JPEG) and XMP (JPEG and PNG), and as container
+    /// metadata when MV-HEVC is encoded with ffmpeg. The native and `spatial`
+    /// CLI MV-HEVC writers do not carry it.
+    pub attribution: Option<Attribution>,
+
+    /// How the eyes of `Separate` output and the MV-HEVC spatial file are
+    /// named; the stereo image itself is always written to the output path
+    pub filename_template: FilenameTemplate,
+
+    /// Downscaled copies to write alongside the full-size output (see
+    /// [`PreviewSize`])
+    pub previews: Vec<PreviewSize>,
+
+    /// File name tags, sidecar, and stream signalling for VR and 3D players
+    /// (see [`crate::naming`])
+    pub player: PlayerNaming,
 }
 
 impl Default for OutputOptions {
@@ -135,7 +633,166 @@ impl Default for OutputOptions {
             layout: OutputFormat::SideBySide,
             image_format: ImageEncoding::Jpeg { quality: 95 },
             mvhevc: None,
+            cancel: None,
+            exif: None,
+            icc_profile: None,
+            half_resolution: false,
+            projection: Projection::Rectilinear,
+            attribution: None,
+            filename_template: FilenameTemplate::default(),
+            previews: Vec::new(),
+            player: PlayerNaming::default(),
+        }
+    }
+}
+
+/// Colormap for depth visualization
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    /// Google's Turbo: high contrast, blue (far) to red (near)
+    #[default]
+    Turbo,
+    /// Perceptually uniform, dark purple (far) to yellow (near)
+    Viridis,
+}
+
+impl Colormap {
+    /// Get the canonical colormap name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Turbo => "turbo",
+            Colormap::Viridis => "viridis",
+        }
+    }
+
+    /// Map a value in 0-1 to RGB using a polynomial fit of the colormap
+    pub fn color(&self, value: f32) -> [u8; 3] {
+        let t = value.clamp(0.0, 1.0);
+        let coefficients: [[f32; 7]; 3] = match self {
+            Colormap::Turbo => [
+                [
+                    0.135_721_4,
+                    4.615_392_6,
+                    -42.660_32,
+                    132.131_08,
+                    -152.942_4,
+                    59.286_38,
+                    0.0,
+                ],
+                [
+                    0.091_402_61,
+                    2.194_188_4,
+                    4.842_966_6,
+                    -14.185_033,
+                    4.277_298_6,
+                    2.829_566,
+                    0.0,
+                ],
+                [
+                    0.106_673_3,
+                    12.641_946,
+                    -60.582_05,
+                    110.362_77,
+                    -89.903_11,
+                    27.348_25,
+                    0.0,
+                ],
+            ],
+            Colormap::Viridis => [
+                [
+                    0.277_727_33,
+                    0.105_093_04,
+                    -0.330_861_8,
+                    -4.634_230_4,
+                    6.228_27,
+                    4.776_385,
+                    -5.435_456,
+                ],
+                [
+                    0.005_407_344_5,
+                    1.404_613_5,
+                    0.214_847_56,
+                    -5.799_101,
+                    14.179_933,
+                    -13.745_145,
+                    4.645_852_6,
+                ],
+                [
+                    0.334_099_8,
+                    1.384_590_1,
+                    0.095_095_16,
+                    -19.332_441,
+                    56.690_55,
+                    -65.353_03,
+                    26.312_435,
+                ],
+            ],
+        };
+        coefficients.map(|c| {
+            let v = c.iter().rev().fold(0.0, |acc, &k| acc * t + k);
+            (v.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+    }
+}
+
+/// Layout of a Looking Glass quilt
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuiltConfig {
+    /// Views per row
+    pub columns: u32,
+
+    /// Rows of views
+    pub rows: u32,
+
+    /// Disparity between the leftmost and rightmost views, in source-image
+    /// pixels; views are spread evenly around the original camera
+    pub disparity_range: f32,
+
+    /// Width of the whole quilt in pixels; views are scaled to fit
+    pub quilt_width: u32,
+
+    /// Append Looking Glass's `_qs{columns}x{rows}a{aspect}` tag to the file
+    /// name so the display software picks up the layout
+    pub tag_filename: bool,
+}
+
+impl Default for QuiltConfig {
+    fn default() -> Self {
+        Self {
+            columns: 5,
+            rows: 9,
+            disparity_range: 60.0,
+            quilt_width: 4096,
+            tag_filename: true,
+        }
+    }
+}
+
+impl QuiltConfig {
+    /// Total number of views
+    pub fn view_count(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    /// Horizontal shift of view `index` at full source resolution
+    ///
+    /// View 0 is the leftmost camera, the last view the rightmost.
+    pub fn view_shift(&self, index: u32) -> f32 {
+        let count = self.view_count();
+        if count <= 1 {
+            return 0.0;
         }
+        self.disparity_range * (index as f32 / (count - 1) as f32 - 0.5)
+    }
+
+    /// Looking Glass file name tag, e.g. `_qs5x9a0.75`
+    pub fn filename_tag(&self, aspect: f32) -> String {
+        format!(
+            "_qs{}x{}a{}",
+            self.columns,
+            self.rows,
+            (aspect * 10000.0).round() / 10000.0
+        )
     }
 }
 
@@ -166,46 +823,361 @@ pub fn save_stereo_image(
     output_path: impl AsRef<Path>,
     options: OutputOptions,
 ) -> SpatialResult<()> {
-    let output_path = output_path.as_ref();
+    validate_options(&options)?;
+    let output_path = &naming::player_path(output_path.as_ref(), &options);
 
     tracing::info!("💾 Saving stereo image to {:?}", output_path);
+    cancel::check(options.cancel.as_ref())?;
 
     // Create parent directory if needed
     if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            SpatialError::ImageError(format!("Failed to create output directory: {}", e))
-        })?;
+        std::fs::create_dir_all(parent)
+            .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+    }
+
+    // Squeeze each eye for half-SBS / half-TB
+    let squeezed = options
+        .half_resolution
+        .then(|| squeeze_views(left, right, options.layout))
+        .flatten();
+    let (left_view, right_view) = match &squeezed {
+        Some((left, right)) => (left, right),
+        None => (left, right),
+    };
+
+    write_stereo_file(
+        left_view,
+        right_view,
+        (left.width(), left.height()),
+        output_path,
+        &options,
+    )?;
+
+    // Previews reuse the views in memory rather than re-reading the output
+    for preview in &options.previews {
+        cancel::check(options.cancel.as_ref())?;
+        let preview_path = preview.path_for(output_path)?;
+        let scale = preview.scale(left.height());
+        let scaled = |(width, height): (u32, u32)| {
+            (
+                ((width as f64 * scale).round() as u32).max(1),
+                ((height as f64 * scale).round() as u32).max(1),
+            )
+        };
+        let (width, height) = scaled((left.width(), left.height()));
+        let (view_width, view_height) = scaled((left_view.width(), left_view.height()));
+        let resize = |view: &DynamicImage| {
+            view.resize_exact(
+                view_width,
+                view_height,
+                image::imageops::FilterType::Lanczos3,
+            )
+        };
+        write_stereo_file(
+            &resize(left_view),
+            &resize(right_view),
+            (width, height),
+            &preview_path,
+            &options,
+        )?;
+        tracing::info!(
+            "   Preview: {:?} ({}x{} per eye)",
+            preview_path,
+            width,
+            height
+        );
+    }
+    naming::write_sidecar(output_path, &options)?;
+
+    // Handle optional MV-HEVC encoding
+    if let Some(mvhevc_config) = &options.mvhevc {
+        apply_mvhevc(
+            left,
+            right,
+            output_path,
+            options.layout,
+            options.image_format,
+            mvhevc_config,
+            &options.filename_template,
+            options.attribution.as_ref(),
+            options.cancel.as_ref(),
+        )?;
     }
 
+    tracing::info!("✅ Stereo image saved to {:?}", output_path);
+    Ok(())
+}
+
+/// Compose `left_view` and `right_view` into `output_path` per the layout and
+/// embed metadata; `eye_size` is the unsqueezed size of one eye
+fn write_stereo_file(
+    left_view: &DynamicImage,
+    right_view: &DynamicImage,
+    eye_size: (u32, u32),
+    output_path: &Path,
+    options: &OutputOptions,
+) -> SpatialResult<()> {
     // Generate stereo image based on layout
     match options.layout {
         OutputFormat::SideBySide => {
-            save_side_by_side(left, right, output_path, options.image_format)?;
+            save_side_by_side(left_view, right_view, output_path, options.image_format)?;
         }
         OutputFormat::TopAndBottom => {
-            save_top_and_bottom(left, right, output_path, options.image_format)?;
+            save_top_and_bottom(left_view, right_view, output_path, options.image_format)?;
         }
         OutputFormat::Separate => {
-            save_separate(left, right, output_path, options.image_format)?;
+            save_separate(
+                left_view,
+                right_view,
+                output_path,
+                options.image_format,
+                &options.filename_template,
+            )?;
+        }
+        OutputFormat::CrossEye => {
+            save_side_by_side(right_view, left_view, output_path, options.image_format)?;
+        }
+        OutputFormat::MirroredSideBySide => {
+            let mirrored = right_view.fliph();
+            save_side_by_side(left_view, &mirrored, output_path, options.image_format)?;
         }
     }
 
-    // Handle optional MV-HEVC encoding
-    if let Some(mvhevc_config) = options.mvhevc {
-        if mvhevc_config.enabled {
-            encode_mvhevc(output_path, &mvhevc_config)?;
-            if !mvhevc_config.keep_intermediate {
-                if let Err(e) = std::fs::remove_file(output_path) {
-                    tracing::warn!("Failed to remove intermediate stereo image: {}", e);
+    // VR viewers read the projection from GPano XMP describing one eye
+    let mut xmp_descriptions = Vec::new();
+    match options.projection {
+        Projection::Vr180 => xmp_descriptions.push(crate::metadata::vr180_gpano_description(
+            eye_size.0, eye_size.1,
+        )),
+        Projection::Panorama360 => xmp_descriptions.push(
+            crate::metadata::panorama_gpano_description(eye_size.0, eye_size.1),
+        ),
+        Projection::Rectilinear => {}
+    }
+    if let Some(attribution) = &options.attribution {
+        xmp_descriptions.push(crate::metadata::attribution_xmp_description(attribution));
+    }
+    let xmp =
+        (!xmp_descriptions.is_empty()).then(|| crate::metadata::xmp_packet(&xmp_descriptions));
+
+    // Embed EXIF/ICC/XMP metadata (before MV-HEVC so the intermediate carries it)
+    if options.exif.is_some() || options.icc_profile.is_some() || xmp.is_some() {
+        let targets = match options.layout {
+            OutputFormat::Separate => {
+                let (left_path, right_path) = separate_paths(
+                    output_path,
+                    options.image_format,
+                    &options.filename_template,
+                )?;
+                vec![left_path, right_path]
+            }
+            _ => vec![output_path.to_path_buf()],
+        };
+        for target in targets {
+            embed_metadata_in_file(&target, options, xmp.as_deref())?;
+        }
+    }
+    Ok(())
+}
+
+/// Encode an already-saved stereo pair to MV-HEVC if enabled, removing the
+/// intermediate image(s) unless `keep_intermediate` is set
+///
+/// The spatial file is written next to `output_path`, named by `template`
+/// with the backend's extension (see [`MVHEVCBackend::extension`]).
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_mvhevc(
+    left: &DynamicImage,
+    right: &DynamicImage,
+    output_path: &Path,
+    layout: OutputFormat,
+    encoding: ImageEncoding,
+    config: &MVHEVCConfig,
+    template: &FilenameTemplate,
+    attribution: Option<&Attribution>,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+
+    cancel::check(cancel)?;
+    let backend = config.resolve_backend();
+    let hevc_path = spatial_path(output_path, backend, template)?;
+    match backend {
+        MVHEVCBackend::Native => {
+            encode_native(left, right, &hevc_path, config, cancel)?;
+        }
+        MVHEVCBackend::Ffmpeg => {
+            let inputs = match layout {
+                OutputFormat::Separate => {
+                    let (left_path, right_path) = separate_paths(output_path, encoding, template)?;
+                    vec![left_path, right_path]
                 }
+                _ => vec![output_path.to_path_buf()],
+            };
+            encode_mvhevc_ffmpeg(&inputs, &hevc_path, layout, config, attribution, cancel)?;
+        }
+        MVHEVCBackend::SpatialCli => {
+            if !matches!(
+                layout,
+                OutputFormat::SideBySide | OutputFormat::TopAndBottom
+            ) {
+                return Err(SpatialError::ConfigError(format!(
+                    "The `spatial` CLI needs a side-by-side or top-and-bottom image. Use the native (macOS) or ffmpeg backend for {} output.",
+                    layout.name()
+                )));
             }
+            encode_mvhevc(output_path, &hevc_path, layout, config, cancel)?;
         }
+        MVHEVCBackend::Auto => unreachable!("resolve_backend always picks a backend"),
     }
 
-    tracing::info!("✅ Stereo image saved to {:?}", output_path);
+    if !config.keep_intermediate {
+        let intermediates = match layout {
+            OutputFormat::Separate => {
+                let (left_path, right_path) = separate_paths(output_path, encoding, template)?;
+                vec![left_path, right_path]
+            }
+            _ => vec![output_path.to_path_buf()],
+        };
+        for path in intermediates {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("Failed to remove intermediate stereo image: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the spatial photo with the native ImageIO writer
+#[cfg(target_os = "macos")]
+fn encode_native(
+    left: &DynamicImage,
+    right: &DynamicImage,
+    hevc_path: &Path,
+    config: &MVHEVCConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    let params = crate::spatial_heic::StereoCameraParams {
+        baseline_mm: config.baseline_mm,
+        horizontal_fov: config.horizontal_fov,
+        quality: config.quality as f32 / 100.0,
+    };
+    crate::spatial_heic::write_spatial_heic(left, right, hevc_path, params, cancel)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn encode_native(
+    _left: &DynamicImage,
+    _right: &DynamicImage,
+    _hevc_path: &Path,
+    _config: &MVHEVCConfig,
+    _cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    Err(SpatialError::ConfigError(
+        "Native spatial HEIC writing is only available on macOS; use the `spatial` CLI backend"
+            .to_string(),
+    ))
+}
+
+/// Check option combinations that cannot be written
+pub(crate) fn validate_options(options: &OutputOptions) -> SpatialResult<()> {
+    let mvhevc = options.mvhevc.as_ref().is_some_and(|c| c.enabled);
+    if options.half_resolution && mvhevc {
+        return Err(SpatialError::ConfigError(
+            "Half-resolution stereo cannot be MV-HEVC encoded; spatial photos store full-size views"
+                .to_string(),
+        ));
+    }
+    if options.image_format == ImageEncoding::Exr && mvhevc {
+        return Err(SpatialError::ConfigError(
+            "EXR stereo cannot be MV-HEVC encoded; spatial photos are 8-bit, so write JPEG or PNG"
+                .to_string(),
+        ));
+    }
+    if options.layout == OutputFormat::Separate && !options.filename_template.has_eye() {
+        return Err(SpatialError::ConfigError(format!(
+            "Filename template '{}' needs an {{eye}} placeholder for separate output, or both eyes get the same name",
+            options.filename_template
+        )));
+    }
+    naming::validate(options)?;
+    for (index, preview) in options.previews.iter().enumerate() {
+        if preview.max_height == 0 {
+            return Err(SpatialError::ConfigError(format!(
+                "Preview '{}' needs a height of at least 1 pixel",
+                preview.suffix
+            )));
+        }
+        if preview.suffix.is_empty() || preview.suffix.contains(['/', '\\']) {
+            return Err(SpatialError::ConfigError(format!(
+                "Preview suffix '{}' must be non-empty and must not contain directory separators",
+                preview.suffix
+            )));
+        }
+        if options.previews[..index]
+            .iter()
+            .any(|other| other.suffix == preview.suffix)
+        {
+            return Err(SpatialError::ConfigError(format!(
+                "Two previews share the suffix '{}' and would overwrite each other",
+                preview.suffix
+            )));
+        }
+    }
+    if options.projection == Projection::Vr180 {
+        if !matches!(
+            options.layout,
+            OutputFormat::SideBySide | OutputFormat::TopAndBottom
+        ) {
+            return Err(SpatialError::ConfigError(format!(
+                "VR180 output requires side-by-side or top-and-bottom layout, not {}",
+                options.layout.name()
+            )));
+        }
+        if options.half_resolution || mvhevc {
+            return Err(SpatialError::ConfigError(
+                "VR180 output stores full-resolution equirectangular eyes; disable half resolution and MV-HEVC"
+                    .to_string(),
+            ));
+        }
+    }
+    if options.projection == Projection::Panorama360 {
+        if options.layout != OutputFormat::TopAndBottom {
+            return Err(SpatialError::ConfigError(format!(
+                "360° panorama output requires top-and-bottom layout, not {}",
+                options.layout.name()
+            )));
+        }
+        if options.half_resolution || mvhevc {
+            return Err(SpatialError::ConfigError(
+                "360° panorama output stores full-resolution equirectangular eyes; disable half resolution and MV-HEVC"
+                    .to_string(),
+            ));
+        }
+    }
     Ok(())
 }
 
+/// Halve each view along the layout's stacking axis, or `None` for layouts
+/// without one
+fn squeeze_views(
+    left: &DynamicImage,
+    right: &DynamicImage,
+    layout: OutputFormat,
+) -> Option<(DynamicImage, DynamicImage)> {
+    let squeeze = |image: &DynamicImage| {
+        let (width, height) = match layout {
+            OutputFormat::TopAndBottom => (image.width(), (image.height() / 2).max(1)),
+            OutputFormat::Separate => return None,
+            _ => ((image.width() / 2).max(1), image.height()),
+        };
+        Some(image.resize_exact(width, height, image::imageops::FilterType::Lanczos3))
+    };
+    Some((squeeze(left)?, squeeze(right)?))
+}
+
 /// Create and save a side-by-side stereo image (left | right)
 fn save_side_by_side(
     left: &DynamicImage,
@@ -220,7 +1192,7 @@ fn save_side_by_side(
 
     // Ensure both images have the same height
     if left_height != right_height {
-        return Err(SpatialError::ImageError(format!(
+        return Err(SpatialError::ConfigError(format!(
             "Left and right images must have the same height: {} != {}",
             left_height, right_height
         )));
@@ -229,13 +1201,13 @@ fn save_side_by_side(
     let combined_width = left_width + right_width;
     let combined_height = left_height;
 
-    let mut combined = DynamicImage::new_rgb8(combined_width, combined_height);
-
-    // Paste left image
-    image::imageops::overlay(&mut combined, left, 0, 0);
-
-    // Paste right image
-    image::imageops::overlay(&mut combined, right, left_width as i64, 0);
+    // Left image, then right image beside it
+    let combined = compose(
+        combined_width,
+        combined_height,
+        [(left, 0, 0), (right, left_width as i64, 0)],
+        encoding,
+    );
 
     save_image(&combined, output_path, encoding)
 }
@@ -254,7 +1226,7 @@ fn save_top_and_bottom(
 
     // Ensure both images have the same width
     if left_width != right_width {
-        return Err(SpatialError::ImageError(format!(
+        return Err(SpatialError::ConfigError(format!(
             "Left and right images must have the same width: {} != {}",
             left_width, right_width
         )));
@@ -263,34 +1235,56 @@ fn save_top_and_bottom(
     let combined_width = left_width;
     let combined_height = left_height + right_height;
 
-    let mut combined = DynamicImage::new_rgb8(combined_width, combined_height);
-
-    // Paste left image at top
-    image::imageops::overlay(&mut combined, left, 0, 0);
-
-    // Paste right image at bottom
-    image::imageops::overlay(&mut combined, right, 0, left_height as i64);
+    // Left image at top, right image at bottom
+    let combined = compose(
+        combined_width,
+        combined_height,
+        [(left, 0, 0), (right, 0, left_height as i64)],
+        encoding,
+    );
 
     save_image(&combined, output_path, encoding)
 }
 
-/// Save left and right images as separate files with _L and _R suffixes
+/// Paste views at their (x, y) offsets onto one image, in float for EXR so
+/// no precision is lost on the way, and with alpha when a view has it
+fn compose(
+    width: u32,
+    height: u32,
+    views: [(&DynamicImage, i64, i64); 2],
+    encoding: ImageEncoding,
+) -> DynamicImage {
+    if encoding == ImageEncoding::Exr {
+        let mut combined = image::Rgb32FImage::new(width, height);
+        for (view, x, y) in views {
+            image::imageops::overlay(&mut combined, &view.to_rgb32f(), x, y);
+        }
+        DynamicImage::ImageRgb32F(combined)
+    } else if views.iter().any(|(view, _, _)| view.color().has_alpha()) {
+        // Copied rather than blended, so transparency survives as it is
+        let mut combined = image::RgbaImage::new(width, height);
+        for (view, x, y) in views {
+            image::imageops::replace(&mut combined, &view.to_rgba8(), x, y);
+        }
+        DynamicImage::ImageRgba8(combined)
+    } else {
+        let mut combined = DynamicImage::new_rgb8(width, height);
+        for (view, x, y) in views {
+            image::imageops::overlay(&mut combined, view, x, y);
+        }
+        combined
+    }
+}
+
+/// Save left and right images as separate files named by `template`
 fn save_separate(
     left: &DynamicImage,
     right: &DynamicImage,
     output_path: &Path,
     encoding: ImageEncoding,
+    template: &FilenameTemplate,
 ) -> SpatialResult<()> {
-    let stem = output_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .ok_or_else(|| SpatialError::ImageError("Invalid output path".to_string()))?;
-
-    let parent = output_path.parent().unwrap_or_else(|| Path::new("."));
-    let ext = encoding.extension();
-
-    let left_path = parent.join(format!("{}_L.{}", stem, ext));
-    let right_path = parent.join(format!("{}_R.{}", stem, ext));
+    let (left_path, right_path) = separate_paths(output_path, encoding, template)?;
 
     save_image(left, &left_path, encoding)?;
     save_image(right, &right_path, encoding)?;
@@ -302,39 +1296,605 @@ fn save_separate(
     Ok(())
 }
 
-/// Save an image with the specified encoding
-fn save_image(image: &DynamicImage, path: &Path, encoding: ImageEncoding) -> SpatialResult<()> {
-    match encoding {
-        ImageEncoding::Jpeg { quality } => {
-            let rgb_image = image.to_rgb8();
-            let file = std::fs::File::create(path).map_err(|e| {
-                SpatialError::ImageError(format!("Failed to create output file: {}", e))
+/// Render and save a Looking Glass quilt from an image and its depth map
+///
+/// Views are synthesized from left to right and tiled from the bottom-left
+/// corner, row by row upwards, as Looking Glass software expects.
+///
+/// Returns the path written, which carries the quilt tag when
+/// `tag_filename` is set.
+pub fn save_quilt(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    output_path: impl AsRef<Path>,
+    config: &QuiltConfig,
+    encoding: ImageEncoding,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<PathBuf> {
+    if config.columns == 0 || config.rows == 0 || config.quilt_width < config.columns {
+        return Err(SpatialError::ConfigError(format!(
+            "Invalid quilt layout: {}x{} views at {} px wide",
+            config.columns, config.rows, config.quilt_width
+        )));
+    }
+
+    let aspect = image.width() as f32 / image.height() as f32;
+    let tile_width = config.quilt_width / config.columns;
+    let tile_height = ((tile_width as f32 / aspect).round() as u32).max(1);
+    let scale = tile_width as f32 / image.width() as f32;
+
+    let output_path = output_path.as_ref();
+    let output_path = if config.tag_filename {
+        let stem = output_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| {
+                SpatialError::ConfigError(format!("Invalid output path: {:?}", output_path))
             })?;
+        output_path.with_file_name(format!(
+            "{}{}.{}",
+            stem,
+            config.filename_tag(aspect),
+            encoding.extension()
+        ))
+    } else {
+        output_path.to_path_buf()
+    };
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+    }
 
-            let mut jpeg_encoder =
-                image::codecs::jpeg::JpegEncoder::new_with_quality(file, quality);
-            jpeg_encoder
-                .encode(
-                    rgb_image.as_ref(),
-                    rgb_image.width(),
-                    rgb_image.height(),
-                    image::ColorType::Rgb8,
-                )
-                .map_err(|e| SpatialError::ImageError(format!("Failed to encode JPEG: {}", e)))?;
-        }
-        ImageEncoding::Png => {
-            image
-                .save(path)
-                .map_err(|e| SpatialError::ImageError(format!("Failed to save PNG: {}", e)))?;
-        }
+    tracing::info!(
+        "🔲 Rendering {}x{} quilt ({} views, {}x{} each) to {:?}",
+        config.columns,
+        config.rows,
+        config.view_count(),
+        tile_width,
+        tile_height,
+        output_path
+    );
+
+    // Warp at tile resolution; depth is sampled by relative position
+    let tile_source = image.resize_exact(
+        tile_width,
+        tile_height,
+        image::imageops::FilterType::Lanczos3,
+    );
+    let mut quilt = DynamicImage::new_rgb8(tile_width * config.columns, tile_height * config.rows);
+
+    for index in 0..config.view_count() {
+        cancel::check(cancel)?;
+        let view = crate::stereo::synthesize_view(
+            &tile_source,
+            depth,
+            config.view_shift(index) * scale,
+            cancel,
+        )?;
+        let column = index % config.columns;
+        let row_from_bottom = index / config.columns;
+        let x = column * tile_width;
+        let y = (config.rows - 1 - row_from_bottom) * tile_height;
+        image::imageops::overlay(&mut quilt, &view, x as i64, y as i64);
     }
 
-    Ok(())
+    cancel::check(cancel)?;
+    save_image(&quilt, &output_path, encoding)?;
+    Ok(output_path)
 }
 
-/// Encode stereo image to MV-HEVC using the `spatial` CLI
-fn encode_mvhevc(stereo_path: &Path, config: &MVHEVCConfig) -> SpatialResult<()> {
-    tracing::info!("🎬 Encoding MV-HEVC with `spatial` CLI");
+/// Save a colormapped PNG of a depth map
+///
+/// Near (high) depth maps to the warm end of the colormap. With `overlay`,
+/// the colormap is resized to the source image and blended over it at the
+/// given opacity (0-1), which makes depth edges easy to compare with object
+/// edges; otherwise the PNG has the depth map's resolution.
+pub fn save_depth_visualization(
+    depth: &Array2<f32>,
+    output_path: impl AsRef<Path>,
+    colormap: Colormap,
+    overlay: Option<(&DynamicImage, f32)>,
+) -> SpatialResult<()> {
+    let output_path = output_path.as_ref();
+    let (height, width) = depth.dim();
+    if width == 0 || height == 0 {
+        return Err(SpatialError::ConfigError("Depth map is empty".to_string()));
+    }
+
+    tracing::info!(
+        "🌈 Saving {} depth visualization to {:?}",
+        colormap.name(),
+        output_path
+    );
+
+    let mut visualization = image::RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        image::Rgb(colormap.color(depth[[y as usize, x as usize]]))
+    });
+
+    if let Some((source, opacity)) = overlay {
+        let opacity = opacity.clamp(0.0, 1.0);
+        let source = source.to_rgb8();
+        let colors = image::imageops::resize(
+            &visualization,
+            source.width(),
+            source.height(),
+            image::imageops::FilterType::Triangle,
+        );
+        visualization = image::RgbImage::from_fn(source.width(), source.height(), |x, y| {
+            let base = source.get_pixel(x, y);
+            let color = colors.get_pixel(x, y);
+            image::Rgb(std::array::from_fn(|c| {
+                (base[c] as f32 * (1.0 - opacity) + color[c] as f32 * opacity).round() as u8
+            }))
+        });
+    }
+
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+    }
+    visualization
+        .save_with_format(output_path, image::ImageFormat::Png)
+        .map_err(|e| SpatialError::image("Failed to save depth visualization", e))
+}
+
+/// Save a depth map as a single-channel 32-bit float EXR
+///
+/// The `Z` channel holds the normalized values unchanged (0-1, higher =
+/// closer); pass [`DepthMap::invert`](crate::DepthMap::invert) first for
+/// distance-style depth. Unlike 8- or 16-bit PNGs, nothing is quantized.
+#[cfg(feature = "exr")]
+pub fn save_depth_exr(depth: &Array2<f32>, output_path: impl AsRef<Path>) -> SpatialResult<()> {
+    use exr::prelude::{Image, SpecificChannels, WritableImage};
+
+    let output_path = output_path.as_ref();
+    let (height, width) = depth.dim();
+    if width == 0 || height == 0 {
+        return Err(SpatialError::ConfigError("Depth map is empty".to_string()));
+    }
+
+    tracing::info!("💾 Saving float depth to {:?}", output_path);
+
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+    }
+    let channels = SpecificChannels::build()
+        .with_channel("Z")
+        .with_pixel_fn(|position| (depth[[position.y(), position.x()]],));
+    Image::from_channels((width, height), channels)
+        .write()
+        .to_file(output_path)
+        .map_err(|e| SpatialError::image("Failed to save depth EXR", encoding_error("EXR", e)))
+}
+
+/// Paths of the left and right files written by `OutputFormat::Separate`
+fn separate_paths(
+    output_path: &Path,
+    encoding: ImageEncoding,
+    template: &FilenameTemplate,
+) -> SpatialResult<(PathBuf, PathBuf)> {
+    let layout = OutputFormat::Separate.name();
+    let ext = encoding.extension();
+    Ok((
+        template.path_for(output_path, layout, "L", ext)?,
+        template.path_for(output_path, layout, "R", ext)?,
+    ))
+}
+
+/// Path of the spatial file `backend` writes for `output_path`
+fn spatial_path(
+    output_path: &Path,
+    backend: MVHEVCBackend,
+    template: &FilenameTemplate,
+) -> SpatialResult<PathBuf> {
+    template.path_for(output_path, "spatial", "", backend.extension())
+}
+
+/// Files a save with these options leaves on disk for `output_path`
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn produced_paths(
+    output_path: &Path,
+    options: &OutputOptions,
+) -> SpatialResult<Vec<PathBuf>> {
+    let output_path = &naming::player_path(output_path, options);
+    let mut paths = match options.layout {
+        OutputFormat::Separate => {
+            let (left_path, right_path) = separate_paths(
+                output_path,
+                options.image_format,
+                &options.filename_template,
+            )?;
+            vec![left_path, right_path]
+        }
+        _ => vec![output_path.to_path_buf()],
+    };
+
+    if let Some(mvhevc) = options.mvhevc.as_ref().filter(|c| c.enabled) {
+        let hevc_path = spatial_path(
+            output_path,
+            mvhevc.resolve_backend(),
+            &options.filename_template,
+        )?;
+        if mvhevc.keep_intermediate {
+            paths.push(hevc_path);
+        } else {
+            paths = vec![hevc_path];
+        }
+    }
+
+    for preview in &options.previews {
+        let preview_path = preview.path_for(output_path)?;
+        match options.layout {
+            OutputFormat::Separate => {
+                let (left_path, right_path) = separate_paths(
+                    &preview_path,
+                    options.image_format,
+                    &options.filename_template,
+                )?;
+                paths.extend([left_path, right_path]);
+            }
+            _ => paths.push(preview_path),
+        }
+    }
+
+    if options.player.json_sidecar && naming::sidecar_for(options).is_some() {
+        paths.push(naming::sidecar_path(output_path));
+    }
+
+    Ok(paths)
+}
+
+/// Rewrite a saved image with EXIF (JPEG only), ICC profile and XMP segments
+/// inserted
+fn embed_metadata_in_file(
+    path: &Path,
+    options: &OutputOptions,
+    xmp: Option<&str>,
+) -> SpatialResult<()> {
+    let mut data = std::fs::read(path)
+        .map_err(|e| SpatialError::io(format!("Failed to read {:?}", path), e))?;
+
+    match options.image_format {
+        ImageEncoding::Jpeg { .. } => {
+            let exif = match &options.attribution {
+                Some(attribution) => {
+                    let base = options
+                        .exif
+                        .clone()
+                        .unwrap_or_else(crate::metadata::empty_exif);
+                    crate::metadata::set_exif_attribution(&base, attribution)
+                        .or(options.exif.clone())
+                }
+                None => options.exif.clone(),
+            };
+            if let Some(exif) = &exif {
+                data = crate::metadata::embed_exif_in_jpeg(&data, exif);
+            }
+            if let Some(icc) = &options.icc_profile {
+                data = crate::metadata::embed_icc_in_jpeg(&data, icc);
+            }
+            if let Some(xmp) = xmp {
+                data = crate::metadata::embed_xmp_in_jpeg(&data, xmp);
+            }
+        }
+        ImageEncoding::Png { .. } => {
+            if let Some(icc) = &options.icc_profile {
+                data = crate::metadata::embed_icc_in_png(&data, icc);
+            }
+            if let Some(xmp) = xmp {
+                data = crate::metadata::embed_xmp_in_png(&data, xmp);
+            }
+        }
+        ImageEncoding::WebP { .. } => {
+            tracing::debug!("Skipping EXIF/ICC/XMP metadata for WebP output");
+            return Ok(());
+        }
+        ImageEncoding::Avif { .. } => {
+            tracing::debug!("Skipping EXIF/ICC/XMP metadata for AVIF output");
+            return Ok(());
+        }
+        ImageEncoding::Jxl => {
+            tracing::debug!("Skipping EXIF/ICC/XMP metadata for JPEG XL output");
+            return Ok(());
+        }
+        ImageEncoding::Heic { .. } => {
+            tracing::debug!("Skipping EXIF/ICC/XMP metadata for HEIC output");
+            return Ok(());
+        }
+        // Linear float has no use for an sRGB profile, and EXR keeps no EXIF
+        ImageEncoding::Exr => {
+            tracing::debug!("Skipping EXIF/ICC/XMP metadata for EXR output");
+            return Ok(());
+        }
+    }
+
+    std::fs::write(path, data)
+        .map_err(|e| SpatialError::io(format!("Failed to write {:?}", path), e))?;
+    Ok(())
+}
+
+/// Save an image with the specified encoding
+fn save_image(image: &DynamicImage, path: &Path, encoding: ImageEncoding) -> SpatialResult<()> {
+    match encoding {
+        ImageEncoding::Jpeg { quality } => {
+            let rgb_image = image.to_rgb8();
+            let file = std::fs::File::create(path)
+                .map_err(|e| SpatialError::io("Failed to create output file", e))?;
+
+            let mut jpeg_encoder =
+                image::codecs::jpeg::JpegEncoder::new_with_quality(file, quality);
+            jpeg_encoder
+                .encode(
+                    rgb_image.as_ref(),
+                    rgb_image.width(),
+                    rgb_image.height(),
+                    image::ColorType::Rgb8,
+                )
+                .map_err(|e| SpatialError::image("Failed to encode JPEG", e))?;
+        }
+        ImageEncoding::Png {
+            compression,
+            filter,
+            sixteen_bit,
+        } => {
+            let alpha = image.color().has_alpha();
+            let image = if sixteen_bit && alpha {
+                Cow::Owned(DynamicImage::ImageRgba16(image.to_rgba16()))
+            } else if sixteen_bit {
+                Cow::Owned(DynamicImage::ImageRgb16(image.to_rgb16()))
+            } else if image.color() == ColorType::Rgba32F {
+                // PNG has no float samples
+                Cow::Owned(DynamicImage::ImageRgba8(image.to_rgba8()))
+            } else if image.color() == ColorType::Rgb32F {
+                Cow::Owned(DynamicImage::ImageRgb8(image.to_rgb8()))
+            } else {
+                Cow::Borrowed(image)
+            };
+            let file = std::fs::File::create(path)
+                .map_err(|e| SpatialError::io("Failed to create output file", e))?;
+            let encoder = image::codecs::png::PngEncoder::new_with_quality(
+                std::io::BufWriter::new(file),
+                compression.compression_type(),
+                filter.filter_type(),
+            );
+            image
+                .write_with_encoder(encoder)
+                .map_err(|e| SpatialError::image("Failed to save PNG", e))?;
+        }
+        ImageEncoding::WebP { quality, lossless } => {
+            let data = encode_webp(image, quality, lossless)?;
+            std::fs::write(path, data)
+                .map_err(|e| SpatialError::io("Failed to write output file", e))?;
+        }
+        ImageEncoding::Avif { quality, speed } => {
+            let rgb_image = image.to_rgb8();
+            let data = encode_avif(&rgb_image, quality.clamp(1, 100), speed.clamp(1, 10))?;
+            std::fs::write(path, data)
+                .map_err(|e| SpatialError::io("Failed to write output file", e))?;
+        }
+        ImageEncoding::Jxl => {
+            let data = encode_jxl(&image.to_rgb8())?;
+            std::fs::write(path, data)
+                .map_err(|e| SpatialError::io("Failed to write output file", e))?;
+        }
+        ImageEncoding::Heic { quality } => {
+            let data = encode_heic(&image.to_rgb8(), quality.clamp(1, 100))?;
+            std::fs::write(path, data)
+                .map_err(|e| SpatialError::io("Failed to write output file", e))?;
+        }
+        ImageEncoding::Exr => {
+            if !cfg!(feature = "exr") {
+                return Err(SpatialError::ConfigError(
+                    "EXR output needs the EXR encoder: cargo build --features exr".to_string(),
+                ));
+            }
+            DynamicImage::ImageRgb32F(image.to_rgb32f())
+                .save_with_format(path, image::ImageFormat::OpenExr)
+                .map_err(|e| SpatialError::image("Failed to save EXR", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Encode an image as 8-bit RGB, or RGBA when it has alpha, WebP: lossless
+/// with image's own encoder, lossy with libwebp
+fn encode_webp(image: &DynamicImage, quality: u8, lossless: bool) -> SpatialResult<Vec<u8>> {
+    let (pixels, color) = if image.color().has_alpha() {
+        (image.to_rgba8().into_raw(), image::ColorType::Rgba8)
+    } else {
+        (image.to_rgb8().into_raw(), image::ColorType::Rgb8)
+    };
+    let (width, height) = (image.width(), image.height());
+
+    if lossless {
+        let mut data = Vec::new();
+        image::codecs::webp::WebPEncoder::new_lossless(&mut data)
+            .encode(&pixels, width, height, color)
+            .map_err(|e| SpatialError::image("Failed to encode WebP", e))?;
+        return Ok(data);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let encoder = if color == image::ColorType::Rgba8 {
+            webp::Encoder::from_rgba(&pixels, width, height)
+        } else {
+            webp::Encoder::from_rgb(&pixels, width, height)
+        };
+        Ok(encoder.encode(quality.clamp(1, 100) as f32).to_vec())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = quality;
+        Err(SpatialError::ConfigError(
+            "Lossy WebP needs libwebp, which is not built for wasm32; use lossless WebP"
+                .to_string(),
+        ))
+    }
+}
+
+/// Encode an RGB image as AVIF with ravif, or with libheif when only the
+/// `heic` feature is enabled
+fn encode_avif(image: &image::RgbImage, quality: u8, speed: u8) -> SpatialResult<Vec<u8>> {
+    #[cfg(feature = "avif")]
+    {
+        use image::ImageEncoder;
+
+        let mut data = Vec::new();
+        image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut data, speed, quality)
+            .write_image(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                image::ColorType::Rgb8,
+            )
+            .map_err(|e| SpatialError::image("Failed to encode AVIF", e))?;
+        Ok(data)
+    }
+
+    #[cfg(all(feature = "heic", not(feature = "avif")))]
+    {
+        encode_heif(
+            image,
+            libheif_rs::CompressionFormat::Av1,
+            quality,
+            Some(speed),
+        )
+    }
+
+    #[cfg(not(any(feature = "avif", feature = "heic")))]
+    {
+        let _ = (image, quality, speed);
+        Err(SpatialError::ConfigError(
+            "AVIF output needs an AVIF encoder: cargo build --features avif (ravif) or --features heic (libheif)"
+                .to_string(),
+        ))
+    }
+}
+
+/// Encode an RGB image as HEIC with libheif's HEVC encoder
+fn encode_heic(image: &image::RgbImage, quality: u8) -> SpatialResult<Vec<u8>> {
+    #[cfg(feature = "heic")]
+    {
+        encode_heif(image, libheif_rs::CompressionFormat::Hevc, quality, None)
+    }
+
+    #[cfg(not(feature = "heic"))]
+    {
+        let _ = (image, quality);
+        Err(SpatialError::ConfigError(
+            "HEIC output needs the HEIC encoder: cargo build --features heic (libheif)".to_string(),
+        ))
+    }
+}
+
+/// Encode an RGB image into a HEIF container with libheif, as HEVC (HEIC)
+/// or AV1 (AVIF); `speed` (0-10) is passed to encoders that take one
+#[cfg(feature = "heic")]
+fn encode_heif(
+    image: &image::RgbImage,
+    format: libheif_rs::CompressionFormat,
+    quality: u8,
+    speed: Option<u8>,
+) -> SpatialResult<Vec<u8>> {
+    use libheif_rs::{
+        Channel, ColorSpace, CompressionFormat, EncoderParameterValue, EncoderQuality, HeifContext,
+        Image, LibHeif, RgbChroma,
+    };
+
+    let name = if matches!(format, CompressionFormat::Av1) {
+        "AVIF"
+    } else {
+        "HEIC"
+    };
+    let heif_error = |e: libheif_rs::HeifError| {
+        SpatialError::image(
+            format!("Failed to encode {}", name),
+            encoding_error(name, format!("{:?}", e)),
+        )
+    };
+    let (width, height) = image.dimensions();
+    let mut heif_image =
+        Image::new(width, height, ColorSpace::Rgb(RgbChroma::Rgb)).map_err(heif_error)?;
+    heif_image
+        .create_plane(Channel::Interleaved, width, height, 8)
+        .map_err(heif_error)?;
+    let plane = heif_image.planes_mut().interleaved.ok_or_else(|| {
+        SpatialError::image(
+            format!("Failed to encode {}", name),
+            encoding_error(name, "no interleaved plane in the image"),
+        )
+    })?;
+    let row = width as usize * 3;
+    for (y, pixels) in image.as_raw().chunks_exact(row).enumerate() {
+        plane.data[y * plane.stride..][..row].copy_from_slice(pixels);
+    }
+
+    let lib_heif = LibHeif::new();
+    let mut encoder = lib_heif.encoder_for_format(format).map_err(heif_error)?;
+    encoder
+        .set_quality(EncoderQuality::Lossy(quality))
+        .map_err(heif_error)?;
+    // aom and rav1e both take a 0-10 speed; other encoders may not
+    if let Some(speed) = speed {
+        if let Err(e) =
+            encoder.set_parameter_value("speed", EncoderParameterValue::Int(speed.into()))
+        {
+            tracing::debug!("{} encoder {} ignores speed: {:?}", name, encoder.name(), e);
+        }
+    }
+    let mut context = HeifContext::new().map_err(heif_error)?;
+    context
+        .encode_image(&heif_image, &mut encoder, None)
+        .map_err(heif_error)?;
+    context.write_to_bytes().map_err(heif_error)
+}
+
+/// Encode an RGB image as lossless JPEG XL with zune-jpegxl
+fn encode_jxl(image: &image::RgbImage) -> SpatialResult<Vec<u8>> {
+    #[cfg(feature = "jxl")]
+    {
+        use zune_core::{bit_depth::BitDepth, colorspace::ColorSpace, options::EncoderOptions};
+
+        let options = EncoderOptions::new(
+            image.width() as usize,
+            image.height() as usize,
+            ColorSpace::RGB,
+            BitDepth::Eight,
+        );
+        let mut data = Vec::new();
+        zune_jpegxl::JxlSimpleEncoder::new(image.as_raw(), options)
+            .encode(&mut data)
+            .map_err(|e| {
+                SpatialError::image(
+                    "Failed to encode JPEG XL",
+                    encoding_error("JPEG XL", format!("{:?}", e)),
+                )
+            })?;
+        Ok(data)
+    }
+
+    #[cfg(not(feature = "jxl"))]
+    {
+        let _ = image;
+        Err(SpatialError::ConfigError(
+            "JPEG XL output needs the JXL encoder: cargo build --features jxl".to_string(),
+        ))
+    }
+}
+
+/// Encode stereo image to MV-HEVC using the `spatial` CLI
+fn encode_mvhevc(
+    stereo_path: &Path,
+    hevc_path: &Path,
+    layout: OutputFormat,
+    config: &MVHEVCConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    tracing::info!("🎬 Encoding MV-HEVC with `spatial` CLI");
 
     let spatial_path = config
         .spatial_cli_path
@@ -342,25 +1902,10 @@ fn encode_mvhevc(stereo_path: &Path, config: &MVHEVCConfig) -> SpatialResult<()>
         .map(|p| p.as_path())
         .unwrap_or_else(|| Path::new("spatial"));
 
-    // Determine output path (replace extension with .heic)
-    let hevc_path = stereo_path.with_extension("heic");
-
-    // Determine stereo format from filename or default to side-by-side
-    let format = if stereo_path.to_string_lossy().contains("top-bottom")
-        || stereo_path.to_string_lossy().contains("_tb_")
-    {
-        "hou" // horizontal over/under
-    } else if stereo_path.to_string_lossy().contains("separate")
-        || stereo_path.to_string_lossy().contains("_L.")
-        || stereo_path.to_string_lossy().contains("_R.")
-    {
-        // For separate files, spatial make needs to be called with two inputs
-        // This is handled separately, not through this function
-        return Err(SpatialError::ImageError(
-            "Separate stereo format requires two input files. Use side-by-side or top-bottom format for MV-HEVC encoding.".to_string()
-        ));
-    } else {
-        "sbs" // side-by-side (default)
+    // Only side-by-side and over/under reach here (see `apply_mvhevc`)
+    let format = match layout {
+        OutputFormat::TopAndBottom => "hou", // horizontal over/under
+        _ => "sbs",
     };
 
     // Build the command: spatial make --input <stereo> --output <heic> --format <format> --quality <0.0-1.0>
@@ -371,7 +1916,7 @@ fn encode_mvhevc(stereo_path: &Path, config: &MVHEVCConfig) -> SpatialResult<()>
         .arg("--input")
         .arg(stereo_path)
         .arg("--output")
-        .arg(&hevc_path)
+        .arg(hevc_path)
         .arg("--format")
         .arg(format)
         .arg("--quality")
@@ -380,26 +1925,177 @@ fn encode_mvhevc(stereo_path: &Path, config: &MVHEVCConfig) -> SpatialResult<()>
 
     tracing::debug!("Running: {:?}", cmd);
 
-    // Execute the command
-    let output = cmd.output().map_err(|e| {
-        SpatialError::ImageError(format!(
-            "Failed to run `spatial` CLI: {}. Ensure the `spatial` tool is installed and in PATH.",
-            e
-        ))
+    run_encoder(cmd, "`spatial` CLI", hevc_path, cancel).map_err(|e| match e {
+        SpatialError::Io { context, source } => SpatialError::Io {
+            context: format!(
+                "{}. Ensure the `spatial` tool is installed and in PATH",
+                context
+            ),
+            source,
+        },
+        other => other,
     })?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SpatialError::ImageError(format!(
-            "MV-HEVC encoding failed: {}",
-            stderr
-        )));
+    tracing::info!("✅ MV-HEVC encoded to {:?}", hevc_path);
+    Ok(())
+}
+
+/// Encode the stereo pair to a single-frame MV-HEVC `.mov` with ffmpeg/libx265
+///
+/// `inputs` holds the stereo image, or the left and right images for
+/// `Separate` output.
+fn encode_mvhevc_ffmpeg(
+    inputs: &[PathBuf],
+    hevc_path: &Path,
+    layout: OutputFormat,
+    config: &MVHEVCConfig,
+    attribution: Option<&Attribution>,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    tracing::info!("🎬 Encoding MV-HEVC with ffmpeg (libx265 multiview)");
+
+    let ffmpeg_path = config
+        .ffmpeg_path
+        .as_deref()
+        .unwrap_or_else(|| Path::new("ffmpeg"));
+
+    let mut cmd = Command::new(ffmpeg_path);
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y"]);
+    for input in inputs {
+        cmd.arg("-i").arg(input);
+    }
+
+    // x265 multiview input format: 1 = side-by-side, 2 = over-under.
+    // Separate files are stacked side-by-side first.
+    let x265_format = match layout {
+        OutputFormat::SideBySide => 1,
+        OutputFormat::TopAndBottom => 2,
+        OutputFormat::Separate => {
+            cmd.args(["-filter_complex", "[0:v][1:v]hstack=inputs=2"]);
+            1
+        }
+        // Restore left | right from the swapped or flipped halves
+        OutputFormat::CrossEye => {
+            cmd.args([
+                "-filter_complex",
+                "[0:v]split[a][b];[a]crop=iw/2:ih:iw/2:0[l];[b]crop=iw/2:ih:0:0[r];[l][r]hstack=inputs=2",
+            ]);
+            1
+        }
+        OutputFormat::MirroredSideBySide => {
+            cmd.args([
+                "-filter_complex",
+                "[0:v]split[a][b];[a]crop=iw/2:ih:0:0[l];[b]crop=iw/2:ih:iw/2:0,hflip[r];[l][r]hstack=inputs=2",
+            ]);
+            1
+        }
+    };
+
+    // Map quality 0-100 onto CRF 40-12
+    let crf = 40 - (config.quality.min(100) as u32 * 28 / 100);
+
+    cmd.args(["-frames:v", "1", "-c:v", "libx265"])
+        .arg("-crf")
+        .arg(crf.to_string())
+        .arg("-x265-params")
+        .arg(format!("num-views=2:format={}", x265_format))
+        .args(["-tag:v", "hvc1"]);
+    if let Some(attribution) = attribution {
+        cmd.args(attribution.ffmpeg_args());
     }
+    cmd.arg(hevc_path);
+
+    tracing::debug!("Running: {:?}", cmd);
+    run_encoder(cmd, "ffmpeg", hevc_path, cancel).map_err(|e| match e {
+        SpatialError::Image { context, source } => SpatialError::Image {
+            context: format!(
+                "{}. The ffmpeg backend needs libx265 4.0+ built with multiview (ENABLE_MULTIVIEW)",
+                context
+            ),
+            source,
+        },
+        other => other,
+    })?;
 
     tracing::info!("✅ MV-HEVC encoded to {:?}", hevc_path);
     Ok(())
 }
 
+/// Run an encoder to completion, polling so a cancellation can kill it
+///
+/// On cancellation the partial `output` file is removed. Spawn failures map
+/// to [`SpatialError::Io`]; a non-zero exit maps to [`SpatialError::Image`]
+/// carrying stderr.
+fn run_encoder(
+    mut cmd: Command,
+    tool: &str,
+    output: &Path,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    cmd.stdout(Stdio::null()).stderr(Stdio::piped());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| SpatialError::io(format!("Failed to run {}", tool), e))?;
+    // Read as it comes: an encoder blocks once the pipe is full
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = pipe.read_to_string(&mut text);
+            text
+        })
+    });
+
+    let status = loop {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(output);
+            return Err(SpatialError::Cancelled);
+        }
+
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(SpatialError::io(format!("Failed to wait for {}", tool), e)),
+        }
+    };
+
+    let stderr = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    if !status.success() {
+        return Err(SpatialError::image(
+            format!("MV-HEVC encoding with {} failed", tool),
+            encoding_error("MV-HEVC", stderr.trim().to_string()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether `program args...` runs and exits successfully
+fn tool_runs(program: &Path, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Whether an ffmpeg binary has the libx265 encoder
+fn ffmpeg_has_libx265(ffmpeg: &Path) -> bool {
+    Command::new(ffmpeg)
+        .args(["-hide_banner", "-h", "encoder=libx265"])
+        .output()
+        .map(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stdout).contains("Encoder libx265")
+        })
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,17 +2113,62 @@ mod tests {
         assert_eq!(OutputFormat::SideBySide.name(), "side-by-side");
         assert_eq!(OutputFormat::TopAndBottom.name(), "top-and-bottom");
         assert_eq!(OutputFormat::Separate.name(), "separate");
+        assert_eq!(OutputFormat::CrossEye.name(), "cross-eye");
+        assert_eq!(
+            OutputFormat::MirroredSideBySide.name(),
+            "mirrored-side-by-side"
+        );
     }
 
     #[test]
     fn test_image_encoding_detection() {
         assert_eq!(
-            ImageEncoding::from_path("test.jpg"),
+            ImageEncoding::from_path("test.jpg").unwrap(),
+            ImageEncoding::Jpeg { quality: 95 }
+        );
+        assert_eq!(
+            ImageEncoding::from_path("test.png").unwrap(),
+            ImageEncoding::PNG
+        );
+        assert_eq!(
+            ImageEncoding::from_path("plate.EXR").unwrap(),
+            ImageEncoding::Exr
+        );
+        assert_eq!(
+            ImageEncoding::from_path("photo.jxl").unwrap(),
+            ImageEncoding::Jxl
+        );
+        assert_eq!(
+            ImageEncoding::from_path("small.avif").unwrap(),
+            ImageEncoding::Avif {
+                quality: 80,
+                speed: 4
+            }
+        );
+        assert_eq!(
+            ImageEncoding::from_path("web.webp").unwrap(),
+            ImageEncoding::WebP {
+                quality: 95,
+                lossless: false
+            }
+        );
+        assert_eq!(
+            ImageEncoding::from_path("test.jpeg").unwrap(),
             ImageEncoding::Jpeg { quality: 95 }
         );
-        assert_eq!(ImageEncoding::from_path("test.png"), ImageEncoding::Png);
+        assert!(ImageEncoding::from_path("test.unknown").is_err());
+        assert!(ImageEncoding::from_path("no-extension").is_err());
+
+        let lossless = ImageEncoding::WebP {
+            quality: 100,
+            lossless: true,
+        };
+        assert_eq!(
+            ImageEncoding::from_path_with_defaults("web.WEBP", lossless).unwrap(),
+            lossless
+        );
         assert_eq!(
-            ImageEncoding::from_path("test.unknown"),
+            ImageEncoding::from_path_with_defaults("photo.jpg", lossless).unwrap(),
             ImageEncoding::Jpeg { quality: 95 }
         );
     }
@@ -435,7 +2176,26 @@ mod tests {
     #[test]
     fn test_image_encoding_extension() {
         assert_eq!(ImageEncoding::Jpeg { quality: 95 }.extension(), "jpg");
-        assert_eq!(ImageEncoding::Png.extension(), "png");
+        assert_eq!(ImageEncoding::PNG.extension(), "png");
+        assert_eq!(ImageEncoding::Exr.extension(), "exr");
+        assert_eq!(ImageEncoding::Jxl.extension(), "jxl");
+        assert_eq!(ImageEncoding::Heic { quality: 90 }.extension(), "heic");
+        assert_eq!(
+            ImageEncoding::Avif {
+                quality: 60,
+                speed: 8
+            }
+            .extension(),
+            "avif"
+        );
+        assert_eq!(
+            ImageEncoding::WebP {
+                quality: 80,
+                lossless: true
+            }
+            .extension(),
+            "webp"
+        );
     }
 
     #[test]
@@ -468,7 +2228,7 @@ mod tests {
         let left = create_test_image(100, 100, (255, 0, 0));
         let right = create_test_image(100, 100, (0, 255, 0));
 
-        let result = save_side_by_side(&left, &right, &output_path, ImageEncoding::Png);
+        let result = save_side_by_side(&left, &right, &output_path, ImageEncoding::PNG);
         assert!(result.is_ok());
         assert!(output_path.exists());
 
@@ -524,7 +2284,7 @@ mod tests {
         let left = create_test_image(100, 100, (255, 0, 0));
         let right = create_test_image(100, 100, (0, 255, 0));
 
-        let result = save_top_and_bottom(&left, &right, &output_path, ImageEncoding::Png);
+        let result = save_top_and_bottom(&left, &right, &output_path, ImageEncoding::PNG);
         assert!(result.is_ok());
         assert!(output_path.exists());
 
@@ -563,6 +2323,7 @@ mod tests {
             &right,
             &output_path,
             ImageEncoding::Jpeg { quality: 95 },
+            &FilenameTemplate::default(),
         );
         assert!(result.is_ok());
 
@@ -580,7 +2341,13 @@ mod tests {
         let left = create_test_image(100, 100, (255, 0, 0));
         let right = create_test_image(100, 100, (0, 255, 0));
 
-        let result = save_separate(&left, &right, &output_path, ImageEncoding::Png);
+        let result = save_separate(
+            &left,
+            &right,
+            &output_path,
+            ImageEncoding::PNG,
+            &FilenameTemplate::default(),
+        );
         assert!(result.is_ok());
 
         let left_path = temp_dir.path().join("test_L.png");
@@ -590,36 +2357,290 @@ mod tests {
     }
 
     #[test]
-    fn test_save_stereo_image_sbs() {
-        let temp_dir = tempfile::tempdir().unwrap();
-        let output_path = temp_dir.path().join("output.jpg");
+    fn test_filename_template_render() {
+        let template = FilenameTemplate::new("{stem}_{layout}_{eye}.{ext}").unwrap();
+        assert_eq!(
+            template.render("photo", "separate", "L", "png").unwrap(),
+            "photo_separate_L.png"
+        );
+        assert_eq!(
+            template.render("photo", "spatial", "", "heic").unwrap(),
+            "photo_spatial.heic"
+        );
 
-        let left = create_test_image(100, 100, (255, 0, 0));
-        let right = create_test_image(100, 100, (0, 255, 0));
+        let leading = FilenameTemplate::new("{eye}-{stem}.{ext}").unwrap();
+        assert_eq!(
+            leading.render("photo", "spatial", "", "mov").unwrap(),
+            "photo.mov"
+        );
 
-        let options = OutputOptions {
-            layout: OutputFormat::SideBySide,
-            image_format: ImageEncoding::Jpeg { quality: 95 },
-            mvhevc: None,
-        };
+        let default = FilenameTemplate::default();
+        assert_eq!(
+            default.render("photo", "separate", "R", "jpg").unwrap(),
+            "photo_R.jpg"
+        );
+        assert_eq!(
+            default.render("photo", "spatial", "", "heic").unwrap(),
+            "photo.heic"
+        );
+    }
 
-        let result = save_stereo_image(&left, &right, &output_path, options);
-        assert!(result.is_ok());
-        assert!(output_path.exists());
+    #[test]
+    fn test_filename_template_rejects_invalid() {
+        for template in [
+            "{stem}_{side}.{ext}",
+            "{stem.{ext}",
+            "{stem}}.{ext}",
+            "out/{stem}.{ext}",
+        ] {
+            assert!(
+                matches!(
+                    FilenameTemplate::new(template),
+                    Err(SpatialError::ConfigError(_))
+                ),
+                "{template} should be rejected"
+            );
+        }
+        assert!(FilenameTemplate::new("{eye}").is_ok());
+        assert!(FilenameTemplate::new("{eye}")
+            .unwrap()
+            .render("photo", "spatial", "", "heic")
+            .is_err());
     }
 
     #[test]
-    fn test_save_stereo_image_tb() {
+    fn test_save_separate_with_template() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let output_path = temp_dir.path().join("output.jpg");
+        let output_path = temp_dir.path().join("shot.png");
+        let left = create_test_image(10, 10, (255, 0, 0));
+        let right = create_test_image(10, 10, (0, 255, 0));
 
-        let left = create_test_image(100, 100, (255, 0, 0));
-        let right = create_test_image(100, 100, (0, 255, 0));
+        let options = OutputOptions {
+            layout: OutputFormat::Separate,
+            image_format: ImageEncoding::PNG,
+            filename_template: FilenameTemplate::new("{stem}.{eye}.{ext}").unwrap(),
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &output_path, options.clone()).unwrap();
+        assert!(temp_dir.path().join("shot.L.png").exists());
+        assert!(temp_dir.path().join("shot.R.png").exists());
+        assert!(!temp_dir.path().join("shot_L.png").exists());
+
+        let produced = produced_paths(&output_path, &options).unwrap();
+        assert_eq!(
+            produced,
+            vec![
+                temp_dir.path().join("shot.L.png"),
+                temp_dir.path().join("shot.R.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_separate_template_needs_eye() {
+        let options = OutputOptions {
+            layout: OutputFormat::Separate,
+            filename_template: FilenameTemplate::new("{stem}_{layout}.{ext}").unwrap(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_options(&options),
+            Err(SpatialError::ConfigError(_))
+        ));
+
+        let options = OutputOptions {
+            layout: OutputFormat::SideBySide,
+            ..options
+        };
+        assert!(validate_options(&options).is_ok());
+    }
+
+    #[test]
+    fn test_save_stereo_image_previews() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("photo.png");
+        let left = create_test_image(400, 300, (255, 0, 0));
+        let right = create_test_image(400, 300, (0, 255, 0));
+
+        let options = OutputOptions {
+            image_format: ImageEncoding::PNG,
+            previews: vec![PreviewSize::new("small", 150), PreviewSize::new("big", 600)],
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &output_path, options.clone()).unwrap();
+
+        let small = image::open(temp_dir.path().join("photo_small.png")).unwrap();
+        assert_eq!((small.width(), small.height()), (400, 150));
+        assert_eq!(small.to_rgb8().get_pixel(0, 0), &image::Rgb([255, 0, 0]));
+        assert_eq!(small.to_rgb8().get_pixel(399, 0), &image::Rgb([0, 255, 0]));
+
+        // Never upscaled
+        let big = image::open(temp_dir.path().join("photo_big.png")).unwrap();
+        assert_eq!((big.width(), big.height()), (800, 300));
+
+        assert_eq!(
+            produced_paths(&output_path, &options).unwrap(),
+            vec![
+                output_path.clone(),
+                temp_dir.path().join("photo_small.png"),
+                temp_dir.path().join("photo_big.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_half_tb_previews_and_separate_names() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = create_test_image(200, 100, (255, 0, 0));
+        let right = create_test_image(200, 100, (0, 255, 0));
+
+        let output_path = temp_dir.path().join("tb.jpg");
+        let options = OutputOptions {
+            layout: OutputFormat::TopAndBottom,
+            half_resolution: true,
+            previews: vec![PreviewSize::new("thumb", 50)],
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+        let thumb = image::open(temp_dir.path().join("tb_thumb.jpg")).unwrap();
+        assert_eq!((thumb.width(), thumb.height()), (100, 50));
+
+        let output_path = temp_dir.path().join("pair.png");
+        let options = OutputOptions {
+            layout: OutputFormat::Separate,
+            image_format: ImageEncoding::PNG,
+            previews: vec![PreviewSize::thumbnail()],
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &output_path, options.clone()).unwrap();
+        let produced = produced_paths(&output_path, &options).unwrap();
+        assert_eq!(
+            produced[2..],
+            [
+                temp_dir.path().join("pair_thumb_L.png"),
+                temp_dir.path().join("pair_thumb_R.png")
+            ]
+        );
+        assert!(produced.iter().all(|path| path.exists()));
+    }
+
+    #[test]
+    fn test_save_with_player_naming() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("photo.jpg");
+        let left = create_test_image(20, 10, (255, 0, 0));
+        let right = create_test_image(20, 10, (0, 255, 0));
+
+        let options = OutputOptions {
+            half_resolution: true,
+            previews: vec![PreviewSize::new("thumb", 5)],
+            player: PlayerNaming {
+                tag_filename: true,
+                json_sidecar: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &output_path, options.clone()).unwrap();
+        assert!(!output_path.exists());
+
+        let produced = produced_paths(&output_path, &options).unwrap();
+        assert_eq!(
+            produced,
+            vec![
+                temp_dir.path().join("photo_SBS.jpg"),
+                temp_dir.path().join("photo_SBS_thumb.jpg"),
+                temp_dir.path().join("photo_SBS.json")
+            ]
+        );
+        assert!(produced.iter().all(|path| path.exists()));
+
+        let sidecar: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&produced[2]).unwrap()).unwrap();
+        assert_eq!(sidecar["stereoMode"], "sbs");
+        assert_eq!(sidecar["halfResolution"], true);
+    }
+
+    #[test]
+    fn test_preview_validation() {
+        for previews in [
+            vec![PreviewSize::new("thumb", 0)],
+            vec![PreviewSize::new("", 256)],
+            vec![PreviewSize::new("a/b", 256)],
+            vec![PreviewSize::thumbnail(), PreviewSize::new("thumb", 128)],
+        ] {
+            let options = OutputOptions {
+                previews,
+                ..Default::default()
+            };
+            assert!(matches!(
+                validate_options(&options),
+                Err(SpatialError::ConfigError(_))
+            ));
+        }
+
+        let options = OutputOptions {
+            previews: vec![PreviewSize::hd(), PreviewSize::thumbnail()],
+            ..Default::default()
+        };
+        assert!(validate_options(&options).is_ok());
+    }
+
+    #[test]
+    fn test_mvhevc_path_follows_template() {
+        let options = OutputOptions {
+            mvhevc: Some(MVHEVCConfig {
+                enabled: true,
+                backend: MVHEVCBackend::Ffmpeg,
+                keep_intermediate: true,
+                ..Default::default()
+            }),
+            filename_template: FilenameTemplate::new("{stem}_{layout}_{eye}.{ext}").unwrap(),
+            ..Default::default()
+        };
+        let produced = produced_paths(Path::new("out/photo.jpg"), &options).unwrap();
+        assert_eq!(
+            produced,
+            vec![
+                PathBuf::from("out/photo.jpg"),
+                PathBuf::from("out/photo_spatial.mov")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_save_stereo_image_sbs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.jpg");
+
+        let left = create_test_image(100, 100, (255, 0, 0));
+        let right = create_test_image(100, 100, (0, 255, 0));
+
+        let options = OutputOptions {
+            layout: OutputFormat::SideBySide,
+            image_format: ImageEncoding::Jpeg { quality: 95 },
+            mvhevc: None,
+            ..Default::default()
+        };
+
+        let result = save_stereo_image(&left, &right, &output_path, options);
+        assert!(result.is_ok());
+        assert!(output_path.exists());
+    }
+
+    #[test]
+    fn test_save_stereo_image_tb() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.jpg");
+
+        let left = create_test_image(100, 100, (255, 0, 0));
+        let right = create_test_image(100, 100, (0, 255, 0));
 
         let options = OutputOptions {
             layout: OutputFormat::TopAndBottom,
             image_format: ImageEncoding::Jpeg { quality: 90 },
             mvhevc: None,
+            ..Default::default()
         };
 
         let result = save_stereo_image(&left, &right, &output_path, options);
@@ -639,6 +2660,7 @@ mod tests {
             layout: OutputFormat::Separate,
             image_format: ImageEncoding::Jpeg { quality: 95 },
             mvhevc: None,
+            ..Default::default()
         };
 
         let result = save_stereo_image(&left, &right, &output_path, options);
@@ -650,9 +2672,611 @@ mod tests {
         assert!(right_path.exists());
     }
 
+    #[test]
+    fn test_save_stereo_image_cross_eye() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.png");
+
+        let left = create_test_image(100, 100, (255, 0, 0));
+        let right = create_test_image(100, 100, (0, 255, 0));
+
+        let options = OutputOptions {
+            layout: OutputFormat::CrossEye,
+            image_format: ImageEncoding::PNG,
+            ..Default::default()
+        };
+
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+        let saved = image::open(&output_path).unwrap().to_rgb8();
+        assert_eq!(saved.dimensions(), (200, 100));
+        assert_eq!(saved.get_pixel(10, 50).0, [0, 255, 0]);
+        assert_eq!(saved.get_pixel(110, 50).0, [255, 0, 0]);
+    }
+
+    #[test]
+    fn test_save_stereo_image_keeps_alpha() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let view = DynamicImage::ImageRgba8(ImageBuffer::from_fn(40, 30, |x, _| {
+            image::Rgba([255, 0, 0, if x < 20 { 0 } else { 255 }])
+        }));
+
+        for (name, image_format) in [
+            ("output.png", ImageEncoding::PNG),
+            (
+                "output.webp",
+                ImageEncoding::WebP {
+                    quality: 90,
+                    lossless: true,
+                },
+            ),
+        ] {
+            let output_path = temp_dir.path().join(name);
+            let options = OutputOptions {
+                image_format,
+                ..Default::default()
+            };
+            save_stereo_image(&view, &view, &output_path, options).unwrap();
+
+            let saved = image::open(&output_path).unwrap();
+            assert!(saved.color().has_alpha(), "{}: {:?}", name, saved.color());
+            let saved = saved.to_rgba8();
+            assert_eq!(saved.dimensions(), (80, 30));
+            assert_eq!(saved.get_pixel(50, 10)[3], 0);
+            assert_eq!(saved.get_pixel(70, 10).0, [255, 0, 0, 255]);
+        }
+
+        // Formats without alpha still get plain RGB
+        let output_path = temp_dir.path().join("output.jpg");
+        save_stereo_image(&view, &view, &output_path, OutputOptions::default()).unwrap();
+        assert!(!image::open(&output_path).unwrap().color().has_alpha());
+    }
+
+    #[test]
+    fn test_save_stereo_image_mirrored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.png");
+
+        let left = create_test_image(100, 100, (255, 0, 0));
+        let mut right = create_test_image(100, 100, (0, 255, 0)).to_rgb8();
+        right.put_pixel(0, 0, image::Rgb([0, 0, 255]));
+        let right = DynamicImage::ImageRgb8(right);
+
+        let options = OutputOptions {
+            layout: OutputFormat::MirroredSideBySide,
+            image_format: ImageEncoding::PNG,
+            ..Default::default()
+        };
+
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+        let saved = image::open(&output_path).unwrap().to_rgb8();
+        assert_eq!(saved.dimensions(), (200, 100));
+        assert_eq!(saved.get_pixel(10, 50).0, [255, 0, 0]);
+        // The right view's top-left corner ends up at the far right
+        assert_eq!(saved.get_pixel(199, 0).0, [0, 0, 255]);
+        assert_eq!(saved.get_pixel(100, 0).0, [0, 255, 0]);
+    }
+
+    #[test]
+    fn test_save_stereo_image_half_sbs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.png");
+
+        let left = create_test_image(100, 80, (255, 0, 0));
+        let right = create_test_image(100, 80, (0, 255, 0));
+
+        let options = OutputOptions {
+            layout: OutputFormat::SideBySide,
+            image_format: ImageEncoding::PNG,
+            half_resolution: true,
+            ..Default::default()
+        };
+
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+        let saved = image::open(&output_path).unwrap().to_rgb8();
+        assert_eq!(saved.dimensions(), (100, 80));
+        assert_eq!(saved.get_pixel(10, 40).0, [255, 0, 0]);
+        assert_eq!(saved.get_pixel(90, 40).0, [0, 255, 0]);
+    }
+
+    #[test]
+    fn test_save_stereo_image_half_tb() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.png");
+
+        let left = create_test_image(100, 80, (255, 0, 0));
+        let right = create_test_image(100, 80, (0, 255, 0));
+
+        let options = OutputOptions {
+            layout: OutputFormat::TopAndBottom,
+            image_format: ImageEncoding::PNG,
+            half_resolution: true,
+            ..Default::default()
+        };
+
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+        let saved = image::open(&output_path).unwrap();
+        assert_eq!((saved.width(), saved.height()), (100, 80));
+    }
+
+    #[test]
+    fn test_half_resolution_rejects_mvhevc() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.jpg");
+
+        let left = create_test_image(100, 80, (255, 0, 0));
+        let right = create_test_image(100, 80, (0, 255, 0));
+
+        let options = OutputOptions {
+            half_resolution: true,
+            mvhevc: Some(MVHEVCConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let result = save_stereo_image(&left, &right, &output_path, options);
+        assert!(matches!(result, Err(SpatialError::ConfigError(_))));
+        assert!(!output_path.exists());
+    }
+
+    #[test]
+    fn test_save_exr_keeps_float() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("stereo.exr");
+
+        let hdr = |value: f32| {
+            DynamicImage::ImageRgb32F(image::Rgb32FImage::from_pixel(
+                4,
+                3,
+                image::Rgb([value, 0.5, 0.125]),
+            ))
+        };
+        let options = OutputOptions {
+            image_format: ImageEncoding::Exr,
+            ..Default::default()
+        };
+
+        let result = save_stereo_image(&hdr(6.5), &hdr(0.75), &output_path, options);
+        if cfg!(feature = "exr") {
+            result.unwrap();
+            let saved = image::open(&output_path).unwrap().to_rgb32f();
+            assert_eq!(saved.dimensions(), (8, 3));
+            assert_eq!(saved.get_pixel(0, 0).0, [6.5, 0.5, 0.125]);
+            assert_eq!(saved.get_pixel(7, 2)[0], 0.75);
+        } else {
+            assert!(matches!(result, Err(SpatialError::ConfigError(_))));
+        }
+    }
+
+    #[test]
+    fn test_save_side_by_side_webp() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = create_test_image(64, 48, (200, 40, 40));
+        let right = create_test_image(64, 48, (40, 200, 40));
+
+        for lossless in [false, true] {
+            let output_path = temp_dir.path().join(format!("sbs_{}.webp", lossless));
+            let encoding = ImageEncoding::WebP {
+                quality: 90,
+                lossless,
+            };
+            save_side_by_side(&left, &right, &output_path, encoding).unwrap();
+
+            let saved = image::open(&output_path).unwrap().to_rgb8();
+            assert_eq!(saved.dimensions(), (128, 48));
+            let pixel = saved.get_pixel(100, 20);
+            if lossless {
+                assert_eq!(pixel.0, [40, 200, 40]);
+            } else {
+                assert!(pixel[1] > 150 && pixel[0] < 90, "{:?}", pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn test_save_side_by_side_avif() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = create_test_image(64, 48, (200, 40, 40));
+        let right = create_test_image(64, 48, (40, 200, 40));
+        let output_path = temp_dir.path().join("sbs.avif");
+        let encoding = ImageEncoding::Avif {
+            quality: 80,
+            speed: 10,
+        };
+        let result = save_side_by_side(&left, &right, &output_path, encoding);
+
+        if cfg!(any(feature = "avif", feature = "heic")) {
+            result.unwrap();
+            let data = std::fs::read(&output_path).unwrap();
+            assert_eq!(&data[4..12], b"ftypavif");
+        } else {
+            let message = result.unwrap_err().to_string();
+            assert!(message.contains("--features avif"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_save_png_compression_and_bit_depth() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = DynamicImage::ImageRgb8(image::RgbImage::from_fn(96, 64, |x, y| {
+            image::Rgb([(x * 2) as u8, (y * 3) as u8, 128])
+        }));
+        let right = left.fliph();
+
+        let fast_path = temp_dir.path().join("fast.png");
+        save_side_by_side(&left, &right, &fast_path, ImageEncoding::PNG).unwrap();
+        let best_path = temp_dir.path().join("best.png");
+        let best = ImageEncoding::Png {
+            compression: PngCompression::Best,
+            filter: PngFilter::Paeth,
+            sixteen_bit: false,
+        };
+        save_side_by_side(&left, &right, &best_path, best).unwrap();
+        let size = |path: &Path| std::fs::metadata(path).unwrap().len();
+        assert!(size(&best_path) <= size(&fast_path));
+        assert_eq!(
+            image::open(&best_path).unwrap().to_rgb8(),
+            image::open(&fast_path).unwrap().to_rgb8()
+        );
+
+        let deep_path = temp_dir.path().join("deep.png");
+        let deep = ImageEncoding::Png {
+            compression: PngCompression::Balanced,
+            filter: PngFilter::Adaptive,
+            sixteen_bit: true,
+        };
+        let float = DynamicImage::ImageRgb32F(left.to_rgb32f());
+        save_side_by_side(&float, &float, &deep_path, deep).unwrap();
+        let saved = image::open(&deep_path).unwrap();
+        assert_eq!(saved.color(), ColorType::Rgb16);
+        assert_eq!(
+            saved.to_rgb8().get_pixel(10, 20),
+            left.to_rgb8().get_pixel(10, 20)
+        );
+
+        // Float renders drop to 8 bits without sixteen_bit
+        let shallow_path = temp_dir.path().join("shallow.png");
+        save_side_by_side(&float, &float, &shallow_path, ImageEncoding::PNG).unwrap();
+        assert_eq!(image::open(&shallow_path).unwrap().color(), ColorType::Rgb8);
+    }
+
+    #[test]
+    fn test_save_side_by_side_jxl() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = create_test_image(64, 48, (200, 40, 40));
+        let right = create_test_image(64, 48, (40, 200, 40));
+        let output_path = temp_dir.path().join("sbs.jxl");
+        let result = save_side_by_side(&left, &right, &output_path, ImageEncoding::Jxl);
+
+        #[cfg(feature = "jxl")]
+        {
+            result.unwrap();
+            let saved = crate::image_loader::load_image_blocking(&output_path)
+                .unwrap()
+                .to_rgb8();
+            assert_eq!(saved.dimensions(), (128, 48));
+            assert_eq!(saved.get_pixel(100, 20).0, [40, 200, 40]);
+        }
+        #[cfg(not(feature = "jxl"))]
+        {
+            let message = result.unwrap_err().to_string();
+            assert!(message.contains("--features jxl"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_save_side_by_side_heic() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = create_test_image(64, 48, (200, 40, 40));
+        let right = create_test_image(64, 48, (40, 200, 40));
+        let output_path = temp_dir.path().join("sbs.heic");
+        let encoding = ImageEncoding::from_path(&output_path).unwrap();
+        assert_eq!(encoding, ImageEncoding::Heic { quality: 90 });
+        let result = save_side_by_side(&left, &right, &output_path, encoding);
+
+        #[cfg(feature = "heic")]
+        {
+            result.unwrap();
+            let saved = crate::image_loader::load_image_blocking(&output_path)
+                .unwrap()
+                .to_rgb8();
+            assert_eq!(saved.dimensions(), (128, 48));
+            let [r, g, _] = saved.get_pixel(100, 20).0;
+            assert!(g > 150 && r < 90, "{:?}", saved.get_pixel(100, 20));
+        }
+        #[cfg(not(feature = "heic"))]
+        {
+            let message = result.unwrap_err().to_string();
+            assert!(message.contains("--features heic"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_exr_rejects_mvhevc() {
+        let options = OutputOptions {
+            image_format: ImageEncoding::Exr,
+            mvhevc: Some(MVHEVCConfig {
+                enabled: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_options(&options),
+            Err(SpatialError::ConfigError(_))
+        ));
+    }
+
+    #[cfg(feature = "exr")]
+    #[test]
+    fn test_save_depth_exr() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("depth.exr");
+        let depth = Array2::from_shape_fn((2, 3), |(y, x)| (x + 3 * y) as f32 / 7.0);
+
+        save_depth_exr(&depth, &output_path).unwrap();
+
+        let saved = exr::prelude::read_first_flat_layer_from_file(&output_path).unwrap();
+        let channels = &saved.layer_data.channel_data.list;
+        assert_eq!(channels.len(), 1);
+        assert!(channels[0].name.eq("Z"));
+        let values: Vec<f32> = channels[0].sample_data.values_as_f32().collect();
+        assert_eq!(values, depth.iter().copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_vr180_writes_gpano_xmp() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("vr180.jpg");
+
+        let left = create_test_image(100, 100, (255, 0, 0));
+        let right = create_test_image(100, 100, (0, 255, 0));
+
+        let options = OutputOptions {
+            projection: Projection::Vr180,
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+
+        let data = std::fs::read(&output_path).unwrap();
+        let needle = b"GPano:ProjectionType=\"equirectangular\"";
+        assert!(data.windows(needle.len()).any(|w| w == needle));
+        let saved = image::load_from_memory(&data).unwrap();
+        assert_eq!((saved.width(), saved.height()), (200, 100));
+    }
+
+    #[test]
+    fn test_vr180_rejects_unsupported_layouts() {
+        let options = OutputOptions {
+            projection: Projection::Vr180,
+            layout: OutputFormat::CrossEye,
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_options(&options),
+            Err(SpatialError::ConfigError(_))
+        ));
+
+        let options = OutputOptions {
+            projection: Projection::Vr180,
+            half_resolution: true,
+            ..Default::default()
+        };
+        assert!(validate_options(&options).is_err());
+    }
+
+    #[test]
+    fn test_panorama_requires_over_under() {
+        let options = OutputOptions {
+            projection: Projection::Panorama360,
+            layout: OutputFormat::TopAndBottom,
+            ..Default::default()
+        };
+        assert!(validate_options(&options).is_ok());
+
+        for layout in [OutputFormat::SideBySide, OutputFormat::Separate] {
+            let options = OutputOptions {
+                projection: Projection::Panorama360,
+                layout,
+                ..Default::default()
+            };
+            assert!(validate_options(&options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_colormap_endpoints() {
+        // Turbo runs from blue to dark red, viridis from purple to yellow
+        let [r, _, b] = Colormap::Turbo.color(0.1);
+        assert!(b > r);
+        let [r, _, b] = Colormap::Turbo.color(1.0);
+        assert!(r > b);
+        assert_eq!(Colormap::Viridis.color(0.0), [71, 1, 85]);
+        let [r, g, b] = Colormap::Viridis.color(1.0);
+        assert!(r > 200 && g > 200 && b < 80);
+        assert_eq!(Colormap::Turbo.color(-1.0), Colormap::Turbo.color(0.0));
+    }
+
+    #[test]
+    fn test_save_depth_visualization() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let depth = Array2::from_shape_fn((20, 40), |(_, x)| x as f32 / 39.0);
+
+        let plain_path = temp_dir.path().join("depth.png");
+        save_depth_visualization(&depth, &plain_path, Colormap::Viridis, None).unwrap();
+        let plain = image::open(&plain_path).unwrap().to_rgb8();
+        assert_eq!(plain.dimensions(), (40, 20));
+        assert_eq!(plain.get_pixel(0, 0).0, Colormap::Viridis.color(0.0));
+
+        let source = create_test_image(80, 40, (0, 0, 0));
+        let blended_path = temp_dir.path().join("blended.png");
+        save_depth_visualization(&depth, &blended_path, Colormap::Turbo, Some((&source, 0.5)))
+            .unwrap();
+        let blended = image::open(&blended_path).unwrap().to_rgb8();
+        assert_eq!(blended.dimensions(), (80, 40));
+        let full = Colormap::Turbo.color(1.0);
+        let pixel = blended.get_pixel(79, 20);
+        assert!((pixel[0] as i32 - full[0] as i32 / 2).abs() <= 3);
+    }
+
+    #[test]
+    fn test_quilt_view_shifts() {
+        let config = QuiltConfig {
+            columns: 3,
+            rows: 1,
+            disparity_range: 40.0,
+            ..Default::default()
+        };
+        assert_eq!(config.view_count(), 3);
+        assert_eq!(config.view_shift(0), -20.0);
+        assert_eq!(config.view_shift(1), 0.0);
+        assert_eq!(config.view_shift(2), 20.0);
+        assert_eq!(config.filename_tag(0.75), "_qs3x1a0.75");
+    }
+
+    #[test]
+    fn test_save_quilt() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("quilt.png");
+
+        let image = create_test_image(80, 40, (255, 0, 0));
+        let depth = Array2::from_elem((20, 40), 0.5);
+        let config = QuiltConfig {
+            columns: 4,
+            rows: 2,
+            quilt_width: 160,
+            ..Default::default()
+        };
+
+        let written = save_quilt(
+            &image,
+            &depth,
+            &output_path,
+            &config,
+            ImageEncoding::PNG,
+            None,
+        )
+        .unwrap();
+        assert_eq!(written, temp_dir.path().join("quilt_qs4x2a2.png"));
+
+        let saved = image::open(&written).unwrap();
+        assert_eq!((saved.width(), saved.height()), (160, 40));
+    }
+
+    #[test]
+    fn test_save_stereo_image_embeds_exif() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.jpg");
+
+        let left = create_test_image(32, 32, (255, 0, 0));
+        let right = create_test_image(32, 32, (0, 255, 0));
+
+        // Big-endian TIFF block with no IFD entries
+        let exif = vec![b'M', b'M', 0, 42, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0];
+        let options = OutputOptions {
+            exif: Some(exif),
+            ..Default::default()
+        };
+
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+
+        let bytes = std::fs::read(&output_path).unwrap();
+        assert!(bytes.windows(6).any(|w| w == b"Exif\0\0"));
+        assert!(image::open(&output_path).is_ok());
+    }
+
+    #[test]
+    fn test_save_stereo_image_embeds_attribution() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let left = create_test_image(32, 32, (255, 0, 0));
+        let right = create_test_image(32, 32, (0, 255, 0));
+        let attribution = Attribution {
+            creator: Some("Studio Example".to_string()),
+            copyright: Some("CC BY 4.0".to_string()),
+            ..Default::default()
+        };
+
+        // JPEG without source EXIF gets a fresh block with the credits
+        let jpeg_path = temp_dir.path().join("output.jpg");
+        let options = OutputOptions {
+            attribution: Some(attribution.clone()),
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &jpeg_path, options).unwrap();
+        let exif = crate::metadata::read_exif(&jpeg_path).unwrap();
+        let exif = exif::Reader::new().read_raw(exif).unwrap();
+        assert!(exif
+            .get_field(exif::Tag::Copyright, exif::In::PRIMARY)
+            .is_some());
+        let bytes = std::fs::read(&jpeg_path).unwrap();
+        let needle = b"<dc:creator>";
+        assert!(bytes.windows(needle.len()).any(|w| w == needle));
+
+        // PNG carries the XMP packet
+        let png_path = temp_dir.path().join("output.png");
+        let options = OutputOptions {
+            image_format: ImageEncoding::PNG,
+            attribution: Some(attribution),
+            ..Default::default()
+        };
+        save_stereo_image(&left, &right, &png_path, options).unwrap();
+        let bytes = std::fs::read(&png_path).unwrap();
+        let needle = b"Studio Example";
+        assert!(bytes.windows(needle.len()).any(|w| w == needle));
+        assert!(image::open(&png_path).is_ok());
+    }
+
+    #[test]
+    fn test_save_stereo_image_embeds_icc_in_png() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("output.png");
+
+        let left = create_test_image(32, 32, (255, 0, 0));
+        let right = create_test_image(32, 32, (0, 255, 0));
+
+        let options = OutputOptions {
+            image_format: ImageEncoding::PNG,
+            icc_profile: Some(b"test icc".to_vec()),
+            ..Default::default()
+        };
+
+        save_stereo_image(&left, &right, &output_path, options).unwrap();
+
+        assert_eq!(
+            crate::metadata::read_icc_profile(&output_path),
+            Some(b"test icc".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_mvhevc_backend_resolution() {
+        let explicit = MVHEVCConfig {
+            backend: MVHEVCBackend::Ffmpeg,
+            ..Default::default()
+        };
+        assert_eq!(explicit.resolve_backend(), MVHEVCBackend::Ffmpeg);
+        assert_eq!(explicit.resolve_backend().extension(), "mov");
+
+        let cli_path = MVHEVCConfig {
+            spatial_cli_path: Some(PathBuf::from("/opt/spatial")),
+            ffmpeg_path: Some(PathBuf::from("/opt/ffmpeg")),
+            ..Default::default()
+        };
+        assert_eq!(cli_path.resolve_backend(), MVHEVCBackend::SpatialCli);
+
+        let ffmpeg_path = MVHEVCConfig {
+            ffmpeg_path: Some(PathBuf::from("/opt/ffmpeg")),
+            ..Default::default()
+        };
+        assert_eq!(ffmpeg_path.resolve_backend(), MVHEVCBackend::Ffmpeg);
+    }
+
     #[test]
     fn test_mvhevc_config_default() {
         let config = MVHEVCConfig::default();
+        assert_eq!(config.backend, MVHEVCBackend::Auto);
+        assert_eq!(config.ffmpeg_path, None);
         assert_eq!(config.spatial_cli_path, None);
         assert!(!config.enabled);
         assert_eq!(config.quality, 95);
@@ -665,5 +3289,9 @@ mod tests {
         assert_eq!(options.layout, OutputFormat::SideBySide);
         assert_eq!(options.image_format, ImageEncoding::Jpeg { quality: 95 });
         assert!(options.mvhevc.is_none());
+        assert_eq!(
+            options.filename_template.as_str(),
+            FilenameTemplate::DEFAULT
+        );
     }
 }
@@ -0,0 +1,376 @@
+//! 360° equirectangular panoramas
+//!
+//! The depth model is trained on ordinary perspective photos, so running it
+//! on a whole equirectangular panorama (stretched poles, a seam down the
+//! back) gives poor depth. Panoramas are instead estimated view by view:
+//! 1. Overlapping perspective views are rendered from the panorama: a ring
+//!    of [`RING_VIEWS`] around the horizon, plus one straight up and one
+//!    straight down
+//! 2. Depth is estimated for each view
+//! 3. Each view's depth is projected back onto the equirectangular grid,
+//!    aligned to the views already placed with a least-squares scale and
+//!    shift over their overlap (the model's depth is only defined up to
+//!    those), and feathered towards the view's edges
+//!
+//! The stereo pair is then warped in equirectangular space (see
+//! [`generate_stereo_pair_360`](crate::generate_stereo_pair_360)) and saved
+//! over/under.
+//!
+//! ```no_run
+//! use spatial_maker::{
+//!     process_photo, OutputFormat, OutputOptions, Projection, SpatialConfig,
+//! };
+//! use std::path::Path;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let options = OutputOptions {
+//!     layout: OutputFormat::TopAndBottom,
+//!     projection: Projection::Panorama360,
+//!     ..Default::default()
+//! };
+//! process_photo(
+//!     Path::new("pano.jpg"),
+//!     Path::new("pano_stereo.jpg"),
+//!     SpatialConfig::default(),
+//!     options,
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::depth_map::DepthMap;
+use crate::error::{SpatialError, SpatialResult};
+use crate::tiling::fit_scale_shift;
+use image::{DynamicImage, Rgb, RgbImage};
+use ndarray::Array2;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// Perspective views around the horizon
+pub const RING_VIEWS: usize = 8;
+
+/// Field of view of each horizon view, in degrees; with eight views this
+/// leaves 55° of overlap between neighbours
+const RING_FOV_DEGREES: f32 = 100.0;
+
+/// Field of view of the views straight up and down, in degrees; reaches
+/// 35° from the horizon, inside the ring's 50°
+const POLE_FOV_DEGREES: f32 = 110.0;
+
+/// Largest side of a rendered view; the model resizes views to its own
+/// input size anyway
+const MAX_VIEW_SIZE: u32 = 1536;
+
+/// Width cap of the merged depth map, about what eight views at the
+/// model's input size resolve around the horizon
+const MAX_DEPTH_WIDTH: usize = 2048;
+
+/// Overlapping pixels needed before a view is aligned to the ones placed
+const MIN_OVERLAP: usize = 16;
+
+/// A pinhole camera at the panorama's centre
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct View {
+    /// Unit vector the camera looks along
+    forward: [f32; 3],
+    /// Unit vector to the right of the image
+    right: [f32; 3],
+    /// Unit vector to the top of the image
+    up: [f32; 3],
+    /// `tan(fov / 2)`, the image half-width on the unit plane
+    half_extent: f32,
+}
+
+impl View {
+    /// Camera turned `yaw` radians right of the panorama's centre and
+    /// `pitch` radians up, seeing `fov` radians across
+    fn new(yaw: f32, pitch: f32, fov: f32) -> Self {
+        let (sin_yaw, cos_yaw) = yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = pitch.sin_cos();
+        Self {
+            forward: [cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw],
+            right: [cos_yaw, 0.0, -sin_yaw],
+            up: [-sin_pitch * sin_yaw, cos_pitch, -sin_pitch * cos_yaw],
+            half_extent: (fov / 2.0).tan(),
+        }
+    }
+
+    /// Direction through normalized image coordinates (-1 to 1, y up)
+    fn ray(&self, u: f32, v: f32) -> [f32; 3] {
+        let (u, v) = (u * self.half_extent, v * self.half_extent);
+        normalize(std::array::from_fn(|i| {
+            self.forward[i] + u * self.right[i] + v * self.up[i]
+        }))
+    }
+
+    /// Normalized image coordinates (-1 to 1, y up) of a direction, or
+    /// `None` when it falls outside the view
+    fn project(&self, direction: [f32; 3]) -> Option<(f32, f32)> {
+        let z = dot(direction, self.forward);
+        if z <= 1e-6 {
+            return None;
+        }
+        let u = dot(direction, self.right) / (z * self.half_extent);
+        let v = dot(direction, self.up) / (z * self.half_extent);
+        (u.abs() < 1.0 && v.abs() < 1.0).then_some((u, v))
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt().max(f32::EPSILON);
+    v.map(|c| c / length)
+}
+
+/// Unit direction of longitude `lon` (0 at the panorama's centre, positive
+/// to the right) and latitude `lat` (positive up)
+fn direction(lon: f32, lat: f32) -> [f32; 3] {
+    let (sin_lat, cos_lat) = lat.sin_cos();
+    [cos_lat * lon.sin(), sin_lat, cos_lat * lon.cos()]
+}
+
+/// Longitude and latitude of pixel centre (`x`, `y`) in a `width` x
+/// `height` equirectangular image
+fn pixel_angles(x: usize, y: usize, width: usize, height: usize) -> (f32, f32) {
+    let lon = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+    let lat = FRAC_PI_2 - (y as f32 + 0.5) / height as f32 * PI;
+    (lon, lat)
+}
+
+/// Views depth is estimated in, horizon ring first
+pub(crate) fn plan_views() -> Vec<View> {
+    let ring_fov = RING_FOV_DEGREES.to_radians();
+    let pole_fov = POLE_FOV_DEGREES.to_radians();
+    let mut views: Vec<View> = (0..RING_VIEWS)
+        .map(|i| View::new(i as f32 * 2.0 * PI / RING_VIEWS as f32, 0.0, ring_fov))
+        .collect();
+    views.push(View::new(0.0, FRAC_PI_2, pole_fov));
+    views.push(View::new(0.0, -FRAC_PI_2, pole_fov));
+    views
+}
+
+/// Side of a rendered view, matching the panorama's angular resolution at
+/// the view's centre
+fn view_size(panorama_width: u32, view: &View) -> u32 {
+    let pixels_per_radian = panorama_width as f32 / (2.0 * PI);
+    let size = (2.0 * view.half_extent * pixels_per_radian).round() as u32;
+    size.clamp(64, MAX_VIEW_SIZE)
+}
+
+/// Render a `size` x `size` perspective view of an equirectangular image
+pub(crate) fn render_view(panorama: &RgbImage, view: &View, size: u32) -> RgbImage {
+    let (width, height) = (panorama.width() as f32, panorama.height() as f32);
+    RgbImage::from_fn(size, size, |i, j| {
+        let u = (i as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+        let v = 1.0 - (j as f32 + 0.5) / size as f32 * 2.0;
+        let [dx, dy, dz] = view.ray(u, v);
+        let lon = dx.atan2(dz);
+        let lat = dy.clamp(-1.0, 1.0).asin();
+        let x = (lon / (2.0 * PI) + 0.5) * width - 0.5;
+        let y = (0.5 - lat / PI) * height - 0.5;
+        sample_wrapped(panorama, x, y)
+    })
+}
+
+/// Bilinear sample at (`x`, `y`), wrapping horizontally and clamping
+/// vertically
+fn sample_wrapped(image: &RgbImage, x: f32, y: f32) -> Rgb<u8> {
+    let (width, height) = (image.width() as i64, image.height() as i64);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let column = |x: i64| x.rem_euclid(width) as u32;
+    let (xa, xb) = (column(x0 as i64), column(x0 as i64 + 1));
+    let (ya, yb) = (y0 as u32, (y0 as i64 + 1).min(height - 1) as u32);
+
+    let (p00, p10) = (image.get_pixel(xa, ya).0, image.get_pixel(xb, ya).0);
+    let (p01, p11) = (image.get_pixel(xa, yb).0, image.get_pixel(xb, yb).0);
+    Rgb(std::array::from_fn(|c| {
+        let top = p00[c] as f32 * (1.0 - tx) + p10[c] as f32 * tx;
+        let bottom = p01[c] as f32 * (1.0 - tx) + p11[c] as f32 * tx;
+        (top * (1.0 - ty) + bottom * ty).round().clamp(0.0, 255.0) as u8
+    }))
+}
+
+/// Bilinear sample of a depth map at normalized view coordinates
+fn sample_depth(depth: &Array2<f32>, u: f32, v: f32) -> f32 {
+    let (height, width) = depth.dim();
+    let x = ((u + 1.0) / 2.0 * width as f32 - 0.5).clamp(0.0, (width - 1) as f32);
+    let y = ((1.0 - v) / 2.0 * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x as usize, y as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+    let top = depth[[y0, x0]] * (1.0 - tx) + depth[[y0, x1]] * tx;
+    let bottom = depth[[y1, x0]] * (1.0 - tx) + depth[[y1, x1]] * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+/// Estimate the depth of a 360° equirectangular panorama from overlapping
+/// perspective views, each passed to `estimate`
+///
+/// The merged map is normalized like any other [`DepthMap`] and spans the
+/// full panorama, at up to 2048 pixels wide.
+pub(crate) fn estimate_depth(
+    panorama: &DynamicImage,
+    mut estimate: impl FnMut(&DynamicImage) -> SpatialResult<DepthMap>,
+) -> SpatialResult<DepthMap> {
+    let (width, height) = (panorama.width(), panorama.height());
+    if width < 2 || height < 2 {
+        return Err(SpatialError::ConfigError(format!(
+            "A {}x{} image is too small for a 360° panorama",
+            width, height
+        )));
+    }
+    if width != height * 2 {
+        tracing::warn!(
+            "360° panorama is {}x{}, not 2:1; treating it as a full sphere anyway",
+            width,
+            height
+        );
+    }
+
+    let out_w = (width as usize).min(MAX_DEPTH_WIDTH);
+    let out_h = ((height as f32 * out_w as f32 / width as f32).round() as usize).max(1);
+    let directions = Array2::from_shape_fn((out_h, out_w), |(y, x)| {
+        let (lon, lat) = pixel_angles(x, y, out_w, out_h);
+        direction(lon, lat)
+    });
+
+    let rgb = panorama.to_rgb8();
+    let views = plan_views();
+    let mut sum = Array2::<f32>::zeros((out_h, out_w));
+    let mut weights = Array2::<f32>::zeros((out_h, out_w));
+    let mut first: Option<DepthMap> = None;
+
+    for (index, view) in views.iter().enumerate() {
+        tracing::debug!("Panorama view {}/{}", index + 1, views.len());
+        let size = view_size(width, view);
+        let image = DynamicImage::ImageRgb8(render_view(&rgb, view, size));
+        let depth = estimate(&image)?;
+
+        // (pixel, value, weight) for every panorama pixel this view sees
+        let samples: Vec<((usize, usize), f32, f32)> = directions
+            .indexed_iter()
+            .filter_map(|(pixel, &dir)| {
+                let (u, v) = view.project(dir)?;
+                let weight = (1.0 - u.abs()).min(1.0 - v.abs()).max(1e-3);
+                Some((pixel, sample_depth(&depth, u, v), weight))
+            })
+            .collect();
+
+        // Align to what is already placed, in the first view's units
+        let (overlap, placed): (Vec<f32>, Vec<f32>) = samples
+            .iter()
+            .filter(|(pixel, _, _)| weights[*pixel] > 0.0)
+            .map(|&(pixel, value, _)| (value, sum[pixel] / weights[pixel]))
+            .unzip();
+        let (scale, shift) = if overlap.len() >= MIN_OVERLAP {
+            let row = |values: Vec<f32>| {
+                Array2::from_shape_vec((1, values.len()), values).expect("one row")
+            };
+            fit_scale_shift(row(overlap).view(), row(placed).view())
+        } else {
+            (1.0, 0.0)
+        };
+
+        for (pixel, value, weight) in samples {
+            sum[pixel] += weight * (value * scale + shift);
+            weights[pixel] += weight;
+        }
+        first.get_or_insert(depth);
+    }
+
+    // The views cover the whole sphere; the fallback only guards rounding
+    let fallback = sum.iter().sum::<f32>() / weights.iter().sum::<f32>().max(1e-6);
+    let merged = ndarray::Zip::from(&sum)
+        .and(&weights)
+        .map_collect(
+            |&sum, &weight| {
+                if weight > 0.0 {
+                    sum / weight
+                } else {
+                    fallback
+                }
+            },
+        );
+
+    let first = first.expect("at least one view");
+    let (low, high) = crate::depth::value_range(&merged);
+    Ok(DepthMap {
+        data: crate::depth::normalize_depth_map(merged),
+        raw_range: (first.raw_value(low), first.raw_value(high)),
+        inference_size: first.inference_size,
+        model: first.model,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_views_cover_the_sphere() {
+        let views = plan_views();
+        for y in 0..45 {
+            for x in 0..90 {
+                let (lon, lat) = pixel_angles(x, y, 90, 45);
+                let seen = views
+                    .iter()
+                    .filter(|view| view.project(direction(lon, lat)).is_some())
+                    .count();
+                assert!(seen >= 1, "({}, {}) is not covered", x, y);
+            }
+        }
+
+        // Neighbouring horizon views overlap
+        let between = direction(PI / RING_VIEWS as f32, 0.0);
+        assert!(views[0].project(between).is_some() && views[1].project(between).is_some());
+    }
+
+    #[test]
+    fn test_view_centre_matches_panorama() {
+        // Each column of the panorama has its own color
+        let panorama = RgbImage::from_fn(360, 180, |x, _| Rgb([(x % 256) as u8, 0, 0]));
+        let view = View::new(FRAC_PI_2, 0.0, FRAC_PI_2);
+        let rendered = render_view(&panorama, &view, 64);
+
+        // 90° right of centre is three quarters across the panorama
+        let centre = rendered.get_pixel(32, 32).0[0] as i32;
+        assert!((centre - 270 % 256).abs() <= 1, "{}", centre);
+    }
+
+    #[test]
+    fn test_views_are_aligned_into_one_map() {
+        // Brightness follows latitude and longitude smoothly; the fake model
+        // returns each view's brightness at its own scale and offset, as the
+        // real one returns depth only up to those
+        let panorama = RgbImage::from_fn(256, 128, |x, y| {
+            let (lon, lat) = pixel_angles(x as usize, y as usize, 256, 128);
+            let value = 0.5 + 0.25 * lat.sin() + 0.2 * lon.cos();
+            Rgb([(value * 255.0) as u8; 3])
+        });
+        let mut calls = 0;
+        let depth = estimate_depth(&DynamicImage::ImageRgb8(panorama.clone()), |view| {
+            calls += 1;
+            let (scale, offset) = (1.0 + calls as f32 * 0.3, calls as f32 * 0.05);
+            let gray = view.to_luma8();
+            Ok(DepthMap::from_normalized(Array2::from_shape_fn(
+                (gray.height() as usize, gray.width() as usize),
+                |(y, x)| gray.get_pixel(x as u32, y as u32).0[0] as f32 / 255.0 * scale + offset,
+            )))
+        })
+        .unwrap();
+        assert_eq!(calls, RING_VIEWS + 2);
+        assert_eq!(depth.dim(), (128, 256));
+
+        let expected =
+            crate::depth::normalize_depth_map(Array2::from_shape_fn((128, 256), |(y, x)| {
+                panorama.get_pixel(x as u32, y as u32).0[0] as f32
+            }));
+        let error = (&depth.data - &expected).mapv(f32::abs).mean().unwrap();
+        assert!(error < 0.03, "mean error {}", error);
+    }
+}
@@ -0,0 +1,413 @@
+//! Parallel frame and photo processing
+//!
+//! Video frames and batch photos go through the same stages: decode,
+//! estimate depth, render the stereo pair, and encode. Run one item at a
+//! time, the CPU idles during inference and the model idles during
+//! encoding. The pool overlaps them instead:
+//! - The calling thread decodes and queues items
+//! - Worker threads estimate depth and render stereo pairs
+//! - Results come back in input order, so the calling thread hands them to
+//!   the encoder exactly as a sequential run would
+//!
+//! At most [`ParallelConfig::queue_depth`] items are in flight, which bounds
+//! memory on long videos.
+//!
+//! Workers need a depth model session each. [`SessionSharing::Shared`] loads
+//! one and takes turns on it (inference is serialized, everything else
+//! overlaps); [`SessionSharing::PerWorker`] loads one per worker so inference
+//! runs in parallel too, at the cost of that much more model memory.
+//!
+//! There are no threads in the browser, so only the configuration types
+//! exist on `wasm32`.
+//!
+//! ```
+//! use spatial_maker::pool::{ParallelConfig, SessionSharing};
+//! use spatial_maker::SpatialConfig;
+//!
+//! let config = SpatialConfig::builder()
+//!     .parallel(ParallelConfig {
+//!         workers: 4,
+//!         sessions: SessionSharing::PerWorker,
+//!     })
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(config.parallel.worker_count(), 4);
+//! ```
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::depth::{DepthConfig, DepthEstimator};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::depth_map::DepthMap;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::{SpatialError, SpatialResult};
+#[cfg(not(target_arch = "wasm32"))]
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{mpsc, Arc, Mutex};
+
+/// How workers get a depth model session
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionSharing {
+    /// One session that workers take turns on
+    #[default]
+    Shared,
+    /// One session per worker; inference runs in parallel
+    PerWorker,
+}
+
+/// Parallelism for video and batch photo processing
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParallelConfig {
+    /// Worker threads; 0 uses one per available core, 1 processes items one
+    /// at a time
+    pub workers: u32,
+
+    /// Whether workers share one depth session or load their own
+    pub sessions: SessionSharing,
+}
+
+impl Default for ParallelConfig {
+    fn default() -> Self {
+        Self {
+            workers: 1,
+            sessions: SessionSharing::default(),
+        }
+    }
+}
+
+impl ParallelConfig {
+    /// Number of worker threads to start
+    pub fn worker_count(&self) -> usize {
+        match self.workers {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n as usize,
+        }
+    }
+
+    /// Items decoded ahead of the encoder: enough to keep every worker busy
+    /// while one result waits for an earlier one
+    pub fn queue_depth(&self) -> usize {
+        self.worker_count() * 2
+    }
+}
+
+/// A worker's access to a depth model
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) enum DepthWorker {
+    Shared(Arc<Mutex<DepthEstimator>>),
+    Owned(Box<DepthEstimator>),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DepthWorker {
+    pub fn estimate(&mut self, image: &DynamicImage) -> SpatialResult<DepthMap> {
+        match self {
+            DepthWorker::Shared(estimator) => estimator
+                .lock()
+                .map_err(|_| SpatialError::Worker("Depth session is poisoned".to_string()))?
+                .estimate(image),
+            DepthWorker::Owned(estimator) => estimator.estimate(image),
+        }
+    }
+}
+
+/// Load the depth sessions for `count` workers
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn depth_workers(
+    config: &DepthConfig,
+    parallel: &ParallelConfig,
+    count: usize,
+) -> SpatialResult<Vec<DepthWorker>> {
+    match parallel.sessions {
+        SessionSharing::Shared => {
+            let shared = Arc::new(Mutex::new(DepthEstimator::load(config.clone()).await?));
+            Ok((0..count)
+                .map(|_| DepthWorker::Shared(Arc::clone(&shared)))
+                .collect())
+        }
+        SessionSharing::PerWorker => {
+            // The sessions run side by side, so they split the thread budget
+            let mut config = config.clone();
+            config
+                .session
+                .intra_threads
+                .get_or_insert((crate::cpu::threads() / count.max(1)).max(1));
+            let mut workers = Vec::with_capacity(count);
+            for _ in 0..count {
+                workers.push(DepthWorker::Owned(Box::new(
+                    DepthEstimator::load(config.clone()).await?,
+                )));
+            }
+            Ok(workers)
+        }
+    }
+}
+
+/// Run `work` over `inputs` with each worker state on a thread of the CPU
+/// pool ([`crate::cpu`]), passing the results to `sink` in input order
+///
+/// `inputs` is pulled on the calling thread, and at most `capacity` items are
+/// queued, being worked on, or waiting for an earlier result at once. The
+/// first error from `inputs`, `work`, or `sink` stops the pool: workers
+/// finish their current item, queued items are dropped, and the error is
+/// returned. A panicking worker is reported as an error.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run_ordered<W, T, R, I, F, S>(
+    workers: Vec<W>,
+    capacity: usize,
+    inputs: I,
+    work: F,
+    mut sink: S,
+) -> SpatialResult<()>
+where
+    W: Send,
+    T: Send,
+    R: Send,
+    I: IntoIterator<Item = SpatialResult<T>>,
+    F: Fn(&mut W, T) -> SpatialResult<R> + Sync,
+    S: FnMut(R) -> SpatialResult<()>,
+{
+    if workers.is_empty() {
+        return Err(SpatialError::ConfigError(
+            "The worker pool needs at least one worker".to_string(),
+        ));
+    }
+    let capacity = capacity.max(workers.len());
+    let (job_tx, job_rx) = mpsc::sync_channel::<(u64, T)>(capacity);
+    let job_rx = Mutex::new(job_rx);
+    let (result_tx, result_rx) = mpsc::channel::<(u64, SpatialResult<R>)>();
+    let stop = AtomicBool::new(false);
+
+    crate::cpu::scope(|scope| {
+        for mut state in workers {
+            let (job_rx, result_tx, work, stop) = (&job_rx, result_tx.clone(), &work, &stop);
+            scope.spawn(move |_| loop {
+                let job = match job_rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => break,
+                };
+                let Ok((index, item)) = job else {
+                    break;
+                };
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let result = panic::catch_unwind(AssertUnwindSafe(|| work(&mut state, item)))
+                    .unwrap_or_else(|_| Err(SpatialError::Worker("A worker panicked".to_string())));
+                if result_tx.send((index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut inputs = inputs.into_iter();
+        let mut pending = BTreeMap::new();
+        let (mut queued, mut delivered) = (0u64, 0u64);
+        let mut exhausted = false;
+        let outcome = 'pool: loop {
+            while !exhausted && queued - delivered < capacity as u64 {
+                match inputs.next() {
+                    Some(Ok(item)) => {
+                        // Never blocks: the channel holds `capacity` items
+                        if job_tx.send((queued, item)).is_err() {
+                            break 'pool Err(SpatialError::Worker(
+                                "Worker pool stopped unexpectedly".to_string(),
+                            ));
+                        }
+                        queued += 1;
+                    }
+                    Some(Err(e)) => {
+                        // Delivered after the items queued before it
+                        exhausted = true;
+                        pending.insert(queued, Err(e));
+                        queued += 1;
+                    }
+                    None => exhausted = true,
+                }
+            }
+            if delivered == queued {
+                break Ok(());
+            }
+
+            if !pending.contains_key(&delivered) {
+                match result_rx.recv() {
+                    Ok((index, result)) => {
+                        pending.insert(index, result);
+                    }
+                    Err(_) => {
+                        break Err(SpatialError::Worker(
+                            "Worker pool stopped unexpectedly".to_string(),
+                        ))
+                    }
+                }
+            }
+            let mut failed = None;
+            while let Some(result) = pending.remove(&delivered) {
+                delivered += 1;
+                if let Err(e) = result.and_then(&mut sink) {
+                    failed = Some(e);
+                    break;
+                }
+            }
+            if let Some(e) = failed {
+                break Err(e);
+            }
+        };
+
+        stop.store(true, Ordering::SeqCst);
+        drop(job_tx);
+        outcome
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_results_arrive_in_input_order() {
+        let mut seen = Vec::new();
+        run_ordered(
+            vec![(); 4],
+            8,
+            (0..50u64).map(Ok),
+            |_, n| {
+                // Later items finish first
+                std::thread::sleep(Duration::from_millis((50 - n) % 7));
+                Ok(n * 2)
+            },
+            |n| {
+                seen.push(n);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(seen, (0..50).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_errors_stop_the_pool() {
+        let mut seen = Vec::new();
+        let result = run_ordered(
+            vec![(); 3],
+            6,
+            (0..100u32).map(Ok),
+            |_, n| match n {
+                10 => Err(SpatialError::ConfigError("bad frame".to_string())),
+                n => Ok(n),
+            },
+            |n| {
+                seen.push(n);
+                Ok(())
+            },
+        );
+        assert!(matches!(result, Err(SpatialError::ConfigError(msg)) if msg == "bad frame"));
+        assert_eq!(seen, (0..10).collect::<Vec<_>>());
+
+        // Input errors surface after the items before them
+        let mut seen = Vec::new();
+        let inputs = (0..5u32).map(|n| match n {
+            3 => Err(SpatialError::Cancelled),
+            n => Ok(n),
+        });
+        let result = run_ordered(
+            vec![(); 2],
+            4,
+            inputs,
+            |_, n| Ok(n),
+            |n| {
+                seen.push(n);
+                Ok(())
+            },
+        );
+        assert!(matches!(result, Err(SpatialError::Cancelled)));
+        assert_eq!(seen, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_worker_state_and_panics() {
+        let mut total = 0;
+        run_ordered(
+            vec![0u32; 2],
+            2,
+            (0..10u32).map(Ok),
+            |count, n| {
+                *count += 1;
+                Ok(n)
+            },
+            |n| {
+                total += n;
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(total, 45);
+
+        let result = run_ordered(
+            vec![(); 2],
+            2,
+            (0..4u32).map(Ok),
+            |_, n| if n == 2 { panic!("boom") } else { Ok(n) },
+            |_| Ok(()),
+        );
+        assert!(matches!(result, Err(SpatialError::Worker(_))));
+    }
+
+    #[test]
+    fn test_more_workers_than_pool_threads() {
+        // Jobs side by side share the pool; extra workers wait for a thread
+        let jobs: Vec<_> = (0..3)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let mut seen = Vec::new();
+                    run_ordered(
+                        vec![(); crate::cpu::threads() + 2],
+                        8,
+                        (0..200u64).map(Ok),
+                        |_, n| Ok(n * 2),
+                        |n| {
+                            seen.push(n);
+                            Ok(())
+                        },
+                    )
+                    .map(|()| seen)
+                })
+            })
+            .collect();
+        for job in jobs {
+            let seen = job.join().unwrap().unwrap();
+            assert_eq!(seen, (0..200).map(|n| n * 2).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_parallel_config() {
+        let config = ParallelConfig::default();
+        assert_eq!(config.worker_count(), 1);
+        assert_eq!(config.queue_depth(), 2);
+        assert!(
+            ParallelConfig {
+                workers: 0,
+                ..Default::default()
+            }
+            .worker_count()
+                >= 1
+        );
+
+        let parsed: ParallelConfig =
+            serde_json::from_str(r#"{"workers": 3, "sessions": "per-worker"}"#).unwrap();
+        assert_eq!(parsed.worker_count(), 3);
+        assert_eq!(parsed.sessions, SessionSharing::PerWorker);
+    }
+}
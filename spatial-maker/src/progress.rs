@@ -0,0 +1,221 @@
+//! Progress reporting for the spatial pipeline
+//!
+//! Callers that need more than tracing logs (e.g. a GUI progress bar) can pass
+//! a callback receiving the current [`ProcessingStage`] and the overall
+//! completion percentage (0-100).
+//!
+//! For finer detail, set a [`ProgressSink`] on the config
+//! (`SpatialConfig::progress`): the photo and video pipelines push
+//! [`ProgressEvent`]s into it as they go, including model download and
+//! warping progress. A sink wraps a callback or the sending half of a
+//! channel.
+//!
+//! ```
+//! use spatial_maker::progress::{ProgressEvent, ProgressSink};
+//!
+//! let (sender, receiver) = std::sync::mpsc::channel();
+//! let sink = ProgressSink::from(sender);
+//! sink.emit(ProgressEvent::Preprocess);
+//! assert_eq!(receiver.recv().unwrap(), ProgressEvent::Preprocess);
+//! ```
+
+use std::sync::Arc;
+
+/// A stage of single-photo processing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingStage {
+    /// Decoding the input image
+    Loading,
+    /// Running depth estimation
+    Depth,
+    /// Synthesizing the stereo pair
+    Stereo,
+    /// Encoding and writing the stereo image
+    Saving,
+    /// Encoding the MV-HEVC / spatial HEIC output
+    Mvhevc,
+}
+
+impl ProcessingStage {
+    /// Get the canonical stage name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProcessingStage::Loading => "loading",
+            ProcessingStage::Depth => "depth",
+            ProcessingStage::Stereo => "stereo",
+            ProcessingStage::Saving => "saving",
+            ProcessingStage::Mvhevc => "mvhevc",
+        }
+    }
+
+    /// Overall progress percentage at which this stage begins
+    ///
+    /// Depth estimation dominates the runtime, so it gets the largest share.
+    pub fn start_percent(&self) -> f32 {
+        match self {
+            ProcessingStage::Loading => 0.0,
+            ProcessingStage::Depth => 10.0,
+            ProcessingStage::Stereo => 70.0,
+            ProcessingStage::Saving => 85.0,
+            ProcessingStage::Mvhevc => 92.0,
+        }
+    }
+}
+
+/// A step of the pipeline, pushed into a [`ProgressSink`] as it happens
+///
+/// Serializes as an object tagged by `event`, e.g.
+/// `{"event":"warp","row_pct":40.0}`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// Downloading the depth model (only on first use), `pct` 0-100
+    ModelDownload { pct: f32 },
+    /// Decoding the photo, or probing the video
+    Preprocess,
+    /// Running depth estimation (video: converting frames starts)
+    Inference,
+    /// Synthesizing the second view, `row_pct` of rows warped (0-100);
+    /// photos only
+    Warp { row_pct: f32 },
+    /// Video frames written so far, and the expected total when known
+    Frames { done: u64, total: Option<u64> },
+    /// Encoding and writing the output
+    Encode,
+    /// Processing finished successfully
+    Done,
+}
+
+/// Where the pipeline pushes [`ProgressEvent`]s: a callback or a channel
+///
+/// Cheap to clone; events may come from any thread, in order.
+#[derive(Clone)]
+pub struct ProgressSink(Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl ProgressSink {
+    /// Call `on_event` with every event
+    pub fn new(on_event: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(on_event))
+    }
+
+    /// Push `event` to the callback or channel
+    pub fn emit(&self, event: ProgressEvent) {
+        (self.0)(event)
+    }
+}
+
+impl std::fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
+
+/// Send events into a channel, dropping them once the receiver is gone
+impl From<std::sync::mpsc::Sender<ProgressEvent>> for ProgressSink {
+    fn from(sender: std::sync::mpsc::Sender<ProgressEvent>) -> Self {
+        Self::new(move |event| {
+            let _ = sender.send(event);
+        })
+    }
+}
+
+/// Send events into an async channel, dropping them once the receiver is gone
+#[cfg(not(target_arch = "wasm32"))]
+impl From<tokio::sync::mpsc::UnboundedSender<ProgressEvent>> for ProgressSink {
+    fn from(sender: tokio::sync::mpsc::UnboundedSender<ProgressEvent>) -> Self {
+        Self::new(move |event| {
+            let _ = sender.send(event);
+        })
+    }
+}
+
+/// Emit to an optional sink, treating `None` as nobody listening
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn emit(sink: Option<&ProgressSink>, event: ProgressEvent) {
+    if let Some(sink) = sink {
+        sink.emit(event);
+    }
+}
+
+/// Reports [`ProgressEvent::Warp`] as rows are warped, once per whole
+/// percent so large images don't flood the sink
+pub(crate) struct RowProgress<'a> {
+    sink: Option<&'a ProgressSink>,
+    rows: usize,
+    last_pct: Option<u32>,
+}
+
+impl<'a> RowProgress<'a> {
+    pub(crate) fn new(sink: Option<&'a ProgressSink>, rows: usize) -> Self {
+        Self {
+            sink,
+            rows,
+            last_pct: None,
+        }
+    }
+
+    /// Record that `done` of the rows are warped
+    pub(crate) fn rows_done(&mut self, done: usize) {
+        let Some(sink) = self.sink else {
+            return;
+        };
+        let pct = (done * 100 / self.rows.max(1)) as u32;
+        if self.last_pct != Some(pct) {
+            self.last_pct = Some(pct);
+            sink.emit(ProgressEvent::Warp {
+                row_pct: pct as f32,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_names() {
+        assert_eq!(ProcessingStage::Loading.name(), "loading");
+        assert_eq!(ProcessingStage::Mvhevc.name(), "mvhevc");
+    }
+
+    #[test]
+    fn test_stage_percent_ordering() {
+        let stages = [
+            ProcessingStage::Loading,
+            ProcessingStage::Depth,
+            ProcessingStage::Stereo,
+            ProcessingStage::Saving,
+            ProcessingStage::Mvhevc,
+        ];
+        for pair in stages.windows(2) {
+            assert!(pair[0].start_percent() < pair[1].start_percent());
+        }
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event = ProgressEvent::Warp { row_pct: 40.0 };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"event":"warp","row_pct":40.0}"#);
+        assert_eq!(
+            serde_json::to_string(&ProgressEvent::Done).unwrap(),
+            r#"{"event":"done"}"#
+        );
+    }
+
+    #[test]
+    fn test_row_progress_reports_each_percent_once() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sink = ProgressSink::from(sender);
+        let mut progress = RowProgress::new(Some(&sink), 1000);
+        for row in 0..=1000 {
+            progress.rows_done(row);
+        }
+        drop(sink);
+        let events: Vec<_> = receiver.iter().collect();
+        assert_eq!(events.len(), 101);
+        assert_eq!(events[100], ProgressEvent::Warp { row_pct: 100.0 });
+    }
+}
@@ -0,0 +1,408 @@
+//! Resampling between image and depth resolution
+//!
+//! Depth passes through two resizes on its way to a stereo pair:
+//! 1. The image is scaled to the model's input size ([`ResizeFilter`],
+//!    [`DepthConfig::resize_filter`])
+//! 2. The model's depth map is scaled back up to the image
+//!    ([`DepthUpsampling`], [`SpatialConfig::depth_upsampling`])
+//!
+//! The second matters most for stereo: every depth edge becomes a disparity
+//! edge, so a blocky or blurred upsample shows as halos around foreground
+//! subjects. [`DepthUpsampling::Guided`] snaps depth edges to the image's own
+//! colour edges with a guided filter (He et al., 2010).
+//!
+//! [`DepthConfig::resize_filter`]: crate::depth::DepthConfig::resize_filter
+//! [`SpatialConfig::depth_upsampling`]: crate::SpatialConfig::depth_upsampling
+
+use crate::tiling::resize_depth;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Regularization of the guided filter, for a guide in 0-1; larger values
+/// follow image edges less closely
+const GUIDED_EPS: f64 = 1e-3;
+
+/// Window radius of the guided filter, in depth samples
+const GUIDED_RADIUS: usize = 2;
+
+/// Filter for resizing the image to the depth model's input
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResizeFilter {
+    /// Nearest neighbour; fastest, aliases fine detail
+    Nearest,
+    /// Bilinear
+    Triangle,
+    /// Bicubic (Catmull-Rom)
+    CatmullRom,
+    /// Gaussian; soft, no ringing
+    Gaussian,
+    /// Lanczos with a window of 3; sharpest, may ring at hard edges
+    #[default]
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    /// Get the canonical filter name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResizeFilter::Nearest => "nearest",
+            ResizeFilter::Triangle => "triangle",
+            ResizeFilter::CatmullRom => "catmull-rom",
+            ResizeFilter::Gaussian => "gaussian",
+            ResizeFilter::Lanczos3 => "lanczos3",
+        }
+    }
+
+    /// The equivalent `image` crate filter
+    pub fn filter_type(&self) -> FilterType {
+        match self {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// How the depth map is scaled up to the image for stereo rendering
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepthUpsampling {
+    /// Each pixel takes the nearest depth sample; blocky edges
+    #[default]
+    Nearest,
+    /// Blend the four nearest samples
+    Bilinear,
+    /// Catmull-Rom over the sixteen nearest samples; smoother gradients
+    Bicubic,
+    /// Guided filter on the RGB image, which puts depth edges on the image's
+    /// edges; the fewest halos
+    Guided,
+}
+
+impl DepthUpsampling {
+    /// Get the canonical method name
+    pub fn name(&self) -> &'static str {
+        match self {
+            DepthUpsampling::Nearest => "nearest",
+            DepthUpsampling::Bilinear => "bilinear",
+            DepthUpsampling::Bicubic => "bicubic",
+            DepthUpsampling::Guided => "guided",
+        }
+    }
+}
+
+impl std::str::FromStr for DepthUpsampling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nearest" => Ok(DepthUpsampling::Nearest),
+            "bilinear" => Ok(DepthUpsampling::Bilinear),
+            "bicubic" => Ok(DepthUpsampling::Bicubic),
+            "guided" => Ok(DepthUpsampling::Guided),
+            other => Err(format!(
+                "Unknown depth upsampling '{}': expected nearest, bilinear, bicubic, or guided",
+                other
+            )),
+        }
+    }
+}
+
+/// Scale `depth` to the size of `guide` with `method`
+///
+/// Depth already at the image's size is returned as is, as is depth for
+/// [`DepthUpsampling::Nearest`], which the stereo warp samples directly.
+pub fn upsample_depth<'a>(
+    depth: &'a Array2<f32>,
+    guide: &DynamicImage,
+    method: DepthUpsampling,
+) -> Cow<'a, Array2<f32>> {
+    let (height, width) = (guide.height() as usize, guide.width() as usize);
+    if depth.dim() == (height, width) || depth.is_empty() {
+        return Cow::Borrowed(depth);
+    }
+    match method {
+        DepthUpsampling::Nearest => Cow::Borrowed(depth),
+        DepthUpsampling::Bilinear => Cow::Owned(resize_depth(depth, height, width)),
+        DepthUpsampling::Bicubic => Cow::Owned(resize_depth_bicubic(depth, height, width)),
+        DepthUpsampling::Guided => Cow::Owned(guided_upsample(depth, guide)),
+    }
+}
+
+/// Resize a depth map with Catmull-Rom interpolation, clamped to 0-1
+fn resize_depth_bicubic(depth: &Array2<f32>, height: usize, width: usize) -> Array2<f32> {
+    let (src_h, src_w) = depth.dim();
+    let scale_y = src_h as f32 / height as f32;
+    let scale_x = src_w as f32 / width as f32;
+    let at = |y: isize, x: isize| {
+        depth[[
+            y.clamp(0, src_h as isize - 1) as usize,
+            x.clamp(0, src_w as isize - 1) as usize,
+        ]]
+    };
+
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        // Sample at pixel centres, as resize_depth does
+        let sy = (y as f32 + 0.5) * scale_y - 0.5;
+        let sx = (x as f32 + 0.5) * scale_x - 0.5;
+        let (y0, x0) = (sy.floor() as isize, sx.floor() as isize);
+        let wy = catmull_rom_weights(sy - y0 as f32);
+        let wx = catmull_rom_weights(sx - x0 as f32);
+
+        let mut value = 0.0;
+        for (j, wy) in wy.iter().enumerate() {
+            for (i, wx) in wx.iter().enumerate() {
+                value += wy * wx * at(y0 + j as isize - 1, x0 + i as isize - 1);
+            }
+        }
+        value.clamp(0.0, 1.0)
+    })
+}
+
+/// Catmull-Rom weights of the four samples around a point `t` (0-1) past
+/// the second
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+/// Fast guided filter (He and Sun, 2015) with an RGB guide
+///
+/// A linear model from guide colour to depth is fitted in each window at
+/// depth resolution, then evaluated on the full-resolution guide, so depth
+/// edges land where the image's colour edges are. Clamped to 0-1.
+fn guided_upsample(depth: &Array2<f32>, guide: &DynamicImage) -> Array2<f32> {
+    let (low_h, low_w) = depth.dim();
+    let full = guide.to_rgb32f();
+    let low = image::imageops::resize(&full, low_w as u32, low_h as u32, FilterType::Triangle);
+    let color: [Array2<f32>; 3] = std::array::from_fn(|c| {
+        Array2::from_shape_fn((low_h, low_w), |(y, x)| {
+            low.get_pixel(x as u32, y as u32)[c].clamp(0.0, 1.0)
+        })
+    });
+
+    let r = GUIDED_RADIUS;
+    let mean_i = color.each_ref().map(|channel| box_mean(channel, r));
+    let mean_p = box_mean(depth, r);
+    let cov_ip: [Array2<f32>; 3] =
+        std::array::from_fn(|c| box_mean(&(&color[c] * depth), r) - &mean_i[c] * &mean_p);
+    // Upper triangle of the guide's covariance: rr, rg, rb, gg, gb, bb
+    let var_i = [(0, 0), (0, 1), (0, 2), (1, 1), (1, 2), (2, 2)]
+        .map(|(j, k)| box_mean(&(&color[j] * &color[k]), r) - &mean_i[j] * &mean_i[k]);
+
+    let mut a: [Array2<f32>; 3] = std::array::from_fn(|_| Array2::zeros((low_h, low_w)));
+    let mut b = Array2::zeros((low_h, low_w));
+    for y in 0..low_h {
+        for x in 0..low_w {
+            let v = var_i.each_ref().map(|v| v[[y, x]] as f64);
+            let sigma = [
+                [v[0] + GUIDED_EPS, v[1], v[2]],
+                [v[1], v[3] + GUIDED_EPS, v[4]],
+                [v[2], v[4], v[5] + GUIDED_EPS],
+            ];
+            let cov = cov_ip.each_ref().map(|c| c[[y, x]] as f64);
+            let coefficients = solve_symmetric3(sigma, cov);
+            let mut offset = mean_p[[y, x]] as f64;
+            for c in 0..3 {
+                a[c][[y, x]] = coefficients[c] as f32;
+                offset -= coefficients[c] * mean_i[c][[y, x]] as f64;
+            }
+            b[[y, x]] = offset as f32;
+        }
+    }
+    let mean_a = a.each_ref().map(|a| box_mean(a, r));
+    let mean_b = box_mean(&b, r);
+
+    // Evaluate the averaged model at every image pixel, interpolating the
+    // coefficients bilinearly between depth samples
+    let (height, width) = (full.height() as usize, full.width() as usize);
+    let columns: Vec<(usize, usize, f32)> = (0..width)
+        .map(|x| source_position(x, width, low_w))
+        .collect();
+    let mut output = Array2::zeros((height, width));
+    for y in 0..height {
+        let (y0, y1, ty) = source_position(y, height, low_h);
+        for (x, &(x0, x1, tx)) in columns.iter().enumerate() {
+            let sample = |values: &Array2<f32>| {
+                let top = values[[y0, x0]] * (1.0 - tx) + values[[y0, x1]] * tx;
+                let bottom = values[[y1, x0]] * (1.0 - tx) + values[[y1, x1]] * tx;
+                top * (1.0 - ty) + bottom * ty
+            };
+            let pixel = full.get_pixel(x as u32, y as u32);
+            let mut value = sample(&mean_b);
+            for c in 0..3 {
+                value += sample(&mean_a[c]) * pixel[c].clamp(0.0, 1.0);
+            }
+            output[[y, x]] = value.clamp(0.0, 1.0);
+        }
+    }
+    output
+}
+
+/// Neighbouring samples and blend weight for output index `i` of `size`,
+/// from a source of `source_size` samples (pixel centres aligned, as in
+/// [`resize_depth`])
+fn source_position(i: usize, size: usize, source_size: usize) -> (usize, usize, f32) {
+    let scale = source_size as f32 / size as f32;
+    let s = ((i as f32 + 0.5) * scale - 0.5).clamp(0.0, (source_size - 1) as f32);
+    let s0 = s as usize;
+    (s0, (s0 + 1).min(source_size - 1), s - s0 as f32)
+}
+
+/// Solve `matrix * x = rhs` for a symmetric positive definite 3×3 `matrix`
+fn solve_symmetric3(matrix: [[f64; 3]; 3], rhs: [f64; 3]) -> [f64; 3] {
+    let [[a, b, c], [_, d, e], [_, _, f]] = matrix;
+    // Cofactors; the inverse is their matrix over the determinant
+    let inverse = [
+        [d * f - e * e, c * e - b * f, b * e - c * d],
+        [c * e - b * f, a * f - c * c, b * c - a * e],
+        [b * e - c * d, b * c - a * e, a * d - b * b],
+    ];
+    let det = a * inverse[0][0] + b * inverse[0][1] + c * inverse[0][2];
+    inverse.map(|row| (row[0] * rhs[0] + row[1] * rhs[1] + row[2] * rhs[2]) / det)
+}
+
+/// Mean over the `(2 * radius + 1)`² window around each element, shrunk at
+/// the borders
+fn box_mean(values: &Array2<f32>, radius: usize) -> Array2<f32> {
+    let (height, width) = values.dim();
+    // Summed-area table with a zero row and column in front
+    let mut table = Array2::<f64>::zeros((height + 1, width + 1));
+    for y in 0..height {
+        let mut row = 0.0;
+        for x in 0..width {
+            row += values[[y, x]] as f64;
+            table[[y + 1, x + 1]] = table[[y, x + 1]] + row;
+        }
+    }
+
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+        let sum = table[[y1, x1]] - table[[y0, x1]] - table[[y1, x0]] + table[[y0, x0]];
+        (sum / ((y1 - y0) * (x1 - x0)) as f64) as f32
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    /// A guide that is dark on the left `split` columns and bright after
+    fn step_image(width: u32, height: u32, split: u32) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+            Luma([if x < split { 20 } else { 235 }])
+        }))
+    }
+
+    #[test]
+    fn test_upsample_keeps_matching_and_nearest_depth() {
+        let depth = Array2::from_elem((4, 4), 0.5);
+        let image = step_image(4, 4, 2);
+        assert!(matches!(
+            upsample_depth(&depth, &image, DepthUpsampling::Guided),
+            Cow::Borrowed(_)
+        ));
+        let larger = step_image(16, 16, 8);
+        assert!(matches!(
+            upsample_depth(&depth, &larger, DepthUpsampling::Nearest),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_bicubic_is_exact_on_ramps_and_stays_in_range() {
+        // Catmull-Rom reproduces linear gradients away from the borders
+        let ramp = Array2::from_shape_fn((8, 8), |(_, x)| x as f32 / 7.0);
+        let upsampled = resize_depth_bicubic(&ramp, 16, 16);
+        let bilinear = resize_depth(&ramp, 16, 16);
+        for x in 4..12 {
+            assert!((upsampled[[8, x]] - bilinear[[8, x]]).abs() < 1e-5);
+        }
+
+        // Overshoot at a hard edge is clamped
+        let edge = Array2::from_shape_fn((4, 4), |(_, x)| if x < 2 { 0.0 } else { 1.0 });
+        let upsampled = resize_depth_bicubic(&edge, 16, 16);
+        assert!(upsampled.iter().all(|v| (0.0..=1.0).contains(v)));
+    }
+
+    #[test]
+    fn test_guided_follows_image_edges() {
+        // Depth steps between samples 1 and 2 of 4, so bilinear smears it
+        // over image columns 6-9; the image itself steps at column 8
+        let depth = Array2::from_shape_fn((4, 4), |(_, x)| if x < 2 { 0.1 } else { 0.9 });
+        let image = step_image(16, 16, 8);
+        let bilinear = upsample_depth(&depth, &image, DepthUpsampling::Bilinear);
+        let guided = upsample_depth(&depth, &image, DepthUpsampling::Guided);
+        assert_eq!(guided.dim(), (16, 16));
+
+        // The depth edge moves onto the image edge: one sharp step between
+        // columns 7 and 8 instead of a ramp
+        let step = |depth: &Array2<f32>, x: usize| depth[[8, x + 1]] - depth[[8, x]];
+        assert!(step(&bilinear, 7) < 0.25);
+        assert!(step(&guided, 7) > 0.5);
+        assert!(guided[[8, 6]] < 0.25 && guided[[8, 9]] > 0.75);
+    }
+
+    #[test]
+    fn test_guided_uses_colour_edges() {
+        // Red and a green of about the same brightness: invisible in luma
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(32, 8, |x, _| {
+            image::Rgb(if x < 16 { [200, 0, 0] } else { [0, 90, 0] })
+        }));
+        let depth = Array2::from_shape_fn((2, 8), |(_, x)| if x < 4 { 0.2 } else { 0.8 });
+        let guided = upsample_depth(&depth, &image, DepthUpsampling::Guided);
+        assert_eq!(guided.dim(), (8, 32));
+        assert!(guided[[4, 15]] < 0.3);
+        assert!(guided[[4, 16]] > 0.7);
+    }
+
+    #[test]
+    fn test_solve_symmetric3() {
+        let matrix = [[4.0, 1.0, 0.5], [1.0, 3.0, 0.2], [0.5, 0.2, 2.0]];
+        let x = [1.0, -2.0, 0.5];
+        let rhs = matrix.map(|row| row[0] * x[0] + row[1] * x[1] + row[2] * x[2]);
+        let solved = solve_symmetric3(matrix, rhs);
+        for (a, b) in solved.iter().zip(x) {
+            assert!((a - b).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_box_mean() {
+        let values = Array2::from_shape_fn((3, 3), |(y, x)| (y * 3 + x) as f32);
+        let mean = box_mean(&values, 1);
+        assert_eq!(mean[[1, 1]], 4.0);
+        // Corners average the four elements in reach
+        assert_eq!(mean[[0, 0]], 2.0);
+        assert_eq!(box_mean(&values, 0), values);
+    }
+
+    #[test]
+    fn test_names_and_serde() {
+        assert_eq!(ResizeFilter::default(), ResizeFilter::Lanczos3);
+        assert_eq!(
+            serde_json::to_string(&ResizeFilter::CatmullRom).unwrap(),
+            "\"catmull-rom\""
+        );
+        assert_eq!(ResizeFilter::CatmullRom.name(), "catmull-rom");
+        assert_eq!(DepthUpsampling::default(), DepthUpsampling::Nearest);
+        let parsed: DepthUpsampling = serde_json::from_str("\"guided\"").unwrap();
+        assert_eq!(parsed, DepthUpsampling::Guided);
+        assert_eq!(parsed.name(), "guided");
+        assert_eq!("Bicubic".parse(), Ok(DepthUpsampling::Bicubic));
+        assert!("lanczos".parse::<DepthUpsampling>().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! ONNX Runtime session tuning
+//!
+//! The defaults suit a desktop app running one model at a time: inference
+//! uses the crate's CPU thread budget (see [`crate::cpu`]) and ONNX
+//! Runtime's own defaults for everything else. Servers running several
+//! estimators side by side, or sharing the machine with other work, can
+//! tune the thread pools, graph optimization, and memory arena through
+//! [`SessionTuning`] (`DepthConfig::session`).
+//!
+//! ```
+//! use spatial_maker::session::{GraphOptimization, SessionTuning};
+//!
+//! // Four estimators on a 16-core server, each with a fixed share
+//! let tuning = SessionTuning {
+//!     intra_threads: Some(4),
+//!     cpu_arena: false,
+//!     ..Default::default()
+//! };
+//! assert_eq!(tuning.optimization, GraphOptimization::All);
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use serde::{Deserialize, Serialize};
+
+/// How much ONNX Runtime rewrites the graph when a session is built
+///
+/// Higher levels take longer to load and usually run faster.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GraphOptimization {
+    /// No rewrites
+    Disable,
+    /// Constant folding and redundant node removal
+    Basic,
+    /// Basic plus operator fusions
+    Extended,
+    /// Extended plus memory layout changes (NCHWc on CPU)
+    Layout,
+    /// Every optimization ONNX Runtime has
+    #[default]
+    All,
+}
+
+impl GraphOptimization {
+    /// Get the canonical level name
+    pub fn name(&self) -> &'static str {
+        match self {
+            GraphOptimization::Disable => "disable",
+            GraphOptimization::Basic => "basic",
+            GraphOptimization::Extended => "extended",
+            GraphOptimization::Layout => "layout",
+            GraphOptimization::All => "all",
+        }
+    }
+}
+
+impl std::str::FromStr for GraphOptimization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disable" | "none" | "0" => Ok(GraphOptimization::Disable),
+            "basic" | "1" => Ok(GraphOptimization::Basic),
+            "extended" | "2" => Ok(GraphOptimization::Extended),
+            "layout" | "3" => Ok(GraphOptimization::Layout),
+            "all" => Ok(GraphOptimization::All),
+            other => Err(format!(
+                "Unknown graph optimization '{}': expected disable, basic, extended, layout, or all",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<GraphOptimization> for ort::session::builder::GraphOptimizationLevel {
+    fn from(level: GraphOptimization) -> Self {
+        use ort::session::builder::GraphOptimizationLevel;
+        match level {
+            GraphOptimization::Disable => GraphOptimizationLevel::Disable,
+            GraphOptimization::Basic => GraphOptimizationLevel::Level1,
+            GraphOptimization::Extended => GraphOptimizationLevel::Level2,
+            GraphOptimization::Layout => GraphOptimizationLevel::Level3,
+            GraphOptimization::All => GraphOptimizationLevel::All,
+        }
+    }
+}
+
+/// Settings passed through to the ONNX Runtime session builder
+///
+/// Native builds only; ONNX Runtime Web picks its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionTuning {
+    /// Threads each operator is split across; `None` uses the crate's CPU
+    /// thread budget ([`crate::cpu::threads`]), divided between the workers
+    /// when each loads its own session ([`crate::pool::SessionSharing`])
+    pub intra_threads: Option<usize>,
+
+    /// Threads running independent operators at the same time; `None`
+    /// runs operators one after another. Above 1 also turns on parallel
+    /// execution, which rarely helps this model's mostly sequential graph.
+    pub inter_threads: Option<usize>,
+
+    /// Graph optimization level
+    pub optimization: GraphOptimization,
+
+    /// Plan tensor memory from the first run's shapes and reuse the plan;
+    /// only pays off when every image runs at the same size
+    pub memory_pattern: bool,
+
+    /// Keep freed CPU memory in ONNX Runtime's arena for later runs instead
+    /// of returning it to the system; turning it off lowers the resident
+    /// size between jobs at some speed cost
+    pub cpu_arena: bool,
+}
+
+impl Default for SessionTuning {
+    fn default() -> Self {
+        Self {
+            intra_threads: None,
+            inter_threads: None,
+            optimization: GraphOptimization::default(),
+            memory_pattern: true,
+            cpu_arena: true,
+        }
+    }
+}
+
+impl SessionTuning {
+    /// Whether these are the default settings
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check the settings are in range
+    pub fn validate(&self) -> SpatialResult<()> {
+        if self.intra_threads == Some(0) {
+            return Err(SpatialError::ConfigError(
+                "Session intra_threads must be at least 1".to_string(),
+            ));
+        }
+        if self.inter_threads == Some(0) {
+            return Err(SpatialError::ConfigError(
+                "Session inter_threads must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply these settings to a session builder
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn apply(
+        &self,
+        builder: ort::session::builder::SessionBuilder,
+    ) -> SpatialResult<ort::session::builder::SessionBuilder> {
+        let option =
+            |what: &str, e: ort::Error<_>| SpatialError::ort(format!("Failed to set {}", what), e);
+
+        let intra_threads = self.intra_threads.unwrap_or_else(crate::cpu::threads);
+        let mut builder = builder
+            .with_intra_threads(intra_threads)
+            .map_err(|e| option("inference threads", e))?
+            .with_optimization_level(self.optimization.into())
+            .map_err(|e| option("graph optimization level", e))?
+            .with_memory_pattern(self.memory_pattern)
+            .map_err(|e| option("memory pattern", e))?;
+        if let Some(inter_threads) = self.inter_threads {
+            builder = builder
+                .with_inter_threads(inter_threads)
+                .map_err(|e| option("inter-op threads", e))?
+                .with_parallel_execution(inter_threads > 1)
+                .map_err(|e| option("parallel execution", e))?;
+        }
+        if !self.cpu_arena {
+            // The arena is a session option, so it stays off when the depth
+            // model registers its GPU providers afterwards
+            let cpu = ort::ep::CPU::default().with_arena_allocator(false);
+            builder = builder
+                .with_execution_providers([cpu.build().error_on_failure()])
+                .map_err(|e| option("CPU memory arena", e))?;
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph_optimization_names_round_trip() {
+        for level in [
+            GraphOptimization::Disable,
+            GraphOptimization::Basic,
+            GraphOptimization::Extended,
+            GraphOptimization::Layout,
+            GraphOptimization::All,
+        ] {
+            assert_eq!(level.name().parse::<GraphOptimization>(), Ok(level));
+            assert_eq!(
+                serde_json::to_string(&level).unwrap(),
+                format!("\"{}\"", level.name())
+            );
+        }
+        assert!("fastest".parse::<GraphOptimization>().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SessionTuning::default().validate().is_ok());
+        let tuning = SessionTuning {
+            intra_threads: Some(0),
+            ..Default::default()
+        };
+        assert!(tuning.validate().is_err());
+    }
+
+    #[test]
+    fn test_partial_json_keeps_defaults() {
+        let tuning: SessionTuning = serde_json::from_str(r#"{"inter_threads": 2}"#).unwrap();
+        assert_eq!(tuning.inter_threads, Some(2));
+        assert!(tuning.memory_pattern && tuning.cpu_arena);
+    }
+}
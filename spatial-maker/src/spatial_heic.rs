@@ -0,0 +1,300 @@
+//! Native spatial photo (stereo HEIC) writing on macOS
+//!
+//! Writes a left/right pair as a two-image HEIC tagged as a stereo pair
+//! through ImageIO, the same container Apple's `spatial` CLI and the Photos
+//! app produce. This removes the need for an external tool on macOS.
+//!
+//! ImageIO metadata keys are passed as their string values rather than linked
+//! symbols: the stereo-group and camera-model keys only exist in the macOS 15
+//! SDK, and linking them would stop the library loading on older systems.
+//! Older ImageIO versions ignore the keys and write a plain two-image HEIC.
+
+use crate::cancel::{self, CancellationToken};
+use crate::error::{encoding_error, SpatialError, SpatialResult};
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::data::CFData;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use core_foundation::url::{CFURLRef, CFURL};
+use core_foundation_sys::data::CFDataRef;
+use core_foundation_sys::dictionary::CFDictionaryRef;
+use image::DynamicImage;
+use std::ffi::c_void;
+use std::path::Path;
+
+type CGColorSpaceRef = *const c_void;
+type CGDataProviderRef = *const c_void;
+type CGImageRef = *const c_void;
+type CGImageDestinationRef = *const c_void;
+
+/// `kCGImageAlphaNone` with default byte order
+const BITMAP_INFO_RGB: u32 = 0;
+
+/// `kCGRenderingIntentDefault`
+const RENDERING_INTENT_DEFAULT: i32 = 0;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    static kCGColorSpaceSRGB: CFStringRef;
+
+    fn CGColorSpaceCreateWithName(name: CFStringRef) -> CGColorSpaceRef;
+    fn CGColorSpaceRelease(space: CGColorSpaceRef);
+    fn CGDataProviderCreateWithCFData(data: CFDataRef) -> CGDataProviderRef;
+    fn CGDataProviderRelease(provider: CGDataProviderRef);
+    fn CGImageCreate(
+        width: usize,
+        height: usize,
+        bits_per_component: usize,
+        bits_per_pixel: usize,
+        bytes_per_row: usize,
+        space: CGColorSpaceRef,
+        bitmap_info: u32,
+        provider: CGDataProviderRef,
+        decode: *const f64,
+        should_interpolate: bool,
+        intent: i32,
+    ) -> CGImageRef;
+    fn CGImageRelease(image: CGImageRef);
+}
+
+#[link(name = "ImageIO", kind = "framework")]
+extern "C" {
+    fn CGImageDestinationCreateWithURL(
+        url: CFURLRef,
+        type_identifier: CFStringRef,
+        count: usize,
+        options: CFDictionaryRef,
+    ) -> CGImageDestinationRef;
+    fn CGImageDestinationSetProperties(dest: CGImageDestinationRef, properties: CFDictionaryRef);
+    fn CGImageDestinationAddImage(
+        dest: CGImageDestinationRef,
+        image: CGImageRef,
+        properties: CFDictionaryRef,
+    );
+    fn CGImageDestinationFinalize(dest: CGImageDestinationRef) -> bool;
+}
+
+/// Camera parameters recorded in the spatial photo metadata
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StereoCameraParams {
+    /// Distance between the two virtual cameras in millimetres
+    pub baseline_mm: f32,
+    /// Horizontal field of view in degrees
+    pub horizontal_fov: f32,
+    /// Lossy compression quality (0.0-1.0)
+    pub quality: f32,
+}
+
+/// Owned CoreGraphics image, released on drop
+struct CgImage(CGImageRef);
+
+impl Drop for CgImage {
+    fn drop(&mut self) {
+        unsafe { CGImageRelease(self.0) }
+    }
+}
+
+/// Owned ImageIO destination, released on drop
+struct Destination(CGImageDestinationRef);
+
+impl Drop for Destination {
+    fn drop(&mut self) {
+        unsafe { core_foundation_sys::base::CFRelease(self.0) }
+    }
+}
+
+/// Write a stereo pair as an Apple spatial photo (HEIC)
+pub(crate) fn write_spatial_heic(
+    left: &DynamicImage,
+    right: &DynamicImage,
+    output_path: &Path,
+    params: StereoCameraParams,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    if left.width() != right.width() || left.height() != right.height() {
+        return Err(SpatialError::ConfigError(format!(
+            "Left and right images must have the same size: {}x{} != {}x{}",
+            left.width(),
+            left.height(),
+            right.width(),
+            right.height()
+        )));
+    }
+
+    tracing::info!("🎬 Writing spatial HEIC natively to {:?}", output_path);
+
+    let left_image = create_cg_image(left)?;
+    let right_image = create_cg_image(right)?;
+    cancel::check(cancel)?;
+
+    let url = CFURL::from_path(output_path, false).ok_or_else(|| {
+        SpatialError::ConfigError(format!("Invalid output path: {:?}", output_path))
+    })?;
+    let heic_type = CFString::from_static_string("public.heic");
+
+    let destination = unsafe {
+        CGImageDestinationCreateWithURL(
+            url.as_concrete_TypeRef(),
+            heic_type.as_concrete_TypeRef(),
+            2,
+            std::ptr::null(),
+        )
+    };
+    if destination.is_null() {
+        return Err(SpatialError::image(
+            "HEIC encoding is not available on this system",
+            encoding_error("HEIC", "ImageIO has no HEIC destination"),
+        ));
+    }
+    let destination = Destination(destination);
+
+    let container = dictionary(&[("PrimaryImage", CFNumber::from(0).as_CFType())]);
+    unsafe {
+        CGImageDestinationSetProperties(destination.0, container.as_concrete_TypeRef());
+    }
+
+    let (width, height) = (left.width(), left.height());
+    for (image, is_left) in [(&left_image, true), (&right_image, false)] {
+        let properties = image_properties(width, height, is_left, params);
+        unsafe {
+            CGImageDestinationAddImage(destination.0, image.0, properties.as_concrete_TypeRef());
+        }
+    }
+
+    cancel::check(cancel)?;
+    if !unsafe { CGImageDestinationFinalize(destination.0) } {
+        let _ = std::fs::remove_file(output_path);
+        return Err(SpatialError::image(
+            "Failed to finalize spatial HEIC",
+            encoding_error("HEIC", "ImageIO could not write the file"),
+        ));
+    }
+
+    tracing::info!("✅ Spatial HEIC written to {:?}", output_path);
+    Ok(())
+}
+
+/// Per-image properties marking one half of a stereo pair
+fn image_properties(
+    width: u32,
+    height: u32,
+    is_left: bool,
+    params: StereoCameraParams,
+) -> CFDictionary<CFType, CFType> {
+    let side_key = if is_left {
+        "GroupImageIsLeftImage"
+    } else {
+        "GroupImageIsRightImage"
+    };
+    let group = dictionary(&[
+        ("GroupIndex", CFNumber::from(0).as_CFType()),
+        (
+            "GroupType",
+            CFString::from_static_string("StereoPair").as_CFType(),
+        ),
+        (side_key, CFBoolean::true_value().as_CFType()),
+        (
+            "GroupImageDisparityAdjustment",
+            CFNumber::from(0).as_CFType(),
+        ),
+    ]);
+
+    // Pinhole intrinsics (row-major 3x3) with the principal point at the centre
+    let focal_px = 0.5 * width as f64 / (params.horizontal_fov as f64 / 2.0).to_radians().tan();
+    let intrinsics = number_array(&[
+        focal_px,
+        0.0,
+        width as f64 / 2.0,
+        0.0,
+        focal_px,
+        height as f64 / 2.0,
+        0.0,
+        0.0,
+        1.0,
+    ]);
+    let camera_model = dictionary(&[
+        ("Intrinsics", intrinsics.as_CFType()),
+        (
+            "ModelType",
+            CFString::from_static_string("SimplifiedPinhole").as_CFType(),
+        ),
+    ]);
+
+    // Left camera at the origin, right camera offset by the baseline (metres)
+    let x = if is_left {
+        0.0
+    } else {
+        params.baseline_mm as f64 / 1000.0
+    };
+    let extrinsics = dictionary(&[
+        ("Position", number_array(&[x, 0.0, 0.0]).as_CFType()),
+        (
+            "Rotation",
+            number_array(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]).as_CFType(),
+        ),
+    ]);
+    let heif = dictionary(&[
+        ("CameraModel", camera_model.as_CFType()),
+        ("CameraExtrinsics", extrinsics.as_CFType()),
+    ]);
+
+    dictionary(&[
+        (
+            "kCGImageDestinationLossyCompressionQuality",
+            CFNumber::from(params.quality.clamp(0.0, 1.0) as f64).as_CFType(),
+        ),
+        ("Groups", group.as_CFType()),
+        ("{HEIF}", heif.as_CFType()),
+    ])
+}
+
+/// Wrap RGB8 pixels in a CGImage tagged as sRGB
+fn create_cg_image(image: &DynamicImage) -> SpatialResult<CgImage> {
+    let rgb = image.to_rgb8();
+    let (width, height) = (rgb.width() as usize, rgb.height() as usize);
+    let data = CFData::from_buffer(rgb.as_raw());
+
+    unsafe {
+        let space = CGColorSpaceCreateWithName(kCGColorSpaceSRGB);
+        let provider = CGDataProviderCreateWithCFData(data.as_concrete_TypeRef());
+        let cg_image = CGImageCreate(
+            width,
+            height,
+            8,
+            24,
+            width * 3,
+            space,
+            BITMAP_INFO_RGB,
+            provider,
+            std::ptr::null(),
+            false,
+            RENDERING_INTENT_DEFAULT,
+        );
+        CGDataProviderRelease(provider);
+        CGColorSpaceRelease(space);
+
+        if cg_image.is_null() {
+            return Err(SpatialError::image(
+                "Failed to create CGImage from stereo image",
+                encoding_error("HEIC", "CGImageCreate returned null"),
+            ));
+        }
+        Ok(CgImage(cg_image))
+    }
+}
+
+fn dictionary(pairs: &[(&'static str, CFType)]) -> CFDictionary<CFType, CFType> {
+    let pairs: Vec<(CFType, CFType)> = pairs
+        .iter()
+        .map(|(key, value)| (CFString::from_static_string(key).as_CFType(), value.clone()))
+        .collect();
+    CFDictionary::from_CFType_pairs(&pairs)
+}
+
+fn number_array(values: &[f64]) -> CFArray<CFNumber> {
+    let numbers: Vec<CFNumber> = values.iter().map(|&v| CFNumber::from(v)).collect();
+    CFArray::from_CFTypes(&numbers)
+}
@@ -2,10 +2,99 @@
 //!
 //! This module converts a depth map and an input image into left and right stereo views
 //! by horizontally shifting pixels based on their depth values.
+//!
+//! Float images (such as OpenEXR plates) are warped as 32-bit float RGB, so
+//! HDR values and precision survive; 16-bit images are warped at 16 bits,
+//! and everything else as 8-bit RGB.
+//! Images with an alpha channel keep it: alpha is warped with the color, so
+//! transparent cut-outs stay transparent in both eyes.
+//!
+//! Pixels that no source pixel lands on (disocclusions, uncovered beside
+//! foreground edges) are filled as [`HoleFill`] says.
 
+use crate::cancel::{self, CancellationToken};
 use crate::error::SpatialResult;
-use image::{DynamicImage, ImageBuffer};
-use ndarray::Array2;
+use crate::progress::{ProgressSink, RowProgress};
+use crate::tiling::resize_depth;
+use image::{imageops, ColorType, DynamicImage, ImageBuffer, Pixel, Rgb, Rgba};
+use ndarray::{s, Array2, Axis};
+use serde::{Deserialize, Serialize};
+
+/// How disocclusions are filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HoleFillStrategy {
+    /// Extend the farther (background) side of the gap along the row, so
+    /// foreground colors never bleed into the uncovered background
+    #[default]
+    BackgroundStretch,
+    /// Copy the nearest warped pixel, searching outward in square rings
+    NearestValid,
+    /// Copy the nearest warped pixel on the same row
+    HorizontalStretch,
+    /// Average the warped pixels around the hole
+    Blur,
+    /// Leave holes black, e.g. to inpaint them elsewhere
+    None,
+}
+
+impl HoleFillStrategy {
+    /// Get the canonical strategy name
+    pub fn name(&self) -> &'static str {
+        match self {
+            HoleFillStrategy::BackgroundStretch => "background-stretch",
+            HoleFillStrategy::NearestValid => "nearest-valid",
+            HoleFillStrategy::HorizontalStretch => "horizontal-stretch",
+            HoleFillStrategy::Blur => "blur",
+            HoleFillStrategy::None => "none",
+        }
+    }
+}
+
+impl std::str::FromStr for HoleFillStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "background-stretch" | "background" => Ok(HoleFillStrategy::BackgroundStretch),
+            "nearest-valid" | "nearest" => Ok(HoleFillStrategy::NearestValid),
+            "horizontal-stretch" | "stretch" => Ok(HoleFillStrategy::HorizontalStretch),
+            "blur" => Ok(HoleFillStrategy::Blur),
+            "none" => Ok(HoleFillStrategy::None),
+            other => Err(format!(
+                "Unknown hole fill '{}': expected background-stretch, nearest-valid, horizontal-stretch, blur, or none",
+                other
+            )),
+        }
+    }
+}
+
+/// Disocclusion filling settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HoleFill {
+    pub strategy: HoleFillStrategy,
+
+    /// How far from a hole (pixels) to look for warped pixels; holes
+    /// farther than this from any are left black
+    pub search_radius: u32,
+}
+
+impl Default for HoleFill {
+    fn default() -> Self {
+        Self {
+            strategy: HoleFillStrategy::default(),
+            search_radius: 20,
+        }
+    }
+}
+
+impl HoleFill {
+    /// Whether these are the default settings
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
 
 /// Generate a stereo pair (left and right images) from an image and depth map
 ///
@@ -28,15 +117,64 @@ pub fn generate_stereo_pair(
     image: &DynamicImage,
     depth: &Array2<f32>,
     max_disparity: u32,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    warp_stereo_pair(
+        image,
+        depth,
+        max_disparity,
+        &HoleFill::default(),
+        None,
+        None,
+    )
+}
+
+/// Generate a stereo pair, aborting with `SpatialError::Cancelled` once `cancel` is set
+///
+/// The token is checked once per image row, so cancellation takes effect
+/// within a fraction of the total warp time even for very large images.
+pub fn generate_stereo_pair_cancellable(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    cancel: &CancellationToken,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    warp_stereo_pair(
+        image,
+        depth,
+        max_disparity,
+        &HoleFill::default(),
+        Some(cancel),
+        None,
+    )
+}
+
+/// Generate a stereo pair, filling disocclusions as `fill` says
+pub fn generate_stereo_pair_with_fill(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    warp_stereo_pair(image, depth, max_disparity, fill, cancel, None)
+}
+
+/// [`generate_stereo_pair_with_fill`], reporting warped rows to `progress`
+pub(crate) fn warp_stereo_pair(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+    progress: Option<&ProgressSink>,
 ) -> SpatialResult<(DynamicImage, DynamicImage)> {
     tracing::info!(
         "Generating stereo pair with max_disparity: {}",
         max_disparity
     );
 
-    let img_rgb = image.to_rgb8();
-    let width = img_rgb.width() as usize;
-    let height = img_rgb.height() as usize;
+    let width = image.width() as usize;
+    let height = image.height() as usize;
 
     // Validate depth map dimensions
     let (depth_height, depth_width) = depth.dim();
@@ -51,50 +189,367 @@ pub fn generate_stereo_pair(
     }
 
     // Create right image via DIBR
-    let mut right_rgb = ImageBuffer::new(width as u32, height as u32);
+    let shift = max_disparity as f32;
+    let right_image = warp_image(image, depth, |_, _| shift, fill, cancel, progress)?;
+
+    let left_image = image.clone();
+
+    tracing::info!("Stereo pair generation complete");
+    Ok((left_image, right_image))
+}
+
+/// Synthesize a view of `image` from a horizontally offset camera
+///
+/// Each pixel moves left by `depth * shift` pixels, so a positive `shift`
+/// gives a view from the right (as in [`generate_stereo_pair`]) and a
+/// negative one a view from the left. Used for multi-view output such as
+/// Looking Glass quilts.
+pub fn synthesize_view(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    shift: f32,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<DynamicImage> {
+    warp_image(
+        image,
+        depth,
+        |_, _| shift,
+        &HoleFill::default(),
+        cancel,
+        None,
+    )
+}
+
+/// Generate a stereo pair from a 180° equirectangular (VR180) image
+///
+/// Parallax on the sphere shrinks towards the sides of the view (where the
+/// eyes' baseline points at the subject) and, in equirectangular pixels,
+/// grows towards the poles where rows are stretched. The per-pixel shift is
+/// scaled by `cos(longitude) / cos(latitude)` to match, so straight lines and
+/// the horizon stay consistent between eyes. The image is assumed to span
+/// 180° horizontally with square pixels in angle.
+pub fn generate_stereo_pair_vr180(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    warp_stereo_pair_vr180(image, depth, max_disparity, fill, cancel, None)
+}
+
+/// [`generate_stereo_pair_vr180`], reporting warped rows to `progress`
+pub(crate) fn warp_stereo_pair_vr180(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+    progress: Option<&ProgressSink>,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    tracing::info!(
+        "Generating VR180 stereo pair with max_disparity: {}",
+        max_disparity
+    );
+
+    let width = image.width() as f32;
+    let height = image.height() as f32;
+    let radians_per_pixel = std::f32::consts::PI / width;
+    let max_disparity = max_disparity as f32;
+
+    let right_image = warp_image(
+        image,
+        depth,
+        |x, y| {
+            let longitude = (x as f32 + 0.5 - width / 2.0) * radians_per_pixel;
+            let latitude = (height / 2.0 - y as f32 - 0.5) * radians_per_pixel;
+            let scale = longitude.cos() / latitude.cos().max(VR180_MIN_LATITUDE_COS);
+            max_disparity * scale
+        },
+        fill,
+        cancel,
+        progress,
+    )?;
+
+    Ok((image.clone(), right_image))
+}
+
+/// Lower bound on `cos(latitude)`, capping the polar shift boost at 4×
+const VR180_MIN_LATITUDE_COS: f32 = 0.25;
+
+/// Generate an omnidirectional stereo pair from a 360° equirectangular image
+///
+/// Each eye looks along every direction from a point on a small circle, so
+/// parallax is the same all around the horizon, and the left edge of the
+/// panorama continues its right edge: pixels warped off one side come back
+/// in on the other instead of leaving holes at the seam. Towards the poles
+/// there is no sideways baseline to speak of, so the shift fades with
+/// `cos(latitude)` and straight up and down are the same in both eyes.
+pub fn generate_stereo_pair_360(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    warp_stereo_pair_360(image, depth, max_disparity, fill, cancel, None)
+}
+
+/// [`generate_stereo_pair_360`], reporting warped rows to `progress`
+pub(crate) fn warp_stereo_pair_360(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    max_disparity: u32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+    progress: Option<&ProgressSink>,
+) -> SpatialResult<(DynamicImage, DynamicImage)> {
+    tracing::info!(
+        "Generating 360° stereo pair with max_disparity: {}",
+        max_disparity
+    );
+
+    // Wrap a strip of the far side onto each edge, warp, and crop it off
+    let (width, height) = (image.width(), image.height());
+    let pad = (max_disparity + 1).min(width);
+    let depth = resize_depth(depth, height as usize, width as usize);
+    let wrapped_depth = ndarray::concatenate(
+        Axis(1),
+        &[
+            depth.slice(s![.., (width - pad) as usize..]),
+            depth.view(),
+            depth.slice(s![.., ..pad as usize]),
+        ],
+    )
+    .expect("slices share the height");
+    let mut wrapped = DynamicImage::new(width + 2 * pad, height, image.color());
+    imageops::replace(
+        &mut wrapped,
+        &image.crop_imm(width - pad, 0, pad, height),
+        0,
+        0,
+    );
+    imageops::replace(&mut wrapped, image, pad as i64, 0);
+    imageops::replace(
+        &mut wrapped,
+        &image.crop_imm(0, 0, pad, height),
+        (width + pad) as i64,
+        0,
+    );
+
+    let radians_per_row = std::f32::consts::PI / height as f32;
+    let max_disparity = max_disparity as f32;
+    let right_image = warp_image(
+        &wrapped,
+        &wrapped_depth,
+        |_, y| {
+            let latitude = (height as f32 / 2.0 - y as f32 - 0.5) * radians_per_row;
+            max_disparity * latitude.cos()
+        },
+        fill,
+        cancel,
+        progress,
+    )?;
+
+    Ok((image.clone(), right_image.crop_imm(pad, 0, width, height)))
+}
+
+/// Pixel types views are warped in
+trait WarpPixel: Pixel {
+    /// Channels as f64 for averaging, with alpha (or 0) last
+    fn to_f64(&self) -> [f64; 4];
+
+    /// Pixel from averaged channels
+    fn from_f64(channels: [f64; 4]) -> Self;
+}
 
-    // Initialize with a background color (dark gray for disocclusions)
-    for pixel in right_rgb.pixels_mut() {
-        *pixel = image::Rgb([64, 64, 64]);
+fn to_u8(c: f64) -> u8 {
+    c.round().clamp(0.0, 255.0) as u8
+}
+
+fn to_u16(c: f64) -> u16 {
+    c.round().clamp(0.0, 65535.0) as u16
+}
+
+impl WarpPixel for Rgb<u8> {
+    fn to_f64(&self) -> [f64; 4] {
+        let [r, g, b] = self.0.map(f64::from);
+        [r, g, b, 0.0]
     }
 
+    fn from_f64([r, g, b, _]: [f64; 4]) -> Self {
+        Rgb([r, g, b].map(to_u8))
+    }
+}
+
+impl WarpPixel for Rgba<u8> {
+    fn to_f64(&self) -> [f64; 4] {
+        self.0.map(f64::from)
+    }
+
+    fn from_f64(channels: [f64; 4]) -> Self {
+        Rgba(channels.map(to_u8))
+    }
+}
+
+impl WarpPixel for Rgb<u16> {
+    fn to_f64(&self) -> [f64; 4] {
+        let [r, g, b] = self.0.map(f64::from);
+        [r, g, b, 0.0]
+    }
+
+    fn from_f64([r, g, b, _]: [f64; 4]) -> Self {
+        Rgb([r, g, b].map(to_u16))
+    }
+}
+
+impl WarpPixel for Rgba<u16> {
+    fn to_f64(&self) -> [f64; 4] {
+        self.0.map(f64::from)
+    }
+
+    fn from_f64(channels: [f64; 4]) -> Self {
+        Rgba(channels.map(to_u16))
+    }
+}
+
+impl WarpPixel for Rgb<f32> {
+    fn to_f64(&self) -> [f64; 4] {
+        let [r, g, b] = self.0.map(f64::from);
+        [r, g, b, 0.0]
+    }
+
+    fn from_f64([r, g, b, _]: [f64; 4]) -> Self {
+        Rgb([r, g, b].map(|c| c as f32))
+    }
+}
+
+impl WarpPixel for Rgba<f32> {
+    fn to_f64(&self) -> [f64; 4] {
+        self.0.map(f64::from)
+    }
+
+    fn from_f64(channels: [f64; 4]) -> Self {
+        Rgba(channels.map(|c| c as f32))
+    }
+}
+
+/// Warp `image` with [`warp_view`] at the bit depth of its samples (float,
+/// 16, or 8 bits), with alpha when it has alpha
+fn warp_image(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    shift: impl Fn(usize, usize) -> f32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+    progress: Option<&ProgressSink>,
+) -> SpatialResult<DynamicImage> {
+    Ok(match image.color() {
+        ColorType::Rgba32F => DynamicImage::ImageRgba32F(warp_view(
+            &image.to_rgba32f(),
+            depth,
+            shift,
+            fill,
+            cancel,
+            progress,
+        )?),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(warp_view(
+            &image.to_rgb32f(),
+            depth,
+            shift,
+            fill,
+            cancel,
+            progress,
+        )?),
+        ColorType::Rgba16 | ColorType::La16 => DynamicImage::ImageRgba16(warp_view(
+            &image.to_rgba16(),
+            depth,
+            shift,
+            fill,
+            cancel,
+            progress,
+        )?),
+        ColorType::Rgb16 | ColorType::L16 => DynamicImage::ImageRgb16(warp_view(
+            &image.to_rgb16(),
+            depth,
+            shift,
+            fill,
+            cancel,
+            progress,
+        )?),
+        color if color.has_alpha() => DynamicImage::ImageRgba8(warp_view(
+            &image.to_rgba8(),
+            depth,
+            shift,
+            fill,
+            cancel,
+            progress,
+        )?),
+        _ => DynamicImage::ImageRgb8(warp_view(
+            &image.to_rgb8(),
+            depth,
+            shift,
+            fill,
+            cancel,
+            progress,
+        )?),
+    })
+}
+
+/// Forward-warp `img_rgb` by `depth * shift(x, y)` pixels and fill disocclusions
+fn warp_view<P: WarpPixel>(
+    img_rgb: &ImageBuffer<P, Vec<P::Subpixel>>,
+    depth: &Array2<f32>,
+    shift: impl Fn(usize, usize) -> f32,
+    fill: &HoleFill,
+    cancel: Option<&CancellationToken>,
+    progress: Option<&ProgressSink>,
+) -> SpatialResult<ImageBuffer<P, Vec<P::Subpixel>>> {
+    let width = img_rgb.width() as usize;
+    let height = img_rgb.height() as usize;
+
+    // Start black, recording the depth of whatever lands on each pixel; the
+    // pixels nothing lands on are holes
+    let mut view_rgb = ImageBuffer::new(width as u32, height as u32);
+    let mut landed = vec![None; width * height];
+    let mut rows = RowProgress::new(progress, height);
+
     // For each pixel in the original image, compute its disparity and shift it
     for y in 0..height {
+        cancel::check(cancel)?;
         for x in 0..width {
             // Get depth at this pixel (with bounds checking and interpolation)
             let depth_val = get_depth_at(depth, x, y, width, height);
 
             // Compute horizontal disparity (shift amount)
             // Higher depth (closer object) → larger shift
-            let disparity = (depth_val * max_disparity as f32).round() as i32;
+            let disparity = (depth_val * shift(x, y)).round() as i32;
 
-            // New position in right image
-            let x_right = x as i32 - disparity;
+            // New position in the synthesized view
+            let x_view = x as i32 - disparity;
 
             // Check bounds
-            if x_right >= 0 && x_right < width as i32 {
-                // Copy pixel from original to right image
+            if x_view >= 0 && x_view < width as i32 {
+                // Copy pixel from original to the view
                 if let Some(pixel) = img_rgb.get_pixel_checked(x as u32, y as u32) {
-                    right_rgb.put_pixel(x_right as u32, y as u32, *pixel);
+                    view_rgb.put_pixel(x_view as u32, y as u32, *pixel);
+                    landed[y * width + x_view as usize] = Some(depth_val);
                 }
             }
         }
+        rows.rows_done(y + 1);
     }
 
-    // Fill holes (disocclusions) with nearest valid pixel
-    fill_disocclusions(&mut right_rgb);
-
-    let left_image = image.clone();
-    let right_image = DynamicImage::ImageRgb8(right_rgb);
+    cancel::check(cancel)?;
+    fill_disocclusions(&mut view_rgb, &landed, fill);
 
-    tracing::info!("Stereo pair generation complete");
-    Ok((left_image, right_image))
+    Ok(view_rgb)
 }
 
 /// Get depth value at a given pixel coordinate with bilinear interpolation
 ///
 /// If the coordinate is out of bounds, returns 0.5 (background/unknown depth)
-fn get_depth_at(
+pub(crate) fn get_depth_at(
     depth: &Array2<f32>,
     x: usize,
     y: usize,
@@ -122,64 +577,78 @@ fn get_depth_at(
     }
 }
 
-/// Fill holes (disocclusions) in the right image with nearest valid neighbor
+/// Fill the pixels nothing `landed` on, as `fill` says
 ///
-/// This is a simple approach: for each invalid pixel, find the nearest valid pixel
-/// and copy its value. More sophisticated approaches could use median filtering
-/// or edge-aware inpainting.
-fn fill_disocclusions(image: &mut ImageBuffer<image::Rgb<u8>, Vec<u8>>) {
+/// `landed` holds the depth of the source pixel warped to each view pixel.
+/// Every hole is filled from the warped pixels only, never from another
+/// filled hole, so the result does not depend on scan order.
+fn fill_disocclusions<P: WarpPixel>(
+    image: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    landed: &[Option<f32>],
+    fill: &HoleFill,
+) {
+    if landed.iter().all(Option::is_some) {
+        return;
+    }
     let width = image.width() as usize;
     let height = image.height() as usize;
-    let bg_color = image::Rgb([64u8, 64u8, 64u8]);
+    let radius = fill.search_radius as usize;
 
-    // Create a copy to check validity
+    // Sources are read from a copy, so filled holes are never sources
     let original = image.clone();
+    let means =
+        (fill.strategy == HoleFillStrategy::Blur).then(|| WindowMeans::new(&original, landed));
 
-    // Simple filling: for each background pixel, find nearest non-background pixel
     for y in 0..height {
         for x in 0..width {
-            let pixel = original.get_pixel(x as u32, y as u32);
-
-            // Check if this is a "hole" (background color)
-            if pixel[0] == 64 && pixel[1] == 64 && pixel[2] == 64 {
-                // Find nearest valid pixel
-                if let Some(nearest) = find_nearest_valid_pixel(&original, x, y, bg_color) {
-                    image.put_pixel(x as u32, y as u32, nearest);
+            if landed[y * width + x].is_some() {
+                continue;
+            }
+            let filled = match fill.strategy {
+                HoleFillStrategy::BackgroundStretch => {
+                    find_background_in_row(&original, landed, x, y, radius)
+                }
+                HoleFillStrategy::NearestValid => {
+                    find_nearest_valid_pixel(&original, landed, x, y, radius)
                 }
+                HoleFillStrategy::HorizontalStretch => {
+                    find_nearest_in_row(&original, landed, x, y, radius)
+                }
+                HoleFillStrategy::Blur => means.as_ref().and_then(|m| m.mean(x, y, radius)),
+                HoleFillStrategy::None => None,
+            };
+            if let Some(pixel) = filled {
+                image.put_pixel(x as u32, y as u32, pixel);
             }
         }
     }
 }
 
-/// Find the nearest non-background pixel to the given coordinate
-fn find_nearest_valid_pixel(
-    image: &ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+/// Find the nearest warped pixel to the given coordinate, within `max_radius`
+fn find_nearest_valid_pixel<P: WarpPixel>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    landed: &[Option<f32>],
     cx: usize,
     cy: usize,
-    bg_color: image::Rgb<u8>,
-) -> Option<image::Rgb<u8>> {
+    max_radius: usize,
+) -> Option<P> {
     let width = image.width() as usize;
     let height = image.height() as usize;
 
     // Search in expanding rings around the point
-    for radius in 1..=20 {
-        for dy in -(radius as i32)..=(radius as i32) {
-            for dx in -(radius as i32)..=(radius as i32) {
+    for radius in 1..=max_radius as i32 {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
                 // Only check pixels at this radius (not interior)
-                if dx.abs() != radius as i32 && dy.abs() != radius as i32 {
+                if dx.abs() != radius && dy.abs() != radius {
                     continue;
                 }
 
                 let nx = (cx as i32 + dx) as usize;
                 let ny = (cy as i32 + dy) as usize;
 
-                if nx < width && ny < height {
-                    let pixel = image.get_pixel(nx as u32, ny as u32);
-                    // Check if this is not a background/hole pixel
-                    if pixel[0] != bg_color[0] || pixel[1] != bg_color[1] || pixel[2] != bg_color[2]
-                    {
-                        return Some(*pixel);
-                    }
+                if nx < width && ny < height && landed[ny * width + nx].is_some() {
+                    return Some(*image.get_pixel(nx as u32, ny as u32));
                 }
             }
         }
@@ -188,6 +657,126 @@ fn find_nearest_valid_pixel(
     None
 }
 
+/// Find the nearest warped pixel on the same row, preferring the left one
+/// when both sides are equally near
+fn find_nearest_in_row<P: WarpPixel>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    landed: &[Option<f32>],
+    cx: usize,
+    cy: usize,
+    max_radius: usize,
+) -> Option<P> {
+    let width = image.width() as usize;
+    let row = &landed[cy * width..(cy + 1) * width];
+    (1..=max_radius)
+        .flat_map(|d| [cx.checked_sub(d), Some(cx + d)])
+        .flatten()
+        .find(|&x| x < width && row[x].is_some())
+        .map(|x| *image.get_pixel(x as u32, cy as u32))
+}
+
+/// Find the warped pixels bordering the gap on each side of the row and
+/// take the farther one (lower depth), falling back to whichever side exists
+///
+/// A disocclusion is background the foreground moved off of, so its true
+/// color continues from the background edge of the gap.
+fn find_background_in_row<P: WarpPixel>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    landed: &[Option<f32>],
+    cx: usize,
+    cy: usize,
+    max_radius: usize,
+) -> Option<P> {
+    let width = image.width() as usize;
+    let row = &landed[cy * width..(cy + 1) * width];
+    let edge = |xs: &mut dyn Iterator<Item = usize>| {
+        xs.take(max_radius)
+            .find_map(|x| row[x].map(|depth| (x, depth)))
+    };
+    let left = edge(&mut (0..cx).rev());
+    let right = edge(&mut (cx + 1..width));
+
+    let x = match (left, right) {
+        (Some((left_x, left_depth)), Some((right_x, right_depth))) => {
+            if right_depth < left_depth {
+                right_x
+            } else {
+                left_x
+            }
+        }
+        (Some((x, _)), None) | (None, Some((x, _))) => x,
+        (None, None) => return None,
+    };
+    Some(*image.get_pixel(x as u32, cy as u32))
+}
+
+/// Summed-area tables of the warped pixels, for box means in constant time
+struct WindowMeans {
+    width: usize,
+    height: usize,
+    /// Per-channel sums, `(width + 1) * (height + 1)` entries
+    sums: Vec<[f64; 4]>,
+    /// Warped pixel counts, same layout
+    counts: Vec<u32>,
+}
+
+impl WindowMeans {
+    fn new<P: WarpPixel>(image: &ImageBuffer<P, Vec<P::Subpixel>>, landed: &[Option<f32>]) -> Self {
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        let stride = width + 1;
+        let mut sums = vec![[0.0; 4]; stride * (height + 1)];
+        let mut counts = vec![0u32; stride * (height + 1)];
+        for y in 0..height {
+            for x in 0..width {
+                let (channels, count) = if landed[y * width + x].is_some() {
+                    (image.get_pixel(x as u32, y as u32).to_f64(), 1)
+                } else {
+                    ([0.0; 4], 0)
+                };
+                let at = (y + 1) * stride + x + 1;
+                let (up, left, diag) = (at - stride, at - 1, at - stride - 1);
+                for c in 0..4 {
+                    sums[at][c] = channels[c] + sums[up][c] + sums[left][c] - sums[diag][c];
+                }
+                counts[at] = count + counts[up] + counts[left] - counts[diag];
+            }
+        }
+        Self {
+            width,
+            height,
+            sums,
+            counts,
+        }
+    }
+
+    /// Mean of the warped pixels within `radius` of (x, y), if any
+    fn mean<P: WarpPixel>(&self, x: usize, y: usize, radius: usize) -> Option<P> {
+        let stride = self.width + 1;
+        let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(self.width));
+        let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(self.height));
+        let (a, b, c, d) = (
+            y0 * stride + x0,
+            y0 * stride + x1,
+            y1 * stride + x0,
+            y1 * stride + x1,
+        );
+
+        let count = self.counts[d] + self.counts[a] - self.counts[b] - self.counts[c];
+        if count == 0 {
+            return None;
+        }
+        let mut channels = [0.0; 4];
+        for (channel, value) in channels.iter_mut().enumerate() {
+            let sum = self.sums[d][channel] + self.sums[a][channel]
+                - self.sums[b][channel]
+                - self.sums[c][channel];
+            *value = sum / count as f64;
+        }
+        Some(P::from_f64(channels))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -254,4 +843,248 @@ mod tests {
         assert_eq!(right.width(), 200);
         assert_eq!(right.height(), 150);
     }
+
+    #[test]
+    fn test_stereo_pair_cancelled() {
+        let dyn_img = DynamicImage::new_rgb8(50, 50);
+        let depth = Array2::from_elem((50, 50), 0.5);
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let result = generate_stereo_pair_cancellable(&dyn_img, &depth, 10, &token);
+        assert!(matches!(result, Err(crate::SpatialError::Cancelled)));
+    }
+
+    #[test]
+    fn test_float_image_stays_float() {
+        // An HDR highlight, brighter than 8-bit can hold
+        let test_img = image::Rgb32FImage::from_fn(40, 20, |x, _| {
+            if (20..25).contains(&x) {
+                image::Rgb([4.0, 2.0, 1.0])
+            } else {
+                image::Rgb([0.25, 0.25, 0.25])
+            }
+        });
+        let dyn_img = DynamicImage::ImageRgb32F(test_img);
+        let depth = Array2::from_elem((20, 40), 1.0);
+
+        let (_, right) = generate_stereo_pair(&dyn_img, &depth, 10).unwrap();
+        let DynamicImage::ImageRgb32F(right) = right else {
+            panic!("expected a float view, got {:?}", right.color());
+        };
+        assert_eq!(right.get_pixel(12, 10)[0], 4.0);
+        // The uncovered right edge is filled from its neighbours, not left a hole
+        assert_eq!(right.get_pixel(35, 10)[0], 0.25);
+        assert!(right.pixels().all(|p| p[0] > 0.0));
+    }
+
+    #[test]
+    fn test_sixteen_bit_stays_sixteen_bit() {
+        let test_img = image::ImageBuffer::<Rgb<u16>, _>::from_pixel(20, 10, Rgb([1, 300, 65535]));
+        let depth = Array2::from_elem((10, 20), 0.5);
+        let (_, right) =
+            generate_stereo_pair(&DynamicImage::ImageRgb16(test_img), &depth, 4).unwrap();
+        let DynamicImage::ImageRgb16(right) = right else {
+            panic!("expected a 16-bit view, got {:?}", right.color());
+        };
+        assert!(right.pixels().all(|p| p.0 == [1, 300, 65535]));
+    }
+
+    #[test]
+    fn test_alpha_is_warped() {
+        // An opaque near square on a transparent far background
+        let test_img = image::RgbaImage::from_fn(40, 20, |x, _| {
+            if (20..30).contains(&x) {
+                image::Rgba([0, 255, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        });
+        let depth = Array2::from_shape_fn(
+            (20, 40),
+            |(_, x)| if (20..30).contains(&x) { 1.0 } else { 0.0 },
+        );
+        let dyn_img = DynamicImage::ImageRgba8(test_img);
+
+        let (left, right) = generate_stereo_pair(&dyn_img, &depth, 5).unwrap();
+        assert_eq!(left, dyn_img);
+        let DynamicImage::ImageRgba8(right) = right else {
+            panic!("expected an RGBA view, got {:?}", right.color());
+        };
+        let opaque: Vec<u32> = (0..40)
+            .filter(|&x| right.get_pixel(x, 10)[3] == 255)
+            .collect();
+        assert_eq!(opaque, (15..25).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_vr180_shift_follows_longitude() {
+        // White columns at the centre and near the right edge of a 180° view
+        let test_img = image::ImageBuffer::from_fn(180, 90, |x, _| {
+            if x == 90 || x == 170 {
+                image::Rgb([255u8, 255, 255])
+            } else {
+                image::Rgb([0u8, 0, 0])
+            }
+        });
+        let dyn_img = DynamicImage::ImageRgb8(test_img);
+        let depth = Array2::from_elem((90, 180), 1.0);
+
+        let (left, right) =
+            generate_stereo_pair_vr180(&dyn_img, &depth, 10, &HoleFill::default(), None).unwrap();
+        assert_eq!(left.to_rgb8(), dyn_img.to_rgb8());
+
+        // On the horizon the centre moves by the full disparity, the edge far less
+        let right = right.to_rgb8();
+        let row: Vec<u32> = (0..180)
+            .filter(|&x| right.get_pixel(x, 45)[0] > 128)
+            .collect();
+        assert!(row.iter().any(|&x| (79..=81).contains(&x)), "{:?}", row);
+        assert!(row.iter().any(|&x| (167..=169).contains(&x)), "{:?}", row);
+    }
+
+    #[test]
+    fn test_360_wraps_around_the_seam() {
+        let test_img = image::RgbImage::from_fn(64, 32, |x, y| Rgb([x as u8 * 4, y as u8, 0]));
+        let dyn_img = DynamicImage::ImageRgb8(test_img.clone());
+        let depth = Array2::from_elem((32, 64), 1.0);
+        let fill = HoleFill {
+            strategy: HoleFillStrategy::None,
+            ..Default::default()
+        };
+
+        let (left, right) = generate_stereo_pair_360(&dyn_img, &depth, 4, &fill, None).unwrap();
+        assert_eq!(left.to_rgb8(), test_img);
+
+        // On the horizon every column, the last ones included, comes from
+        // four to its right, wrapping past the edge
+        let right = right.to_rgb8();
+        assert_eq!(right.dimensions(), (64, 32));
+        for x in 0..64 {
+            assert_eq!(right.get_pixel(x, 16), test_img.get_pixel((x + 4) % 64, 16));
+        }
+        // The top row hardly moves
+        assert_eq!(right.get_pixel(10, 0), test_img.get_pixel(10, 0));
+    }
+
+    /// A 3-pixel hole at x = 4..7 in every row of a 10x3 view, between
+    /// near content on the left and far content on the right
+    fn holed_view() -> (image::RgbImage, Vec<Option<f32>>) {
+        let view = image::RgbImage::from_fn(10, 3, |x, y| match x {
+            0..=3 => Rgb([100, 0, y as u8]),
+            4..=6 => Rgb([0, 0, 0]),
+            _ => Rgb([200, 0, y as u8]),
+        });
+        let landed = (0..30)
+            .map(|i| match i % 10 {
+                0..=3 => Some(0.9),
+                4..=6 => None,
+                _ => Some(0.1),
+            })
+            .collect();
+        (view, landed)
+    }
+
+    fn filled(strategy: HoleFillStrategy, search_radius: u32) -> image::RgbImage {
+        let (mut view, landed) = holed_view();
+        let fill = HoleFill {
+            strategy,
+            search_radius,
+        };
+        fill_disocclusions(&mut view, &landed, &fill);
+        view
+    }
+
+    #[test]
+    fn test_hole_fill_strategies() {
+        let stretched = filled(HoleFillStrategy::HorizontalStretch, 20);
+        let row: Vec<u8> = (4..7).map(|x| stretched.get_pixel(x, 1)[0]).collect();
+        assert_eq!(row, [100, 100, 200]);
+        // Rows fill from their own row only
+        assert_eq!(stretched.get_pixel(4, 2)[2], 2);
+
+        let blurred = filled(HoleFillStrategy::Blur, 1);
+        assert_eq!(blurred.get_pixel(4, 1)[0], 100);
+        assert_eq!(blurred.get_pixel(5, 1)[0], 0);
+        let blurred = filled(HoleFillStrategy::Blur, 2);
+        assert_eq!(blurred.get_pixel(5, 1)[0], 150);
+
+        let nearest = filled(HoleFillStrategy::NearestValid, 20);
+        assert_eq!(nearest.get_pixel(4, 0)[0], 100);
+        assert_eq!(nearest.get_pixel(6, 0)[0], 200);
+
+        let untouched = filled(HoleFillStrategy::None, 20);
+        assert_eq!(untouched, holed_view().0);
+    }
+
+    #[test]
+    fn test_background_stretch_fills_from_far_side() {
+        let view = filled(HoleFillStrategy::BackgroundStretch, 20);
+        let row: Vec<u8> = (4..7).map(|x| view.get_pixel(x, 1)[0]).collect();
+        assert_eq!(row, [200, 200, 200]);
+
+        // Beyond the radius on the far side, the near side is all there is
+        let view = filled(HoleFillStrategy::BackgroundStretch, 1);
+        assert_eq!(view.get_pixel(4, 1)[0], 100);
+        assert_eq!(view.get_pixel(5, 1)[0], 0);
+        assert_eq!(view.get_pixel(6, 1)[0], 200);
+    }
+
+    #[test]
+    fn test_no_foreground_bleed_behind_moved_object() {
+        // A near red bar on a far gray-blue gradient: the strip it uncovers
+        // must be filled with background, not red
+        let test_img = image::RgbImage::from_fn(60, 4, |x, _| {
+            if (20..30).contains(&x) {
+                Rgb([255, 0, 0])
+            } else {
+                Rgb([0, 0, 100 + x as u8])
+            }
+        });
+        let depth = Array2::from_shape_fn(
+            (4, 60),
+            |(_, x)| {
+                if (20..30).contains(&x) {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+        );
+        let (_, right) =
+            generate_stereo_pair(&DynamicImage::ImageRgb8(test_img), &depth, 8).unwrap();
+        let right = right.to_rgb8();
+        let red: Vec<u32> = (0..60).filter(|&x| right.get_pixel(x, 2)[0] > 0).collect();
+        assert_eq!(red, (12..22).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_search_radius_limits_fill() {
+        let view = filled(HoleFillStrategy::NearestValid, 1);
+        assert_eq!(view.get_pixel(5, 1)[0], 0);
+        assert_eq!(view.get_pixel(4, 1)[0], 100);
+    }
+
+    #[test]
+    fn test_dark_gray_pixels_are_not_holes() {
+        // Real pixels of the old placeholder color must survive warping
+        let dyn_img = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(8, 4, Rgb([64, 64, 64])));
+        let depth = Array2::from_elem((4, 8), 0.0);
+        let (_, right) = generate_stereo_pair(&dyn_img, &depth, 10).unwrap();
+        assert_eq!(right, dyn_img);
+    }
+
+    #[test]
+    fn test_hole_fill_strategy_from_str() {
+        for strategy in [
+            HoleFillStrategy::BackgroundStretch,
+            HoleFillStrategy::NearestValid,
+            HoleFillStrategy::HorizontalStretch,
+            HoleFillStrategy::Blur,
+            HoleFillStrategy::None,
+        ] {
+            assert_eq!(strategy.name().parse::<HoleFillStrategy>(), Ok(strategy));
+        }
+        assert!("smear".parse::<HoleFillStrategy>().is_err());
+    }
 }
@@ -0,0 +1,346 @@
+//! Object-storage input and output (requires the `remote-io` feature)
+//!
+//! This module provides:
+//! - A [`Storage`] trait with whole-object and streaming file get/put
+//! - [`RemoteStorage`], an S3/HTTP implementation built on `object_store`
+//! - [`process_photo_remote`], which runs the photo pipeline against storage keys
+//!
+//! Downloads and uploads stream through local files in fixed-size chunks, so
+//! large inputs and outputs are never held in memory in full.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use spatial_maker::storage::{process_photo_remote, RemoteStorage};
+//! use spatial_maker::{OutputOptions, SpatialConfig};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! // Credentials and region come from the standard AWS_* environment variables
+//! let storage = RemoteStorage::from_url("s3://my-bucket")?;
+//! process_photo_remote(
+//!     &storage,
+//!     "uploads/photo.jpg",
+//!     "spatial/photo_sbs.jpg",
+//!     SpatialConfig::default(),
+//!     OutputOptions::default(),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use crate::output::OutputOptions;
+use crate::{process_photo, SpatialConfig};
+use futures_util::StreamExt;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, WriteMultipart};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Chunk size for streaming uploads
+const UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Maximum number of in-flight upload parts
+const MAX_CONCURRENT_PARTS: usize = 4;
+
+/// Minimal get/put interface over an object store
+pub trait Storage: Send + Sync {
+    /// Fetch a whole object into memory
+    fn get(&self, key: &str) -> impl Future<Output = SpatialResult<Vec<u8>>> + Send;
+
+    /// Store a whole object from memory
+    fn put(&self, key: &str, data: Vec<u8>) -> impl Future<Output = SpatialResult<()>> + Send;
+
+    /// Stream an object to a local file
+    fn get_to_file(
+        &self,
+        key: &str,
+        destination: &Path,
+    ) -> impl Future<Output = SpatialResult<()>> + Send;
+
+    /// Stream a local file into an object
+    fn put_from_file(
+        &self,
+        key: &str,
+        source: &Path,
+    ) -> impl Future<Output = SpatialResult<()>> + Send;
+}
+
+/// S3 or HTTP(S) storage backed by `object_store`
+#[derive(Clone, Debug)]
+pub struct RemoteStorage {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl RemoteStorage {
+    /// Connect to an S3 bucket, reading credentials/region from `AWS_*` env vars
+    pub fn s3(bucket: &str) -> SpatialResult<Self> {
+        let store = object_store::aws::AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()
+            .map_err(storage_error)?;
+        Ok(Self {
+            store: Arc::new(store),
+        })
+    }
+
+    /// Connect to an HTTP(S) server supporting GET and PUT (e.g. WebDAV)
+    pub fn http(base_url: &str) -> SpatialResult<Self> {
+        let store = object_store::http::HttpBuilder::new()
+            .with_url(base_url)
+            .build()
+            .map_err(storage_error)?;
+        Ok(Self {
+            store: Arc::new(store),
+        })
+    }
+
+    /// Build a storage from a URL: `s3://bucket` or `http(s)://host/base`
+    pub fn from_url(url: &str) -> SpatialResult<Self> {
+        let parsed = url::Url::parse(url).map_err(|e| {
+            SpatialError::ConfigError(format!("Invalid storage URL {}: {}", url, e))
+        })?;
+
+        match parsed.scheme() {
+            "s3" => {
+                let bucket = parsed.host_str().ok_or_else(|| {
+                    SpatialError::ConfigError(format!("Missing bucket in storage URL: {}", url))
+                })?;
+                Self::s3(bucket)
+            }
+            "http" | "https" => Self::http(url),
+            other => Err(SpatialError::ConfigError(format!(
+                "Unsupported storage scheme '{}'. Use s3://, http://, or https://",
+                other
+            ))),
+        }
+    }
+
+    /// Wrap an existing `object_store` implementation (e.g. for GCS or Azure)
+    pub fn from_store(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl Storage for RemoteStorage {
+    async fn get(&self, key: &str) -> SpatialResult<Vec<u8>> {
+        let result = self
+            .store
+            .get(&object_path(key)?)
+            .await
+            .map_err(storage_error)?;
+        let bytes = result.bytes().await.map_err(storage_error)?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn put(&self, key: &str, data: Vec<u8>) -> SpatialResult<()> {
+        self.store
+            .put(&object_path(key)?, data.into())
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn get_to_file(&self, key: &str, destination: &Path) -> SpatialResult<()> {
+        let result = self
+            .store
+            .get(&object_path(key)?)
+            .await
+            .map_err(storage_error)?;
+
+        let mut file = tokio::fs::File::create(destination)
+            .await
+            .map_err(|e| SpatialError::io("Failed to create file", e))?;
+
+        let mut stream = result.into_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(storage_error)?;
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| SpatialError::io("Failed to write to file", e))?;
+        }
+        file.flush()
+            .await
+            .map_err(|e| SpatialError::io("Failed to flush file", e))?;
+
+        tracing::debug!("Downloaded {} to {:?}", key, destination);
+        Ok(())
+    }
+
+    async fn put_from_file(&self, key: &str, source: &Path) -> SpatialResult<()> {
+        let upload = self
+            .store
+            .put_multipart(&object_path(key)?)
+            .await
+            .map_err(storage_error)?;
+        let mut writer = WriteMultipart::new_with_chunk_size(upload, UPLOAD_CHUNK_SIZE);
+
+        let mut file = tokio::fs::File::open(source)
+            .await
+            .map_err(|e| SpatialError::io(format!("Failed to open {:?}", source), e))?;
+        let mut buf = vec![0u8; UPLOAD_CHUNK_SIZE];
+
+        loop {
+            let n = file
+                .read(&mut buf)
+                .await
+                .map_err(|e| SpatialError::io(format!("Failed to read {:?}", source), e))?;
+            if n == 0 {
+                break;
+            }
+            writer
+                .wait_for_capacity(MAX_CONCURRENT_PARTS)
+                .await
+                .map_err(storage_error)?;
+            writer.write(&buf[..n]);
+        }
+
+        writer.finish().await.map_err(storage_error)?;
+        tracing::debug!("Uploaded {:?} to {}", source, key);
+        Ok(())
+    }
+}
+
+/// Process a photo stored at `input_key`, writing results under `output_key`
+///
+/// The input is streamed to a local scratch directory, processed with
+/// [`process_photo`], and every produced file (including `_L`/`_R` files for
+/// separate layouts and `.heic` MV-HEVC output) is uploaded next to
+/// `output_key`. Returns the keys that were written.
+pub async fn process_photo_remote<S: Storage>(
+    storage: &S,
+    input_key: &str,
+    output_key: &str,
+    config: SpatialConfig,
+    output_options: OutputOptions,
+) -> SpatialResult<Vec<String>> {
+    let scratch = std::env::temp_dir().join(format!(
+        "spatial_maker_remote_{}_{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    let input_dir = scratch.join("input");
+    let output_dir = scratch.join("output");
+    std::fs::create_dir_all(&input_dir)?;
+    std::fs::create_dir_all(&output_dir)?;
+
+    let result = async {
+        // Keep the file names so format detection by extension still works
+        let input_path = input_dir.join(key_file_name(input_key)?);
+        let output_path = output_dir.join(key_file_name(output_key)?);
+
+        storage.get_to_file(input_key, &input_path).await?;
+        process_photo(&input_path, &output_path, config, output_options).await?;
+
+        let prefix = key_parent(output_key);
+        let mut written = Vec::new();
+        for produced in list_files(&output_dir)? {
+            let file_name = produced
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| {
+                    SpatialError::ConfigError(format!("Invalid output file name: {:?}", produced))
+                })?;
+            let key = format!("{}{}", prefix, file_name);
+            storage.put_from_file(&key, &produced).await?;
+            written.push(key);
+        }
+        Ok(written)
+    }
+    .await;
+
+    let _ = std::fs::remove_dir_all(&scratch);
+    result
+}
+
+fn object_path(key: &str) -> SpatialResult<ObjectPath> {
+    ObjectPath::parse(key)
+        .map_err(|e| SpatialError::ConfigError(format!("Invalid object key '{}': {}", key, e)))
+}
+
+/// Final path segment of an object key
+fn key_file_name(key: &str) -> SpatialResult<&str> {
+    key.rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| SpatialError::ConfigError(format!("Object key has no file name: {}", key)))
+}
+
+/// Everything up to and including the last '/' of an object key
+fn key_parent(key: &str) -> &str {
+    key.rfind('/').map_or("", |i| &key[..=i])
+}
+
+fn list_files(dir: &Path) -> SpatialResult<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn storage_error(e: object_store::Error) -> SpatialError {
+    match e {
+        object_store::Error::NotFound { path, source } => SpatialError::io(
+            format!("Object not found: {}", path),
+            std::io::Error::new(std::io::ErrorKind::NotFound, source),
+        ),
+        other => SpatialError::io("Object storage error", std::io::Error::other(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::local::LocalFileSystem;
+
+    #[test]
+    fn test_key_helpers() {
+        assert_eq!(key_file_name("a/b/photo.jpg").unwrap(), "photo.jpg");
+        assert_eq!(key_file_name("photo.jpg").unwrap(), "photo.jpg");
+        assert!(key_file_name("a/b/").is_err());
+        assert_eq!(key_parent("a/b/photo.jpg"), "a/b/");
+        assert_eq!(key_parent("photo.jpg"), "");
+    }
+
+    #[test]
+    fn test_from_url_rejects_unknown_scheme() {
+        assert!(RemoteStorage::from_url("ftp://example.com").is_err());
+        assert!(RemoteStorage::from_url("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_roundtrip_through_store() {
+        let store_dir = tempfile::tempdir().unwrap();
+        let work_dir = tempfile::tempdir().unwrap();
+        let storage = RemoteStorage::from_store(Arc::new(
+            LocalFileSystem::new_with_prefix(store_dir.path()).unwrap(),
+        ));
+
+        storage
+            .put("inputs/data.bin", b"hello".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(storage.get("inputs/data.bin").await.unwrap(), b"hello");
+
+        let local = work_dir.path().join("data.bin");
+        storage
+            .get_to_file("inputs/data.bin", &local)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&local).unwrap(), b"hello");
+
+        storage
+            .put_from_file("outputs/copy.bin", &local)
+            .await
+            .unwrap();
+        assert_eq!(storage.get("outputs/copy.bin").await.unwrap(), b"hello");
+    }
+}
@@ -0,0 +1,203 @@
+//! Subject-aware convergence
+//!
+//! Plain DIBR keeps the farthest depth at the screen and pushes everything
+//! nearer out toward the viewer, which puts a portrait's face uncomfortably
+//! in front of the frame. [`SubjectFocus`] finds the main subject with a
+//! simple center-weighted saliency on the depth map (near and central pixels
+//! score highest), then remaps depth to signed disparity weights so the
+//! subject sits at the screen plane and the background recedes behind it.
+//! The remapped range is rescaled so the largest shift is still
+//! `max_disparity`, which boosts the separation between subject and
+//! background.
+//!
+//! Videos detect the subject on every frame, so convergence follows it as
+//! it moves.
+//!
+//! ```
+//! use ndarray::Array2;
+//! use spatial_maker::SubjectFocus;
+//!
+//! // A near subject in the middle of a far background
+//! let mut depth = Array2::from_elem((64, 64), 0.1);
+//! depth.slice_mut(ndarray::s![16..48, 16..48]).fill(0.8);
+//!
+//! let weights = SubjectFocus::default().apply(&depth);
+//! assert!(weights[[32, 32]].abs() < 0.05);
+//! assert!(weights[[0, 0]] < -0.9);
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Longest side of the grid saliency is sampled on
+const SALIENCY_GRID: usize = 256;
+
+/// Put the detected subject at the screen plane
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubjectFocus {
+    /// How far the subject moves toward the screen plane: 0 keeps the
+    /// farthest depth at the screen, 1 puts the subject exactly at it
+    pub strength: f32,
+
+    /// Width of the center weighting, as a fraction of the distance from
+    /// the center to a corner; smaller values favor subjects near the middle
+    pub center_sigma: f32,
+}
+
+impl Default for SubjectFocus {
+    fn default() -> Self {
+        Self {
+            strength: 1.0,
+            center_sigma: 0.35,
+        }
+    }
+}
+
+impl SubjectFocus {
+    /// Check the settings are in range
+    pub fn validate(&self) -> SpatialResult<()> {
+        if !(0.0..=1.0).contains(&self.strength) {
+            return Err(SpatialError::ConfigError(format!(
+                "Subject focus strength must be between 0 and 1, got {}",
+                self.strength
+            )));
+        }
+        if !(self.center_sigma.is_finite() && self.center_sigma > 0.0) {
+            return Err(SpatialError::ConfigError(format!(
+                "Subject focus center_sigma must be positive, got {}",
+                self.center_sigma
+            )));
+        }
+        Ok(())
+    }
+
+    /// Remap normalized depth to signed disparity weights (-1 to 1)
+    ///
+    /// At full strength the subject maps to 0 (the screen plane), nearer
+    /// content stays positive (in front of the screen), and farther content
+    /// goes negative (behind it). The result is meant for the stereo
+    /// warp only; it is not a depth map.
+    pub fn apply(&self, depth: &Array2<f32>) -> Array2<f32> {
+        let Some(subject) = detect_subject_depth(depth, self.center_sigma) else {
+            return depth.clone();
+        };
+        let convergence = subject * self.strength;
+
+        let (low, high) = depth
+            .iter()
+            .filter(|v| v.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+                (lo.min(v), hi.max(v))
+            });
+        let reach = (high - convergence).max(convergence - low);
+        if reach <= 1e-6 {
+            return depth.clone();
+        }
+        tracing::debug!(
+            "Subject depth {:.3}, converging at {:.3} (gain {:.2})",
+            subject,
+            convergence,
+            1.0 / reach
+        );
+        depth.mapv(|v| ((v - convergence) / reach).clamp(-1.0, 1.0))
+    }
+}
+
+/// Depth of the most salient region, or `None` for an empty map
+///
+/// Each pixel is weighted by its nearness and by a Gaussian falloff from the
+/// image center (`center_sigma` as a fraction of the half-diagonal); the
+/// subject depth is the weighted median. Large maps are subsampled.
+pub fn detect_subject_depth(depth: &Array2<f32>, center_sigma: f32) -> Option<f32> {
+    let (height, width) = depth.dim();
+    let step = (height.max(width) / SALIENCY_GRID).max(1);
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let radius = (cx * cx + cy * cy).sqrt().max(1.0);
+    let spread = 2.0 * (center_sigma * radius).powi(2);
+
+    let mut samples = Vec::new();
+    for y in (0..height).step_by(step) {
+        for x in (0..width).step_by(step) {
+            let d = depth[[y, x]];
+            if !d.is_finite() {
+                continue;
+            }
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            let center = (-(dx * dx + dy * dy) / spread).exp();
+            let weight = center * d.clamp(0.0, 1.0).powi(2);
+            if weight > 0.0 {
+                samples.push((d, weight));
+            }
+        }
+    }
+    if samples.is_empty() {
+        return None;
+    }
+
+    samples.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    let half = samples.iter().map(|&(_, w)| w).sum::<f32>() / 2.0;
+    let mut cumulative = 0.0;
+    for &(d, w) in &samples {
+        cumulative += w;
+        if cumulative >= half {
+            return Some(d);
+        }
+    }
+    samples.last().map(|&(d, _)| d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    fn portrait() -> Array2<f32> {
+        let mut depth = Array2::from_elem((100, 80), 0.2);
+        depth.slice_mut(s![30..80, 25..55]).fill(0.7);
+        depth
+    }
+
+    #[test]
+    fn test_detects_central_subject() {
+        let subject = detect_subject_depth(&portrait(), 0.35).unwrap();
+        assert!((subject - 0.7).abs() < 1e-6, "{}", subject);
+        assert_eq!(detect_subject_depth(&Array2::zeros((0, 0)), 0.35), None);
+    }
+
+    #[test]
+    fn test_subject_at_screen_plane() {
+        let weights = SubjectFocus::default().apply(&portrait());
+        assert!(weights[[50, 40]].abs() < 1e-6);
+        // Background recedes by the full budget
+        assert!((weights[[0, 0]] + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_zero_strength_only_rescales() {
+        let focus = SubjectFocus {
+            strength: 0.0,
+            ..Default::default()
+        };
+        let weights = focus.apply(&portrait());
+        assert!((weights[[50, 40]] - 1.0).abs() < 1e-6);
+        assert!((weights[[0, 0]] - 0.2 / 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_flat_depth_unchanged() {
+        let depth = Array2::from_elem((10, 10), 0.0);
+        assert_eq!(SubjectFocus::default().apply(&depth), depth);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SubjectFocus::default().validate().is_ok());
+        let focus = SubjectFocus {
+            strength: 1.5,
+            ..Default::default()
+        };
+        assert!(focus.validate().is_err());
+    }
+}
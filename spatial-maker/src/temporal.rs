@@ -0,0 +1,394 @@
+//! Depth keyframing for video
+//!
+//! Running the depth model on every frame dominates video processing time.
+//! [`DepthKeyframer`] runs it only on every Nth frame (a keyframe) and fills
+//! in the frames between two keyframes by interpolating their depth maps:
+//! - [`DepthInterpolation::Hold`] reuses the previous keyframe's depth
+//! - [`DepthInterpolation::Linear`] blends the two keyframes by frame position
+//! - [`DepthInterpolation::Flow`] first warps each keyframe's depth along a
+//!   block-matching optical flow estimate, then blends
+//!
+//! Frames between keyframes are buffered until the next keyframe's depth is
+//! known, so at most `interval` frames are held in memory.
+//!
+//! ```no_run
+//! use spatial_maker::temporal::{DepthInterpolation, DepthKeyframer};
+//! use spatial_maker::{DepthConfig, DepthEstimator};
+//!
+//! # async fn example(frames: Vec<image::DynamicImage>) -> anyhow::Result<()> {
+//! // Load the model once, not per keyframe
+//! let mut estimator = DepthEstimator::load(DepthConfig::default()).await?;
+//! let mut keyframer = DepthKeyframer::new(3, DepthInterpolation::Flow);
+//! for frame in frames {
+//!     if let Some(keyframe) = keyframer.push(frame)? {
+//!         let depth = estimator.estimate(keyframe)?;
+//!         for (frame, depth) in keyframer.submit_depth(depth)? {
+//!             // generate and write the stereo pair for `frame`
+//!         }
+//!     }
+//! }
+//! for (frame, depth) in keyframer.finish()? {
+//!     // trailing frames after the last keyframe
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SpatialError, SpatialResult};
+use image::DynamicImage;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Block size (in depth-map pixels) for flow estimation
+const FLOW_BLOCK: usize = 8;
+
+/// Maximum block displacement searched (in depth-map pixels)
+const FLOW_RADIUS: i32 = 4;
+
+/// How depth is filled in for frames between keyframes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepthInterpolation {
+    /// Reuse the previous keyframe's depth map
+    Hold,
+    /// Blend the surrounding keyframes' depth maps by frame position
+    #[default]
+    Linear,
+    /// Warp each keyframe's depth along estimated optical flow, then blend
+    Flow,
+}
+
+impl DepthInterpolation {
+    /// Get the canonical method name
+    pub fn name(&self) -> &'static str {
+        match self {
+            DepthInterpolation::Hold => "hold",
+            DepthInterpolation::Linear => "linear",
+            DepthInterpolation::Flow => "flow",
+        }
+    }
+}
+
+/// A keyframe whose depth is known
+struct Keyframe {
+    /// Grayscale frame at depth-map resolution, for flow estimation
+    luma: Array2<f32>,
+    depth: Array2<f32>,
+}
+
+/// Schedules depth estimation on keyframes and interpolates the rest
+pub struct DepthKeyframer {
+    interval: u64,
+    method: DepthInterpolation,
+    frames_seen: u64,
+    previous: Option<Keyframe>,
+    /// Frames after `previous`, waiting for the next keyframe
+    pending: Vec<DynamicImage>,
+    /// Keyframe handed out by `push`, waiting for `submit_depth`
+    awaiting: Option<DynamicImage>,
+}
+
+impl DepthKeyframer {
+    /// Run depth on every `interval`th frame (1 = every frame)
+    pub fn new(interval: u32, method: DepthInterpolation) -> Self {
+        Self {
+            interval: interval.max(1) as u64,
+            method,
+            frames_seen: 0,
+            previous: None,
+            pending: Vec::new(),
+            awaiting: None,
+        }
+    }
+
+    /// Add the next frame
+    ///
+    /// Returns the frame when it is a keyframe; estimate its depth and pass
+    /// it to [`submit_depth`](Self::submit_depth) before pushing more frames.
+    pub fn push(&mut self, frame: DynamicImage) -> SpatialResult<Option<&DynamicImage>> {
+        if self.awaiting.is_some() {
+            return Err(SpatialError::ConfigError(
+                "Depth for the previous keyframe has not been submitted".to_string(),
+            ));
+        }
+
+        let is_keyframe = self.frames_seen.is_multiple_of(self.interval);
+        self.frames_seen += 1;
+        if is_keyframe {
+            Ok(Some(self.awaiting.insert(frame)))
+        } else {
+            self.pending.push(frame);
+            Ok(None)
+        }
+    }
+
+    /// Provide the depth map of the keyframe returned by [`push`](Self::push)
+    ///
+    /// Returns the buffered in-between frames and the keyframe itself, in
+    /// order, each with its depth map.
+    pub fn submit_depth(
+        &mut self,
+        depth: impl Into<Array2<f32>>,
+    ) -> SpatialResult<Vec<(DynamicImage, Array2<f32>)>> {
+        let depth = depth.into();
+        let keyframe = self.awaiting.take().ok_or_else(|| {
+            SpatialError::ConfigError("No keyframe is waiting for depth".to_string())
+        })?;
+        let (height, width) = depth.dim();
+        let next = Keyframe {
+            luma: luma_thumbnail(&keyframe, width, height),
+            depth,
+        };
+
+        let mut ready = Vec::with_capacity(self.pending.len() + 1);
+        if let Some(previous) = &self.previous {
+            if previous.depth.dim() != next.depth.dim() {
+                return Err(SpatialError::TensorError(format!(
+                    "Keyframe depth maps differ in size: {:?} != {:?}",
+                    previous.depth.dim(),
+                    next.depth.dim()
+                )));
+            }
+
+            let count = self.pending.len();
+            for (i, frame) in self.pending.drain(..).enumerate() {
+                let t = (i + 1) as f32 / (count + 1) as f32;
+                let depth = interpolate(&frame, previous, Some(&next), t, self.method);
+                ready.push((frame, depth));
+            }
+        }
+
+        ready.push((keyframe, next.depth.clone()));
+        self.previous = Some(next);
+        Ok(ready)
+    }
+
+    /// Flush frames after the last keyframe
+    ///
+    /// With no later keyframe to blend towards, these use the previous
+    /// keyframe's depth (flow-warped for [`DepthInterpolation::Flow`]).
+    pub fn finish(&mut self) -> SpatialResult<Vec<(DynamicImage, Array2<f32>)>> {
+        if self.awaiting.is_some() {
+            return Err(SpatialError::ConfigError(
+                "Depth for the last keyframe has not been submitted".to_string(),
+            ));
+        }
+        let Some(previous) = &self.previous else {
+            return Ok(Vec::new());
+        };
+
+        Ok(self
+            .pending
+            .drain(..)
+            .map(|frame| {
+                let depth = interpolate(&frame, previous, None, 0.0, self.method);
+                (frame, depth)
+            })
+            .collect())
+    }
+
+    /// Number of frames pushed so far
+    pub fn frames_seen(&self) -> u64 {
+        self.frames_seen
+    }
+}
+
+/// Depth for `frame`, positioned `t` (0-1) of the way from `previous` to `next`
+fn interpolate(
+    frame: &DynamicImage,
+    previous: &Keyframe,
+    next: Option<&Keyframe>,
+    t: f32,
+    method: DepthInterpolation,
+) -> Array2<f32> {
+    match (method, next) {
+        (DepthInterpolation::Hold, _) => previous.depth.clone(),
+        (DepthInterpolation::Linear, None) => previous.depth.clone(),
+        (DepthInterpolation::Linear, Some(next)) => blend(&previous.depth, &next.depth, t),
+        (DepthInterpolation::Flow, next) => {
+            let (height, width) = previous.depth.dim();
+            let luma = luma_thumbnail(frame, width, height);
+            let from_previous = warp_depth(&previous.depth, &block_flow(&luma, &previous.luma));
+            match next {
+                Some(next) => {
+                    let from_next = warp_depth(&next.depth, &block_flow(&luma, &next.luma));
+                    blend(&from_previous, &from_next, t)
+                }
+                None => from_previous,
+            }
+        }
+    }
+}
+
+fn blend(a: &Array2<f32>, b: &Array2<f32>, t: f32) -> Array2<f32> {
+    a * (1.0 - t) + b * t
+}
+
+/// Grayscale copy of `frame` resized to the depth-map resolution
+fn luma_thumbnail(frame: &DynamicImage, width: usize, height: usize) -> Array2<f32> {
+    let luma = frame
+        .resize_exact(
+            width as u32,
+            height as u32,
+            image::imageops::FilterType::Triangle,
+        )
+        .to_luma8();
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        luma.get_pixel(x as u32, y as u32).0[0] as f32 / 255.0
+    })
+}
+
+/// Per-block displacement from `from` to the best-matching area of `to`
+///
+/// Exhaustive search within [`FLOW_RADIUS`] minimising the sum of absolute
+/// differences. Returns (dy, dx) per block, in block-grid order.
+fn block_flow(from: &Array2<f32>, to: &Array2<f32>) -> Array2<(i32, i32)> {
+    let (height, width) = from.dim();
+    let rows = height.div_ceil(FLOW_BLOCK);
+    let cols = width.div_ceil(FLOW_BLOCK);
+
+    Array2::from_shape_fn((rows, cols), |(by, bx)| {
+        let y0 = by * FLOW_BLOCK;
+        let x0 = bx * FLOW_BLOCK;
+        let y1 = (y0 + FLOW_BLOCK).min(height);
+        let x1 = (x0 + FLOW_BLOCK).min(width);
+
+        let cost = |dy: i32, dx: i32| -> Option<f32> {
+            let ty0 = y0 as i32 + dy;
+            let tx0 = x0 as i32 + dx;
+            let ty1 = y1 as i32 + dy;
+            let tx1 = x1 as i32 + dx;
+            if ty0 < 0 || tx0 < 0 || ty1 > height as i32 || tx1 > width as i32 {
+                return None;
+            }
+            let mut sad = 0.0;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let ty = (y as i32 + dy) as usize;
+                    let tx = (x as i32 + dx) as usize;
+                    sad += (from[[y, x]] - to[[ty, tx]]).abs();
+                }
+            }
+            Some(sad)
+        };
+
+        // Start from zero motion so flat areas stay put
+        let mut best = (0, 0);
+        let mut best_cost = cost(0, 0).unwrap_or(f32::MAX);
+        for dy in -FLOW_RADIUS..=FLOW_RADIUS {
+            for dx in -FLOW_RADIUS..=FLOW_RADIUS {
+                if let Some(c) = cost(dy, dx) {
+                    if c < best_cost {
+                        best_cost = c;
+                        best = (dy, dx);
+                    }
+                }
+            }
+        }
+        best
+    })
+}
+
+/// Resample `depth` so each block is taken from where its flow points
+fn warp_depth(depth: &Array2<f32>, flow: &Array2<(i32, i32)>) -> Array2<f32> {
+    let (height, width) = depth.dim();
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        let (dy, dx) = flow[[y / FLOW_BLOCK, x / FLOW_BLOCK]];
+        let sy = (y as i32 + dy).clamp(0, height as i32 - 1) as usize;
+        let sx = (x as i32 + dx).clamp(0, width as i32 - 1) as usize;
+        depth[[sy, sx]]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(value: u8) -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            32,
+            16,
+            image::Rgb([value, value, value]),
+        ))
+    }
+
+    fn depth(value: f32) -> Array2<f32> {
+        Array2::from_elem((16, 32), value)
+    }
+
+    #[test]
+    fn test_keyframe_schedule() {
+        let mut keyframer = DepthKeyframer::new(3, DepthInterpolation::Linear);
+        let mut keyframes = Vec::new();
+        for i in 0..7 {
+            if keyframer.push(frame(0)).unwrap().is_some() {
+                keyframes.push(i);
+                keyframer.submit_depth(depth(0.0)).unwrap();
+            }
+        }
+        assert_eq!(keyframes, vec![0, 3, 6]);
+        assert_eq!(keyframer.frames_seen(), 7);
+    }
+
+    #[test]
+    fn test_linear_interpolation_between_keyframes() {
+        let mut keyframer = DepthKeyframer::new(4, DepthInterpolation::Linear);
+        assert!(keyframer.push(frame(0)).unwrap().is_some());
+        assert_eq!(keyframer.submit_depth(depth(0.0)).unwrap().len(), 1);
+
+        for _ in 0..3 {
+            assert!(keyframer.push(frame(0)).unwrap().is_none());
+        }
+        assert!(keyframer.push(frame(0)).unwrap().is_some());
+        let ready = keyframer.submit_depth(depth(1.0)).unwrap();
+
+        let values: Vec<f32> = ready.iter().map(|(_, d)| d[[0, 0]]).collect();
+        assert_eq!(values, vec![0.25, 0.5, 0.75, 1.0]);
+    }
+
+    #[test]
+    fn test_hold_and_finish() {
+        let mut keyframer = DepthKeyframer::new(2, DepthInterpolation::Hold);
+        keyframer.push(frame(0)).unwrap();
+        keyframer.submit_depth(depth(0.3)).unwrap();
+        keyframer.push(frame(0)).unwrap();
+
+        let trailing = keyframer.finish().unwrap();
+        assert_eq!(trailing.len(), 1);
+        assert_eq!(trailing[0].1[[5, 5]], 0.3);
+    }
+
+    #[test]
+    fn test_push_requires_submitted_depth() {
+        let mut keyframer = DepthKeyframer::new(2, DepthInterpolation::Linear);
+        keyframer.push(frame(0)).unwrap();
+        assert!(keyframer.push(frame(0)).is_err());
+        assert!(keyframer.finish().is_err());
+    }
+
+    #[test]
+    fn test_block_flow_tracks_shift() {
+        // A bright square moved 3 pixels right between frames
+        let mut a = Array2::<f32>::zeros((32, 32));
+        let mut b = Array2::<f32>::zeros((32, 32));
+        for y in 8..16 {
+            for x in 8..16 {
+                a[[y, x]] = 1.0;
+                b[[y, x + 3]] = 1.0;
+            }
+        }
+        let flow = block_flow(&b, &a);
+        assert_eq!(flow[[1, 1]], (0, -3));
+
+        // Warping depth keyed to `a` moves the square into `b`'s position
+        let warped = warp_depth(&a, &flow);
+        assert_eq!(warped[[10, 12]], 1.0);
+    }
+
+    #[test]
+    fn test_interpolation_names() {
+        assert_eq!(DepthInterpolation::Hold.name(), "hold");
+        assert_eq!(DepthInterpolation::default().name(), "linear");
+        assert_eq!(DepthInterpolation::Flow.name(), "flow");
+    }
+}
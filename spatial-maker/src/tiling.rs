@@ -0,0 +1,330 @@
+//! Tiled depth inference for very large images
+//!
+//! The depth model sees a fixed number of pixels (the short side is resized
+//! to `target_size`), so a 100 MP panorama loses all local detail in a single
+//! pass, and running the model at full resolution exhausts memory. Tiling
+//! instead:
+//! 1. Runs one global pass over the whole image for the overall depth layout
+//! 2. Runs the model on overlapping crops of `tile_size` source pixels
+//! 3. Aligns each tile's relative depth to the global pass with a least-squares
+//!    scale and shift (the model's depth is only defined up to those)
+//! 4. Feathers tiles together across the overlap so seams do not show
+//!
+//! The merged depth map is `target_size / tile_size` times the source
+//! resolution, so smaller tiles give finer detail at the cost of more passes.
+
+#[cfg(not(target_arch = "wasm32"))]
+use ndarray::s;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// Settings for tiled depth inference
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TilingConfig {
+    /// Side of each square tile, in source pixels; images whose shorter side
+    /// fits in one tile are processed in a single pass
+    pub tile_size: u32,
+
+    /// Source pixels shared by neighbouring tiles and blended across
+    pub overlap: u32,
+}
+
+impl Default for TilingConfig {
+    fn default() -> Self {
+        Self {
+            tile_size: 1024,
+            overlap: 128,
+        }
+    }
+}
+
+impl TilingConfig {
+    /// Whether an image of this size benefits from tiling
+    pub fn applies_to(&self, width: u32, height: u32) -> bool {
+        width.min(height) > self.tile_size
+    }
+}
+
+/// A tile in source-image pixel coordinates
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Split a `width` x `height` image into overlapping tiles covering it
+///
+/// The last tile in each row and column is aligned to the image edge, so
+/// every tile has the full size when the image is at least one tile wide.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn plan_tiles(width: u32, height: u32, config: &TilingConfig) -> Vec<Tile> {
+    let tile = config.tile_size.max(1);
+    let overlap = config.overlap.min(tile / 2);
+    let xs = tile_starts(width, tile, overlap);
+    let ys = tile_starts(height, tile, overlap);
+
+    ys.iter()
+        .flat_map(|&y| {
+            xs.iter().map(move |&x| Tile {
+                x,
+                y,
+                width: tile.min(width - x),
+                height: tile.min(height - y),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn tile_starts(length: u32, tile: u32, overlap: u32) -> Vec<u32> {
+    if length <= tile {
+        return vec![0];
+    }
+    let stride = tile - overlap;
+    let mut starts: Vec<u32> = (0..length - tile).step_by(stride as usize).collect();
+    starts.push(length - tile);
+    starts
+}
+
+/// Resize a depth map with bilinear interpolation
+pub(crate) fn resize_depth(depth: &Array2<f32>, height: usize, width: usize) -> Array2<f32> {
+    let (src_h, src_w) = depth.dim();
+    if (src_h, src_w) == (height, width) {
+        return depth.clone();
+    }
+    let scale_y = src_h as f32 / height as f32;
+    let scale_x = src_w as f32 / width as f32;
+
+    Array2::from_shape_fn((height, width), |(y, x)| {
+        // Sample at pixel centres
+        let sy = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (src_h - 1) as f32);
+        let sx = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (src_w - 1) as f32);
+        let (y0, x0) = (sy as usize, sx as usize);
+        let (y1, x1) = ((y0 + 1).min(src_h - 1), (x0 + 1).min(src_w - 1));
+        let (ty, tx) = (sy - y0 as f32, sx - x0 as f32);
+
+        let top = depth[[y0, x0]] * (1.0 - tx) + depth[[y0, x1]] * tx;
+        let bottom = depth[[y1, x0]] * (1.0 - tx) + depth[[y1, x1]] * tx;
+        top * (1.0 - ty) + bottom * ty
+    })
+}
+
+/// Least-squares `scale` and `shift` so that `tile * scale + shift`
+/// matches `reference`
+///
+/// The scale is kept non-negative so a featureless tile cannot flip its
+/// depth ordering.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn fit_scale_shift(
+    tile: ndarray::ArrayView2<f32>,
+    reference: ndarray::ArrayView2<f32>,
+) -> (f32, f32) {
+    let n = tile.len().max(1) as f64;
+    let mean_t = tile.iter().map(|&v| v as f64).sum::<f64>() / n;
+    let mean_r = reference.iter().map(|&v| v as f64).sum::<f64>() / n;
+
+    let (mut cov, mut var) = (0.0f64, 0.0f64);
+    for (&t, &r) in tile.iter().zip(reference.iter()) {
+        let dt = t as f64 - mean_t;
+        cov += dt * (r as f64 - mean_r);
+        var += dt * dt;
+    }
+
+    let scale = if var > 1e-12 {
+        (cov / var).max(0.0)
+    } else {
+        0.0
+    };
+    (scale as f32, (mean_r - scale * mean_t) as f32)
+}
+
+/// Blending weight at (`y`, `x`) inside a tile of `height` x `width`
+///
+/// Weights ramp up linearly over `ramp` pixels from each edge that borders
+/// another tile; edges on the image border keep full weight.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn feather_weight(
+    y: usize,
+    x: usize,
+    height: usize,
+    width: usize,
+    ramp: f32,
+    open_edges: [bool; 4],
+) -> f32 {
+    let [top, bottom, left, right] = open_edges;
+    let mut weight = 1.0f32;
+    if ramp > 0.0 {
+        let edges = [
+            (top, y as f32 + 0.5),
+            (bottom, height as f32 - y as f32 - 0.5),
+            (left, x as f32 + 0.5),
+            (right, width as f32 - x as f32 - 0.5),
+        ];
+        for (open, distance) in edges {
+            if open {
+                weight = weight.min(distance / ramp);
+            }
+        }
+    }
+    weight.clamp(1e-3, 1.0)
+}
+
+/// Accumulates aligned tiles into one depth map
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct TileBlender {
+    reference: Array2<f32>,
+    sum: Array2<f32>,
+    weights: Array2<f32>,
+    /// Depth-map pixels per source pixel
+    scale: f32,
+    ramp: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TileBlender {
+    /// Start from the global pass, resized to `scale` times the source size
+    pub fn new(global: &Array2<f32>, width: u32, height: u32, scale: f32, overlap: u32) -> Self {
+        let out_h = ((height as f32 * scale).round() as usize).max(1);
+        let out_w = ((width as f32 * scale).round() as usize).max(1);
+        Self {
+            reference: resize_depth(global, out_h, out_w),
+            sum: Array2::zeros((out_h, out_w)),
+            weights: Array2::zeros((out_h, out_w)),
+            scale,
+            ramp: overlap as f32 * scale,
+        }
+    }
+
+    /// Output region of a source-pixel tile: (y0, y1, x0, x1)
+    fn region(&self, tile: &Tile) -> (usize, usize, usize, usize) {
+        let (out_h, out_w) = self.sum.dim();
+        let map = |v: u32, max: usize| ((v as f32 * self.scale).round() as usize).min(max);
+        let y0 = map(tile.y, out_h - 1);
+        let x0 = map(tile.x, out_w - 1);
+        let y1 = map(tile.y + tile.height, out_h).max(y0 + 1);
+        let x1 = map(tile.x + tile.width, out_w).max(x0 + 1);
+        (y0, y1, x0, x1)
+    }
+
+    /// Align a tile's depth to the global pass and blend it in
+    pub fn add(&mut self, tile: &Tile, depth: &Array2<f32>) {
+        let (out_h, out_w) = self.sum.dim();
+        let (y0, y1, x0, x1) = self.region(tile);
+        let (h, w) = (y1 - y0, x1 - x0);
+
+        let resized = resize_depth(depth, h, w);
+        let (scale, shift) =
+            fit_scale_shift(resized.view(), self.reference.slice(s![y0..y1, x0..x1]));
+        let open_edges = [y0 > 0, y1 < out_h, x0 > 0, x1 < out_w];
+
+        for ((y, x), &value) in resized.indexed_iter() {
+            let weight = feather_weight(y, x, h, w, self.ramp, open_edges);
+            self.sum[[y0 + y, x0 + x]] += weight * (value * scale + shift);
+            self.weights[[y0 + y, x0 + x]] += weight;
+        }
+    }
+
+    /// The blended map; pixels no tile covered fall back to the global pass
+    pub fn finish(self) -> Array2<f32> {
+        let mut merged = self.reference;
+        ndarray::Zip::from(&mut merged)
+            .and(&self.sum)
+            .and(&self.weights)
+            .for_each(|m, &sum, &weight| {
+                if weight > 0.0 {
+                    *m = sum / weight;
+                }
+            });
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_tiles_covers_image() {
+        let config = TilingConfig {
+            tile_size: 100,
+            overlap: 20,
+        };
+        let tiles = plan_tiles(250, 100, &config);
+        let xs: Vec<u32> = tiles.iter().map(|t| t.x).collect();
+        assert_eq!(xs, vec![0, 80, 150]);
+        assert!(tiles.iter().all(|t| t.width == 100 && t.height == 100));
+
+        assert_eq!(plan_tiles(60, 40, &config).len(), 1);
+        assert!(!config.applies_to(4000, 100));
+        assert!(config.applies_to(4000, 101));
+    }
+
+    #[test]
+    fn test_fit_scale_shift() {
+        let tile = Array2::from_shape_fn((4, 4), |(y, x)| (y * 4 + x) as f32 / 15.0);
+        let reference = tile.mapv(|v| v * 0.5 + 0.2);
+        let (scale, shift) = fit_scale_shift(tile.view(), reference.view());
+        assert!((scale - 0.5).abs() < 1e-5);
+        assert!((shift - 0.2).abs() < 1e-5);
+
+        let flat = Array2::from_elem((4, 4), 0.3);
+        let (scale, shift) = fit_scale_shift(flat.view(), reference.view());
+        assert_eq!(scale, 0.0);
+        assert!((shift - 0.45).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_feather_weight() {
+        let open = [false, false, true, true];
+        assert_eq!(feather_weight(0, 5, 10, 10, 4.0, open), 1.0);
+        assert!(feather_weight(5, 0, 10, 10, 4.0, open) < 0.2);
+        assert_eq!(feather_weight(5, 0, 10, 10, 4.0, [false; 4]), 1.0);
+    }
+
+    #[test]
+    fn test_resize_depth() {
+        let depth = Array2::from_shape_fn((2, 2), |(_, x)| x as f32);
+        let resized = resize_depth(&depth, 4, 4);
+        assert_eq!(resized.dim(), (4, 4));
+        assert_eq!(resized[[0, 0]], 0.0);
+        assert_eq!(resized[[0, 3]], 1.0);
+        assert!((resized[[0, 1]] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_blender_keeps_consistent_tiles_seamless() {
+        // A horizontal gradient, as the global pass and each tile would see it
+        // up to an arbitrary scale and shift
+        let (width, height) = (300u32, 120u32);
+        let truth = |x: f32| x / width as f32;
+        let global = Array2::from_shape_fn((30, 75), |(_, x)| truth((x as f32 + 0.5) * 4.0));
+
+        let config = TilingConfig {
+            tile_size: 120,
+            overlap: 40,
+        };
+        let mut blender = TileBlender::new(&global, width, height, 0.5, config.overlap);
+        for tile in plan_tiles(width, height, &config) {
+            let depth = Array2::from_shape_fn((60, 60), |(_, x)| {
+                let source_x = tile.x as f32 + (x as f32 + 0.5) * 2.0;
+                truth(source_x) * 3.0 - 1.0
+            });
+            blender.add(&tile, &depth);
+        }
+
+        let merged = blender.finish();
+        assert_eq!(merged.dim(), (60, 150));
+        for x in 1..150 {
+            assert!(
+                merged[[30, x]] >= merged[[30, x - 1]] - 1e-3,
+                "seam at {}",
+                x
+            );
+        }
+        assert!((merged[[30, 75]] - 0.5).abs() < 0.02);
+    }
+}
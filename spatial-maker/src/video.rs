@@ -0,0 +1,1837 @@
+//! Streaming video pipeline
+//!
+//! Frames travel between ffmpeg and the pipeline as raw RGB over stdio, so
+//! converting a long video never writes an image sequence to disk:
+//! 1. ffprobe reads the frame size, frame rate, and frame count
+//! 2. [`FrameReader`] decodes frames from one ffmpeg process's stdout
+//! 3. Depth runs on keyframes ([`DepthKeyframer`]), stereo on every frame,
+//!    spread over [`SpatialConfig::parallel`] workers (see [`pool`])
+//! 4. [`FrameWriter`] pipes the stereo frames into an encoding ffmpeg
+//!
+//! Output is encoded in segments tracked by a [`VideoCheckpoint`], so an
+//! interrupted job resumes after the last finished segment. At the end the
+//! segments are joined and muxed with the source audio (see [`audio`]) in a
+//! single stream-copy pass. With [`SpatialConfig::depth_video`] set, the
+//! depth maps are segmented and joined the same way. With
+//! [`VideoConfig::cuts`] set, only the listed spans are converted (see
+//! [`cutlist`]) and their audio is trimmed to match.
+//!
+//! [`process_video_dir`] converts a whole directory one video at a time.
+//! It rewrites a [`RunManifest`] after every video, so a batch interrupted
+//! hours in skips the videos that finished, picks up the one in progress
+//! from its checkpoint, and retries the ones that failed.
+//!
+//! ```no_run
+//! use spatial_maker::video::{process_video, VideoConfig};
+//! use spatial_maker::{OutputOptions, SpatialConfig};
+//! use std::path::Path;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let config = SpatialConfig::builder().depth_interval(3).build()?;
+//! let report = process_video(
+//!     Path::new("input.mp4"),
+//!     Path::new("output_sbs.mp4"),
+//!     config,
+//!     OutputOptions::default(),
+//!     &VideoConfig::default(),
+//!     Some(|done: u64, total: Option<u64>| println!("{} / {:?} frames", done, total)),
+//! )
+//! .await?;
+//! println!("{} frames, {} depth passes", report.frames, report.depth_keyframes);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`audio`]: crate::audio
+//! [`cutlist`]: crate::cutlist
+//! [`pool`]: crate::pool
+
+use crate::audio::{self, AudioConfig};
+use crate::cancel;
+use crate::checkpoint::{settings_fingerprint, VideoCheckpoint};
+use crate::cpu;
+use crate::cutlist::CutList;
+use crate::depth::{DepthConfig, DepthEstimator};
+use crate::depth_video::{depth_video_path, DepthVideoEncoding, DepthVideoWriter};
+use crate::error::{SpatialError, SpatialResult};
+use crate::manifest::{self, RunManifest};
+use crate::naming;
+use crate::output::{self, OutputFormat, OutputOptions, Projection};
+use crate::pool;
+use crate::progress::{self, ProgressEvent};
+use crate::resample::upsample_depth;
+use crate::stereo::{generate_stereo_pair_vr180, generate_stereo_pair_with_fill};
+use crate::temporal::DepthKeyframer;
+use crate::SpatialConfig;
+use image::{imageops, DynamicImage, RgbImage};
+use ndarray::Array2;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::Instant;
+
+/// File extensions treated as videos when scanning a directory
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "mkv", "webm", "avi", "mts", "m2ts"];
+
+/// Whether `path` has a video file extension (see [`VIDEO_EXTENSIONS`])
+pub fn is_video_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Encoding and tool settings for video output
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VideoConfig {
+    /// Path to ffmpeg (defaults to "ffmpeg" in PATH)
+    pub ffmpeg_path: Option<PathBuf>,
+
+    /// Path to ffprobe (defaults to "ffprobe" in PATH)
+    pub ffprobe_path: Option<PathBuf>,
+
+    /// ffmpeg encoder for the stereo video
+    pub codec: String,
+
+    /// Constant rate factor passed to the encoder (lower = better)
+    pub crf: u32,
+
+    /// Encoder preset
+    pub preset: String,
+
+    /// How the source audio is carried over
+    pub audio: AudioConfig,
+
+    /// Frames per checkpointed segment; 0 writes a single segment
+    pub segment_frames: u64,
+
+    /// Spans of the input to convert, joined in list order; `None` converts
+    /// the whole video
+    pub cuts: Option<CutList>,
+}
+
+impl Default for VideoConfig {
+    fn default() -> Self {
+        Self {
+            ffmpeg_path: None,
+            ffprobe_path: None,
+            codec: "libx264".to_string(),
+            crf: 18,
+            preset: "medium".to_string(),
+            audio: AudioConfig::default(),
+            segment_frames: 900,
+            cuts: None,
+        }
+    }
+}
+
+impl VideoConfig {
+    fn ffmpeg(&self) -> &Path {
+        self.ffmpeg_path
+            .as_deref()
+            .unwrap_or_else(|| Path::new("ffmpeg"))
+    }
+
+    fn ffprobe(&self) -> &Path {
+        self.ffprobe_path
+            .as_deref()
+            .unwrap_or_else(|| Path::new("ffprobe"))
+    }
+
+    fn is_hevc(&self) -> bool {
+        self.codec.contains("265") || self.codec.contains("hevc")
+    }
+}
+
+/// Frame size, rate, and length of a video's first video stream
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoInfo {
+    /// Display width, after the stream's rotation is applied
+    pub width: u32,
+    /// Display height, after the stream's rotation is applied
+    pub height: u32,
+    /// Frames per second
+    pub fps: f64,
+    /// Frame count from the container, when it records one
+    pub frame_count: Option<u64>,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+}
+
+impl VideoInfo {
+    /// Frame count, or an estimate from the duration when the container has none
+    pub fn estimated_frames(&self) -> Option<u64> {
+        self.frame_count.or_else(|| {
+            self.duration
+                .map(|seconds| (seconds * self.fps).round() as u64)
+        })
+    }
+}
+
+/// Read the first video stream of `input` with ffprobe
+pub fn probe_video(ffprobe: &Path, input: &Path) -> SpatialResult<VideoInfo> {
+    let output = Command::new(ffprobe)
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=width,height,avg_frame_rate,r_frame_rate,nb_frames,duration:\
+             stream_tags=rotate:stream_side_data=rotation:format=duration",
+            "-of",
+            "json",
+        ])
+        .arg(input)
+        .output()
+        .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffprobe), e))?;
+
+    if !output.status.success() {
+        return Err(SpatialError::io(
+            format!("ffprobe failed on {:?}", input),
+            std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        ));
+    }
+
+    parse_ffprobe_video(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `ffprobe -select_streams v:0 -of json` output
+pub fn parse_ffprobe_video(json: &str) -> SpatialResult<VideoInfo> {
+    #[derive(Deserialize)]
+    struct Probe {
+        #[serde(default)]
+        streams: Vec<ProbeStream>,
+        #[serde(default)]
+        format: Option<ProbeFormat>,
+    }
+
+    #[derive(Deserialize)]
+    struct ProbeStream {
+        #[serde(default)]
+        width: u32,
+        #[serde(default)]
+        height: u32,
+        #[serde(default)]
+        avg_frame_rate: Option<String>,
+        #[serde(default)]
+        r_frame_rate: Option<String>,
+        #[serde(default)]
+        nb_frames: Option<String>,
+        #[serde(default)]
+        duration: Option<String>,
+        #[serde(default)]
+        tags: Option<ProbeTags>,
+        #[serde(default)]
+        side_data_list: Vec<ProbeSideData>,
+    }
+
+    #[derive(Deserialize)]
+    struct ProbeTags {
+        #[serde(default)]
+        rotate: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct ProbeSideData {
+        #[serde(default)]
+        rotation: Option<f64>,
+    }
+
+    #[derive(Deserialize)]
+    struct ProbeFormat {
+        #[serde(default)]
+        duration: Option<String>,
+    }
+
+    let probe: Probe = serde_json::from_str(json)
+        .map_err(|e| SpatialError::io("Invalid ffprobe output", e.into()))?;
+    let stream = probe.streams.into_iter().next().ok_or_else(|| {
+        SpatialError::io("Input has no video stream", ErrorKind::InvalidData.into())
+    })?;
+    if stream.width == 0 || stream.height == 0 {
+        return Err(SpatialError::io(
+            "Video stream has no frame size",
+            ErrorKind::InvalidData.into(),
+        ));
+    }
+
+    let fps = [&stream.avg_frame_rate, &stream.r_frame_rate]
+        .into_iter()
+        .find_map(|rate| rate.as_deref().and_then(parse_rate))
+        .ok_or_else(|| {
+            SpatialError::io(
+                "Video stream has no frame rate",
+                ErrorKind::InvalidData.into(),
+            )
+        })?;
+
+    // ffmpeg applies the rotation when decoding, so quarter turns swap the
+    // size of the frames we receive
+    let rotation = stream
+        .side_data_list
+        .iter()
+        .find_map(|side_data| side_data.rotation)
+        .or_else(|| {
+            stream
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.rotate.as_deref())
+                .and_then(|rotate| rotate.trim().parse().ok())
+        })
+        .unwrap_or(0.0);
+    let quarter_turn = (rotation.round() as i64).rem_euclid(180) == 90;
+    let (width, height) = if quarter_turn {
+        (stream.height, stream.width)
+    } else {
+        (stream.width, stream.height)
+    };
+
+    let parse_seconds = |value: Option<&str>| {
+        value
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite() && *v > 0.0)
+    };
+    Ok(VideoInfo {
+        width,
+        height,
+        fps,
+        frame_count: stream
+            .nb_frames
+            .as_deref()
+            .and_then(|n| n.trim().parse().ok())
+            .filter(|&n: &u64| n > 0),
+        duration: parse_seconds(stream.duration.as_deref())
+            .or_else(|| parse_seconds(probe.format.and_then(|f| f.duration).as_deref())),
+    })
+}
+
+/// Parse an ffprobe rate such as "30000/1001"; "0/0" and the like are `None`
+fn parse_rate(rate: &str) -> Option<f64> {
+    let value = match rate.split_once('/') {
+        Some((num, den)) => num.trim().parse::<f64>().ok()? / den.trim().parse::<f64>().ok()?,
+        None => rate.trim().parse().ok()?,
+    };
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+/// Decodes a video into RGB frames through an ffmpeg pipe
+pub struct FrameReader {
+    child: Option<Child>,
+    stdout: Option<ChildStdout>,
+    width: u32,
+    height: u32,
+    frames_read: u64,
+}
+
+impl FrameReader {
+    /// Start decoding `input` at `start_frame`
+    ///
+    /// Frames come out at `info.fps` (variable frame rate sources are
+    /// resampled) and at `info.width` x `info.height`.
+    pub fn open(
+        ffmpeg: &Path,
+        input: &Path,
+        info: &VideoInfo,
+        start_frame: u64,
+    ) -> SpatialResult<Self> {
+        let mut cmd = Command::new(ffmpeg);
+        cmd.args(["-hide_banner", "-loglevel", "error", "-nostdin"]);
+        if start_frame > 0 {
+            cmd.arg("-ss")
+                .arg(format!("{:.6}", start_frame as f64 / info.fps));
+        }
+        cmd.arg("-i")
+            .arg(input)
+            .args(["-map", "0:v:0", "-an", "-sn"])
+            .arg("-r")
+            .arg(format!("{:.6}", info.fps))
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        tracing::debug!("Running: {:?}", cmd);
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffmpeg), e))?;
+        let stdout = child.stdout.take();
+
+        Ok(Self {
+            child: Some(child),
+            stdout,
+            width: info.width,
+            height: info.height,
+            frames_read: 0,
+        })
+    }
+
+    /// The next frame, or `None` at the end of the video
+    pub fn read_frame(&mut self) -> SpatialResult<Option<RgbImage>> {
+        let Some(stdout) = self.stdout.as_mut() else {
+            return Ok(None);
+        };
+
+        let frame_bytes = self.width as usize * self.height as usize * 3;
+        let mut buffer = vec![0u8; frame_bytes];
+        let mut filled = 0;
+        while filled < frame_bytes {
+            match stdout.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(SpatialError::io("Failed to read decoded frame", e)),
+            }
+        }
+
+        if filled < frame_bytes {
+            if filled > 0 {
+                tracing::warn!(
+                    "Dropping truncated frame {} ({} of {} bytes)",
+                    self.frames_read,
+                    filled,
+                    frame_bytes
+                );
+            }
+            self.finish()?;
+            return Ok(None);
+        }
+
+        self.frames_read += 1;
+        RgbImage::from_raw(self.width, self.height, buffer)
+            .map(Some)
+            .ok_or_else(|| {
+                SpatialError::io(
+                    "Decoded frame has the wrong size",
+                    ErrorKind::InvalidData.into(),
+                )
+            })
+    }
+
+    /// Frames decoded so far
+    pub fn frames_read(&self) -> u64 {
+        self.frames_read
+    }
+
+    /// Wait for ffmpeg after the last frame, surfacing decode errors
+    fn finish(&mut self) -> SpatialResult<()> {
+        drop(self.stdout.take());
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+        let status = child
+            .wait()
+            .map_err(|e| SpatialError::io("Failed to wait for ffmpeg", e))?;
+        if !status.success() {
+            return Err(ffmpeg_error(&mut child, "Video decoding"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FrameReader {
+    fn drop(&mut self) {
+        drop(self.stdout.take());
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Encodes RGB frames into a video through an ffmpeg pipe
+pub struct FrameWriter {
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    frames_written: u64,
+}
+
+impl FrameWriter {
+    /// Start ffmpeg writing a `width` x `height` video at `fps`
+    pub fn create(
+        ffmpeg: &Path,
+        path: &Path,
+        width: u32,
+        height: u32,
+        fps: f64,
+        config: &VideoConfig,
+    ) -> SpatialResult<Self> {
+        Self::create_packed(ffmpeg, path, width, height, fps, config, None)
+    }
+
+    /// [`FrameWriter::create`], tagging the stream with an x264 frame-packing
+    /// arrangement SEI message when `frame_packing` is set
+    fn create_packed(
+        ffmpeg: &Path,
+        path: &Path,
+        width: u32,
+        height: u32,
+        fps: f64,
+        config: &VideoConfig,
+        frame_packing: Option<u8>,
+    ) -> SpatialResult<Self> {
+        if width == 0 || height == 0 || !fps.is_finite() || fps <= 0.0 {
+            return Err(SpatialError::ConfigError(format!(
+                "Invalid video size {}x{} at {} fps",
+                width, height, fps
+            )));
+        }
+
+        let mut cmd = Command::new(ffmpeg);
+        cmd.args(["-hide_banner", "-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .arg("-s")
+            .arg(format!("{}x{}", width, height))
+            .arg("-framerate")
+            .arg(format!("{:.6}", fps))
+            .args(["-i", "-"])
+            // yuv420p needs even dimensions
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .arg("-c:v")
+            .arg(&config.codec)
+            .arg("-crf")
+            .arg(config.crf.to_string())
+            .arg("-preset")
+            .arg(&config.preset);
+        if let Some(packing) = frame_packing {
+            cmd.arg("-x264-params")
+                .arg(format!("frame-packing={}", packing));
+        }
+        cmd.args(["-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        tracing::debug!("Running: {:?}", cmd);
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffmpeg), e))?;
+        let stdin = child.stdin.take();
+
+        Ok(Self {
+            child: Some(child),
+            stdin,
+            path: path.to_path_buf(),
+            width,
+            height,
+            frames_written: 0,
+        })
+    }
+
+    /// Append the next frame
+    pub fn write_frame(&mut self, frame: &RgbImage) -> SpatialResult<()> {
+        if frame.dimensions() != (self.width, self.height) {
+            return Err(SpatialError::ConfigError(format!(
+                "Frame is {}x{}, video is {}x{}",
+                frame.width(),
+                frame.height(),
+                self.width,
+                self.height
+            )));
+        }
+        let Some(stdin) = self.stdin.as_mut() else {
+            return Err(SpatialError::ConfigError(
+                "Video already finished".to_string(),
+            ));
+        };
+        if stdin.write_all(frame.as_raw()).is_err() {
+            // ffmpeg exited early; its stderr explains why
+            drop(self.stdin.take());
+            return Err(match self.child.take() {
+                Some(mut child) => {
+                    let _ = child.wait();
+                    ffmpeg_error(&mut child, "Video encoding")
+                }
+                None => SpatialError::ConfigError("Video already finished".to_string()),
+            });
+        }
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    /// Frames written so far
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    /// Close the stream and wait for ffmpeg, returning the video path
+    pub fn finish(mut self) -> SpatialResult<PathBuf> {
+        drop(self.stdin.take());
+        let Some(mut child) = self.child.take() else {
+            return Ok(self.path.clone());
+        };
+        let status = child
+            .wait()
+            .map_err(|e| SpatialError::io("Failed to wait for ffmpeg", e))?;
+        if !status.success() {
+            return Err(ffmpeg_error(&mut child, "Video encoding"));
+        }
+        Ok(self.path.clone())
+    }
+}
+
+impl Drop for FrameWriter {
+    /// An unfinished writer (error or cancellation) kills ffmpeg and removes
+    /// the partial file
+    fn drop(&mut self) {
+        drop(self.stdin.take());
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn ffmpeg_error(child: &mut Child, what: &str) -> SpatialError {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+    SpatialError::io(
+        format!("{} with ffmpeg failed", what),
+        std::io::Error::other(stderr.trim().to_string()),
+    )
+}
+
+/// Size of a stereo frame built from `width` x `height` eyes
+pub fn stereo_frame_size(
+    width: u32,
+    height: u32,
+    layout: OutputFormat,
+    half_resolution: bool,
+) -> (u32, u32) {
+    match (layout, half_resolution) {
+        (OutputFormat::TopAndBottom, false) => (width, height * 2),
+        (OutputFormat::TopAndBottom, true) => (width, (height / 2).max(1) * 2),
+        (_, false) => (width * 2, height),
+        (_, true) => ((width / 2).max(1) * 2, height),
+    }
+}
+
+/// Pack a stereo pair into one video frame
+///
+/// `Separate` has no single-frame form and is rejected.
+pub fn compose_stereo_frame(
+    left: &RgbImage,
+    right: &RgbImage,
+    layout: OutputFormat,
+    half_resolution: bool,
+) -> SpatialResult<RgbImage> {
+    if layout == OutputFormat::Separate {
+        return Err(SpatialError::ConfigError(
+            "Video output needs a single-frame layout, not separate".to_string(),
+        ));
+    }
+
+    let (width, height) = stereo_frame_size(left.width(), left.height(), layout, half_resolution);
+    let (eye_width, eye_height) = match layout {
+        OutputFormat::TopAndBottom => (width, height / 2),
+        _ => (width / 2, height),
+    };
+    // Triangle filtering keeps per-frame squeezing cheap
+    let fit = |image: &RgbImage| {
+        if image.dimensions() == (eye_width, eye_height) {
+            image.clone()
+        } else {
+            imageops::resize(image, eye_width, eye_height, imageops::FilterType::Triangle)
+        }
+    };
+
+    let (first, second) = match layout {
+        OutputFormat::CrossEye => (fit(right), fit(left)),
+        OutputFormat::MirroredSideBySide => (fit(left), imageops::flip_horizontal(&fit(right))),
+        _ => (fit(left), fit(right)),
+    };
+
+    let mut frame = RgbImage::new(width, height);
+    imageops::replace(&mut frame, &first, 0, 0);
+    match layout {
+        OutputFormat::TopAndBottom => imageops::replace(&mut frame, &second, 0, eye_height as i64),
+        _ => imageops::replace(&mut frame, &second, eye_width as i64, 0),
+    }
+    Ok(frame)
+}
+
+/// Path of output segment `index`: `<stem>.partNNNN.mkv` next to the output
+///
+/// Segments are Matroska whatever the final container, since they are only
+/// joined by stream copy.
+pub fn segment_path(output: &Path, index: usize) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output".to_string());
+    output.with_file_name(format!("{}.part{:04}.mkv", stem, index))
+}
+
+/// ffmpeg concat demuxer list for `segments`
+fn concat_list(segments: &[PathBuf]) -> String {
+    segments
+        .iter()
+        .map(|path| {
+            let path = path.to_string_lossy().replace('\\', "/");
+            format!("file '{}'\n", path.replace('\'', "'\\''"))
+        })
+        .collect()
+}
+
+/// Summary of a finished video job
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoReport {
+    /// Frames in the output
+    pub frames: u64,
+    /// Frames taken from a previous run's checkpoint
+    pub resumed_frames: u64,
+    /// Frames depth estimation ran on in this run
+    pub depth_keyframes: u64,
+    /// Stereo video written
+    pub output: PathBuf,
+    /// Depth video written, when requested
+    pub depth_video: Option<PathBuf>,
+}
+
+/// Convert a video to stereo, streaming frames through ffmpeg pipes
+///
+/// Uses `config.depth_interval` / `depth_interpolation` to limit depth
+/// passes and `config.depth_video` for a depth video next to the output.
+/// The layout must fit in one frame (not `Separate`) and MV-HEVC is not
+/// available for video. `progress_fn` receives the frames written so far and
+/// the expected total, when known; `config.progress`, when set, gets the same
+/// counts as [`ProgressEvent::Frames`] between the other events.
+///
+/// Warping is not reported per row for video, only per frame.
+pub async fn process_video<F>(
+    input_path: &Path,
+    output_path: &Path,
+    config: SpatialConfig,
+    output_options: OutputOptions,
+    video: &VideoConfig,
+    mut progress_fn: Option<F>,
+) -> SpatialResult<VideoReport>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    config.validate()?;
+    output::validate_options(&output_options)?;
+    if output_options.layout == OutputFormat::Separate {
+        return Err(SpatialError::ConfigError(
+            "Video output needs a single-frame layout, not separate".to_string(),
+        ));
+    }
+    if output_options.mvhevc.as_ref().is_some_and(|c| c.enabled) {
+        return Err(SpatialError::ConfigError(
+            "MV-HEVC encoding is only available for photos".to_string(),
+        ));
+    }
+    if output_options.projection == Projection::Panorama360 {
+        return Err(SpatialError::ConfigError(
+            "360° panoramas are only available for photos".to_string(),
+        ));
+    }
+    if output_options.player.frame_packing_sei && video.codec != "libx264" {
+        return Err(SpatialError::ConfigError(format!(
+            "Frame-packing SEI is written by libx264 only, not {}",
+            video.codec
+        )));
+    }
+    let output_path = &naming::player_path(output_path, &output_options);
+
+    tracing::info!("🎬 Processing video: {:?}", input_path);
+    let sink = config.progress.clone();
+    progress::emit(sink.as_ref(), ProgressEvent::Preprocess);
+    let info = {
+        let (ffprobe, input) = (video.ffprobe().to_path_buf(), input_path.to_path_buf());
+        cpu::run(move || probe_video(&ffprobe, &input)).await?
+    };
+    let cuts = match &video.cuts {
+        Some(cuts) => Some(cuts.frame_ranges(info.fps, info.estimated_frames())?),
+        None => None,
+    };
+    tracing::info!(
+        "{}x{} at {:.3} fps, {:?} frames",
+        info.width,
+        info.height,
+        info.fps,
+        info.estimated_frames()
+    );
+    let total_frames = match &cuts {
+        Some(ranges) => {
+            let frames = ranges.iter().map(|r| r.end - r.start).sum();
+            tracing::info!("Converting {} cuts, {} frames", ranges.len(), frames);
+            Some(frames)
+        }
+        None => info.estimated_frames(),
+    };
+
+    let parallel = &config.parallel;
+    // Each frame is estimated once, so caching frames would only fill the disk
+    let depth_config = DepthConfig {
+        cache: None,
+        ..config.depth_config()
+    };
+    let depth = if config.depth_interval == 1 {
+        VideoDepth::Workers(
+            pool::depth_workers(&depth_config, parallel, parallel.worker_count()).await?,
+        )
+    } else {
+        VideoDepth::Keyframes(Box::new(DepthEstimator::load(depth_config).await?))
+    };
+    progress::emit(sink.as_ref(), ProgressEvent::Inference);
+
+    // Decoding, depth, rendering, and encoding all run on the CPU pool;
+    // frame counts are reported back here
+    let (input_path, output_path) = (input_path.to_path_buf(), output_path.to_path_buf());
+    let video = video.clone();
+    let report = cpu::run_with_progress(
+        move |report| {
+            convert_video(
+                &input_path,
+                &output_path,
+                config,
+                output_options,
+                &video,
+                &info,
+                cuts.as_deref(),
+                depth,
+                report,
+            )
+        },
+        |frames| {
+            progress::emit(
+                sink.as_ref(),
+                ProgressEvent::Frames {
+                    done: frames,
+                    total: total_frames,
+                },
+            );
+            if let Some(ref mut f) = progress_fn {
+                f(frames, total_frames);
+            }
+        },
+    )
+    .await?;
+    progress::emit(sink.as_ref(), ProgressEvent::Done);
+    Ok(report)
+}
+
+/// Convert every video in `input_dir` to stereo, resuming an interrupted batch
+///
+/// Videos (see [`VIDEO_EXTENSIONS`]; subdirectories are not searched) are
+/// converted one after another, in name order, with [`process_video`],
+/// each to `<output_dir>/<name>.mp4` plus its player tag (videos sharing a
+/// name keep their extension in it, as `clip_mov.mp4`). The manifest is
+/// written to `manifest_path`, or to `spatial-maker-manifest.json` in
+/// `output_dir` when `None`, after every video.
+///
+/// When a manifest from an earlier run with the same settings is found
+/// there, videos it lists as succeeded are skipped as long as the input
+/// size is unchanged and the outputs are still on disk. A video that was
+/// being converted resumes from its own [`VideoCheckpoint`]. A failed
+/// video is recorded and the batch continues; a cancellation stops the
+/// batch and the manifest records where it stopped.
+///
+/// `progress_fn` receives the video being converted with its frame counts,
+/// as in [`process_video`].
+///
+/// # Example
+///
+/// ```no_run
+/// use spatial_maker::video::{process_video_dir, VideoConfig};
+/// use spatial_maker::{OutputOptions, SpatialConfig};
+/// use std::path::Path;
+///
+/// # async fn example() -> anyhow::Result<()> {
+/// let manifest = process_video_dir(
+///     Path::new("clips"),
+///     Path::new("clips/stereo"),
+///     SpatialConfig::default(),
+///     OutputOptions::default(),
+///     &VideoConfig::default(),
+///     None,
+///     Some(|input: &Path, done: u64, _total: Option<u64>| println!("{:?}: {}", input, done)),
+/// )
+/// .await?;
+/// println!(
+///     "{} of {} converted ({} from an earlier run)",
+///     manifest.stats.succeeded, manifest.stats.total, manifest.stats.resumed
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub async fn process_video_dir<F>(
+    input_dir: &Path,
+    output_dir: &Path,
+    config: SpatialConfig,
+    output_options: OutputOptions,
+    video: &VideoConfig,
+    manifest_path: Option<&Path>,
+    mut progress_fn: Option<F>,
+) -> SpatialResult<RunManifest>
+where
+    F: FnMut(&Path, u64, Option<u64>),
+{
+    config.validate()?;
+    if let (Ok(input), Ok(output)) = (input_dir.canonicalize(), output_dir.canonicalize()) {
+        if input == output {
+            return Err(SpatialError::ConfigError(
+                "Batch output needs a different directory than its input".to_string(),
+            ));
+        }
+    }
+    let inputs = list_videos(input_dir)?;
+    let manifest_path = match manifest_path {
+        Some(path) => path.to_path_buf(),
+        None => manifest::default_manifest_path(output_dir),
+    };
+    tracing::info!(
+        "🎞️  Converting {} videos from {:?}",
+        inputs.len(),
+        input_dir
+    );
+
+    // Recording the model hashes the whole file
+    let mut manifest = {
+        let (config, output_options) = (config.clone(), output_options.clone());
+        cpu::run(move || Ok(RunManifest::new(&config, &output_options))).await?
+    };
+    let previous = previous_manifest(&manifest_path, manifest.settings.as_deref());
+
+    for (input, output) in inputs.iter().zip(batch_outputs(&inputs, output_dir)) {
+        if let Some(entry) = previous.as_ref().and_then(|p| p.completed(input, &output)) {
+            tracing::info!("⏭️  Already converted: {:?}", input);
+            manifest.resume(entry.clone());
+            continue;
+        }
+
+        let started = Instant::now();
+        let result = process_video(
+            input,
+            &output,
+            config.clone(),
+            output_options.clone(),
+            video,
+            progress_fn
+                .as_mut()
+                .map(|f| |done: u64, total: Option<u64>| f(input, done, total)),
+        )
+        .await;
+        let (outputs, result) = match result {
+            Ok(report) => {
+                let mut outputs = vec![naming::sidecar_path(&report.output), report.output];
+                outputs.extend(report.depth_video);
+                (outputs, Ok(()))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to convert {:?}: {}", input, e);
+                (Vec::new(), Err(e))
+            }
+        };
+        let cancelled = matches!(result, Err(SpatialError::Cancelled));
+        manifest.record(input, &output, outputs, &result, started.elapsed());
+        manifest.write(&manifest_path)?;
+        if cancelled {
+            break;
+        }
+    }
+
+    manifest.finish();
+    manifest.write(&manifest_path)?;
+    Ok(manifest)
+}
+
+/// Where each of `inputs` is written in `output_dir`
+///
+/// Videos become `<stem>.mp4`. Inputs sharing a stem (`clip.mov` and
+/// `clip.mp4`) keep their extension in the name (`clip_mov.mp4`), and a
+/// number is added if that name is taken too, so no output overwrites
+/// another. Names are compared ignoring case, as on macOS and Windows.
+fn batch_outputs(inputs: &[PathBuf], output_dir: &Path) -> Vec<PathBuf> {
+    fn stem(path: &Path) -> std::borrow::Cow<'_, str> {
+        path.file_stem().unwrap_or_default().to_string_lossy()
+    }
+    let mut shared: HashMap<String, usize> = HashMap::new();
+    for input in inputs {
+        *shared.entry(stem(input).to_lowercase()).or_default() += 1;
+    }
+
+    let mut taken = HashSet::new();
+    inputs
+        .iter()
+        .map(|input| {
+            let mut base = stem(input).to_string();
+            if shared[&base.to_lowercase()] > 1 {
+                let extension = input.extension().unwrap_or_default().to_string_lossy();
+                base = format!("{}_{}", base, extension);
+            }
+            let mut name = base.clone();
+            let mut n = 2;
+            while !taken.insert(name.to_lowercase()) {
+                name = format!("{}_{}", base, n);
+                n += 1;
+            }
+            output_dir.join(format!("{}.mp4", name))
+        })
+        .collect()
+}
+
+/// Videos directly inside `dir`, sorted by name
+fn list_videos(dir: &Path) -> SpatialResult<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| SpatialError::io(format!("Failed to read {:?}", dir), e))?;
+    let mut videos: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_video_path(path))
+        .collect();
+    videos.sort();
+    Ok(videos)
+}
+
+/// The manifest an earlier batch left at `path`, if it used `settings`
+fn previous_manifest(path: &Path, settings: Option<&str>) -> Option<RunManifest> {
+    if !path.exists() {
+        return None;
+    }
+    match RunManifest::read(path) {
+        Ok(previous) if previous.settings.as_deref() == settings => {
+            tracing::info!(
+                "⏩ Resuming batch: {} videos already converted",
+                previous.stats.succeeded
+            );
+            Some(previous)
+        }
+        Ok(_) => {
+            tracing::info!(
+                "Manifest {:?} is for different settings, starting over",
+                path
+            );
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable manifest: {}", e);
+            None
+        }
+    }
+}
+
+/// Depth sessions for a video, loaded before any frame is read
+enum VideoDepth {
+    /// Every frame is a keyframe, so workers estimate depth themselves
+    Workers(Vec<pool::DepthWorker>),
+    /// Keyframes depend on each other, so one estimator runs them in order
+    Keyframes(Box<DepthEstimator>),
+}
+
+/// [`process_video`] once the input is probed and depth is loaded, run
+/// outside the async executor
+#[allow(clippy::too_many_arguments)]
+fn convert_video(
+    input_path: &Path,
+    output_path: &Path,
+    config: SpatialConfig,
+    output_options: OutputOptions,
+    video: &VideoConfig,
+    info: &VideoInfo,
+    cuts: Option<&[Range<u64>]>,
+    depth: VideoDepth,
+    report: &mut dyn FnMut(u64),
+) -> SpatialResult<VideoReport> {
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+    }
+
+    // Encoder settings and cuts change the output too, so they are part of
+    // the fingerprint
+    let mut fingerprint = format!(
+        "{}:{}:{}:{}",
+        settings_fingerprint(&config, &output_options),
+        video.codec,
+        video.crf,
+        video.preset
+    );
+    if let Some(ranges) = cuts {
+        fingerprint.push_str(&format!(":{:?}", ranges));
+    }
+    let checkpoint_path = VideoCheckpoint::path_for(output_path);
+    let checkpoint =
+        match VideoCheckpoint::load_matching(&checkpoint_path, input_path, &fingerprint)? {
+            Some(mut existing) => {
+                for stale in existing.discard_partial() {
+                    remove_segment(&stale, config.depth_video);
+                }
+                existing
+            }
+            None => VideoCheckpoint::new(input_path, &fingerprint)?,
+        };
+    let resumed_frames = checkpoint.frames_completed;
+
+    let mut sink = SegmentSink {
+        ffmpeg: video.ffmpeg(),
+        output_path,
+        video,
+        info,
+        layout: output_options.layout,
+        half_resolution: output_options.half_resolution,
+        frame_packing: naming::frame_packing(&output_options),
+        depth_encoding: config.depth_video,
+        checkpoint,
+        checkpoint_path: &checkpoint_path,
+        current: None,
+    };
+
+    let parallel = &config.parallel;
+    let whole_video = 0..u64::MAX;
+    let mut reader = CutReader::new(
+        video.ffmpeg(),
+        input_path,
+        info,
+        cuts.unwrap_or(std::slice::from_ref(&whole_video)),
+        resumed_frames,
+    );
+    let mut depth_keyframes = 0u64;
+    report(resumed_frames);
+
+    let render = |frame: &DynamicImage, depth: Array2<f32>| {
+        cancel::check(config.cancel.as_ref())?;
+        let (left, right) = render_stereo(frame, &depth, &config, output_options.projection)?;
+        Ok(RenderedFrame {
+            frame: compose_stereo_frame(
+                &left,
+                &right,
+                output_options.layout,
+                output_options.half_resolution,
+            )?,
+            depth: config.depth_video.map(|_| depth),
+        })
+    };
+
+    match depth {
+        VideoDepth::Workers(workers) => {
+            let frames = std::iter::from_fn(|| reader.read_frame().transpose());
+            pool::run_ordered(
+                workers,
+                parallel.queue_depth(),
+                frames,
+                |depth, frame| {
+                    let frame = DynamicImage::ImageRgb8(frame);
+                    let depth = depth.estimate(&frame)?.into_array();
+                    render(&frame, depth)
+                },
+                |rendered| {
+                    depth_keyframes += 1;
+                    sink.write(&rendered)?;
+                    report(sink.frames_completed());
+                    Ok(())
+                },
+            )?;
+        }
+        VideoDepth::Keyframes(estimator) => {
+            // Depth runs in order on this thread and workers render the
+            // stereo pairs
+            let mut frames = KeyframedFrames {
+                reader: &mut reader,
+                keyframer: DepthKeyframer::new(config.depth_interval, config.depth_interpolation),
+                config: &config,
+                cut: 0,
+                estimator: *estimator,
+                ready: VecDeque::new(),
+                keyframes: 0,
+                finished: false,
+            };
+            pool::run_ordered(
+                vec![(); parallel.worker_count()],
+                parallel.queue_depth(),
+                &mut frames,
+                |_, (frame, depth)| render(&frame, depth),
+                |rendered| {
+                    sink.write(&rendered)?;
+                    report(sink.frames_completed());
+                    Ok(())
+                },
+            )?;
+            depth_keyframes = frames.keyframes;
+        }
+    }
+
+    let checkpoint = sink.finish()?;
+    let segments: Vec<PathBuf> = checkpoint
+        .completed_segments()
+        .into_iter()
+        .map(Path::to_path_buf)
+        .collect();
+    if segments.is_empty() {
+        return Err(SpatialError::io(
+            format!("No frames decoded from {:?}", input_path),
+            ErrorKind::InvalidData.into(),
+        ));
+    }
+
+    cancel::check(config.cancel.as_ref())?;
+    match cuts {
+        Some(ranges) => {
+            let audio_path = output_path.with_extension("cuts.mka");
+            let muxed = cut_audio(video, input_path, info, ranges, &audio_path).and_then(|cut| {
+                let source = if cut {
+                    audio_path.as_path()
+                } else {
+                    input_path
+                };
+                mux_output(source, output_path, &segments, &output_options, video)
+            });
+            let _ = std::fs::remove_file(&audio_path);
+            muxed?;
+        }
+        None => mux_output(input_path, output_path, &segments, &output_options, video)?,
+    }
+    let depth_video = match config.depth_video {
+        Some(encoding) => {
+            let depth_segments: Vec<PathBuf> = segments
+                .iter()
+                .map(|segment| depth_video_path(segment, encoding))
+                .collect();
+            let path = depth_video_path(output_path, encoding);
+            join_segments(video.ffmpeg(), &depth_segments, &path, &[])?;
+            Some(path)
+        }
+        None => None,
+    };
+
+    for segment in &segments {
+        remove_segment(segment, config.depth_video);
+    }
+    VideoCheckpoint::remove(&checkpoint_path)?;
+    naming::write_sidecar(output_path, &output_options)?;
+
+    tracing::info!(
+        "✅ Video saved to {:?} ({} frames, {} depth passes)",
+        output_path,
+        checkpoint.frames_completed,
+        depth_keyframes
+    );
+    Ok(VideoReport {
+        frames: checkpoint.frames_completed,
+        resumed_frames,
+        depth_keyframes,
+        output: output_path.to_path_buf(),
+        depth_video,
+    })
+}
+
+/// Decodes the cut ranges of a video one after another, as one stream
+struct CutReader<'a> {
+    ffmpeg: &'a Path,
+    input: &'a Path,
+    info: &'a VideoInfo,
+    /// Ranges not yet started
+    pending: VecDeque<Range<u64>>,
+    /// Decoder for the current range and the frames left in it
+    current: Option<(FrameReader, u64)>,
+    ranges_opened: usize,
+}
+
+impl<'a> CutReader<'a> {
+    /// Reader for `ranges`, skipping their first `skip` frames
+    fn new(
+        ffmpeg: &'a Path,
+        input: &'a Path,
+        info: &'a VideoInfo,
+        ranges: &[Range<u64>],
+        skip: u64,
+    ) -> Self {
+        Self {
+            ffmpeg,
+            input,
+            info,
+            pending: skip_frames(ranges, skip),
+            current: None,
+            ranges_opened: 0,
+        }
+    }
+
+    /// The next frame, or `None` after the last range
+    fn read_frame(&mut self) -> SpatialResult<Option<RgbImage>> {
+        loop {
+            if let Some((reader, left)) = self.current.as_mut() {
+                if *left > 0 {
+                    if let Some(frame) = reader.read_frame()? {
+                        *left -= 1;
+                        return Ok(Some(frame));
+                    }
+                }
+            }
+            // Dropping a decoder stops its ffmpeg when the range ended early
+            self.current = None;
+            let Some(range) = self.pending.pop_front() else {
+                return Ok(None);
+            };
+            let reader = FrameReader::open(self.ffmpeg, self.input, self.info, range.start)?;
+            self.current = Some((reader, range.end - range.start));
+            self.ranges_opened += 1;
+        }
+    }
+
+    /// Which range (counted from this reader's first) the last frame came from
+    fn cut(&self) -> usize {
+        self.ranges_opened.saturating_sub(1)
+    }
+}
+
+/// `ranges` with their first `skip` frames removed
+fn skip_frames(ranges: &[Range<u64>], mut skip: u64) -> VecDeque<Range<u64>> {
+    ranges
+        .iter()
+        .filter_map(|range| {
+            let len = range.end - range.start;
+            if skip >= len {
+                skip -= len;
+                return None;
+            }
+            let start = range.start + skip;
+            skip = 0;
+            Some(start..range.end)
+        })
+        .collect()
+}
+
+/// Decoded frames paired with their depth: estimated on keyframes,
+/// interpolated in between
+struct KeyframedFrames<'a> {
+    reader: &'a mut CutReader<'a>,
+    keyframer: DepthKeyframer,
+    config: &'a SpatialConfig,
+    /// Cut the keyframer's frames come from
+    cut: usize,
+    estimator: DepthEstimator,
+    ready: VecDeque<(DynamicImage, Array2<f32>)>,
+    /// Depth passes run so far
+    keyframes: u64,
+    finished: bool,
+}
+
+impl KeyframedFrames<'_> {
+    /// Read frames until at least one has its depth
+    fn fill(&mut self) -> SpatialResult<()> {
+        while self.ready.is_empty() && !self.finished {
+            let Some(frame) = self.reader.read_frame()? else {
+                self.finished = true;
+                self.ready.extend(self.keyframer.finish()?);
+                break;
+            };
+            // Depth is never interpolated across a cut
+            if self.reader.cut() != self.cut {
+                self.ready.extend(self.keyframer.finish()?);
+                self.keyframer = DepthKeyframer::new(
+                    self.config.depth_interval,
+                    self.config.depth_interpolation,
+                );
+                self.cut = self.reader.cut();
+            }
+            let Some(keyframe) = self.keyframer.push(DynamicImage::ImageRgb8(frame))? else {
+                continue;
+            };
+            let depth = self.estimator.estimate(keyframe)?;
+            self.keyframes += 1;
+            self.ready.extend(self.keyframer.submit_depth(depth)?);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for KeyframedFrames<'_> {
+    type Item = SpatialResult<(DynamicImage, Array2<f32>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(e) = self.fill() {
+            self.finished = true;
+            self.ready.clear();
+            return Some(Err(e));
+        }
+        self.ready.pop_front().map(Ok)
+    }
+}
+
+/// A packed stereo frame, with its depth when a depth video is written
+struct RenderedFrame {
+    frame: RgbImage,
+    depth: Option<Array2<f32>>,
+}
+
+fn render_stereo(
+    frame: &DynamicImage,
+    depth: &Array2<f32>,
+    config: &SpatialConfig,
+    projection: Projection,
+) -> SpatialResult<(RgbImage, RgbImage)> {
+    let upsampled = upsample_depth(depth, frame, config.depth_upsampling);
+    let focused = config.subject_focus.map(|focus| focus.apply(&upsampled));
+    let depth = focused.as_ref().unwrap_or(&upsampled);
+    let cancel = config.cancel.as_ref();
+    let (left, right) = if projection == Projection::Vr180 {
+        generate_stereo_pair_vr180(
+            frame,
+            depth,
+            config.max_disparity,
+            &config.hole_fill,
+            cancel,
+        )?
+    } else {
+        generate_stereo_pair_with_fill(
+            frame,
+            depth,
+            config.max_disparity,
+            &config.hole_fill,
+            cancel,
+        )?
+    };
+    Ok((left.to_rgb8(), right.to_rgb8()))
+}
+
+/// Writers for the segment currently being encoded
+struct OpenSegment {
+    video: FrameWriter,
+    depth: Option<DepthVideoWriter>,
+    frames: u64,
+}
+
+/// Splits the stereo (and depth) frames into checkpointed segments
+struct SegmentSink<'a> {
+    ffmpeg: &'a Path,
+    output_path: &'a Path,
+    video: &'a VideoConfig,
+    info: &'a VideoInfo,
+    layout: OutputFormat,
+    half_resolution: bool,
+    frame_packing: Option<u8>,
+    depth_encoding: Option<DepthVideoEncoding>,
+    checkpoint: VideoCheckpoint,
+    checkpoint_path: &'a Path,
+    current: Option<OpenSegment>,
+}
+
+impl SegmentSink<'_> {
+    fn frames_completed(&self) -> u64 {
+        self.checkpoint.frames_completed
+    }
+
+    fn write(&mut self, rendered: &RenderedFrame) -> SpatialResult<()> {
+        if self.current.is_none() {
+            self.open_segment()?;
+        }
+        let segment = self.current.as_mut().expect("segment was just opened");
+
+        segment.video.write_frame(&rendered.frame)?;
+        if let (Some(depth_writer), Some(depth)) = (segment.depth.as_mut(), &rendered.depth) {
+            depth_writer.write_frame(depth)?;
+        }
+        segment.frames += 1;
+        self.checkpoint.record_frames(1);
+
+        let limit = match self.video.segment_frames {
+            0 => u64::MAX,
+            frames => frames,
+        };
+        if segment.frames >= limit {
+            self.close_segment()?;
+        }
+        Ok(())
+    }
+
+    fn open_segment(&mut self) -> SpatialResult<()> {
+        let path = segment_path(self.output_path, self.checkpoint.segments.len());
+        let (width, height) = stereo_frame_size(
+            self.info.width,
+            self.info.height,
+            self.layout,
+            self.half_resolution,
+        );
+        let video = FrameWriter::create_packed(
+            self.ffmpeg,
+            &path,
+            width,
+            height,
+            self.info.fps,
+            self.video,
+            self.frame_packing,
+        )?;
+        let depth = match self.depth_encoding {
+            Some(encoding) => Some(DepthVideoWriter::create(
+                self.ffmpeg,
+                &depth_video_path(&path, encoding),
+                self.info.width,
+                self.info.height,
+                self.info.fps,
+                encoding,
+            )?),
+            None => None,
+        };
+        self.checkpoint.begin_segment(&path);
+        self.current = Some(OpenSegment {
+            video,
+            depth,
+            frames: 0,
+        });
+        Ok(())
+    }
+
+    /// Finalize the open segment and record it in the checkpoint
+    fn close_segment(&mut self) -> SpatialResult<()> {
+        let Some(segment) = self.current.take() else {
+            return Ok(());
+        };
+        segment.video.finish()?;
+        if let Some(depth_writer) = segment.depth {
+            depth_writer.finish()?;
+        }
+        self.checkpoint.complete_segment();
+        self.checkpoint.save(self.checkpoint_path)
+    }
+
+    fn finish(mut self) -> SpatialResult<VideoCheckpoint> {
+        self.close_segment()?;
+        Ok(self.checkpoint)
+    }
+}
+
+/// Trim the audio of `input` to `ranges` and join the pieces into `output`
+///
+/// Returns false, writing nothing, when there is no audio to keep.
+fn cut_audio(
+    video: &VideoConfig,
+    input: &Path,
+    info: &VideoInfo,
+    ranges: &[Range<u64>],
+    output: &Path,
+) -> SpatialResult<bool> {
+    if video.audio.mode == audio::AudioMode::Drop {
+        return Ok(false);
+    }
+    let streams = match audio::probe_audio_streams(video.ffprobe(), input) {
+        Ok(streams) if !streams.is_empty() => streams,
+        Ok(_) => return Ok(false),
+        Err(e) => {
+            tracing::warn!("Could not read source audio, writing silent video: {}", e);
+            return Ok(false);
+        }
+    };
+    let spans: Vec<(f64, f64)> = ranges
+        .iter()
+        .map(|r| (r.start as f64 / info.fps, r.end as f64 / info.fps))
+        .collect();
+
+    let mut cmd = Command::new(video.ffmpeg());
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y", "-nostdin"])
+        .args(audio::cut_args(input, &streams, &spans))
+        .arg(output);
+    tracing::debug!("Running: {:?}", cmd);
+    let result = cmd
+        .output()
+        .map_err(|e| SpatialError::io(format!("Failed to run {:?}", video.ffmpeg()), e))?;
+    if !result.status.success() {
+        return Err(SpatialError::io(
+            "Cutting audio with ffmpeg failed",
+            std::io::Error::other(String::from_utf8_lossy(&result.stderr).trim().to_string()),
+        ));
+    }
+    Ok(true)
+}
+
+/// Join the stereo segments and add the audio of `input_path` and credits
+fn mux_output(
+    input_path: &Path,
+    output_path: &Path,
+    segments: &[PathBuf],
+    options: &OutputOptions,
+    video: &VideoConfig,
+) -> SpatialResult<()> {
+    let extension = output_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mp4")
+        .to_ascii_lowercase();
+    let streams = match audio::probe_audio_streams(video.ffprobe(), input_path) {
+        Ok(streams) => streams,
+        Err(e) => {
+            tracing::warn!("Could not read source audio, writing silent video: {}", e);
+            Vec::new()
+        }
+    };
+
+    let mut args: Vec<String> = vec!["-i".to_string(), input_path.to_string_lossy().into_owned()];
+    args.extend(["-map", "0:v:0", "-c:v", "copy"].map(String::from));
+    args.extend(audio::audio_args(&streams, 1, &video.audio, &extension));
+    if let Some(attribution) = &options.attribution {
+        args.extend(attribution.ffmpeg_args());
+    }
+    if matches!(extension.as_str(), "mp4" | "m4v" | "mov") {
+        args.extend(["-movflags", "+faststart"].map(String::from));
+        // Apple players only recognize HEVC tagged as hvc1
+        if video.is_hevc() {
+            args.extend(["-tag:v", "hvc1"].map(String::from));
+        }
+    }
+    join_segments(video.ffmpeg(), segments, output_path, &args)
+}
+
+/// Concatenate `segments` into `output` by stream copy
+///
+/// `extra_args` follow the concat input (input 0), e.g. further inputs and
+/// mappings; without them every stream is copied.
+fn join_segments(
+    ffmpeg: &Path,
+    segments: &[PathBuf],
+    output: &Path,
+    extra_args: &[String],
+) -> SpatialResult<()> {
+    let list_path = output.with_extension("concat.txt");
+    std::fs::write(&list_path, concat_list(segments))
+        .map_err(|e| SpatialError::io(format!("Failed to write {:?}", list_path), e))?;
+
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path);
+    if extra_args.is_empty() {
+        cmd.args(["-c", "copy"]);
+    } else {
+        cmd.args(extra_args);
+    }
+    cmd.arg(output);
+
+    tracing::debug!("Running: {:?}", cmd);
+    let result = cmd
+        .output()
+        .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffmpeg), e));
+    let _ = std::fs::remove_file(&list_path);
+
+    let result = result?;
+    if !result.status.success() {
+        return Err(SpatialError::io(
+            "Joining video segments with ffmpeg failed",
+            std::io::Error::other(String::from_utf8_lossy(&result.stderr).trim().to_string()),
+        ));
+    }
+    Ok(())
+}
+
+/// Delete a stereo segment and its depth segment, if any
+fn remove_segment(path: &Path, depth_encoding: Option<DepthVideoEncoding>) {
+    let _ = std::fs::remove_file(path);
+    if let Some(encoding) = depth_encoding {
+        let _ = std::fs::remove_file(depth_video_path(path, encoding));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ffprobe_video() {
+        let json = r#"{
+            "streams": [{
+                "width": 1920,
+                "height": 1080,
+                "avg_frame_rate": "30000/1001",
+                "r_frame_rate": "30000/1001",
+                "nb_frames": "300",
+                "duration": "10.010000"
+            }],
+            "format": { "duration": "10.050000" }
+        }"#;
+        let info = parse_ffprobe_video(json).unwrap();
+        assert_eq!((info.width, info.height), (1920, 1080));
+        assert!((info.fps - 29.97).abs() < 0.01);
+        assert_eq!(info.frame_count, Some(300));
+        assert_eq!(info.duration, Some(10.01));
+        assert_eq!(info.estimated_frames(), Some(300));
+    }
+
+    #[test]
+    fn test_parse_ffprobe_video_rotation_and_fallbacks() {
+        // Phone video shot in portrait: stored landscape with a -90° display matrix
+        let json = r#"{
+            "streams": [{
+                "width": 1920,
+                "height": 1080,
+                "avg_frame_rate": "0/0",
+                "r_frame_rate": "25/1",
+                "side_data_list": [{ "rotation": -90 }]
+            }],
+            "format": { "duration": "4.0" }
+        }"#;
+        let info = parse_ffprobe_video(json).unwrap();
+        assert_eq!((info.width, info.height), (1080, 1920));
+        assert_eq!(info.fps, 25.0);
+        assert_eq!(info.frame_count, None);
+        assert_eq!(info.estimated_frames(), Some(100));
+
+        let tagged = r#"{"streams": [{"width": 640, "height": 480,
+            "r_frame_rate": "30/1", "tags": {"rotate": "270"}}]}"#;
+        let info = parse_ffprobe_video(tagged).unwrap();
+        assert_eq!((info.width, info.height), (480, 640));
+
+        assert!(parse_ffprobe_video(r#"{"streams": []}"#).is_err());
+        assert!(parse_ffprobe_video(
+            r#"{"streams": [{"width": 640, "height": 480, "r_frame_rate": "0/0"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_compose_stereo_frame() {
+        let left = RgbImage::from_pixel(4, 2, image::Rgb([255, 0, 0]));
+        let right = RgbImage::from_pixel(4, 2, image::Rgb([0, 0, 255]));
+
+        let sbs = compose_stereo_frame(&left, &right, OutputFormat::SideBySide, false).unwrap();
+        assert_eq!(sbs.dimensions(), (8, 2));
+        assert_eq!(sbs.get_pixel(0, 0)[0], 255);
+        assert_eq!(sbs.get_pixel(7, 1)[2], 255);
+
+        let cross = compose_stereo_frame(&left, &right, OutputFormat::CrossEye, false).unwrap();
+        assert_eq!(cross.get_pixel(0, 0)[2], 255);
+
+        let tb = compose_stereo_frame(&left, &right, OutputFormat::TopAndBottom, false).unwrap();
+        assert_eq!(tb.dimensions(), (4, 4));
+        assert_eq!(tb.get_pixel(0, 3)[2], 255);
+
+        // Half-SBS keeps the source frame size
+        let half = compose_stereo_frame(&left, &right, OutputFormat::SideBySide, true).unwrap();
+        assert_eq!(half.dimensions(), (4, 2));
+        assert_eq!(half.get_pixel(0, 0)[0], 255);
+        assert_eq!(half.get_pixel(3, 0)[2], 255);
+
+        assert!(compose_stereo_frame(&left, &right, OutputFormat::Separate, false).is_err());
+    }
+
+    #[test]
+    fn test_segment_paths_and_concat_list() {
+        assert_eq!(
+            segment_path(Path::new("/out/clip_sbs.mp4"), 3),
+            PathBuf::from("/out/clip_sbs.part0003.mkv")
+        );
+        assert_eq!(
+            depth_video_path(
+                &segment_path(Path::new("clip.mov"), 0),
+                DepthVideoEncoding::Gray16
+            ),
+            PathBuf::from("clip.part0000_depth.mkv")
+        );
+
+        let list = concat_list(&[
+            PathBuf::from("/out/a.part0000.mkv"),
+            PathBuf::from("/out/it's.part0001.mkv"),
+        ]);
+        assert_eq!(
+            list,
+            "file '/out/a.part0000.mkv'\nfile '/out/it'\\''s.part0001.mkv'\n"
+        );
+    }
+
+    #[test]
+    fn test_resume_skips_across_cuts() {
+        let ranges = [10..20, 50..55, 100..110];
+        assert_eq!(skip_frames(&ranges, 0), ranges);
+        assert_eq!(skip_frames(&ranges, 12), [52..55, 100..110]);
+        assert_eq!(skip_frames(&ranges, 15), &ranges[2..]);
+        assert!(skip_frames(&ranges, 25).is_empty());
+        let endless = skip_frames(std::slice::from_ref(&(0..u64::MAX)), 900);
+        assert_eq!(endless.len(), 1);
+        assert_eq!(endless[0], 900..u64::MAX);
+    }
+
+    #[test]
+    fn test_writer_rejects_invalid_size() {
+        let result = FrameWriter::create(
+            Path::new("ffmpeg"),
+            Path::new("out.mkv"),
+            1920,
+            1080,
+            0.0,
+            &VideoConfig::default(),
+        );
+        assert!(matches!(result, Err(SpatialError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_process_video_rejects_separate_layout() {
+        let options = OutputOptions {
+            layout: OutputFormat::Separate,
+            ..Default::default()
+        };
+        let result = process_video(
+            Path::new("missing.mp4"),
+            Path::new("out.mp4"),
+            SpatialConfig::default(),
+            options,
+            &VideoConfig::default(),
+            None::<fn(u64, Option<u64>)>,
+        )
+        .await;
+        assert!(matches!(result, Err(SpatialError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_list_videos() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for name in ["b.MOV", "a.mp4", "notes.txt", "photo.jpg"] {
+            std::fs::write(temp_dir.path().join(name), b"").unwrap();
+        }
+        std::fs::create_dir(temp_dir.path().join("nested.mp4")).unwrap();
+
+        let videos = list_videos(temp_dir.path()).unwrap();
+        let names: Vec<_> = videos.iter().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(names, ["a.mp4", "b.MOV"]);
+        assert!(list_videos(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_batch_outputs_do_not_collide() {
+        let inputs: Vec<PathBuf> = [
+            "a.mp4",
+            "b.MOV",
+            "clip.mov",
+            "clip.mp4",
+            "clip_mov.mov",
+            "take.2.mp4",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        let outputs = batch_outputs(&inputs, Path::new("out"));
+        let names: Vec<_> = outputs.iter().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(
+            names,
+            [
+                "a.mp4",
+                "b.mp4",
+                "clip_mov.mp4",
+                "clip_mp4.mp4",
+                "clip_mov_2.mp4",
+                "take.2.mp4"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_video_dir_rejects_same_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = process_video_dir(
+            temp_dir.path(),
+            temp_dir.path(),
+            SpatialConfig::default(),
+            OutputOptions::default(),
+            &VideoConfig::default(),
+            None,
+            None::<fn(&Path, u64, Option<u64>)>,
+        )
+        .await;
+        assert!(matches!(result, Err(SpatialError::ConfigError(_))));
+    }
+}
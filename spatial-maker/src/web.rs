@@ -0,0 +1,62 @@
+//! Browser support (`wasm32` with the `web` feature)
+//!
+//! Depth runs on ONNX Runtime Web through `ort-web`, and stereo rendering is
+//! plain Rust, so [`crate::process_image`] works in a browser as it does
+//! natively. What does not exist there is left out of the build:
+//! - The checkpoint directory and model downloads; [`DepthEstimator::load`]
+//!   fetches the model from HuggingFace instead, and
+//!   [`DepthEstimator::from_url`] / [`DepthEstimator::from_model_bytes`] take
+//!   a self-hosted or already fetched model
+//! - Path-based pipelines (`process_photo`, `process_photos`, ...), video,
+//!   run manifests, and the worker pool
+//! - Tiled depth; estimation is always a single pass
+//!
+//! Estimation is `async` in the browser, so [`DepthEstimator::estimate`] and
+//! [`crate::process_image_with`] return futures there.
+//!
+//! ```ignore
+//! use spatial_maker::{process_image, web, OutputOptions, SpatialConfig};
+//!
+//! async fn stereo(bytes: &[u8]) -> spatial_maker::SpatialResult<()> {
+//!     // Optional: pick execution providers before the first model loads
+//!     web::init(web::FEATURE_WEBGPU).await?;
+//!
+//!     let image = image::load_from_memory(bytes)?;
+//!     let result = process_image(&image, &SpatialConfig::default(), &OutputOptions::default())
+//!         .await?;
+//!     // Hand result.left / result.right to a canvas
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`DepthEstimator::load`]: crate::DepthEstimator::load
+//! [`DepthEstimator::from_url`]: crate::DepthEstimator::from_url
+//! [`DepthEstimator::from_model_bytes`]: crate::DepthEstimator::from_model_bytes
+//! [`DepthEstimator::estimate`]: crate::DepthEstimator::estimate
+
+use crate::error::{SpatialError, SpatialResult};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub use ort_web::{FEATURE_NONE, FEATURE_WEBGL, FEATURE_WEBGPU, FEATURE_WEBNN};
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Load ONNX Runtime Web with the given execution provider features
+///
+/// Estimators call this with [`FEATURE_NONE`] (CPU via WebAssembly) when it
+/// has not been called yet; call it first to use WebGL, WebGPU, or WebNN.
+/// Later calls do nothing.
+pub async fn init(features: u8) -> SpatialResult<()> {
+    if INITIALIZED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    let api = ort_web::api(features).await.map_err(|e| {
+        SpatialError::ort(
+            "Failed to load ONNX Runtime Web",
+            ort::Error::new(e.to_string()),
+        )
+    })?;
+    ort::set_api(api);
+    INITIALIZED.store(true, Ordering::SeqCst);
+    Ok(())
+}
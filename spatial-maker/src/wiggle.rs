@@ -0,0 +1,330 @@
+//! Parallax "wiggle" animation export
+//!
+//! A wigglegram shows the 3D effect without a headset: the camera sweeps
+//! left and right between a handful of viewpoints synthesized from the depth
+//! map, and the animation plays back and forth in a loop.
+//!
+//! GIFs are encoded in-process; MP4s pipe raw frames into ffmpeg (libx264).
+//!
+//! ```no_run
+//! use spatial_maker::{estimate_depth, save_wiggle, DepthConfig, WiggleConfig};
+//!
+//! # async fn example(image: image::DynamicImage) -> anyhow::Result<()> {
+//! let depth = estimate_depth(&image, &DepthConfig::default()).await?;
+//! save_wiggle(&image, &depth, "wiggle.gif", &WiggleConfig::default(), None)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cancel::{self, CancellationToken};
+use crate::error::{SpatialError, SpatialResult};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{DynamicImage, Frame, RgbImage};
+use ndarray::Array2;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Container for a wiggle animation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WiggleFormat {
+    /// Looping animated GIF
+    Gif,
+    /// H.264 MP4 via ffmpeg
+    Mp4,
+}
+
+impl WiggleFormat {
+    /// Pick the format from the output file extension (defaults to GIF)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .as_deref()
+        {
+            Some("mp4") | Some("m4v") | Some("mov") => WiggleFormat::Mp4,
+            _ => WiggleFormat::Gif,
+        }
+    }
+}
+
+/// Settings for a wiggle animation
+#[derive(Clone, Debug, PartialEq)]
+pub struct WiggleConfig {
+    /// Distinct viewpoints across the sweep (at least 2)
+    pub views: u32,
+
+    /// Disparity between the outermost viewpoints, in source-image pixels
+    pub disparity_range: f32,
+
+    /// Time each frame is shown, in milliseconds
+    pub frame_delay_ms: u32,
+
+    /// Frames are scaled down to at most this width
+    pub max_width: u32,
+
+    /// Back-and-forth cycles written to MP4 (GIFs loop forever)
+    pub mp4_loops: u32,
+
+    /// Path to ffmpeg for MP4 output (defaults to "ffmpeg" in PATH)
+    pub ffmpeg_path: Option<PathBuf>,
+}
+
+impl Default for WiggleConfig {
+    fn default() -> Self {
+        Self {
+            views: 6,
+            disparity_range: 24.0,
+            frame_delay_ms: 80,
+            max_width: 1080,
+            mp4_loops: 4,
+            ffmpeg_path: None,
+        }
+    }
+}
+
+impl WiggleConfig {
+    /// Horizontal shift of viewpoint `index`, from `-range/2` to `+range/2`
+    pub fn view_shift(&self, index: u32) -> f32 {
+        let views = self.views.max(2);
+        let t = index.min(views - 1) as f32 / (views - 1) as f32;
+        (t - 0.5) * self.disparity_range
+    }
+
+    /// Viewpoint indices for one back-and-forth cycle, without repeating the
+    /// turning points (e.g. 0 1 2 3 2 1 for four views)
+    pub fn cycle(&self) -> Vec<u32> {
+        let views = self.views.max(2);
+        (0..views).chain((1..views - 1).rev()).collect()
+    }
+}
+
+/// Render the wiggle frames for one back-and-forth cycle
+///
+/// Frames are scaled to `max_width` (keeping even dimensions for video
+/// encoders) before the views are synthesized.
+pub fn render_wiggle_frames(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    config: &WiggleConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<Vec<RgbImage>> {
+    let scale = (config.max_width.max(2) as f32 / image.width() as f32).min(1.0);
+    let width = (((image.width() as f32 * scale) as u32) & !1).max(2);
+    let height = (((image.height() as f32 * scale) as u32) & !1).max(2);
+    let source = image.resize_exact(width, height, image::imageops::FilterType::Lanczos3);
+    let shift_scale = width as f32 / image.width() as f32;
+
+    let views = (0..config.views.max(2))
+        .map(|index| {
+            cancel::check(cancel)?;
+            Ok(crate::stereo::synthesize_view(
+                &source,
+                depth,
+                config.view_shift(index) * shift_scale,
+                cancel,
+            )?
+            .to_rgb8())
+        })
+        .collect::<SpatialResult<Vec<_>>>()?;
+
+    Ok(config
+        .cycle()
+        .into_iter()
+        .map(|index| views[index as usize].clone())
+        .collect())
+}
+
+/// Render and save a wiggle animation as GIF or MP4 (by file extension)
+pub fn save_wiggle(
+    image: &DynamicImage,
+    depth: &Array2<f32>,
+    output_path: impl AsRef<Path>,
+    config: &WiggleConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    let output_path = output_path.as_ref();
+    let format = WiggleFormat::from_path(output_path);
+
+    tracing::info!(
+        "〰️ Rendering {}-view wiggle ({:?}) to {:?}",
+        config.views.max(2),
+        format,
+        output_path
+    );
+
+    let frames = render_wiggle_frames(image, depth, config, cancel)?;
+
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| SpatialError::io("Failed to create output directory", e))?;
+    }
+
+    match format {
+        WiggleFormat::Gif => write_gif(&frames, output_path, config, cancel),
+        WiggleFormat::Mp4 => write_mp4(&frames, output_path, config, cancel),
+    }
+}
+
+fn write_gif(
+    frames: &[RgbImage],
+    output_path: &Path,
+    config: &WiggleConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    let file = std::fs::File::create(output_path)
+        .map_err(|e| SpatialError::io("Failed to create output file", e))?;
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), 10);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|e| SpatialError::image("Failed to write GIF", e))?;
+
+    let delay = image::Delay::from_numer_denom_ms(config.frame_delay_ms.max(10), 1);
+    for frame in frames {
+        cancel::check(cancel)?;
+        let rgba = DynamicImage::ImageRgb8(frame.clone()).to_rgba8();
+        encoder
+            .encode_frame(Frame::from_parts(rgba, 0, 0, delay))
+            .map_err(|e| SpatialError::image("Failed to write GIF", e))?;
+    }
+    Ok(())
+}
+
+fn write_mp4(
+    frames: &[RgbImage],
+    output_path: &Path,
+    config: &WiggleConfig,
+    cancel: Option<&CancellationToken>,
+) -> SpatialResult<()> {
+    let Some(first) = frames.first() else {
+        return Err(SpatialError::ConfigError("No frames to encode".to_string()));
+    };
+    let ffmpeg_path = config
+        .ffmpeg_path
+        .as_deref()
+        .unwrap_or_else(|| Path::new("ffmpeg"));
+    let fps = 1000.0 / config.frame_delay_ms.max(10) as f32;
+
+    let mut child = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+        .arg("-s")
+        .arg(format!("{}x{}", first.width(), first.height()))
+        .arg("-framerate")
+        .arg(format!("{:.3}", fps))
+        .args(["-i", "-"])
+        .args([
+            "-c:v",
+            "libx264",
+            "-pix_fmt",
+            "yuv420p",
+            "-movflags",
+            "+faststart",
+        ])
+        .arg(output_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| SpatialError::io(format!("Failed to run {:?}", ffmpeg_path), e))?;
+
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    for _ in 0..config.mp4_loops.max(1) {
+        for frame in frames {
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                drop(stdin);
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = std::fs::remove_file(output_path);
+                return Err(SpatialError::Cancelled);
+            }
+            // A write error means ffmpeg exited; its stderr explains why
+            if stdin.write_all(frame.as_raw()).is_err() {
+                break;
+            }
+        }
+    }
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .map_err(|e| SpatialError::io("Failed to wait for ffmpeg", e))?;
+    if !status.success() {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        return Err(SpatialError::io(
+            "Wiggle MP4 encoding with ffmpeg failed",
+            std::io::Error::other(stderr.trim().to_string()),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_and_shifts() {
+        let config = WiggleConfig {
+            views: 4,
+            disparity_range: 30.0,
+            ..Default::default()
+        };
+        assert_eq!(config.cycle(), vec![0, 1, 2, 3, 2, 1]);
+        assert_eq!(config.view_shift(0), -15.0);
+        assert_eq!(config.view_shift(3), 15.0);
+
+        let two = WiggleConfig {
+            views: 1,
+            ..Default::default()
+        };
+        assert_eq!(two.cycle(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(WiggleFormat::from_path("a.MP4"), WiggleFormat::Mp4);
+        assert_eq!(WiggleFormat::from_path("a.gif"), WiggleFormat::Gif);
+        assert_eq!(WiggleFormat::from_path("a"), WiggleFormat::Gif);
+    }
+
+    #[test]
+    fn test_save_wiggle_gif() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("wiggle.gif");
+
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(41, 30, |x, _| {
+            image::Rgb([(x * 6) as u8, 0, 0])
+        }));
+        let depth = Array2::from_elem((30, 41), 0.5);
+        let config = WiggleConfig {
+            views: 3,
+            ..Default::default()
+        };
+        save_wiggle(&image, &depth, &output_path, &config, None).unwrap();
+
+        let file = std::fs::File::open(&output_path).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(file).unwrap();
+        let frames = image::AnimationDecoder::into_frames(decoder)
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 4);
+        // Odd widths are trimmed to even for video encoders
+        assert_eq!(frames[0].buffer().dimensions(), (40, 30));
+    }
+
+    #[test]
+    fn test_wiggle_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let image = DynamicImage::new_rgb8(8, 8);
+        let depth = Array2::from_elem((8, 8), 0.5);
+        let result = render_wiggle_frames(&image, &depth, &WiggleConfig::default(), Some(&token));
+        assert!(matches!(result, Err(SpatialError::Cancelled)));
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, command};
+use tauri_plugin_notification::NotificationExt;
+
+/// Queues that report when they finish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueKind {
+    Conversion,
+    Spatial,
+}
+
+/// Sound played when a queue finishes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum CompletionSound {
+    #[default]
+    Off,
+    /// One of the sounds listed by `list_completion_sounds`.
+    System { name: String },
+    /// An audio file the user picked.
+    File { path: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QueueAlerts {
+    pub notify: bool,
+    pub sound: CompletionSound,
+    /// Stay silent while the OS is in Do Not Disturb or a Focus mode.
+    pub respect_do_not_disturb: bool,
+}
+
+impl Default for QueueAlerts {
+    fn default() -> Self {
+        Self {
+            notify: true,
+            sound: CompletionSound::Off,
+            respect_do_not_disturb: true,
+        }
+    }
+}
+
+/// What a finished queue did to get the user's attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertOutcome {
+    pub notify: bool,
+    pub sound: bool,
+    pub do_not_disturb: bool,
+}
+
+impl QueueAlerts {
+    pub fn outcome(&self, do_not_disturb: bool) -> AlertOutcome {
+        let quiet = do_not_disturb && self.respect_do_not_disturb;
+        AlertOutcome {
+            notify: self.notify && !quiet,
+            sound: self.sound != CompletionSound::Off && !quiet,
+            do_not_disturb,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AlertCenter {
+    queues: Mutex<HashMap<QueueKind, QueueAlerts>>,
+}
+
+impl AlertCenter {
+    pub fn alerts(&self, queue: QueueKind) -> QueueAlerts {
+        self.queues
+            .lock()
+            .unwrap()
+            .get(&queue)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_alerts(&self, queue: QueueKind, alerts: QueueAlerts) {
+        self.queues.lock().unwrap().insert(queue, alerts);
+    }
+}
+
+#[cfg(target_os = "macos")]
+const SYSTEM_SOUND_EXTENSION: &str = "aiff";
+#[cfg(target_os = "windows")]
+const SYSTEM_SOUND_EXTENSION: &str = "wav";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const SYSTEM_SOUND_EXTENSION: &str = "oga";
+
+fn system_sounds_dir() -> PathBuf {
+    #[cfg(target_os = "macos")]
+    let dir = PathBuf::from("/System/Library/Sounds");
+    #[cfg(target_os = "windows")]
+    let dir = PathBuf::from(std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".into()))
+        .join("Media");
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let dir = PathBuf::from("/usr/share/sounds/freedesktop/stereo");
+    dir
+}
+
+/// Names of the sound files in `dir` with the given extension, sorted.
+pub fn sound_names(dir: &Path, extension: &str) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+        })
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// File to play for `sound`, if it names one that exists.
+pub fn resolve_sound(
+    sound: &CompletionSound,
+    sounds_dir: &Path,
+    extension: &str,
+) -> Option<PathBuf> {
+    let path = match sound {
+        CompletionSound::Off => return None,
+        // Names come from the frontend; never let one climb out of the directory
+        CompletionSound::System { name } if name.contains(['/', '\\']) || name.starts_with('.') => {
+            return None;
+        }
+        CompletionSound::System { name } => sounds_dir.join(format!("{}.{}", name, extension)),
+        CompletionSound::File { path } => PathBuf::from(path),
+    };
+    path.is_file().then_some(path)
+}
+
+fn player_command(path: &Path) -> tokio::process::Command {
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = tokio::process::Command::new("afplay");
+        cmd.arg(path);
+        cmd
+    }
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let script = format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.to_string_lossy().replace('\'', "''")
+        );
+        let mut cmd = tokio::process::Command::new("powershell");
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let mut cmd = tokio::process::Command::new("paplay");
+        cmd.arg(path);
+        cmd
+    }
+}
+
+fn play_sound(path: &Path) {
+    let mut cmd = player_command(path);
+    cmd.stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = cmd.status().await {
+            eprintln!("Failed to play completion sound: {}", e);
+        }
+    });
+}
+
+/// macOS keeps manually enabled Focus modes in
+/// `~/Library/DoNotDisturb/DB/Assertions.json`; any assertion record means
+/// one is on. Scheduled Focus modes are not listed there.
+#[cfg(any(target_os = "macos", test))]
+pub fn macos_focus_active(assertions_json: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(assertions_json)
+        .ok()
+        .and_then(|value| value.get("data")?.as_array().cloned())
+        .is_some_and(|data| {
+            data.iter().any(|entry| {
+                entry
+                    .get("storeAssertionRecords")
+                    .and_then(|records| records.as_array())
+                    .is_some_and(|records| !records.is_empty())
+            })
+        })
+}
+
+/// Windows turns toasts off globally while Do Not Disturb is on, reported by
+/// `reg query` as `NOC_GLOBAL_SETTING_TOASTS_ENABLED    REG_DWORD    0x0`.
+#[cfg(any(target_os = "windows", test))]
+pub fn windows_toasts_disabled(reg_output: &str) -> bool {
+    reg_output
+        .lines()
+        .find(|line| line.contains("NOC_GLOBAL_SETTING_TOASTS_ENABLED"))
+        .and_then(|line| line.split_whitespace().last())
+        .is_some_and(|value| value == "0x0")
+}
+
+/// GNOME hides notification banners while Do Not Disturb is on.
+#[cfg(any(not(any(target_os = "macos", target_os = "windows")), test))]
+pub fn gnome_banners_hidden(gsettings_output: &str) -> bool {
+    gsettings_output.trim() == "false"
+}
+
+pub(crate) async fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.args(args);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x0800_0000);
+    let output = cmd.output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether the OS is asking apps to stay quiet. Unknown counts as no.
+pub async fn do_not_disturb_active() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        let Some(home) = std::env::var_os("HOME") else {
+            return false;
+        };
+        let path = PathBuf::from(home).join("Library/DoNotDisturb/DB/Assertions.json");
+        tokio::fs::read_to_string(path)
+            .await
+            .is_ok_and(|json| macos_focus_active(&json))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        command_output(
+            "reg",
+            &[
+                "query",
+                "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Notifications\\Settings",
+                "/v",
+                "NOC_GLOBAL_SETTING_TOASTS_ENABLED",
+            ],
+        )
+        .await
+        .is_some_and(|out| windows_toasts_disabled(&out))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        command_output(
+            "gsettings",
+            &["get", "org.gnome.desktop.notifications", "show-banners"],
+        )
+        .await
+        .is_some_and(|out| gnome_banners_hidden(&out))
+    }
+}
+
+#[command]
+pub fn get_queue_alerts(center: tauri::State<'_, AlertCenter>, queue: QueueKind) -> QueueAlerts {
+    center.alerts(queue)
+}
+
+#[command]
+pub fn set_queue_alerts(
+    center: tauri::State<'_, AlertCenter>,
+    queue: QueueKind,
+    alerts: QueueAlerts,
+) -> Result<(), String> {
+    center.set_alerts(queue, alerts);
+    Ok(())
+}
+
+#[command]
+pub fn list_completion_sounds() -> Vec<String> {
+    sound_names(&system_sounds_dir(), SYSTEM_SOUND_EXTENSION)
+}
+
+#[command]
+pub async fn is_do_not_disturb() -> bool {
+    do_not_disturb_active().await
+}
+
+#[command]
+pub async fn play_completion_sound(sound: CompletionSound) -> Result<(), String> {
+    let path = resolve_sound(&sound, &system_sounds_dir(), SYSTEM_SOUND_EXTENSION)
+        .ok_or_else(|| "Sound not found".to_string())?;
+    play_sound(&path);
+    Ok(())
+}
+
+/// Announce a finished queue with its notification and sound, unless the
+/// queue's settings or Do Not Disturb say otherwise.
+#[command]
+pub async fn queue_finished(
+    app: AppHandle,
+    center: tauri::State<'_, AlertCenter>,
+    queue: QueueKind,
+    title: String,
+    body: String,
+) -> Result<AlertOutcome, String> {
+    let alerts = center.alerts(queue);
+    let do_not_disturb = alerts.respect_do_not_disturb && do_not_disturb_active().await;
+    let mut outcome = alerts.outcome(do_not_disturb);
+
+    if outcome.notify {
+        app.notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+            .map_err(|e| e.to_string())?;
+    }
+    if outcome.sound {
+        match resolve_sound(&alerts.sound, &system_sounds_dir(), SYSTEM_SOUND_EXTENSION) {
+            Some(path) => play_sound(&path),
+            None => outcome.sound = false,
+        }
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,273 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::window::Color;
+use tauri::{AppHandle, Emitter, Manager, Runtime, WebviewWindow, command};
+
+use crate::alerts::command_output;
+
+pub const APPEARANCE_CHANGED_EVENT: &str = "appearance-changed";
+
+/// Windows whose background follows the appearance settings.
+const EFFECT_WINDOWS: [&str; 2] = ["main", "splash"];
+
+/// OS accessibility settings that change how the app should draw itself.
+/// Settings the OS does not expose read as off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityPreferences {
+    pub reduce_transparency: bool,
+    pub increase_contrast: bool,
+    pub reduce_motion: bool,
+}
+
+/// How the window background is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowEffects {
+    /// Translucent unless the OS asks for less transparency or more contrast.
+    #[default]
+    Auto,
+    /// HUD vibrancy on macOS, Acrylic on Windows.
+    Translucent,
+    /// An opaque background with no blur.
+    Solid,
+}
+
+impl WindowEffects {
+    pub fn translucent(self, preferences: &AccessibilityPreferences) -> bool {
+        match self {
+            WindowEffects::Auto => {
+                !(preferences.reduce_transparency || preferences.increase_contrast)
+            }
+            WindowEffects::Translucent => true,
+            WindowEffects::Solid => false,
+        }
+    }
+}
+
+/// What the frontend needs to match the window: the chosen mode, what the
+/// OS asked for, and whether the window ended up translucent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppearanceState {
+    pub effects: WindowEffects,
+    pub preferences: AccessibilityPreferences,
+    pub translucent: bool,
+}
+
+#[derive(Default)]
+pub struct Appearance {
+    effects: Mutex<WindowEffects>,
+    preferences: Mutex<AccessibilityPreferences>,
+}
+
+impl Appearance {
+    pub fn state(&self) -> AppearanceState {
+        let effects = *self.effects.lock().unwrap();
+        let preferences = *self.preferences.lock().unwrap();
+        AppearanceState {
+            effects,
+            preferences,
+            translucent: effects.translucent(&preferences),
+        }
+    }
+
+    pub fn set_effects(&self, effects: WindowEffects) {
+        *self.effects.lock().unwrap() = effects;
+    }
+
+    /// Stores freshly read OS settings; true if they changed.
+    pub fn set_preferences(&self, preferences: AccessibilityPreferences) -> bool {
+        let mut current = self.preferences.lock().unwrap();
+        let changed = *current != preferences;
+        *current = preferences;
+        changed
+    }
+}
+
+/// `defaults read` prints booleans as `1` or `0`.
+#[cfg(any(target_os = "macos", test))]
+pub fn defaults_flag(defaults_output: &str) -> bool {
+    defaults_output.trim() == "1"
+}
+
+/// The data of value `name` in `reg query` output, e.g. `0x0` from
+/// `EnableTransparency    REG_DWORD    0x0`.
+#[cfg(any(target_os = "windows", test))]
+pub fn reg_value<'a>(reg_output: &'a str, name: &str) -> Option<&'a str> {
+    reg_output
+        .lines()
+        .map(str::split_whitespace)
+        .find_map(|mut fields| {
+            (fields.next() == Some(name))
+                .then(|| fields.nth(1))
+                .flatten()
+        })
+}
+
+/// High Contrast keeps its state in bit 0 of a decimal `Flags` string.
+#[cfg(any(target_os = "windows", test))]
+pub fn windows_high_contrast_on(reg_output: &str) -> bool {
+    reg_value(reg_output, "Flags")
+        .and_then(|flags| flags.parse::<u32>().ok())
+        .is_some_and(|flags| flags & 1 != 0)
+}
+
+/// `gsettings get` prints booleans as `true` or `false`.
+#[cfg(any(not(any(target_os = "macos", target_os = "windows")), test))]
+pub fn gsettings_flag(gsettings_output: &str) -> Option<bool> {
+    match gsettings_output.trim() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+async fn reg_query(key: &str, name: &str) -> Option<String> {
+    command_output("reg", &["query", key, "/v", name]).await
+}
+
+/// Reads the OS accessibility settings. Unknown counts as off.
+pub async fn read_accessibility_preferences() -> AccessibilityPreferences {
+    #[cfg(target_os = "macos")]
+    {
+        let flag = |key: &'static str| async move {
+            command_output("defaults", &["read", "com.apple.universalaccess", key])
+                .await
+                .is_some_and(|out| defaults_flag(&out))
+        };
+        AccessibilityPreferences {
+            reduce_transparency: flag("reduceTransparency").await,
+            increase_contrast: flag("increaseContrast").await,
+            reduce_motion: flag("reduceMotion").await,
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let transparency = reg_query(
+            "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
+            "EnableTransparency",
+        )
+        .await;
+        let high_contrast =
+            reg_query("HKCU\\Control Panel\\Accessibility\\HighContrast", "Flags").await;
+        let animations =
+            reg_query("HKCU\\Control Panel\\Desktop\\WindowMetrics", "MinAnimate").await;
+        AccessibilityPreferences {
+            reduce_transparency: transparency
+                .as_deref()
+                .and_then(|out| reg_value(out, "EnableTransparency"))
+                == Some("0x0"),
+            increase_contrast: high_contrast
+                .as_deref()
+                .is_some_and(windows_high_contrast_on),
+            reduce_motion: animations
+                .as_deref()
+                .and_then(|out| reg_value(out, "MinAnimate"))
+                == Some("0"),
+        }
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let flag = |schema: &'static str, key: &'static str| async move {
+            command_output("gsettings", &["get", schema, key])
+                .await
+                .and_then(|out| gsettings_flag(&out))
+        };
+        let high_contrast = flag("org.gnome.desktop.a11y.interface", "high-contrast")
+            .await
+            .unwrap_or(false);
+        AccessibilityPreferences {
+            // GNOME has no transparency setting; High Contrast implies it
+            reduce_transparency: high_contrast,
+            increase_contrast: high_contrast,
+            reduce_motion: flag("org.gnome.desktop.interface", "enable-animations").await
+                == Some(false),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn apply_translucent_effect<R: Runtime>(window: &WebviewWindow<R>) {
+    use tauri::window::{Effect, EffectState, EffectsBuilder};
+
+    window
+        .set_effects(
+            EffectsBuilder::new()
+                .effect(Effect::HudWindow)
+                .state(EffectState::Active)
+                .radius(16.0)
+                .build(),
+        )
+        .expect("Unsupported platform! 'HudWindow' effect is only supported on macOS");
+}
+
+#[cfg(target_os = "windows")]
+fn apply_translucent_effect<R: Runtime>(window: &WebviewWindow<R>) {
+    use tauri::window::{Effect, EffectsBuilder};
+
+    window
+        .set_effects(EffectsBuilder::new().effect(Effect::Acrylic).build())
+        .expect("Unsupported platform! 'Acrylic' effect is only supported on Windows");
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn apply_translucent_effect<R: Runtime>(_window: &WebviewWindow<R>) {}
+
+/// Draws `window` translucent or solid, as the appearance settings say.
+pub fn apply_window_effect<R: Runtime>(window: &WebviewWindow<R>) {
+    let translucent = window.state::<Appearance>().state().translucent;
+    if translucent {
+        apply_translucent_effect(window);
+        let _ = window.set_background_color(Some(Color(0, 0, 0, 0)));
+    } else {
+        // The page paints its own opaque background once told (see
+        // APPEARANCE_CHANGED_EVENT); black avoids a flash of the desktop
+        let _ = window.set_effects(None::<tauri::utils::config::WindowEffectsConfig>);
+        let _ = window.set_background_color(Some(Color(0, 0, 0, 255)));
+    }
+}
+
+/// Reapplies the window effects and tells the frontend.
+fn apply_appearance<R: Runtime>(app: &AppHandle<R>) -> AppearanceState {
+    for label in EFFECT_WINDOWS {
+        if let Some(window) = app.get_webview_window(label) {
+            apply_window_effect(&window);
+        }
+    }
+    let state = app.state::<Appearance>().state();
+    let _ = app.emit(APPEARANCE_CHANGED_EVENT, state);
+    state
+}
+
+/// Rereads the OS settings and, if they changed, redraws the windows to
+/// match. Run when a window gains focus, as the OS sends no notice.
+pub async fn refresh_accessibility_preferences<R: Runtime>(app: &AppHandle<R>) {
+    let preferences = read_accessibility_preferences().await;
+    if app.state::<Appearance>().set_preferences(preferences) {
+        apply_appearance(app);
+    }
+}
+
+#[command]
+pub async fn get_accessibility_preferences(app: AppHandle) -> AccessibilityPreferences {
+    refresh_accessibility_preferences(&app).await;
+    app.state::<Appearance>().state().preferences
+}
+
+#[command]
+pub fn get_appearance(appearance: tauri::State<'_, Appearance>) -> AppearanceState {
+    appearance.state()
+}
+
+#[command]
+pub fn set_window_effects(
+    app: AppHandle,
+    appearance: tauri::State<'_, Appearance>,
+    effects: WindowEffects,
+) -> AppearanceState {
+    appearance.set_effects(effects);
+    apply_appearance(&app)
+}
@@ -3,6 +3,8 @@ use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Manager, command};
 use tauri_plugin_shell::ShellExt;
 
+use crate::conversion::gpu::{GpuDevice, list_gpu_devices};
+
 #[derive(serde::Serialize, Clone, Debug)]
 pub struct AvailableEncoders {
     pub h264_videotoolbox: bool,
@@ -67,3 +69,8 @@ pub async fn get_available_encoders(app: AppHandle) -> Result<AvailableEncoders,
         ml_upscale,
     })
 }
+
+#[command]
+pub async fn get_gpu_devices(app: AppHandle) -> Result<Vec<GpuDevice>, String> {
+    Ok(list_gpu_devices(&app).await)
+}
@@ -1,19 +1,27 @@
 use std::path::{Path, PathBuf};
 
 use crate::conversion::codec::{
-    add_audio_codec_args, add_fps_args, add_subtitle_codec_args, add_video_codec_args,
+    add_audio_codec_args, add_fps_args, add_image_codec_args, add_subtitle_codec_args,
+    add_video_codec_args,
 };
 use crate::conversion::error::ConversionError;
 use crate::conversion::filters::{build_audio_filters, build_video_filters};
 use crate::conversion::types::{ConversionConfig, MetadataConfig, MetadataMode};
-use crate::conversion::utils::{get_hwaccel_args, is_audio_only_container, parse_time};
+use crate::conversion::utils::{
+    get_hwaccel_args, is_audio_only_container, is_image_container, is_nvenc_codec, parse_time,
+};
 
 pub fn build_ffmpeg_args(input: &str, output: &str, config: &ConversionConfig) -> Vec<String> {
     let mut args = Vec::new();
+    let is_image = is_image_container(&config.container);
 
     // Hardware decode acceleration (must be before -i)
-    if config.hw_decode {
+    if config.hw_decode && !is_image {
         args.extend(get_hwaccel_args(&config.video_codec));
+        if let Some(gpu) = config.nvenc_gpu_index.filter(|_| is_nvenc_codec(&config.video_codec)) {
+            args.push("-hwaccel_device".to_string());
+            args.push(gpu.to_string());
+        }
     }
 
     if let Some(start) = &config.start_time {
@@ -71,6 +79,29 @@ pub fn build_ffmpeg_args(input: &str, output: &str, config: &ConversionConfig) -
         .as_ref()
         .is_some_and(|path| !path.trim().is_empty());
 
+    if is_image {
+        // One still from the first video stream; for video inputs this is the
+        // frame at the start time
+        add_image_codec_args(&mut args, config);
+
+        let video_filters = build_video_filters(config, true);
+        if !video_filters.is_empty() {
+            args.push("-vf".to_string());
+            args.push(video_filters.join(","));
+        }
+
+        args.push("-map".to_string());
+        args.push("0:v:0".to_string());
+        args.push("-frames:v".to_string());
+        args.push("1".to_string());
+        args.push("-update".to_string());
+        args.push("1".to_string());
+
+        args.push("-y".to_string());
+        args.push(output.to_string());
+        return args;
+    }
+
     if is_audio_only {
         args.push("-vn".to_string());
 
@@ -174,7 +205,7 @@ pub fn add_metadata_flags(args: &mut Vec<String>, metadata: &MetadataConfig) {
     }
 }
 
-fn sanitize_output_name(raw: &str) -> Option<String> {
+pub(crate) fn sanitize_output_name(raw: &str) -> Option<String> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
         return None;
@@ -283,7 +314,12 @@ pub fn validate_task_input(
         }
     }
 
-    if config.video_bitrate_mode == "bitrate" && !is_audio_only_container(&config.container) {
+    let is_image = is_image_container(&config.container);
+
+    if config.video_bitrate_mode == "bitrate"
+        && !is_audio_only_container(&config.container)
+        && !is_image
+    {
         let bitrate = config.video_bitrate.parse::<f64>().map_err(|_| {
             ConversionError::InvalidInput(format!(
                 "Invalid video bitrate: {}",
@@ -298,14 +334,17 @@ pub fn validate_task_input(
     }
 
     let is_audio_only = is_audio_only_container(&config.container);
-    if !is_audio_only && !is_video_codec_allowed(&config.container, &config.video_codec) {
+    if !is_audio_only
+        && !is_image
+        && !is_video_codec_allowed(&config.container, &config.video_codec)
+    {
         return Err(ConversionError::InvalidInput(format!(
             "Video codec '{}' is not compatible with container '{}'",
             config.video_codec, config.container
         )));
     }
 
-    if !is_audio_codec_allowed(&config.container, &config.audio_codec) {
+    if !is_image && !is_audio_codec_allowed(&config.container, &config.audio_codec) {
         return Err(ConversionError::InvalidInput(format!(
             "Audio codec '{}' is not compatible with container '{}'",
             config.audio_codec, config.container
@@ -332,6 +371,19 @@ pub fn validate_task_input(
         ));
     }
 
+    if is_image && has_ml_upscale {
+        return Err(ConversionError::InvalidInput(
+            "ML upscaling is not available for image outputs".to_string(),
+        ));
+    }
+
+    if is_image && config.quality > 100 {
+        return Err(ConversionError::InvalidInput(format!(
+            "Image quality must be between 0 and 100: {}",
+            config.quality
+        )));
+    }
+
     if is_audio_only
         && (!config.selected_subtitle_tracks.is_empty()
             || config
@@ -46,6 +46,10 @@ pub fn add_video_codec_args(args: &mut Vec<String>, config: &ConversionConfig) {
             args.push("-temporal_aq".to_string());
             args.push("1".to_string());
         }
+        if let Some(gpu) = config.nvenc_gpu_index {
+            args.push("-gpu".to_string());
+            args.push(gpu.to_string());
+        }
     }
 
     if is_videotoolbox && config.videotoolbox_allow_sw {
@@ -54,6 +58,48 @@ pub fn add_video_codec_args(args: &mut Vec<String>, config: &ConversionConfig) {
     }
 }
 
+/// Encoder and quality flags for a single-image output
+///
+/// `quality` (0-100, higher is better) is mapped onto each encoder's own
+/// scale; PNG is lossless and ignores it.
+pub fn add_image_codec_args(args: &mut Vec<String>, config: &ConversionConfig) {
+    let quality = config.quality.min(100) as f64;
+
+    match config.container.to_lowercase().as_str() {
+        "jpg" | "jpeg" => {
+            let q = (31.0 - quality * 29.0 / 100.0).round() as u32;
+            args.push("-c:v".to_string());
+            args.push("mjpeg".to_string());
+            args.push("-q:v".to_string());
+            args.push(q.to_string());
+            args.push("-pix_fmt".to_string());
+            args.push("yuvj444p".to_string());
+        }
+        "png" => {
+            args.push("-c:v".to_string());
+            args.push("png".to_string());
+        }
+        "webp" => {
+            args.push("-c:v".to_string());
+            args.push("libwebp".to_string());
+            args.push("-quality".to_string());
+            args.push((quality.round() as u32).to_string());
+        }
+        "avif" => {
+            let crf = (63.0 - quality * 0.63).round() as u32;
+            args.push("-c:v".to_string());
+            args.push("libaom-av1".to_string());
+            args.push("-still-picture".to_string());
+            args.push("1".to_string());
+            args.push("-crf".to_string());
+            args.push(crf.to_string());
+            args.push("-pix_fmt".to_string());
+            args.push("yuv420p".to_string());
+        }
+        _ => {}
+    }
+}
+
 pub fn add_audio_codec_args(args: &mut Vec<String>, config: &ConversionConfig) {
     args.push("-c:a".to_string());
     args.push(config.audio_codec.clone());
@@ -1,10 +1,27 @@
-use tauri::{AppHandle, command};
+use std::path::Path;
+
+use tauri::{AppHandle, Emitter, command};
+use tauri_plugin_shell::ShellExt;
 
 use crate::conversion::args::validate_task_input;
+use crate::conversion::contact_sheet::{
+    build_contact_sheet_args, build_contact_sheet_path, format_duration,
+    validate_contact_sheet_options,
+};
+use crate::conversion::dedupe::job_key;
 use crate::conversion::error::ConversionError;
+use crate::conversion::history::{
+    CodecSpeed, DAY_SECS, FailureBucket, HistorySummary, JobHistory, codec_speeds,
+    failure_timeline, summarize,
+};
 use crate::conversion::manager::{ConversionManager, ManagerMessage};
 use crate::conversion::probe::probe_media_file;
-use crate::conversion::types::{ConversionConfig, ConversionTask, ProbeMetadata};
+use crate::conversion::sidecar::sidecar_error;
+use crate::conversion::types::{
+    ContactSheetOptions, ConversionConfig, ConversionTask, DuplicateAction, DuplicatePolicy,
+    LogPayload, ProbeMetadata,
+};
+use crate::conversion::utils::{collect_image_files, parse_time};
 
 #[command]
 pub async fn queue_conversion(
@@ -25,6 +42,25 @@ pub async fn queue_conversion(
         crate::conversion::upscale::validate_upscale_runtime(&app, mode).await?;
     }
 
+    let policy = manager.duplicate_policy();
+    if policy.action != DuplicateAction::Off {
+        let (path, job_config) = (file_path.clone(), config.clone());
+        let key = tauri::async_runtime::spawn_blocking(move || {
+            job_key(&path, &job_config, policy.identity)
+        })
+        .await
+        .map_err(|e| ConversionError::Worker(e.to_string()))??;
+        if let Some(original) = manager.check_duplicate(&id, &file_path, key, policy.action)? {
+            let _ = app.emit(
+                "conversion-log",
+                LogPayload {
+                    id: id.clone(),
+                    line: format!("[QUEUE] Same file and settings as {}", original),
+                },
+            );
+        }
+    }
+
     let task = ConversionTask {
         id,
         file_path,
@@ -64,6 +100,15 @@ pub async fn cancel_conversion(
     manager.cancel_task(&id)
 }
 
+#[command]
+pub async fn relink_conversion_source(
+    manager: tauri::State<'_, ConversionManager>,
+    id: String,
+    file_path: String,
+) -> Result<(), ConversionError> {
+    manager.relink_task(&id, &file_path).await
+}
+
 #[command]
 pub async fn probe_media(
     app: AppHandle,
@@ -72,6 +117,107 @@ pub async fn probe_media(
     probe_media_file(&app, &file_path).await
 }
 
+/// Image files in a folder, for queueing a batch of image conversions
+#[command]
+pub async fn list_image_files(
+    folder: String,
+    recursive: Option<bool>,
+) -> Result<Vec<String>, ConversionError> {
+    let dir = Path::new(&folder);
+    if !dir.is_dir() {
+        return Err(ConversionError::InvalidInput(format!(
+            "Not a folder: {}",
+            folder
+        )));
+    }
+    let files = collect_image_files(dir, recursive.unwrap_or(false))?;
+    Ok(files
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Render a grid of timestamped thumbnails for a video with one ffmpeg run,
+/// returning the image path
+#[command]
+pub async fn generate_contact_sheet(
+    app: AppHandle,
+    file_path: String,
+    options: Option<ContactSheetOptions>,
+) -> Result<String, ConversionError> {
+    let options = options.unwrap_or_default();
+    validate_contact_sheet_options(&options)?;
+    if !Path::new(&file_path).is_file() {
+        return Err(ConversionError::InvalidInput(format!(
+            "Input file does not exist: {}",
+            file_path
+        )));
+    }
+
+    let metadata = probe_media_file(&app, &file_path).await?;
+    if metadata.video_codec.is_none() {
+        return Err(ConversionError::InvalidInput(
+            "Contact sheets need a video stream".to_string(),
+        ));
+    }
+    let duration = metadata
+        .duration
+        .as_deref()
+        .and_then(parse_time)
+        .filter(|d| *d > 0.0)
+        .ok_or_else(|| {
+            ConversionError::InvalidInput("Could not determine video duration".to_string())
+        })?;
+
+    let file_name = Path::new(&file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file_path.clone());
+    let mut header = format!("{}  |  {}", file_name, format_duration(duration));
+    if let Some(resolution) = &metadata.resolution {
+        header.push_str(&format!("  |  {}", resolution));
+    }
+
+    let output_path = options
+        .output_path
+        .clone()
+        .filter(|path| !path.trim().is_empty())
+        .unwrap_or_else(|| build_contact_sheet_path(&file_path));
+    let args = build_contact_sheet_args(&file_path, &output_path, duration, &header, &options);
+
+    let output = app
+        .shell()
+        .sidecar("ffmpeg")
+        .map_err(|e| sidecar_error("ffmpeg", e))?
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| sidecar_error("ffmpeg", e))?;
+
+    if !output.status.success() {
+        return Err(ConversionError::Worker(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(output_path)
+}
+
+#[command]
+pub fn get_duplicate_policy(
+    manager: tauri::State<'_, ConversionManager>,
+) -> Result<DuplicatePolicy, ConversionError> {
+    Ok(manager.duplicate_policy())
+}
+
+#[command]
+pub fn set_duplicate_policy(
+    manager: tauri::State<'_, ConversionManager>,
+    policy: DuplicatePolicy,
+) -> Result<(), ConversionError> {
+    manager.set_duplicate_policy(policy);
+    Ok(())
+}
+
 #[command]
 pub fn get_max_concurrency(
     manager: tauri::State<'_, ConversionManager>,
@@ -86,3 +232,40 @@ pub fn set_max_concurrency(
 ) -> Result<(), ConversionError> {
     manager.update_max_concurrency(value)
 }
+
+/// Totals over conversions finished since `since` (Unix seconds), or ever.
+#[command]
+pub fn get_history_summary(
+    history: tauri::State<'_, JobHistory>,
+    since: Option<u64>,
+) -> Result<HistorySummary, ConversionError> {
+    Ok(summarize(&history.entries_since(since)))
+}
+
+#[command]
+pub fn get_codec_speeds(
+    history: tauri::State<'_, JobHistory>,
+    since: Option<u64>,
+) -> Result<Vec<CodecSpeed>, ConversionError> {
+    Ok(codec_speeds(&history.entries_since(since)))
+}
+
+/// Failures by error class, bucketed by `bucket_secs` (one day by default).
+#[command]
+pub fn get_failure_timeline(
+    history: tauri::State<'_, JobHistory>,
+    since: Option<u64>,
+    bucket_secs: Option<u64>,
+) -> Result<Vec<FailureBucket>, ConversionError> {
+    Ok(failure_timeline(
+        &history.entries_since(since),
+        bucket_secs.unwrap_or(DAY_SECS),
+    ))
+}
+
+#[command]
+pub fn clear_conversion_history(
+    history: tauri::State<'_, JobHistory>,
+) -> Result<(), ConversionError> {
+    history.clear()
+}
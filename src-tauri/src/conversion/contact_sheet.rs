@@ -0,0 +1,117 @@
+use crate::conversion::error::ConversionError;
+use crate::conversion::types::ContactSheetOptions;
+
+const HEADER_HEIGHT: u32 = 48;
+const TILE_PADDING: u32 = 4;
+
+/// Escape text for a `drawtext` option inside a filtergraph
+///
+/// The value is unescaped twice: once by the filtergraph parser and once by
+/// the filter's option parser.
+pub(crate) fn escape_drawtext(text: &str) -> String {
+    let escape = |value: &str, special: &[char]| {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            if special.contains(&c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+        escaped
+    };
+    let cleaned: String = text.chars().filter(|c| !c.is_control()).collect();
+    let option_level = escape(&cleaned, &['\\', '\'', ':']);
+    escape(&option_level, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+pub(crate) fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}",
+        total / 3600,
+        (total % 3600) / 60,
+        total % 60
+    )
+}
+
+pub(crate) fn build_contact_sheet_path(file_path: &str) -> String {
+    format!("{}_contact_sheet.jpg", file_path)
+}
+
+pub(crate) fn validate_contact_sheet_options(
+    options: &ContactSheetOptions,
+) -> Result<(), ConversionError> {
+    if !(1..=12).contains(&options.columns) || !(1..=20).contains(&options.rows) {
+        return Err(ConversionError::InvalidInput(format!(
+            "Contact sheet grid must be 1-12 columns by 1-20 rows, got {}x{}",
+            options.columns, options.rows
+        )));
+    }
+    if !(64..=1920).contains(&options.thumb_width) {
+        return Err(ConversionError::InvalidInput(format!(
+            "Thumbnail width must be between 64 and 1920 pixels, got {}",
+            options.thumb_width
+        )));
+    }
+    Ok(())
+}
+
+/// Arguments for a single ffmpeg run that samples evenly spaced frames,
+/// tiles them into a grid, and adds a header line
+///
+/// `header` is drawn verbatim (no `%{...}` expansion).
+pub(crate) fn build_contact_sheet_args(
+    input: &str,
+    output: &str,
+    duration: f64,
+    header: &str,
+    options: &ContactSheetOptions,
+) -> Vec<String> {
+    let count = options.columns * options.rows;
+    let mut filters = vec![
+        // One frame per grid cell, sampled across the whole duration
+        format!("fps={}/{:.3}", count, duration.max(0.001)),
+        format!("scale={}:-2", options.thumb_width),
+    ];
+
+    if options.show_timestamps {
+        filters.push(format!(
+            "drawtext=text={}:x=w-tw-6:y=h-th-6:fontsize=14:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=4",
+            escape_drawtext("%{pts:hms}")
+        ));
+    }
+
+    filters.push(format!(
+        "tile={}x{}:padding={pad}:margin={pad}:color=black",
+        options.columns,
+        options.rows,
+        pad = TILE_PADDING
+    ));
+    filters.push(format!(
+        "pad=iw:ih+{h}:0:{h}:color=black",
+        h = HEADER_HEIGHT
+    ));
+    filters.push(format!(
+        "drawtext=text={}:expansion=none:x=12:y=({}-th)/2:fontsize=20:fontcolor=white",
+        escape_drawtext(header),
+        HEADER_HEIGHT
+    ));
+
+    vec![
+        "-hide_banner".to_string(),
+        "-i".to_string(),
+        input.to_string(),
+        "-an".to_string(),
+        "-sn".to_string(),
+        "-vf".to_string(),
+        filters.join(","),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-update".to_string(),
+        "1".to_string(),
+        "-q:v".to_string(),
+        "3".to_string(),
+        "-y".to_string(),
+        output.to_string(),
+    ]
+}
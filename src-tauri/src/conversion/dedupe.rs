@@ -0,0 +1,140 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::conversion::types::{ConversionConfig, SourceIdentity};
+
+/// Finished jobs remembered for duplicate detection
+pub const RECENT_JOB_LIMIT: usize = 200;
+
+/// Bytes read from each of the start, middle, and end of a file for
+/// `SourceIdentity::ContentHash`
+const CONTENT_SAMPLE_BYTES: u64 = 1024 * 1024;
+
+/// Identifies the work a job does: which source, converted with which settings
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JobKey {
+    pub source: String,
+    pub config: u64,
+}
+
+pub fn job_key(
+    file_path: &str,
+    config: &ConversionConfig,
+    identity: SourceIdentity,
+) -> io::Result<JobKey> {
+    Ok(JobKey {
+        source: source_fingerprint(Path::new(file_path), identity)?,
+        config: config_fingerprint(config),
+    })
+}
+
+/// Output-affecting settings, hashed. The output name is not part of it:
+/// the same file with the same settings under another name is still the
+/// same conversion.
+pub fn config_fingerprint(config: &ConversionConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(config)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn source_fingerprint(path: &Path, identity: SourceIdentity) -> io::Result<String> {
+    let metadata = std::fs::metadata(path)?;
+    match identity {
+        SourceIdentity::PathAndMtime => {
+            let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            Ok(format!(
+                "path:{}:{}:{}",
+                path.to_string_lossy(),
+                metadata.len(),
+                modified
+            ))
+        }
+        SourceIdentity::ContentHash => {
+            let hash = sampled_content_hash(path, metadata.len())?;
+            Ok(format!("content:{}:{:016x}", metadata.len(), hash))
+        }
+    }
+}
+
+/// Hashes small files whole and large ones by three samples, so queueing a
+/// multi-gigabyte video does not mean reading all of it
+fn sampled_content_hash(path: &Path, len: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = Vec::new();
+
+    if len <= CONTENT_SAMPLE_BYTES * 3 {
+        file.read_to_end(&mut buffer)?;
+        buffer.hash(&mut hasher);
+        return Ok(hasher.finish());
+    }
+
+    let middle = len / 2 - CONTENT_SAMPLE_BYTES / 2;
+    for offset in [0, middle, len - CONTENT_SAMPLE_BYTES] {
+        file.seek(SeekFrom::Start(offset))?;
+        buffer.clear();
+        (&mut file)
+            .take(CONTENT_SAMPLE_BYTES)
+            .read_to_end(&mut buffer)?;
+        buffer.hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+/// A task doing some job, by id and source path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobEntry {
+    pub id: String,
+    pub file_path: String,
+    pub key: JobKey,
+}
+
+/// Job keys of queued and running tasks, plus recently finished ones
+#[derive(Default)]
+pub struct DuplicateRegistry {
+    active: HashMap<String, JobEntry>,
+    recent: VecDeque<JobEntry>,
+}
+
+impl DuplicateRegistry {
+    /// Task already doing this work, other than `id` itself (a retry)
+    pub fn find(&self, id: &str, key: &JobKey) -> Option<&JobEntry> {
+        let matches = |entry: &&JobEntry| entry.id != id && entry.key == *key;
+        self.active
+            .values()
+            .find(matches)
+            .or_else(|| self.recent.iter().rev().find(matches))
+    }
+
+    pub fn register(&mut self, entry: JobEntry) {
+        self.recent.retain(|other| other.id != entry.id);
+        self.active.insert(entry.id.clone(), entry);
+    }
+
+    /// Moves a successful task to the recent history; failed and cancelled
+    /// tasks are forgotten so they can be queued again.
+    pub fn finish(&mut self, id: &str, succeeded: bool) {
+        let Some(entry) = self.active.remove(id) else {
+            return;
+        };
+        if succeeded {
+            self.recent.push_back(entry);
+            while self.recent.len() > RECENT_JOB_LIMIT {
+                self.recent.pop_front();
+            }
+        }
+    }
+}
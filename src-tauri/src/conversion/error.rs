@@ -1,6 +1,9 @@
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::conversion::sidecar::SidecarIssue;
+use crate::watchdog::{WorkerFault, format_limit};
+
 #[derive(Debug, Error)]
 pub enum ConversionError {
     #[error("Shell command failed: {0}")]
@@ -19,6 +22,23 @@ pub enum ConversionError {
     InvalidInput(String),
     #[error("Task not found: {0}")]
     TaskNotFound(String),
+    #[error("Same file and settings as {0}, skipped")]
+    Duplicate(String),
+    #[error("Bundled tool unavailable: {0}")]
+    SidecarUnavailable(SidecarIssue),
+    #[error("{0}")]
+    Fault(WorkerFault),
+    #[error("Timed out: stopped at the {} time limit", format_limit(*.0))]
+    TimedOut(u64),
+}
+
+impl From<WorkerFault> for ConversionError {
+    fn from(fault: WorkerFault) -> Self {
+        match fault {
+            WorkerFault::TimedOut { limit_secs } => ConversionError::TimedOut(limit_secs),
+            fault => ConversionError::Fault(fault),
+        }
+    }
 }
 
 impl Serialize for ConversionError {
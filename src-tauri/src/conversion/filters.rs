@@ -103,40 +103,7 @@ mod tests {
     use crate::conversion::types::CropConfig;
 
     fn default_config() -> ConversionConfig {
-        ConversionConfig {
-            container: "mp4".to_string(),
-            video_codec: "libx264".to_string(),
-            video_bitrate_mode: "crf".to_string(),
-            video_bitrate: "5000".to_string(),
-            audio_codec: "aac".to_string(),
-            audio_bitrate: "192".to_string(),
-            audio_channels: "original".to_string(),
-            audio_volume: 100.0,
-            audio_normalize: false,
-            selected_audio_tracks: vec![],
-            selected_subtitle_tracks: vec![],
-            subtitle_burn_path: None,
-            resolution: "original".to_string(),
-            custom_width: None,
-            custom_height: None,
-            scaling_algorithm: "lanczos".to_string(),
-            fps: "original".to_string(),
-            crf: 23,
-            quality: 50,
-            preset: "medium".to_string(),
-            start_time: None,
-            end_time: None,
-            metadata: Default::default(),
-            rotation: "0".to_string(),
-            flip_horizontal: false,
-            flip_vertical: false,
-            ml_upscale: None,
-            crop: None,
-            nvenc_spatial_aq: false,
-            nvenc_temporal_aq: false,
-            videotoolbox_allow_sw: false,
-            hw_decode: false,
-        }
+        ConversionConfig::default()
     }
 
     #[test]
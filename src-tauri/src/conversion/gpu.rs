@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tauri::AppHandle;
+use tauri_plugin_shell::ShellExt;
+use tokio::process::Command;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const MIB: u64 = 1024 * 1024;
+
+/// Memory of the GPU the upscaler runs on (device 0).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GpuMemory {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    /// Where the numbers came from, for logs and error messages.
+    pub source: &'static str,
+}
+
+/// A GPU the upscaler and encoders can be pointed at.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuDevice {
+    /// Vulkan device index, as taken by the upscaler's `-g`.
+    pub index: u32,
+    pub name: String,
+    /// "discrete", "integrated", "virtual", or "unknown".
+    pub kind: String,
+    /// CUDA index for NVENC's `-gpu`, for NVIDIA devices.
+    pub nvenc_index: Option<u32>,
+}
+
+static VULKAN_GPU_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\[AVHWDeviceContext[^\]]*\]\s+(\d+): (.+?) \((\w+)\)").unwrap()
+});
+
+static NVIDIA_SMI_LIST_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^GPU (\d+): (.+?) \(UUID").unwrap());
+
+/// Enumerates GPUs in Vulkan order (the order the upscaler uses) from the
+/// ffmpeg sidecar's device listing, falling back to `nvidia-smi -L`.
+pub(crate) async fn list_gpu_devices(app: &AppHandle) -> Vec<GpuDevice> {
+    // Asking for an out-of-range device makes ffmpeg log every GPU it sees
+    let listing = match app.shell().sidecar("ffmpeg") {
+        Ok(cmd) => cmd
+            .args([
+                "-hide_banner",
+                "-v",
+                "verbose",
+                "-init_hw_device",
+                "vulkan=probe:999",
+            ])
+            .output()
+            .await
+            .ok()
+            .map(|output| String::from_utf8_lossy(&output.stderr).into_owned()),
+        Err(_) => None,
+    };
+
+    let devices = listing
+        .as_deref()
+        .map(parse_vulkan_gpu_listing)
+        .unwrap_or_default();
+    if !devices.is_empty() {
+        return devices;
+    }
+
+    match run_probe("nvidia-smi", &["-L"]).await {
+        Some(stdout) => parse_nvidia_smi_list(&stdout),
+        None => Vec::new(),
+    }
+}
+
+/// Parses ffmpeg's Vulkan "GPU listing" log. Software rasterizers are
+/// skipped; NVIDIA devices get CUDA indices in listing order.
+pub(crate) fn parse_vulkan_gpu_listing(stderr: &str) -> Vec<GpuDevice> {
+    let mut nvidia_count = 0;
+    VULKAN_GPU_REGEX
+        .captures_iter(stderr)
+        .filter_map(|caps| {
+            let index = caps[1].parse::<u32>().ok()?;
+            let name = caps[2].trim().to_string();
+            let kind = caps[3].to_string();
+            if kind == "software" {
+                return None;
+            }
+
+            let nvenc_index = if name.to_ascii_uppercase().contains("NVIDIA") {
+                nvidia_count += 1;
+                Some(nvidia_count - 1)
+            } else {
+                None
+            };
+
+            Some(GpuDevice {
+                index,
+                name,
+                kind,
+                nvenc_index,
+            })
+        })
+        .collect()
+}
+
+/// Parses `nvidia-smi -L` output.
+pub(crate) fn parse_nvidia_smi_list(stdout: &str) -> Vec<GpuDevice> {
+    NVIDIA_SMI_LIST_REGEX
+        .captures_iter(stdout)
+        .filter_map(|caps| {
+            let index = caps[1].parse::<u32>().ok()?;
+            Some(GpuDevice {
+                index,
+                name: caps[2].trim().to_string(),
+                kind: "discrete".to_string(),
+                nvenc_index: Some(index),
+            })
+        })
+        .collect()
+}
+
+/// Best-effort query of GPU memory. `nvidia_index` selects the CUDA device
+/// nvidia-smi reports on; `None` skips nvidia-smi. Returns `None` when no
+/// probe applies, in which case callers fall back to pixel-count heuristics.
+pub(crate) async fn query_gpu_memory(nvidia_index: Option<u32>) -> Option<GpuMemory> {
+    if cfg!(target_os = "macos") {
+        return query_unified_memory().await;
+    }
+
+    if let Some(index) = nvidia_index
+        && let Some(memory) = query_nvidia_smi(index).await
+    {
+        return Some(memory);
+    }
+
+    if cfg!(target_os = "linux") {
+        return query_amdgpu_sysfs();
+    }
+
+    None
+}
+
+async fn run_probe(program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = Command::new(program);
+    cmd.args(args).kill_on_drop(true);
+
+    #[cfg(windows)]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let output = tokio::time::timeout(PROBE_TIMEOUT, cmd.output())
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn query_nvidia_smi(index: u32) -> Option<GpuMemory> {
+    let stdout = run_probe(
+        "nvidia-smi",
+        &[
+            "--query-gpu=memory.total,memory.free",
+            "--format=csv,noheader,nounits",
+        ],
+    )
+    .await?;
+    parse_nvidia_smi(&stdout, index)
+}
+
+/// Parses line `index` of `nvidia-smi --query-gpu=memory.total,memory.free
+/// --format=csv,noheader,nounits` (one line per GPU, values in MiB).
+pub(crate) fn parse_nvidia_smi(stdout: &str, index: u32) -> Option<GpuMemory> {
+    let line = stdout
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .nth(index as usize)?;
+    let mut values = line.split(',').map(|v| v.trim().parse::<u64>());
+    let total = values.next()?.ok()?;
+    let free = values.next()?.ok()?;
+
+    Some(GpuMemory {
+        total_bytes: total * MIB,
+        available_bytes: free * MIB,
+        source: "nvidia-smi",
+    })
+}
+
+fn query_amdgpu_sysfs() -> Option<GpuMemory> {
+    let entries = std::fs::read_dir("/sys/class/drm").ok()?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let device = entry.path().join("device");
+        let read = |name: &str| -> Option<u64> {
+            std::fs::read_to_string(device.join(name))
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+        };
+
+        if let (Some(total), Some(used)) =
+            (read("mem_info_vram_total"), read("mem_info_vram_used"))
+            && total > 0
+        {
+            return Some(GpuMemory {
+                total_bytes: total,
+                available_bytes: total.saturating_sub(used),
+                source: "amdgpu sysfs",
+            });
+        }
+    }
+    None
+}
+
+/// Installed system memory, from `hw.memsize` on macOS and `/proc/meminfo`
+/// on Linux. `None` elsewhere or when the probe fails.
+pub(crate) async fn query_system_memory() -> Option<u64> {
+    if cfg!(target_os = "macos") {
+        return query_unified_memory()
+            .await
+            .map(|memory| memory.total_bytes);
+    }
+    if cfg!(target_os = "linux") {
+        return std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .as_deref()
+            .and_then(parse_meminfo);
+    }
+    None
+}
+
+/// Parses the `MemTotal` line of `/proc/meminfo` (in KiB) into bytes.
+pub(crate) fn parse_meminfo(meminfo: &str) -> Option<u64> {
+    let kib = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Whether this is an Apple Silicon Mac, including Intel builds running
+/// under Rosetta.
+pub(crate) async fn is_apple_silicon() -> bool {
+    if !cfg!(target_os = "macos") {
+        return false;
+    }
+    if cfg!(target_arch = "aarch64") {
+        return true;
+    }
+    run_probe("sysctl", &["-n", "hw.optional.arm64"])
+        .await
+        .is_some_and(|stdout| stdout.trim() == "1")
+}
+
+async fn query_unified_memory() -> Option<GpuMemory> {
+    let stdout = run_probe("sysctl", &["-n", "hw.memsize"]).await?;
+    parse_unified_memory(&stdout)
+}
+
+/// Apple GPUs share system memory; Metal caps a process's working set at
+/// roughly three quarters of it.
+pub(crate) fn parse_unified_memory(stdout: &str) -> Option<GpuMemory> {
+    let total = stdout.trim().parse::<u64>().ok()?;
+    Some(GpuMemory {
+        total_bytes: total,
+        available_bytes: total / 4 * 3,
+        source: "unified memory",
+    })
+}
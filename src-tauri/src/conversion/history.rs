@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::conversion::args::build_output_path;
+use crate::conversion::error::ConversionError;
+use crate::conversion::probe::probe_media_file;
+use crate::conversion::types::ConversionTask;
+use crate::conversion::utils::parse_time;
+use crate::watchdog::WorkerFault;
+
+/// File in the app data directory holding one finished job per line
+pub const HISTORY_FILE: &str = "conversion-history.jsonl";
+
+/// Finished jobs kept; older ones are dropped from the file
+pub const HISTORY_LIMIT: usize = 5000;
+
+pub const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Broad cause of a failed job, for charting failures over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorClass {
+    /// ffmpeg or the upscaler exited with an error
+    Encoder,
+    InvalidInput,
+    Io,
+    Probe,
+    MissingTool,
+    Crashed,
+    Stalled,
+    TimedOut,
+    Other,
+}
+
+impl ErrorClass {
+    pub fn of(err: &ConversionError) -> Self {
+        match err {
+            ConversionError::Worker(_) | ConversionError::Shell(_) => ErrorClass::Encoder,
+            ConversionError::InvalidInput(_) | ConversionError::Duplicate(_) => {
+                ErrorClass::InvalidInput
+            }
+            ConversionError::Io(_) => ErrorClass::Io,
+            ConversionError::Probe(_) | ConversionError::Json(_) => ErrorClass::Probe,
+            ConversionError::SidecarUnavailable(_) => ErrorClass::MissingTool,
+            ConversionError::Fault(WorkerFault::Panicked { .. }) => ErrorClass::Crashed,
+            ConversionError::Fault(WorkerFault::Stalled { .. }) => ErrorClass::Stalled,
+            ConversionError::Fault(WorkerFault::TimedOut { .. }) | ConversionError::TimedOut(_) => {
+                ErrorClass::TimedOut
+            }
+            ConversionError::Channel(_) | ConversionError::TaskNotFound(_) => ErrorClass::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum JobOutcome {
+    Completed,
+    #[serde(rename_all = "camelCase")]
+    Failed {
+        error_class: ErrorClass,
+    },
+    Cancelled,
+}
+
+impl JobOutcome {
+    pub fn of(result: &Result<(), ConversionError>, cancelled: bool) -> Self {
+        match result {
+            _ if cancelled => JobOutcome::Cancelled,
+            Ok(()) => JobOutcome::Completed,
+            Err(err) => JobOutcome::Failed {
+                error_class: ErrorClass::of(err),
+            },
+        }
+    }
+}
+
+/// One finished conversion, as stored in the history file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    pub file_path: String,
+    pub video_codec: String,
+    /// Unix seconds
+    pub finished_at: u64,
+    /// Wall-clock time the worker ran
+    pub elapsed_secs: f64,
+    /// Length of the encoded output; 0 unless completed
+    pub media_secs: f64,
+    pub input_bytes: u64,
+    /// Size of the output; `None` unless completed
+    pub output_bytes: Option<u64>,
+    pub outcome: JobOutcome,
+}
+
+/// Persistent record of finished conversions, managed as Tauri state
+#[derive(Default)]
+pub struct JobHistory {
+    path: Option<PathBuf>,
+    entries: Mutex<Vec<HistoryEntry>>,
+}
+
+impl JobHistory {
+    /// History backed by `path`, with the entries already in it. Lines
+    /// that do not parse are skipped.
+    pub fn load(path: PathBuf) -> Self {
+        let mut entries: Vec<HistoryEntry> = File::open(&path)
+            .map(|file| {
+                BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| serde_json::from_str(&line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let excess = entries.len().saturating_sub(HISTORY_LIMIT);
+        entries.drain(..excess);
+        Self {
+            path: Some(path),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Entries finished at or after `since` (Unix seconds), or all of them
+    pub fn entries_since(&self, since: Option<u64>) -> Vec<HistoryEntry> {
+        let since = since.unwrap_or(0);
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry.finished_at >= since)
+            .cloned()
+            .collect()
+    }
+
+    /// Adds `entry`, keeping it in memory even when the file cannot be
+    /// written
+    pub fn record(&self, entry: HistoryEntry) -> std::io::Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(entry);
+        if entries.len() > HISTORY_LIMIT {
+            let excess = entries.len() - HISTORY_LIMIT;
+            entries.drain(..excess);
+            self.rewrite(&entries)
+        } else {
+            self.append(entries.last().unwrap())
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), ConversionError> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clear();
+        self.rewrite(&entries)?;
+        Ok(())
+    }
+
+    fn append(&self, entry: &HistoryEntry) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)
+    }
+
+    fn rewrite(&self, entries: &[HistoryEntry]) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        let temp = path.with_extension("jsonl.tmp");
+        std::fs::write(&temp, lines)?;
+        std::fs::rename(temp, path)
+    }
+}
+
+/// Build the history entry for a conversion that just ended
+pub async fn conversion_entry(
+    app: &AppHandle,
+    task: &ConversionTask,
+    elapsed: Duration,
+    outcome: JobOutcome,
+) -> HistoryEntry {
+    let (media_secs, output_bytes) = if outcome == JobOutcome::Completed {
+        let output_path = build_output_path(
+            &task.file_path,
+            &task.config.container,
+            task.output_name.clone(),
+        );
+        let media_secs = probe_media_file(app, &output_path)
+            .await
+            .ok()
+            .and_then(|probe| probe.duration)
+            .as_deref()
+            .and_then(parse_time)
+            .unwrap_or(0.0);
+        (media_secs, file_size(Path::new(&output_path)))
+    } else {
+        (0.0, None)
+    };
+
+    HistoryEntry {
+        id: task.id.clone(),
+        file_path: task.file_path.clone(),
+        video_codec: task.config.video_codec.clone(),
+        finished_at: unix_now(),
+        elapsed_secs: elapsed.as_secs_f64(),
+        media_secs,
+        input_bytes: file_size(Path::new(&task.file_path)).unwrap_or(0),
+        output_bytes,
+        outcome,
+    }
+}
+
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok().map(|m| m.len())
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Totals across a set of finished jobs
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySummary {
+    pub jobs: u32,
+    pub completed: u32,
+    pub failed: u32,
+    pub cancelled: u32,
+    /// Minutes of media written by completed jobs
+    pub minutes_encoded: f64,
+    /// Hours workers spent on all jobs, including failed ones
+    pub hours_spent: f64,
+    /// Input minus output size over completed jobs; negative when outputs
+    /// grew
+    pub bytes_saved: i64,
+    pub gb_saved: f64,
+}
+
+pub fn summarize(entries: &[HistoryEntry]) -> HistorySummary {
+    let mut summary = HistorySummary::default();
+    let mut seconds_spent = 0.0;
+    for entry in entries {
+        summary.jobs += 1;
+        seconds_spent += entry.elapsed_secs;
+        match entry.outcome {
+            JobOutcome::Completed => {
+                summary.completed += 1;
+                summary.minutes_encoded += entry.media_secs / 60.0;
+                if let Some(output) = entry.output_bytes {
+                    summary.bytes_saved += entry.input_bytes as i64 - output as i64;
+                }
+            }
+            JobOutcome::Failed { .. } => summary.failed += 1,
+            JobOutcome::Cancelled => summary.cancelled += 1,
+        }
+    }
+    summary.hours_spent = seconds_spent / 3600.0;
+    summary.gb_saved = summary.bytes_saved as f64 / 1e9;
+    summary
+}
+
+/// Encoding speed of one codec over completed jobs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodecSpeed {
+    pub codec: String,
+    pub jobs: u32,
+    pub media_secs: f64,
+    pub elapsed_secs: f64,
+    /// Seconds of media per second of work (2.0 = twice realtime)
+    pub average_speed: f64,
+}
+
+/// Per-codec speeds, fastest first
+pub fn codec_speeds(entries: &[HistoryEntry]) -> Vec<CodecSpeed> {
+    let mut by_codec: HashMap<&str, CodecSpeed> = HashMap::new();
+    for entry in entries {
+        if entry.outcome != JobOutcome::Completed || entry.media_secs <= 0.0 {
+            continue;
+        }
+        let speed = by_codec
+            .entry(entry.video_codec.as_str())
+            .or_insert_with(|| CodecSpeed {
+                codec: entry.video_codec.clone(),
+                jobs: 0,
+                media_secs: 0.0,
+                elapsed_secs: 0.0,
+                average_speed: 0.0,
+            });
+        speed.jobs += 1;
+        speed.media_secs += entry.media_secs;
+        speed.elapsed_secs += entry.elapsed_secs;
+    }
+
+    let mut speeds: Vec<CodecSpeed> = by_codec
+        .into_values()
+        .map(|mut speed| {
+            if speed.elapsed_secs > 0.0 {
+                speed.average_speed = speed.media_secs / speed.elapsed_secs;
+            }
+            speed
+        })
+        .collect();
+    speeds.sort_by(|a, b| {
+        b.average_speed
+            .total_cmp(&a.average_speed)
+            .then_with(|| a.codec.cmp(&b.codec))
+    });
+    speeds
+}
+
+/// Failures finished within one bucket of time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailureBucket {
+    /// Unix seconds at the start of the bucket
+    pub start: u64,
+    pub total: u32,
+    pub by_class: BTreeMap<ErrorClass, u32>,
+}
+
+/// Failures grouped into `bucket_secs`-long buckets (e.g. `DAY_SECS`),
+/// oldest first. Buckets without failures are left out.
+pub fn failure_timeline(entries: &[HistoryEntry], bucket_secs: u64) -> Vec<FailureBucket> {
+    let bucket_secs = bucket_secs.max(1);
+    let mut buckets: BTreeMap<u64, FailureBucket> = BTreeMap::new();
+    for entry in entries {
+        let JobOutcome::Failed { error_class } = entry.outcome else {
+            continue;
+        };
+        let start = entry.finished_at - entry.finished_at % bucket_secs;
+        let bucket = buckets.entry(start).or_insert_with(|| FailureBucket {
+            start,
+            total: 0,
+            by_class: BTreeMap::new(),
+        });
+        bucket.total += 1;
+        *bucket.by_class.entry(error_class).or_default() += 1;
+    }
+    buckets.into_values().collect()
+}
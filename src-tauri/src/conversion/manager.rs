@@ -1,12 +1,16 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
 use std::sync::{
     Arc, Mutex,
     atomic::{AtomicUsize, Ordering},
 };
-use tauri::{AppHandle, Emitter};
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{mpsc, watch};
 
-use crate::conversion::types::{ErrorPayload, LogPayload};
+use crate::conversion::types::{
+    ErrorPayload, LogPayload, SidecarIssuePayload, SourceMissingPayload,
+};
 
 #[cfg(unix)]
 use libc;
@@ -23,9 +27,19 @@ use windows::{
     core::s,
 };
 
+use crate::alerts::QueueKind;
+use crate::conversion::dedupe::{DuplicateRegistry, JobEntry, JobKey};
 use crate::conversion::error::ConversionError;
-use crate::conversion::types::{ConversionTask, DEFAULT_MAX_CONCURRENCY};
+use crate::conversion::history::{JobHistory, JobOutcome, conversion_entry};
+use crate::conversion::types::{
+    ConversionTask, DEFAULT_MAX_CONCURRENCY, DuplicateAction, DuplicatePolicy,
+};
 use crate::conversion::worker::run_ffmpeg_worker;
+use crate::governor::{ResourceClass, ResourceGovernor};
+use crate::watchdog::{
+    PauseClock, WORKER_DIAGNOSTIC_EVENT, WORKER_STALL_TIMEOUT, WorkerDiagnosticPayload, supervise,
+    time_limit,
+};
 
 pub enum ManagerMessage {
     Enqueue(ConversionTask),
@@ -33,13 +47,21 @@ pub enum ManagerMessage {
     TaskStarted(String, u32),
     TaskCompleted(String),
     TaskError(String, ConversionError),
+    CheckSources,
+    Relink(String, String),
+    Cancelled(String),
 }
 
+const SOURCE_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
 pub struct ConversionManager {
     pub(crate) sender: mpsc::Sender<ManagerMessage>,
     max_concurrency: Arc<AtomicUsize>,
     active_tasks: Arc<Mutex<HashMap<String, u32>>>,
     cancelled_tasks: Arc<Mutex<HashSet<String>>>,
+    pause_clocks: Arc<Mutex<HashMap<String, PauseClock>>>,
+    duplicate_policy: Arc<Mutex<DuplicatePolicy>>,
+    duplicates: Arc<Mutex<DuplicateRegistry>>,
 }
 
 impl ConversionManager {
@@ -52,13 +74,27 @@ impl ConversionManager {
         let active_tasks_loop = Arc::clone(&active_tasks);
         let cancelled_tasks = Arc::new(Mutex::new(HashSet::new()));
         let cancelled_tasks_loop = Arc::clone(&cancelled_tasks);
+        let pause_clocks = Arc::new(Mutex::new(HashMap::new()));
+        let pause_clocks_loop = Arc::clone(&pause_clocks);
+        let duplicates = Arc::new(Mutex::new(DuplicateRegistry::default()));
+        let duplicates_loop = Arc::clone(&duplicates);
+        let (sources_queued, sources_queued_watch) = watch::channel(false);
 
         tauri::async_runtime::spawn(async move {
             let mut queue: VecDeque<ConversionTask> = VecDeque::new();
             let mut queued_ids: HashSet<String> = HashSet::new();
             let mut running_tasks: HashMap<String, ()> = HashMap::new();
-
-            while let Some(msg) = rx.recv().await {
+            // Tasks whose source file disappeared while queued, awaiting relink
+            let mut missing_tasks: HashMap<String, ConversionTask> = HashMap::new();
+
+            loop {
+                // Missing sources are only polled for while something is queued
+                let queued = !queue.is_empty();
+                sources_queued
+                    .send_if_modified(|watched| std::mem::replace(watched, queued) != queued);
+                let Some(msg) = rx.recv().await else {
+                    break;
+                };
                 match msg {
                     ManagerMessage::Enqueue(task) => {
                         {
@@ -78,8 +114,10 @@ impl ConversionManager {
                             &mut queue,
                             &mut queued_ids,
                             &mut running_tasks,
+                            &mut missing_tasks,
                             Arc::clone(&limiter),
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
@@ -90,8 +128,10 @@ impl ConversionManager {
                             &mut queue,
                             &mut queued_ids,
                             &mut running_tasks,
+                            &mut missing_tasks,
                             Arc::clone(&limiter),
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
@@ -116,8 +156,10 @@ impl ConversionManager {
                                 &mut queue,
                                 &mut queued_ids,
                                 &mut running_tasks,
+                                &mut missing_tasks,
                                 Arc::clone(&limiter),
                                 Arc::clone(&cancelled_tasks_loop),
+                                Arc::clone(&pause_clocks_loop),
                             )
                             .await;
                             continue;
@@ -128,6 +170,7 @@ impl ConversionManager {
                     }
                     ManagerMessage::TaskCompleted(id) => {
                         running_tasks.remove(&id);
+                        duplicates_loop.lock().unwrap().finish(&id, true);
                         {
                             let mut cancelled = cancelled_tasks_loop.lock().unwrap();
                             cancelled.remove(&id);
@@ -143,14 +186,72 @@ impl ConversionManager {
                             &mut queue,
                             &mut queued_ids,
                             &mut running_tasks,
+                            &mut missing_tasks,
                             Arc::clone(&limiter),
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
+                    ManagerMessage::CheckSources => {
+                        let mut still_queued = VecDeque::with_capacity(queue.len());
+                        while let Some(task) = queue.pop_front() {
+                            if Path::new(&task.file_path).exists() {
+                                still_queued.push_back(task);
+                            } else if cancelled_tasks_loop.lock().unwrap().remove(&task.id) {
+                                // Cancelled while queued; nothing to relink
+                                queued_ids.remove(&task.id);
+                            } else {
+                                queued_ids.remove(&task.id);
+                                ConversionManager::mark_source_missing(
+                                    &app,
+                                    &mut missing_tasks,
+                                    task,
+                                );
+                            }
+                        }
+                        queue = still_queued;
+                    }
+                    ManagerMessage::Relink(id, new_path) => {
+                        if let Some(mut task) = missing_tasks.remove(&id) {
+                            task.file_path = new_path;
+                            queued_ids.insert(task.id.clone());
+                            queue.push_back(task);
+                            ConversionManager::process_queue(
+                                &app,
+                                &tx_clone,
+                                &mut queue,
+                                &mut queued_ids,
+                                &mut running_tasks,
+                                &mut missing_tasks,
+                                Arc::clone(&limiter),
+                                Arc::clone(&cancelled_tasks_loop),
+                                Arc::clone(&pause_clocks_loop),
+                            )
+                            .await;
+                        }
+                    }
+                    ManagerMessage::Cancelled(id) => {
+                        ConversionManager::forget_missing(
+                            &mut missing_tasks,
+                            &cancelled_tasks_loop,
+                            &id,
+                        );
+                    }
                     ManagerMessage::TaskError(id, err) => {
                         eprintln!("Task {} failed: {}", id, err);
 
+                        // A crashed, stuck or timed-out worker leaves its ffmpeg behind
+                        if matches!(
+                            err,
+                            ConversionError::Fault(_) | ConversionError::TimedOut(_)
+                        ) {
+                            let pid = active_tasks_loop.lock().unwrap().get(&id).copied();
+                            if let Some(pid) = pid.filter(|&pid| pid > 0) {
+                                let _ = ConversionManager::terminate_process(pid);
+                            }
+                        }
+
                         let _ = app.emit(
                             "conversion-log",
                             LogPayload {
@@ -159,6 +260,16 @@ impl ConversionManager {
                             },
                         );
 
+                        if let ConversionError::SidecarUnavailable(issue) = &err {
+                            let _ = app.emit(
+                                "conversion-sidecar-missing",
+                                SidecarIssuePayload {
+                                    id: id.clone(),
+                                    issue: issue.clone(),
+                                },
+                            );
+                        }
+
                         let _ = app.emit(
                             "conversion-error",
                             ErrorPayload {
@@ -168,6 +279,7 @@ impl ConversionManager {
                         );
 
                         running_tasks.remove(&id);
+                        duplicates_loop.lock().unwrap().finish(&id, false);
                         {
                             let mut cancelled = cancelled_tasks_loop.lock().unwrap();
                             cancelled.remove(&id);
@@ -183,8 +295,10 @@ impl ConversionManager {
                             &mut queue,
                             &mut queued_ids,
                             &mut running_tasks,
+                            &mut missing_tasks,
                             Arc::clone(&limiter),
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
@@ -192,22 +306,34 @@ impl ConversionManager {
             }
         });
 
+        tauri::async_runtime::spawn(poll_sources(
+            tx.clone(),
+            sources_queued_watch,
+            SOURCE_CHECK_INTERVAL,
+        ));
+
         Self {
             sender: tx,
             max_concurrency,
             active_tasks,
             cancelled_tasks,
+            pause_clocks,
+            duplicate_policy: Arc::new(Mutex::new(DuplicatePolicy::default())),
+            duplicates,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn process_queue(
         app: &AppHandle,
         tx: &mpsc::Sender<ManagerMessage>,
         queue: &mut VecDeque<ConversionTask>,
         queued_ids: &mut HashSet<String>,
         running_tasks: &mut HashMap<String, ()>,
+        missing_tasks: &mut HashMap<String, ConversionTask>,
         max_concurrency: Arc<AtomicUsize>,
         cancelled_tasks: Arc<Mutex<HashSet<String>>>,
+        pause_clocks: Arc<Mutex<HashMap<String, PauseClock>>>,
     ) {
         let limit = max_concurrency.load(Ordering::SeqCst).max(1);
 
@@ -222,24 +348,107 @@ impl ConversionManager {
                     continue;
                 }
 
+                // Revalidate right before starting: the file may have been moved
+                // or deleted since it was queued
+                if !Path::new(&task.file_path).exists() {
+                    ConversionManager::mark_source_missing(app, missing_tasks, task);
+                    continue;
+                }
+
                 running_tasks.insert(task.id.clone(), ());
 
                 let app_clone = app.clone();
                 let tx_worker = tx.clone();
                 let task_clone = task.clone();
+                let cancelled_worker = Arc::clone(&cancelled_tasks);
+                let pause = PauseClock::default();
+                pause_clocks
+                    .lock()
+                    .unwrap()
+                    .insert(task.id.clone(), pause.clone());
+                let pause_clocks_worker = Arc::clone(&pause_clocks);
 
                 tauri::async_runtime::spawn(async move {
-                    if let Err(e) =
-                        run_ffmpeg_worker(app_clone, tx_worker.clone(), task_clone.clone()).await
-                    {
+                    let class = ResourceClass::for_conversion(&task_clone.config);
+                    let governor = app_clone.state::<ResourceGovernor>();
+                    let permit = match governor.try_acquire(class) {
+                        Some(permit) => permit,
+                        None => {
+                            let _ = app_clone.emit(
+                                "conversion-log",
+                                LogPayload {
+                                    id: task_clone.id.clone(),
+                                    line: "[QUEUE] Waiting for a free encoder slot".to_string(),
+                                },
+                            );
+                            governor.acquire(class).await
+                        }
+                    };
+
+                    if cancelled_worker.lock().unwrap().contains(&task_clone.id) {
+                        pause_clocks_worker.lock().unwrap().remove(&task_clone.id);
+                        let _ = tx_worker
+                            .send(ManagerMessage::TaskCompleted(task_clone.id))
+                            .await;
+                        return;
+                    }
+
+                    let worker_app = app_clone.clone();
+                    let worker_tx = tx_worker.clone();
+                    let worker_task = task_clone.clone();
+                    let started = Instant::now();
+                    let result = supervise(
+                        app_clone.clone(),
+                        pause,
+                        WORKER_STALL_TIMEOUT,
+                        time_limit(task_clone.config.max_duration_secs),
+                        |events| run_ffmpeg_worker(worker_app, events, worker_tx, worker_task),
+                    )
+                    .await
+                    .unwrap_or_else(|fault| {
+                        let _ = app_clone.emit(
+                            WORKER_DIAGNOSTIC_EVENT,
+                            WorkerDiagnosticPayload::new(
+                                &task_clone.id,
+                                QueueKind::Conversion,
+                                &fault,
+                            ),
+                        );
+                        Err(fault.into())
+                    });
+                    let elapsed = started.elapsed();
+                    drop(permit);
+                    pause_clocks_worker.lock().unwrap().remove(&task_clone.id);
+
+                    let cancelled = cancelled_worker.lock().unwrap().contains(&task_clone.id);
+                    let outcome = JobOutcome::of(&result, cancelled);
+                    if let Err(e) = result {
                         let _ = tx_worker
-                            .send(ManagerMessage::TaskError(task_clone.id, e))
+                            .send(ManagerMessage::TaskError(task_clone.id.clone(), e))
                             .await;
                     } else {
                         let _ = tx_worker
-                            .send(ManagerMessage::TaskCompleted(task_clone.id))
+                            .send(ManagerMessage::TaskCompleted(task_clone.id.clone()))
                             .await;
                     }
+
+                    // The queue has moved on; probing the output and writing
+                    // the history file hold up nothing
+                    let entry = conversion_entry(&app_clone, &task_clone, elapsed, outcome).await;
+                    let history_app = app_clone.clone();
+                    let saved = tauri::async_runtime::spawn_blocking(move || {
+                        history_app.state::<JobHistory>().record(entry)
+                    })
+                    .await;
+                    if let Ok(Err(e)) = saved {
+                        let _ = app_clone.emit(
+                            "conversion-log",
+                            LogPayload {
+                                id: task_clone.id,
+                                line: format!("[ERROR] Failed to save conversion history: {}", e),
+                            },
+                        );
+                    }
                 });
             } else {
                 break;
@@ -247,6 +456,46 @@ impl ConversionManager {
         }
     }
 
+    fn mark_source_missing(
+        app: &AppHandle,
+        missing_tasks: &mut HashMap<String, ConversionTask>,
+        task: ConversionTask,
+    ) {
+        let _ = app.emit(
+            "conversion-source-missing",
+            SourceMissingPayload {
+                id: task.id.clone(),
+                file_path: task.file_path.clone(),
+            },
+        );
+        missing_tasks.insert(task.id.clone(), task);
+    }
+
+    /// Drops a cancelled task that was waiting for its source to be relinked,
+    /// along with its cancel mark, which no queue or worker is left to clear
+    pub(crate) fn forget_missing(
+        missing_tasks: &mut HashMap<String, ConversionTask>,
+        cancelled_tasks: &Mutex<HashSet<String>>,
+        id: &str,
+    ) {
+        if missing_tasks.remove(id).is_some() {
+            cancelled_tasks.lock().unwrap().remove(id);
+        }
+    }
+
+    pub async fn relink_task(&self, id: &str, new_path: &str) -> Result<(), ConversionError> {
+        if !Path::new(new_path).exists() {
+            return Err(ConversionError::InvalidInput(format!(
+                "Input file does not exist: {}",
+                new_path
+            )));
+        }
+        self.sender
+            .send(ManagerMessage::Relink(id.to_string(), new_path.to_string()))
+            .await
+            .map_err(|e| ConversionError::Channel(e.to_string()))
+    }
+
     pub fn current_max_concurrency(&self) -> usize {
         self.max_concurrency.load(Ordering::SeqCst)
     }
@@ -265,6 +514,41 @@ impl ConversionManager {
         Ok(())
     }
 
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        *self.duplicate_policy.lock().unwrap()
+    }
+
+    pub fn set_duplicate_policy(&self, policy: DuplicatePolicy) {
+        *self.duplicate_policy.lock().unwrap() = policy;
+    }
+
+    /// Records the job a task is about to do and returns the path of an
+    /// earlier task doing the same job, if any. With `DuplicateAction::Skip`
+    /// such a task is rejected instead.
+    pub fn check_duplicate(
+        &self,
+        id: &str,
+        file_path: &str,
+        key: JobKey,
+        action: DuplicateAction,
+    ) -> Result<Option<String>, ConversionError> {
+        let mut duplicates = self.duplicates.lock().unwrap();
+        let original = duplicates
+            .find(id, &key)
+            .map(|entry| entry.file_path.clone());
+        if let Some(original) = &original
+            && action == DuplicateAction::Skip
+        {
+            return Err(ConversionError::Duplicate(original.clone()));
+        }
+        duplicates.register(JobEntry {
+            id: id.to_string(),
+            file_path: file_path.to_string(),
+            key,
+        });
+        Ok(original)
+    }
+
     pub fn pause_task(&self, id: &str) -> Result<(), ConversionError> {
         let tasks = self.active_tasks.lock().unwrap();
         if let Some(&pid) = tasks.get(id) {
@@ -284,6 +568,9 @@ impl ConversionManager {
                 windows_suspend_resume(pid, true)?;
             }
 
+            if let Some(clock) = self.pause_clocks.lock().unwrap().get(id) {
+                clock.pause();
+            }
             Ok(())
         } else {
             Err(ConversionError::TaskNotFound(id.to_string()))
@@ -309,6 +596,9 @@ impl ConversionManager {
                 windows_suspend_resume(pid, false)?;
             }
 
+            if let Some(clock) = self.pause_clocks.lock().unwrap().get(id) {
+                clock.resume();
+            }
             Ok(())
         } else {
             Err(ConversionError::TaskNotFound(id.to_string()))
@@ -320,6 +610,12 @@ impl ConversionManager {
             let mut cancelled = self.cancelled_tasks.lock().unwrap();
             cancelled.insert(id.to_string());
         }
+        self.duplicates.lock().unwrap().finish(id, false);
+        let tx = self.sender.clone();
+        let cancelled_id = id.to_string();
+        tauri::async_runtime::spawn(async move {
+            let _ = tx.send(ManagerMessage::Cancelled(cancelled_id)).await;
+        });
 
         let tasks = self.active_tasks.lock().unwrap();
         if let Some(&pid) = tasks.get(id) {
@@ -373,6 +669,28 @@ impl ConversionManager {
     }
 }
 
+/// Sends `CheckSources` every `interval` while `queued` is set, and nothing
+/// while the queue is empty
+pub(crate) async fn poll_sources(
+    tx: mpsc::Sender<ManagerMessage>,
+    mut queued: watch::Receiver<bool>,
+    interval: Duration,
+) {
+    loop {
+        if queued.wait_for(|&queued| queued).await.is_err() {
+            break;
+        }
+        tokio::time::sleep(interval).await;
+        // The queue may have emptied while waiting
+        if !*queued.borrow() {
+            continue;
+        }
+        if tx.send(ManagerMessage::CheckSources).await.is_err() {
+            break;
+        }
+    }
+}
+
 #[cfg(windows)]
 unsafe fn windows_suspend_resume(pid: u32, suspend: bool) -> Result<(), ConversionError> {
     let process_handle = OpenProcess(PROCESS_SUSPEND_RESUME, false, pid)
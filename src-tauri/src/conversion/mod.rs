@@ -1,10 +1,15 @@
 pub(crate) mod args;
 pub(crate) mod codec;
+pub(crate) mod contact_sheet;
 pub mod commands;
+pub(crate) mod dedupe;
 pub mod error;
 pub(crate) mod filters;
+pub(crate) mod gpu;
+pub(crate) mod history;
 pub(crate) mod manager;
 mod probe;
+pub(crate) mod sidecar;
 pub(crate) mod types;
 pub(crate) mod upscale;
 pub(crate) mod utils;
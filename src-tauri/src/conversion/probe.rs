@@ -2,6 +2,7 @@ use tauri::AppHandle;
 use tauri_plugin_shell::ShellExt;
 
 use crate::conversion::error::ConversionError;
+use crate::conversion::sidecar::sidecar_error;
 use crate::conversion::types::{AudioTrack, FfprobeOutput, ProbeMetadata, SubtitleTrack};
 use crate::conversion::utils::{parse_frame_rate_string, parse_probe_bitrate};
 
@@ -22,11 +23,11 @@ pub async fn probe_media_file(
     let output = app
         .shell()
         .sidecar("ffprobe")
-        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .map_err(|e| sidecar_error("ffprobe", e))?
         .args(args)
         .output()
         .await
-        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+        .map_err(|e| sidecar_error("ffprobe", e))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -0,0 +1,110 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::conversion::error::ConversionError;
+
+/// What is wrong with a bundled binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SidecarProblem {
+    /// The file is not next to the app executable.
+    Missing,
+    /// The file exists but may not be executed.
+    NotExecutable,
+    /// The file exists but the OS cannot run it (truncated, wrong
+    /// architecture, quarantined).
+    Corrupted,
+}
+
+/// A bundled binary that could not be started, with what the user can do
+/// about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarIssue {
+    pub binary: String,
+    pub expected_path: String,
+    pub problem: SidecarProblem,
+    pub suggested_fix: String,
+}
+
+impl std::fmt::Display for SidecarIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.problem {
+            SidecarProblem::Missing => "is missing",
+            SidecarProblem::NotExecutable => "is not executable",
+            SidecarProblem::Corrupted => "cannot be run",
+        };
+        write!(
+            f,
+            "{} {} (expected at {}). {}",
+            self.binary, what, self.expected_path, self.suggested_fix
+        )
+    }
+}
+
+/// Where Tauri looks for a sidecar: next to the app executable.
+pub(crate) fn expected_sidecar_path(binary: &str) -> PathBuf {
+    let dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .unwrap_or_default();
+    let file = if cfg!(windows) {
+        format!("{binary}.exe")
+    } else {
+        binary.to_string()
+    };
+    dir.join(file)
+}
+
+fn setup_script(binary: &str) -> &'static str {
+    if binary.starts_with("realesrgan") {
+        "bun run setup:upscaler"
+    } else {
+        "bun run setup:ffmpeg"
+    }
+}
+
+/// Recognizes spawn failures caused by a broken install rather than by the
+/// job itself.
+pub(crate) fn classify_io_error(binary: &str, err: &std::io::Error) -> Option<SidecarIssue> {
+    // ENOEXEC on Unix, ERROR_BAD_EXE_FORMAT on Windows
+    let bad_format = if cfg!(windows) { 193 } else { 8 };
+    let problem = match err.kind() {
+        ErrorKind::NotFound => SidecarProblem::Missing,
+        ErrorKind::PermissionDenied => SidecarProblem::NotExecutable,
+        _ if err.raw_os_error() == Some(bad_format) => SidecarProblem::Corrupted,
+        _ => return None,
+    };
+
+    let suggested_fix = match problem {
+        SidecarProblem::NotExecutable if !cfg!(windows) => format!(
+            "Reinstall Frame, or restore the permission with `chmod +x`. When building from source, run `{}` and rebuild.",
+            setup_script(binary)
+        ),
+        _ => format!(
+            "Reinstall Frame to restore the bundled tools. When building from source, run `{}` and rebuild.",
+            setup_script(binary)
+        ),
+    };
+
+    Some(SidecarIssue {
+        binary: binary.to_string(),
+        expected_path: expected_sidecar_path(binary).to_string_lossy().into_owned(),
+        problem,
+        suggested_fix,
+    })
+}
+
+/// Maps a failure to create or spawn `binary` to a `ConversionError`,
+/// singling out broken installs.
+pub(crate) fn sidecar_error(binary: &str, err: tauri_plugin_shell::Error) -> ConversionError {
+    match &err {
+        tauri_plugin_shell::Error::Io(io) => match classify_io_error(binary, io) {
+            Some(issue) => ConversionError::SidecarUnavailable(issue),
+            None => ConversionError::Shell(err.to_string()),
+        },
+        _ => ConversionError::Shell(err.to_string()),
+    }
+}
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::conversion::args::{build_ffmpeg_args, build_output_path, validate_task_input};
-    use crate::conversion::types::{ConversionConfig, MetadataConfig, MetadataMode};
+    use crate::conversion::types::{ConversionConfig, MetadataMode};
     use crate::conversion::upscale::build_upscale_encode_args;
     use crate::conversion::utils::parse_time;
     use std::fs;
@@ -20,37 +20,9 @@ mod tests {
     fn sample_config(container: &str) -> ConversionConfig {
         ConversionConfig {
             container: container.into(),
-            video_codec: "libx264".into(),
-            video_bitrate_mode: "crf".into(),
-            video_bitrate: "5000".into(),
-            audio_codec: "aac".into(),
-            audio_bitrate: "128".into(),
-            audio_channels: "original".into(),
-            audio_volume: 100.0,
             selected_audio_tracks: vec![1],
-            selected_subtitle_tracks: vec![],
-            subtitle_burn_path: None,
-            resolution: "original".into(),
-            custom_width: None,
-            custom_height: None,
             scaling_algorithm: "bicubic".into(),
-            fps: "original".into(),
-            crf: 23,
-            quality: 50,
-            preset: "medium".into(),
-            start_time: None,
-            end_time: None,
-            audio_normalize: false,
-            metadata: MetadataConfig::default(),
-            rotation: "0".into(),
-            flip_horizontal: false,
-            flip_vertical: false,
-            ml_upscale: None,
-            crop: None,
-            nvenc_spatial_aq: false,
-            nvenc_temporal_aq: false,
-            videotoolbox_allow_sw: false,
-            hw_decode: false,
+            ..Default::default()
         }
     }
 
@@ -289,6 +261,22 @@ mod tests {
         assert!(contains_args(&args, &["-temporal_aq", "1"]));
     }
 
+    #[test]
+    fn test_nvenc_gpu_selection() {
+        let mut config = sample_config("mp4");
+        config.video_codec = "hevc_nvenc".into();
+        config.hw_decode = true;
+        config.nvenc_gpu_index = Some(1);
+
+        let args = build_ffmpeg_args("in.mp4", "out.mp4", &config);
+        assert!(contains_args(&args, &["-gpu", "1"]));
+        assert!(contains_args(&args, &["-hwaccel_device", "1"]));
+
+        config.video_codec = "libx264".into();
+        let args = build_ffmpeg_args("in.mp4", "out.mp4", &config);
+        assert!(!args.contains(&"-gpu".to_string()));
+    }
+
     #[test]
     fn test_videotoolbox_option_flags() {
         let mut config = sample_config("mov");
@@ -731,40 +719,7 @@ mod scenario_tests {
     use crate::conversion::types::{ConversionConfig, CropConfig, MetadataConfig, MetadataMode};
 
     fn base_config() -> ConversionConfig {
-        ConversionConfig {
-            container: "mp4".into(),
-            video_codec: "libx264".into(),
-            video_bitrate_mode: "crf".into(),
-            video_bitrate: "5000".into(),
-            audio_codec: "aac".into(),
-            audio_bitrate: "128".into(),
-            audio_channels: "original".into(),
-            audio_volume: 100.0,
-            selected_audio_tracks: vec![],
-            selected_subtitle_tracks: vec![],
-            subtitle_burn_path: None,
-            resolution: "original".into(),
-            custom_width: None,
-            custom_height: None,
-            scaling_algorithm: "lanczos".into(),
-            fps: "original".into(),
-            crf: 23,
-            quality: 50,
-            preset: "medium".into(),
-            start_time: None,
-            end_time: None,
-            audio_normalize: false,
-            metadata: MetadataConfig::default(),
-            rotation: "0".into(),
-            flip_horizontal: false,
-            flip_vertical: false,
-            ml_upscale: None,
-            crop: None,
-            nvenc_spatial_aq: false,
-            nvenc_temporal_aq: false,
-            videotoolbox_allow_sw: false,
-            hw_decode: false,
-        }
+        ConversionConfig::default()
     }
 
     #[test]
@@ -1016,46 +971,39 @@ mod scenario_tests {
 #[cfg(test)]
 mod hwaccel_tests {
     use crate::conversion::args::build_ffmpeg_args;
-    use crate::conversion::types::{ConversionConfig, MetadataConfig};
+    use crate::conversion::types::ConversionConfig;
     use crate::conversion::utils::get_hwaccel_args;
+    use crate::governor::ResourceClass;
 
     fn hwaccel_config(codec: &str) -> ConversionConfig {
         ConversionConfig {
-            container: "mp4".into(),
             video_codec: codec.into(),
-            video_bitrate_mode: "crf".into(),
-            video_bitrate: "5000".into(),
-            audio_codec: "aac".into(),
-            audio_bitrate: "128".into(),
-            audio_channels: "original".into(),
-            audio_volume: 100.0,
-            selected_audio_tracks: vec![],
-            selected_subtitle_tracks: vec![],
-            subtitle_burn_path: None,
-            resolution: "original".into(),
-            custom_width: None,
-            custom_height: None,
             scaling_algorithm: "bicubic".into(),
-            fps: "original".into(),
-            crf: 23,
-            quality: 50,
-            preset: "medium".into(),
-            start_time: None,
-            end_time: None,
-            audio_normalize: false,
-            metadata: MetadataConfig::default(),
-            rotation: "0".into(),
-            flip_horizontal: false,
-            flip_vertical: false,
-            ml_upscale: None,
-            crop: None,
-            nvenc_spatial_aq: false,
-            nvenc_temporal_aq: false,
-            videotoolbox_allow_sw: false,
             hw_decode: true,
+            ..Default::default()
         }
     }
 
+    #[test]
+    fn resource_class_follows_encoder() {
+        assert_eq!(
+            ResourceClass::for_conversion(&hwaccel_config("h264_nvenc")),
+            ResourceClass::HardwareEncoder
+        );
+        assert_eq!(
+            ResourceClass::for_conversion(&hwaccel_config("hevc_videotoolbox")),
+            ResourceClass::HardwareEncoder
+        );
+        assert_eq!(
+            ResourceClass::for_conversion(&hwaccel_config("libx264")),
+            ResourceClass::Heavy
+        );
+
+        let mut upscale = hwaccel_config("h264_nvenc");
+        upscale.ml_upscale = Some("esrgan-2x".into());
+        assert_eq!(ResourceClass::for_conversion(&upscale), ResourceClass::Heavy);
+    }
+
     #[test]
     fn get_hwaccel_args_nvenc_codecs() {
         for codec in ["h264_nvenc", "hevc_nvenc", "av1_nvenc"] {
@@ -1182,3 +1130,2280 @@ mod upscale_thread_tests {
         assert_eq!(extract_proc(&result), 2);
     }
 }
+
+#[cfg(test)]
+mod upscale_vram_tests {
+    use crate::conversion::gpu::{
+        GpuMemory, parse_meminfo, parse_nvidia_smi, parse_nvidia_smi_list, parse_unified_memory,
+        parse_vulkan_gpu_listing,
+    };
+    use crate::conversion::upscale::{estimate_upscale_vram, plan_upscale_threads};
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn gpu(available_gib: u64) -> GpuMemory {
+        GpuMemory {
+            total_bytes: 8 * GIB,
+            available_bytes: available_gib * GIB,
+            source: "test",
+        }
+    }
+
+    fn extract_proc(threads_str: &str) -> u32 {
+        threads_str.split(':').nth(1).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn parses_nvidia_smi_output() {
+        let memory = parse_nvidia_smi("8192, 6144\n", 0).unwrap();
+        assert_eq!(memory.total_bytes, 8 * GIB);
+        assert_eq!(memory.available_bytes, 6 * GIB);
+        assert!(parse_nvidia_smi("N/A, N/A", 0).is_none());
+
+        let second = parse_nvidia_smi("8192, 6144\n24576, 20480\n", 1).unwrap();
+        assert_eq!(second.total_bytes, 24 * GIB);
+        assert!(parse_nvidia_smi("8192, 6144\n", 1).is_none());
+    }
+
+    #[test]
+    fn parses_vulkan_gpu_listing() {
+        let stderr = "\
+[AVHWDeviceContext @ 0x600000c0] GPU listing:
+[AVHWDeviceContext @ 0x600000c0]     0: Intel(R) UHD Graphics 770 (integrated) (0x4680)
+[AVHWDeviceContext @ 0x600000c0]     1: NVIDIA GeForce RTX 4090 (discrete) (0x2684)
+[AVHWDeviceContext @ 0x600000c0]     2: NVIDIA GeForce RTX 3060 (discrete) (0x2504)
+[AVHWDeviceContext @ 0x600000c0]     3: llvmpipe (LLVM 15.0.7, 256 bits) (software) (0x0)
+[AVHWDeviceContext @ 0x600000c0] Unable to find device with index 999!
+";
+        let devices = parse_vulkan_gpu_listing(stderr);
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[0].name, "Intel(R) UHD Graphics 770");
+        assert_eq!(devices[0].kind, "integrated");
+        assert_eq!(devices[0].nvenc_index, None);
+        assert_eq!(devices[1].index, 1);
+        assert_eq!(devices[1].nvenc_index, Some(0));
+        assert_eq!(devices[2].nvenc_index, Some(1));
+    }
+
+    #[test]
+    fn parses_nvidia_smi_device_list() {
+        let devices = parse_nvidia_smi_list(
+            "GPU 0: NVIDIA GeForce RTX 3080 (UUID: GPU-1234)\nGPU 1: NVIDIA A100 (UUID: GPU-5678)\n",
+        );
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[1].name, "NVIDIA A100");
+        assert_eq!(devices[1].nvenc_index, Some(1));
+    }
+
+    #[test]
+    fn unified_memory_reserves_a_quarter() {
+        let memory = parse_unified_memory("17179869184\n").unwrap();
+        assert_eq!(memory.available_bytes, 12 * GIB);
+    }
+
+    #[test]
+    fn meminfo_total_is_read_in_bytes() {
+        let meminfo = "MemTotal:       16777216 kB\nMemFree:         1048576 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(16 * GIB));
+        assert_eq!(parse_meminfo("MemFree: 10 kB\n"), None);
+    }
+
+    #[test]
+    fn plan_without_gpu_info_uses_heuristic() {
+        let plan = plan_upscale_threads(854, 480, 2, None).unwrap();
+        assert_eq!(extract_proc(&plan), 4);
+    }
+
+    #[test]
+    fn plan_downgrades_concurrency_to_fit() {
+        // 1708×960 output ≈ 0.1 GB per frame; 4 frames need ~0.9 GB
+        let needed = estimate_upscale_vram(854, 480, 2, 4);
+        let tight = GpuMemory {
+            available_bytes: needed - 1,
+            ..gpu(8)
+        };
+        let plan = plan_upscale_threads(854, 480, 2, Some(&tight)).unwrap();
+        assert_eq!(extract_proc(&plan), 3);
+    }
+
+    #[test]
+    fn plan_rejects_8k_4x_on_8gb_gpu() {
+        let err = plan_upscale_threads(7680, 4320, 4, Some(&gpu(8))).unwrap_err();
+        assert!(err.to_string().contains("GPU memory"));
+    }
+
+    #[test]
+    fn plan_accepts_fullhd_4x_on_8gb_gpu() {
+        let plan = plan_upscale_threads(1920, 1080, 4, Some(&gpu(8))).unwrap();
+        assert_eq!(extract_proc(&plan), 1);
+    }
+}
+
+#[cfg(test)]
+mod governor_tests {
+    use crate::governor::{ResourceClass, ResourceGovernor, ResourceLimits};
+
+    #[test]
+    fn limits_are_enforced_per_class() {
+        let governor = ResourceGovernor::new(ResourceLimits {
+            max_hardware_encodes: 1,
+            max_heavy_jobs: 2,
+        });
+
+        let encode = governor.try_acquire(ResourceClass::HardwareEncoder).unwrap();
+        assert!(governor.try_acquire(ResourceClass::HardwareEncoder).is_none());
+
+        let _a = governor.try_acquire(ResourceClass::Heavy).unwrap();
+        let _b = governor.try_acquire(ResourceClass::Heavy).unwrap();
+        assert!(governor.try_acquire(ResourceClass::Heavy).is_none());
+
+        drop(encode);
+        assert!(governor.try_acquire(ResourceClass::HardwareEncoder).is_some());
+    }
+
+    #[test]
+    fn raising_limit_frees_slots() {
+        let governor = ResourceGovernor::new(ResourceLimits {
+            max_hardware_encodes: 1,
+            max_heavy_jobs: 1,
+        });
+        let _held = governor.try_acquire(ResourceClass::Heavy).unwrap();
+        assert!(governor.try_acquire(ResourceClass::Heavy).is_none());
+
+        governor
+            .set_limits(ResourceLimits {
+                max_hardware_encodes: 1,
+                max_heavy_jobs: 2,
+            })
+            .unwrap();
+        assert!(governor.try_acquire(ResourceClass::Heavy).is_some());
+        assert_eq!(governor.limits().max_heavy_jobs, 2);
+    }
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        let governor = ResourceGovernor::default();
+        assert!(
+            governor
+                .set_limits(ResourceLimits {
+                    max_hardware_encodes: 0,
+                    max_heavy_jobs: 1,
+                })
+                .is_err()
+        );
+        assert_eq!(governor.limits(), ResourceLimits::default());
+    }
+}
+
+/// End-to-end harness: generates synthetic media with the system ffmpeg
+/// (`testsrc` + `sine`), runs the worker's real argument building and event
+/// loop against it headlessly, and checks the files it produces. Tests skip
+/// when ffmpeg is not on PATH.
+#[cfg(test)]
+mod media_harness_tests {
+    use std::path::{Path, PathBuf};
+    use std::process::Stdio;
+
+    use tauri_plugin_shell::process::{CommandEvent, TerminatedPayload};
+    use tokio::io::AsyncReadExt;
+    use tokio::sync::mpsc;
+
+    use crate::conversion::args::{build_ffmpeg_args, build_output_path};
+    use crate::conversion::types::ConversionConfig;
+    use crate::conversion::worker::{ProgressTracker, drain_ffmpeg_events, relay_ffmpeg_events};
+    use crate::events::RecordingSink;
+
+    struct HeadlessRun {
+        exit_code: Option<i32>,
+        progress: Vec<f64>,
+        log: Vec<String>,
+    }
+
+    impl HeadlessRun {
+        fn from_events(exit_code: Option<i32>, sink: &RecordingSink) -> Self {
+            Self {
+                exit_code,
+                progress: sink
+                    .payloads("conversion-progress")
+                    .iter()
+                    .filter_map(|p| p["progress"].as_f64())
+                    .collect(),
+                log: sink
+                    .payloads("conversion-log")
+                    .iter()
+                    .filter_map(|p| p["line"].as_str().map(str::to_string))
+                    .collect(),
+            }
+        }
+    }
+
+    fn ffmpeg_available() -> bool {
+        std::process::Command::new("ffmpeg")
+            .arg("-version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|s| s.success())
+    }
+
+    macro_rules! require_ffmpeg {
+        () => {
+            if !ffmpeg_available() {
+                eprintln!("skipping: ffmpeg not found on PATH");
+                return;
+            }
+        };
+    }
+
+    fn base_config(container: &str) -> ConversionConfig {
+        ConversionConfig {
+            container: container.into(),
+            video_codec: "mpeg4".into(),
+            video_bitrate_mode: "bitrate".into(),
+            video_bitrate: "1000".into(),
+            scaling_algorithm: "bicubic".into(),
+            ..Default::default()
+        }
+    }
+
+    /// Writes a clip with a test pattern and a 440 Hz tone.
+    fn synth_clip(dir: &Path, name: &str, seconds: u32) -> PathBuf {
+        let path = dir.join(name);
+        let status = std::process::Command::new("ffmpeg")
+            .args([
+                "-hide_banner",
+                "-loglevel",
+                "error",
+                "-y",
+                "-f",
+                "lavfi",
+                "-i",
+            ])
+            .arg(format!("testsrc=duration={seconds}:size=320x240:rate=25"))
+            .args(["-f", "lavfi", "-i"])
+            .arg(format!("sine=frequency=440:duration={seconds}"))
+            .args(["-c:v", "mpeg4", "-c:a", "aac", "-shortest"])
+            .arg(&path)
+            .status()
+            .expect("failed to run ffmpeg");
+        assert!(status.success(), "could not synthesize {name}");
+        path
+    }
+
+    /// Runs ffmpeg with `args` and feeds its output through the worker's
+    /// event loop, the way the sidecar's events would arrive.
+    fn run_headless(args: Vec<String>, expected_duration: f64) -> HeadlessRun {
+        tauri::async_runtime::block_on(async move {
+            let (tx, mut rx) = mpsc::channel(64);
+            let producer = tauri::async_runtime::spawn(async move {
+                let mut child = tokio::process::Command::new("ffmpeg")
+                    .args(&args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .expect("failed to spawn ffmpeg");
+                let mut stderr = child.stderr.take().unwrap();
+                let mut buf = [0u8; 4096];
+                while let Ok(n) = stderr.read(&mut buf).await
+                    && n > 0
+                {
+                    let _ = tx.send(CommandEvent::Stderr(buf[..n].to_vec())).await;
+                }
+                let status = child.wait().await.expect("ffmpeg did not exit");
+                let _ = tx
+                    .send(CommandEvent::Terminated(TerminatedPayload {
+                        code: status.code(),
+                        signal: None,
+                    }))
+                    .await;
+            });
+
+            let sink = RecordingSink::default();
+            let exit_code = relay_ffmpeg_events(&sink, "job", &mut rx, expected_duration).await;
+            producer.await.unwrap();
+
+            HeadlessRun::from_events(exit_code, &sink)
+        })
+    }
+
+    fn probe_streams(path: &str) -> String {
+        let output = std::process::Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-show_entries",
+                "stream=codec_type,codec_name",
+                "-of",
+                "csv=p=0",
+                path,
+            ])
+            .output()
+            .expect("failed to run ffprobe");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[test]
+    fn event_loop_splits_lines_and_tracks_progress() {
+        let run = tauri::async_runtime::block_on(async {
+            let (tx, mut rx) = mpsc::channel(8);
+            tx.send(CommandEvent::Stderr(
+                b"  Duration: 00:00:10.00, start: 0.000000\n".to_vec(),
+            ))
+            .await
+            .unwrap();
+            tx.send(CommandEvent::Stderr(
+                b"frame=1 time=00:00:02.50 bitrate=1\rframe=2 time=00:00:05.00 bitrate=1\r"
+                    .to_vec(),
+            ))
+            .await
+            .unwrap();
+            tx.send(CommandEvent::Terminated(TerminatedPayload {
+                code: Some(0),
+                signal: None,
+            }))
+            .await
+            .unwrap();
+            drop(tx);
+
+            let mut tracker = ProgressTracker::new(0.0);
+            let mut progress = Vec::new();
+            let mut lines = 0;
+            let code = drain_ffmpeg_events(&mut rx, |line| {
+                lines += 1;
+                progress.extend(tracker.update(line));
+            })
+            .await;
+            (code, progress, lines)
+        });
+
+        assert_eq!(run.0, Some(0));
+        assert_eq!(run.1, vec![25.0, 50.0]);
+        assert_eq!(run.2, 3);
+    }
+
+    #[test]
+    fn converts_synthetic_clip_to_mkv() {
+        require_ffmpeg!();
+        let dir = tempfile::tempdir().unwrap();
+        let input = synth_clip(dir.path(), "pattern.mp4", 2);
+        let input = input.to_string_lossy().to_string();
+
+        let config = base_config("mkv");
+        let output = build_output_path(&input, &config.container, None);
+        let mut args = vec!["-y".to_string()];
+        args.extend(build_ffmpeg_args(&input, &output, &config));
+
+        let run = run_headless(args, 2.0);
+        assert_eq!(
+            run.exit_code,
+            Some(0),
+            "ffmpeg failed:\n{}",
+            run.log.join("\n")
+        );
+        assert!(run.progress.last().is_some_and(|p| *p > 90.0));
+
+        let streams = probe_streams(&output);
+        assert!(streams.contains("mpeg4,video"), "{streams}");
+        assert!(streams.contains("aac,audio"), "{streams}");
+    }
+
+    #[test]
+    fn trimmed_conversion_reports_progress_against_trim() {
+        require_ffmpeg!();
+        let dir = tempfile::tempdir().unwrap();
+        let input = synth_clip(dir.path(), "long.mp4", 4);
+        let input = input.to_string_lossy().to_string();
+
+        let mut config = base_config("mp4");
+        config.start_time = Some("00:00:01".into());
+        config.end_time = Some("00:00:02".into());
+        let output = build_output_path(&input, &config.container, Some("trimmed".into()));
+        let mut args = vec!["-y".to_string()];
+        args.extend(build_ffmpeg_args(&input, &output, &config));
+
+        let run = run_headless(args, 1.0);
+        assert_eq!(
+            run.exit_code,
+            Some(0),
+            "ffmpeg failed:\n{}",
+            run.log.join("\n")
+        );
+        assert!(Path::new(&output).exists());
+        assert!(run.progress.iter().all(|p| *p <= 100.0));
+    }
+
+    #[test]
+    fn failing_conversion_surfaces_exit_code() {
+        require_ffmpeg!();
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.mp4").to_string_lossy().to_string();
+        let config = base_config("mp4");
+        let output = build_output_path(&missing, &config.container, None);
+
+        let run = run_headless(build_ffmpeg_args(&missing, &output, &config), 0.0);
+        assert_ne!(run.exit_code, Some(0));
+        assert!(!Path::new(&output).exists());
+    }
+}
+
+#[cfg(test)]
+mod event_sink_tests {
+    use std::time::Duration;
+
+    use spatial_maker::ProgressEvent;
+
+    use crate::events::RecordingSink;
+    use crate::spatial::types::FrameStats;
+    use crate::spatial::worker::ProgressRelay;
+
+    fn progress(sink: &RecordingSink) -> Vec<(String, f64)> {
+        sink.payloads("spatial-progress")
+            .iter()
+            .map(|p| {
+                (
+                    p["stage"].as_str().unwrap_or_default().to_string(),
+                    p["progress"].as_f64().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spatial_progress_maps_to_events() {
+        let sink = RecordingSink::default();
+        let relay = ProgressRelay::new(sink.clone(), "job");
+        relay.relay(ProgressEvent::Preprocess);
+        relay.relay(ProgressEvent::Inference);
+        for done in [50, 50, 100] {
+            relay.relay(ProgressEvent::Frames {
+                done,
+                total: Some(100),
+            });
+        }
+        relay.relay(ProgressEvent::Encode);
+
+        assert_eq!(
+            progress(&sink),
+            vec![
+                ("preprocess".to_string(), 0.0),
+                ("depth_stereo".to_string(), 0.0),
+                ("depth_stereo".to_string(), 42.5),
+                ("depth_stereo".to_string(), 85.0),
+                ("encode".to_string(), 85.0),
+            ]
+        );
+        let lines: Vec<String> = sink
+            .payloads("spatial-log")
+            .iter()
+            .filter_map(|p| p["line"].as_str().map(str::to_string))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "[SPATIAL] preprocess",
+                "[SPATIAL] depth_stereo",
+                "[SPATIAL] encode"
+            ]
+        );
+    }
+
+    #[test]
+    fn spatial_progress_is_reported_once_per_percent() {
+        let sink = RecordingSink::default();
+        let relay = ProgressRelay::new(sink.clone(), "job");
+        for done in 0..1000 {
+            relay.relay(ProgressEvent::Frames {
+                done,
+                total: Some(1000),
+            });
+        }
+        assert_eq!(progress(&sink).len(), 85);
+
+        // Without a total, every 100 frames still reach the watchdog
+        let sink = RecordingSink::default();
+        let relay = ProgressRelay::new(sink.clone(), "job");
+        for done in 0..1000 {
+            relay.relay(ProgressEvent::Frames { done, total: None });
+        }
+        assert_eq!(progress(&sink).len(), 10);
+        assert!(progress(&sink).iter().all(|(_, pct)| *pct == 0.0));
+    }
+
+    #[test]
+    fn slow_spatial_progress_is_repeated() {
+        let sink = RecordingSink::default();
+        let relay =
+            ProgressRelay::new(sink.clone(), "job").with_keepalive(Duration::from_millis(50));
+        let frame = |done| ProgressEvent::Frames {
+            done,
+            total: Some(10_000),
+        };
+        relay.relay(frame(1));
+        relay.relay(frame(2));
+        assert_eq!(progress(&sink).len(), 1);
+
+        // Same percent, but long enough since the last event
+        std::thread::sleep(Duration::from_millis(60));
+        relay.relay(frame(3));
+        assert_eq!(progress(&sink).len(), 2);
+        assert_eq!(sink.payloads("spatial-log").len(), 1);
+    }
+
+    #[test]
+    fn frame_events_carry_counts() {
+        let sink = RecordingSink::default();
+        let relay = ProgressRelay::new(sink.clone(), "job");
+        relay.relay(ProgressEvent::Inference);
+        relay.relay(ProgressEvent::Frames {
+            done: 20,
+            total: Some(200),
+        });
+        relay.relay(ProgressEvent::Encode);
+
+        let payloads = sink.payloads("spatial-progress");
+        assert!(payloads[0].get("frames").is_none());
+        assert_eq!(payloads[1]["frames"]["done"], 20);
+        assert_eq!(payloads[1]["frames"]["total"], 200);
+        assert!(payloads[1]["frames"]["etaSecs"].is_null());
+        assert!(payloads[2].get("frames").is_none());
+    }
+
+    #[test]
+    fn frame_stats_estimate_time_remaining() {
+        let stats = FrameStats::new(150, Some(1000), 100, 4.0);
+        assert_eq!(stats.fps, Some(25.0));
+        assert_eq!(stats.eta_secs, Some(34.0));
+
+        let unknown_total = FrameStats::new(150, None, 100, 4.0);
+        assert_eq!(unknown_total.fps, Some(25.0));
+        assert_eq!(unknown_total.eta_secs, None);
+
+        let first = FrameStats::new(10, Some(1000), 0, 0.0);
+        assert_eq!(first.fps, None);
+        assert_eq!(first.eta_secs, None);
+    }
+}
+
+#[cfg(test)]
+mod sidecar_tests {
+    use std::io::{Error, ErrorKind};
+
+    use serde_json::json;
+
+    use crate::conversion::error::ConversionError;
+    use crate::conversion::sidecar::{
+        SidecarProblem, classify_io_error, expected_sidecar_path, sidecar_error,
+    };
+    use crate::conversion::types::SidecarIssuePayload;
+
+    #[test]
+    fn missing_binary_is_reported_with_its_path() {
+        let issue = classify_io_error("ffmpeg", &Error::from(ErrorKind::NotFound)).unwrap();
+        assert_eq!(issue.problem, SidecarProblem::Missing);
+        assert_eq!(issue.binary, "ffmpeg");
+        assert_eq!(
+            issue.expected_path,
+            expected_sidecar_path("ffmpeg").to_string_lossy()
+        );
+        assert!(issue.suggested_fix.contains("setup:ffmpeg"));
+    }
+
+    #[test]
+    fn permission_and_format_errors_are_classified() {
+        let issue = classify_io_error(
+            "realesrgan-ncnn-vulkan",
+            &Error::from(ErrorKind::PermissionDenied),
+        )
+        .unwrap();
+        assert_eq!(issue.problem, SidecarProblem::NotExecutable);
+        assert!(issue.suggested_fix.contains("setup:upscaler"));
+
+        let code = if cfg!(windows) { 193 } else { 8 };
+        let issue = classify_io_error("ffprobe", &Error::from_raw_os_error(code)).unwrap();
+        assert_eq!(issue.problem, SidecarProblem::Corrupted);
+    }
+
+    #[test]
+    fn unrelated_failures_stay_shell_errors() {
+        assert!(classify_io_error("ffmpeg", &Error::from(ErrorKind::Interrupted)).is_none());
+
+        let err = sidecar_error(
+            "ffmpeg",
+            tauri_plugin_shell::Error::Io(Error::from(ErrorKind::Interrupted)),
+        );
+        assert!(matches!(err, ConversionError::Shell(_)));
+
+        let err = sidecar_error(
+            "ffmpeg",
+            tauri_plugin_shell::Error::Io(Error::from(ErrorKind::NotFound)),
+        );
+        assert!(matches!(err, ConversionError::SidecarUnavailable(_)));
+    }
+
+    #[test]
+    fn payload_flattens_issue_fields() {
+        let issue = classify_io_error("ffmpeg", &Error::from(ErrorKind::NotFound)).unwrap();
+        let value = serde_json::to_value(SidecarIssuePayload {
+            id: "job".to_string(),
+            issue: issue.clone(),
+        })
+        .unwrap();
+        assert_eq!(value["id"], json!("job"));
+        assert_eq!(value["binary"], json!("ffmpeg"));
+        assert_eq!(value["problem"], json!("missing"));
+        assert_eq!(value["expectedPath"], json!(issue.expected_path));
+        assert!(value["suggestedFix"].is_string());
+    }
+}
+
+#[cfg(test)]
+mod image_tests {
+    use std::fs;
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::conversion::args::{build_ffmpeg_args, build_output_path, validate_task_input};
+    use crate::conversion::types::ConversionConfig;
+    use crate::conversion::utils::{collect_image_files, is_image_container, is_image_file};
+
+    fn image_config(container: &str, quality: u32) -> ConversionConfig {
+        ConversionConfig {
+            container: container.into(),
+            quality,
+            hw_decode: true,
+            ..Default::default()
+        }
+    }
+
+    fn arg_after<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|a| a == flag)
+            .map(|i| args[i + 1].as_str())
+    }
+
+    fn temp_dir(label: &str) -> std::path::PathBuf {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("frame-{}-{}", label, ts));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn heic_to_jpeg_writes_single_frame() {
+        let config = image_config("jpg", 100);
+        let args = build_ffmpeg_args("photo.heic", "photo.jpg", &config);
+
+        assert_eq!(args[0], "-i");
+        assert_eq!(arg_after(&args, "-c:v"), Some("mjpeg"));
+        assert_eq!(arg_after(&args, "-q:v"), Some("2"));
+        assert_eq!(arg_after(&args, "-map"), Some("0:v:0"));
+        assert_eq!(arg_after(&args, "-frames:v"), Some("1"));
+        assert!(!args.iter().any(|a| a == "-c:a" || a == "-preset" || a == "-hwaccel"));
+        assert_eq!(args.last().map(String::as_str), Some("photo.jpg"));
+    }
+
+    #[test]
+    fn quality_maps_onto_encoder_scales() {
+        let webp = build_ffmpeg_args("a.png", "a.webp", &image_config("webp", 80));
+        assert_eq!(arg_after(&webp, "-c:v"), Some("libwebp"));
+        assert_eq!(arg_after(&webp, "-quality"), Some("80"));
+
+        let avif = build_ffmpeg_args("a.png", "a.avif", &image_config("avif", 50));
+        assert_eq!(arg_after(&avif, "-c:v"), Some("libaom-av1"));
+        assert_eq!(arg_after(&avif, "-still-picture"), Some("1"));
+        assert_eq!(arg_after(&avif, "-crf"), Some("32"));
+
+        let worst = build_ffmpeg_args("a.png", "a.jpg", &image_config("jpg", 0));
+        assert_eq!(arg_after(&worst, "-q:v"), Some("31"));
+
+        let png = build_ffmpeg_args("a.heic", "a.png", &image_config("png", 10));
+        assert_eq!(arg_after(&png, "-c:v"), Some("png"));
+        assert!(!png.iter().any(|a| a == "-quality" || a == "-q:v"));
+    }
+
+    #[test]
+    fn image_jobs_keep_transform_filters() {
+        let mut config = image_config("webp", 75);
+        config.rotation = "90".into();
+        config.resolution = "720p".into();
+        let args = build_ffmpeg_args("a.jxl", "a.webp", &config);
+        assert_eq!(
+            arg_after(&args, "-vf"),
+            Some("transpose=1,scale=-2:720:flags=lanczos")
+        );
+    }
+
+    #[test]
+    fn image_validation() {
+        let input = temp_dir("image-validate").join("photo.heic");
+        fs::write(&input, b"test").unwrap();
+        let input = input.to_string_lossy().into_owned();
+
+        // Video codec and bitrate settings do not apply to image outputs
+        let mut config = image_config("avif", 60);
+        config.video_codec = "vp9".into();
+        config.video_bitrate_mode = "bitrate".into();
+        config.video_bitrate = "".into();
+        assert!(validate_task_input(&input, &config).is_ok());
+
+        config.quality = 101;
+        assert!(validate_task_input(&input, &config).is_err());
+
+        let mut config = image_config("png", 50);
+        config.ml_upscale = Some("esrgan-2x".into());
+        assert!(validate_task_input(&input, &config).is_err());
+
+        assert_eq!(
+            build_output_path("/photos/IMG_0001.HEIC", "jpg", None),
+            "/photos/IMG_0001.HEIC_converted.jpg"
+        );
+    }
+
+    #[test]
+    fn image_containers_and_files() {
+        assert!(is_image_container("AVIF"));
+        assert!(is_image_container("jpeg"));
+        assert!(!is_image_container("mp4"));
+        assert!(is_image_file(Path::new("IMG_0001.HEIC")));
+        assert!(is_image_file(Path::new("scan.jxl")));
+        assert!(!is_image_file(Path::new("clip.mov")));
+        assert!(!is_image_file(Path::new("README")));
+    }
+
+    #[test]
+    fn collects_images_from_folder() {
+        let dir = temp_dir("image-batch");
+        fs::write(dir.join("b.heic"), b"x").unwrap();
+        fs::write(dir.join("a.PNG"), b"x").unwrap();
+        fs::write(dir.join("notes.txt"), b"x").unwrap();
+        fs::write(dir.join(".hidden.jpg"), b"x").unwrap();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested").join("c.avif"), b"x").unwrap();
+
+        let names = |files: Vec<std::path::PathBuf>| {
+            files
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(&dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let flat = collect_image_files(&dir, false).unwrap();
+        assert_eq!(names(flat), vec!["a.PNG", "b.heic"]);
+
+        let recursive = collect_image_files(&dir, true).unwrap();
+        assert_eq!(names(recursive), vec!["a.PNG", "b.heic", "nested/c.avif"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod contact_sheet_tests {
+    use crate::conversion::contact_sheet::{
+        build_contact_sheet_args, build_contact_sheet_path, escape_drawtext, format_duration,
+        validate_contact_sheet_options,
+    };
+    use crate::conversion::types::ContactSheetOptions;
+
+    fn filter_graph(args: &[String]) -> &str {
+        let index = args.iter().position(|a| a == "-vf").unwrap();
+        &args[index + 1]
+    }
+
+    #[test]
+    fn samples_one_frame_per_cell() {
+        let options = ContactSheetOptions::default();
+        let args = build_contact_sheet_args("in.mp4", "sheet.jpg", 600.0, "in.mp4", &options);
+        let graph = filter_graph(&args);
+
+        assert!(graph.starts_with("fps=20/600.000,scale=320:-2,drawtext="));
+        assert!(graph.contains("tile=4x5:padding=4:margin=4"));
+        assert!(graph.contains("pad=iw:ih+48:0:48"));
+        assert!(graph.contains("%{pts\\\\:hms}"));
+        assert_eq!(args.last().map(String::as_str), Some("sheet.jpg"));
+        assert!(args.windows(2).any(|w| w[0] == "-frames:v" && w[1] == "1"));
+    }
+
+    #[test]
+    fn timestamps_can_be_turned_off() {
+        let options = ContactSheetOptions {
+            columns: 3,
+            rows: 2,
+            show_timestamps: false,
+            ..Default::default()
+        };
+        let args = build_contact_sheet_args("in.mp4", "out.jpg", 12.5, "header", &options);
+        let graph = filter_graph(&args);
+        assert!(graph.starts_with("fps=6/12.500,scale=320:-2,tile=3x2"));
+        assert_eq!(graph.matches("drawtext=").count(), 1);
+        assert!(graph.contains("expansion=none"));
+    }
+
+    #[test]
+    fn header_text_is_escaped_for_the_filtergraph() {
+        assert_eq!(escape_drawtext("plain name"), "plain name");
+        assert_eq!(escape_drawtext("a:b"), "a\\\\:b");
+        assert_eq!(escape_drawtext("it's"), "it\\\\\\'s");
+        assert_eq!(escape_drawtext("x,y[1];"), "x\\,y\\[1\\]\\;");
+        assert_eq!(escape_drawtext("line\nbreak"), "linebreak");
+    }
+
+    #[test]
+    fn durations_and_paths() {
+        assert_eq!(format_duration(3725.4), "01:02:05");
+        assert_eq!(format_duration(-1.0), "00:00:00");
+        assert_eq!(
+            build_contact_sheet_path("/videos/clip.mov"),
+            "/videos/clip.mov_contact_sheet.jpg"
+        );
+    }
+
+    #[test]
+    fn rejects_unreasonable_grids() {
+        assert!(validate_contact_sheet_options(&ContactSheetOptions::default()).is_ok());
+        let empty = ContactSheetOptions {
+            columns: 0,
+            ..Default::default()
+        };
+        assert!(validate_contact_sheet_options(&empty).is_err());
+        let tiny = ContactSheetOptions {
+            thumb_width: 16,
+            ..Default::default()
+        };
+        assert!(validate_contact_sheet_options(&tiny).is_err());
+    }
+}
+
+#[cfg(test)]
+mod dedupe_tests {
+    use std::fs;
+
+    use crate::conversion::dedupe::{
+        DuplicateRegistry, JobEntry, JobKey, RECENT_JOB_LIMIT, config_fingerprint, job_key,
+        source_fingerprint,
+    };
+    use crate::conversion::types::{ConversionConfig, SourceIdentity};
+
+    fn config(crf: u8) -> ConversionConfig {
+        ConversionConfig {
+            crf,
+            ..Default::default()
+        }
+    }
+
+    fn entry(id: &str, source: &str) -> JobEntry {
+        JobEntry {
+            id: id.into(),
+            file_path: format!("/videos/{}", source),
+            key: JobKey {
+                source: source.into(),
+                config: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn config_fingerprint_follows_settings() {
+        assert_eq!(
+            config_fingerprint(&config(23)),
+            config_fingerprint(&config(23))
+        );
+        assert_ne!(
+            config_fingerprint(&config(23)),
+            config_fingerprint(&config(18))
+        );
+    }
+
+    #[test]
+    fn content_hash_matches_copies_that_path_identity_does_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("clip.mp4");
+        let copy = dir.path().join("clip copy.mp4");
+        let other = dir.path().join("other.mp4");
+        fs::write(&original, b"same bytes").unwrap();
+        fs::write(&copy, b"same bytes").unwrap();
+        fs::write(&other, b"different!").unwrap();
+
+        let content = |path| source_fingerprint(path, SourceIdentity::ContentHash).unwrap();
+        assert_eq!(content(&original), content(&copy));
+        assert_ne!(content(&original), content(&other));
+
+        let by_path = |path| source_fingerprint(path, SourceIdentity::PathAndMtime).unwrap();
+        assert_eq!(by_path(&original), by_path(&original));
+        assert_ne!(by_path(&original), by_path(&copy));
+
+        let key = job_key(
+            original.to_str().unwrap(),
+            &config(23),
+            SourceIdentity::ContentHash,
+        );
+        assert_eq!(key.unwrap().source, content(&copy));
+
+        let missing = dir.path().join("missing.mp4");
+        assert!(source_fingerprint(&missing, SourceIdentity::PathAndMtime).is_err());
+    }
+
+    #[test]
+    fn large_files_are_hashed_by_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.bin");
+        let b = dir.path().join("b.bin");
+        let mut data = vec![0u8; 8 * 1024 * 1024];
+        fs::write(&a, &data).unwrap();
+        let last = data.len() - 1;
+        data[last] = 1;
+        fs::write(&b, &data).unwrap();
+
+        let content = |path| source_fingerprint(path, SourceIdentity::ContentHash).unwrap();
+        assert_ne!(content(&a), content(&b));
+    }
+
+    #[test]
+    fn registry_tracks_active_and_recent_jobs() {
+        let mut registry = DuplicateRegistry::default();
+        let first = entry("a", "clip.mp4");
+        registry.register(first.clone());
+
+        // A retry of the same task is not its own duplicate
+        assert!(registry.find("a", &first.key).is_none());
+        assert_eq!(
+            registry.find("b", &first.key).map(|e| e.id.as_str()),
+            Some("a")
+        );
+
+        // Failed jobs are forgotten, finished ones remembered
+        registry.finish("a", false);
+        assert!(registry.find("b", &first.key).is_none());
+        registry.register(first.clone());
+        registry.finish("a", true);
+        assert_eq!(
+            registry.find("b", &first.key).map(|e| e.file_path.as_str()),
+            Some("/videos/clip.mp4")
+        );
+
+        for i in 0..RECENT_JOB_LIMIT {
+            let id = format!("job-{}", i);
+            registry.register(entry(&id, &format!("{}.mp4", i)));
+            registry.finish(&id, true);
+        }
+        assert!(registry.find("b", &first.key).is_none());
+    }
+}
+
+#[cfg(test)]
+mod source_check_tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use tokio::sync::{mpsc, watch};
+
+    use crate::conversion::manager::{ConversionManager, ManagerMessage, poll_sources};
+    use crate::conversion::types::ConversionTask;
+
+    fn task(id: &str) -> ConversionTask {
+        ConversionTask {
+            id: id.to_string(),
+            file_path: format!("/videos/{}.mov", id),
+            output_name: None,
+            config: Default::default(),
+        }
+    }
+
+    #[test]
+    fn sources_are_polled_only_while_tasks_are_queued() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (queued, watch) = watch::channel(false);
+        tauri::async_runtime::block_on(async move {
+            tauri::async_runtime::spawn(poll_sources(tx, watch, Duration::from_millis(20)));
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(rx.try_recv().is_err());
+
+            queued.send_replace(true);
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(matches!(rx.try_recv(), Ok(ManagerMessage::CheckSources)));
+
+            queued.send_replace(false);
+            // A check already waiting out its interval is dropped
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            while rx.try_recv().is_ok() {}
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            assert!(rx.try_recv().is_err());
+        });
+    }
+
+    #[test]
+    fn cancelling_a_task_with_a_missing_source_forgets_it() {
+        let mut missing = HashMap::from([("a".to_string(), task("a"))]);
+        let cancelled = Mutex::new(HashSet::from(["a".to_string(), "b".to_string()]));
+
+        ConversionManager::forget_missing(&mut missing, &cancelled, "a");
+        ConversionManager::forget_missing(&mut missing, &cancelled, "b");
+        assert!(missing.is_empty());
+        // "b" is queued or running, where its mark still has to be seen
+        assert_eq!(*cancelled.lock().unwrap(), HashSet::from(["b".to_string()]));
+    }
+}
+
+#[cfg(test)]
+mod alerts_tests {
+    use crate::alerts::{
+        AlertCenter, CompletionSound, QueueAlerts, QueueKind, gnome_banners_hidden,
+        macos_focus_active, resolve_sound, sound_names, windows_toasts_disabled,
+    };
+    use std::fs;
+
+    #[test]
+    fn do_not_disturb_silences_only_queues_that_respect_it() {
+        let alerts = QueueAlerts {
+            sound: CompletionSound::System {
+                name: "Glass".into(),
+            },
+            ..Default::default()
+        };
+        let outcome = alerts.outcome(false);
+        assert!(outcome.notify && outcome.sound);
+
+        let outcome = alerts.outcome(true);
+        assert!(!outcome.notify && !outcome.sound && outcome.do_not_disturb);
+
+        let loud = QueueAlerts {
+            respect_do_not_disturb: false,
+            ..alerts
+        };
+        assert!(loud.outcome(true).notify);
+        assert!(!QueueAlerts::default().outcome(false).sound);
+    }
+
+    #[test]
+    fn alerts_are_kept_per_queue() {
+        let center = AlertCenter::default();
+        center.set_alerts(
+            QueueKind::Spatial,
+            QueueAlerts {
+                notify: false,
+                ..Default::default()
+            },
+        );
+        assert!(!center.alerts(QueueKind::Spatial).notify);
+        assert_eq!(center.alerts(QueueKind::Conversion), QueueAlerts::default());
+
+        let parsed: QueueAlerts =
+            serde_json::from_str(r#"{"sound": {"kind": "file", "path": "/tmp/done.wav"}}"#)
+                .unwrap();
+        assert!(parsed.notify && parsed.respect_do_not_disturb);
+        assert_eq!(
+            parsed.sound,
+            CompletionSound::File {
+                path: "/tmp/done.wav".into()
+            }
+        );
+    }
+
+    #[test]
+    fn system_sounds_resolve_inside_their_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("complete.oga"), b"").unwrap();
+        fs::write(dir.path().join("bell.oga"), b"").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        assert_eq!(sound_names(dir.path(), "oga"), vec!["bell", "complete"]);
+
+        let system = |name: &str| CompletionSound::System { name: name.into() };
+        assert_eq!(
+            resolve_sound(&system("complete"), dir.path(), "oga"),
+            Some(dir.path().join("complete.oga"))
+        );
+        assert!(resolve_sound(&system("missing"), dir.path(), "oga").is_none());
+        assert!(resolve_sound(&system("../complete"), dir.path(), "oga").is_none());
+        assert!(resolve_sound(&CompletionSound::Off, dir.path(), "oga").is_none());
+
+        let file = CompletionSound::File {
+            path: dir.path().join("notes.txt").to_string_lossy().into_owned(),
+        };
+        assert!(resolve_sound(&file, dir.path(), "oga").is_some());
+    }
+
+    #[test]
+    fn reads_do_not_disturb_state_from_each_platform() {
+        assert!(macos_focus_active(
+            r#"{"data": [{"storeAssertionRecords": [{"assertionDetails": {}}]}]}"#
+        ));
+        assert!(!macos_focus_active(
+            r#"{"data": [{"storeAssertionRecords": []}]}"#
+        ));
+        assert!(!macos_focus_active(r#"{"data": [{}]}"#));
+        assert!(!macos_focus_active("not json"));
+
+        let reg = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Notifications\\Settings\r\n    NOC_GLOBAL_SETTING_TOASTS_ENABLED    REG_DWORD    0x0\r\n";
+        assert!(windows_toasts_disabled(reg));
+        assert!(!windows_toasts_disabled(&reg.replace("0x0", "0x1")));
+        assert!(!windows_toasts_disabled(""));
+
+        assert!(gnome_banners_hidden("false\n"));
+        assert!(!gnome_banners_hidden("true\n"));
+    }
+}
+
+#[cfg(test)]
+mod watchdog_tests {
+    use std::time::Duration;
+
+    use crate::conversion::error::ConversionError;
+    use crate::events::{EventSink, RecordingSink};
+    use crate::watchdog::{PauseClock, WorkerFault, format_limit, supervise, time_limit};
+
+    #[test]
+    fn returns_worker_result_and_forwards_events() {
+        let sink = RecordingSink::default();
+        let result = tauri::async_runtime::block_on(supervise(
+            sink.clone(),
+            PauseClock::default(),
+            Duration::from_secs(5),
+            None,
+            |events| async move {
+                events.emit("conversion-log", "working");
+                42
+            },
+        ));
+        assert_eq!(result, Ok(42));
+        assert_eq!(sink.payloads("conversion-log").len(), 1);
+    }
+
+    #[test]
+    fn panic_is_reported_with_its_message() {
+        let result: Result<(), WorkerFault> = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            PauseClock::default(),
+            Duration::from_secs(5),
+            None,
+            |_events| async move { panic!("bad frame {}", 7) },
+        ));
+        assert_eq!(
+            result,
+            Err(WorkerFault::Panicked {
+                message: "bad frame 7".to_string()
+            })
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Worker crashed: bad frame 7"
+        );
+    }
+
+    #[test]
+    fn silent_worker_is_stalled_but_chatty_one_is_not() {
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            PauseClock::default(),
+            Duration::from_millis(100),
+            None,
+            |_events| tokio::time::sleep(Duration::from_secs(30)),
+        ));
+        assert!(matches!(result, Err(WorkerFault::Stalled { .. })));
+
+        // Runs longer than the timeout, but reports in between
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            PauseClock::default(),
+            Duration::from_millis(200),
+            None,
+            |events| async move {
+                for _ in 0..6 {
+                    tokio::time::sleep(Duration::from_millis(60)).await;
+                    events.emit("spatial-progress", 0.5);
+                }
+                "done"
+            },
+        ));
+        assert_eq!(result, Ok("done"));
+    }
+
+    #[test]
+    fn paused_worker_is_not_stalled() {
+        // Silent for longer than the timeout, but paused for most of it
+        let pause = PauseClock::default();
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            pause.clone(),
+            Duration::from_millis(150),
+            None,
+            |events| async move {
+                events.emit("conversion-log", "started");
+                pause.pause();
+                tokio::time::sleep(Duration::from_millis(400)).await;
+                pause.resume();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                "done"
+            },
+        ));
+        assert_eq!(result, Ok("done"));
+
+        // The stall clock picks up again after a resume
+        let pause = PauseClock::default();
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            pause.clone(),
+            Duration::from_millis(150),
+            None,
+            |_events| async move {
+                pause.pause();
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                pause.resume();
+                tokio::time::sleep(Duration::from_secs(30)).await;
+            },
+        ));
+        assert!(matches!(result, Err(WorkerFault::Stalled { .. })));
+    }
+
+    #[test]
+    fn worker_past_its_time_limit_is_timed_out() {
+        // Keeps reporting, so only the time limit can stop it
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            PauseClock::default(),
+            Duration::from_secs(5),
+            Some(Duration::from_millis(150)),
+            |events| async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    events.emit("conversion-progress", 0.1);
+                }
+            },
+        ));
+        assert_eq!(result, Err(WorkerFault::TimedOut { limit_secs: 0 }));
+
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            PauseClock::default(),
+            Duration::from_secs(5),
+            Some(Duration::from_secs(5)),
+            |_events| async move { "done" },
+        ));
+        assert_eq!(result, Ok("done"));
+    }
+
+    #[test]
+    fn time_paused_does_not_count_towards_the_limit() {
+        let pause = PauseClock::default();
+        let result = tauri::async_runtime::block_on(supervise(
+            RecordingSink::default(),
+            pause.clone(),
+            Duration::from_secs(5),
+            Some(Duration::from_millis(200)),
+            |_events| async move {
+                pause.pause();
+                tokio::time::sleep(Duration::from_millis(400)).await;
+                pause.resume();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                "done"
+            },
+        ));
+        assert_eq!(result, Ok("done"));
+    }
+
+    #[test]
+    fn timed_out_is_its_own_error() {
+        let err = ConversionError::from(WorkerFault::TimedOut { limit_secs: 43_200 });
+        assert!(matches!(err, ConversionError::TimedOut(43_200)));
+        assert_eq!(err.to_string(), "Timed out: stopped at the 12h time limit");
+
+        let err = ConversionError::from(WorkerFault::Stalled { idle_secs: 600 });
+        assert!(matches!(err, ConversionError::Fault(_)));
+    }
+
+    #[test]
+    fn time_limit_formats_and_zero_means_none() {
+        assert_eq!(time_limit(Some(0)), None);
+        assert_eq!(time_limit(None), None);
+        assert_eq!(time_limit(Some(90)), Some(Duration::from_secs(90)));
+        assert_eq!(format_limit(5400), "90m");
+        assert_eq!(format_limit(45), "45s");
+    }
+
+    #[test]
+    fn fault_serializes_for_the_diagnostic_event() {
+        let stalled = serde_json::to_value(WorkerFault::Stalled { idle_secs: 600 }).unwrap();
+        assert_eq!(stalled["kind"], "stalled");
+        assert_eq!(stalled["idleSecs"], 600);
+    }
+}
+
+#[cfg(test)]
+mod spatial_extract_tests {
+    use crate::spatial::types::{SpatialConfig, SpatialImageFormat, SpatialLayout};
+    use crate::spatial::worker::{
+        build_extract_args, build_spatial_make_args, output_file, validate_extract_config,
+        validate_output_config,
+    };
+    use std::path::Path;
+
+    pub(super) fn spatial_config() -> SpatialConfig {
+        SpatialConfig {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn has_pair(args: &[String], first: &str, second: &str) -> bool {
+        args.windows(2).any(|w| w[0] == first && w[1] == second)
+    }
+
+    #[test]
+    fn no_trim_or_limit_skips_extraction() {
+        let mut config = spatial_config();
+        assert!(build_extract_args("in.mp4", "out.mkv", &config).is_none());
+
+        config.start_time = Some("  ".to_string());
+        assert!(build_extract_args("in.mp4", "out.mkv", &config).is_none());
+    }
+
+    #[test]
+    fn trim_cuts_before_and_after_input() {
+        let mut config = spatial_config();
+        config.start_time = Some("1:30".to_string());
+        config.end_time = Some("00:02:00.5".to_string());
+        let args = build_extract_args("in.mp4", "out.mkv", &config).unwrap();
+
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        assert!(has_pair(&args[..input], "-ss", "90.000"));
+        assert!(has_pair(&args[input..], "-t", "30.500"));
+        assert!(!args.contains(&"-vf".to_string()));
+        assert!(has_pair(&args, "-c:a", "copy"));
+        assert_eq!(args.last().unwrap(), "out.mkv");
+
+        config.start_time = None;
+        let args = build_extract_args("in.mp4", "out.mkv", &config).unwrap();
+        assert!(!args.contains(&"-ss".to_string()));
+        assert!(has_pair(&args, "-t", "120.500"));
+    }
+
+    #[test]
+    fn resolution_limits_only_scale_down() {
+        let mut config = spatial_config();
+        config.max_height = Some(1080);
+        let args = build_extract_args("in.mp4", "out.mkv", &config).unwrap();
+        assert!(has_pair(
+            &args,
+            "-vf",
+            "scale=w=iw:h='min(ih,1080)':force_original_aspect_ratio=decrease:force_divisible_by=2"
+        ));
+
+        config.max_width = Some(1920);
+        let args = build_extract_args("in.mp4", "out.mkv", &config).unwrap();
+        assert!(
+            args.iter()
+                .any(|a| a.starts_with("scale=w='min(iw,1920)':h='min(ih,1080)'"))
+        );
+    }
+
+    #[test]
+    fn invalid_trim_and_limits_are_rejected() {
+        let mut config = spatial_config();
+        config.start_time = Some("1:30".to_string());
+        config.end_time = Some("1:00".to_string());
+        assert!(validate_extract_config(&config).is_err());
+
+        config.end_time = Some("soon".to_string());
+        assert!(validate_extract_config(&config).is_err());
+
+        config.end_time = Some("2:00".to_string());
+        assert!(validate_extract_config(&config).is_ok());
+
+        config.max_width = Some(0);
+        assert!(validate_extract_config(&config).is_err());
+    }
+
+    #[test]
+    fn output_options_reach_the_spatial_cli() {
+        let mut config = spatial_config();
+        let args = build_spatial_make_args("in_sbs.mp4", "out.mov", &config);
+        assert!(has_pair(&args, "--format", "sbs"));
+        assert!(!args.contains(&"--quality".to_string()));
+
+        config.layout = SpatialLayout::TopAndBottom;
+        config.mvhevc_quality = Some(80);
+        let args = build_spatial_make_args("in_tb.mp4", "out.mov", &config);
+        assert!(has_pair(&args, "--input", "in_tb.mp4"));
+        assert!(has_pair(&args, "--format", "hou"));
+        assert!(has_pair(&args, "--quality", "0.80"));
+    }
+
+    #[test]
+    fn output_qualities_are_checked() {
+        let mut config = spatial_config();
+        assert!(validate_output_config(&config).is_ok());
+        assert_eq!(config.image_encoding().extension(), "jpg");
+
+        config.image_format = SpatialImageFormat::Png;
+        assert_eq!(config.image_encoding().extension(), "png");
+
+        config.image_quality = 0;
+        assert!(validate_output_config(&config).is_err());
+
+        config.image_quality = 90;
+        config.mvhevc_quality = Some(101);
+        assert!(validate_output_config(&config).is_err());
+    }
+
+    #[test]
+    fn outputs_follow_name_and_folder() {
+        let input = Path::new("clips").join("beach.mp4");
+        let mut config = spatial_config();
+        assert_eq!(
+            output_file(&input, &config, "_sbs", "mp4"),
+            Path::new("clips").join("beach_spatial_sbs.mp4")
+        );
+
+        let out = tempfile::tempdir().unwrap();
+        config.output_dir = Some(out.path().to_string_lossy().into_owned());
+        config.output_name = Some("3d/{stem}-vision".to_string());
+        assert!(validate_output_config(&config).is_ok());
+        assert_eq!(
+            output_file(&input, &config, "", "mov"),
+            out.path().join("beach-vision.mov")
+        );
+
+        config.output_name = Some("..".to_string());
+        assert!(validate_output_config(&config).is_err());
+
+        config.output_name = None;
+        config.output_dir = Some(out.path().join("missing").to_string_lossy().into_owned());
+        assert!(validate_output_config(&config).is_err());
+    }
+
+    #[test]
+    fn output_options_default_when_missing() {
+        let config: SpatialConfig = serde_json::from_value(serde_json::json!({
+            "encoderSize": "s",
+            "maxDisparity": 40,
+            "skipDownscale": true,
+            "duration": null,
+        }))
+        .unwrap();
+        assert_eq!(config.layout, SpatialLayout::SideBySide);
+        assert_eq!(config.image_format, SpatialImageFormat::Jpeg);
+        assert_eq!(config.image_quality, 95);
+        assert!(config.mvhevc);
+        assert!(!config.keep_stereo);
+    }
+}
+
+#[cfg(test)]
+mod spatial_batch_tests {
+    use super::spatial_extract_tests::spatial_config;
+    use crate::spatial::commands::{batch_output_names, collect_batch_inputs};
+    use crate::spatial::worker::output_file;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn batch_picks_up_photos_and_videos() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.MOV", "a.jpg", "notes.txt", ".hidden.mp4"] {
+            fs::write(dir.path().join(name), b"x").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("nested")).unwrap();
+        fs::write(dir.path().join("nested").join("c.mkv"), b"x").unwrap();
+
+        let names = |files: Vec<std::path::PathBuf>| {
+            files
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let all = collect_batch_inputs(dir.path(), false, None).unwrap();
+        assert_eq!(names(all), vec!["a.jpg", "b.MOV"]);
+
+        let recursive = collect_batch_inputs(dir.path(), true, None).unwrap();
+        assert_eq!(names(recursive), vec!["a.jpg", "b.MOV", "nested/c.mkv"]);
+
+        let videos = [".mov".to_string(), "MKV".to_string()];
+        let filtered = collect_batch_inputs(dir.path(), true, Some(&videos[..])).unwrap();
+        assert_eq!(names(filtered), vec!["b.MOV", "nested/c.mkv"]);
+    }
+
+    #[test]
+    fn batch_rejects_unsupported_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = ["txt".to_string()];
+        assert!(collect_batch_inputs(dir.path(), false, Some(&text[..])).is_err());
+        assert!(collect_batch_inputs(dir.path(), false, Some(&[][..])).is_err());
+    }
+
+    #[test]
+    fn batch_outputs_get_their_own_names() {
+        let files: Vec<PathBuf> = [
+            "in/a.jpg",
+            "in/a.png",
+            "in/b.mov",
+            "in/x/c.mp4",
+            "in/y/c.mp4",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        let mut config = spatial_config();
+
+        // Next to their inputs only the photos clash
+        let names = batch_output_names(&files, &config);
+        assert_eq!(
+            names,
+            [
+                Some("{stem}_jpg_spatial".to_string()),
+                Some("{stem}_png_spatial".to_string()),
+                None,
+                None,
+                None
+            ]
+        );
+
+        // In one output folder the videos from both subfolders clash too
+        config.output_dir = Some("out".to_string());
+        let names = batch_output_names(&files, &config);
+        let outputs: Vec<String> = files
+            .iter()
+            .zip(&names)
+            .map(|(file, name)| {
+                let mut config = config.clone();
+                if name.is_some() {
+                    config.output_name = name.clone();
+                }
+                output_file(file, &config, "", "mov")
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(
+            outputs,
+            [
+                "out/a_jpg_spatial.mov",
+                "out/a_png_spatial.mov",
+                "out/b_spatial.mov",
+                "out/c_mp4_spatial.mov",
+                "out/c_mp4_spatial_2.mov"
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod spatial_hardware_tests {
+    use crate::spatial::hardware::{SpatialHardware, is_accelerated, recommend_encoder};
+    use crate::spatial::types::EncoderSize;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn hardware(memory_gib: Option<u64>, accelerated: bool, cpu_threads: usize) -> SpatialHardware {
+        SpatialHardware {
+            memory_bytes: memory_gib.map(|gib| gib * GIB),
+            apple_silicon: accelerated,
+            gpu: None,
+            cpu_threads,
+            accelerated,
+        }
+    }
+
+    #[test]
+    fn accelerated_machines_scale_with_memory() {
+        let size = |gib| recommend_encoder(hardware(Some(gib), true, 8)).encoder_size;
+        assert_eq!(size(8), EncoderSize::Small);
+        assert_eq!(size(16), EncoderSize::Base);
+        assert_eq!(size(64), EncoderSize::Large);
+    }
+
+    #[test]
+    fn cpu_only_machines_stay_small() {
+        let laptop = recommend_encoder(hardware(Some(32), false, 8));
+        assert_eq!(laptop.encoder_size, EncoderSize::Small);
+        assert_eq!(laptop.expected_fps, 2.0);
+
+        let workstation = recommend_encoder(hardware(Some(64), false, 32));
+        assert_eq!(workstation.encoder_size, EncoderSize::Base);
+        assert!(workstation.expected_fps > 2.0);
+
+        let unknown = recommend_encoder(hardware(None, true, 8));
+        assert_eq!(unknown.encoder_size, EncoderSize::Small);
+    }
+
+    #[test]
+    fn intel_macs_need_a_discrete_gpu() {
+        assert!(is_accelerated(true, true, Some("integrated")));
+        assert!(is_accelerated(false, true, Some("discrete")));
+        assert!(!is_accelerated(false, true, Some("integrated")));
+        assert!(!is_accelerated(false, true, None));
+        // ONNX Runtime only runs on the CPU off macOS
+        assert!(!is_accelerated(false, false, Some("discrete")));
+    }
+}
+
+#[cfg(test)]
+mod spatial_completed_tests {
+    use crate::spatial::types::SpatialCompletedPayload;
+    use spatial_maker::video::VideoInfo;
+    use std::path::Path;
+
+    fn probe() -> VideoInfo {
+        VideoInfo {
+            width: 3840,
+            height: 1080,
+            fps: 30.0,
+            frame_count: Some(300),
+            duration: Some(10.0),
+        }
+    }
+
+    #[test]
+    fn video_results_carry_size_and_length() {
+        let payload = SpatialCompletedPayload::new(
+            "task",
+            Path::new("/out/clip_spatial.mov"),
+            Some(52_000_000),
+            Some(&probe()),
+            95.5,
+        );
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["outputPath"], "/out/clip_spatial.mov");
+        assert_eq!(json["outputBytes"], 52_000_000);
+        assert_eq!(json["width"], 3840);
+        assert_eq!(json["height"], 1080);
+        assert_eq!(json["durationSecs"], 10.0);
+        assert_eq!(json["elapsedSecs"], 95.5);
+    }
+
+    #[test]
+    fn photo_results_have_no_duration() {
+        let photo = SpatialCompletedPayload::new(
+            "task",
+            Path::new("/out/photo_spatial_sbs.jpg"),
+            Some(900_000),
+            Some(&probe()),
+            4.0,
+        );
+        assert_eq!(photo.width, Some(3840));
+        assert_eq!(photo.duration_secs, None);
+
+        let unprobed =
+            SpatialCompletedPayload::new("task", Path::new("/out/clip.mov"), None, None, 1.0);
+        assert_eq!(unprobed.width, None);
+        assert_eq!(unprobed.duration_secs, None);
+    }
+}
+
+#[cfg(test)]
+mod spatial_manager_tests {
+    use std::collections::VecDeque;
+
+    use crate::spatial::manager::RunningSlot;
+
+    fn start_next(slot: &mut RunningSlot, queue: &mut VecDeque<&str>) {
+        if slot.is_free()
+            && let Some(id) = queue.pop_front()
+        {
+            slot.occupy(id.to_string());
+        }
+    }
+
+    #[test]
+    fn cancelling_between_stages_runs_one_task_at_a_time() {
+        let mut slot = RunningSlot::default();
+        let mut queue = VecDeque::from(["a", "b", "c"]);
+        start_next(&mut slot, &mut queue);
+        assert_eq!(slot.id(), Some("a"));
+
+        // "a" is cancelled after its stereo stage; its ffmpeg stage starting
+        // does not free the slot while the pipeline winds down
+        start_next(&mut slot, &mut queue);
+        assert_eq!(slot.id(), Some("a"));
+
+        assert!(slot.release("a"));
+        start_next(&mut slot, &mut queue);
+        assert_eq!(slot.id(), Some("b"));
+
+        // A second end reported for "a" leaves "b" as the only task running
+        assert!(!slot.release("a"));
+        start_next(&mut slot, &mut queue);
+        assert_eq!(slot.id(), Some("b"));
+        assert_eq!(queue, ["c"]);
+    }
+}
+
+#[cfg(test)]
+mod spatial_queue_tests {
+    use crate::spatial::saved_queue::{QUEUE_FILE, SavedQueue};
+    use crate::spatial::types::{SpatialConfig, SpatialTask};
+
+    fn task(id: &str) -> SpatialTask {
+        let config: SpatialConfig = serde_json::from_value(serde_json::json!({
+            "encoderSize": "s",
+            "maxDisparity": 40,
+            "skipDownscale": true,
+            "duration": null,
+        }))
+        .unwrap();
+        SpatialTask {
+            id: id.to_string(),
+            file_path: format!("/videos/{}.mov", id),
+            config,
+            cancel: spatial_maker::CancellationToken::new(),
+            batch_id: None,
+            restored: false,
+        }
+    }
+
+    #[test]
+    fn unfinished_tasks_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+
+        let queue = SavedQueue::load(path.clone());
+        queue.add(&task("a"));
+        queue.add(&task("b"));
+        queue.add(&task("c"));
+        queue.remove("b");
+        queue.add(&SpatialTask {
+            batch_id: Some("batch".to_string()),
+            ..task("a")
+        });
+
+        let relaunched = SavedQueue::load(path);
+        let restored = relaunched.take_unrestored();
+        let ids: Vec<&str> = restored.iter().map(|task| task.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+        assert_eq!(restored[0].batch_id.as_deref(), Some("batch"));
+        assert!(restored.iter().all(|task| task.restored));
+        assert!(relaunched.take_unrestored().is_empty());
+    }
+
+    #[test]
+    fn listing_saved_tasks_leaves_them_to_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+        let queue = SavedQueue::load(path.clone());
+        queue.add(&task("a"));
+        queue.add(&task("b"));
+
+        let relaunched = SavedQueue::load(path.clone());
+        assert_eq!(relaunched.unrestored().len(), 2);
+        // Cancelled before it was queued again
+        relaunched.remove("a");
+        let ids: Vec<String> = relaunched
+            .take_unrestored()
+            .into_iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(ids, ["b"]);
+        assert_eq!(SavedQueue::load(path).unrestored().len(), 1);
+    }
+
+    #[test]
+    fn unreadable_queue_file_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUEUE_FILE);
+        std::fs::write(&path, "not json").unwrap();
+
+        let queue = SavedQueue::load(path.clone());
+        assert!(queue.take_unrestored().is_empty());
+        queue.add(&task("a"));
+        assert_eq!(SavedQueue::load(path).take_unrestored().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod spatial_disk_tests {
+    use crate::spatial::disk::{available_space, check_space, video_job_bytes};
+    use crate::spatial::error::SpatialError;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn short_volumes_fail_with_a_readable_error() {
+        assert!(check_space(GIB, 2 * GIB).is_ok());
+
+        let err = check_space(2 * GIB, GIB).unwrap_err();
+        assert!(matches!(err, SpatialError::InsufficientSpace { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Not enough disk space: needs 2.2 GB but only 1.0 GB is free"
+        );
+    }
+
+    #[test]
+    fn video_estimate_counts_the_largest_intermediates() {
+        assert_eq!(video_job_bytes(100, false, false), 200);
+        assert_eq!(video_job_bytes(100, true, false), 600);
+        assert_eq!(video_job_bytes(100, false, true), 400);
+        assert_eq!(video_job_bytes(100, true, true), 600);
+        assert_eq!(video_job_bytes(u64::MAX, true, true), u64::MAX);
+    }
+
+    #[test]
+    fn missing_folders_use_their_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("not").join("yet").join("out.mov");
+        assert!(available_space(dir.path()).is_some());
+        assert_eq!(
+            available_space(&missing).is_some(),
+            available_space(dir.path()).is_some()
+        );
+    }
+}
+
+#[cfg(test)]
+mod spatial_model_tests {
+    use crate::spatial::types::{EncoderSize, ModelIntegrity, ModelVerification};
+    use spatial_maker::LockStatus;
+
+    #[test]
+    fn lock_status_maps_to_integrity() {
+        let verified = ModelVerification::new(EncoderSize::Small, &LockStatus::Verified);
+        assert_eq!(verified.integrity, ModelIntegrity::Verified);
+        assert_eq!(verified.expected_bytes, None);
+
+        let unlocked = ModelVerification::new(EncoderSize::Base, &LockStatus::Unlocked);
+        assert_eq!(unlocked.integrity, ModelIntegrity::Unverified);
+
+        let truncated = LockStatus::Changed {
+            expected_sha256: "aa".to_string(),
+            actual_sha256: "bb".to_string(),
+            expected_bytes: 1_000,
+            actual_bytes: 400,
+        };
+        let corrupt = ModelVerification::new(EncoderSize::Large, &truncated);
+        assert_eq!(corrupt.integrity, ModelIntegrity::Corrupt);
+        assert_eq!(corrupt.expected_bytes, Some(1_000));
+        assert_eq!(corrupt.actual_bytes, Some(400));
+        assert!(!corrupt.redownloaded);
+
+        let json = serde_json::to_value(&corrupt).unwrap();
+        assert_eq!(json["integrity"], "corrupt");
+        assert_eq!(json["expectedBytes"], 1_000);
+    }
+}
+
+#[cfg(test)]
+mod appearance_tests {
+    use crate::appearance::{
+        AccessibilityPreferences, Appearance, WindowEffects, defaults_flag, gsettings_flag,
+        reg_value, windows_high_contrast_on,
+    };
+
+    #[test]
+    fn auto_goes_solid_for_reduced_transparency_or_more_contrast() {
+        let none = AccessibilityPreferences::default();
+        assert!(WindowEffects::Auto.translucent(&none));
+        assert!(!WindowEffects::Solid.translucent(&none));
+
+        let reduced = AccessibilityPreferences {
+            reduce_transparency: true,
+            ..none
+        };
+        let contrast = AccessibilityPreferences {
+            increase_contrast: true,
+            ..none
+        };
+        let motion = AccessibilityPreferences {
+            reduce_motion: true,
+            ..none
+        };
+        assert!(!WindowEffects::Auto.translucent(&reduced));
+        assert!(!WindowEffects::Auto.translucent(&contrast));
+        assert!(WindowEffects::Auto.translucent(&motion));
+        assert!(WindowEffects::Translucent.translucent(&reduced));
+    }
+
+    #[test]
+    fn appearance_state_tracks_effects_and_preference_changes() {
+        let appearance = Appearance::default();
+        assert!(appearance.state().translucent);
+
+        let reduced = AccessibilityPreferences {
+            reduce_transparency: true,
+            ..Default::default()
+        };
+        assert!(appearance.set_preferences(reduced));
+        assert!(!appearance.set_preferences(reduced));
+        assert!(!appearance.state().translucent);
+
+        appearance.set_effects(WindowEffects::Translucent);
+        let state = appearance.state();
+        assert_eq!(state.effects, WindowEffects::Translucent);
+        assert_eq!(state.preferences, reduced);
+        assert!(state.translucent);
+    }
+
+    #[test]
+    fn window_effects_use_camel_case_names() {
+        assert_eq!(
+            serde_json::from_str::<WindowEffects>("\"solid\"").unwrap(),
+            WindowEffects::Solid
+        );
+        let json = serde_json::to_value(AccessibilityPreferences::default()).unwrap();
+        assert_eq!(json["reduceTransparency"], false);
+    }
+
+    #[test]
+    fn parses_os_setting_output() {
+        assert!(defaults_flag("1\n"));
+        assert!(!defaults_flag("0\n"));
+        assert!(!defaults_flag(""));
+
+        assert_eq!(gsettings_flag("true\n"), Some(true));
+        assert_eq!(gsettings_flag("false"), Some(false));
+        assert_eq!(gsettings_flag("No such key"), None);
+
+        let personalize = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\r\n    EnableTransparency    REG_DWORD    0x0\r\n\r\n";
+        assert_eq!(reg_value(personalize, "EnableTransparency"), Some("0x0"));
+        assert_eq!(reg_value(personalize, "Flags"), None);
+
+        let high_contrast = "\nHKEY_CURRENT_USER\\Control Panel\\Accessibility\\HighContrast\n    Flags    REG_SZ    127\n";
+        assert!(windows_high_contrast_on(high_contrast));
+        let off = high_contrast.replace("127", "126");
+        assert!(!windows_high_contrast_on(&off));
+    }
+}
+
+#[cfg(test)]
+mod stereo_preview_tests {
+    use crate::spatial::error::SpatialError;
+    use crate::spatial::preview::{
+        DisplayInfo, PreviewState, StereoLayout, StereoPreview, default_display, resolve_display,
+    };
+
+    fn display(index: usize, primary: bool) -> DisplayInfo {
+        DisplayInfo {
+            index,
+            name: Some(format!("Display {}", index)),
+            width: 1920,
+            height: 1080,
+            x: 1920 * index as i32,
+            y: 0,
+            scale_factor: 1.0,
+            primary,
+        }
+    }
+
+    #[test]
+    fn prefers_a_secondary_display() {
+        let secondary_last = [display(0, true), display(1, false)];
+        let secondary_first = [display(0, false), display(1, true)];
+        assert_eq!(default_display(&secondary_last), Some(1));
+        assert_eq!(default_display(&secondary_first), Some(0));
+        assert_eq!(default_display(&[display(0, true)]), Some(0));
+        assert_eq!(default_display(&[]), None);
+    }
+
+    #[test]
+    fn resolves_requested_display() {
+        let displays = [display(0, true), display(1, false), display(2, false)];
+        assert_eq!(resolve_display(&displays, Some(2)).unwrap().index, 2);
+        assert_eq!(resolve_display(&displays, None).unwrap().index, 1);
+        assert!(matches!(
+            resolve_display(&displays, Some(3)),
+            Err(SpatialError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            resolve_display(&[], None),
+            Err(SpatialError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn layouts_use_camel_case_names() {
+        assert_eq!(
+            serde_json::from_str::<StereoLayout>("\"topAndBottom\"").unwrap(),
+            StereoLayout::TopAndBottom
+        );
+        let json = serde_json::to_value(PreviewState::default()).unwrap();
+        assert_eq!(json["sourceLayout"], "sideBySide");
+        assert_eq!(json["open"], false);
+        assert_eq!(StereoPreview::default().state(), PreviewState::default());
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use crate::conversion::error::ConversionError;
+    use crate::conversion::history::{
+        DAY_SECS, ErrorClass, HISTORY_FILE, HistoryEntry, JobHistory, JobOutcome, codec_speeds,
+        failure_timeline, summarize,
+    };
+    use crate::watchdog::WorkerFault;
+
+    fn entry(codec: &str, finished_at: u64, outcome: JobOutcome) -> HistoryEntry {
+        let completed = outcome == JobOutcome::Completed;
+        HistoryEntry {
+            id: format!("{}-{}", codec, finished_at),
+            file_path: "/videos/in.mov".into(),
+            video_codec: codec.into(),
+            finished_at,
+            elapsed_secs: 60.0,
+            media_secs: if completed { 120.0 } else { 0.0 },
+            input_bytes: 3_000_000_000,
+            output_bytes: completed.then_some(1_000_000_000),
+            outcome,
+        }
+    }
+
+    fn failed(error_class: ErrorClass) -> JobOutcome {
+        JobOutcome::Failed { error_class }
+    }
+
+    #[test]
+    fn summary_totals_completed_work() {
+        let entries = vec![
+            entry("libx264", 10, JobOutcome::Completed),
+            entry("libx265", 20, JobOutcome::Completed),
+            entry("libx264", 30, failed(ErrorClass::Encoder)),
+            entry("libx264", 40, JobOutcome::Cancelled),
+        ];
+        let summary = summarize(&entries);
+        assert_eq!(
+            (
+                summary.jobs,
+                summary.completed,
+                summary.failed,
+                summary.cancelled
+            ),
+            (4, 2, 1, 1)
+        );
+        assert_eq!(summary.minutes_encoded, 4.0);
+        assert!((summary.hours_spent - 4.0 / 60.0).abs() < 1e-9);
+        assert_eq!(summary.bytes_saved, 4_000_000_000);
+        assert_eq!(summary.gb_saved, 4.0);
+        assert_eq!(summarize(&[]).jobs, 0);
+    }
+
+    #[test]
+    fn codec_speeds_average_over_completed_jobs() {
+        let mut slow = entry("libx265", 10, JobOutcome::Completed);
+        slow.elapsed_secs = 240.0;
+        let entries = vec![
+            entry("libx264", 10, JobOutcome::Completed),
+            entry("libx264", 20, JobOutcome::Completed),
+            slow,
+            entry("h264_nvenc", 30, failed(ErrorClass::Crashed)),
+        ];
+        let speeds = codec_speeds(&entries);
+        assert_eq!(speeds.len(), 2);
+        assert_eq!(speeds[0].codec, "libx264");
+        assert_eq!(speeds[0].jobs, 2);
+        assert_eq!(speeds[0].average_speed, 2.0);
+        assert_eq!(speeds[1].codec, "libx265");
+        assert_eq!(speeds[1].average_speed, 0.5);
+    }
+
+    #[test]
+    fn failure_timeline_buckets_by_class() {
+        let entries = vec![
+            entry("libx264", 100, failed(ErrorClass::Encoder)),
+            entry("libx264", 200, failed(ErrorClass::Encoder)),
+            entry("libx264", 300, failed(ErrorClass::TimedOut)),
+            entry("libx264", DAY_SECS * 2 + 5, failed(ErrorClass::Stalled)),
+            entry("libx264", DAY_SECS + 5, JobOutcome::Completed),
+        ];
+        let timeline = failure_timeline(&entries, DAY_SECS);
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(timeline[0].start, 0);
+        assert_eq!(timeline[0].total, 3);
+        assert_eq!(timeline[0].by_class[&ErrorClass::Encoder], 2);
+        assert_eq!(timeline[0].by_class[&ErrorClass::TimedOut], 1);
+        assert_eq!(timeline[1].start, DAY_SECS * 2);
+
+        let json = serde_json::to_value(&timeline[1]).unwrap();
+        assert_eq!(json["byClass"]["stalled"], 1);
+    }
+
+    #[test]
+    fn error_classes_follow_the_error() {
+        assert_eq!(
+            ErrorClass::of(&ConversionError::Worker("exit 1".into())),
+            ErrorClass::Encoder
+        );
+        assert_eq!(
+            ErrorClass::of(&ConversionError::TimedOut(60)),
+            ErrorClass::TimedOut
+        );
+        assert_eq!(
+            ErrorClass::of(&ConversionError::Fault(WorkerFault::Stalled {
+                idle_secs: 600
+            })),
+            ErrorClass::Stalled
+        );
+        assert_eq!(
+            ErrorClass::of(&ConversionError::Fault(WorkerFault::Panicked {
+                message: "boom".into()
+            })),
+            ErrorClass::Crashed
+        );
+    }
+
+    #[test]
+    fn cancelled_jobs_count_as_cancelled_whatever_they_returned() {
+        let failed_run = Err(ConversionError::Worker("killed".into()));
+        assert_eq!(JobOutcome::of(&failed_run, true), JobOutcome::Cancelled);
+        assert_eq!(JobOutcome::of(&Ok(()), true), JobOutcome::Cancelled);
+        assert_eq!(JobOutcome::of(&Ok(()), false), JobOutcome::Completed);
+        assert_eq!(
+            JobOutcome::of(&failed_run, false),
+            failed(ErrorClass::Encoder)
+        );
+    }
+
+    #[test]
+    fn history_persists_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+
+        let history = JobHistory::load(path.clone());
+        history
+            .record(entry("libx264", 10, JobOutcome::Completed))
+            .unwrap();
+        history
+            .record(entry("libx265", 20, failed(ErrorClass::Io)))
+            .unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, b"not json\n"))
+            .unwrap();
+
+        let reloaded = JobHistory::load(path.clone());
+        assert_eq!(reloaded.entries_since(None), history.entries_since(None));
+        assert_eq!(reloaded.entries_since(Some(15)).len(), 1);
+        assert_eq!(
+            serde_json::to_value(reloaded.entries_since(Some(15))[0].outcome).unwrap(),
+            serde_json::json!({"status": "failed", "errorClass": "io"})
+        );
+
+        reloaded.clear().unwrap();
+        assert!(JobHistory::load(path).entries_since(None).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod bootstrap_tests {
+    use serde_json::json;
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::spatial::bootstrap::{
+        BootstrapCheck, BootstrapStatus, BootstrapStep, RunningGuard, find_in_path,
+    };
+    use crate::spatial::types::EncoderSize;
+
+    fn check(step: BootstrapStep, ready: bool) -> BootstrapCheck {
+        BootstrapCheck {
+            step,
+            ready,
+            required: step.required(),
+            detail: (!ready).then(|| "missing".to_string()),
+        }
+    }
+
+    #[test]
+    fn steps_serialize_with_a_kind_tag() {
+        assert_eq!(
+            serde_json::to_value(BootstrapStep::Model {
+                encoder_size: EncoderSize::Base
+            })
+            .unwrap(),
+            json!({"kind": "model", "encoderSize": "m"})
+        );
+        let parsed: Vec<BootstrapStep> =
+            serde_json::from_value(json!([{"kind": "ffmpeg"}, {"kind": "warmDepth"}])).unwrap();
+        assert_eq!(parsed, [BootstrapStep::Ffmpeg, BootstrapStep::WarmDepth]);
+    }
+
+    #[test]
+    fn all_steps_cover_every_model() {
+        let steps = BootstrapStep::all();
+        assert_eq!(steps.first(), Some(&BootstrapStep::Ffmpeg));
+        for encoder_size in EncoderSize::ALL {
+            assert!(steps.contains(&BootstrapStep::Model { encoder_size }));
+        }
+    }
+
+    #[test]
+    fn required_steps_are_the_sidecars_and_the_chosen_model() {
+        let base = BootstrapStep::Model {
+            encoder_size: EncoderSize::Base,
+        };
+        assert_eq!(
+            BootstrapStep::required_for(EncoderSize::Base),
+            [BootstrapStep::Ffmpeg, base]
+        );
+    }
+
+    #[test]
+    fn running_flag_clears_when_a_run_ends() {
+        let running = AtomicBool::new(false);
+        let guard = RunningGuard::acquire(&running);
+        assert!(guard.is_some());
+        assert!(RunningGuard::acquire(&running).is_none());
+
+        drop(guard);
+        assert!(!running.load(Ordering::SeqCst));
+        assert!(RunningGuard::acquire(&running).is_some());
+    }
+
+    #[test]
+    fn only_required_checks_block_readiness() {
+        let small = BootstrapStep::Model {
+            encoder_size: EncoderSize::Small,
+        };
+        let large = BootstrapStep::Model {
+            encoder_size: EncoderSize::Large,
+        };
+        assert!(small.required());
+        assert!(!large.required());
+        assert!(!BootstrapStep::SpatialTool.required());
+
+        let status = BootstrapStatus::new(vec![
+            check(BootstrapStep::Ffmpeg, true),
+            check(small, true),
+            check(large, false),
+            check(BootstrapStep::Notifications, false),
+        ]);
+        assert!(status.ready);
+
+        let status = BootstrapStatus::new(vec![
+            check(BootstrapStep::Ffmpeg, true),
+            check(small, false),
+        ]);
+        assert!(!status.ready);
+    }
+
+    #[test]
+    fn finds_binaries_on_the_path() {
+        let empty = tempfile::tempdir().unwrap();
+        let tools = tempfile::tempdir().unwrap();
+        let file = if cfg!(windows) {
+            "spatial.exe"
+        } else {
+            "spatial"
+        };
+        std::fs::write(tools.path().join(file), b"").unwrap();
+
+        let path_var = std::env::join_paths([empty.path(), tools.path()]).unwrap();
+        assert_eq!(
+            find_in_path("spatial", &path_var),
+            Some(tools.path().join(file))
+        );
+        assert_eq!(find_in_path("ffmpeg", &path_var), None);
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::conversion::sidecar::SidecarIssue;
+
 pub const DEFAULT_MAX_CONCURRENCY: usize = 2;
 pub const VOLUME_EPSILON: f64 = 0.01;
 
@@ -100,6 +102,62 @@ pub struct ConversionConfig {
     pub videotoolbox_allow_sw: bool,
     #[serde(default = "default_hw_decode")]
     pub hw_decode: bool,
+    /// Vulkan device index for the ML upscaler (`-g`). `None` uses device 0.
+    #[serde(default)]
+    pub gpu_index: Option<u32>,
+    /// CUDA device index for NVENC encode/decode (`-gpu`). `None` lets the
+    /// driver pick.
+    #[serde(default)]
+    pub nvenc_gpu_index: Option<u32>,
+    /// Stop the task once it has run this many seconds (e.g. 43200 for
+    /// 12 hours), not counting time paused. `None` or 0 means no limit.
+    #[serde(default)]
+    pub max_duration_secs: Option<u64>,
+}
+
+/// What tests start from: an untouched mp4 preset. Fixtures override the
+/// fields they exercise, so new options do not have to be added to each.
+#[cfg(test)]
+impl Default for ConversionConfig {
+    fn default() -> Self {
+        Self {
+            container: "mp4".into(),
+            video_codec: "libx264".into(),
+            video_bitrate_mode: "crf".into(),
+            video_bitrate: "5000".into(),
+            audio_codec: "aac".into(),
+            audio_bitrate: "128".into(),
+            audio_channels: "original".into(),
+            audio_volume: default_audio_volume(),
+            audio_normalize: false,
+            selected_audio_tracks: vec![],
+            selected_subtitle_tracks: vec![],
+            subtitle_burn_path: None,
+            resolution: "original".into(),
+            custom_width: None,
+            custom_height: None,
+            scaling_algorithm: "lanczos".into(),
+            fps: "original".into(),
+            crf: 23,
+            quality: default_quality(),
+            preset: "medium".into(),
+            start_time: None,
+            end_time: None,
+            metadata: MetadataConfig::default(),
+            rotation: default_rotation(),
+            flip_horizontal: false,
+            flip_vertical: false,
+            ml_upscale: None,
+            crop: None,
+            nvenc_spatial_aq: false,
+            nvenc_temporal_aq: false,
+            videotoolbox_allow_sw: false,
+            hw_decode: default_hw_decode(),
+            gpu_index: None,
+            nvenc_gpu_index: None,
+            max_duration_secs: None,
+        }
+    }
 }
 
 fn default_rotation() -> String {
@@ -155,6 +213,57 @@ pub enum MetadataMode {
     Replace,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContactSheetOptions {
+    pub columns: u32,
+    pub rows: u32,
+    pub thumb_width: u32,
+    pub show_timestamps: bool,
+    pub output_path: Option<String>,
+}
+
+impl Default for ContactSheetOptions {
+    fn default() -> Self {
+        Self {
+            columns: 4,
+            rows: 5,
+            thumb_width: 320,
+            show_timestamps: true,
+            output_path: None,
+        }
+    }
+}
+
+/// What to do when a queued job repeats one already queued, running, or
+/// recently finished
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateAction {
+    #[default]
+    Off,
+    Warn,
+    Skip,
+}
+
+/// How two jobs are recognized as reading the same source file
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceIdentity {
+    /// Same path, size, and modification time
+    #[default]
+    PathAndMtime,
+    /// Same size and sampled file contents, wherever the file lives
+    ContentHash,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DuplicatePolicy {
+    pub action: DuplicateAction,
+    pub identity: SourceIdentity,
+}
+
 #[derive(Clone, Serialize)]
 pub struct ProgressPayload {
     pub id: String,
@@ -179,6 +288,21 @@ pub struct ErrorPayload {
     pub error: String,
 }
 
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceMissingPayload {
+    pub id: String,
+    pub file_path: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarIssuePayload {
+    pub id: String,
+    #[serde(flatten)]
+    pub issue: SidecarIssue,
+}
+
 #[derive(Clone, Serialize)]
 pub struct LogPayload {
     pub id: String,
@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use tauri::path::BaseDirectory;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Manager};
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_shell::process::CommandEvent;
 use tokio::sync::mpsc;
@@ -12,12 +12,15 @@ use crate::conversion::codec::{
 };
 use crate::conversion::error::ConversionError;
 use crate::conversion::filters::{build_audio_filters, build_video_filters};
+use crate::conversion::gpu::{GpuMemory, query_gpu_memory};
 use crate::conversion::manager::ManagerMessage;
+use crate::conversion::sidecar::sidecar_error;
 use crate::conversion::types::{
     CompletedPayload, ConversionConfig, ConversionTask, LogPayload, MetadataMode, ProgressPayload,
     StartedPayload,
 };
 use crate::conversion::utils::{FRAME_REGEX, parse_time, sanitize_external_tool_path};
+use crate::events::EventSink;
 
 pub(crate) fn build_upscale_encode_args(
     output_frames_dir: &Path,
@@ -145,13 +148,36 @@ pub(crate) fn resolve_upscale_mode(
     }
 }
 
+/// Fixed GPU cost of an upscale run: model weights plus tile workspace.
+const UPSCALE_BASE_VRAM_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Approximate GPU cost per output pixel of one in-flight frame
+/// (~500MB for a 4K output frame).
+const UPSCALE_VRAM_BYTES_PER_PIXEL: u64 = 64;
+
+fn upscale_output_pixels(source_width: u32, source_height: u32, scale: u32) -> u64 {
+    (source_width as u64 * scale as u64) * (source_height as u64 * scale as u64)
+}
+
+/// Estimated GPU memory needed to upscale with `proc` concurrent frames.
+pub(crate) fn estimate_upscale_vram(
+    source_width: u32,
+    source_height: u32,
+    scale: u32,
+    proc: u32,
+) -> u64 {
+    UPSCALE_BASE_VRAM_BYTES
+        + upscale_output_pixels(source_width, source_height, scale)
+            * UPSCALE_VRAM_BYTES_PER_PIXEL
+            * proc as u64
+}
+
 pub(crate) fn compute_upscale_threads(
     source_width: u32,
     source_height: u32,
     scale: u32,
 ) -> String {
-    let output_pixels = (source_width as u64 * scale as u64)
-        * (source_height as u64 * scale as u64);
+    let output_pixels = upscale_output_pixels(source_width, source_height, scale);
 
     // proc: concurrent GPU inference frames — limited by VRAM
     // > 4K output (~8.3M px): ~500MB+ per frame → single concurrent frame
@@ -165,6 +191,10 @@ pub(crate) fn compute_upscale_threads(
         4
     };
 
+    format_upscale_threads(proc)
+}
+
+fn format_upscale_threads(proc: u32) -> String {
     // load/save: I/O threads — limited by CPU cores
     let cpus = std::thread::available_parallelism()
         .map(|n| n.get() as u32)
@@ -174,6 +204,54 @@ pub(crate) fn compute_upscale_threads(
     format!("{}:{}:{}", io, proc, io)
 }
 
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+/// Thread plan for the upscaler, checked against measured GPU memory.
+///
+/// Starts from the pixel-count heuristic and lowers the number of concurrent
+/// frames until the estimate fits. Rejects the job when even a single frame
+/// would not fit. Without a memory reading the heuristic is used as-is.
+pub(crate) fn plan_upscale_threads(
+    source_width: u32,
+    source_height: u32,
+    scale: u32,
+    gpu: Option<&GpuMemory>,
+) -> Result<String, ConversionError> {
+    let heuristic = compute_upscale_threads(source_width, source_height, scale);
+    let Some(gpu) = gpu else {
+        return Ok(heuristic);
+    };
+
+    let mut proc = heuristic
+        .split(':')
+        .nth(1)
+        .and_then(|p| p.parse::<u32>().ok())
+        .unwrap_or(1);
+    while proc > 1
+        && estimate_upscale_vram(source_width, source_height, scale, proc) > gpu.available_bytes
+    {
+        proc -= 1;
+    }
+
+    let needed = estimate_upscale_vram(source_width, source_height, scale, proc);
+    if needed > gpu.available_bytes {
+        return Err(ConversionError::InvalidInput(format!(
+            "Upscaling {}x{} by {}x needs about {} of GPU memory, but only {} of {} is available ({}). Use 2x mode, lower the resolution, or close other GPU-heavy apps.",
+            source_width,
+            source_height,
+            scale,
+            format_gib(needed),
+            format_gib(gpu.available_bytes),
+            format_gib(gpu.total_bytes),
+            gpu.source
+        )));
+    }
+
+    Ok(format_upscale_threads(proc))
+}
+
 pub(crate) async fn validate_upscale_runtime(
     app: &AppHandle,
     mode: &str,
@@ -239,8 +317,9 @@ pub(crate) async fn validate_upscale_runtime(
     Ok(())
 }
 
-pub async fn run_upscale_worker(
+pub async fn run_upscale_worker<E: EventSink>(
     app: AppHandle,
+    events: E,
     tx: mpsc::Sender<ManagerMessage>,
     task: ConversionTask,
 ) -> Result<(), ConversionError> {
@@ -284,6 +363,21 @@ pub async fn run_upscale_worker(
     let active_duration = (end_t - start_t).max(0.0);
     let total_frames = (active_duration * fps).ceil() as u32;
 
+    // Check GPU memory before extracting frames so oversized jobs fail fast
+    // nvidia-smi uses CUDA indices; a non-NVIDIA selection has none
+    let nvidia_index = match (task.config.gpu_index, task.config.nvenc_gpu_index) {
+        (_, Some(index)) => Some(index),
+        (None, None) => Some(0),
+        (Some(_), None) => None,
+    };
+    let gpu_memory = query_gpu_memory(nvidia_index).await;
+    let upscale_threads = plan_upscale_threads(
+        probe.width.unwrap_or(1920),
+        probe.height.unwrap_or(1080),
+        scale.parse::<u32>().unwrap_or(2),
+        gpu_memory.as_ref(),
+    )?;
+
     let temp_dir = std::env::temp_dir().join(format!("frame_upscale_{}", task.id));
     if temp_dir.exists() {
         let _ = std::fs::remove_dir_all(&temp_dir);
@@ -294,17 +388,16 @@ pub async fn run_upscale_worker(
     std::fs::create_dir_all(&input_frames_dir).map_err(ConversionError::Io)?;
     std::fs::create_dir_all(&output_frames_dir).map_err(ConversionError::Io)?;
 
-    let app_clone = app.clone();
     let id_clone = task.id.clone();
 
-    let _ = app_clone.emit(
+    events.emit(
         "conversion-started",
         StartedPayload {
             id: id_clone.clone(),
         },
     );
 
-    let _ = app_clone.emit(
+    events.emit(
         "conversion-progress",
         ProgressPayload {
             id: id_clone.clone(),
@@ -312,6 +405,22 @@ pub async fn run_upscale_worker(
         },
     );
 
+    if let Some(gpu) = &gpu_memory {
+        events.emit(
+            "conversion-log",
+            LogPayload {
+                id: id_clone.clone(),
+                line: format!(
+                    "[UPSCALE] GPU memory: {} available of {} ({}), threads {}",
+                    format_gib(gpu.available_bytes),
+                    format_gib(gpu.total_bytes),
+                    gpu.source,
+                    upscale_threads
+                ),
+            },
+        );
+    }
+
     let mut dec_args = Vec::new();
 
     // Hardware decode acceleration (only -hwaccel, no output_format since we need CPU frames)
@@ -319,6 +428,10 @@ pub async fn run_upscale_worker(
         if crate::conversion::utils::is_nvenc_codec(&task.config.video_codec) {
             dec_args.push("-hwaccel".to_string());
             dec_args.push("cuda".to_string());
+            if let Some(gpu) = task.config.nvenc_gpu_index {
+                dec_args.push("-hwaccel_device".to_string());
+                dec_args.push(gpu.to_string());
+            }
         } else if crate::conversion::utils::is_videotoolbox_codec(&task.config.video_codec) {
             dec_args.push("-hwaccel".to_string());
             dec_args.push("videotoolbox".to_string());
@@ -379,10 +492,10 @@ pub async fn run_upscale_worker(
     let (mut dec_rx, dec_child) = app
         .shell()
         .sidecar("ffmpeg")
-        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .map_err(|e| sidecar_error("ffmpeg", e))?
         .args(dec_args)
         .spawn()
-        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+        .map_err(|e| sidecar_error("ffmpeg", e))?;
 
     let _ = tx
         .send(ManagerMessage::TaskStarted(
@@ -397,7 +510,7 @@ pub async fn run_upscale_worker(
         match event {
             CommandEvent::Stderr(ref line_bytes) => {
                 let line = String::from_utf8_lossy(line_bytes);
-                let _ = app_clone.emit(
+                events.emit(
                     "conversion-log",
                     LogPayload {
                         id: id_clone.clone(),
@@ -411,7 +524,7 @@ pub async fn run_upscale_worker(
                             if let Ok(current_frame) = frame_match.as_str().parse::<u32>() {
                                 let decode_progress =
                                     (current_frame as f64 / total_frames as f64) * 5.0;
-                                let _ = app_clone.emit(
+                                events.emit(
                                     "conversion-progress",
                                     ProgressPayload {
                                         id: id_clone.clone(),
@@ -475,13 +588,9 @@ pub async fn run_upscale_worker(
         "-n".to_string(),
         model_name.to_string(),
         "-j".to_string(),
-        compute_upscale_threads(
-            probe.width.unwrap_or(1920),
-            probe.height.unwrap_or(1080),
-            scale.parse::<u32>().unwrap_or(2),
-        ),
+        upscale_threads,
         "-g".to_string(),
-        "0".to_string(),
+        task.config.gpu_index.unwrap_or(0).to_string(),
         "-t".to_string(),
         "0".to_string(),
     ];
@@ -489,10 +598,10 @@ pub async fn run_upscale_worker(
     let (mut upscale_rx, upscale_child) = app
         .shell()
         .sidecar("realesrgan-ncnn-vulkan")
-        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .map_err(|e| sidecar_error("realesrgan-ncnn-vulkan", e))?
         .args(upscaler_args)
         .spawn()
-        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+        .map_err(|e| sidecar_error("realesrgan-ncnn-vulkan", e))?;
 
     let _ = tx
         .send(ManagerMessage::TaskStarted(
@@ -520,7 +629,7 @@ pub async fn run_upscale_worker(
                     .unwrap_or(false);
 
             if !is_percentage_line && !trimmed.is_empty() {
-                let _ = app_clone.emit(
+                events.emit(
                     "conversion-log",
                     LogPayload {
                         id: id_clone.clone(),
@@ -539,7 +648,7 @@ pub async fn run_upscale_worker(
 
                 if progress > last_upscale_progress {
                     last_upscale_progress = progress;
-                    let _ = app_clone.emit(
+                    events.emit(
                         "conversion-progress",
                         ProgressPayload {
                             id: id_clone.clone(),
@@ -574,10 +683,10 @@ pub async fn run_upscale_worker(
     let (mut enc_rx, enc_child) = app
         .shell()
         .sidecar("ffmpeg")
-        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .map_err(|e| sidecar_error("ffmpeg", e))?
         .args(enc_args)
         .spawn()
-        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+        .map_err(|e| sidecar_error("ffmpeg", e))?;
 
     let _ = tx
         .send(ManagerMessage::TaskStarted(
@@ -590,7 +699,7 @@ pub async fn run_upscale_worker(
         match event {
             CommandEvent::Stderr(ref line_bytes) => {
                 let line = String::from_utf8_lossy(line_bytes);
-                let _ = app_clone.emit(
+                events.emit(
                     "conversion-log",
                     LogPayload {
                         id: id_clone.clone(),
@@ -604,7 +713,7 @@ pub async fn run_upscale_worker(
                             if let Ok(current_frame) = frame_match.as_str().parse::<u32>() {
                                 let encode_progress =
                                     90.0 + (current_frame as f64 / total_frames as f64) * 10.0;
-                                let _ = app_clone.emit(
+                                events.emit(
                                     "conversion-progress",
                                     ProgressPayload {
                                         id: id_clone.clone(),
@@ -619,7 +728,7 @@ pub async fn run_upscale_worker(
             CommandEvent::Terminated(payload) => {
                 let _ = std::fs::remove_dir_all(&temp_dir);
                 if payload.code == Some(0) {
-                    let _ = app.emit(
+                    events.emit(
                         "conversion-completed",
                         CompletedPayload {
                             id: task.id.clone(),
@@ -1,6 +1,6 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub static FRAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"frame=\s*(\d+)").unwrap());
 
@@ -47,6 +47,64 @@ pub fn is_audio_only_container(container: &str) -> bool {
     )
 }
 
+pub fn is_image_container(container: &str) -> bool {
+    matches!(
+        container.to_lowercase().as_str(),
+        "jpg" | "jpeg" | "png" | "webp" | "avif"
+    )
+}
+
+/// Still-image extensions accepted for image conversion jobs and folder batches
+pub const IMAGE_INPUT_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "avif", "heic", "heif", "jxl", "tif", "tiff", "bmp",
+];
+
+pub fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_INPUT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Image files in `dir`, sorted by path; `recursive` descends into
+/// subfolders, skipping hidden entries
+pub fn collect_image_files(dir: &Path, recursive: bool) -> std::io::Result<Vec<PathBuf>> {
+    collect_files(dir, recursive, is_image_file)
+}
+
+/// Files in `dir` accepted by `include`, sorted by path; `recursive`
+/// descends into subfolders, skipping hidden entries
+pub fn collect_files(
+    dir: &Path,
+    recursive: bool,
+    include: impl Fn(&Path) -> bool,
+) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if include(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
 pub fn is_nvenc_codec(codec: &str) -> bool {
     matches!(codec, "h264_nvenc" | "hevc_nvenc" | "av1_nvenc")
 }
@@ -1,4 +1,4 @@
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_shell::process::CommandEvent;
 use tokio::sync::mpsc;
@@ -6,20 +6,23 @@ use tokio::sync::mpsc;
 use crate::conversion::args::{build_ffmpeg_args, build_output_path};
 use crate::conversion::error::ConversionError;
 use crate::conversion::manager::ManagerMessage;
+use crate::conversion::sidecar::sidecar_error;
 use crate::conversion::types::{
     CompletedPayload, ConversionTask, LogPayload, ProgressPayload, StartedPayload,
 };
 use crate::conversion::upscale::run_upscale_worker;
 use crate::conversion::utils::{DURATION_REGEX, TIME_REGEX, parse_time};
+use crate::events::EventSink;
 
-pub async fn run_ffmpeg_worker(
+pub async fn run_ffmpeg_worker<E: EventSink>(
     app: AppHandle,
+    events: E,
     tx: mpsc::Sender<ManagerMessage>,
     task: ConversionTask,
 ) -> Result<(), ConversionError> {
     if let Some(upscale_mode) = &task.config.ml_upscale {
         if upscale_mode != "none" && !upscale_mode.is_empty() {
-            return run_upscale_worker(app, tx, task).await;
+            return run_upscale_worker(app, events, tx, task).await;
         }
     }
 
@@ -33,12 +36,12 @@ pub async fn run_ffmpeg_worker(
     let sidecar_command = app
         .shell()
         .sidecar("ffmpeg")
-        .map_err(|e| ConversionError::Shell(e.to_string()))?
+        .map_err(|e| sidecar_error("ffmpeg", e))?
         .args(args);
 
     let (mut rx, child) = sidecar_command
         .spawn()
-        .map_err(|e| ConversionError::Shell(e.to_string()))?;
+        .map_err(|e| sidecar_error("ffmpeg", e))?;
 
     let id = task.id.clone();
 
@@ -46,9 +49,9 @@ pub async fn run_ffmpeg_worker(
         .send(ManagerMessage::TaskStarted(id.clone(), child.pid()))
         .await;
 
-    let _ = app.emit("conversion-started", StartedPayload { id: id.clone() });
+    events.emit("conversion-started", StartedPayload { id: id.clone() });
 
-    let _ = app.emit(
+    events.emit(
         "conversion-progress",
         ProgressPayload {
             id: id.clone(),
@@ -56,9 +59,6 @@ pub async fn run_ffmpeg_worker(
         },
     );
 
-    let mut exit_code: Option<i32> = None;
-    let mut total_duration: Option<f64> = None;
-
     let expected_duration = {
         let start_t = task
             .config
@@ -83,55 +83,102 @@ pub async fn run_ffmpeg_worker(
         (end_t - start_t).max(0.0)
     };
 
+    let exit_code = relay_ffmpeg_events(&events, &id, &mut rx, expected_duration).await;
+
+    if exit_code == Some(0) {
+        events.emit(
+            "conversion-completed",
+            CompletedPayload {
+                id: id.clone(),
+                output_path,
+            },
+        );
+        Ok(())
+    } else {
+        let err_msg = format!("Process terminated with code {:?}", exit_code);
+        Err(ConversionError::Worker(err_msg))
+    }
+}
+
+/// Turns ffmpeg's `time=` status lines into a completion percentage.
+pub(crate) struct ProgressTracker {
+    expected_duration: f64,
+    total_duration: Option<f64>,
+}
+
+impl ProgressTracker {
+    /// `expected_duration` is the trimmed output length in seconds; when it
+    /// is 0 the input's `Duration:` line is used instead.
+    pub(crate) fn new(expected_duration: f64) -> Self {
+        Self {
+            expected_duration,
+            total_duration: None,
+        }
+    }
+
+    pub(crate) fn update(&mut self, line: &str) -> Option<f64> {
+        if self.total_duration.is_none()
+            && let Some(caps) = DURATION_REGEX.captures(line)
+        {
+            self.total_duration = parse_time(&caps[1]);
+        }
+
+        let current_time = parse_time(&TIME_REGEX.captures(line)?[1])?;
+        let duration = if self.expected_duration > 0.0 {
+            self.expected_duration
+        } else {
+            self.total_duration.unwrap_or(0.0)
+        };
+        (duration > 0.0).then(|| (current_time / duration * 100.0).min(100.0))
+    }
+}
+
+/// Forwards ffmpeg's stderr as `conversion-log` events and its status lines
+/// as `conversion-progress`, and returns the exit code.
+pub(crate) async fn relay_ffmpeg_events<E: EventSink>(
+    events: &E,
+    id: &str,
+    rx: &mut mpsc::Receiver<CommandEvent>,
+    expected_duration: f64,
+) -> Option<i32> {
+    let mut tracker = ProgressTracker::new(expected_duration);
+    drain_ffmpeg_events(rx, |line| {
+        events.emit(
+            "conversion-log",
+            LogPayload {
+                id: id.to_string(),
+                line: line.to_string(),
+            },
+        );
+
+        if let Some(progress) = tracker.update(line) {
+            events.emit(
+                "conversion-progress",
+                ProgressPayload {
+                    id: id.to_string(),
+                    progress,
+                },
+            );
+        }
+    })
+    .await
+}
+
+/// Feeds every non-empty stderr line of a sidecar to `on_line` until the
+/// process ends, and returns its exit code.
+pub(crate) async fn drain_ffmpeg_events(
+    rx: &mut mpsc::Receiver<CommandEvent>,
+    mut on_line: impl FnMut(&str),
+) -> Option<i32> {
+    let mut exit_code = None;
     while let Some(event) = rx.recv().await {
         match event {
             CommandEvent::Stderr(line_bytes) => {
-                let raw_output = String::from_utf8_lossy(&line_bytes).to_string();
-
+                let raw_output = String::from_utf8_lossy(&line_bytes);
                 for segment in raw_output.split(['\r', '\n']) {
                     let line = segment.trim();
-                    if line.is_empty() {
-                        continue;
-                    }
-
-                    let _ = app.emit(
-                        "conversion-log",
-                        LogPayload {
-                            id: id.clone(),
-                            line: line.to_string(),
-                        },
-                    );
-
-                    if let Some(caps) = TIME_REGEX.captures(line) {
-                        if let Some(match_str) = caps.get(1) {
-                            if let Some(current_time) = parse_time(match_str.as_str()) {
-                                let duration = if expected_duration > 0.0 {
-                                    expected_duration
-                                } else if let Some(d) = total_duration {
-                                    d
-                                } else if let Some(caps) = DURATION_REGEX.captures(line) {
-                                    if let Some(m) = caps.get(1) {
-                                        total_duration = parse_time(m.as_str());
-                                        total_duration.unwrap_or(0.0)
-                                    } else {
-                                        0.0
-                                    }
-                                } else {
-                                    0.0
-                                };
-
-                                if duration > 0.0 {
-                                    let progress = (current_time / duration * 100.0).min(100.0);
-                                    let _ = app.emit(
-                                        "conversion-progress",
-                                        ProgressPayload {
-                                            id: id.clone(),
-                                            progress,
-                                        },
-                                    );
-                                }
-                            }
-                        }
+                    if !line.is_empty() {
+                        on_line(line);
                     }
                 }
             }
@@ -141,18 +188,5 @@ pub async fn run_ffmpeg_worker(
             _ => {}
         }
     }
-
-    if exit_code == Some(0) {
-        let _ = app.emit(
-            "conversion-completed",
-            CompletedPayload {
-                id: id.clone(),
-                output_path,
-            },
-        );
-        Ok(())
-    } else {
-        let err_msg = format!("Process terminated with code {:?}", exit_code);
-        Err(ConversionError::Worker(err_msg))
-    }
+    exit_code
 }
@@ -0,0 +1,47 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// Destination for the events workers report to the frontend.
+///
+/// The app handle forwards them over Tauri's event bus; tests use a
+/// recorder so workers can run without a Tauri app.
+pub trait EventSink: Clone + Send + Sync + 'static {
+    fn emit<P: Serialize + Clone>(&self, event: &str, payload: P);
+}
+
+impl<R: Runtime> EventSink for AppHandle<R> {
+    fn emit<P: Serialize + Clone>(&self, event: &str, payload: P) {
+        let _ = Emitter::emit(self, event, payload);
+    }
+}
+
+/// Records every emitted event with its JSON payload.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct RecordingSink {
+    events: std::sync::Arc<std::sync::Mutex<Vec<(String, serde_json::Value)>>>,
+}
+
+#[cfg(test)]
+impl RecordingSink {
+    pub(crate) fn events(&self) -> Vec<(String, serde_json::Value)> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Payloads of every event named `event`, in order.
+    pub(crate) fn payloads(&self, event: &str) -> Vec<serde_json::Value> {
+        self.events()
+            .into_iter()
+            .filter(|(name, _)| name == event)
+            .map(|(_, payload)| payload)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl EventSink for RecordingSink {
+    fn emit<P: Serialize + Clone>(&self, event: &str, payload: P) {
+        let value = serde_json::to_value(payload).unwrap_or(serde_json::Value::Null);
+        self.events.lock().unwrap().push((event.to_string(), value));
+    }
+}
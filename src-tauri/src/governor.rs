@@ -0,0 +1,194 @@
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use tokio::sync::Notify;
+
+use crate::conversion::types::ConversionConfig;
+use crate::conversion::utils::{is_nvenc_codec, is_videotoolbox_codec};
+
+/// Consumer NVIDIA cards cap concurrent NVENC sessions; two stays well clear
+/// of the limit and keeps VideoToolbox responsive.
+pub const DEFAULT_MAX_HARDWARE_ENCODES: usize = 2;
+pub const DEFAULT_MAX_HEAVY_JOBS: usize = 2;
+
+/// Kind of machine resource a job occupies for its whole run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceClass {
+    /// A hardware video encoder session (NVENC, VideoToolbox).
+    HardwareEncoder,
+    /// A CPU- or GPU-compute-heavy job: software encodes, ML upscaling, depth
+    /// inference.
+    Heavy,
+}
+
+impl ResourceClass {
+    pub fn for_conversion(config: &ConversionConfig) -> Self {
+        let upscaling = config
+            .ml_upscale
+            .as_deref()
+            .is_some_and(|mode| !mode.is_empty() && mode != "none");
+        if !upscaling
+            && (is_nvenc_codec(&config.video_codec) || is_videotoolbox_codec(&config.video_codec))
+        {
+            ResourceClass::HardwareEncoder
+        } else {
+            ResourceClass::Heavy
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimits {
+    pub max_hardware_encodes: usize,
+    pub max_heavy_jobs: usize,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_hardware_encodes: DEFAULT_MAX_HARDWARE_ENCODES,
+            max_heavy_jobs: DEFAULT_MAX_HEAVY_JOBS,
+        }
+    }
+}
+
+/// Counting limit whose size can change while jobs hold or wait for slots.
+struct Pool {
+    limit: AtomicUsize,
+    in_use: Mutex<usize>,
+    released: Notify,
+}
+
+impl Pool {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit: AtomicUsize::new(limit),
+            in_use: Mutex::new(0),
+            released: Notify::new(),
+        }
+    }
+
+    fn try_take(&self) -> bool {
+        let mut in_use = self.in_use.lock().unwrap();
+        if *in_use < self.limit.load(Ordering::SeqCst).max(1) {
+            *in_use += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn release(&self) {
+        {
+            let mut in_use = self.in_use.lock().unwrap();
+            *in_use = in_use.saturating_sub(1);
+        }
+        self.released.notify_waiters();
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::SeqCst);
+        self.released.notify_waiters();
+    }
+}
+
+/// Slot in the governor, returned to its pool on drop.
+pub struct ResourcePermit {
+    pool: Arc<Pool>,
+}
+
+impl Drop for ResourcePermit {
+    fn drop(&mut self) {
+        self.pool.release();
+    }
+}
+
+/// App-wide limits shared by the conversion and spatial queues, so that
+/// both managers draw from the same hardware encoder and compute budgets
+/// instead of each saturating the machine on its own.
+pub struct ResourceGovernor {
+    hardware_encodes: Arc<Pool>,
+    heavy_jobs: Arc<Pool>,
+}
+
+impl Default for ResourceGovernor {
+    fn default() -> Self {
+        Self::new(ResourceLimits::default())
+    }
+}
+
+impl ResourceGovernor {
+    pub fn new(limits: ResourceLimits) -> Self {
+        Self {
+            hardware_encodes: Arc::new(Pool::new(limits.max_hardware_encodes)),
+            heavy_jobs: Arc::new(Pool::new(limits.max_heavy_jobs)),
+        }
+    }
+
+    fn pool(&self, class: ResourceClass) -> &Arc<Pool> {
+        match class {
+            ResourceClass::HardwareEncoder => &self.hardware_encodes,
+            ResourceClass::Heavy => &self.heavy_jobs,
+        }
+    }
+
+    /// Takes a slot without waiting, if one is free.
+    pub fn try_acquire(&self, class: ResourceClass) -> Option<ResourcePermit> {
+        let pool = self.pool(class);
+        pool.try_take().then(|| ResourcePermit {
+            pool: Arc::clone(pool),
+        })
+    }
+
+    /// Waits until a slot of `class` is free and takes it.
+    pub async fn acquire(&self, class: ResourceClass) -> ResourcePermit {
+        let pool = self.pool(class);
+        loop {
+            // Register for wakeups before checking so a release between the
+            // check and the await is not missed
+            let released = pool.released.notified();
+            if pool.try_take() {
+                return ResourcePermit {
+                    pool: Arc::clone(pool),
+                };
+            }
+            released.await;
+        }
+    }
+
+    pub fn limits(&self) -> ResourceLimits {
+        ResourceLimits {
+            max_hardware_encodes: self.hardware_encodes.limit.load(Ordering::SeqCst),
+            max_heavy_jobs: self.heavy_jobs.limit.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Applies new limits. Running jobs keep their slots; lowering a limit
+    /// only delays new starts until enough of them finish.
+    pub fn set_limits(&self, limits: ResourceLimits) -> Result<(), String> {
+        if limits.max_hardware_encodes == 0 || limits.max_heavy_jobs == 0 {
+            return Err("Resource limits must be at least 1".to_string());
+        }
+        self.hardware_encodes.set_limit(limits.max_hardware_encodes);
+        self.heavy_jobs.set_limit(limits.max_heavy_jobs);
+        Ok(())
+    }
+}
+
+#[command]
+pub fn get_resource_limits(governor: tauri::State<'_, ResourceGovernor>) -> ResourceLimits {
+    governor.limits()
+}
+
+#[command]
+pub fn set_resource_limits(
+    governor: tauri::State<'_, ResourceGovernor>,
+    limits: ResourceLimits,
+) -> Result<(), String> {
+    governor.set_limits(limits)
+}
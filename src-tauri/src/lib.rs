@@ -1,9 +1,15 @@
+mod alerts;
+mod appearance;
 mod capabilities;
 mod conversion;
 mod dialog;
+mod events;
+mod governor;
 mod spatial;
+mod watchdog;
+use appearance::apply_window_effect;
 use std::time::Duration;
-use tauri::window::{Color, EffectState};
+use tauri::window::Color;
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
 use tauri_plugin_store::Builder as StoreBuilder;
 use tokio::time::sleep;
@@ -16,33 +22,6 @@ async fn close_splash(window: tauri::Window) {
     window.get_webview_window("main").unwrap().show().unwrap();
 }
 
-#[cfg(target_os = "macos")]
-fn apply_window_effect(window: &tauri::WebviewWindow) {
-    use tauri::window::{Effect, EffectsBuilder};
-
-    window
-        .set_effects(
-            EffectsBuilder::new()
-                .effect(Effect::HudWindow)
-                .state(EffectState::Active)
-                .radius(16.0)
-                .build(),
-        )
-        .expect("Unsupported platform! 'HudWindow' effect is only supported on macOS");
-}
-
-#[cfg(target_os = "windows")]
-fn apply_window_effect(window: &tauri::WebviewWindow) {
-    use tauri::window::{Effect, EffectsBuilder};
-
-    window
-        .set_effects(EffectsBuilder::new().effect(Effect::Acrylic).build())
-        .expect("Unsupported platform! 'Acrylic' effect is only supported on Windows");
-}
-
-#[cfg(not(any(target_os = "macos", target_os = "windows")))]
-fn apply_window_effect(_window: &tauri::WebviewWindow) {}
-
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -50,6 +29,8 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
+            app.manage(appearance::Appearance::default());
+
             let builder =
                 WebviewWindowBuilder::new(app, "main", WebviewUrl::App("index.html".into()))
                     .title("Frame")
@@ -73,6 +54,8 @@ pub fn run() {
                         tauri::async_runtime::spawn(async move {
                             sleep(Duration::from_millis(10)).await;
                             apply_window_effect(&target);
+                            appearance::refresh_accessibility_preferences(target.app_handle())
+                                .await;
                         });
                     }
                     if let WindowEvent::CloseRequested { .. } = event {
@@ -95,6 +78,11 @@ pub fn run() {
 
             apply_window_effect(&splash);
 
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                appearance::refresh_accessibility_preferences(&handle).await;
+            });
+
             #[cfg(target_os = "macos")]
             {
                 let dialog_host = WebviewWindowBuilder::new(
@@ -120,8 +108,36 @@ pub fn run() {
                 let _ = dialog_host.hide();
             }
 
+            app.manage(governor::ResourceGovernor::default());
+            app.manage(alerts::AlertCenter::default());
+            app.manage(match app.path().app_data_dir() {
+                Ok(dir) => conversion::history::JobHistory::load(
+                    dir.join(conversion::history::HISTORY_FILE),
+                ),
+                Err(e) => {
+                    eprintln!(
+                        "No app data directory, conversion history is not saved: {}",
+                        e
+                    );
+                    conversion::history::JobHistory::default()
+                }
+            });
+            app.manage(match app.path().app_data_dir() {
+                Ok(dir) => spatial::saved_queue::SavedQueue::load(
+                    dir.join(spatial::saved_queue::QUEUE_FILE),
+                ),
+                Err(e) => {
+                    eprintln!(
+                        "No app data directory, the spatial queue is not saved: {}",
+                        e
+                    );
+                    spatial::saved_queue::SavedQueue::default()
+                }
+            });
             app.manage(conversion::ConversionManager::new(app.handle().clone()));
             app.manage(spatial::SpatialManager::new(app.handle().clone()));
+            app.manage(spatial::preview::StereoPreview::default());
+            app.manage(spatial::bootstrap::Bootstrap::default());
 
             Ok(())
         })
@@ -137,16 +153,54 @@ pub fn run() {
             conversion::commands::pause_conversion,
             conversion::commands::resume_conversion,
             conversion::commands::cancel_conversion,
+            conversion::commands::relink_conversion_source,
             conversion::commands::probe_media,
+            conversion::commands::list_image_files,
+            conversion::commands::generate_contact_sheet,
+            conversion::commands::get_duplicate_policy,
+            conversion::commands::set_duplicate_policy,
             conversion::commands::get_max_concurrency,
             conversion::commands::set_max_concurrency,
+            conversion::commands::get_history_summary,
+            conversion::commands::get_codec_speeds,
+            conversion::commands::get_failure_timeline,
+            conversion::commands::clear_conversion_history,
             capabilities::get_available_encoders,
+            capabilities::get_gpu_devices,
+            governor::get_resource_limits,
+            governor::set_resource_limits,
+            alerts::get_queue_alerts,
+            alerts::set_queue_alerts,
+            alerts::list_completion_sounds,
+            alerts::is_do_not_disturb,
+            alerts::play_completion_sound,
+            alerts::queue_finished,
+            appearance::get_accessibility_preferences,
+            appearance::get_appearance,
+            appearance::set_window_effects,
             dialog::open_native_file_dialog,
             dialog::ask_native_dialog,
             spatial::commands::queue_spatial,
+            spatial::commands::queue_spatial_batch,
+            spatial::commands::saved_spatial_queue,
+            spatial::commands::restore_spatial_queue,
+            spatial::commands::pause_spatial,
+            spatial::commands::resume_spatial,
             spatial::commands::cancel_spatial,
             spatial::commands::check_spatial_models,
             spatial::commands::download_spatial_model,
+            spatial::commands::cancel_spatial_model_download,
+            spatial::commands::delete_spatial_model,
+            spatial::commands::verify_spatial_model,
+            spatial::commands::recommend_spatial_encoder,
+            spatial::bootstrap::bootstrap_status,
+            spatial::bootstrap::bootstrap_run,
+            spatial::preview::list_displays,
+            spatial::preview::get_stereo_preview,
+            spatial::preview::open_stereo_preview,
+            spatial::preview::set_stereo_preview_display,
+            spatial::preview::set_stereo_preview_layout,
+            spatial::preview::close_stereo_preview,
             close_splash,
         ])
         .run(tauri::generate_context!())
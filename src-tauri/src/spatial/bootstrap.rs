@@ -0,0 +1,343 @@
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::plugin::PermissionState;
+use tauri::{AppHandle, command};
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_shell::ShellExt;
+
+use crate::conversion::sidecar::{expected_sidecar_path, sidecar_error};
+use crate::events::EventSink;
+use crate::spatial::commands::{download_model, model_downloaded};
+use crate::spatial::error::SpatialError;
+use crate::spatial::types::EncoderSize;
+
+pub const BOOTSTRAP_PROGRESS_EVENT: &str = "bootstrap-progress";
+
+const SIDECARS: [&str; 2] = ["ffmpeg", "ffprobe"];
+
+/// Something first-run setup checks and, where it can, fixes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum BootstrapStep {
+    /// The bundled ffmpeg and ffprobe sidecars start.
+    Ffmpeg,
+    /// The `spatial` CLI is on the PATH to turn side-by-side renders into
+    /// MV-HEVC spatial videos. Without it jobs keep the side-by-side video.
+    SpatialTool,
+    /// A depth model checkpoint is downloaded.
+    #[serde(rename_all = "camelCase")]
+    Model { encoder_size: EncoderSize },
+    /// The small depth model has been loaded once this session, so the first
+    /// job does not wait for its session to build.
+    WarmDepth,
+    /// Notifications may be shown when a queue finishes.
+    Notifications,
+}
+
+impl BootstrapStep {
+    /// Every step, in the order a wizard should run them.
+    pub fn all() -> Vec<BootstrapStep> {
+        let mut steps = vec![BootstrapStep::Ffmpeg, BootstrapStep::SpatialTool];
+        steps.extend(
+            EncoderSize::ALL
+                .into_iter()
+                .map(|encoder_size| BootstrapStep::Model { encoder_size }),
+        );
+        steps.push(BootstrapStep::WarmDepth);
+        steps.push(BootstrapStep::Notifications);
+        steps
+    }
+
+    /// The steps conversions need with the `encoder_size` model: the
+    /// sidecars and that model.
+    pub fn required_for(encoder_size: EncoderSize) -> Vec<BootstrapStep> {
+        vec![BootstrapStep::Ffmpeg, BootstrapStep::Model { encoder_size }]
+    }
+
+    /// Whether conversions fail without this step. Only the small model is
+    /// needed, as it is the default.
+    pub fn required(self) -> bool {
+        matches!(
+            self,
+            BootstrapStep::Ffmpeg
+                | BootstrapStep::Model {
+                    encoder_size: EncoderSize::Small
+                }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapCheck {
+    pub step: BootstrapStep,
+    pub ready: bool,
+    pub required: bool,
+    /// What is wrong and how to fix it, when not ready.
+    pub detail: Option<String>,
+}
+
+impl BootstrapCheck {
+    fn new(step: BootstrapStep, problem: Option<String>) -> Self {
+        Self {
+            step,
+            ready: problem.is_none(),
+            required: step.required(),
+            detail: problem,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapStatus {
+    /// Every required check passes.
+    pub ready: bool,
+    pub checks: Vec<BootstrapCheck>,
+}
+
+impl BootstrapStatus {
+    pub fn new(checks: Vec<BootstrapCheck>) -> Self {
+        Self {
+            ready: checks.iter().all(|check| check.ready || !check.required),
+            checks,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepState {
+    Running,
+    Done,
+    /// Already in place; nothing was done.
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapProgressPayload {
+    pub step: BootstrapStep,
+    pub state: StepState,
+    /// Position of the step in the run, from 0.
+    pub index: usize,
+    pub total: usize,
+    pub error: Option<String>,
+}
+
+/// What setup has done this session, managed as Tauri state.
+#[derive(Default)]
+pub struct Bootstrap {
+    warmed: AtomicBool,
+    running: AtomicBool,
+}
+
+/// Holds `Bootstrap::running` for one run and clears it when dropped, so a
+/// run that returns early or panics does not block the next one.
+pub(crate) struct RunningGuard<'a>(&'a AtomicBool);
+
+impl<'a> RunningGuard<'a> {
+    /// `None` while another run holds the flag.
+    pub(crate) fn acquire(running: &'a AtomicBool) -> Option<Self> {
+        (!running.swap(true, Ordering::SeqCst)).then_some(RunningGuard(running))
+    }
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// First match for `binary` in a PATH-style list of directories.
+pub(crate) fn find_in_path(binary: &str, path_var: &OsStr) -> Option<PathBuf> {
+    let file = if cfg!(windows) {
+        format!("{binary}.exe")
+    } else {
+        binary.to_string()
+    };
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(&file))
+        .find(|candidate| candidate.is_file())
+}
+
+fn spatial_cli_problem() -> Option<String> {
+    let path_var = std::env::var_os("PATH").unwrap_or_default();
+    match find_in_path("spatial", &path_var) {
+        Some(_) => None,
+        None => Some(
+            "The spatial CLI is not installed, so spatial videos stay side-by-side. Install it with `brew install spatial`."
+                .to_string(),
+        ),
+    }
+}
+
+fn sidecar_problem() -> Option<String> {
+    SIDECARS.iter().find_map(|binary| {
+        let expected = expected_sidecar_path(binary);
+        (!expected.is_file()).then(|| {
+            format!(
+                "{} is missing (expected at {}). Reinstall Frame to restore the bundled tools.",
+                binary,
+                expected.display()
+            )
+        })
+    })
+}
+
+fn notification_problem(app: &AppHandle) -> Option<String> {
+    match app.notification().permission_state() {
+        Ok(PermissionState::Granted) => None,
+        Ok(PermissionState::Denied) => {
+            Some("Notifications are turned off for Frame in system settings.".to_string())
+        }
+        Ok(_) => Some("Frame has not asked to show notifications yet.".to_string()),
+        Err(e) => Some(format!("Could not read the notification permission: {}", e)),
+    }
+}
+
+fn check(app: &AppHandle, bootstrap: &Bootstrap, step: BootstrapStep) -> BootstrapCheck {
+    let problem = match step {
+        BootstrapStep::Ffmpeg => sidecar_problem(),
+        BootstrapStep::SpatialTool => spatial_cli_problem(),
+        BootstrapStep::Model { encoder_size } => (!model_downloaded(encoder_size)).then(|| {
+            format!(
+                "The {} depth model is not downloaded.",
+                encoder_size.as_str()
+            )
+        }),
+        BootstrapStep::WarmDepth => (!bootstrap.warmed.load(Ordering::SeqCst))
+            .then(|| "The depth model has not been loaded yet this session.".to_string()),
+        BootstrapStep::Notifications => notification_problem(app),
+    };
+    BootstrapCheck::new(step, problem)
+}
+
+fn collect_status(app: &AppHandle, bootstrap: &Bootstrap) -> BootstrapStatus {
+    BootstrapStatus::new(
+        BootstrapStep::all()
+            .into_iter()
+            .map(|step| check(app, bootstrap, step))
+            .collect(),
+    )
+}
+
+async fn verify_sidecars(app: &AppHandle) -> Result<(), String> {
+    for binary in SIDECARS {
+        let output = app
+            .shell()
+            .sidecar(binary)
+            .map_err(|e| sidecar_error(binary, e).to_string())?
+            .args(["-version"])
+            .output()
+            .await
+            .map_err(|e| sidecar_error(binary, e).to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} -version failed: {}",
+                binary,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Load the small depth model once so spatial-maker keeps its session for
+/// the first job.
+async fn warm_depth_model() -> Result<(), String> {
+    spatial_maker::warmup(EncoderSize::Small.model_size())
+        .await
+        .map_err(|e| format!("Failed to load the depth model: {}", e))
+}
+
+async fn run_step(
+    app: &AppHandle,
+    bootstrap: &Bootstrap,
+    step: BootstrapStep,
+) -> Result<(), String> {
+    match step {
+        BootstrapStep::Ffmpeg => verify_sidecars(app).await,
+        BootstrapStep::SpatialTool => spatial_cli_problem().map_or(Ok(()), Err),
+        BootstrapStep::Model { encoder_size } => download_model(app, encoder_size)
+            .await
+            .map_err(|e| e.to_string()),
+        BootstrapStep::WarmDepth => {
+            warm_depth_model().await?;
+            bootstrap.warmed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        BootstrapStep::Notifications => match app.notification().request_permission() {
+            Ok(PermissionState::Granted) => Ok(()),
+            Ok(_) => Err("Notifications were not allowed.".to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+    }
+}
+
+/// Reports what first-run setup still has to do.
+#[command]
+pub async fn bootstrap_status(
+    app: AppHandle,
+    bootstrap: tauri::State<'_, Bootstrap>,
+) -> Result<BootstrapStatus, SpatialError> {
+    Ok(collect_status(&app, &bootstrap))
+}
+
+/// Runs `steps` in order, emitting `bootstrap-progress` before and after
+/// each. With no steps it runs the required ones: the sidecars and the
+/// `encoder_size` model (small when unset). Steps already in place are
+/// skipped, except the ffmpeg check, which always runs the sidecars. A failed
+/// step does not stop the ones after it; the returned status shows what is
+/// still missing.
+#[command]
+pub async fn bootstrap_run(
+    app: AppHandle,
+    bootstrap: tauri::State<'_, Bootstrap>,
+    steps: Vec<BootstrapStep>,
+    encoder_size: Option<EncoderSize>,
+) -> Result<BootstrapStatus, SpatialError> {
+    let Some(_running) = RunningGuard::acquire(&bootstrap.running) else {
+        return Err(SpatialError::InvalidInput(
+            "Setup is already running".to_string(),
+        ));
+    };
+
+    let steps = if steps.is_empty() {
+        BootstrapStep::required_for(encoder_size.unwrap_or(EncoderSize::Small))
+    } else {
+        steps
+    };
+    let total = steps.len();
+    for (index, step) in steps.into_iter().enumerate() {
+        let emit = |state: StepState, error: Option<String>| {
+            EventSink::emit(
+                &app,
+                BOOTSTRAP_PROGRESS_EVENT,
+                BootstrapProgressPayload {
+                    step,
+                    state,
+                    index,
+                    total,
+                    error,
+                },
+            );
+        };
+
+        if step != BootstrapStep::Ffmpeg && check(&app, &bootstrap, step).ready {
+            emit(StepState::Skipped, None);
+            continue;
+        }
+        emit(StepState::Running, None);
+        match run_step(&app, &bootstrap, step).await {
+            Ok(()) => emit(StepState::Done, None),
+            Err(error) => emit(StepState::Failed, Some(error)),
+        }
+    }
+
+    Ok(collect_status(&app, &bootstrap))
+}
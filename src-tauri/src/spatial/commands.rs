@@ -1,111 +1,105 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use futures_util::StreamExt;
-use tauri::{command, AppHandle, Emitter};
+use spatial_maker::CancellationToken;
+use spatial_maker::model::{ModelMetadata, get_checkpoint_dir};
+use spatial_maker::model_lock::{LockStatus, ModelLock, lock_path};
+use spatial_maker::video::VIDEO_EXTENSIONS;
+use tauri::{command, AppHandle, Emitter, Manager};
 
+use crate::conversion::args::sanitize_output_name;
+use crate::conversion::utils::{IMAGE_INPUT_EXTENSIONS, collect_files};
+use crate::spatial::disk::ensure_space;
 use crate::spatial::error::SpatialError;
+use crate::spatial::hardware::{EncoderRecommendation, SpatialHardware, recommend_encoder};
 use crate::spatial::manager::{SpatialManager, SpatialMessage};
+use crate::spatial::saved_queue::SavedQueue;
 use crate::spatial::types::{
-    ModelDownloadCompletePayload, ModelDownloadErrorPayload, ModelDownloadProgressPayload,
-    SpatialConfig, SpatialTask,
+    EncoderSize, ModelDownloadCompletePayload, ModelDownloadErrorPayload,
+    ModelDownloadProgressPayload, ModelIntegrity, ModelVerification, RestoredSpatialTask,
+    SpatialBatch, SpatialBatchTask, SpatialConfig, SpatialTask,
+};
+use crate::spatial::worker::{
+    OUTPUT_STEM, output_file, validate_extract_config, validate_output_config,
 };
 
-fn get_checkpoint_dir() -> PathBuf {
-    dirs_next().join("checkpoints")
+/// Whether the ONNX depth model for `encoder_size` is downloaded.
+pub(crate) fn model_downloaded(encoder_size: EncoderSize) -> bool {
+    spatial_maker::model_exists(encoder_size.model_size())
 }
 
-fn dirs_next() -> PathBuf {
-    if let Ok(val) = std::env::var("SPATIAL_MAKER_CHECKPOINTS") {
-        return PathBuf::from(val);
-    }
-    let home = std::env::var("HOME")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| PathBuf::from("."));
-    home.join(".spatial-maker")
+/// Which models are downloaded, keyed by encoder size.
+fn model_states() -> HashMap<String, bool> {
+    EncoderSize::ALL
+        .into_iter()
+        .map(|size| (size.as_str().to_string(), model_downloaded(size)))
+        .collect()
 }
 
-fn encoder_to_checkpoint(encoder_size: &str) -> Option<(&'static str, &'static str)> {
-    match encoder_size {
-        "s" => Some((
-            "depth_anything_v2_vits.pth",
-            "https://huggingface.co/depth-anything/Depth-Anything-V2-Small/resolve/main/depth_anything_v2_vits.pth",
-        )),
-        "m" => Some((
-            "depth_anything_v2_vitb.pth",
-            "https://huggingface.co/depth-anything/Depth-Anything-V2-Base/resolve/main/depth_anything_v2_vitb.pth",
-        )),
-        "l" => Some((
-            "depth_anything_v2_vitl.pth",
-            "https://huggingface.co/depth-anything/Depth-Anything-V2-Large/resolve/main/depth_anything_v2_vitl.pth",
-        )),
-        _ => None,
-    }
+#[command]
+pub async fn check_spatial_models() -> Result<HashMap<String, bool>, SpatialError> {
+    Ok(model_states())
 }
 
+/// Removes the downloaded model for `encoder_size` from the checkpoint dir
+/// and emits `spatial-models-changed` with the new model states. A model
+/// that is still downloading has to be cancelled first.
 #[command]
-pub async fn check_spatial_models() -> Result<HashMap<String, bool>, SpatialError> {
-    let checkpoint_dir = get_checkpoint_dir();
-    let mut result = HashMap::new();
-    for size in &["s", "m", "l"] {
-        if let Some((filename, _)) = encoder_to_checkpoint(size) {
-            let exists = checkpoint_dir.join(filename).exists();
-            result.insert(size.to_string(), exists);
-        }
+pub async fn delete_spatial_model(
+    app: AppHandle,
+    manager: tauri::State<'_, SpatialManager>,
+    encoder_size: EncoderSize,
+) -> Result<(), SpatialError> {
+    if manager.is_downloading(encoder_size) {
+        return Err(SpatialError::InvalidInput(format!(
+            "The {} model is still downloading",
+            encoder_size.as_str()
+        )));
     }
-    Ok(result)
+
+    spatial_maker::delete_model(encoder_size.model_size())?;
+    let _ = app.emit("spatial-models-changed", model_states());
+    Ok(())
 }
 
 #[command]
 pub async fn download_spatial_model(
     app: AppHandle,
-    encoder_size: String,
+    encoder_size: EncoderSize,
 ) -> Result<(), SpatialError> {
-    let (filename, url) = encoder_to_checkpoint(&encoder_size).ok_or_else(|| {
-        SpatialError::InvalidInput(format!("Invalid encoder size: {}", encoder_size))
-    })?;
-
-    let checkpoint_dir = get_checkpoint_dir();
-    std::fs::create_dir_all(&checkpoint_dir).map_err(|e| {
-        SpatialError::Io(std::io::Error::new(
-            e.kind(),
-            format!("Failed to create checkpoint dir: {}", e),
-        ))
-    })?;
-
-    let dest = checkpoint_dir.join(filename);
-    let temp_dest = checkpoint_dir.join(format!("{}.downloading", filename));
-
-    let response = reqwest::get(url).await.map_err(|e| {
+    download_model(&app, encoder_size).await
+}
+
+/// Downloads the depth model for `encoder_size` through spatial-maker,
+/// emitting the `spatial-model-download-*` events as it goes. The download
+/// is registered with the `SpatialManager` so it can be cancelled; a
+/// cancelled download ends with the error event and leaves no partial file,
+/// as does one the checkpoint volume has no room for.
+pub(crate) async fn download_model(
+    app: &AppHandle,
+    encoder_size: EncoderSize,
+) -> Result<(), SpatialError> {
+    let emit_error = |error: &SpatialError| {
         let _ = app.emit(
             "spatial-model-download-error",
             ModelDownloadErrorPayload {
-                encoder_size: encoder_size.clone(),
-                error: e.to_string(),
+                encoder_size,
+                error: error.to_string(),
             },
         );
-        SpatialError::Shell(format!("Download failed: {}", e))
-    })?;
+    };
 
-    let total_bytes = response.content_length().unwrap_or(0);
-    let mut stream = response.bytes_stream();
-    let mut downloaded: u64 = 0;
-    let mut file = std::fs::File::create(&temp_dest).map_err(SpatialError::Io)?;
+    let model_bytes = u64::from(ModelMetadata::for_encoder(encoder_size.model_size()).size_mb);
+    let space = get_checkpoint_dir()
+        .map_err(SpatialError::from)
+        .and_then(|dir| ensure_space(&dir, model_bytes * 1_000_000));
+    if let Err(e) = space {
+        emit_error(&e);
+        return Err(e);
+    }
 
-    use std::io::Write;
     let mut last_emit_pct: f64 = -1.0;
-
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.map_err(|e| {
-            let _ = std::fs::remove_file(&temp_dest);
-            SpatialError::Shell(format!("Download stream error: {}", e))
-        })?;
-        file.write_all(&chunk).map_err(|e| {
-            let _ = std::fs::remove_file(&temp_dest);
-            SpatialError::Io(e)
-        })?;
-        downloaded += chunk.len() as u64;
-
+    let on_progress = |downloaded: u64, total_bytes: u64| {
         let pct = if total_bytes > 0 {
             (downloaded as f64 / total_bytes as f64 * 100.0).round()
         } else {
@@ -117,31 +111,101 @@ pub async fn download_spatial_model(
             let _ = app.emit(
                 "spatial-model-download-progress",
                 ModelDownloadProgressPayload {
-                    encoder_size: encoder_size.clone(),
+                    encoder_size,
                     bytes_downloaded: downloaded,
                     total_bytes,
                     progress: pct,
                 },
             );
         }
-    }
+    };
+
+    let cancel = app
+        .state::<SpatialManager>()
+        .start_model_download(encoder_size)?;
+    let result = spatial_maker::model::ensure_model_exists_with(
+        encoder_size.model_size(),
+        Some(on_progress),
+        Some(&cancel),
+    )
+    .await;
+    app.state::<SpatialManager>()
+        .finish_model_download(encoder_size);
 
-    drop(file);
-    std::fs::rename(&temp_dest, &dest).map_err(|e| {
-        let _ = std::fs::remove_file(&temp_dest);
-        SpatialError::Io(e)
-    })?;
+    if let Err(e) = result {
+        let e = SpatialError::from(e);
+        emit_error(&e);
+        return Err(e);
+    }
 
     let _ = app.emit(
         "spatial-model-download-complete",
-        ModelDownloadCompletePayload {
-            encoder_size: encoder_size.clone(),
-        },
+        ModelDownloadCompletePayload { encoder_size },
     );
 
     Ok(())
 }
 
+/// Suggests the encoder size this machine handles best, with a rough speed.
+#[command]
+pub async fn recommend_spatial_encoder(
+    app: AppHandle,
+) -> Result<EncoderRecommendation, SpatialError> {
+    Ok(recommend_encoder(SpatialHardware::detect(&app).await))
+}
+
+/// Hashes the downloaded model for `encoder_size` and compares it with
+/// `models.lock`.
+async fn verify_model(encoder_size: EncoderSize) -> Result<ModelVerification, SpatialError> {
+    // Hashing the large model takes a few seconds
+    let status = tauri::async_runtime::spawn_blocking(move || {
+        let size = encoder_size.model_size();
+        let Ok(path) = spatial_maker::find_model(size) else {
+            return Ok(LockStatus::NotInstalled);
+        };
+        ModelLock::load(&lock_path()?)?.check(size, &path)
+    })
+    .await
+    .map_err(|e| SpatialError::Worker(format!("Model check stopped: {}", e)))??;
+    Ok(ModelVerification::new(encoder_size, &status))
+}
+
+/// Checks the downloaded model for `encoder_size` against its recorded size
+/// and SHA-256. With `redownload`, a corrupt or partial file is deleted and
+/// downloaded again, and the result describes the new file.
+#[command]
+pub async fn verify_spatial_model(
+    app: AppHandle,
+    encoder_size: EncoderSize,
+    redownload: bool,
+) -> Result<ModelVerification, SpatialError> {
+    let verification = verify_model(encoder_size).await?;
+    if !redownload || verification.integrity != ModelIntegrity::Corrupt {
+        return Ok(verification);
+    }
+    if app.state::<SpatialManager>().is_downloading(encoder_size) {
+        return Err(SpatialError::InvalidInput(format!(
+            "The {} model is already downloading",
+            encoder_size.as_str()
+        )));
+    }
+
+    spatial_maker::delete_model(encoder_size.model_size())?;
+    download_model(&app, encoder_size).await?;
+    Ok(ModelVerification {
+        redownloaded: true,
+        ..verify_model(encoder_size).await?
+    })
+}
+
+#[command]
+pub async fn cancel_spatial_model_download(
+    manager: tauri::State<'_, SpatialManager>,
+    encoder_size: EncoderSize,
+) -> Result<(), SpatialError> {
+    manager.cancel_model_download(encoder_size)
+}
+
 #[command]
 pub async fn queue_spatial(
     manager: tauri::State<'_, SpatialManager>,
@@ -160,18 +224,16 @@ pub async fn queue_spatial(
         )));
     }
 
-    let valid_sizes = ["s", "m", "l"];
-    if !valid_sizes.contains(&config.encoder_size.as_str()) {
-        return Err(SpatialError::InvalidInput(format!(
-            "Invalid encoder size '{}'. Must be s, m, or l",
-            config.encoder_size
-        )));
-    }
+    validate_extract_config(&config)?;
+    validate_output_config(&config)?;
 
     let task = SpatialTask {
         id,
         file_path,
         config,
+        cancel: CancellationToken::new(),
+        batch_id: None,
+        restored: false,
     };
 
     manager
@@ -182,6 +244,226 @@ pub async fn queue_spatial(
     Ok(())
 }
 
+/// Photos and videos in `dir` for a spatial batch, sorted by path.
+/// `extensions` narrows the file types picked up; each must be one spatial
+/// conversion supports.
+pub(crate) fn collect_batch_inputs(
+    dir: &Path,
+    recursive: bool,
+    extensions: Option<&[String]>,
+) -> Result<Vec<PathBuf>, SpatialError> {
+    let supported: Vec<&str> = IMAGE_INPUT_EXTENSIONS
+        .iter()
+        .chain(VIDEO_EXTENSIONS)
+        .copied()
+        .collect();
+    let included: Vec<String> = match extensions {
+        Some(extensions) => extensions
+            .iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .collect(),
+        None => supported.iter().map(|ext| ext.to_string()).collect(),
+    };
+    if included.is_empty() {
+        return Err(SpatialError::InvalidInput(
+            "No file types selected".to_string(),
+        ));
+    }
+    if let Some(unsupported) = included
+        .iter()
+        .find(|ext| !supported.contains(&ext.as_str()))
+    {
+        return Err(SpatialError::InvalidInput(format!(
+            "Unsupported file type for spatial conversion: .{}",
+            unsupported
+        )));
+    }
+
+    Ok(collect_files(dir, recursive, |path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| included.contains(&ext.to_lowercase()))
+    })?)
+}
+
+/// Output names for a batch's files, so no task overwrites another's files.
+/// Files that would share a name in the same folder (`a.jpg` and `a.png`,
+/// or `x/a.mp4` and `y/a.mp4` written to one output folder) get their
+/// extension added (`{stem}_jpg_spatial`), and a number if that is taken
+/// too. `None` keeps the configured name.
+pub(crate) fn batch_output_names(files: &[PathBuf], config: &SpatialConfig) -> Vec<Option<String>> {
+    // Every file a task writes is `{name}{suffix}.{ext}`, so equal names clash
+    let key = |path: &Path, config: &SpatialConfig| {
+        output_file(path, config, "", "")
+            .to_string_lossy()
+            .to_lowercase()
+    };
+    let mut shared: HashMap<String, usize> = HashMap::new();
+    for file in files {
+        *shared.entry(key(file, config)).or_default() += 1;
+    }
+
+    let template = config
+        .output_name
+        .as_deref()
+        .and_then(sanitize_output_name)
+        .unwrap_or_else(|| format!("{}_spatial", OUTPUT_STEM));
+    let mut taken = HashSet::new();
+    files
+        .iter()
+        .map(|file| {
+            let own = key(file, config);
+            if shared[&own] == 1 && taken.insert(own) {
+                return None;
+            }
+            let extension = file
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            let base = template.replace(OUTPUT_STEM, &format!("{}_{}", OUTPUT_STEM, extension));
+            let mut name = base.clone();
+            let mut n = 2;
+            loop {
+                let renamed = SpatialConfig {
+                    output_name: Some(name.clone()),
+                    ..config.clone()
+                };
+                if taken.insert(key(file, &renamed)) {
+                    return Some(name);
+                }
+                name = format!("{}_{}", base, n);
+                n += 1;
+            }
+        })
+        .collect()
+}
+
+/// Queues every photo and video in `folder` as its own task, with ids
+/// `{batch_id}-{n}` in path order. Files whose outputs would overwrite each
+/// other get their own names (see `batch_output_names`). Each task's
+/// `spatial-started` event carries the batch id so the frontend can total
+/// progress per folder.
+#[command]
+pub async fn queue_spatial_batch(
+    manager: tauri::State<'_, SpatialManager>,
+    batch_id: String,
+    folder: String,
+    config: SpatialConfig,
+    extensions: Option<Vec<String>>,
+    recursive: Option<bool>,
+) -> Result<SpatialBatch, SpatialError> {
+    let dir = Path::new(&folder);
+    if !dir.is_dir() {
+        return Err(SpatialError::InvalidInput(format!(
+            "Not a folder: {}",
+            folder
+        )));
+    }
+    validate_extract_config(&config)?;
+    validate_output_config(&config)?;
+    if let Some(name) = config.output_name.as_deref()
+        && !name.trim().is_empty()
+        && !name.contains(OUTPUT_STEM)
+    {
+        return Err(SpatialError::InvalidInput(format!(
+            "Include {} in the output name so each file in the folder gets its own",
+            OUTPUT_STEM
+        )));
+    }
+
+    let files = collect_batch_inputs(dir, recursive.unwrap_or(false), extensions.as_deref())?;
+    if files.is_empty() {
+        return Err(SpatialError::InvalidInput(format!(
+            "No photos or videos to convert in {}",
+            folder
+        )));
+    }
+
+    let names = batch_output_names(&files, &config);
+    let mut tasks = Vec::with_capacity(files.len());
+    for (index, (path, name)) in files.into_iter().zip(names).enumerate() {
+        let mut config = config.clone();
+        if name.is_some() {
+            config.output_name = name;
+        }
+        let task = SpatialTask {
+            id: format!("{}-{}", batch_id, index + 1),
+            file_path: path.to_string_lossy().into_owned(),
+            config,
+            cancel: CancellationToken::new(),
+            batch_id: Some(batch_id.clone()),
+            restored: false,
+        };
+        tasks.push(SpatialBatchTask {
+            id: task.id.clone(),
+            file_path: task.file_path.clone(),
+        });
+        manager
+            .sender
+            .send(SpatialMessage::Enqueue(task))
+            .await
+            .map_err(|e| SpatialError::Channel(e.to_string()))?;
+    }
+
+    Ok(SpatialBatch { batch_id, tasks })
+}
+
+/// Lists the tasks left unfinished when the app last quit, dropping any
+/// whose input is gone, so the frontend can add their rows before
+/// `restore_spatial_queue` queues them.
+#[command]
+pub async fn saved_spatial_queue(
+    saved: tauri::State<'_, SavedQueue>,
+) -> Result<Vec<RestoredSpatialTask>, SpatialError> {
+    let mut restored = Vec::new();
+    for task in saved.unrestored() {
+        if !Path::new(&task.file_path).exists() {
+            saved.remove(&task.id);
+            continue;
+        }
+        restored.push(RestoredSpatialTask {
+            id: task.id,
+            file_path: task.file_path,
+            batch_id: task.batch_id,
+        });
+    }
+    Ok(restored)
+}
+
+/// Queues again the tasks `saved_spatial_queue` listed. Only the first call
+/// after launch finds any.
+#[command]
+pub async fn restore_spatial_queue(
+    manager: tauri::State<'_, SpatialManager>,
+    saved: tauri::State<'_, SavedQueue>,
+) -> Result<(), SpatialError> {
+    for task in saved.take_unrestored() {
+        manager
+            .sender
+            .send(SpatialMessage::Enqueue(task))
+            .await
+            .map_err(|e| SpatialError::Channel(e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn pause_spatial(
+    manager: tauri::State<'_, SpatialManager>,
+    id: String,
+) -> Result<(), SpatialError> {
+    manager.pause_task(&id)
+}
+
+#[command]
+pub async fn resume_spatial(
+    manager: tauri::State<'_, SpatialManager>,
+    id: String,
+) -> Result<(), SpatialError> {
+    manager.resume_task(&id)
+}
+
 #[command]
 pub async fn cancel_spatial(
     manager: tauri::State<'_, SpatialManager>,
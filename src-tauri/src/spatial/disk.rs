@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use crate::spatial::error::SpatialError;
+
+/// Headroom kept free on top of a job's own estimate.
+const SPACE_MARGIN: u64 = 256 * 1024 * 1024;
+
+/// Free space available to this user on the volume holding `path`, or on
+/// its nearest existing parent. `None` when it can't be read.
+pub(crate) fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|dir| dir.exists())?;
+    query_available_space(existing)
+}
+
+#[cfg(unix)]
+fn query_available_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // Field widths differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+#[cfg(windows)]
+fn query_available_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    use windows::core::PCWSTR;
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut available = 0u64;
+    let free = Some(&raw mut available);
+    unsafe { GetDiskFreeSpaceExW(PCWSTR(wide.as_ptr()), free, None, None) }.ok()?;
+    Some(available)
+}
+
+/// Fails with `InsufficientSpace` when the volume holding `path` can't fit
+/// `needed` more bytes. Passes when free space can't be read, leaving the
+/// write itself to report the problem.
+pub(crate) fn ensure_space(path: &Path, needed: u64) -> Result<(), SpatialError> {
+    match available_space(path) {
+        Some(available) => check_space(needed, available),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn check_space(needed: u64, available: u64) -> Result<(), SpatialError> {
+    let needed = needed.saturating_add(SPACE_MARGIN);
+    if available < needed {
+        return Err(SpatialError::InsufficientSpace { needed, available });
+    }
+    Ok(())
+}
+
+/// Peak disk use of a video job over an `input_bytes` source. The
+/// double-width stereo render sits next to either the near-lossless extract
+/// or the MV-HEVC file, never both.
+pub(crate) fn video_job_bytes(input_bytes: u64, extracts: bool, mvhevc: bool) -> u64 {
+    let stereo = input_bytes.saturating_mul(2);
+    let extract = if extracts {
+        input_bytes.saturating_mul(4)
+    } else {
+        0
+    };
+    let mvhevc = if mvhevc {
+        input_bytes.saturating_mul(2)
+    } else {
+        0
+    };
+    stereo.saturating_add(extract.max(mvhevc))
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const MB: f64 = 1024.0 * 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes / GB)
+    } else {
+        format!("{:.0} MB", bytes / MB)
+    }
+}
@@ -1,6 +1,9 @@
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::spatial::disk::format_bytes;
+use crate::watchdog::{WorkerFault, format_limit};
+
 #[derive(Debug, Error)]
 pub enum SpatialError {
     #[error("Shell command failed: {0}")]
@@ -13,10 +16,33 @@ pub enum SpatialError {
     Channel(String),
     #[error("Worker process error: {0}")]
     Worker(String),
+    #[error("{0}")]
+    Pipeline(#[from] spatial_maker::SpatialError),
     #[error("Invalid input: {0}")]
     InvalidInput(String),
     #[error("Task not found: {0}")]
     TaskNotFound(String),
+    #[error("{0}")]
+    Fault(WorkerFault),
+    #[error("Timed out: stopped at the {} time limit", format_limit(*.0))]
+    TimedOut(u64),
+    #[error(
+        "Not enough disk space: needs {} but only {} is free",
+        format_bytes(*needed),
+        format_bytes(*available)
+    )]
+    InsufficientSpace { needed: u64, available: u64 },
+    #[error("Window error: {0}")]
+    Window(#[from] tauri::Error),
+}
+
+impl From<WorkerFault> for SpatialError {
+    fn from(fault: WorkerFault) -> Self {
+        match fault {
+            WorkerFault::TimedOut { limit_secs } => SpatialError::TimedOut(limit_secs),
+            fault => SpatialError::Fault(fault),
+        }
+    }
 }
 
 impl Serialize for SpatialError {
@@ -0,0 +1,125 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::conversion::gpu::{is_apple_silicon, list_gpu_devices, query_system_memory};
+use crate::spatial::types::EncoderSize;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Memory assumed when it can't be read.
+const UNKNOWN_MEMORY: u64 = 8 * GIB;
+
+/// What this machine offers for depth estimation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpatialHardware {
+    pub memory_bytes: Option<u64>,
+    pub apple_silicon: bool,
+    /// The first GPU found, preferring discrete ones.
+    pub gpu: Option<String>,
+    pub cpu_threads: usize,
+    /// Whether the depth model runs on an accelerator (see
+    /// [`is_accelerated`]).
+    pub accelerated: bool,
+}
+
+impl SpatialHardware {
+    pub async fn detect(app: &AppHandle) -> Self {
+        let devices = list_gpu_devices(app).await;
+        let gpu = devices
+            .iter()
+            .find(|device| device.kind == "discrete")
+            .or(devices.first());
+        let apple_silicon = is_apple_silicon().await;
+        let accelerated = is_accelerated(
+            apple_silicon,
+            cfg!(target_os = "macos"),
+            gpu.map(|device| device.kind.as_str()),
+        );
+        Self {
+            memory_bytes: query_system_memory().await,
+            apple_silicon,
+            gpu: gpu.map(|device| device.name.clone()),
+            cpu_threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            accelerated,
+        }
+    }
+}
+
+/// Whether the depth model gets an accelerator. The app's ONNX Runtime uses
+/// CoreML on macOS and the CPU elsewhere, so only a Mac counts: Apple
+/// silicon, or an Intel Mac with a discrete GPU (its integrated graphics are
+/// no faster than the CPU).
+pub fn is_accelerated(apple_silicon: bool, macos: bool, gpu_kind: Option<&str>) -> bool {
+    apple_silicon || (macos && gpu_kind == Some("discrete"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncoderRecommendation {
+    pub encoder_size: EncoderSize,
+    /// Rough video frames per second with the suggested size.
+    pub expected_fps: f64,
+    pub reason: String,
+    pub hardware: SpatialHardware,
+}
+
+/// Picks the largest encoder `hardware` runs at a usable speed: the bigger
+/// models want an accelerator and the memory to hold them.
+pub fn recommend_encoder(hardware: SpatialHardware) -> EncoderRecommendation {
+    let memory = hardware.memory_bytes.unwrap_or(UNKNOWN_MEMORY);
+    let (encoder_size, reason) = if hardware.accelerated {
+        if memory >= 32 * GIB {
+            (
+                EncoderSize::Large,
+                "Accelerated, with memory to spare for the large model",
+            )
+        } else if memory >= 16 * GIB {
+            (
+                EncoderSize::Base,
+                "Accelerated, with memory for the medium model",
+            )
+        } else {
+            (
+                EncoderSize::Small,
+                "Accelerated, but memory is tight for the larger models",
+            )
+        }
+    } else if memory >= 16 * GIB && hardware.cpu_threads >= 16 {
+        (
+            EncoderSize::Base,
+            "Runs on the CPU, with enough cores for the medium model",
+        )
+    } else {
+        (
+            EncoderSize::Small,
+            "Runs on the CPU, where the small model keeps videos practical",
+        )
+    };
+
+    EncoderRecommendation {
+        encoder_size,
+        expected_fps: expected_fps(encoder_size, &hardware),
+        reason: reason.to_string(),
+        hardware,
+    }
+}
+
+/// Rough video frames per second at the default depth resolution. CPU
+/// speed scales with the thread count from an 8-thread baseline; real
+/// speed also depends on the input size.
+pub fn expected_fps(encoder_size: EncoderSize, hardware: &SpatialHardware) -> f64 {
+    let fps = match (encoder_size, hardware.accelerated) {
+        (EncoderSize::Small, true) => 10.0,
+        (EncoderSize::Base, true) => 4.0,
+        (EncoderSize::Large, true) => 1.5,
+        (EncoderSize::Small, false) => 2.0,
+        (EncoderSize::Base, false) => 0.7,
+        (EncoderSize::Large, false) => 0.2,
+    };
+    if hardware.accelerated {
+        fps
+    } else {
+        fps * hardware.cpu_threads.clamp(1, 32) as f64 / 8.0
+    }
+}
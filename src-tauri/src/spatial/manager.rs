@@ -1,11 +1,20 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Emitter};
+
+use spatial_maker::CancellationToken;
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::mpsc;
 
+use crate::alerts::QueueKind;
+use crate::governor::{ResourceClass, ResourceGovernor};
 use crate::spatial::error::SpatialError;
-use crate::spatial::types::{SpatialErrorPayload, SpatialLogPayload, SpatialTask};
+use crate::spatial::saved_queue::SavedQueue;
+use crate::spatial::types::{EncoderSize, SpatialErrorPayload, SpatialLogPayload, SpatialTask};
 use crate::spatial::worker::run_spatial_worker;
+use crate::watchdog::{
+    PauseClock, WORKER_DIAGNOSTIC_EVENT, WORKER_STALL_TIMEOUT, WorkerDiagnosticPayload, supervise,
+    time_limit,
+};
 
 pub enum SpatialMessage {
     Enqueue(SpatialTask),
@@ -14,10 +23,49 @@ pub enum SpatialMessage {
     TaskError(String, SpatialError),
 }
 
+/// The one spatial task allowed to run at a time (GPU-bound). Only the end
+/// of the task holding it frees it: a cancelled pipeline keeps the slot until
+/// its worker returns.
+#[derive(Debug, Default)]
+pub(crate) struct RunningSlot(Option<String>);
+
+impl RunningSlot {
+    pub(crate) fn id(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    pub(crate) fn is_free(&self) -> bool {
+        self.0.is_none()
+    }
+
+    pub(crate) fn occupy(&mut self, id: String) {
+        self.0 = Some(id);
+    }
+
+    /// Frees the slot if `id` holds it, returning whether it did, so an end
+    /// reported for any other task leaves the running one alone.
+    pub(crate) fn release(&mut self, id: &str) -> bool {
+        let held = self.id() == Some(id);
+        if held {
+            self.0 = None;
+        }
+        held
+    }
+}
+
 pub struct SpatialManager {
     pub(crate) sender: mpsc::Sender<SpatialMessage>,
+    app: AppHandle,
     active_tasks: Arc<Mutex<HashMap<String, u32>>>,
     cancelled_tasks: Arc<Mutex<HashSet<String>>>,
+    /// Stops the in-process pipeline of queued and running tasks; the pid in
+    /// `active_tasks` only covers the ffmpeg and `spatial` stages.
+    cancel_tokens: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// How long each running task has been paused, so the watchdog doesn't
+    /// count it.
+    pause_clocks: Arc<Mutex<HashMap<String, PauseClock>>>,
+    /// Model downloads in flight, one per encoder size.
+    model_downloads: Mutex<HashMap<EncoderSize, CancellationToken>>,
 }
 
 impl SpatialManager {
@@ -28,12 +76,16 @@ impl SpatialManager {
         let active_tasks_loop = Arc::clone(&active_tasks);
         let cancelled_tasks = Arc::new(Mutex::new(HashSet::new()));
         let cancelled_tasks_loop = Arc::clone(&cancelled_tasks);
+        let cancel_tokens = Arc::new(Mutex::new(HashMap::new()));
+        let cancel_tokens_loop = Arc::clone(&cancel_tokens);
+        let pause_clocks = Arc::new(Mutex::new(HashMap::new()));
+        let pause_clocks_loop = Arc::clone(&pause_clocks);
+        let app_handle = app.clone();
 
         tauri::async_runtime::spawn(async move {
             let mut queue: VecDeque<SpatialTask> = VecDeque::new();
             let mut queued_ids: HashSet<String> = HashSet::new();
-            // Only run one spatial task at a time (GPU-bound)
-            let mut running: Option<String> = None;
+            let mut running = RunningSlot::default();
 
             while let Some(msg) = rx.recv().await {
                 match msg {
@@ -43,11 +95,16 @@ impl SpatialManager {
                             cancelled.remove(&task.id);
                         }
 
-                        if running.as_ref() == Some(&task.id) || queued_ids.contains(&task.id) {
+                        if running.id() == Some(task.id.as_str()) || queued_ids.contains(&task.id) {
                             continue;
                         }
 
+                        app.state::<SavedQueue>().add(&task);
                         queued_ids.insert(task.id.clone());
+                        cancel_tokens_loop
+                            .lock()
+                            .unwrap()
+                            .insert(task.id.clone(), task.cancel.clone());
                         queue.push_back(task);
                         Self::process_queue(
                             &app,
@@ -56,6 +113,7 @@ impl SpatialManager {
                             &mut queued_ids,
                             &mut running,
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
@@ -65,32 +123,33 @@ impl SpatialManager {
                             cancelled.contains(&id)
                         };
 
+                        // The pipeline keeps running until it reaches its
+                        // cancellation check; its worker's end frees the slot
                         if is_cancelled {
                             if pid > 0 {
                                 let _ = Self::terminate_process(pid);
                             }
-                            running = None;
-                            {
-                                let mut tasks = active_tasks_loop.lock().unwrap();
-                                tasks.remove(&id);
-                            }
-                            Self::process_queue(
-                                &app,
-                                &tx_clone,
-                                &mut queue,
-                                &mut queued_ids,
-                                &mut running,
-                                Arc::clone(&cancelled_tasks_loop),
-                            )
-                            .await;
+                            active_tasks_loop.lock().unwrap().remove(&id);
                             continue;
                         }
 
                         let mut tasks = active_tasks_loop.lock().unwrap();
+                        // A pause that came between stages holds the new one too
+                        let paused = cancel_tokens_loop
+                            .lock()
+                            .unwrap()
+                            .get(&id)
+                            .is_some_and(CancellationToken::is_paused);
+                        if paused && pid > 0 {
+                            let _ = Self::suspend_process(pid, true);
+                        }
                         tasks.insert(id, pid);
                     }
                     SpatialMessage::TaskCompleted(id) => {
-                        running = None;
+                        if !running.release(&id) {
+                            continue;
+                        }
+                        app.state::<SavedQueue>().remove(&id);
                         {
                             let mut cancelled = cancelled_tasks_loop.lock().unwrap();
                             cancelled.remove(&id);
@@ -99,6 +158,7 @@ impl SpatialManager {
                             let mut tasks = active_tasks_loop.lock().unwrap();
                             tasks.remove(&id);
                         }
+                        cancel_tokens_loop.lock().unwrap().remove(&id);
 
                         Self::process_queue(
                             &app,
@@ -107,11 +167,29 @@ impl SpatialManager {
                             &mut queued_ids,
                             &mut running,
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
                     SpatialMessage::TaskError(id, err) => {
+                        if !running.release(&id) {
+                            continue;
+                        }
                         eprintln!("Spatial task {} failed: {}", id, err);
+                        app.state::<SavedQueue>().remove(&id);
+
+                        // A crashed, stuck or timed-out worker leaves its pipeline
+                        // thread or child process behind
+                        let token = cancel_tokens_loop.lock().unwrap().remove(&id);
+                        if matches!(err, SpatialError::Fault(_) | SpatialError::TimedOut(_)) {
+                            if let Some(token) = token {
+                                token.cancel();
+                            }
+                            let pid = active_tasks_loop.lock().unwrap().get(&id).copied();
+                            if let Some(pid) = pid.filter(|&pid| pid > 0) {
+                                let _ = Self::terminate_process(pid);
+                            }
+                        }
 
                         let _ = app.emit(
                             "spatial-log",
@@ -129,7 +207,6 @@ impl SpatialManager {
                             },
                         );
 
-                        running = None;
                         {
                             let mut cancelled = cancelled_tasks_loop.lock().unwrap();
                             cancelled.remove(&id);
@@ -146,6 +223,7 @@ impl SpatialManager {
                             &mut queued_ids,
                             &mut running,
                             Arc::clone(&cancelled_tasks_loop),
+                            Arc::clone(&pause_clocks_loop),
                         )
                         .await;
                     }
@@ -155,8 +233,12 @@ impl SpatialManager {
 
         Self {
             sender: tx,
+            app: app_handle,
             active_tasks,
             cancelled_tasks,
+            cancel_tokens,
+            pause_clocks,
+            model_downloads: Mutex::new(HashMap::new()),
         }
     }
 
@@ -165,10 +247,11 @@ impl SpatialManager {
         tx: &mpsc::Sender<SpatialMessage>,
         queue: &mut VecDeque<SpatialTask>,
         queued_ids: &mut HashSet<String>,
-        running: &mut Option<String>,
+        running: &mut RunningSlot,
         cancelled_tasks: Arc<Mutex<HashSet<String>>>,
+        pause_clocks: Arc<Mutex<HashMap<String, PauseClock>>>,
     ) {
-        if running.is_some() {
+        if !running.is_free() {
             return;
         }
 
@@ -179,17 +262,67 @@ impl SpatialManager {
                 cancelled.remove(&task.id)
             };
             if is_cancelled {
+                app.state::<SavedQueue>().remove(&task.id);
                 continue;
             }
 
-            *running = Some(task.id.clone());
+            running.occupy(task.id.clone());
 
             let app_clone = app.clone();
             let tx_worker = tx.clone();
             let task_clone = task.clone();
+            let cancelled_worker = Arc::clone(&cancelled_tasks);
+            let pause = PauseClock::default();
+            pause_clocks
+                .lock()
+                .unwrap()
+                .insert(task.id.clone(), pause.clone());
+            let pause_clocks_worker = Arc::clone(&pause_clocks);
 
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = run_spatial_worker(app_clone, tx_worker.clone(), task_clone.clone()).await {
+                let governor = app_clone.state::<ResourceGovernor>();
+                let _permit = match governor.try_acquire(ResourceClass::Heavy) {
+                    Some(permit) => permit,
+                    None => {
+                        let _ = app_clone.emit(
+                            "spatial-log",
+                            SpatialLogPayload {
+                                id: task_clone.id.clone(),
+                                line: "[QUEUE] Waiting for running conversions to free up".to_string(),
+                            },
+                        );
+                        governor.acquire(ResourceClass::Heavy).await
+                    }
+                };
+
+                if cancelled_worker.lock().unwrap().contains(&task_clone.id) {
+                    pause_clocks_worker.lock().unwrap().remove(&task_clone.id);
+                    let _ = tx_worker
+                        .send(SpatialMessage::TaskCompleted(task_clone.id))
+                        .await;
+                    return;
+                }
+
+                let worker_tx = tx_worker.clone();
+                let worker_task = task_clone.clone();
+                let result = supervise(
+                    app_clone.clone(),
+                    pause,
+                    WORKER_STALL_TIMEOUT,
+                    time_limit(task_clone.config.max_duration_secs),
+                    |events| run_spatial_worker(events, worker_tx, worker_task),
+                )
+                .await
+                .unwrap_or_else(|fault| {
+                    let _ = app_clone.emit(
+                        WORKER_DIAGNOSTIC_EVENT,
+                        WorkerDiagnosticPayload::new(&task_clone.id, QueueKind::Spatial, &fault),
+                    );
+                    Err(fault.into())
+                });
+                pause_clocks_worker.lock().unwrap().remove(&task_clone.id);
+
+                if let Err(e) = result {
                     let _ = tx_worker
                         .send(SpatialMessage::TaskError(task_clone.id, e))
                         .await;
@@ -204,11 +337,93 @@ impl SpatialManager {
         }
     }
 
+    /// Registers a download of `encoder_size`'s model, returning the token
+    /// that stops it. Only one download per size runs at a time.
+    pub(crate) fn start_model_download(
+        &self,
+        encoder_size: EncoderSize,
+    ) -> Result<CancellationToken, SpatialError> {
+        let mut downloads = self.model_downloads.lock().unwrap();
+        if downloads.contains_key(&encoder_size) {
+            return Err(SpatialError::InvalidInput(format!(
+                "The {} model is already downloading",
+                encoder_size.as_str()
+            )));
+        }
+        let token = CancellationToken::new();
+        downloads.insert(encoder_size, token.clone());
+        Ok(token)
+    }
+
+    pub(crate) fn is_downloading(&self, encoder_size: EncoderSize) -> bool {
+        self.model_downloads
+            .lock()
+            .unwrap()
+            .contains_key(&encoder_size)
+    }
+
+    pub(crate) fn finish_model_download(&self, encoder_size: EncoderSize) {
+        self.model_downloads.lock().unwrap().remove(&encoder_size);
+    }
+
+    pub fn cancel_model_download(&self, encoder_size: EncoderSize) -> Result<(), SpatialError> {
+        match self.model_downloads.lock().unwrap().get(&encoder_size) {
+            Some(token) => {
+                token.cancel();
+                Ok(())
+            }
+            None => Err(SpatialError::TaskNotFound(format!(
+                "{} model download",
+                encoder_size.as_str()
+            ))),
+        }
+    }
+
+    /// Holds a running task: the in-process pipeline stops at its next
+    /// checkpoint, and an ffmpeg or `spatial` stage is suspended.
+    pub fn pause_task(&self, id: &str) -> Result<(), SpatialError> {
+        self.set_paused(id, true)
+    }
+
+    pub fn resume_task(&self, id: &str) -> Result<(), SpatialError> {
+        self.set_paused(id, false)
+    }
+
+    fn set_paused(&self, id: &str, paused: bool) -> Result<(), SpatialError> {
+        let tasks = self.active_tasks.lock().unwrap();
+        let Some(&pid) = tasks.get(id) else {
+            return Err(SpatialError::TaskNotFound(id.to_string()));
+        };
+
+        if let Some(token) = self.cancel_tokens.lock().unwrap().get(id) {
+            if paused {
+                token.pause();
+            } else {
+                token.resume();
+            }
+        }
+        if let Some(clock) = self.pause_clocks.lock().unwrap().get(id) {
+            if paused {
+                clock.pause();
+            } else {
+                clock.resume();
+            }
+        }
+        if pid > 0 {
+            Self::suspend_process(pid, paused)?;
+        }
+        Ok(())
+    }
+
     pub fn cancel_task(&self, id: &str) -> Result<(), SpatialError> {
         {
             let mut cancelled = self.cancelled_tasks.lock().unwrap();
             cancelled.insert(id.to_string());
         }
+        if let Some(token) = self.cancel_tokens.lock().unwrap().remove(id) {
+            token.cancel();
+        }
+        self.app.state::<SavedQueue>().remove(id);
 
         let tasks = self.active_tasks.lock().unwrap();
         if let Some(&pid) = tasks.get(id) {
@@ -219,6 +434,62 @@ impl SpatialManager {
         Ok(())
     }
 
+    #[cfg(unix)]
+    fn suspend_process(pid: u32, suspend: bool) -> Result<(), SpatialError> {
+        let (signal, name) = if suspend {
+            (libc::SIGSTOP, "SIGSTOP")
+        } else {
+            (libc::SIGCONT, "SIGCONT")
+        };
+        unsafe {
+            if libc::kill(pid as libc::pid_t, signal) != 0 {
+                return Err(SpatialError::Shell(format!("Failed to send {}", name)));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn suspend_process(pid: u32, suspend: bool) -> Result<(), SpatialError> {
+        use windows::Win32::Foundation::{CloseHandle, HANDLE};
+        use windows::Win32::System::LibraryLoader::{GetModuleHandleA, GetProcAddress};
+        use windows::Win32::System::Threading::{OpenProcess, PROCESS_SUSPEND_RESUME};
+        use windows::core::s;
+
+        unsafe {
+            let process_handle = OpenProcess(PROCESS_SUSPEND_RESUME, false, pid)
+                .map_err(|e| SpatialError::Shell(format!("Failed to open process: {}", e)))?;
+
+            let ntdll = GetModuleHandleA(s!("ntdll.dll")).map_err(|e| {
+                let _ = CloseHandle(process_handle);
+                SpatialError::Shell(format!("Failed to get ntdll handle: {}", e))
+            })?;
+            let fn_name = if suspend {
+                s!("NtSuspendProcess")
+            } else {
+                s!("NtResumeProcess")
+            };
+
+            let Some(func) = GetProcAddress(ntdll, fn_name) else {
+                let _ = CloseHandle(process_handle);
+                return Err(SpatialError::Shell(
+                    "Could not find NtSuspendProcess/NtResumeProcess in ntdll".to_string(),
+                ));
+            };
+            let func: extern "system" fn(HANDLE) -> i32 = std::mem::transmute(func);
+            let status = func(process_handle);
+            let _ = CloseHandle(process_handle);
+
+            if status != 0 {
+                return Err(SpatialError::Shell(format!(
+                    "NtSuspendProcess/NtResumeProcess failed with status: {}",
+                    status
+                )));
+            }
+        }
+        Ok(())
+    }
+
     #[cfg(unix)]
     fn terminate_process(pid: u32) -> Result<(), SpatialError> {
         unsafe {
@@ -232,6 +503,7 @@ impl SpatialManager {
 
     #[cfg(windows)]
     fn terminate_process(pid: u32) -> Result<(), SpatialError> {
+        let _ = Self::suspend_process(pid, false);
         unsafe {
             let process_handle = windows::Win32::System::Threading::OpenProcess(
                 windows::Win32::System::Threading::PROCESS_TERMINATE,
@@ -1,6 +1,11 @@
+pub mod bootstrap;
 pub mod commands;
+pub(crate) mod disk;
 pub(crate) mod error;
+pub(crate) mod hardware;
 pub(crate) mod manager;
+pub mod preview;
+pub(crate) mod saved_queue;
 pub(crate) mod types;
 pub(crate) mod worker;
 
@@ -0,0 +1,254 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::window::Color;
+use tauri::{
+    AppHandle, Emitter, Manager, Monitor, PhysicalPosition, PhysicalSize, Runtime, WebviewUrl,
+    WebviewWindow, WebviewWindowBuilder, WindowEvent, command,
+};
+
+use crate::spatial::error::SpatialError;
+
+pub const PREVIEW_WINDOW: &str = "stereo-preview";
+pub const PREVIEW_CHANGED_EVENT: &str = "stereo-preview-changed";
+
+/// Where a frame keeps its two eyes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StereoLayout {
+    /// Left eye in the left half, right eye in the right half.
+    #[default]
+    SideBySide,
+    /// Left eye in the top half, right eye in the bottom half.
+    TopAndBottom,
+    /// A single view, such as the first view of an MV-HEVC file.
+    Mono,
+}
+
+/// A connected display, numbered in the order the OS lists them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewState {
+    pub open: bool,
+    pub path: Option<String>,
+    /// How the previewed file stores its eyes.
+    pub source_layout: StereoLayout,
+    /// How the display expects them. A 3D TV in SBS or TB mode stretches
+    /// each half of the screen back to full size, so each eye fills half.
+    pub layout: StereoLayout,
+    pub display: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct StereoPreview {
+    state: Mutex<PreviewState>,
+}
+
+impl StereoPreview {
+    pub fn state(&self) -> PreviewState {
+        self.state.lock().unwrap().clone()
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut PreviewState)) -> PreviewState {
+        let mut state = self.state.lock().unwrap();
+        apply(&mut state);
+        state.clone()
+    }
+}
+
+/// The display to preview on when none is chosen: the first secondary
+/// display, as the primary one is showing the app.
+pub fn default_display(displays: &[DisplayInfo]) -> Option<usize> {
+    displays
+        .iter()
+        .find(|display| !display.primary)
+        .or_else(|| displays.first())
+        .map(|display| display.index)
+}
+
+pub fn resolve_display(
+    displays: &[DisplayInfo],
+    requested: Option<usize>,
+) -> Result<&DisplayInfo, SpatialError> {
+    let index = requested
+        .or_else(|| default_display(displays))
+        .ok_or_else(|| SpatialError::InvalidInput("No displays found".to_string()))?;
+    displays
+        .iter()
+        .find(|display| display.index == index)
+        .ok_or_else(|| {
+            SpatialError::InvalidInput(format!(
+                "Display {} not found; {} connected",
+                index,
+                displays.len()
+            ))
+        })
+}
+
+fn display_info(index: usize, monitor: &Monitor, primary: Option<&Monitor>) -> DisplayInfo {
+    let position = monitor.position();
+    DisplayInfo {
+        index,
+        name: monitor.name().cloned(),
+        width: monitor.size().width,
+        height: monitor.size().height,
+        x: position.x,
+        y: position.y,
+        scale_factor: monitor.scale_factor(),
+        primary: primary.is_some_and(|primary| {
+            primary.position() == position && primary.name() == monitor.name()
+        }),
+    }
+}
+
+fn displays<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<DisplayInfo>, SpatialError> {
+    let primary = app.primary_monitor()?;
+    Ok(app
+        .available_monitors()?
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| display_info(index, monitor, primary.as_ref()))
+        .collect())
+}
+
+fn preview_window<R: Runtime>(app: &AppHandle<R>) -> Result<WebviewWindow<R>, SpatialError> {
+    if let Some(window) = app.get_webview_window(PREVIEW_WINDOW) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(app, PREVIEW_WINDOW, WebviewUrl::App("preview".into()))
+        .title("Stereo Preview")
+        .decorations(false)
+        .background_color(Color(0, 0, 0, 255))
+        .visible(false)
+        .build()?;
+
+    let handle = app.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            let state = handle
+                .state::<StereoPreview>()
+                .update(|state| state.open = false);
+            let _ = handle.emit(PREVIEW_CHANGED_EVENT, state);
+        }
+    });
+    Ok(window)
+}
+
+/// Fullscreen follows the display the window is on, so leave it, move the
+/// window over, and enter it again there.
+fn place_on_display<R: Runtime>(
+    window: &WebviewWindow<R>,
+    display: &DisplayInfo,
+) -> Result<(), SpatialError> {
+    window.set_fullscreen(false)?;
+    window.set_position(PhysicalPosition::new(display.x, display.y))?;
+    window.set_size(PhysicalSize::new(display.width, display.height))?;
+    window.set_fullscreen(true)?;
+    window.show()?;
+    window.set_focus()?;
+    Ok(())
+}
+
+fn publish<R: Runtime>(app: &AppHandle<R>, state: PreviewState) -> PreviewState {
+    let _ = app.emit(PREVIEW_CHANGED_EVENT, state.clone());
+    state
+}
+
+#[command]
+pub fn list_displays(app: AppHandle) -> Result<Vec<DisplayInfo>, SpatialError> {
+    displays(&app)
+}
+
+#[command]
+pub fn get_stereo_preview(preview: tauri::State<'_, StereoPreview>) -> PreviewState {
+    preview.state()
+}
+
+/// Shows `path` fullscreen on `display`, or on a secondary display if none
+/// is given. Async because creating a window from a synchronous command
+/// deadlocks on Windows.
+#[command]
+pub async fn open_stereo_preview(
+    app: AppHandle,
+    preview: tauri::State<'_, StereoPreview>,
+    path: String,
+    source_layout: StereoLayout,
+    layout: StereoLayout,
+    display: Option<usize>,
+) -> Result<PreviewState, SpatialError> {
+    if !Path::new(&path).is_file() {
+        return Err(SpatialError::InvalidInput(format!(
+            "File not found: {}",
+            path
+        )));
+    }
+
+    let displays = displays(&app)?;
+    let target = resolve_display(&displays, display)?;
+    let window = preview_window(&app)?;
+    place_on_display(&window, target)?;
+
+    let state = preview.update(|state| {
+        *state = PreviewState {
+            open: true,
+            path: Some(path),
+            source_layout,
+            layout,
+            display: Some(target.index),
+        }
+    });
+    Ok(publish(&app, state))
+}
+
+#[command]
+pub async fn set_stereo_preview_display(
+    app: AppHandle,
+    preview: tauri::State<'_, StereoPreview>,
+    display: usize,
+) -> Result<PreviewState, SpatialError> {
+    let displays = displays(&app)?;
+    let target = resolve_display(&displays, Some(display))?;
+    if let Some(window) = app.get_webview_window(PREVIEW_WINDOW) {
+        place_on_display(&window, target)?;
+    }
+
+    let state = preview.update(|state| state.display = Some(target.index));
+    Ok(publish(&app, state))
+}
+
+#[command]
+pub fn set_stereo_preview_layout(
+    app: AppHandle,
+    preview: tauri::State<'_, StereoPreview>,
+    source_layout: StereoLayout,
+    layout: StereoLayout,
+) -> PreviewState {
+    let state = preview.update(|state| {
+        state.source_layout = source_layout;
+        state.layout = layout;
+    });
+    publish(&app, state)
+}
+
+#[command]
+pub fn close_stereo_preview(app: AppHandle) -> Result<(), SpatialError> {
+    if let Some(window) = app.get_webview_window(PREVIEW_WINDOW) {
+        window.destroy()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::spatial::types::SpatialTask;
+
+/// File in the app data directory holding the spatial tasks not finished yet
+pub const QUEUE_FILE: &str = "spatial-queue.json";
+
+/// Spatial tasks that are queued or running, saved so quitting the app does
+/// not lose them. Managed as Tauri state.
+#[derive(Default)]
+pub struct SavedQueue {
+    path: Option<PathBuf>,
+    tasks: Mutex<Vec<SpatialTask>>,
+    /// Tasks found in the file at launch, until they are queued again
+    unrestored: Mutex<Vec<SpatialTask>>,
+}
+
+impl SavedQueue {
+    /// Queue backed by `path`, holding the tasks left in it by the last run.
+    /// A file that does not parse is treated as empty.
+    pub fn load(path: PathBuf) -> Self {
+        let tasks: Vec<SpatialTask> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            tasks: Mutex::new(tasks.clone()),
+            unrestored: Mutex::new(tasks),
+        }
+    }
+
+    /// Saves `task`, replacing an earlier copy with the same id in place.
+    pub fn add(&self, task: &SpatialTask) {
+        let mut tasks = self.tasks.lock().unwrap();
+        match tasks.iter_mut().find(|saved| saved.id == task.id) {
+            Some(saved) => *saved = task.clone(),
+            None => tasks.push(task.clone()),
+        }
+        self.save(&tasks);
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.unrestored.lock().unwrap().retain(|task| task.id != id);
+        let mut tasks = self.tasks.lock().unwrap();
+        let before = tasks.len();
+        tasks.retain(|task| task.id != id);
+        if tasks.len() != before {
+            self.save(&tasks);
+        }
+    }
+
+    /// The tasks left over from the last run that are not queued yet.
+    pub fn unrestored(&self) -> Vec<SpatialTask> {
+        self.unrestored.lock().unwrap().clone()
+    }
+
+    /// The tasks left over from the last run, marked restored. Empty after
+    /// the first call, so they are only queued again once per launch.
+    pub fn take_unrestored(&self) -> Vec<SpatialTask> {
+        std::mem::take(&mut *self.unrestored.lock().unwrap())
+            .into_iter()
+            .map(|task| SpatialTask {
+                restored: true,
+                ..task
+            })
+            .collect()
+    }
+
+    fn save(&self, tasks: &[SpatialTask]) {
+        if let Err(e) = self.write(tasks) {
+            eprintln!("Failed to save the spatial queue: {}", e);
+        }
+    }
+
+    fn write(&self, tasks: &[SpatialTask]) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string(tasks)?)?;
+        std::fs::rename(temp, path)
+    }
+}
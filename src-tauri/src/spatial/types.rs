@@ -1,20 +1,190 @@
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
+use spatial_maker::model_lock::LockStatus;
+use spatial_maker::video::{VideoInfo, is_video_path};
+
+/// Depth Anything V2 encoder size.
+///
+/// Serialized as the `spatial-maker` CLI's `--encoder` values ("s", "m", "l");
+/// the library's "b" spelling and the full names are accepted on input, so an
+/// unknown size fails when the command arguments are deserialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EncoderSize {
+    #[serde(rename = "s", alias = "small")]
+    Small,
+    #[serde(rename = "m", alias = "b", alias = "base", alias = "medium")]
+    Base,
+    #[serde(rename = "l", alias = "large")]
+    Large,
+}
+
+impl EncoderSize {
+    pub const ALL: [EncoderSize; 3] = [EncoderSize::Small, EncoderSize::Base, EncoderSize::Large];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EncoderSize::Small => "s",
+            EncoderSize::Base => "m",
+            EncoderSize::Large => "l",
+        }
+    }
+
+    /// The same size in the spatial-maker crate.
+    pub fn model_size(self) -> spatial_maker::EncoderSize {
+        match self {
+            EncoderSize::Small => spatial_maker::EncoderSize::Small,
+            EncoderSize::Base => spatial_maker::EncoderSize::Base,
+            EncoderSize::Large => spatial_maker::EncoderSize::Large,
+        }
+    }
+}
+
+/// How the two eyes are packed into the rendered stereo file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpatialLayout {
+    #[default]
+    SideBySide,
+    TopAndBottom,
+}
+
+impl SpatialLayout {
+    /// File name suffix for the stereo render.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            SpatialLayout::SideBySide => "sbs",
+            SpatialLayout::TopAndBottom => "tb",
+        }
+    }
+
+    /// The `spatial make --format` value for this layout.
+    pub fn cli_format(self) -> &'static str {
+        match self {
+            SpatialLayout::SideBySide => "sbs",
+            SpatialLayout::TopAndBottom => "hou",
+        }
+    }
+
+    pub fn output_format(self) -> spatial_maker::OutputFormat {
+        match self {
+            SpatialLayout::SideBySide => spatial_maker::OutputFormat::SideBySide,
+            SpatialLayout::TopAndBottom => spatial_maker::OutputFormat::TopAndBottom,
+        }
+    }
+}
+
+/// Encoding of the stereo image rendered for photo inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpatialImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SpatialConfig {
-    pub encoder_size: String,
+    pub encoder_size: EncoderSize,
     pub max_disparity: u32,
     pub skip_downscale: bool,
     pub duration: Option<f64>,
     #[serde(default)]
     pub enabled: bool,
+    /// Stop the task once it has run this many seconds, not counting time
+    /// paused. `None` or 0 means no limit.
+    #[serde(default)]
+    pub max_duration_secs: Option<u64>,
+    /// Trim points in the conversion trim format ("90", "1:30",
+    /// "00:01:30.5"), applied before depth estimation.
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+    /// Larger inputs are scaled down to fit before depth estimation. `None`
+    /// leaves that side unlimited.
+    #[serde(default)]
+    pub max_width: Option<u32>,
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    #[serde(default)]
+    pub layout: SpatialLayout,
+    #[serde(default)]
+    pub image_format: SpatialImageFormat,
+    /// JPEG quality (1-100) of photo renders.
+    #[serde(default = "default_image_quality")]
+    pub image_quality: u8,
+    /// Turn the stereo render into an Apple spatial (MV-HEVC) file. Videos
+    /// need the `spatial` CLI for this; photos also encode natively on macOS.
+    #[serde(default = "default_mvhevc")]
+    pub mvhevc: bool,
+    /// MV-HEVC quality (1-100). `None` keeps the encoder's default.
+    #[serde(default)]
+    pub mvhevc_quality: Option<u8>,
+    /// Keep the stereo render next to the spatial file.
+    #[serde(default)]
+    pub keep_stereo: bool,
+    /// Folder for the results. `None` writes them next to the input.
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    /// File name for the results, without extension; `{stem}` stands for
+    /// the input's name. `None` means `{stem}_spatial`.
+    #[serde(default)]
+    pub output_name: Option<String>,
+}
+
+/// What tests start from: the frontend's default spatial settings. Fixtures
+/// override the fields they exercise.
+#[cfg(test)]
+impl Default for SpatialConfig {
+    fn default() -> Self {
+        Self {
+            encoder_size: EncoderSize::Small,
+            max_disparity: 40,
+            skip_downscale: true,
+            duration: None,
+            enabled: false,
+            max_duration_secs: None,
+            start_time: None,
+            end_time: None,
+            max_width: None,
+            max_height: None,
+            layout: SpatialLayout::default(),
+            image_format: SpatialImageFormat::default(),
+            image_quality: default_image_quality(),
+            mvhevc: default_mvhevc(),
+            mvhevc_quality: None,
+            keep_stereo: false,
+            output_dir: None,
+            output_name: None,
+        }
+    }
+}
+
+fn default_image_quality() -> u8 {
+    95
+}
+
+fn default_mvhevc() -> bool {
+    true
+}
+
+impl SpatialConfig {
+    pub fn image_encoding(&self) -> spatial_maker::ImageEncoding {
+        match self.image_format {
+            SpatialImageFormat::Jpeg => spatial_maker::ImageEncoding::Jpeg {
+                quality: self.image_quality,
+            },
+            SpatialImageFormat::Png => spatial_maker::ImageEncoding::PNG,
+        }
+    }
 }
 
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelDownloadProgressPayload {
-    pub encoder_size: String,
+    pub encoder_size: EncoderSize,
     pub bytes_downloaded: u64,
     pub total_bytes: u64,
     pub progress: f64,
@@ -23,39 +193,201 @@ pub struct ModelDownloadProgressPayload {
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelDownloadCompletePayload {
-    pub encoder_size: String,
+    pub encoder_size: EncoderSize,
 }
 
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelDownloadErrorPayload {
-    pub encoder_size: String,
+    pub encoder_size: EncoderSize,
     pub error: String,
 }
 
-#[derive(Debug, Clone)]
+/// How a downloaded model compares to the size and SHA-256 spatial-maker
+/// recorded in `models.lock` when it downloaded the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelIntegrity {
+    Verified,
+    NotInstalled,
+    /// Downloaded, but nothing was recorded to check it against.
+    Unverified,
+    /// Truncated or otherwise different from the recorded file.
+    Corrupt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVerification {
+    pub encoder_size: EncoderSize,
+    pub integrity: ModelIntegrity,
+    /// File sizes when the model is corrupt.
+    pub expected_bytes: Option<u64>,
+    pub actual_bytes: Option<u64>,
+    /// A corrupt file was replaced with a fresh download.
+    pub redownloaded: bool,
+}
+
+impl ModelVerification {
+    pub fn new(encoder_size: EncoderSize, status: &LockStatus) -> Self {
+        let (integrity, expected_bytes, actual_bytes) = match status {
+            LockStatus::Verified => (ModelIntegrity::Verified, None, None),
+            LockStatus::NotInstalled => (ModelIntegrity::NotInstalled, None, None),
+            LockStatus::Unlocked => (ModelIntegrity::Unverified, None, None),
+            LockStatus::Changed {
+                expected_bytes,
+                actual_bytes,
+                ..
+            } => (
+                ModelIntegrity::Corrupt,
+                Some(*expected_bytes),
+                Some(*actual_bytes),
+            ),
+        };
+        Self {
+            encoder_size,
+            integrity,
+            expected_bytes,
+            actual_bytes,
+            redownloaded: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SpatialTask {
     pub id: String,
     pub file_path: String,
     pub config: SpatialConfig,
+    /// Stops the in-process pipeline when the task is cancelled.
+    #[serde(skip)]
+    pub cancel: spatial_maker::CancellationToken,
+    /// The folder batch this task was queued with, if any.
+    pub batch_id: Option<String>,
+    /// Queued again from the saved queue after a restart.
+    #[serde(skip)]
+    pub restored: bool,
+}
+
+impl SpatialTask {
+    pub fn started_payload(&self) -> SpatialStartedPayload {
+        SpatialStartedPayload {
+            id: self.id.clone(),
+            batch_id: self.batch_id.clone(),
+            restored: self.restored,
+        }
+    }
+}
+
+/// The tasks `queue_spatial_batch` queued for one folder.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpatialBatch {
+    pub batch_id: String,
+    pub tasks: Vec<SpatialBatchTask>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpatialBatchTask {
+    pub id: String,
+    pub file_path: String,
 }
 
 #[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SpatialStartedPayload {
     pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    pub restored: bool,
+}
+
+/// A task left unfinished at the last quit, listed by `saved_spatial_queue`
+/// before `restore_spatial_queue` queues it again.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredSpatialTask {
+    pub id: String,
+    pub file_path: String,
+    pub batch_id: Option<String>,
 }
 
 #[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SpatialProgressPayload {
     pub id: String,
     pub progress: f64,
     pub stage: String,
+    /// Frame counts and speed while a video's depth and stereo stage runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frames: Option<FrameStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameStats {
+    pub done: u64,
+    pub total: Option<u64>,
+    pub fps: Option<f64>,
+    /// Seconds until the last frame is rendered; encoding comes after.
+    pub eta_secs: Option<f64>,
+}
+
+impl FrameStats {
+    /// Stats for `done` of `total` frames, after `counted` frames took
+    /// `elapsed_secs`. Speed and ETA stay unknown until a frame is counted.
+    pub fn new(done: u64, total: Option<u64>, counted: u64, elapsed_secs: f64) -> Self {
+        let fps = (counted > 0 && elapsed_secs > 0.0).then(|| counted as f64 / elapsed_secs);
+        let eta_secs = fps
+            .zip(total)
+            .map(|(fps, total)| total.saturating_sub(done) as f64 / fps);
+        Self {
+            done,
+            total,
+            fps,
+            eta_secs,
+        }
+    }
 }
 
 #[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SpatialCompletedPayload {
     pub id: String,
     pub output_path: String,
+    /// Size of the output file; `None` when it can't be read
+    pub output_bytes: Option<u64>,
+    /// Frame size ffprobe reports, which is one eye for MV-HEVC files
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Length of a video output in seconds; `None` for photos
+    pub duration_secs: Option<f64>,
+    /// Time the task ran, not counting its wait in the queue
+    pub elapsed_secs: f64,
+}
+
+impl SpatialCompletedPayload {
+    pub fn new(
+        id: &str,
+        output: &Path,
+        output_bytes: Option<u64>,
+        probe: Option<&VideoInfo>,
+        elapsed_secs: f64,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            output_path: output.to_string_lossy().into_owned(),
+            output_bytes,
+            width: probe.map(|info| info.width),
+            height: probe.map(|info| info.height),
+            duration_secs: probe
+                .filter(|_| is_video_path(output))
+                .and_then(|info| info.duration),
+            elapsed_secs,
+        }
+    }
 }
 
 #[derive(Clone, Serialize)]
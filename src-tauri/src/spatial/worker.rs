@@ -1,69 +1,366 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tauri::{AppHandle, Emitter};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use spatial_maker::cutlist::Cut;
+use spatial_maker::video::{is_video_path, probe_video};
+use spatial_maker::{
+    CutList, DepthUpsampling, MVHEVCBackend, MVHEVCConfig, OutputOptions, ProgressEvent,
+    ProgressSink, VideoConfig,
+};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
+use crate::conversion::args::sanitize_output_name;
+use crate::conversion::sidecar::{classify_io_error, expected_sidecar_path};
+use crate::conversion::utils::parse_time;
+use crate::events::EventSink;
+use crate::spatial::bootstrap::find_in_path;
+use crate::spatial::disk::{ensure_space, video_job_bytes};
 use crate::spatial::error::SpatialError;
 use crate::spatial::manager::SpatialMessage;
 use crate::spatial::types::{
-    SpatialCompletedPayload, SpatialLogPayload, SpatialProgressPayload, SpatialStartedPayload,
+    FrameStats, SpatialCompletedPayload, SpatialConfig, SpatialLogPayload, SpatialProgressPayload,
     SpatialTask,
 };
 
-pub async fn run_spatial_worker(
-    app: AppHandle,
+pub async fn run_spatial_worker<E: EventSink>(
+    events: E,
     tx: mpsc::Sender<SpatialMessage>,
     task: SpatialTask,
 ) -> Result<(), SpatialError> {
-    let id = task.id.clone();
-
+    let started = Instant::now();
     let input_path = Path::new(&task.file_path);
-    let output_path = input_path.with_file_name(format!(
-        "{}_spatial.mov",
-        input_path.file_stem().unwrap_or_default().to_string_lossy()
-    ));
-
-    let mut cmd = Command::new("uv");
-    cmd.arg("tool")
-        .arg("run")
-        .arg("spatial-maker")
-        .arg(&task.file_path)
-        .arg("--output")
-        .arg(&output_path)
-        .arg("--json-progress")
-        .arg("--encoder")
-        .arg(&task.config.encoder_size)
-        .arg("--max-disparity")
-        .arg(task.config.max_disparity.to_string())
-        .arg("--skip-downscale");
-
-    if let Some(duration) = task.config.duration {
-        cmd.arg("--duration").arg(duration.to_string());
-    }
-
-    cmd.stdout(Stdio::piped());
-    cmd.stderr(Stdio::piped());
-
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| SpatialError::Shell(format!("Failed to spawn spatial-maker: {}", e)))?;
+    let config = &task.config;
+    let layout = format!("_{}", config.layout.suffix());
 
-    let pid = child.id().unwrap_or(0);
-    let _ = tx
-        .send(SpatialMessage::TaskStarted(id.clone(), pid))
-        .await;
-    let _ = app.emit(
-        "spatial-started",
-        SpatialStartedPayload { id: id.clone() },
+    if !is_video_path(input_path) {
+        let extension = config.image_encoding().extension();
+        let stereo_path = output_file(input_path, config, &layout, extension);
+        let mvhevc = photo_mvhevc(&events, &task);
+        let output_path = match &mvhevc {
+            Some(mvhevc) => stereo_path.with_extension(mvhevc.backend.extension()),
+            None => stereo_path.clone(),
+        };
+        let options = OutputOptions {
+            mvhevc,
+            cancel: Some(task.cancel.clone()),
+            ..output_options(&task.config)
+        };
+        run_stereo(&events, &tx, &task, input_path, &stereo_path, options).await?;
+        emit_completed(&events, &task.id, &output_path, started).await;
+        return Ok(());
+    }
+
+    let output_path = output_file(input_path, config, "", "mov");
+    if output_path == input_path {
+        return Err(SpatialError::InvalidInput(
+            "The output name would overwrite the input video".to_string(),
+        ));
+    }
+    let stereo_path = output_file(input_path, config, &layout, "mp4");
+    let extract_path = output_file(input_path, config, "_source", "mkv");
+
+    let extract_args = build_extract_args(
+        &task.file_path,
+        &extract_path.to_string_lossy(),
+        &task.config,
+    );
+    // The intermediates are large; fail now rather than partway through
+    let input_bytes = std::fs::metadata(input_path)?.len();
+    ensure_space(
+        &output_path,
+        video_job_bytes(input_bytes, extract_args.is_some(), config.mvhevc),
+    )?;
+    let source = match extract_args {
+        Some(args) => {
+            let extracted = run_extraction(&events, &tx, &task, args).await;
+            if extracted.is_err() {
+                let _ = std::fs::remove_file(&extract_path);
+            }
+            extracted?;
+            extract_path.clone()
+        }
+        None => input_path.to_path_buf(),
+    };
+
+    let options = output_options(&task.config);
+    let result = run_stereo(&events, &tx, &task, &source, &stereo_path, options).await;
+    if source == extract_path {
+        let _ = std::fs::remove_file(&extract_path);
+    }
+    result?;
+
+    let output = if task.config.mvhevc {
+        run_spatial_make(&events, &tx, &task, &stereo_path, &output_path).await?
+    } else {
+        stereo_path
+    };
+    emit_completed(&events, &task.id, &output, started).await;
+    Ok(())
+}
+
+/// Reports the finished output along with its size, frame size and length,
+/// so the UI doesn't have to probe it again.
+async fn emit_completed<E: EventSink>(events: &E, id: &str, output: &Path, started: Instant) {
+    let elapsed_secs = started.elapsed().as_secs_f64();
+    let output_bytes = std::fs::metadata(output).ok().map(|meta| meta.len());
+    let probe_path = output.to_path_buf();
+    let probe = tokio::task::spawn_blocking(move || {
+        probe_video(&expected_sidecar_path("ffprobe"), &probe_path).ok()
+    })
+    .await
+    .ok()
+    .flatten();
+    events.emit(
+        "spatial-completed",
+        SpatialCompletedPayload::new(id, output, output_bytes, probe.as_ref(), elapsed_secs),
     );
-    let _ = app.emit(
+}
+
+/// The spatial-maker settings for `task`, reporting through `progress` and
+/// stopped by the task's cancellation token. Depth is upsampled with the
+/// guided filter, so disparity edges follow the image instead of the model's
+/// coarse grid, and a depth pass that runs out of memory is retried with a
+/// smaller size, model or the CPU rather than failing the task.
+fn pipeline_config(
+    task: &SpatialTask,
+    progress: ProgressSink,
+) -> Result<spatial_maker::SpatialConfig, SpatialError> {
+    Ok(spatial_maker::SpatialConfig::builder()
+        .encoder_size(task.config.encoder_size.model_size())
+        .max_disparity(task.config.max_disparity)
+        .depth_upsampling(DepthUpsampling::Guided)
+        .degrade_on_failure(true)
+        .cancel(task.cancel.clone())
+        .progress(progress)
+        .build()?)
+}
+
+/// Stands for the input's file stem in `SpatialConfig::output_name`.
+pub(crate) const OUTPUT_STEM: &str = "{stem}";
+
+/// Path of one of the task's files: `{name}{suffix}.{ext}`, where the name
+/// is the configured output name with `{stem}` filled in (`{stem}_spatial`
+/// by default), in the output folder or else next to the input.
+pub(crate) fn output_file(
+    input_path: &Path,
+    config: &SpatialConfig,
+    suffix: &str,
+    extension: &str,
+) -> PathBuf {
+    let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = config
+        .output_name
+        .as_deref()
+        .and_then(sanitize_output_name)
+        .unwrap_or_else(|| format!("{}_spatial", OUTPUT_STEM))
+        .replace(OUTPUT_STEM, &stem);
+    let dir = match output_dir(config) {
+        Some(dir) => PathBuf::from(dir),
+        None => input_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+    dir.join(format!("{}{}.{}", name, suffix, extension))
+}
+
+fn output_dir(config: &SpatialConfig) -> Option<&str> {
+    config
+        .output_dir
+        .as_deref()
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+}
+
+/// Layout and image encoding of the stereo render for `config`.
+fn output_options(config: &SpatialConfig) -> OutputOptions {
+    OutputOptions {
+        layout: config.layout.output_format(),
+        image_format: config.image_encoding(),
+        ..OutputOptions::default()
+    }
+}
+
+/// MV-HEVC settings for a photo task: the native writer on macOS, the
+/// `spatial` CLI elsewhere. `None` when turned off or the CLI is missing,
+/// which leaves the stereo image as the result.
+fn photo_mvhevc<E: EventSink>(events: &E, task: &SpatialTask) -> Option<MVHEVCConfig> {
+    let config = &task.config;
+    if !config.mvhevc {
+        return None;
+    }
+    let backend = if cfg!(target_os = "macos") {
+        MVHEVCBackend::Native
+    } else {
+        let path_var = std::env::var_os("PATH").unwrap_or_default();
+        if find_in_path("spatial", &path_var).is_none() {
+            events.emit(
+                "spatial-log",
+                SpatialLogPayload {
+                    id: task.id.clone(),
+                    line: "[SPATIAL] `spatial` CLI not found; keeping the stereo image".to_string(),
+                },
+            );
+            return None;
+        }
+        MVHEVCBackend::SpatialCli
+    };
+    let defaults = MVHEVCConfig::default();
+    Some(MVHEVCConfig {
+        backend,
+        enabled: true,
+        quality: config.mvhevc_quality.unwrap_or(defaults.quality),
+        keep_intermediate: config.keep_stereo,
+        ..defaults
+    })
+}
+
+/// Renders the stereo version of `source` in-process with
+/// spatial-maker, which runs the heavy stages on its own thread pool. There
+/// is no process to kill, so cancelling goes through the task's token.
+async fn run_stereo<E: EventSink>(
+    events: &E,
+    tx: &mpsc::Sender<SpatialMessage>,
+    task: &SpatialTask,
+    source: &Path,
+    output_path: &Path,
+    options: OutputOptions,
+) -> Result<(), SpatialError> {
+    let id = task.id.clone();
+
+    let _ = tx.send(SpatialMessage::TaskStarted(id.clone(), 0)).await;
+    if source == Path::new(&task.file_path) {
+        events.emit("spatial-started", task.started_payload());
+    }
+    events.emit(
         "spatial-progress",
         SpatialProgressPayload {
             id: id.clone(),
             progress: 0.0,
             stage: "starting".to_string(),
+            frames: None,
+        },
+    );
+
+    let relay = ProgressRelay::new(events.clone(), &id);
+    let config = pipeline_config(task, ProgressSink::new(move |event| relay.relay(event)))?;
+
+    if is_video_path(source) {
+        let video = VideoConfig {
+            ffmpeg_path: Some(expected_sidecar_path("ffmpeg")),
+            ffprobe_path: Some(expected_sidecar_path("ffprobe")),
+            cuts: task
+                .config
+                .duration
+                .filter(|&duration| duration > 0.0)
+                .map(|duration| CutList {
+                    cuts: vec![Cut::from_secs(0.0, duration)],
+                }),
+            ..VideoConfig::default()
+        };
+        spatial_maker::process_video::<fn(u64, Option<u64>)>(
+            source,
+            output_path,
+            config,
+            options,
+            &video,
+            None,
+        )
+        .await?;
+    } else {
+        spatial_maker::process_photo(source, output_path, config, options).await?;
+    }
+    Ok(())
+}
+
+/// `spatial make` arguments turning the stereo video at `input` into the
+/// MV-HEVC file at `output`.
+pub(crate) fn build_spatial_make_args(
+    input: &str,
+    output: &str,
+    config: &SpatialConfig,
+) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "make",
+        "--input",
+        input,
+        "--output",
+        output,
+        "--format",
+        config.layout.cli_format(),
+        "--overwrite",
+    ]
+    .map(String::from)
+    .to_vec();
+    if let Some(quality) = config.mvhevc_quality {
+        args.push("--quality".to_string());
+        args.push(format!("{:.2}", f32::from(quality) / 100.0));
+    }
+    args
+}
+
+/// Turns the stereo render into an MV-HEVC spatial video with the `spatial`
+/// CLI, reporting it as the task's process so cancelling stops it. Without
+/// the CLI the stereo video is the result.
+async fn run_spatial_make<E: EventSink>(
+    events: &E,
+    tx: &mpsc::Sender<SpatialMessage>,
+    task: &SpatialTask,
+    stereo_path: &Path,
+    output_path: &Path,
+) -> Result<PathBuf, SpatialError> {
+    let id = task.id.as_str();
+    let log = |line: String| {
+        events.emit(
+            "spatial-log",
+            SpatialLogPayload {
+                id: id.to_string(),
+                line,
+            },
+        )
+    };
+
+    let spawned = Command::new("spatial")
+        .args(build_spatial_make_args(
+            &stereo_path.to_string_lossy(),
+            &output_path.to_string_lossy(),
+            &task.config,
+        ))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log("[SPATIAL] `spatial` CLI not found; keeping the stereo video".to_string());
+            return Ok(stereo_path.to_path_buf());
+        }
+        Err(e) => {
+            return Err(SpatialError::Shell(format!(
+                "Failed to spawn spatial: {}",
+                e
+            )));
+        }
+    };
+
+    let _ = tx
+        .send(SpatialMessage::TaskStarted(
+            id.to_string(),
+            child.id().unwrap_or(0),
+        ))
+        .await;
+    events.emit(
+        "spatial-progress",
+        SpatialProgressPayload {
+            id: id.to_string(),
+            progress: 90.0,
+            stage: "spatial_make".to_string(),
+            frames: None,
         },
     );
 
@@ -75,127 +372,368 @@ pub async fn run_spatial_worker(
         .stderr
         .take()
         .ok_or_else(|| SpatialError::Worker("Failed to capture stderr".to_string()))?;
+    // Logged as it comes, so a long run keeps the watchdog fed
+    let stdout_handle = tokio::spawn(log_lines(events.clone(), id.to_string(), stdout));
+    let stderr_handle = tokio::spawn(log_lines(events.clone(), id.to_string(), stderr));
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| SpatialError::Worker(format!("Failed to wait for spatial: {}", e)))?;
+    let _ = stdout_handle.await;
+    let _ = stderr_handle.await;
+
+    if status.success() {
+        if !task.config.keep_stereo {
+            let _ = std::fs::remove_file(stereo_path);
+        }
+        Ok(output_path.to_path_buf())
+    } else {
+        Err(SpatialError::Worker(format!(
+            "spatial exited with code {}",
+            status.code().unwrap_or(-1)
+        )))
+    }
+}
 
-    let stdout_reader = BufReader::new(stdout);
-    let stderr_reader = BufReader::new(stderr);
+/// Sends each line of `stream` as a `spatial-log` event.
+async fn log_lines<E: EventSink>(events: E, id: String, stream: impl AsyncRead + Unpin) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        events.emit(
+            "spatial-log",
+            SpatialLogPayload {
+                id: id.clone(),
+                line,
+            },
+        );
+    }
+}
+
+/// Trim points of `config` in seconds, `None` where unset.
+fn trim_bounds(config: &SpatialConfig) -> (Option<f64>, Option<f64>) {
+    let bound = |time: &Option<String>| {
+        time.as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .and_then(parse_time)
+    };
+    (bound(&config.start_time), bound(&config.end_time))
+}
+
+/// Rejects trim points and size limits the extraction stage cannot apply.
+pub(crate) fn validate_extract_config(config: &SpatialConfig) -> Result<(), SpatialError> {
+    for (label, time) in [("start", &config.start_time), ("end", &config.end_time)] {
+        if let Some(time) = time.as_deref().map(str::trim).filter(|t| !t.is_empty())
+            && parse_time(time).is_none_or(|t| t < 0.0)
+        {
+            return Err(SpatialError::InvalidInput(format!(
+                "Invalid {} time: {}",
+                label, time
+            )));
+        }
+    }
+    if let (Some(start), Some(end)) = trim_bounds(config)
+        && end <= start
+    {
+        return Err(SpatialError::InvalidInput(
+            "End time must be greater than start time".to_string(),
+        ));
+    }
+    if config.max_width == Some(0) || config.max_height == Some(0) {
+        return Err(SpatialError::InvalidInput(
+            "Resolution limits cannot be zero".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects output qualities outside 1-100, a missing output folder, and
+/// output names that are only a path.
+pub(crate) fn validate_output_config(config: &SpatialConfig) -> Result<(), SpatialError> {
+    if let Some(dir) = output_dir(config)
+        && !Path::new(dir).is_dir()
+    {
+        return Err(SpatialError::InvalidInput(format!(
+            "Output folder not found: {}",
+            dir
+        )));
+    }
+    if let Some(name) = config.output_name.as_deref()
+        && !name.trim().is_empty()
+        && sanitize_output_name(name).is_none()
+    {
+        return Err(SpatialError::InvalidInput(format!(
+            "Invalid output name: {}",
+            name
+        )));
+    }
+    let in_range = |quality: u8| (1..=100).contains(&quality);
+    if !in_range(config.image_quality) {
+        return Err(SpatialError::InvalidInput(
+            "Image quality must be between 1 and 100".to_string(),
+        ));
+    }
+    if let Some(quality) = config.mvhevc_quality
+        && !in_range(quality)
+    {
+        return Err(SpatialError::InvalidInput(
+            "MV-HEVC quality must be between 1 and 100".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// ffmpeg arguments for the extraction stage: the clip cut to the trim
+/// points and scaled down to the resolution limits, re-encoded near
+/// losslessly so the cuts land on exact frames. `None` when the task has
+/// neither, and spatial-maker reads the input directly.
+pub(crate) fn build_extract_args(
+    input: &str,
+    output: &str,
+    config: &SpatialConfig,
+) -> Option<Vec<String>> {
+    let (start, end) = trim_bounds(config);
+    if start.is_none() && end.is_none() && config.max_width.is_none() && config.max_height.is_none()
+    {
+        return None;
+    }
+
+    let mut args: Vec<String> = ["-hide_banner", "-nostdin", "-y", "-loglevel", "error"]
+        .map(String::from)
+        .to_vec();
+    if let Some(start) = start {
+        args.push("-ss".to_string());
+        args.push(format!("{:.3}", start));
+    }
+    args.push("-i".to_string());
+    args.push(input.to_string());
+    if let Some(end) = end {
+        args.push("-t".to_string());
+        args.push(format!("{:.3}", end - start.unwrap_or(0.0)));
+    }
+
+    if config.max_width.is_some() || config.max_height.is_some() {
+        let limit = |max: Option<u32>, side: &str| match max {
+            Some(max) => format!("'min({},{})'", side, max),
+            None => side.to_string(),
+        };
+        args.push("-vf".to_string());
+        args.push(format!(
+            "scale=w={}:h={}:force_original_aspect_ratio=decrease:force_divisible_by=2",
+            limit(config.max_width, "iw"),
+            limit(config.max_height, "ih")
+        ));
+    }
+
+    for pair in [
+        ["-map", "0:v:0"],
+        ["-map", "0:a?"],
+        ["-c:v", "libx264"],
+        ["-crf", "12"],
+        ["-preset", "veryfast"],
+        ["-c:a", "copy"],
+        ["-progress", "pipe:1"],
+    ] {
+        args.extend(pair.map(String::from));
+    }
+    args.push("-nostats".to_string());
+    args.push(output.to_string());
+    Some(args)
+}
 
-    let app_stdout = app.clone();
-    let id_stdout = id.clone();
+/// Runs the extraction stage with the bundled ffmpeg, reporting it as the
+/// task's process so cancelling stops it too.
+async fn run_extraction<E: EventSink>(
+    events: &E,
+    tx: &mpsc::Sender<SpatialMessage>,
+    task: &SpatialTask,
+    args: Vec<String>,
+) -> Result<(), SpatialError> {
+    let id = task.id.as_str();
+    let mut child = Command::new(expected_sidecar_path("ffmpeg"))
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match classify_io_error("ffmpeg", &e) {
+            Some(issue) => SpatialError::Shell(issue.to_string()),
+            None => SpatialError::Shell(format!("Failed to spawn ffmpeg: {}", e)),
+        })?;
+
+    let pid = child.id().unwrap_or(0);
+    let _ = tx
+        .send(SpatialMessage::TaskStarted(id.to_string(), pid))
+        .await;
+    events.emit("spatial-started", task.started_payload());
+    let extracting = SpatialProgressPayload {
+        id: id.to_string(),
+        progress: 0.0,
+        stage: "extracting".to_string(),
+        frames: None,
+    };
+    events.emit("spatial-progress", extracting.clone());
 
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| SpatialError::Worker("Failed to capture stdout".to_string()))?;
+    let stderr = child
+        .stderr
+        .take()
+        .ok_or_else(|| SpatialError::Worker("Failed to capture stderr".to_string()))?;
+
+    // ffmpeg's progress blocks keep the watchdog from taking a long
+    // extraction for a stall
+    let events_stdout = events.clone();
     let stdout_handle = tokio::spawn(async move {
-        let mut lines = stdout_reader.lines();
+        let mut lines = BufReader::new(stdout).lines();
         while let Ok(Some(line)) = lines.next_line().await {
-            let line: String = line;
-            let _ = app_stdout.emit(
-                "spatial-log",
-                SpatialLogPayload {
-                    id: id_stdout.clone(),
-                    line: line.clone(),
-                },
-            );
-
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-                match json.get("event").and_then(|v| v.as_str()) {
-                    Some("stage") => {
-                        let stage = json
-                            .get("stage")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("unknown");
-                        let progress = match stage {
-                            "depth_stereo" => 0.0,
-                            "audio_mux" => 85.0,
-                            "spatial_make" => 90.0,
-                            _ => 0.0,
-                        };
-                        let _ = app_stdout.emit(
-                            "spatial-progress",
-                            SpatialProgressPayload {
-                                id: id_stdout.clone(),
-                                progress,
-                                stage: stage.to_string(),
-                            },
-                        );
-                    }
-                    Some("progress") => {
-                        if let Some(pct) = json.get("pct").and_then(|v| v.as_f64()) {
-                            // depth_stereo is 0-85% of total (no downscale step)
-                            let mapped = (pct / 100.0) * 85.0;
-                            let _ = app_stdout.emit(
-                                "spatial-progress",
-                                SpatialProgressPayload {
-                                    id: id_stdout.clone(),
-                                    progress: mapped,
-                                    stage: "depth_stereo".to_string(),
-                                },
-                            );
-                        }
-                    }
-                    Some("done") => {
-                        let output = json
-                            .get("output")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                            .to_string();
-                        let _ = app_stdout.emit(
-                            "spatial-completed",
-                            SpatialCompletedPayload {
-                                id: id_stdout.clone(),
-                                output_path: output,
-                            },
-                        );
-                    }
-                    Some("error") => {
-                        let msg = json
-                            .get("message")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("Unknown error")
-                            .to_string();
-                        let _ = app_stdout.emit(
-                            "spatial-log",
-                            SpatialLogPayload {
-                                id: id_stdout.clone(),
-                                line: format!("[SPATIAL ERROR] {}", msg),
-                            },
-                        );
-                    }
-                    _ => {}
-                }
+            if line.starts_with("progress=") {
+                events_stdout.emit("spatial-progress", extracting.clone());
             }
         }
     });
-
-    let app_stderr = app.clone();
-    let id_stderr = id.clone();
-
     let stderr_handle = tokio::spawn(async move {
-        let mut lines = stderr_reader.lines();
+        let mut errors = Vec::new();
+        let mut lines = BufReader::new(stderr).lines();
         while let Ok(Some(line)) = lines.next_line().await {
-            let line: String = line;
-            let _ = app_stderr.emit(
-                "spatial-log",
-                SpatialLogPayload {
-                    id: id_stderr.clone(),
-                    line: line.clone(),
-                },
-            );
+            errors.push(line);
         }
+        errors
     });
 
     let status = child
         .wait()
         .await
-        .map_err(|e| SpatialError::Worker(format!("Failed to wait for process: {}", e)))?;
-
+        .map_err(|e| SpatialError::Worker(format!("Failed to wait for ffmpeg: {}", e)))?;
     let _ = stdout_handle.await;
-    let _ = stderr_handle.await;
+    let errors = stderr_handle.await.unwrap_or_default();
+    for line in &errors {
+        events.emit(
+            "spatial-log",
+            SpatialLogPayload {
+                id: id.to_string(),
+                line: line.clone(),
+            },
+        );
+    }
 
     if status.success() {
         Ok(())
     } else {
-        let code = status.code().unwrap_or(-1);
         Err(SpatialError::Worker(format!(
-            "spatial-maker exited with code {}",
-            code
+            "Extracting the trimmed clip failed: {}",
+            errors
+                .last()
+                .map(String::as_str)
+                .unwrap_or("ffmpeg exited with an error")
         )))
     }
 }
+
+/// Share of the task's progress taken by depth estimation and stereo
+/// synthesis; the rest is encoding.
+const STEREO_SHARE: f64 = 85.0;
+
+/// How often a slow stage is reported even when its percentage hasn't
+/// moved, well inside the watchdog's stall timeout.
+const RELAY_KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// Forwards spatial-maker's progress events as `spatial-progress`, with a
+/// `spatial-log` line as each stage begins. Steps are reported once per whole
+/// percent so long videos don't flood the frontend, and frame counts without
+/// a known total every 100 frames; an unchanged step is repeated after
+/// `RELAY_KEEPALIVE` so slow videos don't look stalled. Frame events also
+/// carry the speed and time remaining, measured from the first frame seen.
+pub(crate) struct ProgressRelay<E> {
+    events: E,
+    id: String,
+    keepalive: Duration,
+    last: Mutex<Option<(&'static str, u64, Instant)>>,
+    first_frame: Mutex<Option<(Instant, u64)>>,
+}
+
+impl<E: EventSink> ProgressRelay<E> {
+    pub(crate) fn new(events: E, id: &str) -> Self {
+        Self {
+            events,
+            id: id.to_string(),
+            keepalive: RELAY_KEEPALIVE,
+            last: Mutex::new(None),
+            first_frame: Mutex::new(None),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_keepalive(mut self, keepalive: Duration) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    fn frame_stats(&self, done: u64, total: Option<u64>) -> FrameStats {
+        let mut first_frame = self.first_frame.lock().unwrap();
+        let (started, first) = *first_frame.get_or_insert((Instant::now(), done));
+        FrameStats::new(
+            done,
+            total.filter(|&total| total > 0),
+            done.saturating_sub(first),
+            started.elapsed().as_secs_f64(),
+        )
+    }
+
+    pub(crate) fn relay(&self, event: ProgressEvent) {
+        let frames = match event {
+            ProgressEvent::Frames { done, total } => Some(self.frame_stats(done, total)),
+            _ => None,
+        };
+        let (stage, progress, step) = match event {
+            ProgressEvent::ModelDownload { pct } => ("model_download", 0.0, pct as u64),
+            ProgressEvent::Preprocess => ("preprocess", 0.0, 0),
+            ProgressEvent::Inference => ("depth_stereo", 0.0, 0),
+            ProgressEvent::Warp { row_pct } => {
+                let progress = row_pct as f64 / 100.0 * STEREO_SHARE;
+                ("depth_stereo", progress, progress as u64)
+            }
+            ProgressEvent::Frames {
+                done,
+                total: Some(total),
+            } if total > 0 => {
+                let progress = (done as f64 / total as f64).min(1.0) * STEREO_SHARE;
+                ("depth_stereo", progress, progress as u64)
+            }
+            ProgressEvent::Frames { done, .. } => ("depth_stereo", 0.0, done / 100),
+            ProgressEvent::Encode => ("encode", STEREO_SHARE, 0),
+            ProgressEvent::Done => ("rendered", STEREO_SHARE, 0),
+        };
+
+        let mut last = self.last.lock().unwrap();
+        if last.is_some_and(|(previous, previous_step, at)| {
+            (previous, previous_step) == (stage, step) && at.elapsed() < self.keepalive
+        }) {
+            return;
+        }
+        if last.is_none_or(|(previous, ..)| previous != stage) {
+            self.events.emit(
+                "spatial-log",
+                SpatialLogPayload {
+                    id: self.id.clone(),
+                    line: format!("[SPATIAL] {}", stage),
+                },
+            );
+        }
+        *last = Some((stage, step, Instant::now()));
+        drop(last);
+
+        self.events.emit(
+            "spatial-progress",
+            SpatialProgressPayload {
+                id: self.id.clone(),
+                progress,
+                stage: stage.to_string(),
+                frames,
+            },
+        );
+    }
+}
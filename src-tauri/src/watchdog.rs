@@ -0,0 +1,232 @@
+use std::any::Any;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::alerts::QueueKind;
+use crate::events::EventSink;
+
+/// A worker that reports nothing for this long is presumed stuck. ffmpeg and
+/// spatial-maker both print progress every second or so.
+pub const WORKER_STALL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+pub const WORKER_DIAGNOSTIC_EVENT: &str = "worker-diagnostic";
+
+/// How a supervised worker ended without returning.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum WorkerFault {
+    Panicked {
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Stalled {
+        idle_secs: u64,
+    },
+    /// Still running when the task's time limit ran out.
+    #[serde(rename_all = "camelCase")]
+    TimedOut {
+        limit_secs: u64,
+    },
+}
+
+impl fmt::Display for WorkerFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerFault::Panicked { message } => write!(f, "Worker crashed: {}", message),
+            WorkerFault::Stalled { idle_secs } => {
+                write!(
+                    f,
+                    "Worker stopped responding ({}s without output)",
+                    idle_secs
+                )
+            }
+            WorkerFault::TimedOut { limit_secs } => {
+                write!(
+                    f,
+                    "Worker exceeded its {} time limit",
+                    format_limit(*limit_secs)
+                )
+            }
+        }
+    }
+}
+
+/// A task's `max_duration_secs` as a limit for `supervise`; 0 means none.
+pub fn time_limit(max_duration_secs: Option<u64>) -> Option<Duration> {
+    max_duration_secs
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// A time limit as the user would write it: "12h", "90m", "45s".
+pub fn format_limit(secs: u64) -> String {
+    if secs >= 3600 && secs.is_multiple_of(3600) {
+        format!("{}h", secs / 3600)
+    } else if secs >= 60 && secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerDiagnosticPayload {
+    pub id: String,
+    pub queue: QueueKind,
+    pub fault: WorkerFault,
+    pub message: String,
+}
+
+impl WorkerDiagnosticPayload {
+    pub fn new(id: &str, queue: QueueKind, fault: &WorkerFault) -> Self {
+        Self {
+            id: id.to_string(),
+            queue,
+            fault: fault.clone(),
+            message: fault.to_string(),
+        }
+    }
+}
+
+/// Time a supervised worker spent paused on purpose, which the watchdog
+/// does not count: a suspended worker sends nothing, but it is not stuck.
+#[derive(Clone, Default)]
+pub struct PauseClock {
+    state: Arc<Mutex<PauseState>>,
+}
+
+#[derive(Default)]
+struct PauseState {
+    paused_at: Option<Instant>,
+    paused_for: Duration,
+}
+
+impl PauseClock {
+    pub fn pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.paused_at.is_none() {
+            state.paused_at = Some(Instant::now());
+        }
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(paused_at) = state.paused_at.take() {
+            state.paused_for += paused_at.elapsed();
+        }
+    }
+
+    /// Total time paused, including a pause still going on.
+    pub fn paused_for(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        state.paused_for + state.paused_at.map_or(Duration::ZERO, |at| at.elapsed())
+    }
+}
+
+/// Forwards a worker's events and notes when it last sent one.
+#[derive(Clone)]
+pub struct Heartbeat<E> {
+    inner: E,
+    pause: PauseClock,
+    /// When the last event came, and how long the worker had been paused by then
+    last_beat: Arc<Mutex<(Instant, Duration)>>,
+}
+
+impl<E> Heartbeat<E> {
+    fn new(inner: E, pause: PauseClock) -> Self {
+        let paused_for = pause.paused_for();
+        Self {
+            inner,
+            pause,
+            last_beat: Arc::new(Mutex::new((Instant::now(), paused_for))),
+        }
+    }
+
+    /// Time since the last event, not counting time spent paused.
+    fn idle(&self) -> Duration {
+        let (at, paused_then) = *self.last_beat.lock().unwrap();
+        let paused_since = self.pause.paused_for().saturating_sub(paused_then);
+        at.elapsed().saturating_sub(paused_since)
+    }
+}
+
+impl<E: EventSink> EventSink for Heartbeat<E> {
+    fn emit<P: Serialize + Clone>(&self, event: &str, payload: P) {
+        *self.last_beat.lock().unwrap() = (Instant::now(), self.pause.paused_for());
+        self.inner.emit(event, payload);
+    }
+}
+
+/// Text of a panic payload: the `panic!` message when it has one.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Runs a worker on its own task, reporting through `events`. A panic comes
+/// back as `WorkerFault::Panicked`; a worker that emits nothing for
+/// `stall_timeout` is aborted and comes back as `WorkerFault::Stalled`, and
+/// one still running after `max_duration` as `WorkerFault::TimedOut`. Time
+/// the caller marks paused on `pause` counts neither as silence nor towards
+/// `max_duration`.
+/// Processes the worker started are left to the caller.
+pub async fn supervise<E, F, Fut, T>(
+    events: E,
+    pause: PauseClock,
+    stall_timeout: Duration,
+    max_duration: Option<Duration>,
+    worker: F,
+) -> Result<T, WorkerFault>
+where
+    E: EventSink,
+    F: FnOnce(Heartbeat<E>) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let started = Instant::now();
+    let paused_before = pause.paused_for();
+    let heartbeat = Heartbeat::new(events, pause);
+    let mut handle = tauri::async_runtime::spawn(worker(heartbeat.clone()));
+    loop {
+        let idle = heartbeat.idle();
+        if idle >= stall_timeout {
+            handle.abort();
+            return Err(WorkerFault::Stalled {
+                idle_secs: idle.as_secs(),
+            });
+        }
+        let mut wait = stall_timeout - idle;
+        if let Some(limit) = max_duration {
+            let paused = heartbeat.pause.paused_for().saturating_sub(paused_before);
+            let active = started.elapsed().saturating_sub(paused);
+            let Some(left) = limit.checked_sub(active).filter(|d| !d.is_zero()) else {
+                handle.abort();
+                return Err(WorkerFault::TimedOut {
+                    limit_secs: limit.as_secs(),
+                });
+            };
+            wait = wait.min(left);
+        }
+        if let Ok(result) = tokio::time::timeout(wait, &mut handle).await {
+            return result.map_err(join_fault);
+        }
+    }
+}
+
+fn join_fault(error: tauri::Error) -> WorkerFault {
+    let message = match error {
+        tauri::Error::JoinError(e) if e.is_panic() => panic_message(&*e.into_panic()),
+        e => e.to_string(),
+    };
+    WorkerFault::Panicked { message }
+}
@@ -3,9 +3,10 @@ import {
 	startConversion as startConversionService,
 	pauseConversion,
 	resumeConversion,
-	cancelConversion
+	cancelConversion,
+	type SidecarIssueEvent
 } from '$lib/services/conversion';
-import { sendAppNotification } from '$lib/services/notifications';
+import { notifyQueueFinished, sendAppNotification } from '$lib/services/notifications';
 import { FileStatus, type FileItem } from '$lib/types';
 import { _ } from '$lib/i18n';
 import { get } from 'svelte/store';
@@ -17,10 +18,36 @@ export interface ConversionCallbacks {
 	getIsProcessing: () => boolean;
 	setIsProcessing: (value: boolean) => void;
 	onConversionCompleted?: (id: string, outputPath: string) => void;
+	onSidecarIssue?: (issue: SidecarIssueEvent) => void;
 }
 
 export function createConversionQueue(callbacks: ConversionCallbacks) {
 	let unlistenPromise: Promise<() => void> | null = null;
+	const reportedSidecars = new Set<string>();
+
+	function handleSidecarIssue(issue: SidecarIssueEvent) {
+		callbacks.onLogsUpdate((logs) => {
+			const current = logs[issue.id] || [];
+			return {
+				...logs,
+				[issue.id]: [...current, `[SETUP] ${issue.binary}: ${issue.suggestedFix}`]
+			};
+		});
+
+		if (callbacks.onSidecarIssue) {
+			callbacks.onSidecarIssue(issue);
+			return;
+		}
+
+		// Every queued file fails the same way; notify once per binary
+		if (reportedSidecars.has(issue.binary)) return;
+		reportedSidecars.add(issue.binary);
+		const t = get(_);
+		sendAppNotification(
+			t('notifications.sidecarMissingTitle', { values: { binary: issue.binary } }),
+			t('notifications.sidecarMissingBody', { values: { binary: issue.binary } })
+		);
+	}
 
 	function setupListeners() {
 		unlistenPromise = setupConversionListeners(
@@ -72,7 +99,8 @@ export function createConversionQueue(callbacks: ConversionCallbacks) {
 						return f;
 					})
 				);
-			}
+			},
+			handleSidecarIssue
 		);
 
 		return () => {
@@ -95,7 +123,8 @@ export function createConversionQueue(callbacks: ConversionCallbacks) {
 
 			if (completedCount > 0 || errorCount > 0) {
 				const t = get(_);
-				sendAppNotification(
+				notifyQueueFinished(
+					'conversion',
 					t('notifications.conversionFinishedTitle'),
 					t('notifications.conversionFinishedBody', {
 						values: { count: completedCount, errors: errorCount }
@@ -1,6 +1,11 @@
 import { v4 as uuidv4 } from 'uuid';
 import { stat } from '@tauri-apps/plugin-fs';
-import { probeMedia, getDefaultAudioCodec } from '$lib/services/media';
+import {
+	probeMedia,
+	getDefaultAudioCodec,
+	isImageFile,
+	listImageFiles
+} from '$lib/services/media';
 import { getDefaultConfig } from '$lib/services/presets';
 import { normalizeConversionConfig } from '$lib/services/config';
 import { cancelConversion } from '$lib/services/conversion';
@@ -9,7 +14,8 @@ import {
 	FileStatus,
 	type FileItem,
 	type ConversionConfig,
-	AUDIO_ONLY_CONTAINERS
+	AUDIO_ONLY_CONTAINERS,
+	IMAGE_INPUT_EXTENSIONS
 } from '$lib/types';
 
 export interface FileListState {
@@ -34,44 +40,68 @@ export function createFileListManager() {
 	const totalSize = $derived(files.reduce((acc, curr) => acc + curr.size, 0));
 	const selectedCount = $derived(files.filter((f) => f.isSelectedForConversion).length);
 
-	function createInitialConfig(): ConversionConfig {
-		return getDefaultConfig();
-	}
+	function createInitialConfig(fileName: string): ConversionConfig {
+		const config = getDefaultConfig();
+		if (!isImageFile(fileName)) return config;
 
-	function deriveOutputName(fileName: string): string {
-		const base = fileName.replace(/\.[^/.]+$/, '');
-		return base ? `${base}_converted` : 'output_converted';
+		// Still images default to an image job; JPEG sources go to WebP instead
+		const isJpeg = /\.jpe?g$/i.test(fileName);
+		return normalizeConversionConfig({
+			...config,
+			container: isJpeg ? 'webp' : 'jpg',
+			quality: 85
+		});
 	}
 
-	async function addFilesFromPaths(paths: string[]) {
-		const newFiles: FileItem[] = [];
-
+	async function expandFolders(paths: string[]): Promise<string[]> {
+		const expanded: string[] = [];
 		for (const pathStr of paths) {
-			const name = pathStr.split(/[/\\]/).pop() || 'unknown';
-
-			let size = 0;
 			try {
 				const metadata = await stat(pathStr);
-				size = metadata.size;
+				if (metadata.isDirectory) {
+					expanded.push(...(await listImageFiles(pathStr)));
+					continue;
+				}
 			} catch (e) {
-				console.error('Failed to stat file:', pathStr, e);
+				console.error('Failed to stat path:', pathStr, e);
 			}
+			expanded.push(pathStr);
+		}
+		return expanded;
+	}
 
-			newFiles.push({
-				id: uuidv4(),
-				name: name,
-				size: size,
-				status: FileStatus.IDLE,
-				progress: 0,
-				originalFormat: name.split('.').pop() || 'unknown',
-				config: createInitialConfig(),
-				outputName: deriveOutputName(name),
-				metadataStatus: 'idle',
-				path: pathStr,
-				isSelectedForConversion: true
-			});
+	function deriveOutputName(fileName: string): string {
+		const base = fileName.replace(/\.[^/.]+$/, '');
+		return base ? `${base}_converted` : 'output_converted';
+	}
+
+	async function createFileItem(pathStr: string, id = uuidv4()): Promise<FileItem> {
+		const name = pathStr.split(/[/\\]/).pop() || 'unknown';
+
+		let size = 0;
+		try {
+			const metadata = await stat(pathStr);
+			size = metadata.size;
+		} catch (e) {
+			console.error('Failed to stat file:', pathStr, e);
 		}
 
+		return {
+			id,
+			name: name,
+			size: size,
+			status: FileStatus.IDLE,
+			progress: 0,
+			originalFormat: name.split('.').pop() || 'unknown',
+			config: createInitialConfig(name),
+			outputName: deriveOutputName(name),
+			metadataStatus: 'idle',
+			path: pathStr,
+			isSelectedForConversion: true
+		};
+	}
+
+	function appendFiles(newFiles: FileItem[]) {
 		if (newFiles.length > 0) {
 			files = [...files, ...newFiles];
 			for (const file of newFiles) {
@@ -83,13 +113,43 @@ export function createFileListManager() {
 		}
 	}
 
+	async function addFilesFromPaths(paths: string[]) {
+		const newFiles: FileItem[] = [];
+		for (const pathStr of await expandFolders(paths)) {
+			newFiles.push(await createFileItem(pathStr));
+		}
+		appendFiles(newFiles);
+	}
+
+	/** Lists spatial tasks restored from the last session as queued rows, keeping their ids. */
+	async function addRestoredFiles(tasks: { id: string; filePath: string }[]) {
+		const newFiles: FileItem[] = [];
+		for (const task of tasks) {
+			if (files.some((f) => f.id === task.id)) continue;
+			const file = await createFileItem(task.filePath, task.id);
+			newFiles.push({ ...file, status: FileStatus.QUEUED });
+		}
+		appendFiles(newFiles);
+	}
+
 	async function handleAddFile() {
 		const selected = await openNativeFileDialog({
 			multiple: true,
 			filters: [
 				{
 					name: 'Media Files',
-					extensions: ['mp4', 'mov', 'mkv', 'avi', 'webm', 'mp3', 'm4a', 'wav', 'flac']
+					extensions: [
+						'mp4',
+						'mov',
+						'mkv',
+						'avi',
+						'webm',
+						'mp3',
+						'm4a',
+						'wav',
+						'flac',
+						...IMAGE_INPUT_EXTENSIONS
+					]
 				},
 				{
 					name: 'Videos',
@@ -98,6 +158,10 @@ export function createFileListManager() {
 				{
 					name: 'Audio',
 					extensions: ['mp3', 'm4a', 'wav', 'flac']
+				},
+				{
+					name: 'Images',
+					extensions: IMAGE_INPUT_EXTENSIONS
 				}
 			]
 		});
@@ -254,6 +318,7 @@ export function createFileListManager() {
 			return logs;
 		},
 		addFilesFromPaths,
+		addRestoredFiles,
 		handleAddFile,
 		handleRemoveFile,
 		updateSelectedConfig,
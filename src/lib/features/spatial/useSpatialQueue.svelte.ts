@@ -1,9 +1,17 @@
 import {
 	setupSpatialListeners,
 	startSpatial as startSpatialService,
-	cancelSpatial
+	savedSpatialQueue,
+	restoreSpatialQueue,
+	pauseSpatial,
+	resumeSpatial,
+	cancelSpatial,
+	type RestoredSpatialTask
 } from '$lib/services/spatial';
+import { notifyQueueFinished } from '$lib/services/notifications';
 import { FileStatus, type FileItem, type SpatialConfig, DEFAULT_SPATIAL_CONFIG } from '$lib/types';
+import { _ } from '$lib/i18n';
+import { get } from 'svelte/store';
 
 export interface SpatialCallbacks {
 	onFilesUpdate: (updater: (files: FileItem[]) => FileItem[]) => void;
@@ -11,10 +19,34 @@ export interface SpatialCallbacks {
 	getFiles: () => FileItem[];
 	getIsProcessing: () => boolean;
 	setIsProcessing: (value: boolean) => void;
+	/** Adds rows for tasks restored from the last session, keeping their ids. */
+	onTasksRestored: (tasks: RestoredSpatialTask[]) => Promise<void>;
+}
+
+const RESOLUTION_HEIGHTS: Record<string, number> = { '1080p': 1080, '720p': 720, '480p': 480 };
+
+function parseDimension(value?: string): number | null {
+	const parsed = Number(value);
+	return Number.isInteger(parsed) && parsed > 0 ? parsed : null;
+}
+
+/** Carries a file's trim and resolution settings over to its spatial job. */
+function spatialConfigForFile(config: SpatialConfig, file: FileItem): SpatialConfig {
+	const { startTime, endTime, resolution, customWidth, customHeight } = file.config;
+	const custom = resolution === 'custom';
+	return {
+		...config,
+		startTime: startTime || undefined,
+		endTime: endTime || undefined,
+		maxWidth: custom ? parseDimension(customWidth) : null,
+		maxHeight: custom ? parseDimension(customHeight) : (RESOLUTION_HEIGHTS[resolution] ?? null)
+	};
 }
 
 export function createSpatialQueue(callbacks: SpatialCallbacks) {
 	let spatialConfig = $state<SpatialConfig>({ ...DEFAULT_SPATIAL_CONFIG });
+	// Files queued or running here, so pausing reaches the right queue
+	const taskIds = new Set<string>();
 
 	function setupListeners() {
 		const unlistenPromise = setupSpatialListeners(
@@ -24,21 +56,37 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 						if (f.id === payload.id) {
 							const status =
 								f.status === FileStatus.QUEUED ? FileStatus.CONVERTING : f.status;
-							return { ...f, status, progress: payload.progress };
+							return {
+								...f,
+								status,
+								progress: payload.progress,
+								spatialFrames: payload.frames
+							};
 						}
 						return f;
 					})
 				);
 			},
 			(payload) => {
+				taskIds.delete(payload.id);
+				const { id, outputPath, ...result } = payload;
 				callbacks.onFilesUpdate((files) =>
 					files.map((f) =>
-						f.id === payload.id ? { ...f, status: FileStatus.COMPLETED, progress: 100 } : f
+						f.id === id
+							? {
+									...f,
+									status: FileStatus.COMPLETED,
+									progress: 100,
+									spatialOutputPath: outputPath,
+									spatialResult: result
+								}
+							: f
 					)
 				);
 				checkAllDone();
 			},
 			(payload) => {
+				taskIds.delete(payload.id);
 				callbacks.onFilesUpdate((files) =>
 					files.map((f) =>
 						f.id === payload.id
@@ -55,6 +103,15 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 				});
 			},
 			(payload) => {
+				if (payload.restored) {
+					callbacks.onLogsUpdate((logs) => {
+						const current = logs[payload.id] || [];
+						return {
+							...logs,
+							[payload.id]: [...current, '[QUEUE] Restored from the last session']
+						};
+					});
+				}
 				callbacks.onFilesUpdate((files) =>
 					files.map((f) => {
 						if (f.id === payload.id && f.status === FileStatus.QUEUED) {
@@ -81,6 +138,19 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 		);
 
 		if (allDone && callbacks.getIsProcessing()) {
+			const completedCount = files.filter((f) => f.status === FileStatus.COMPLETED).length;
+			const errorCount = files.filter((f) => f.status === FileStatus.ERROR).length;
+
+			if (completedCount > 0 || errorCount > 0) {
+				const t = get(_);
+				notifyQueueFinished(
+					'spatial',
+					t('notifications.conversionFinishedTitle'),
+					t('notifications.conversionFinishedBody', {
+						values: { count: completedCount, errors: errorCount }
+					})
+				);
+			}
 			callbacks.setIsProcessing(false);
 		}
 	}
@@ -122,7 +192,8 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 
 		for (const file of pendingFiles) {
 			try {
-				await startSpatialService(file.id, file.path, spatialConfig);
+				await startSpatialService(file.id, file.path, spatialConfigForFile(spatialConfig, file));
+				taskIds.add(file.id);
 			} catch (error) {
 				const message = error instanceof Error ? error.message : String(error);
 				enqueueErrors[file.id] = message;
@@ -171,6 +242,7 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 
 		try {
 			await startSpatialService(id, filePath, spatialConfig);
+			taskIds.add(id);
 		} catch (error) {
 			const message = error instanceof Error ? error.message : String(error);
 			callbacks.onFilesUpdate((files) =>
@@ -191,6 +263,47 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 		}
 	}
 
+	/** Picks up the tasks left queued or running when the app last quit. */
+	async function restoreQueue() {
+		const restored = await savedSpatialQueue();
+		if (restored.length === 0) return;
+
+		for (const task of restored) {
+			taskIds.add(task.id);
+		}
+
+		// Rows first, so the tasks' events have somewhere to land
+		await callbacks.onTasksRestored(restored);
+		await restoreSpatialQueue();
+		callbacks.setIsProcessing(true);
+	}
+
+	function hasTask(id: string) {
+		return taskIds.has(id);
+	}
+
+	async function handlePause(id: string) {
+		try {
+			await pauseSpatial(id);
+			callbacks.onFilesUpdate((files) =>
+				files.map((f) => (f.id === id ? { ...f, status: FileStatus.PAUSED } : f))
+			);
+		} catch (error) {
+			console.error('Failed to pause:', error);
+		}
+	}
+
+	async function handleResume(id: string) {
+		try {
+			await resumeSpatial(id);
+			callbacks.onFilesUpdate((files) =>
+				files.map((f) => (f.id === id ? { ...f, status: FileStatus.CONVERTING } : f))
+			);
+		} catch (error) {
+			console.error('Failed to resume:', error);
+		}
+	}
+
 	async function cancelTask(id: string) {
 		try {
 			await cancelSpatial(id);
@@ -210,6 +323,10 @@ export function createSpatialQueue(callbacks: SpatialCallbacks) {
 		setupListeners,
 		startSpatialConversion,
 		queueSpatialForFile,
+		restoreQueue,
+		hasTask,
+		handlePause,
+		handleResume,
 		cancelTask,
 		checkAllDone,
 		updateConfig
@@ -0,0 +1,55 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { SpatialEncoderSize } from '../types';
+
+export type BootstrapStep =
+	| { kind: 'ffmpeg' }
+	| { kind: 'spatialTool' }
+	| { kind: 'model'; encoderSize: SpatialEncoderSize }
+	| { kind: 'warmDepth' }
+	| { kind: 'notifications' };
+
+export interface BootstrapCheck {
+	step: BootstrapStep;
+	ready: boolean;
+	required: boolean;
+	/** What is wrong and how to fix it, when not ready. */
+	detail: string | null;
+}
+
+export interface BootstrapStatus {
+	/** Every required check passes. */
+	ready: boolean;
+	checks: BootstrapCheck[];
+}
+
+export type BootstrapStepState = 'running' | 'done' | 'skipped' | 'failed';
+
+export interface BootstrapProgressEvent {
+	step: BootstrapStep;
+	state: BootstrapStepState;
+	index: number;
+	total: number;
+	error: string | null;
+}
+
+export async function getBootstrapStatus(): Promise<BootstrapStatus> {
+	return invoke<BootstrapStatus>('bootstrap_status');
+}
+
+/**
+ * Runs the given steps in order. An empty list runs the required ones: the
+ * sidecars and the `encoderSize` model (small when unset).
+ */
+export async function runBootstrap(
+	steps: BootstrapStep[] = [],
+	encoderSize?: SpatialEncoderSize
+): Promise<BootstrapStatus> {
+	return invoke<BootstrapStatus>('bootstrap_run', { steps, encoderSize });
+}
+
+export async function setupBootstrapListener(
+	onProgress: (payload: BootstrapProgressEvent) => void
+): Promise<UnlistenFn> {
+	return listen<BootstrapProgressEvent>('bootstrap-progress', (event) => onProgress(event.payload));
+}
@@ -24,3 +24,19 @@ export async function getAvailableEncoders(): Promise<AvailableEncoders> {
 		};
 	}
 }
+
+export interface GpuDevice {
+	index: number;
+	name: string;
+	kind: string;
+	nvencIndex: number | null;
+}
+
+export async function getGpuDevices(): Promise<GpuDevice[]> {
+	try {
+		return await invoke('get_gpu_devices');
+	} catch (error) {
+		console.error('Failed to list GPU devices:', error);
+		return [];
+	}
+}
@@ -1,4 +1,9 @@
-import { AUDIO_ONLY_CONTAINERS, type ConversionConfig, type SourceMetadata } from '$lib/types';
+import {
+	AUDIO_ONLY_CONTAINERS,
+	IMAGE_CONTAINERS,
+	type ConversionConfig,
+	type SourceMetadata
+} from '$lib/types';
 import { getDefaultAudioCodec, isAudioCodecAllowed } from '$lib/services/media';
 import {
 	NVENC_ENCODERS,
@@ -31,13 +36,23 @@ export function normalizeConversionConfig(
 	}
 
 	const isAudioContainer = AUDIO_ONLY_CONTAINERS.includes(next.container);
-	if (isAudioContainer) {
+	const isImageContainer = IMAGE_CONTAINERS.includes(next.container);
+	if (isAudioContainer || isImageContainer) {
 		next.mlUpscale = 'none';
 		next.selectedSubtitleTracks = [];
 		next.subtitleBurnPath = undefined;
 	}
 
-	if (!isAudioContainer && !isVideoCodecAllowed(next.container, next.videoCodec)) {
+	if (isImageContainer) {
+		next.hwDecode = false;
+		next.quality = Math.min(100, Math.max(0, Math.round(next.quality)));
+	}
+
+	if (
+		!isAudioContainer &&
+		!isImageContainer &&
+		!isVideoCodecAllowed(next.container, next.videoCodec)
+	) {
 		next.videoCodec = getFirstAllowedVideoCodec(next.container);
 	}
 
@@ -26,6 +26,16 @@ export interface StartedEvent {
 	id: string;
 }
 
+export type SidecarProblem = 'missing' | 'not-executable' | 'corrupted';
+
+export interface SidecarIssueEvent {
+	id: string;
+	binary: string;
+	expectedPath: string;
+	problem: SidecarProblem;
+	suggestedFix: string;
+}
+
 export async function startConversion(
 	id: string,
 	filePath: string,
@@ -77,7 +87,8 @@ export async function setupConversionListeners(
 	onCompleted: (payload: CompletedEvent) => void,
 	onError: (payload: ErrorEvent) => void,
 	onLog: (payload: LogEvent) => void,
-	onStarted: (payload: StartedEvent) => void
+	onStarted: (payload: StartedEvent) => void,
+	onSidecarIssue?: (payload: SidecarIssueEvent) => void
 ): Promise<UnlistenFn> {
 	const unlistenStarted = await listen<StartedEvent>('conversion-started', (event) => {
 		onStarted(event.payload);
@@ -99,11 +110,16 @@ export async function setupConversionListeners(
 		onLog(event.payload);
 	});
 
+	const unlistenSidecar = await listen<SidecarIssueEvent>('conversion-sidecar-missing', (event) => {
+		onSidecarIssue?.(event.payload);
+	});
+
 	return () => {
 		unlistenStarted();
 		unlistenProgress();
 		unlistenCompleted();
 		unlistenError();
 		unlistenLog();
+		unlistenSidecar();
 	};
 }
@@ -0,0 +1,61 @@
+import { invoke } from '@tauri-apps/api/core';
+
+export const DAY_SECS = 24 * 60 * 60;
+
+export type ErrorClass =
+	| 'encoder'
+	| 'invalidInput'
+	| 'io'
+	| 'probe'
+	| 'missingTool'
+	| 'crashed'
+	| 'stalled'
+	| 'timedOut'
+	| 'other';
+
+export interface HistorySummary {
+	jobs: number;
+	completed: number;
+	failed: number;
+	cancelled: number;
+	minutesEncoded: number;
+	hoursSpent: number;
+	bytesSaved: number;
+	gbSaved: number;
+}
+
+export interface CodecSpeed {
+	codec: string;
+	jobs: number;
+	mediaSecs: number;
+	elapsedSecs: number;
+	/** Seconds of media per second of work (2 = twice realtime). */
+	averageSpeed: number;
+}
+
+export interface FailureBucket {
+	/** Unix seconds at the start of the bucket. */
+	start: number;
+	total: number;
+	byClass: Partial<Record<ErrorClass, number>>;
+}
+
+/** `since` is in Unix seconds; omit it for all recorded history. */
+export async function getHistorySummary(since?: number): Promise<HistorySummary> {
+	return invoke<HistorySummary>('get_history_summary', { since });
+}
+
+export async function getCodecSpeeds(since?: number): Promise<CodecSpeed[]> {
+	return invoke<CodecSpeed[]>('get_codec_speeds', { since });
+}
+
+export async function getFailureTimeline(
+	since?: number,
+	bucketSecs: number = DAY_SECS
+): Promise<FailureBucket[]> {
+	return invoke<FailureBucket[]>('get_failure_timeline', { since, bucketSecs });
+}
+
+export async function clearConversionHistory(): Promise<void> {
+	await invoke('clear_conversion_history');
+}
@@ -1,10 +1,30 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { SourceMetadata } from '$lib/types';
+import {
+	IMAGE_INPUT_EXTENSIONS,
+	type ContactSheetOptions,
+	type SourceMetadata
+} from '$lib/types';
 
 export async function probeMedia(filePath: string): Promise<SourceMetadata> {
 	return invoke('probe_media', { filePath });
 }
 
+export async function listImageFiles(folder: string, recursive = false): Promise<string[]> {
+	return invoke('list_image_files', { folder, recursive });
+}
+
+export async function generateContactSheet(
+	filePath: string,
+	options?: Partial<ContactSheetOptions>
+): Promise<string> {
+	return invoke('generate_contact_sheet', { filePath, options });
+}
+
+export function isImageFile(name: string): boolean {
+	const extension = name.split('.').pop()?.toLowerCase() ?? '';
+	return IMAGE_INPUT_EXTENSIONS.includes(extension);
+}
+
 export function isAudioCodecAllowed(codec: string, container: string): boolean {
 	if (container === 'mp3') return codec === 'mp3';
 	if (container === 'wav') return codec === 'pcm_s16le';
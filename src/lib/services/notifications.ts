@@ -1,10 +1,18 @@
+import { invoke } from '@tauri-apps/api/core';
 import {
 	isPermissionGranted,
 	requestPermission,
 	sendNotification
 } from '@tauri-apps/plugin-notification';
+import type { QueueKind } from '$lib/services/settings';
 
-export async function sendAppNotification(title: string, body: string) {
+export interface AlertOutcome {
+	notify: boolean;
+	sound: boolean;
+	doNotDisturb: boolean;
+}
+
+async function ensurePermission(): Promise<boolean> {
 	let permissionGranted = await isPermissionGranted();
 
 	if (!permissionGranted) {
@@ -12,7 +20,29 @@ export async function sendAppNotification(title: string, body: string) {
 		permissionGranted = permission === 'granted';
 	}
 
-	if (permissionGranted) {
+	return permissionGranted;
+}
+
+export async function sendAppNotification(title: string, body: string) {
+	if (await ensurePermission()) {
 		sendNotification({ title, body });
 	}
 }
+
+/**
+ * Announces a finished queue. The backend applies the queue's alert settings
+ * and stays quiet while the OS is in Do Not Disturb.
+ */
+export async function notifyQueueFinished(
+	queue: QueueKind,
+	title: string,
+	body: string
+): Promise<AlertOutcome | null> {
+	try {
+		await ensurePermission();
+		return await invoke<AlertOutcome>('queue_finished', { queue, title, body });
+	} catch (error) {
+		console.error('Failed to announce finished queue', error);
+		return null;
+	}
+}
@@ -3,8 +3,12 @@ import { Store } from '@tauri-apps/plugin-store';
 
 const SETTINGS_STORE_PATH = 'app-settings.dat';
 const MAX_CONCURRENCY_KEY = 'maxConcurrency';
+const RESOURCE_LIMITS_KEY = 'resourceLimits';
+const DUPLICATE_POLICY_KEY = 'duplicatePolicy';
+const QUEUE_ALERTS_KEY = 'queueAlerts';
 const AUTO_UPDATE_CHECK_KEY = 'autoUpdateCheck';
 const WINDOW_OPACITY_KEY = 'windowOpacity';
+const WINDOW_EFFECTS_KEY = 'windowEffects';
 const FONT_FAMILY_KEY = 'fontFamily';
 
 const DEFAULT_MAX_CONCURRENCY = 2;
@@ -56,6 +60,169 @@ export async function persistMaxConcurrency(value: number): Promise<void> {
 	await store.save();
 }
 
+export interface ResourceLimits {
+	maxHardwareEncodes: number;
+	maxHeavyJobs: number;
+}
+
+function isValidResourceLimits(value: unknown): value is ResourceLimits {
+	if (!value || typeof value !== 'object') return false;
+	const limits = value as ResourceLimits;
+	return (
+		Number.isInteger(limits.maxHardwareEncodes) &&
+		limits.maxHardwareEncodes > 0 &&
+		Number.isInteger(limits.maxHeavyJobs) &&
+		limits.maxHeavyJobs > 0
+	);
+}
+
+export async function loadInitialResourceLimits(): Promise<ResourceLimits> {
+	try {
+		const store = await getStore();
+		const stored = await store.get<ResourceLimits>(RESOURCE_LIMITS_KEY);
+
+		if (isValidResourceLimits(stored)) {
+			await invoke('set_resource_limits', { limits: stored });
+			return stored;
+		}
+	} catch (error) {
+		console.error('Failed to hydrate stored resource limits', error);
+	}
+
+	return invoke<ResourceLimits>('get_resource_limits');
+}
+
+export async function loadResourceLimits(): Promise<ResourceLimits> {
+	return invoke<ResourceLimits>('get_resource_limits');
+}
+
+export async function persistResourceLimits(limits: ResourceLimits): Promise<void> {
+	if (!isValidResourceLimits(limits)) {
+		throw new Error('Resource limits must be positive');
+	}
+
+	await invoke('set_resource_limits', { limits });
+	const store = await getStore();
+	await store.set(RESOURCE_LIMITS_KEY, limits);
+	await store.save();
+}
+
+export interface DuplicatePolicy {
+	action: 'off' | 'warn' | 'skip';
+	identity: 'pathAndMtime' | 'contentHash';
+}
+
+function isValidDuplicatePolicy(value: unknown): value is DuplicatePolicy {
+	if (!value || typeof value !== 'object') return false;
+	const policy = value as DuplicatePolicy;
+	return (
+		['off', 'warn', 'skip'].includes(policy.action) &&
+		['pathAndMtime', 'contentHash'].includes(policy.identity)
+	);
+}
+
+export async function loadInitialDuplicatePolicy(): Promise<DuplicatePolicy> {
+	try {
+		const store = await getStore();
+		const stored = await store.get<DuplicatePolicy>(DUPLICATE_POLICY_KEY);
+
+		if (isValidDuplicatePolicy(stored)) {
+			await invoke('set_duplicate_policy', { policy: stored });
+			return stored;
+		}
+	} catch (error) {
+		console.error('Failed to hydrate stored duplicate policy', error);
+	}
+
+	return invoke<DuplicatePolicy>('get_duplicate_policy');
+}
+
+export async function loadDuplicatePolicy(): Promise<DuplicatePolicy> {
+	return invoke<DuplicatePolicy>('get_duplicate_policy');
+}
+
+export async function persistDuplicatePolicy(policy: DuplicatePolicy): Promise<void> {
+	if (!isValidDuplicatePolicy(policy)) {
+		throw new Error('Invalid duplicate policy');
+	}
+
+	await invoke('set_duplicate_policy', { policy });
+	const store = await getStore();
+	await store.set(DUPLICATE_POLICY_KEY, policy);
+	await store.save();
+}
+
+export type QueueKind = 'conversion' | 'spatial';
+
+export const QUEUE_KINDS: QueueKind[] = ['conversion', 'spatial'];
+
+export type CompletionSound =
+	| { kind: 'off' }
+	| { kind: 'system'; name: string }
+	| { kind: 'file'; path: string };
+
+export interface QueueAlerts {
+	notify: boolean;
+	sound: CompletionSound;
+	respectDoNotDisturb: boolean;
+}
+
+function isValidQueueAlerts(value: unknown): value is QueueAlerts {
+	if (!value || typeof value !== 'object') return false;
+	const alerts = value as QueueAlerts;
+	return (
+		typeof alerts.notify === 'boolean' &&
+		typeof alerts.respectDoNotDisturb === 'boolean' &&
+		!!alerts.sound &&
+		['off', 'system', 'file'].includes(alerts.sound.kind)
+	);
+}
+
+export async function loadInitialQueueAlerts(): Promise<Record<QueueKind, QueueAlerts>> {
+	try {
+		const store = await getStore();
+		const stored = await store.get<Partial<Record<QueueKind, QueueAlerts>>>(QUEUE_ALERTS_KEY);
+
+		for (const queue of QUEUE_KINDS) {
+			const alerts = stored?.[queue];
+			if (isValidQueueAlerts(alerts)) {
+				await invoke('set_queue_alerts', { queue, alerts });
+			}
+		}
+	} catch (error) {
+		console.error('Failed to hydrate stored queue alerts', error);
+	}
+
+	return loadQueueAlerts();
+}
+
+export async function loadQueueAlerts(): Promise<Record<QueueKind, QueueAlerts>> {
+	const [conversion, spatial] = await Promise.all(
+		QUEUE_KINDS.map((queue) => invoke<QueueAlerts>('get_queue_alerts', { queue }))
+	);
+	return { conversion, spatial };
+}
+
+export async function persistQueueAlerts(queue: QueueKind, alerts: QueueAlerts): Promise<void> {
+	if (!isValidQueueAlerts(alerts)) {
+		throw new Error('Invalid queue alerts');
+	}
+
+	await invoke('set_queue_alerts', { queue, alerts });
+	const store = await getStore();
+	const stored = (await store.get<Partial<Record<QueueKind, QueueAlerts>>>(QUEUE_ALERTS_KEY)) ?? {};
+	await store.set(QUEUE_ALERTS_KEY, { ...stored, [queue]: alerts });
+	await store.save();
+}
+
+export async function listCompletionSounds(): Promise<string[]> {
+	return invoke<string[]>('list_completion_sounds');
+}
+
+export async function previewCompletionSound(sound: CompletionSound): Promise<void> {
+	await invoke('play_completion_sound', { sound });
+}
+
 export async function loadAutoUpdateCheck(): Promise<boolean> {
 	try {
 		const store = await getStore();
@@ -98,6 +265,53 @@ export async function persistWindowOpacity(value: number): Promise<void> {
 	await store.save();
 }
 
+export type WindowEffects = 'auto' | 'translucent' | 'solid';
+
+export interface AccessibilityPreferences {
+	reduceTransparency: boolean;
+	increaseContrast: boolean;
+	reduceMotion: boolean;
+}
+
+export interface AppearanceState {
+	effects: WindowEffects;
+	preferences: AccessibilityPreferences;
+	translucent: boolean;
+}
+
+export const APPEARANCE_CHANGED_EVENT = 'appearance-changed';
+
+function isValidWindowEffects(value: unknown): value is WindowEffects {
+	return value === 'auto' || value === 'translucent' || value === 'solid';
+}
+
+export async function loadInitialWindowEffects(): Promise<AppearanceState> {
+	try {
+		const store = await getStore();
+		const stored = await store.get<WindowEffects>(WINDOW_EFFECTS_KEY);
+
+		if (isValidWindowEffects(stored)) {
+			return await invoke<AppearanceState>('set_window_effects', { effects: stored });
+		}
+	} catch (error) {
+		console.error('Failed to hydrate stored window effects', error);
+	}
+
+	return invoke<AppearanceState>('get_appearance');
+}
+
+export async function persistWindowEffects(effects: WindowEffects): Promise<AppearanceState> {
+	if (!isValidWindowEffects(effects)) {
+		throw new Error('Invalid window effects');
+	}
+
+	const state = await invoke<AppearanceState>('set_window_effects', { effects });
+	const store = await getStore();
+	await store.set(WINDOW_EFFECTS_KEY, effects);
+	await store.save();
+	return state;
+}
+
 export async function loadFontFamily(): Promise<'mono' | 'sans'> {
 	try {
 		const store = await getStore();
@@ -1,14 +1,16 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
-import type { SpatialConfig } from '../types';
+import type { SpatialConfig, SpatialEncoderSize, SpatialFrameStats, SpatialResult } from '../types';
 
 export interface SpatialProgressEvent {
 	id: string;
 	progress: number;
 	stage: string;
+	/** Set while a video's depth and stereo stage runs. */
+	frames?: SpatialFrameStats;
 }
 
-export interface SpatialCompletedEvent {
+export interface SpatialCompletedEvent extends SpatialResult {
 	id: string;
 	outputPath: string;
 }
@@ -25,32 +27,99 @@ export interface SpatialLogEvent {
 
 export interface SpatialStartedEvent {
 	id: string;
+	batchId?: string;
+	/** Queued again from the saved queue after the app restarted. */
+	restored: boolean;
+}
+
+export interface RestoredSpatialTask {
+	id: string;
+	filePath: string;
+	batchId: string | null;
 }
 
 export interface ModelDownloadProgressEvent {
-	encoderSize: string;
+	encoderSize: SpatialEncoderSize;
 	bytesDownloaded: number;
 	totalBytes: number;
 	progress: number;
 }
 
 export interface ModelDownloadCompleteEvent {
-	encoderSize: string;
+	encoderSize: SpatialEncoderSize;
 }
 
 export interface ModelDownloadErrorEvent {
-	encoderSize: string;
+	encoderSize: SpatialEncoderSize;
 	error: string;
 }
 
+export type ModelIntegrity = 'verified' | 'notInstalled' | 'unverified' | 'corrupt';
+
+export interface ModelVerification {
+	encoderSize: SpatialEncoderSize;
+	integrity: ModelIntegrity;
+	expectedBytes: number | null;
+	actualBytes: number | null;
+	redownloaded: boolean;
+}
+
+export interface SpatialHardware {
+	memoryBytes: number | null;
+	appleSilicon: boolean;
+	gpu: string | null;
+	cpuThreads: number;
+	accelerated: boolean;
+}
+
+export interface EncoderRecommendation {
+	encoderSize: SpatialEncoderSize;
+	/** Rough video frames per second with the suggested size. */
+	expectedFps: number;
+	reason: string;
+	hardware: SpatialHardware;
+}
+
+export async function recommendSpatialEncoder(): Promise<EncoderRecommendation> {
+	return invoke('recommend_spatial_encoder');
+}
+
 export async function checkSpatialModels(): Promise<Record<string, boolean>> {
 	return invoke('check_spatial_models');
 }
 
-export async function downloadSpatialModel(encoderSize: string): Promise<void> {
+export async function downloadSpatialModel(encoderSize: SpatialEncoderSize): Promise<void> {
 	return invoke('download_spatial_model', { encoderSize });
 }
 
+export async function cancelSpatialModelDownload(encoderSize: SpatialEncoderSize): Promise<void> {
+	return invoke('cancel_spatial_model_download', { encoderSize });
+}
+
+/** Removes a downloaded model; `spatial-models-changed` reports the new states. */
+export async function deleteSpatialModel(encoderSize: SpatialEncoderSize): Promise<void> {
+	return invoke('delete_spatial_model', { encoderSize });
+}
+
+/**
+ * Hashes a downloaded model against the checksum recorded when it was downloaded.
+ * With `redownload`, a corrupt or partial file is replaced.
+ */
+export async function verifySpatialModel(
+	encoderSize: SpatialEncoderSize,
+	redownload = false
+): Promise<ModelVerification> {
+	return invoke('verify_spatial_model', { encoderSize, redownload });
+}
+
+export async function listenSpatialModels(
+	onChange: (status: Record<string, boolean>) => void
+): Promise<UnlistenFn> {
+	return listen<Record<string, boolean>>('spatial-models-changed', (event) =>
+		onChange(event.payload)
+	);
+}
+
 export async function startSpatial(id: string, filePath: string, config: SpatialConfig) {
 	try {
 		await invoke('queue_spatial', { id, filePath, config });
@@ -60,6 +129,39 @@ export async function startSpatial(id: string, filePath: string, config: Spatial
 	}
 }
 
+/**
+ * Lists the spatial tasks left unfinished when the app last quit, without queuing them.
+ */
+export async function savedSpatialQueue(): Promise<RestoredSpatialTask[]> {
+	return invoke('saved_spatial_queue');
+}
+
+/**
+ * Queues again the tasks `savedSpatialQueue` listed. Only the first call after launch
+ * queues any.
+ */
+export async function restoreSpatialQueue(): Promise<void> {
+	await invoke('restore_spatial_queue');
+}
+
+export async function pauseSpatial(id: string) {
+	try {
+		await invoke('pause_spatial', { id });
+	} catch (error) {
+		console.error('Failed to pause spatial conversion:', error);
+		throw error;
+	}
+}
+
+export async function resumeSpatial(id: string) {
+	try {
+		await invoke('resume_spatial', { id });
+	} catch (error) {
+		console.error('Failed to resume spatial conversion:', error);
+		throw error;
+	}
+}
+
 export async function cancelSpatial(id: string) {
 	try {
 		await invoke('cancel_spatial', { id });
@@ -131,3 +233,68 @@ export async function setupModelDownloadListeners(
 		unlistenError();
 	};
 }
+
+/** Where a frame keeps its two eyes; matches the backend's `StereoLayout`. */
+export type StereoLayout = 'sideBySide' | 'topAndBottom' | 'mono';
+
+export interface DisplayInfo {
+	index: number;
+	name: string | null;
+	width: number;
+	height: number;
+	x: number;
+	y: number;
+	scaleFactor: number;
+	primary: boolean;
+}
+
+export interface StereoPreviewState {
+	open: boolean;
+	path: string | null;
+	sourceLayout: StereoLayout;
+	layout: StereoLayout;
+	display: number | null;
+}
+
+export const STEREO_PREVIEW_CHANGED_EVENT = 'stereo-preview-changed';
+
+export async function listDisplays(): Promise<DisplayInfo[]> {
+	return invoke('list_displays');
+}
+
+export async function getStereoPreview(): Promise<StereoPreviewState> {
+	return invoke('get_stereo_preview');
+}
+
+/** Opens the fullscreen preview; without a display it picks a secondary one. */
+export async function openStereoPreview(
+	path: string,
+	sourceLayout: StereoLayout,
+	layout: StereoLayout,
+	display?: number
+): Promise<StereoPreviewState> {
+	return invoke('open_stereo_preview', { path, sourceLayout, layout, display });
+}
+
+export async function setStereoPreviewDisplay(display: number): Promise<StereoPreviewState> {
+	return invoke('set_stereo_preview_display', { display });
+}
+
+export async function setStereoPreviewLayout(
+	sourceLayout: StereoLayout,
+	layout: StereoLayout
+): Promise<StereoPreviewState> {
+	return invoke('set_stereo_preview_layout', { sourceLayout, layout });
+}
+
+export async function closeStereoPreview(): Promise<void> {
+	return invoke('close_stereo_preview');
+}
+
+export async function listenStereoPreview(
+	onChange: (state: StereoPreviewState) => void
+): Promise<UnlistenFn> {
+	return listen<StereoPreviewState>(STEREO_PREVIEW_CHANGED_EVENT, (event) =>
+		onChange(event.payload)
+	);
+}
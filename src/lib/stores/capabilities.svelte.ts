@@ -1,6 +1,11 @@
-import { getAvailableEncoders, type AvailableEncoders } from '$lib/services/capabilities';
+import {
+	getAvailableEncoders,
+	getGpuDevices,
+	type AvailableEncoders,
+	type GpuDevice
+} from '$lib/services/capabilities';
 
-export const capabilities = $state<{ encoders: AvailableEncoders }>({
+export const capabilities = $state<{ encoders: AvailableEncoders; gpus: GpuDevice[] }>({
 	encoders: {
 		h264_videotoolbox: false,
 		h264_nvenc: false,
@@ -8,10 +13,12 @@ export const capabilities = $state<{ encoders: AvailableEncoders }>({
 		hevc_nvenc: false,
 		av1_nvenc: false,
 		ml_upscale: false
-	}
+	},
+	gpus: []
 });
 
 export async function initCapabilities() {
-	const encoders = await getAvailableEncoders();
+	const [encoders, gpus] = await Promise.all([getAvailableEncoders(), getGpuDevices()]);
 	capabilities.encoders = encoders;
+	capabilities.gpus = gpus;
 }
@@ -1,4 +1,7 @@
+import type { AppearanceState } from '$lib/services/settings';
+
 export const themeStore = $state({
 	opacity: 100,
-	fontFamily: 'mono' as 'mono' | 'sans'
+	fontFamily: 'mono' as 'mono' | 'sans',
+	appearance: null as AppearanceState | null
 });
@@ -68,6 +68,10 @@ export interface ConversionConfig {
 	nvencTemporalAq: boolean;
 	videotoolboxAllowSw: boolean;
 	hwDecode: boolean;
+	gpuIndex?: number | null;
+	nvencGpuIndex?: number | null;
+	/** Stop the task after this many seconds, not counting pauses; 0 or unset means no limit. */
+	maxDurationSecs?: number | null;
 }
 
 export type MetadataMode = 'preserve' | 'clean' | 'replace';
@@ -82,6 +86,14 @@ export interface MetadataConfig {
 	comment?: string;
 }
 
+export interface ContactSheetOptions {
+	columns: number;
+	rows: number;
+	thumbWidth: number;
+	showTimestamps: boolean;
+	outputPath?: string;
+}
+
 export interface SourceMetadata {
 	duration?: string;
 	bitrate?: string;
@@ -117,6 +129,12 @@ export interface FileItem {
 	path: string;
 	isSelectedForConversion: boolean;
 	conversionError?: string;
+	/** The spatial file written for this item, once its spatial job finishes. */
+	spatialOutputPath?: string;
+	/** Frame speed and time remaining while its spatial job renders a video. */
+	spatialFrames?: SpatialFrameStats;
+	/** What the finished spatial job wrote, as reported when it completed. */
+	spatialResult?: SpatialResult;
 }
 
 export interface PresetDefinition {
@@ -126,14 +144,61 @@ export interface PresetDefinition {
 	builtIn?: boolean;
 }
 
+export interface SpatialFrameStats {
+	done: number;
+	total: number | null;
+	fps: number | null;
+	/** Seconds until the last frame is rendered; encoding comes after. */
+	etaSecs: number | null;
+}
+
+export interface SpatialResult {
+	outputBytes: number | null;
+	/** Frame size of the output; one eye for MV-HEVC files. */
+	width: number | null;
+	height: number | null;
+	/** Length of a video output; null for photos. */
+	durationSecs: number | null;
+	/** Time the job ran, not counting its wait in the queue. */
+	elapsedSecs: number;
+}
+
 export type MetadataStatus = 'idle' | 'loading' | 'ready' | 'error';
 
+/** Depth model size; matches the backend's `EncoderSize` serialization. */
+export type SpatialEncoderSize = 's' | 'm' | 'l';
+
+export type SpatialLayout = 'sideBySide' | 'topAndBottom';
+
+export type SpatialImageFormat = 'jpeg' | 'png';
+
 export interface SpatialConfig {
 	enabled: boolean;
-	encoderSize: 's' | 'm' | 'l';
+	encoderSize: SpatialEncoderSize;
 	maxDisparity: number;
 	skipDownscale: boolean;
 	duration?: number;
+	/** Stop the task after this many seconds, not counting pauses; 0 or unset means no limit. */
+	maxDurationSecs?: number | null;
+	/** Trim points in the conversion trim format, cut before depth estimation. */
+	startTime?: string;
+	endTime?: string;
+	/** Larger inputs are scaled down to fit before depth estimation. */
+	maxWidth?: number | null;
+	maxHeight?: number | null;
+	layout: SpatialLayout;
+	/** Encoding of photo renders; `imageQuality` applies to JPEG. */
+	imageFormat: SpatialImageFormat;
+	imageQuality: number;
+	/** Turn the stereo render into an Apple spatial (MV-HEVC) file. */
+	mvhevc: boolean;
+	mvhevcQuality?: number | null;
+	/** Keep the stereo render next to the spatial file. */
+	keepStereo: boolean;
+	/** Folder for the results; unset writes them next to the input. */
+	outputDir?: string | null;
+	/** File name without extension; `{stem}` is the input's name. Defaults to `{stem}_spatial`. */
+	outputName?: string | null;
 }
 
 export type SpatialStatus = 'idle' | 'queued' | 'processing' | 'completed' | 'error';
@@ -143,8 +208,42 @@ export const DEFAULT_SPATIAL_CONFIG: SpatialConfig = {
 	encoderSize: 's',
 	maxDisparity: 40,
 	skipDownscale: true,
+	layout: 'sideBySide',
+	imageFormat: 'jpeg',
+	imageQuality: 95,
+	mvhevc: true,
+	keepStereo: false
 };
 
 export const AUDIO_ONLY_CONTAINERS = ['mp3', 'm4a', 'wav', 'flac'];
 
-export const ALL_CONTAINERS = ['mp4', 'mkv', 'webm', 'mov', 'mp3', 'm4a', 'wav', 'flac'] as const;
+export const IMAGE_CONTAINERS = ['jpg', 'png', 'webp', 'avif'];
+
+export const IMAGE_INPUT_EXTENSIONS = [
+	'jpg',
+	'jpeg',
+	'png',
+	'webp',
+	'avif',
+	'heic',
+	'heif',
+	'jxl',
+	'tif',
+	'tiff',
+	'bmp'
+];
+
+export const ALL_CONTAINERS = [
+	'mp4',
+	'mkv',
+	'webm',
+	'mov',
+	'mp3',
+	'm4a',
+	'wav',
+	'flac',
+	'jpg',
+	'png',
+	'webp',
+	'avif'
+] as const;
@@ -0,0 +1,12 @@
+/**
+ * Seconds as `m:ss`, or `h:mm:ss` from an hour up. Rounds to the nearest
+ * second; pass `Math.ceil` for time remaining so it does not read 0:00 early.
+ */
+export function formatDuration(secs: number, round: (value: number) => number = Math.round) {
+	const total = round(secs);
+	const minutes = Math.floor(total / 60);
+	const seconds = String(total % 60).padStart(2, '0');
+	return minutes >= 60
+		? `${Math.floor(minutes / 60)}:${String(minutes % 60).padStart(2, '0')}:${seconds}`
+		: `${minutes}:${seconds}`;
+}